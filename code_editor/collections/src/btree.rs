@@ -41,6 +41,14 @@ impl<T: Chunk> BTree<T> {
         self.root.measure_at::<M>(position)
     }
 
+    /// Returns the chunk that contains the unit with the given index, as counted by the measure
+    /// `M`, together with the position of the start of that chunk, and the measure of everything
+    /// before it.
+    pub(crate) fn search_by_measure<M: Measure<T>>(&self, index: usize) -> (&T, usize, usize) {
+        assert!(index < self.measure::<M>());
+        self.root.search_by_measure::<M>(index)
+    }
+
     pub(crate) fn slice<R: RangeBounds<usize>>(&self, range: R) -> Slice<'_, T> {
         use std::ops::Bound;
 
@@ -306,13 +314,17 @@ impl<'a, T: Chunk> Slice<'a, T> {
 
 impl<'a, T: Chunk> Copy for Slice<'a, T> {}
 
+// The maximum height of a tree that a cursor can descend into. Since the tree is kept balanced,
+// this is more than enough for any text that fits in memory.
+const MAX_HEIGHT: usize = 32;
+
 #[derive(Clone)]
 pub(crate) struct Cursor<'a, T: Chunk> {
     root: &'a Node<T>,
     start: usize,
     end: usize,
     position: usize,
-    path: [(Option<&'a Branch<T>>, usize); 8],
+    path: [(Option<&'a Branch<T>>, usize); MAX_HEIGHT],
     path_len: usize,
 }
 
@@ -372,7 +384,7 @@ impl<'a, T: Chunk> Cursor<'a, T> {
             start,
             end,
             position: 0,
-            path: [(None, 0); 8],
+            path: [(None, 0); MAX_HEIGHT],
             path_len: 0,
         }
     }
@@ -540,6 +552,24 @@ impl<T: Chunk> Node<T> {
         }
     }
 
+    fn search_by_measure<M: Measure<T>>(&self, index: usize) -> (&T, usize, usize) {
+        let mut node = self;
+        let mut summed_len = 0;
+        let mut summed_measure = 0;
+        loop {
+            match node {
+                Node::Leaf(leaf) => break (leaf.as_chunk(), summed_len, summed_measure),
+                Node::Branch(branch) => {
+                    let (index, len, measure) =
+                        branch.search_by_measure::<M>(index - summed_measure);
+                    node = &branch[index];
+                    summed_len += len;
+                    summed_measure += measure;
+                }
+            }
+        }
+    }
+
     fn as_mut_branch(&mut self) -> &mut Branch<T> {
         match self {
             Self::Branch(branch) => branch,
@@ -794,7 +824,9 @@ struct Branch<T: Chunk> {
 }
 
 impl<T: Chunk> Branch<T> {
-    const MAX_LEN: usize = 2;
+    // Unit tests use tiny branches so that small trees are already deep. The integration tests run
+    // with the sizes below.
+    const MAX_LEN: usize = if cfg!(test) { 2 } else { 16 };
 
     fn new() -> Self {
         Self::from_nodes(Arc::new(Vec::new()))
@@ -985,6 +1017,7 @@ trait NodeSliceExt<T: Chunk> {
     fn search(&self, position: usize) -> (usize, usize);
     fn search_with_info(&self, position: usize) -> (usize, usize, T::Info);
     fn search_with_measure<M: Measure<T>>(&self, position: usize) -> (usize, usize, usize);
+    fn search_by_measure<M: Measure<T>>(&self, index: usize) -> (usize, usize, usize);
 }
 
 impl<T: Chunk> NodeSliceExt<T> for [Node<T>] {
@@ -1049,4 +1082,20 @@ impl<T: Chunk> NodeSliceExt<T> for [Node<T>] {
         }
         (index, summed_len, summed_measure)
     }
+
+    fn search_by_measure<M: Measure<T>>(&self, index: usize) -> (usize, usize, usize) {
+        let mut node_index = 0;
+        let mut summed_len = 0;
+        let mut summed_measure = 0;
+        for node in &self[..self.len() - 1] {
+            let new_summed_measure = summed_measure + M::measure_info(node.summed_info());
+            if index < new_summed_measure {
+                break;
+            }
+            node_index += 1;
+            summed_len += node.summed_len();
+            summed_measure = new_summed_measure;
+        }
+        (node_index, summed_len, summed_measure)
+    }
 }
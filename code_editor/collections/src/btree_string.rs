@@ -1,6 +1,9 @@
 use {
     crate::{btree, btree::Measure, BTree},
-    std::ops::{Add, AddAssign, RangeBounds, Sub, SubAssign},
+    std::{
+        mem,
+        ops::{Add, AddAssign, Range, RangeBounds, Sub, SubAssign},
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -35,6 +38,87 @@ impl BTreeString {
         self.btree.measure_at::<CharMeasure>(position)
     }
 
+    pub fn utf16_count(&self) -> usize {
+        self.btree.measure::<Utf16Measure>()
+    }
+
+    /// Returns the index of the line that contains the given byte position.
+    pub fn byte_to_line(&self, position: usize) -> usize {
+        self.btree.measure_at::<LineBreakMeasure>(position)
+    }
+
+    /// Returns the number of UTF-16 code units before the given byte position.
+    pub fn byte_to_utf16(&self, position: usize) -> usize {
+        self.btree.measure_at::<Utf16Measure>(position)
+    }
+
+    /// Returns the byte position of the char that contains the UTF-16 code unit with the given
+    /// index, or the length of the string if the index is equal to the number of code units.
+    pub fn utf16_to_byte(&self, utf16_index: usize) -> usize {
+        if utf16_index == self.utf16_count() {
+            return self.len();
+        }
+        let (chunk, mut position, utf16_count) =
+            self.btree.search_by_measure::<Utf16Measure>(utf16_index);
+        let mut utf16_index = utf16_index - utf16_count;
+        for char in chunk.chars() {
+            if utf16_index < char.len_utf16() {
+                break;
+            }
+            utf16_index -= char.len_utf16();
+            position += char.len_utf8();
+        }
+        position
+    }
+
+    /// Returns the byte position of the char with the given index, or the length of the string if
+    /// the index is equal to the number of chars.
+    pub fn char_to_byte(&self, char_index: usize) -> usize {
+        if char_index == self.char_count() {
+            return self.len();
+        }
+        let (chunk, position, char_count) = self.btree.search_by_measure::<CharMeasure>(char_index);
+        position + chunk.char_indices().nth(char_index - char_count).unwrap().0
+    }
+
+    /// Returns the byte position of the start of the line with the given index.
+    pub fn line_to_byte(&self, line_index: usize) -> usize {
+        assert!(line_index < self.line_count());
+        if line_index == 0 {
+            return 0;
+        }
+        let (chunk, position, line_break_count) =
+            self.btree.search_by_measure::<LineBreakMeasure>(line_index - 1);
+        position + chunk.line_break_range(line_index - 1 - line_break_count).end
+    }
+
+    /// Returns the byte range of the line with the given index, not including its line break.
+    pub fn line_range(&self, line_index: usize) -> Range<usize> {
+        let start = self.line_to_byte(line_index);
+        if line_index + 1 == self.line_count() {
+            return start..self.len();
+        }
+        let (chunk, position, line_break_count) =
+            self.btree.search_by_measure::<LineBreakMeasure>(line_index);
+        start..position + chunk.line_break_range(line_index - line_break_count).start
+    }
+
+    /// Returns the line with the given index, not including its line break.
+    pub fn line(&self, line_index: usize) -> Slice<'_> {
+        self.slice(self.line_range(line_index))
+    }
+
+    /// Returns an iterator over the lines of this string, not including their line breaks.
+    ///
+    /// Unlike `str::lines`, a line break at the end of the string is followed by an empty line,
+    /// so that the iterator always yields `line_count` lines.
+    pub fn lines(&self) -> Lines<'_> {
+        Lines {
+            string: self,
+            line_index: 0,
+        }
+    }
+
     pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Slice<'_> {
         Slice {
             slice: self.btree.slice(range),
@@ -83,12 +167,21 @@ impl BTreeString {
         }
     }
 
-    pub fn prepend(&mut self, other: Self) {
-        self.btree.prepend(other.btree);
+    pub fn prepend(&mut self, mut other: Self) {
+        if other.ends_with_cr() && self.starts_with_lf() {
+            other.join_crlf(mem::replace(self, Self::new()));
+            *self = other;
+        } else {
+            self.btree.prepend(other.btree);
+        }
     }
 
     pub fn append(&mut self, other: Self) {
-        self.btree.append(other.btree);
+        if self.ends_with_cr() && other.starts_with_lf() {
+            self.join_crlf(other);
+        } else {
+            self.btree.append(other.btree);
+        }
     }
 
     pub fn split_off(&mut self, at: usize) -> Self {
@@ -104,6 +197,24 @@ impl BTreeString {
     pub fn truncate_back(&mut self, start: usize) {
         self.btree.truncate_back(start);
     }
+
+    fn starts_with_lf(&self) -> bool {
+        self.bytes().next() == Some(0x0A)
+    }
+
+    fn ends_with_cr(&self) -> bool {
+        self.bytes().next_back() == Some(0x0D)
+    }
+
+    // A `\r\n` is only counted as a single line break if it is contained in a single chunk, so a
+    // `\r` at the end of this string and a `\n` at the start of `other` are moved into a chunk
+    // of their own before the two strings are joined.
+    fn join_crlf(&mut self, mut other: Self) {
+        self.truncate_back(self.len() - 1);
+        other.truncate_front(1);
+        self.btree.append(Self::from("\r\n").btree);
+        self.btree.append(other.btree);
+    }
 }
 
 impl From<String> for BTreeString {
@@ -146,7 +257,7 @@ impl Builder {
                 break;
             }
             let mut index = <String as btree::Chunk>::MAX_LEN - self.chunk.len();
-            while !chunk.is_char_boundary(index) {
+            while !chunk.is_boundary(index) {
                 index -= 1;
             }
             let (left_chunk, right_chunk) = chunk.split_at(index);
@@ -327,6 +438,24 @@ impl<'a> Cursor<'a> {
     }
 }
 
+pub struct Lines<'a> {
+    string: &'a BTreeString,
+    line_index: usize,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Slice<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.line_index == self.string.line_count() {
+            return None;
+        }
+        let line = self.string.line(self.line_index);
+        self.line_index += 1;
+        Some(line)
+    }
+}
+
 pub struct Chunks<'a> {
     cursor_front: Cursor<'a>,
     cursor_back: Cursor<'a>,
@@ -417,7 +546,9 @@ impl<'a> DoubleEndedIterator for Chars<'a> {
 impl btree::Chunk for String {
     type Info = Info;
 
-    const MAX_LEN: usize = 8;
+    // Unit tests use tiny chunks so that small strings already span many of them. The integration
+    // tests run with the sizes below.
+    const MAX_LEN: usize = if cfg!(test) { 8 } else { 1024 };
 
     fn new() -> Self {
         String::new()
@@ -434,6 +565,7 @@ impl btree::Chunk for String {
     fn info_at(&self, index: usize) -> Self::Info {
         Info {
             char_count: self[..index].count_chars(),
+            utf16_count: self[..index].count_utf16(),
             line_break_count: self[..index].count_line_breaks(),
         }
     }
@@ -460,6 +592,7 @@ impl btree::Chunk for String {
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct Info {
     char_count: usize,
+    utf16_count: usize,
     line_break_count: usize,
 }
 
@@ -467,6 +600,7 @@ impl btree::Info for Info {
     fn new() -> Self {
         Self {
             char_count: 0,
+            utf16_count: 0,
             line_break_count: 0,
         }
     }
@@ -478,6 +612,7 @@ impl Add for Info {
     fn add(self, other: Self) -> Self::Output {
         Self {
             char_count: self.char_count + other.char_count,
+            utf16_count: self.utf16_count + other.utf16_count,
             line_break_count: self.line_break_count + other.line_break_count,
         }
     }
@@ -495,6 +630,7 @@ impl Sub for Info {
     fn sub(self, other: Self) -> Self::Output {
         Self {
             char_count: self.char_count - other.char_count,
+            utf16_count: self.utf16_count - other.utf16_count,
             line_break_count: self.line_break_count - other.line_break_count,
         }
     }
@@ -518,6 +654,18 @@ impl Measure<String> for CharMeasure {
     }
 }
 
+struct Utf16Measure;
+
+impl Measure<String> for Utf16Measure {
+    fn measure_chunk_at(chunk: &String, index: usize) -> usize {
+        chunk[..index].count_utf16()
+    }
+
+    fn measure_info(info: Info) -> usize {
+        info.utf16_count
+    }
+}
+
 struct LineBreakMeasure;
 
 impl Measure<String> for LineBreakMeasure {
@@ -555,8 +703,11 @@ impl U8Ext for u8 {
 
 trait StrExt {
     fn count_chars(&self) -> usize;
+    fn count_utf16(&self) -> usize;
     fn count_line_breaks(&self) -> usize;
+    fn line_break_range(&self, index: usize) -> Range<usize>;
     fn is_boundary(&self, index: usize) -> bool;
+    fn line_break_len_at(&self, index: usize) -> Option<usize>;
 }
 
 impl StrExt for str {
@@ -570,42 +721,65 @@ impl StrExt for str {
         count
     }
 
+    fn count_utf16(&self) -> usize {
+        let mut count = 0;
+        for byte in self.bytes() {
+            if byte.is_utf8_char_start() {
+                count += if byte >= 0xF0 { 2 } else { 1 };
+            }
+        }
+        count
+    }
+
     fn count_line_breaks(&self) -> usize {
         let mut count = 0;
-        let bytes = self.as_bytes();
         let mut index = 0;
-        while index < bytes.len() {
-            let byte = bytes[index];
-            if byte >= 0x0A && byte <= 0x0D {
-                count += 1;
-                if byte == 0x0D && index + 1 < bytes.len() && bytes[index + 1] == 0x0A {
-                    index += 2;
-                } else {
-                    index += 1;
-                };
-            } else if byte == 0xC2 && index + 1 < bytes.len() && bytes[index + 1] == 0x85 {
-                count += 1;
-                index += 2;
-            } else if byte == 0xE2
-                && index + 2 < bytes.len()
-                && bytes[index + 1] == 0x80
-                && bytes[index + 2] >> 1 == 0x54
-            {
-                count += 1;
-                index += 3;
-            } else {
-                index += 1;
+        while index < self.len() {
+            match self.line_break_len_at(index) {
+                Some(len) => {
+                    count += 1;
+                    index += len;
+                }
+                None => index += 1,
             }
         }
         count
     }
 
+    fn line_break_range(&self, line_break_index: usize) -> Range<usize> {
+        let mut count = 0;
+        let mut index = 0;
+        loop {
+            match self.line_break_len_at(index) {
+                Some(len) => {
+                    if count == line_break_index {
+                        break index..index + len;
+                    }
+                    count += 1;
+                    index += len;
+                }
+                None => index += 1,
+            }
+        }
+    }
+
     fn is_boundary(&self, index: usize) -> bool {
         if index == 0 || index == self.len() {
             return true;
         }
         let bytes = self.as_bytes();
-        bytes[index].is_utf8_char_start() && bytes[index - 1] != 0x0D && bytes[index] != 0x0F
+        bytes[index].is_utf8_char_start() && !(bytes[index - 1] == 0x0D && bytes[index] == 0x0A)
+    }
+
+    // Only `\n`, `\r\n` and `\r` are line breaks. Other chars that Unicode considers line breaks,
+    // such as form feeds and U+2028, are part of their line, as they are in most text editors.
+    fn line_break_len_at(&self, index: usize) -> Option<usize> {
+        let bytes = self.as_bytes();
+        match bytes[index] {
+            0x0D if index + 1 < bytes.len() && bytes[index + 1] == 0x0A => Some(2),
+            0x0A | 0x0D => Some(1),
+            _ => None,
+        }
     }
 }
 
@@ -613,6 +787,56 @@ impl StrExt for str {
 mod tests {
     use {super::*, proptest::prelude::*, std::ops::Range};
 
+    // Strings with many line breaks of every kind, chars that are not line breaks in a string but
+    // are in Unicode, and some multibyte chars.
+    fn text() -> impl Strategy<Value = String> {
+        "[ab\r\n\u{0B}\u{85}\u{2028}é😀]*"
+    }
+
+    fn text_and_index() -> impl Strategy<Value = (String, usize)> {
+        text().prop_flat_map(|string| {
+            {
+                let string_len = string.len();
+                (Just(string), 0..=string_len)
+            }
+            .prop_map(|(string, mut index)| {
+                while !string.is_char_boundary(index) {
+                    index -= 1;
+                }
+                (string, index)
+            })
+        })
+    }
+
+    fn line_break_ranges(string: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut char_indices = string.char_indices().peekable();
+        while let Some((index, char)) = char_indices.next() {
+            match char {
+                '\r' if char_indices.peek().map(|&(_, char)| char) == Some('\n') => {
+                    char_indices.next();
+                    ranges.push(index..index + 2);
+                }
+                '\n' | '\r' => {
+                    ranges.push(index..index + char.len_utf8());
+                }
+                _ => {}
+            }
+        }
+        ranges
+    }
+
+    fn lines(string: &str) -> Vec<&str> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for range in line_break_ranges(string) {
+            lines.push(&string[start..range.start]);
+            start = range.end;
+        }
+        lines.push(&string[start..]);
+        lines
+    }
+
     fn string_and_index() -> impl Strategy<Value = (String, usize)> {
         any::<String>().prop_flat_map(|string| {
             {
@@ -686,6 +910,65 @@ mod tests {
             assert_eq!(btree_string.char_count_at(index), string[..index].chars().count());
         }
 
+        #[test]
+        fn test_char_to_byte((string, index) in string_and_index()) {
+            let btree_string = BTreeString::from(&string);
+            let char_index = string[..index].chars().count();
+            assert_eq!(btree_string.char_to_byte(char_index), index);
+        }
+
+        #[test]
+        fn test_line_to_byte(string in text()) {
+            let btree_string = BTreeString::from(&string);
+            assert_eq!(btree_string.line_to_byte(0), 0);
+            for (line_index, range) in line_break_ranges(&string).into_iter().enumerate() {
+                assert_eq!(btree_string.line_to_byte(line_index + 1), range.end);
+            }
+        }
+
+        #[test]
+        fn test_line_range(string in text()) {
+            let btree_string = BTreeString::from(&string);
+            let mut start = 0;
+            for (line_index, range) in line_break_ranges(&string).into_iter().enumerate() {
+                assert_eq!(btree_string.line_range(line_index), start..range.start);
+                start = range.end;
+            }
+            assert_eq!(btree_string.line_range(btree_string.line_count() - 1), start..string.len());
+        }
+
+        #[test]
+        fn test_byte_to_line((string, index) in text_and_index()) {
+            let btree_string = BTreeString::from(&string);
+            assert_eq!(
+                btree_string.byte_to_line(index),
+                line_break_ranges(&string).into_iter().filter(|range| range.start < index).count()
+            );
+        }
+
+        #[test]
+        fn test_utf16_count(string in any::<String>()) {
+            let btree_string = BTreeString::from(&string);
+            assert_eq!(btree_string.utf16_count(), string.encode_utf16().count());
+        }
+
+        #[test]
+        fn test_utf16_to_byte((string, index) in string_and_index()) {
+            let btree_string = BTreeString::from(&string);
+            let utf16_index = string[..index].encode_utf16().count();
+            assert_eq!(btree_string.byte_to_utf16(index), utf16_index);
+            assert_eq!(btree_string.utf16_to_byte(utf16_index), index);
+        }
+
+        #[test]
+        fn test_lines(string in text()) {
+            let btree_string = BTreeString::from(&string);
+            assert_eq!(
+                btree_string.lines().map(|line| line.chars().collect::<String>()).collect::<Vec<_>>(),
+                lines(&string)
+            );
+        }
+
         #[test]
         fn test_chunks(string in any::<String>()) {
             let btree_string = BTreeString::from(&string);
@@ -757,6 +1040,22 @@ mod tests {
             assert_eq!(btree_string.chunks().collect::<String>(), string);
         }
 
+        #[test]
+        fn test_prepend_line_count(string in "[a\r\n]*", other_string in "[a\r\n]*") {
+            let mut btree_string = BTreeString::from(&string);
+            btree_string.prepend(BTreeString::from(&other_string));
+            let string = other_string + &string;
+            assert_eq!(btree_string.line_count(), string.count_line_breaks() + 1);
+        }
+
+        #[test]
+        fn test_append_line_count(string in "[a\r\n]*", other_string in "[a\r\n]*") {
+            let mut btree_string = BTreeString::from(&string);
+            btree_string.append(BTreeString::from(&other_string));
+            let string = string + &other_string;
+            assert_eq!(btree_string.line_count(), string.count_line_breaks() + 1);
+        }
+
         #[test]
         fn test_split_off((mut string, at) in string_and_index()) {
             let mut btree_string = BTreeString::from(&string);
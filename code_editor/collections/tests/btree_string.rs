@@ -0,0 +1,89 @@
+// The unit tests of the B-tree use tiny nodes and chunks, so that small strings already span many
+// levels. These tests run against the sizes that are used outside of the unit tests, with strings
+// that are large enough to span several levels of those.
+
+use {makepad_collections::BTreeString, proptest::prelude::*, std::ops::Range};
+
+// Large strings made of short pieces with line breaks of every kind and multibyte chars.
+fn large_text() -> impl Strategy<Value = String> {
+    prop::collection::vec("[ab\r\n\u{0C}\u{2028}é😀]{0,64}", 0..600)
+        .prop_map(|pieces| pieces.concat())
+}
+
+fn large_text_and_index() -> impl Strategy<Value = (String, usize)> {
+    large_text().prop_flat_map(|string| {
+        let string_len = string.len();
+        (Just(string), 0..=string_len)
+    })
+    .prop_map(|(string, mut index)| {
+        while !string.is_char_boundary(index) {
+            index -= 1;
+        }
+        (string, index)
+    })
+}
+
+fn line_break_ranges(string: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut char_indices = string.char_indices().peekable();
+    while let Some((index, char)) = char_indices.next() {
+        match char {
+            '\r' if char_indices.peek().map(|&(_, char)| char) == Some('\n') => {
+                char_indices.next();
+                ranges.push(index..index + 2);
+            }
+            '\n' | '\r' => ranges.push(index..index + 1),
+            _ => {}
+        }
+    }
+    ranges
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn test_counts(string in large_text()) {
+        let btree_string = BTreeString::from(&string);
+        assert_eq!(btree_string.len(), string.len());
+        assert_eq!(btree_string.char_count(), string.chars().count());
+        assert_eq!(btree_string.utf16_count(), string.encode_utf16().count());
+        assert_eq!(btree_string.line_count(), line_break_ranges(&string).len() + 1);
+        assert_eq!(btree_string.chunks().collect::<String>(), string);
+    }
+
+    #[test]
+    fn test_lines(string in large_text()) {
+        let btree_string = BTreeString::from(&string);
+        let mut start = 0;
+        for (line_index, range) in line_break_ranges(&string).into_iter().enumerate() {
+            assert_eq!(btree_string.line_range(line_index), start..range.start);
+            assert_eq!(btree_string.line_to_byte(line_index + 1), range.end);
+            start = range.end;
+        }
+    }
+
+    #[test]
+    fn test_positions((string, index) in large_text_and_index()) {
+        let btree_string = BTreeString::from(&string);
+        let char_index = string[..index].chars().count();
+        assert_eq!(btree_string.char_count_at(index), char_index);
+        assert_eq!(btree_string.char_to_byte(char_index), index);
+        let utf16_index = string[..index].encode_utf16().count();
+        assert_eq!(btree_string.utf16_to_byte(utf16_index), index);
+    }
+
+    #[test]
+    fn test_split_off_and_append((string, index) in large_text_and_index(), other_string in large_text()) {
+        let mut btree_string = BTreeString::from(&string);
+        let back = btree_string.split_off(index);
+        assert_eq!(btree_string.chunks().collect::<String>(), &string[..index]);
+        assert_eq!(back.chunks().collect::<String>(), &string[index..]);
+        btree_string.append(BTreeString::from(&other_string));
+        btree_string.append(back);
+        let mut expected = string.clone();
+        expected.insert_str(index, &other_string);
+        assert_eq!(btree_string.chars().rev().collect::<String>(), expected.chars().rev().collect::<String>());
+        assert_eq!(btree_string.line_count(), line_break_ranges(&expected).len() + 1);
+    }
+}
@@ -134,6 +134,11 @@ pub struct Button {
     #[alias(width, walk.width)]
     #[alias(height, walk.height)]
     #[alias(margin, walk.margin)]
+    #[alias(min_width, walk.min_width)]
+    #[alias(max_width, walk.max_width)]
+    #[alias(min_height, walk.min_height)]
+    #[alias(max_height, walk.max_height)]
+    #[alias(aspect_ratio, walk.aspect_ratio)]
     walk: Walk,
 
     layout: Layout,
@@ -150,6 +150,10 @@ impl FoldButton {
         };
     }
     
+    pub fn is_open(&self, cx: &mut Cx) -> bool {
+        self.state.is_in_state(cx, ids!(open.yes))
+    }
+    
     pub fn set_is_open(&mut self, cx: &mut Cx, is_open: bool, animate: Animate) {
         self.toggle_state(cx, is_open, animate, ids!(open.yes), ids!(open.no))
     }
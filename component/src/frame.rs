@@ -33,11 +33,16 @@ live_register!{
 pub struct Frame { // draw info per UI element
     bg: DrawShape,
     
-    layout: Layout,
+    pub layout: Layout,
     
     #[alias(width, walk.width)]
     #[alias(height, walk.height)]
     #[alias(margin, walk.margin)]
+    #[alias(min_width, walk.min_width)]
+    #[alias(max_width, walk.max_width)]
+    #[alias(min_height, walk.min_height)]
+    #[alias(max_height, walk.max_height)]
+    #[alias(aspect_ratio, walk.aspect_ratio)]
     pub walk: Walk,
     
     clip: bool,
@@ -51,6 +56,7 @@ pub struct Frame { // draw info per UI element
     scroll_y: FrameComponentRef,
     
     #[rust] self_id: LiveId,
+    #[rust] live_ptr: Option<LivePtr>,
     
     #[rust] defer_walks: Vec<(LiveId, DeferWalk)>,
     #[rust] draw_state: DrawStateWrap<DrawState>,
@@ -67,14 +73,14 @@ impl LiveHook for Frame {
         None
     }
     
-    fn after_apply(&mut self, cx: &mut Cx, _from: ApplyFrom, _index: usize, _nodes: &[LiveNode]) {
+    fn after_apply(&mut self, cx: &mut Cx, from: ApplyFrom, index: usize, _nodes: &[LiveNode]) {
         if self.clip && self.view.is_none() {
             self.view = Some(View::new(cx));
         }
         //self.self_id = nodes[index].id;
-        //if let Some(file_id) = from.file_id() {
-        //self.live_ptr = Some(LivePtr::from_index(file_id, index, cx.live_registry.borrow().file_id_to_file(file_id).generation));
-        //}
+        if let Some(file_id) = from.file_id() {
+            self.live_ptr = Some(cx.live_registry.borrow().file_id_index_to_live_ptr(file_id, index));
+        }
     }
     
     fn apply_value_instance(&mut self, cx: &mut Cx, from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> usize {
@@ -200,6 +206,14 @@ impl FrameComponent for Frame {
         }
         NoChild
     }
+    
+    fn child_ids(&self) -> Vec<LiveId> {
+        self.draw_order.clone()
+    }
+    
+    fn area(&self) -> Area {
+        Frame::area(self)
+    }
 }
 
 #[derive(Clone)]
@@ -252,6 +266,11 @@ impl Frame {
         FrameActions::from_vec(actions)
     }
     
+    /// The node this frame was last applied from, if it was applied from a document.
+    pub fn live_ptr(&self) -> Option<LivePtr> {
+        self.live_ptr
+    }
+    
     pub fn area(&self)->Area{
         if let Some(view) = &self.view{
             view.area()
@@ -35,6 +35,10 @@ pub trait FrameComponent: LiveApply {
     }
     
     fn type_id(&self) -> LiveType where Self: 'static {LiveType::of::<Self>()}
+    
+    // introspection, used by the inspector
+    fn child_ids(&self) -> Vec<LiveId> {Vec::new()}
+    fn area(&self) -> Area {Area::Empty}
 }
 
 generate_ref_cast_api!(FrameComponent);
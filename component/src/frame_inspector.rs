@@ -0,0 +1,631 @@
+use {
+    std::{
+        collections::HashMap,
+        fs,
+    },
+    crate::{
+        makepad_platform::*,
+        makepad_live_compiler::{LiveEditEvent, LiveTokenId, TextPos},
+        color_picker::{ColorPicker, ColorPickerAction},
+        frame::Frame,
+        frame_component::*,
+    }
+};
+
+live_register!{
+    use makepad_component::theme::*;
+
+    FrameInspector: {{FrameInspector}} {
+        draw_hover: {color: #4a90ff40}
+        draw_selected: {color: #ffa00050}
+        draw_panel: {color: #1e1e1ef0}
+        draw_row: {color: #ffffff14}
+        draw_name: {
+            text_style: FONT_LABEL {}
+            color: #9
+        }
+        draw_value: {
+            text_style: FONT_LABEL {}
+            color: #e
+        }
+    }
+}
+
+// The inspector is an overlay over a frame tree. F12 toggles it, and while it is enabled it
+// highlights the component under the finger, and clicking a component selects it. The panel
+// on the right shows the path, walk and layout of the selected component, and the properties
+// of its node in the expanded document, which can be edited in place.
+#[derive(Live, LiveHook)]
+pub struct FrameInspector {
+    draw_hover: DrawColor,
+    draw_selected: DrawColor,
+    draw_panel: DrawColor,
+    draw_row: DrawColor,
+    draw_name: DrawText,
+    draw_value: DrawText,
+    color_picker: ColorPicker,
+
+    #[live(320.0)] panel_width: f32,
+    #[live(20.0)] line_height: f32,
+    #[live(130.0)] name_width: f32,
+    #[live(160.0)] picker_size: f32,
+    // writes edits back to the live source of the edited property, and to its file on disk
+    #[live(false)] pub write_back: bool,
+
+    #[rust] pub enabled: bool,
+    #[rust] hover: Option<Vec<LiveId>>,
+    #[rust] selected: Option<Vec<LiveId>>,
+    #[rust] type_name: Option<LiveId>,
+    #[rust] location: Option<String>,
+    #[rust] rows: Vec<InspectorRow>,
+    #[rust] edits: HashMap<(Vec<LiveId>, Vec<LiveId>), LiveValue>,
+    #[rust] open_row: Option<usize>,
+    #[rust] drag: Option<InspectorDrag>,
+    #[rust] lines: Vec<(Rect, InspectorLine)>,
+    #[rust] panel_rect: Rect,
+    #[rust] picker_rect: Rect,
+}
+
+// A property of the selected component.
+struct InspectorRow {
+    // the path of the property below the component, such as `bg.color`
+    path: Vec<LiveId>,
+    value: LiveValue,
+    text: String,
+    token_id: Option<LiveTokenId>,
+    // the bare variants of the type of an enum property
+    variants: Vec<LiveId>,
+}
+
+impl InspectorRow {
+    fn is_editable(&self) -> bool {
+        match self.value {
+            LiveValue::Bool(_) | LiveValue::Int(_) | LiveValue::Float(_) | LiveValue::Color(_) => true,
+            LiveValue::Id(_) | LiveValue::BareEnum {..} => !self.variants.is_empty(),
+            _ => false
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum InspectorLine {
+    Parent,
+    Child(LiveId),
+    Row(usize),
+    Variant(usize, LiveId),
+}
+
+#[derive(Clone, Copy)]
+struct InspectorDrag {
+    row: usize,
+    start_x: f32,
+    start_value: f64,
+    moved: bool,
+}
+
+impl FrameInspector {
+
+    /// Handles the events of the inspector. While it is enabled it takes all finger events, so
+    /// the frame tree `root` should only get an event when this returns false.
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &mut Event, root: &mut Frame) -> bool {
+        match event {
+            Event::KeyDown(ke) if ke.key_code == KeyCode::F12 => {
+                self.enabled = !self.enabled;
+                self.hover = None;
+                self.drag = None;
+                cx.redraw_all();
+                return true
+            }
+            Event::KeyDown(ke) if ke.key_code == KeyCode::Escape && self.enabled => {
+                if self.open_row.take().is_none() {
+                    self.selected = None;
+                    self.rows.clear();
+                }
+                cx.redraw_all();
+                return true
+            }
+            Event::LiveEdit(live_edit_event) => {
+                // a reparsed document is applied again, which undoes the edits that were not written back
+                if let LiveEditEvent::ReparseDocument = live_edit_event {
+                    self.edits.clear();
+                }
+                if let Some(path) = self.selected.clone() {
+                    self.select(cx, root, path);
+                }
+                return false
+            }
+            _ => ()
+        }
+        if !self.enabled {
+            return false
+        }
+
+        if let Some(row) = self.open_row {
+            if let LiveValue::Color(_) = self.rows[row].value {
+                match self.color_picker.handle_event(cx, event) {
+                    ColorPickerAction::Change {rgba} => self.apply_row(cx, root, row, LiveValue::Color(rgba.to_u32())),
+                    ColorPickerAction::DoneChanging => self.write_back_row(cx, row),
+                    ColorPickerAction::None => ()
+                }
+            }
+        }
+
+        match event {
+            Event::FingerHover(fe) => {
+                let hover = if self.panel_rect.contains(fe.abs) {None} else {Some(pick(cx, root, fe.abs))};
+                if hover != self.hover {
+                    self.hover = hover;
+                    cx.redraw_all();
+                }
+                true
+            }
+            Event::FingerDown(fe) => {
+                if self.picker_rect.contains(fe.abs) {
+                    return true
+                }
+                if !self.panel_rect.contains(fe.abs) {
+                    let path = pick(cx, root, fe.abs);
+                    self.select(cx, root, path);
+                    return true
+                }
+                let line = self.lines.iter().find( | (rect, _) | rect.contains(fe.abs)).map( | (_, line) | *line);
+                match line {
+                    Some(InspectorLine::Parent) => {
+                        let mut path = self.selected.clone().unwrap_or_default();
+                        path.pop();
+                        self.select(cx, root, path);
+                    }
+                    Some(InspectorLine::Child(id)) => {
+                        let mut path = self.selected.clone().unwrap_or_default();
+                        path.push(id);
+                        self.select(cx, root, path);
+                    }
+                    Some(InspectorLine::Row(row)) => match self.rows[row].value {
+                        LiveValue::Float(value) => {
+                            self.drag = Some(InspectorDrag {row, start_x: fe.abs.x, start_value: value, moved: false});
+                        }
+                        LiveValue::Int(value) => {
+                            self.drag = Some(InspectorDrag {row, start_x: fe.abs.x, start_value: value as f64, moved: false});
+                        }
+                        LiveValue::Bool(value) => {
+                            self.apply_row(cx, root, row, LiveValue::Bool(!value));
+                            self.write_back_row(cx, row);
+                        }
+                        _ => {
+                            self.open_row = if self.open_row == Some(row) {None} else {Some(row)};
+                            cx.redraw_all();
+                        }
+                    }
+                    Some(InspectorLine::Variant(row, variant)) => {
+                        let value = match self.rows[row].value {
+                            LiveValue::BareEnum {base, ..} => LiveValue::BareEnum {base, variant},
+                            _ => LiveValue::Id(variant)
+                        };
+                        self.open_row = None;
+                        self.apply_row(cx, root, row, value);
+                        self.write_back_row(cx, row);
+                    }
+                    None => ()
+                }
+                true
+            }
+            Event::FingerMove(fe) => {
+                if let Some(drag) = self.drag {
+                    // small values such as alignments are dragged in finer steps
+                    let dx = (fe.abs.x - drag.start_x) as f64;
+                    let value = if drag.start_value.abs() <= 1.0 {
+                        ((drag.start_value + dx * 0.01) * 100.0).round() / 100.0
+                    }
+                    else {
+                        ((drag.start_value + dx * 0.5) * 10.0).round() / 10.0
+                    };
+                    let value = match self.rows[drag.row].value {
+                        LiveValue::Int(_) => LiveValue::Int(value.round() as i64),
+                        _ => LiveValue::Float(value)
+                    };
+                    self.drag = Some(InspectorDrag {moved: true, ..drag});
+                    self.apply_row(cx, root, drag.row, value);
+                }
+                true
+            }
+            Event::FingerUp(_) => {
+                if let Some(drag) = self.drag.take() {
+                    if drag.moved {
+                        self.write_back_row(cx, drag.row);
+                    }
+                }
+                true
+            }
+            _ => false
+        }
+    }
+
+    /// Draws the highlights and the panel over the frame tree `root`, which has to be drawn
+    /// before the inspector.
+    pub fn draw(&mut self, cx: &mut Cx2d, root: &mut Frame) {
+        self.lines.clear();
+        self.panel_rect = Rect::default();
+        self.picker_rect = Rect::default();
+        if !self.enabled {
+            return
+        }
+
+        self.draw_hover.new_draw_call(cx);
+        if let Some(rect) = self.hover.as_ref().and_then( | path | component_rect(cx, root, path)) {
+            self.draw_hover.draw_abs(cx, rect);
+        }
+        if let Some(rect) = self.selected.as_ref().and_then( | path | component_rect(cx, root, path)) {
+            self.draw_selected.draw_abs(cx, rect);
+        }
+
+        let window = cx.turtle().rect();
+        self.panel_rect = Rect {
+            pos: vec2(window.pos.x + window.size.x - self.panel_width, window.pos.y),
+            size: vec2(self.panel_width, window.size.y)
+        };
+        self.draw_panel.draw_abs(cx, self.panel_rect);
+        self.draw_name.new_draw_call(cx);
+
+        let mut y = self.panel_rect.pos.y;
+        self.draw_line(cx, &mut y, "inspector", "F12 to close", None);
+        let path = match self.selected.clone() {
+            Some(path) => path,
+            None => {
+                self.draw_line(cx, &mut y, "", "click a component", None);
+                return
+            }
+        };
+
+        let path_text = if path.is_empty() {
+            "root".to_string()
+        }
+        else {
+            path.iter().map( | id | id.to_string()).collect::<Vec<_>>().join(".")
+        };
+        self.draw_line(cx, &mut y, "path", &path_text, None);
+        let type_text = self.type_name.map( | type_name | type_name.to_string()).unwrap_or_default();
+        self.draw_line(cx, &mut y, "type", &type_text, None);
+        if let Some(location) = self.location.clone() {
+            self.draw_line(cx, &mut y, "source", &location, None);
+        }
+
+        let info = with_component(root, &path, | component | {
+            (component.get_walk(), component.cast::<Frame>().map( | frame | frame.layout), component.child_ids())
+        });
+        let (walk, layout, child_ids) = match info {
+            Some(info) => info,
+            None => return
+        };
+        self.draw_line(cx, &mut y, "walk", "", None);
+        self.draw_line(cx, &mut y, "  width", &format!("{:?}", walk.width), None);
+        self.draw_line(cx, &mut y, "  height", &format!("{:?}", walk.height), None);
+        let margin = walk.margin;
+        self.draw_line(cx, &mut y, "  margin", &format!("{} {} {} {}", margin.left, margin.top, margin.right, margin.bottom), None);
+        if let Some(layout) = layout {
+            self.draw_line(cx, &mut y, "layout", "", None);
+            self.draw_line(cx, &mut y, "  flow", &format!("{:?}", layout.flow), None);
+            let padding = layout.padding;
+            self.draw_line(cx, &mut y, "  padding", &format!("{} {} {} {}", padding.left, padding.top, padding.right, padding.bottom), None);
+            self.draw_line(cx, &mut y, "  spacing", &format!("{}", layout.spacing), None);
+            self.draw_line(cx, &mut y, "  align", &format!("{} {}", layout.align.x, layout.align.y), None);
+        }
+
+        self.draw_line(cx, &mut y, "children", "", None);
+        if !path.is_empty() {
+            self.draw_line(cx, &mut y, "", "..", Some(InspectorLine::Parent));
+        }
+        for id in child_ids {
+            self.draw_line(cx, &mut y, "", &id.to_string(), Some(InspectorLine::Child(id)));
+        }
+
+        self.draw_line(cx, &mut y, "properties", "", None);
+        for row in 0..self.rows.len() {
+            let name = self.rows[row].path.iter().map( | id | id.to_string()).collect::<Vec<_>>().join(".");
+            let text = self.rows[row].text.clone();
+            let line = if self.rows[row].is_editable() {Some(InspectorLine::Row(row))} else {None};
+            self.draw_line(cx, &mut y, &format!("  {}", name), &text, line);
+            if self.open_row != Some(row) {
+                continue
+            }
+            match self.rows[row].value {
+                LiveValue::Color(color) => {
+                    let rect = Rect {
+                        pos: vec2(self.panel_rect.pos.x + self.name_width, y),
+                        size: vec2(self.picker_size, self.picker_size)
+                    };
+                    cx.begin_turtle(Walk {
+                        abs_pos: Some(rect.pos),
+                        margin: Margin::default(),
+                        width: Size::Fixed(rect.size.x),
+                        height: Size::Fixed(rect.size.y),
+                        ..Walk::default()
+                    }, Layout::default());
+                    self.color_picker.draw(cx, Vec4::from_u32(color), 1.0);
+                    cx.end_turtle();
+                    self.picker_rect = rect;
+                    y += rect.size.y;
+                }
+                LiveValue::Id(current) | LiveValue::BareEnum {variant: current, ..} => {
+                    for variant in self.rows[row].variants.clone() {
+                        let marker = if variant == current {">"} else {" "};
+                        self.draw_line(cx, &mut y, "", &format!("{} {}", marker, variant), Some(InspectorLine::Variant(row, variant)));
+                    }
+                }
+                _ => ()
+            }
+        }
+    }
+
+    fn draw_line(&mut self, cx: &mut Cx2d, y: &mut f32, name: &str, value: &str, line: Option<InspectorLine>) {
+        if *y + self.line_height > self.panel_rect.pos.y + self.panel_rect.size.y {
+            return
+        }
+        let rect = Rect {
+            pos: vec2(self.panel_rect.pos.x, *y),
+            size: vec2(self.panel_rect.size.x, self.line_height)
+        };
+        if let Some(line) = line {
+            self.draw_row.draw_abs(cx, Rect {
+                pos: vec2(rect.pos.x + self.name_width, rect.pos.y + 1.0),
+                size: vec2(rect.size.x - self.name_width - 4.0, rect.size.y - 2.0)
+            });
+            self.lines.push((rect, line));
+        }
+        self.draw_name.draw_abs(cx, rect.pos + vec2(6.0, 4.0), name);
+        self.draw_value.draw_abs(cx, rect.pos + vec2(self.name_width + 4.0, 4.0), value);
+        *y += self.line_height;
+    }
+
+    fn select(&mut self, cx: &mut Cx, root: &mut Frame, path: Vec<LiveId>) {
+        self.rows.clear();
+        self.open_row = None;
+        self.drag = None;
+        self.type_name = None;
+        self.location = None;
+        if with_component(root, &path, | _ | ()).is_none() {
+            self.selected = None;
+            cx.redraw_all();
+            return
+        }
+        if let Some(live_ptr) = component_ptr(cx, root, &path) {
+            let live_registry = cx.live_registry.borrow();
+            let (nodes, index) = live_registry.ptr_to_nodes_index(live_ptr);
+            let type_info = if let LiveValue::Class {live_type, ..} = nodes[index].value {
+                live_registry.live_type_infos.get(&live_type)
+            }
+            else {
+                None
+            };
+            self.type_name = type_info.map( | type_info | type_info.type_name);
+            if let Some(token_id) = nodes[index].origin.token_id() {
+                let span = live_registry.token_id_to_span(token_id);
+                let file_name = live_registry.file_id_to_file_name(token_id.file_id());
+                self.location = Some(format!("{}:{}", file_name, span.start.line + 1));
+            }
+            collect_rows(&live_registry, type_info, nodes, index, &mut Vec::new(), &mut self.rows);
+        }
+        for row in &mut self.rows {
+            if let Some(value) = self.edits.get(&(path.clone(), row.path.clone())) {
+                row.value = value.clone();
+                row.text = value_source(value).unwrap_or_default();
+            }
+        }
+        self.selected = Some(path);
+        cx.redraw_all();
+    }
+
+    fn apply_row(&mut self, cx: &mut Cx, root: &mut Frame, row: usize, value: LiveValue) {
+        let path = match &self.selected {
+            Some(path) => path.clone(),
+            None => return
+        };
+        let prop_path: Vec<LiveProp> = self.rows[row].path.iter().map( | id | id.as_field()).collect();
+        let mut nodes = Vec::new();
+        nodes.open();
+        nodes.replace_or_insert_last_node_by_path(0, &prop_path, &[LiveNode {
+            origin: LiveNodeOrigin::empty(),
+            id: LiveId(0),
+            value: value.clone()
+        }]);
+        nodes.close();
+        with_component(root, &path, | component | component.apply(cx, ApplyFrom::ApplyOver, 0, &nodes));
+
+        let row = &mut self.rows[row];
+        row.text = value_source(&value).unwrap_or_default();
+        row.value = value.clone();
+        self.edits.insert((path, row.path.clone()), value);
+        cx.redraw_all();
+    }
+
+    // Replaces the value of a property in the source it came from, which is reloaded like an
+    // edited live file, and in the file on disk.
+    fn write_back_row(&mut self, cx: &mut Cx, row: usize) {
+        if !self.write_back {
+            return
+        }
+        let row = &self.rows[row];
+        let (token_id, text) = match (row.token_id, value_source(&row.value)) {
+            (Some(token_id), Some(text)) => (token_id, text),
+            _ => return
+        };
+        let (file_name, source, start_pos, span) = {
+            let live_registry = cx.live_registry.borrow();
+            let live_file = &live_registry.live_files[token_id.file_id().to_index()];
+            let tokens = &live_file.original.tokens;
+            // the node starts at the name of the property, which is followed by `:` and the value
+            let mut first = token_id.token_index() + 2;
+            if !tokens.get(first - 1).is_some_and( | token | token.is_punct_id(id!(:))) || first >= tokens.len() {
+                return
+            }
+            let mut last = first;
+            if tokens[first].is_punct_id(id!(-)) {
+                last += 1;
+            }
+            else if tokens.get(first + 1).is_some_and( | token | token.is_punct_id(id!(::))) {
+                // only the variant of an enum such as `Size::Fill` is replaced
+                first += 2;
+                last = first;
+            }
+            let end = match tokens.get(last) {
+                Some(token) => token.span.end,
+                None => return
+            };
+            (live_file.file_name.clone(), live_file.source.clone(), live_file.start_pos, (tokens[first].span.start, end))
+        };
+        let range = match (
+            text_pos_to_offset(&source, start_pos, span.0),
+            text_pos_to_offset(&source, start_pos, span.1)
+        ) {
+            (Some(start), Some(end)) => start..end,
+            _ => return
+        };
+        let old_text = &source[range.clone()];
+
+        match fs::read_to_string(&file_name) {
+            Ok(disk) => match (
+                text_pos_to_offset(&disk, TextPos::default(), span.0),
+                text_pos_to_offset(&disk, TextPos::default(), span.1)
+            ) {
+                (Some(start), Some(end)) if &disk[start..end] == old_text => {
+                    let disk = format!("{}{}{}", &disk[..start], text, &disk[end..]);
+                    if let Err(err) = fs::write(&file_name, disk) {
+                        println!("Cannot write {}: {}", file_name, err);
+                    }
+                }
+                _ => println!("Not writing {}, it changed on disk", file_name)
+            }
+            Err(err) => println!("Cannot read {}: {}", file_name, err)
+        }
+
+        let source = format!("{}{}{}", &source[..range.start], text, &source[range.end..]);
+        cx.reload_live_file_from_bytes(&file_name, source.as_bytes());
+    }
+}
+
+fn with_component<R>(root: &mut Frame, path: &[LiveId], f: impl FnOnce(&mut (dyn FrameComponent + 'static)) -> R) -> Option<R> {
+    if path.is_empty() {
+        return Some(f(root))
+    }
+    match root.find_child(path) {
+        Child(child) => Some(f(&mut **child)),
+        NoChild => None
+    }
+}
+
+fn component_rect(cx: &Cx, root: &mut Frame, path: &[LiveId]) -> Option<Rect> {
+    match with_component(root, path, | component | component.area()) ? {
+        Area::Empty => None,
+        area => Some(area.get_rect(cx))
+    }
+}
+
+// Returns the path of the innermost component under `pos`. Components that are drawn later are
+// on top, so they win over the ones drawn before them.
+fn pick(cx: &Cx, root: &mut Frame, pos: Vec2) -> Vec<LiveId> {
+    fn pick_in(cx: &Cx, component: &mut dyn FrameComponent, pos: Vec2, path: &mut Vec<LiveId>, best: &mut Vec<LiveId>) {
+        for id in component.child_ids() {
+            if let Child(child) = component.find_child(&[id]) {
+                path.push(id);
+                let area = child.area();
+                if !matches!(area, Area::Empty) && area.get_rect(cx).contains(pos) {
+                    *best = path.clone();
+                }
+                pick_in(cx, &mut **child, pos, path, best);
+                path.pop();
+            }
+        }
+    }
+    let mut best = Vec::new();
+    pick_in(cx, root, pos, &mut Vec::new(), &mut best);
+    best
+}
+
+// Finds the document node of the component at `path`. Frames know their own node, and the nodes
+// of other components are found below the node of the nearest frame on the path.
+fn component_ptr(cx: &Cx, root: &mut Frame, path: &[LiveId]) -> Option<LivePtr> {
+    let mut found = None;
+    for depth in 0..=path.len() {
+        if let Some(live_ptr) = with_component(root, &path[..depth], | component | {
+            component.cast::<Frame>().and_then( | frame | frame.live_ptr())
+        }).flatten() {
+            found = Some((live_ptr, depth));
+        }
+    }
+    let (mut live_ptr, depth) = found ?;
+    let live_registry = cx.live_registry.borrow();
+    if !live_registry.generation_valid(live_ptr) {
+        return None
+    }
+    for id in &path[depth..] {
+        let (nodes, index) = live_registry.ptr_to_nodes_index(live_ptr);
+        let child_index = nodes.child_by_name(index, id.as_instance()) ?;
+        live_ptr = live_registry.file_id_index_to_live_ptr(live_ptr.file_id, child_index);
+    }
+    Some(live_ptr)
+}
+
+// Returns the type of the field `id` of a type. Aliases such as `width` for `walk.width` name a
+// field of a field, so those are searched as well.
+fn field_type_info(type_info: &LiveTypeInfo, id: LiveId) -> Option<&LiveTypeInfo> {
+    type_info.fields.iter().find( | field | field.id == id)
+        .or_else( || type_info.fields.iter().flat_map( | field | field.live_type_info.fields.iter()).find( | field | field.id == id))
+        .map( | field | &field.live_type_info)
+}
+
+fn collect_rows(live_registry: &LiveRegistry, type_info: Option<&LiveTypeInfo>, nodes: &[LiveNode], index: usize, path: &mut Vec<LiveId>, rows: &mut Vec<InspectorRow>) {
+    let mut child_index = index + 1;
+    while !nodes[child_index].is_close() {
+        let node = &nodes[child_index];
+        if node.origin.has_prop_type(LivePropType::Field) {
+            let field_info = type_info.and_then( | type_info | field_type_info(type_info, node.id));
+            path.push(node.id);
+            match node.value {
+                LiveValue::Object | LiveValue::Clone(_) | LiveValue::Class {..} => {
+                    collect_rows(live_registry, field_info, nodes, child_index, path, rows);
+                }
+                LiveValue::DSL {..} | LiveValue::Use(_) | LiveValue::UseComponent(_) => (),
+                _ => {
+                    let text = value_source(&node.value)
+                        .or_else( || live_registry.live_node_as_string(node).map( | string | format!("{:?}", string)))
+                        .unwrap_or_else( || "...".to_string());
+                    let variants = field_info
+                        .and_then( | field_info | live_registry.enum_variants.get(&field_info.live_type))
+                        .cloned()
+                        .unwrap_or_default();
+                    rows.push(InspectorRow {
+                        path: path.clone(),
+                        value: node.value.clone(),
+                        text,
+                        token_id: node.origin.token_id(),
+                        variants
+                    });
+                }
+            }
+            path.pop();
+        }
+        child_index = nodes.skip_node(child_index);
+    }
+}
+
+/// Returns the live source of the values that the inspector edits.
+pub fn value_source(value: &LiveValue) -> Option<String> {
+    Some(match value {
+        LiveValue::Bool(value) => format!("{}", value),
+        LiveValue::Int(value) => format!("{}", value),
+        LiveValue::Float(value) if value.fract() == 0.0 => format!("{:.1}", value),
+        LiveValue::Float(value) => format!("{}", value),
+        LiveValue::Color(value) => format!("#{:08x}", value),
+        LiveValue::Id(variant) | LiveValue::BareEnum {variant, ..} => variant.to_string(),
+        _ => return None
+    })
+}
+
+/// Returns the byte offset of a position in a source that starts at `start_pos`. The first line
+/// of the body of a `live_register!` block starts at the column of the block, so positions on it
+/// are offset by that column.
+pub fn text_pos_to_offset(source: &str, start_pos: TextPos, pos: TextPos) -> Option<usize> {
+    let line = pos.line.checked_sub(start_pos.line)? as usize;
+    let column = if line == 0 {pos.column.checked_sub(start_pos.column) ?} else {pos.column} as usize;
+    let line_start = if line == 0 {0} else {source.match_indices('\n').nth(line - 1)?.0 + 1};
+    source[line_start..].char_indices().map( | (offset, _) | line_start + offset).chain(Some(source.len())).nth(column)
+}
@@ -23,6 +23,7 @@ pub mod slider;
 #[macro_use]
 pub mod frame_component;
 pub mod frame;
+pub mod frame_inspector;
 pub mod window_menu;
 
 pub use makepad_platform;
@@ -47,6 +48,7 @@ pub use crate::{
     scroll_shadow::{ScrollShadow},
     scroll_bar::{ScrollBar},
     frame::{Frame},
+    frame_inspector::{FrameInspector},
     frame_component::{
         Child,
         NoChild,
@@ -69,6 +71,7 @@ pub fn live_register(cx: &mut Cx) {
     crate::slider::live_register(cx);
     crate::label::live_register(cx);
     crate::frame::live_register(cx);
+    crate::frame_inspector::live_register(cx);
     crate::fold_button::live_register(cx);
     crate::text_input::live_register(cx);
     crate::link_button::live_register(cx);
@@ -145,6 +145,11 @@ pub struct Slider {
     #[alias(width, walk.width)]
     #[alias(height, walk.height)]
     #[alias(margin, walk.margin)]
+    #[alias(min_width, walk.min_width)]
+    #[alias(max_width, walk.max_width)]
+    #[alias(min_height, walk.min_height)]
+    #[alias(max_height, walk.max_height)]
+    #[alias(aspect_ratio, walk.aspect_ratio)]
     walk: Walk,
     
     layout: Layout,
@@ -237,7 +242,8 @@ impl Slider {
         if let Some(dw) = cx.defer_walk(self.label_walk){
             self.text_input.value = format!("{:.2}", self.value); //, (self.value*100.0) as usize);
             self.text_input.draw_walk(cx, self.text_input.get_walk());
-            self.label_text.draw_walk(cx, dw.resolve(cx), self.label_align, &self.label);
+            let label_walk = dw.resolve(cx);
+            self.label_text.draw_walk(cx, label_walk, self.label_align, &self.label);
         }
         self.draw_slider.end(cx);
     }
@@ -1,5 +1,6 @@
 use crate::{
     makepad_platform::*,
+    makepad_platform::makepad_micro_serde::*,
     frame_component::*,
 };
 
@@ -359,7 +360,7 @@ impl Splitter {
     }
 }
 
-#[derive(Clone, Copy, Debug, Live, LiveHook)]
+#[derive(Clone, Copy, Debug, Live, LiveHook, SerRon, DeRon)]
 pub enum SplitterAlign {
     #[live(50.0)] FromStart(f32),
     #[live(50.0)] FromEnd(f32),
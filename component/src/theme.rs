@@ -139,5 +139,31 @@ live_register!{
     const DIM_SPLITTER_MIN_VERTICAL: (DIM_SPLITTER_HORIZONTAL),
     const DIM_SPLITTER_MAX_VERTICAL: (DIM_SPLITTER_HORIZONTAL + DIM_SPLITTER_SIZE),
     const DIM_SPLITTER_SIZE: 5.0
+    
+    // THEMES, switched with cx.set_theme
+    
+    ThemeLight: {
+        BRIGHTNESS: #xE4
+        COLOR_HIGHLIGHT: #B4C8E0
+        COLOR_UP_80: #000000CC
+        COLOR_UP_50: #00000080
+        COLOR_UP_25: #00000040
+        COLOR_UP_15: #00000026
+        COLOR_UP_10: #0000001A
+        COLOR_UP_4: #0000000A
+        COLOR_DOWN_7: #FFFFFF13
+        COLOR_DOWN_10: #FFFFFF30
+        COLOR_DOWN_20: #FFFFFF40
+        COLOR_DOWN_50: #FFFFFF80
+    }
+    
+    ThemeHighContrast: ThemeLight {
+        BRIGHTNESS: #FFF
+        COLOR_HIGHLIGHT: #FC0
+        COLOR_TEXT_DEFAULT: #000
+        COLOR_TEXT_META: #222
+        COLOR_TEXT_HOVER: #000
+        COLOR_TEXT_SELECTED: #000
+    }
 }
 
@@ -0,0 +1,48 @@
+use makepad_component::{
+    frame_inspector::{text_pos_to_offset, value_source},
+    makepad_platform::{*, makepad_live_compiler::TextPos},
+};
+
+fn pos(line: u32, column: u32) -> TextPos {
+    TextPos {line, column}
+}
+
+#[test]
+fn values_are_written_as_live_source() {
+    assert_eq!(value_source(&LiveValue::Bool(true)).unwrap(), "true");
+    assert_eq!(value_source(&LiveValue::Int(-3)).unwrap(), "-3");
+    // whole floats keep their decimal point, so they are not read back as ints
+    assert_eq!(value_source(&LiveValue::Float(40.0)).unwrap(), "40.0");
+    assert_eq!(value_source(&LiveValue::Float(0.25)).unwrap(), "0.25");
+    assert_eq!(value_source(&LiveValue::Color(0x00ff00ff)).unwrap(), "#00ff00ff");
+    // ids are written by name, so they have to be known to the interner
+    let (fill, fit) = (LiveId::from_str("Fill").unwrap(), LiveId::from_str("Fit").unwrap());
+    assert_eq!(value_source(&LiveValue::Id(fill)).unwrap(), "Fill");
+    assert_eq!(value_source(&LiveValue::BareEnum {base: id!(Size), variant: fit}).unwrap(), "Fit");
+    assert!(value_source(&LiveValue::Object).is_none());
+}
+
+#[test]
+fn positions_of_a_standalone_file_are_offsets() {
+    let source = "A: {\n    width: 1.0\n}\n";
+    assert_eq!(text_pos_to_offset(source, pos(0, 0), pos(0, 0)), Some(0));
+    assert_eq!(text_pos_to_offset(source, pos(0, 0), pos(1, 11)), Some(16));
+    assert_eq!(&source[16..19], "1.0");
+    assert_eq!(text_pos_to_offset(source, pos(0, 0), pos(3, 0)), Some(source.len()));
+    assert_eq!(text_pos_to_offset(source, pos(0, 0), pos(5, 0)), None);
+}
+
+#[test]
+fn positions_of_a_rust_block_are_offset_by_its_start() {
+    // the body of `live_register!{A: {width: 1.0}` starting at line 2, column 15 of the Rust file
+    let source = "A: {width: 1.0}\n    B: {height: é2.0}\n";
+    let start_pos = pos(2, 15);
+    // on the first line the column of the block is subtracted
+    assert_eq!(text_pos_to_offset(source, start_pos, pos(2, 26)), Some(11));
+    assert_eq!(&source[11..14], "1.0");
+    assert_eq!(text_pos_to_offset(source, start_pos, pos(2, 3)), None);
+    // on later lines columns are counted from the start of the line, in chars
+    let offset = text_pos_to_offset(source, start_pos, pos(3, 17)).unwrap();
+    assert_eq!(&source[offset..offset + 3], "2.0");
+    assert_eq!(text_pos_to_offset(source, start_pos, pos(1, 0)), None);
+}
@@ -5,6 +5,7 @@ live_register!{
     use makepad_component::frame::*;
     use FrameComponent::*;
     App: {{App}} {
+        inspector: {write_back: false}
         frame: {
             width: Fill
             height: Fill
@@ -41,6 +42,7 @@ pub enum FromUI {
 #[derive(Live, LiveHook)]
 pub struct App {
     frame: Frame,
+    inspector: FrameInspector,
     window: DesktopWindow,
     #[rust] to_ui: ToUIReceiver<ToUI>,
     #[rust] from_ui: FromUISender<FromUI>,
@@ -57,6 +59,10 @@ impl App {
     
     pub fn handle_event(&mut self, cx: &mut Cx, event: &mut Event) {
         self.window.handle_event(cx, event);
+        if self.inspector.handle_event(cx, event, &mut self.frame) {
+            return
+        }
+        self.frame.handle_event(cx, event);
         
         if let Ok(data) = self.to_ui.try_recv(event){
             console_log!("GOT DATA {:?}", data);
@@ -92,6 +98,7 @@ impl App {
         while let Err(_child) = self.frame.draw(cx){
             
         };
+        self.inspector.draw(cx, &mut self.frame);
         self.window.end(cx);
     }
 }
\ No newline at end of file
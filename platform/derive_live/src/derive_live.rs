@@ -343,11 +343,11 @@ fn parse_live_type(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Result<()
                         if attr.name != "live" {
                             return error_result("For option type only use of live is supported")
                         }
-                        tb.add("live_type_info:").stream(Some(inside)).add("::live_type_info(cx),");
+                        tb.add("live_type_info:<").stream(Some(inside)).add(">::live_type_info(cx),");
                         tb.add("live_field_kind: LiveFieldKind::LiveOption");
                     }
                     Err(not_option) => {
-                        tb.add("live_type_info:").stream(Some(not_option)).add("::live_type_info(cx),");
+                        tb.add("live_type_info:<").stream(Some(not_option)).add(">::live_type_info(cx),");
                         if attr.name == "live" {
                             tb.add("live_field_kind: LiveFieldKind::Live");
                         }
@@ -384,10 +384,10 @@ fn parse_live_type(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Result<()
             if attr.name == "live" || attr.name == "calc" {
                 match unwrap_option(field.ty.clone()) {
                     Ok(inside) => {
-                        tb.stream(Some(inside)).add("::live_register(cx);");
+                        tb.add("<").stream(Some(inside)).add(">::live_register(cx);");
                     }
                     Err(not_option) => {
-                        tb.stream(Some(not_option)).add("::live_register(cx);");
+                        tb.add("<").stream(Some(not_option)).add(">::live_register(cx);");
                     }
                 }
             }
@@ -515,7 +515,13 @@ fn parse_live_type(parser: &mut TokenParser, tb: &mut TokenBuilder) -> Result<()
         tb.add("    }");
         
         tb.add("    fn live_register(cx: &mut Cx) {");
-        
+        tb.add("        cx.live_registry.borrow_mut().register_enum_variants(LiveType::of::<Self>(), vec![");
+        for item in &items {
+            if let EnumKind::Bare = item.kind {
+                tb.add("LiveId::from_str(").string(&item.name).add(").unwrap(),");
+            }
+        }
+        tb.add("        ]);");
         
         let is_u32_enum = main_attribs.iter().find( | attr | attr.name == "repr" && attr.args.as_ref().unwrap().to_string().to_lowercase() == "u32").is_some();
         if is_u32_enum {
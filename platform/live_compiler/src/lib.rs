@@ -12,6 +12,9 @@ pub mod live_registry;
 pub mod live_expander;
 pub mod live_ptr;
 pub mod live_eval;
+pub mod live_eval_fn;
+pub mod live_catalog;
+pub mod live_printer;
 pub mod live_component;
 
 pub use makepad_math;
@@ -35,6 +38,15 @@ pub use {
             live_eval,
             LiveEval
         },
+        live_eval_fn::{
+            LiveEvalFn,
+            LiveEvalFns
+        },
+        live_catalog::{
+            check_catalogs,
+            plural_category
+        },
+        live_printer::LivePrinter,
         live_registry::{
             LiveEditEvent,
             LiveRegistry,
@@ -0,0 +1,186 @@
+use {
+    std::collections::HashSet,
+    crate::{
+        makepad_live_id::*,
+        makepad_live_tokenizer::{live_error_origin, LiveErrorOrigin},
+        live_error::LiveError,
+        live_eval::LiveEval,
+        live_node::{LiveValue, LiveNode, LiveIdAsProp},
+        live_node_vec::LiveNodeSlice,
+        live_registry::LiveRegistry,
+    }
+};
+
+// Message catalogs are live documents with a root object `Catalog`, which has the locale it
+// translates to as `locale` and the messages as the other properties:
+//
+//     Catalog: {
+//         locale: nl
+//         greeting: "Hallo {0}"
+//         files: {one: "{0} bestand", other: "{0} bestanden"}
+//     }
+//
+// A message is either a string, or an object with a string for each plural category of the
+// language. Any number of documents can have a catalog for the same locale.
+
+/// Returns the locale without its region, such as `nl` for `nl_BE`, or `None` if it has no region.
+pub fn locale_language(locale: LiveId) -> Option<LiveId> {
+    locale.as_string( | locale | {
+        let (language, _) = locale?.split_once('_') ?;
+        LiveId::from_str(language).ok()
+    })
+}
+
+/// Returns the plural category of the number `n` in the language of `locale`, following the
+/// CLDR rules for integers. Languages without rules here use the English rule.
+pub fn plural_category(locale: LiveId, n: f64) -> LiveId {
+    let language = locale_language(locale).unwrap_or(locale);
+    if n.fract() != 0.0 {
+        return id!(other)
+    }
+    let n = n.abs() as u64;
+    let (n10, n100) = (n % 10, n % 100);
+    match language {
+        id!(ja) | id!(zh) | id!(ko) | id!(th) | id!(vi) | id!(id) | id!(ms) => id!(other),
+        id!(fr) | id!(pt) | id!(hi) => if n <= 1 {id!(one)} else {id!(other)},
+        id!(ru) | id!(uk) | id!(be) | id!(sr) | id!(hr) | id!(bs) => {
+            if n10 == 1 && n100 != 11 {id!(one)}
+            else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {id!(few)}
+            else {id!(many)}
+        }
+        id!(pl) => {
+            if n == 1 {id!(one)}
+            else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {id!(few)}
+            else {id!(many)}
+        }
+        id!(cs) | id!(sk) => match n {
+            1 => id!(one),
+            2..=4 => id!(few),
+            _ => id!(other)
+        }
+        id!(ar) => match n {
+            0 => id!(zero),
+            1 => id!(one),
+            2 => id!(two),
+            _ if (3..=10).contains(&n100) => id!(few),
+            _ if n100 >= 11 => id!(many),
+            _ => id!(other)
+        }
+        _ => if n == 1 {id!(one)} else {id!(other)}
+    }
+}
+
+/// Replaces the placeholders `{0}`, `{1}` and so on in `format` with the arguments. Braces that
+/// are not a placeholder of an argument are kept.
+pub fn format_message(format: &str, args: &[LiveEval]) -> String {
+    let mut out = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let arg = rest.find('}').and_then( | end | {
+            let index = rest[1..end].parse::<usize>().ok() ?;
+            Some((args.get(index) ?, end))
+        });
+        match arg {
+            Some((arg, end)) => {
+                match arg {
+                    LiveEval::String(v) => out.push_str(v),
+                    LiveEval::Int(v) => out.push_str(&v.to_string()),
+                    LiveEval::Float(v) => out.push_str(&v.to_string()),
+                    LiveEval::Bool(v) => out.push_str(&v.to_string()),
+                    arg => out.push_str(&format!("{:?}", arg))
+                }
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Checks the message catalogs against each other and against the messages that are used, which
+/// are the messages of the `tr` calls in the live documents and the `used` messages, such as the
+/// ones Rust code asks for. A message that is used or in any catalog but missing from a catalog is
+/// an error, and so is a message in a catalog that is not used.
+pub fn check_catalogs(live_registry: &LiveRegistry, used: &HashSet<LiveId>) -> Vec<LiveError> {
+    let mut used = used.clone();
+    let mut catalogs = Vec::new();
+    for live_file in &live_registry.live_files {
+        let nodes = &live_file.expanded.nodes;
+        if nodes.is_empty() {
+            continue
+        }
+        for (index, node) in nodes.iter().enumerate() {
+            if let LiveValue::ExprCall {ident: id!(tr), args} = node.value {
+                if let Some(message) = nodes.get(index + 1).filter( | _ | args > 0).and_then( | node | live_registry.live_node_as_string(node)) {
+                    used.insert(LiveId::from_str_unchecked(&message));
+                }
+            }
+        }
+        if let Some(catalog) = nodes.child_by_name(0, id!(Catalog).as_field()) {
+            catalogs.push((nodes.as_slice(), catalog));
+        }
+    }
+    
+    // the messages of all catalogs in order, followed by the used messages that are in none
+    let mut messages = Vec::new();
+    for (nodes, catalog) in &catalogs {
+        for index in catalog_messages(nodes, *catalog) {
+            if !messages.contains(&nodes[index].id) {
+                messages.push(nodes[index].id);
+            }
+        }
+    }
+    let mut unknown: Vec<LiveId> = used.iter().filter( | id | !messages.contains(id)).cloned().collect();
+    unknown.sort_by_key( | id | id.to_string());
+    messages.extend(unknown);
+    
+    let mut errors = Vec::new();
+    for (nodes, catalog) in &catalogs {
+        let locale = match nodes.child_by_name(*catalog, id!(locale).as_field()).map( | index | &nodes[index].value) {
+            Some(LiveValue::Id(locale)) => *locale,
+            _ => {
+                errors.push(catalog_error(nodes, *catalog, "Catalog has no locale".to_string()));
+                continue
+            }
+        };
+        let catalog_messages = catalog_messages(nodes, *catalog);
+        for message in &messages {
+            if !catalog_messages.iter().any( | index | nodes[*index].id == *message) {
+                errors.push(catalog_error(nodes, *catalog, format!("Catalog {} is missing message {}", locale, message)));
+            }
+        }
+        for index in catalog_messages {
+            if !used.contains(&nodes[index].id) {
+                errors.push(catalog_error(nodes, index, format!("Message {} in catalog {} is not used", nodes[index].id, locale)));
+            }
+        }
+    }
+    errors
+}
+
+// Returns the indices of the messages of the catalog at `catalog`.
+fn catalog_messages(nodes: &[LiveNode], catalog: usize) -> Vec<usize> {
+    let mut messages = Vec::new();
+    let mut index = catalog + 1;
+    while !nodes[index].value.is_close() {
+        if nodes[index].id != id!(locale) {
+            messages.push(index);
+        }
+        index = nodes.skip_node(index);
+    }
+    messages
+}
+
+fn catalog_error(nodes: &[LiveNode], index: usize, message: String) -> LiveError {
+    LiveError {
+        origin: live_error_origin!(),
+        span: nodes[index].origin.token_id().unwrap().into(),
+        message
+    }
+}
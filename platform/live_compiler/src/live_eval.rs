@@ -9,12 +9,13 @@ pub use {
         live_error::{LiveError},
         live_node_vec::*,
         live_registry::LiveRegistry,
+        live_eval_fn::Components,
         live_node::*
     }
 };
 
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum LiveEval {
     Float(f64),
     Vec2(Vec2),
@@ -42,6 +43,10 @@ impl LiveError {
         Self::eval_error(origin, index, nodes, format!("Expression call not implemented ident:{} with number of args: {}", ident, args))
     }
     
+    fn eval_error_expression_call_failed(origin: LiveErrorOrigin, index: usize, nodes: &[LiveNode], ident: LiveId, message: String)->Self {
+        Self::eval_error(origin, index, nodes, format!("Expression call {} failed: {}", ident, message))
+    }
+    
     fn eval_error_cant_find_target(origin: LiveErrorOrigin, index: usize, nodes: &[LiveNode], id: LiveId)->Self {
         Self::eval_error(origin, index, nodes, format!("cant find target: {}", id))
     }
@@ -61,7 +66,8 @@ pub fn live_eval(live_registry: &LiveRegistry, start: usize, index: &mut usize,
         LiveValue::FittedString(_) |
         LiveValue::InlineString(_) |
         LiveValue::DocumentString {..} => {
-            LiveEval::String(live_registry.live_node_as_string(&nodes[*index]).unwrap())
+            *index += 1;
+            LiveEval::String(live_registry.live_node_as_string(&nodes[*index - 1]).unwrap())
         }
         LiveValue::Float(v) => {
             *index += 1;
@@ -181,23 +187,52 @@ pub fn live_eval(live_registry: &LiveRegistry, start: usize, index: &mut usize,
         }
         LiveValue::ExprCall {ident, args} => {
             *index += 1;
-            match ident {
-                id!(blend) if *args == 2 => {
-                    let a = live_eval(live_registry, start, index, nodes)?;
-                    let b = live_eval(live_registry, start, index, nodes)?;
-                    if let LiveEval::Vec4(va) = a {
-                        if let LiveEval::Vec4(vb) = b {
-                            // ok so how do we blend this eh.
-                            return Ok(LiveEval::Vec4(vec4(
-                                va.x + (vb.x - va.x) * vb.w,
-                                va.y + (vb.y - va.y) * vb.w,
-                                va.z + (vb.z - va.z) * vb.w,
-                                va.w
-                            )))
-                        }
-                    }
+            if *ident == id!(cond) && *args == 3 {
+                // only the branch that is picked is evaluated
+                let call_index = *index - 1;
+                return match live_eval(live_registry, start, index, nodes)? {
+                    LiveEval::Bool(true) => {
+                        let value = live_eval(live_registry, start, index, nodes)?;
+                        skip_expr(index, nodes);
+                        Ok(value)
+                    }
+                    LiveEval::Bool(false) => {
+                        skip_expr(index, nodes);
+                        live_eval(live_registry, start, index, nodes)
+                    }
+                    value => Err(LiveError::eval_error_expression_call_failed(
+                        live_error_origin!(),
+                        call_index,
+                        nodes,
+                        *ident,
+                        format!("expected a bool as condition, got {:?}", value)
+                    ))
                 }
-                _ => {}
+            }
+            if *ident == id!(tr) && *args > 0 {
+                // translations depend on the locale of the registry
+                let call_index = *index - 1;
+                let mut values = Vec::with_capacity(*args);
+                for _ in 0..*args {
+                    values.push(live_eval(live_registry, start, index, nodes)?);
+                }
+                let message = match &values[0] {
+                    LiveEval::String(message) => LiveId::from_str(message).map_err( | _ | format!("message name {} collides with another id", message)),
+                    value => Err(format!("expected a message name, got {:?}", value))
+                };
+                return message.and_then( | message | live_registry.tr(message, &values[1..])).map(LiveEval::String).map_err( | message | {
+                    LiveError::eval_error_expression_call_failed(live_error_origin!(), call_index, nodes, *ident, message)
+                })
+            }
+            if let Some(f) = live_registry.eval_fns.get(*ident) {
+                let call_index = *index - 1;
+                let mut values = Vec::with_capacity(*args);
+                for _ in 0..*args {
+                    values.push(live_eval(live_registry, start, index, nodes)?);
+                }
+                return f(&values).map_err( | message | {
+                    LiveError::eval_error_expression_call_failed(live_error_origin!(), call_index, nodes, *ident, message)
+                })
             }
             
             return Err(LiveError::eval_error_expression_call_not_implemented(live_error_origin!(), *index, nodes, *ident, *args))
@@ -332,6 +367,13 @@ pub fn live_eval(live_registry: &LiveRegistry, start: usize, index: &mut usize,
                     _ => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b))
                 },
                 LiveBinOp::Add => match a {
+                    LiveEval::String(va) => match b {
+                        LiveEval::String(vb) => LiveEval::String(va + &vb),
+                        LiveEval::Int(vb) => LiveEval::String(format!("{}{}", va, vb)),
+                        LiveEval::Float(vb) => LiveEval::String(format!("{}{}", va, vb)),
+                        LiveEval::Bool(vb) => LiveEval::String(format!("{}{}", va, vb)),
+                        _ => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, LiveEval::String(va), b))
+                    }
                     LiveEval::Int(va) => match b {
                         LiveEval::Int(vb) => LiveEval::Int(va + vb),
                         LiveEval::Float(vb) => LiveEval::Float((va as f64) + vb),
@@ -478,6 +520,17 @@ pub fn live_eval(live_registry: &LiveRegistry, start: usize, index: &mut usize,
                         _ => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b))
                     } _ => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b))
                 },
+                LiveBinOp::Rem => match (&a, &b) {
+                    (LiveEval::Int(_), LiveEval::Int(0)) => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b)),
+                    (LiveEval::Int(va), LiveEval::Int(vb)) => LiveEval::Int(va.rem_euclid(*vb)),
+                    _ => match (Components::from_eval(&a), Components::from_eval(&b)) {
+                        (Ok(ca), Ok(cb)) => match Components::zip(ca, cb, false, f64::rem_euclid) {
+                            Ok(result) => result.into_eval(),
+                            Err(_) => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b))
+                        }
+                        _ => return Err(LiveError::eval_error_binop_undefined_in_expression(live_error_origin!(), *index, nodes, *op, a, b))
+                    }
+                },
             }
         }
         _ => {
@@ -485,3 +538,17 @@ pub fn live_eval(live_registry: &LiveRegistry, start: usize, index: &mut usize,
         }
     })
 }
+
+// Skips the nodes of the expression that starts at `index`, without evaluating it.
+fn skip_expr(index: &mut usize, nodes: &[LiveNode]) {
+    let operand_count = match &nodes[*index].value {
+        LiveValue::ExprBinOp(_) => 2,
+        LiveValue::ExprUnOp(_) | LiveValue::ExprMember(_) => 1,
+        LiveValue::ExprCall {args, ..} => *args,
+        _ => 0
+    };
+    *index += 1;
+    for _ in 0..operand_count {
+        skip_expr(index, nodes);
+    }
+}
@@ -0,0 +1,276 @@
+use {
+    std::collections::HashMap,
+    crate::{
+        makepad_math::*,
+        makepad_live_id::*,
+        live_eval::LiveEval,
+    }
+};
+
+/// A function that can be called from a live expression. It gets the values of its arguments, and
+/// returns either its result or a message that says why the arguments are invalid. Functions must
+/// be pure, since expressions can be evaluated any number of times.
+pub type LiveEvalFn = fn(args: &[LiveEval]) -> Result<LiveEval, String>;
+
+/// The functions that can be called from live expressions, by name.
+///
+/// Besides the built-in functions, applications can register their own with `register`. The
+/// conditional expression `c ? a : b` is parsed as a call to `cond`, which is evaluated by
+/// `live_eval` itself, since only one of its branches may be evaluated, and so is `tr`, which
+/// translates a message with `LiveRegistry::tr`.
+pub struct LiveEvalFns {
+    fns: HashMap<LiveId, LiveEvalFn>,
+}
+
+impl Default for LiveEvalFns {
+    fn default() -> Self {
+        let mut fns = Self {fns: HashMap::new()};
+        fns.register(id!(blend), blend);
+        fns.register(id!(mix), mix);
+        fns.register(id!(min), | args | zip_args(args, f64::min));
+        fns.register(id!(max), | args | zip_args(args, f64::max));
+        fns.register(id!(clamp), clamp);
+        fns.register(id!(abs), | args | map_arg(args, true, f64::abs));
+        fns.register(id!(floor), | args | map_arg(args, false, f64::floor));
+        fns.register(id!(ceil), | args | map_arg(args, false, f64::ceil));
+        fns.register(id!(round), | args | map_arg(args, false, f64::round));
+        fns.register(id!(sqrt), | args | map_arg(args, false, f64::sqrt));
+        fns.register(id!(sin), | args | map_arg(args, false, f64::sin));
+        fns.register(id!(cos), | args | map_arg(args, false, f64::cos));
+        fns.register(id!(pow), | args | {
+            let (base, exponent) = expect_two(args) ?;
+            Ok(Components::zip(base, exponent, false, f64::powf) ?.into_eval())
+        });
+        fns.register(id!(vec2), | args | Components::from_args(args, 2).map(Components::into_eval));
+        fns.register(id!(vec3), | args | Components::from_args(args, 3).map(Components::into_eval));
+        fns.register(id!(vec4), | args | Components::from_args(args, 4).map(Components::into_eval));
+        fns.register(id!(hsl), | args | hsla(args, 3));
+        fns.register(id!(hsla), | args | hsla(args, 4));
+        fns.register(id!(lighten), | args | adjust_hsl(args, | hsl, amount | hsl.z += amount));
+        fns.register(id!(darken), | args | adjust_hsl(args, | hsl, amount | hsl.z -= amount));
+        fns.register(id!(saturate), | args | adjust_hsl(args, | hsl, amount | hsl.y += amount));
+        fns.register(id!(alpha), | args | {
+            expect_arg_count(args, 2) ?;
+            let mut color = expect_color(&args[0]) ?;
+            color.w = expect_number(&args[1]) ? as f32;
+            Ok(LiveEval::Vec4(color))
+        });
+        fns
+    }
+}
+
+impl LiveEvalFns {
+    /// Makes `f` callable from live expressions as `ident`, replacing the function that had that
+    /// name before, if any.
+    pub fn register(&mut self, ident: LiveId, f: LiveEvalFn) {
+        self.fns.insert(ident, f);
+    }
+
+    pub fn get(&self, ident: LiveId) -> Option<LiveEvalFn> {
+        self.fns.get(&ident).cloned()
+    }
+}
+
+/// The value of a number or vector as its components, for the functions and operators that work
+/// on each component.
+#[derive(Clone, Copy, Debug)]
+pub struct Components {
+    /// The number of components, which is 1 for a number.
+    pub len: usize,
+    /// Whether the value is an integer, rather than a float or a vector.
+    pub is_int: bool,
+    pub values: [f64; 4],
+}
+
+impl Components {
+    pub fn from_eval(value: &LiveEval) -> Result<Components, String> {
+        let (len, values) = match value {
+            LiveEval::Int(v) => return Ok(Components {len: 1, is_int: true, values: [*v as f64, 0.0, 0.0, 0.0]}),
+            LiveEval::Float(v) => (1, [*v, 0.0, 0.0, 0.0]),
+            LiveEval::Vec2(v) => (2, [v.x as f64, v.y as f64, 0.0, 0.0]),
+            LiveEval::Vec3(v) => (3, [v.x as f64, v.y as f64, v.z as f64, 0.0]),
+            LiveEval::Vec4(v) => (4, [v.x as f64, v.y as f64, v.z as f64, v.w as f64]),
+            value => return Err(format!("expected a number or vector, got {:?}", value))
+        };
+        Ok(Components {len, is_int: false, values})
+    }
+
+    /// Concatenates the components of the `args` into a vector with `len` components, or makes a
+    /// vector with `len` times the same component if there is only one.
+    pub fn from_args(args: &[LiveEval], len: usize) -> Result<Components, String> {
+        let mut values = Vec::new();
+        for arg in args {
+            let components = Components::from_eval(arg) ?;
+            values.extend_from_slice(&components.values[..components.len]);
+        }
+        if values.len() == 1 {
+            values.resize(len, values[0]);
+        }
+        if values.len() != len {
+            return Err(format!("expected {} components, got {}", len, values.len()))
+        }
+        let mut components = Components {len, is_int: false, values: [0.0; 4]};
+        components.values[..len].copy_from_slice(&values);
+        Ok(components)
+    }
+
+    fn get(&self, index: usize) -> f64 {
+        if self.len == 1 {self.values[0]} else {self.values[index]}
+    }
+
+    /// Applies `f` to each component.
+    pub fn map(self, keeps_int: bool, f: impl Fn(f64) -> f64) -> Components {
+        let mut result = self;
+        result.is_int = self.is_int && keeps_int;
+        for index in 0..self.len {
+            result.values[index] = f(self.values[index]);
+        }
+        result
+    }
+
+    /// Applies `f` to each pair of components of `a` and `b`. If one of them is a number, it is
+    /// paired with each component of the other.
+    pub fn zip(a: Components, b: Components, keeps_int: bool, f: impl Fn(f64, f64) -> f64) -> Result<Components, String> {
+        if a.len != b.len && a.len != 1 && b.len != 1 {
+            return Err(format!("expected vectors of the same size, got sizes {} and {}", a.len, b.len))
+        }
+        let mut result = Components {len: a.len.max(b.len), is_int: a.is_int && b.is_int && keeps_int, values: [0.0; 4]};
+        for index in 0..result.len {
+            result.values[index] = f(a.get(index), b.get(index));
+        }
+        Ok(result)
+    }
+
+    pub fn into_eval(self) -> LiveEval {
+        let v = self.values;
+        match self.len {
+            1 if self.is_int => LiveEval::Int(v[0] as i64),
+            1 => LiveEval::Float(v[0]),
+            2 => LiveEval::Vec2(vec2(v[0] as f32, v[1] as f32)),
+            3 => LiveEval::Vec3(vec3(v[0] as f32, v[1] as f32, v[2] as f32)),
+            _ => LiveEval::Vec4(vec4(v[0] as f32, v[1] as f32, v[2] as f32, v[3] as f32)),
+        }
+    }
+}
+
+fn expect_arg_count(args: &[LiveEval], count: usize) -> Result<(), String> {
+    if args.len() != count {
+        return Err(format!("expected {} arguments, got {}", count, args.len()))
+    }
+    Ok(())
+}
+
+fn expect_two(args: &[LiveEval]) -> Result<(Components, Components), String> {
+    expect_arg_count(args, 2) ?;
+    Ok((Components::from_eval(&args[0]) ?, Components::from_eval(&args[1]) ?))
+}
+
+fn expect_number(arg: &LiveEval) -> Result<f64, String> {
+    match arg {
+        LiveEval::Int(v) => Ok(*v as f64),
+        LiveEval::Float(v) => Ok(*v),
+        arg => Err(format!("expected a number, got {:?}", arg))
+    }
+}
+
+fn expect_color(arg: &LiveEval) -> Result<Vec4, String> {
+    match arg {
+        LiveEval::Vec4(v) => Ok(*v),
+        arg => Err(format!("expected a color, got {:?}", arg))
+    }
+}
+
+fn map_arg(args: &[LiveEval], keeps_int: bool, f: fn(f64) -> f64) -> Result<LiveEval, String> {
+    expect_arg_count(args, 1) ?;
+    Ok(Components::from_eval(&args[0]) ?.map(keeps_int, f).into_eval())
+}
+
+fn zip_args(args: &[LiveEval], f: fn(f64, f64) -> f64) -> Result<LiveEval, String> {
+    let (a, b) = expect_two(args) ?;
+    Ok(Components::zip(a, b, true, f) ?.into_eval())
+}
+
+// Blends the color `b` over the color `a`, using the alpha of `b`.
+fn blend(args: &[LiveEval]) -> Result<LiveEval, String> {
+    expect_arg_count(args, 2) ?;
+    let (a, b) = (expect_color(&args[0]) ?, expect_color(&args[1]) ?);
+    Ok(LiveEval::Vec4(vec4(
+        a.x + (b.x - a.x) * b.w,
+        a.y + (b.y - a.y) * b.w,
+        a.z + (b.z - a.z) * b.w,
+        a.w
+    )))
+}
+
+fn mix(args: &[LiveEval]) -> Result<LiveEval, String> {
+    expect_arg_count(args, 3) ?;
+    let a = Components::from_eval(&args[0]) ?;
+    let b = Components::from_eval(&args[1]) ?;
+    let t = Components::from_eval(&args[2]) ?;
+    let delta = Components::zip(b, a, false, | b, a | b - a) ?;
+    let scaled = Components::zip(delta, t, false, | delta, t | delta * t) ?;
+    Ok(Components::zip(a, scaled, false, | a, scaled | a + scaled) ?.into_eval())
+}
+
+fn clamp(args: &[LiveEval]) -> Result<LiveEval, String> {
+    expect_arg_count(args, 3) ?;
+    let x = Components::from_eval(&args[0]) ?;
+    let low = Components::from_eval(&args[1]) ?;
+    let high = Components::from_eval(&args[2]) ?;
+    let x = Components::zip(x, low, true, f64::max) ?;
+    Ok(Components::zip(x, high, true, f64::min) ?.into_eval())
+}
+
+// Makes a color from a hue in degrees, and a saturation, lightness and, if `count` is 4, alpha
+// between 0 and 1.
+fn hsla(args: &[LiveEval], count: usize) -> Result<LiveEval, String> {
+    expect_arg_count(args, count) ?;
+    let mut hsla = [1.0; 4];
+    for (index, arg) in args.iter().enumerate() {
+        hsla[index] = expect_number(arg) ? as f32;
+    }
+    Ok(LiveEval::Vec4(hsl_to_rgb(vec4(hsla[0] / 360.0, hsla[1], hsla[2], hsla[3]))))
+}
+
+// Changes the hue, saturation and lightness of a color with `f`, which gets them as the `x`, `y`
+// and `z` of a vector, and the amount to change them by.
+fn adjust_hsl(args: &[LiveEval], f: fn(&mut Vec4, f32)) -> Result<LiveEval, String> {
+    expect_arg_count(args, 2) ?;
+    let mut hsl = rgb_to_hsl(expect_color(&args[0]) ?);
+    f(&mut hsl, expect_number(&args[1]) ? as f32);
+    hsl.y = hsl.y.clamp(0.0, 1.0);
+    hsl.z = hsl.z.clamp(0.0, 1.0);
+    Ok(LiveEval::Vec4(hsl_to_rgb(hsl)))
+}
+
+// Converts a color from RGB to HSL, with the hue between 0 and 1. The alpha is kept.
+fn rgb_to_hsl(color: Vec4) -> Vec4 {
+    let max = color.x.max(color.y).max(color.z);
+    let min = color.x.min(color.y).min(color.z);
+    let lightness = (max + min) / 2.0;
+    if max == min {
+        return vec4(0.0, 0.0, lightness, color.w)
+    }
+    let delta = max - min;
+    let saturation = if lightness > 0.5 {delta / (2.0 - max - min)} else {delta / (max + min)};
+    let hue = if max == color.x {
+        (color.y - color.z) / delta + if color.y < color.z {6.0} else {0.0}
+    }
+    else if max == color.y {
+        (color.z - color.x) / delta + 2.0
+    }
+    else {
+        (color.x - color.y) / delta + 4.0
+    };
+    vec4(hue / 6.0, saturation, lightness, color.w)
+}
+
+// Converts a color from HSL, with the hue between 0 and 1, to RGB. The alpha is kept.
+fn hsl_to_rgb(hsl: Vec4) -> Vec4 {
+    let chroma = (1.0 - (2.0 * hsl.z - 1.0).abs()) * hsl.y;
+    let channel = | n: f32 | {
+        let k = (n + hsl.x.rem_euclid(1.0) * 12.0) % 12.0;
+        hsl.z - chroma / 2.0 * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    vec4(channel(0.0), channel(8.0), channel(4.0), hsl.w)
+}
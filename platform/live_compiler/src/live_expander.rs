@@ -5,7 +5,7 @@ use {
         live_ptr::{LiveFileId, LivePtr, LiveFileGeneration},
         live_error::{LiveError},
         live_document::{LiveOriginal, LiveExpanded},
-        live_node::{LiveValue, LiveNode, LiveIdAsProp},
+        live_node::{LiveValue, LiveNode, LiveIdAsProp, LivePropType},
         live_node_vec::{LiveNodeSlice, LiveNodeVec},
         live_registry::{LiveRegistry, LiveScopeTarget},
     }
@@ -47,6 +47,52 @@ impl<'a> LiveExpander<'a> {
         }
     }
     
+    /// Overrides the root values of a document with the properties of its root object `theme`,
+    /// if it has one. Objects are overridden property by property, and replaced nodes keep
+    /// their origin so constants stay constants.
+    pub fn apply_theme(nodes: &mut Vec<LiveNode>, theme: LiveId) {
+        let theme_index = match nodes.child_by_name(0, theme.as_field()) {
+            Some(index) if nodes[index].value.is_open() => index,
+            _ => return
+        };
+        for value_nodes in Self::child_slices(nodes, theme_index) {
+            if value_nodes[0].id != theme {
+                Self::apply_theme_value(nodes, 0, value_nodes, false);
+            }
+        }
+    }
+    
+    fn child_slices(nodes: &[LiveNode], parent_index: usize) -> Vec<Vec<LiveNode >> {
+        let mut children = Vec::new();
+        let mut index = parent_index + 1;
+        while !nodes[index].value.is_close() {
+            let next_index = nodes.skip_node(index);
+            children.push(nodes[index..next_index].to_vec());
+            index = next_index;
+        }
+        children
+    }
+    
+    fn apply_theme_value(nodes: &mut Vec<LiveNode>, parent_index: usize, value_nodes: Vec<LiveNode>, insert: bool) {
+        let id = value_nodes[0].id;
+        if let Some(index) = nodes.child_by_name(parent_index, id.as_field()) {
+            if nodes[index].value.is_open() && value_nodes[0].value.is_object() {
+                for child_nodes in Self::child_slices(&value_nodes, 0) {
+                    Self::apply_theme_value(nodes, index, child_nodes, true);
+                }
+                return
+            }
+            let origin = nodes[index].origin;
+            let next_index = nodes.skip_node(index);
+            nodes.splice(index..next_index, value_nodes);
+            nodes[index].origin = origin;
+        }
+        else if insert {
+            let close_index = nodes.skip_node(parent_index) - 1;
+            nodes.splice(close_index..close_index, value_nodes);
+        }
+    }
+    
     pub fn expand(&mut self, in_doc: &LiveOriginal, out_doc: &mut LiveExpanded, generation: LiveFileGeneration) {
         
         // ok first copy the edit_info over.
@@ -125,7 +171,14 @@ impl<'a> LiveExpander<'a> {
             
             //// determine node overwrite rules
             
-            let out_index = match out_doc.nodes.child_or_append_index_by_name(current_parent.last().unwrap().1, in_node.prop()) {
+            // array items have no name, so they are always appended instead of overwriting each other
+            let overwrite = if in_node.origin.has_prop_type(LivePropType::Nameless) {
+                Err(out_doc.nodes.append_child_index(current_parent.last().unwrap().1))
+            }
+            else {
+                out_doc.nodes.child_or_append_index_by_name(current_parent.last().unwrap().1, in_node.prop())
+            };
+            let out_index = match overwrite {
                 Ok(overwrite) => {
                     let out_value = &out_doc.nodes[overwrite].value;
                     
@@ -447,6 +500,10 @@ impl<'a> LiveExpander<'a> {
         }
         out_doc.nodes.push(in_doc.nodes.last().unwrap().clone());
         
+        if let Some(theme) = self.live_registry.active_theme {
+            Self::apply_theme(&mut out_doc.nodes, theme);
+        }
+        
         // this stores the node index on nodes that don't have a node index
         for i in 1..out_doc.nodes.len() {
             if out_doc.nodes[i].value.is_dsl() {
@@ -378,6 +378,7 @@ pub enum LiveBinOp {
     Sub,
     Mul,
     Div,
+    Rem,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
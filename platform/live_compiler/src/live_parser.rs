@@ -351,9 +351,10 @@ impl<'a> LiveParser<'a> {
             value: LiveValue::Array
         });
         while self.peek_token() != LiveToken::Eof {
+            let token_id = self.get_token_id();
             if self.accept_token(LiveToken::Close(Delim::Bracket)) {
                 ld.nodes.push(LiveNode {
-                    origin: LiveNodeOrigin::from_token_id(self.get_token_id()),
+                    origin: LiveNodeOrigin::from_token_id(token_id),
                     id: LiveId::empty(),
                     value: LiveValue::Close
                 });
@@ -378,9 +379,10 @@ impl<'a> LiveParser<'a> {
             value: LiveValue::TupleEnum {base, variant}
         });
         while self.peek_token() != LiveToken::Eof {
+            let token_id = self.get_token_id();
             if self.accept_token(LiveToken::Close(Delim::Paren)) {
                 ld.nodes.push(LiveNode {
-                    origin: LiveNodeOrigin::from_token_id(self.get_token_id()),
+                    origin: LiveNodeOrigin::from_token_id(token_id),
                     id: prop_id,
                     value: LiveValue::Close
                 });
@@ -404,15 +406,15 @@ impl<'a> LiveParser<'a> {
         });
         
         while self.peek_token() != LiveToken::Eof {
+            let token_id = self.get_token_id();
             if self.accept_token(LiveToken::Close(Delim::Brace)) {
                 ld.nodes.push(LiveNode {
-                    origin: LiveNodeOrigin::from_token_id(self.get_token_id()),
+                    origin: LiveNodeOrigin::from_token_id(token_id),
                     id: prop_id,
                     value: LiveValue::Close
                 });
                 return Ok(())
             }
-            let token_id = self.get_token_id();
             let prop_id = self.expect_ident() ?;
             let edit_info = self.possible_edit_info(ld) ?;
             self.expect_token(LiveToken::Punct(id!(:))) ?;
@@ -908,6 +910,13 @@ impl<'a> LiveParser<'a> {
                         value: LiveValue::Color(v)
                     });
                 }
+                Expr::Str {token_id, index, len} => {
+                    ld.nodes.push(LiveNode {
+                        origin: LiveNodeOrigin::from_token_id(token_id).with_prop_type(LivePropType::Nameless),
+                        id: LiveId::empty(),
+                        value: LiveValue::DocumentString {string_start: index as usize, string_count: len as usize}
+                    });
+                }
             }
         }
         
@@ -1103,6 +1112,11 @@ impl<'a> LiveParser<'a> {
                 let token_id = self.get_token_id();
                 Ok(Expr::Color {token_id, v})
             }
+            LiveToken::String {index, len} => {
+                let token_id = self.get_token_id();
+                self.skip_token();
+                Ok(Expr::Str {token_id, index, len})
+            }
             LiveToken::Open(Delim::Paren) => {
                 self.skip_token();
                 let expr = self.expect_expr() ?;
@@ -1173,6 +1187,11 @@ enum Expr {
     Color {
         token_id: LiveTokenId,
         v: u32
+    },
+    Str {
+        token_id: LiveTokenId,
+        index: u32,
+        len: u32
     }
 }
 
@@ -1222,6 +1241,7 @@ impl LiveBinOp {
         match token {
             LiveToken::Punct(id!(*)) => Some(Self::Mul),
             LiveToken::Punct(id!( /)) => Some(Self::Div),
+            LiveToken::Punct(id!(%)) => Some(Self::Rem),
             _ => None,
         }
     }
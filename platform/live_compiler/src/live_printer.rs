@@ -0,0 +1,738 @@
+use {
+    std::{
+        collections::HashMap,
+        fmt::Write,
+    },
+    crate::{
+        makepad_live_id::*,
+        makepad_live_tokenizer::Delim,
+        live_document::LiveOriginal,
+        live_node::{LiveNode, LiveValue, LivePropType, LiveBinOp, LiveUnOp},
+        live_node_vec::LiveNodeSlice,
+        live_ptr::LiveFileId,
+        live_registry::LiveRegistry,
+        live_token::{LiveToken, TokenWithSpan},
+        span::TextPos,
+    }
+};
+
+const INDENT: &str = "    ";
+
+/// Prints the nodes of a live document back to live source.
+///
+/// The nodes are usually an edited copy of the original nodes of a file. Nodes that are
+/// unchanged since that file was parsed are copied from its source, together with the comments
+/// and whitespace in front of them, so that printing an unchanged document gives back its
+/// source exactly. All other nodes are printed in the canonical format, which is also used for
+/// every node after calling `reformat`. Reformatting keeps the comments around the members of
+/// objects, and a single empty line where members were separated by empty lines.
+pub struct LivePrinter<'a> {
+    live_registry: &'a LiveRegistry,
+    file_id: LiveFileId,
+    preserve_source: bool,
+    // the byte offset in the source of the start of each line
+    line_starts: Vec<usize>,
+    // the index of each original node that has a source range, by the index of its origin token
+    original_indices: HashMap<usize, usize>,
+    out: String,
+}
+
+impl<'a> LivePrinter<'a> {
+    pub fn new(live_registry: &'a LiveRegistry, file_id: LiveFileId) -> Self {
+        let source = &live_registry.live_files[file_id.to_index()].source;
+        let mut line_starts = vec![0];
+        for (offset, byte) in source.bytes().enumerate() {
+            if byte == b'\n' {
+                line_starts.push(offset + 1);
+            }
+        }
+        let nodes = &live_registry.live_files[file_id.to_index()].original.nodes;
+        let mut original_indices = HashMap::new();
+        let mut index = 1;
+        while index < nodes.len() {
+            if !nodes[index].is_close() {
+                if let Some(token_id) = nodes[index].origin.token_id() {
+                    original_indices.insert(token_id.token_index(), index);
+                }
+            }
+            // expressions are always printed as a whole
+            if let LiveValue::Expr {..} = nodes[index].value {
+                index = nodes.skip_node(index);
+            }
+            else {
+                index += 1;
+            }
+        }
+        Self {
+            live_registry,
+            file_id,
+            preserve_source: true,
+            line_starts,
+            original_indices,
+            out: String::new(),
+        }
+    }
+
+    /// Prints all nodes in the canonical format, rather than copying unchanged nodes from the
+    /// source.
+    pub fn reformat(mut self) -> Self {
+        self.preserve_source = false;
+        self
+    }
+
+    /// Prints `nodes`, which should start with the root object of a document.
+    pub fn print(mut self, nodes: &[LiveNode]) -> String {
+        let original = self.original();
+        if !self.preserve_source {
+            let mut index = 1;
+            while index < nodes.len() && !nodes[index].is_close() {
+                index = self.print_member(nodes, index, "");
+            }
+            // the comments at the end of the file
+            let eof_token = original.tokens.len() - 1;
+            let start = if eof_token > 0 {self.trailing_end(eof_token - 1)} else {0};
+            self.print_comment_lines(&self.source()[start..], "");
+            self.out.push('\n');
+            return self.out
+        }
+        if nodes.len() == original.nodes.len() && nodes.iter().zip(original.nodes.iter()).all( | (a, b) | a == b) {
+            return self.source().to_string()
+        }
+        let mut index = 1;
+        while index < nodes.len() && !nodes[index].is_close() {
+            index = self.print_member(nodes, index, "");
+        }
+        // the comments and whitespace at the end of the file
+        let eof_token = original.tokens.len() - 1;
+        let start = if eof_token > 0 {self.trailing_end(eof_token - 1)} else {0};
+        self.out.push_str(&self.source()[start..]);
+        self.out
+    }
+
+    fn original(&self) -> &'a LiveOriginal {
+        &self.live_registry.live_files[self.file_id.to_index()].original
+    }
+
+    fn source(&self) -> &'a str {
+        &self.live_registry.live_files[self.file_id.to_index()].source
+    }
+
+    fn offset(&self, pos: TextPos) -> usize {
+        self.file_offset(self.file_id, pos)
+    }
+
+    // Returns the byte offset of `pos` in the source of the file `file_id`.
+    fn file_offset(&self, file_id: LiveFileId, pos: TextPos) -> usize {
+        let live_file = &self.live_registry.live_files[file_id.to_index()];
+        let line = (pos.line - live_file.start_pos.line) as usize;
+        let column = if line == 0 {pos.column - live_file.start_pos.column} else {pos.column} as usize;
+        let line_start = if file_id == self.file_id {
+            self.line_starts[line]
+        }
+        else {
+            live_file.source.split('\n').take(line).map( | line | line.len() + 1).sum()
+        };
+        match live_file.source[line_start..].char_indices().nth(column) {
+            Some((offset, _)) => line_start + offset,
+            None => live_file.source.len()
+        }
+    }
+
+    fn token_start(&self, token_index: usize) -> usize {
+        let token = &self.original().tokens[token_index];
+        if token.token == LiveToken::Eof {
+            return self.source().len()
+        }
+        self.offset(token.span.start)
+    }
+
+    fn token_end(&self, token_index: usize) -> usize {
+        self.offset(self.original().tokens[token_index].span.end)
+    }
+
+    // Returns the end of the comment on the same line as the token at `token_index`, if it is
+    // the last token on its line, and the end of the token otherwise.
+    fn trailing_end(&self, token_index: usize) -> usize {
+        let end = self.token_end(token_index);
+        let next_start = self.token_start(token_index + 1);
+        match self.source()[end..next_start].find('\n') {
+            Some(line_end) => end + line_end,
+            None if next_start == self.source().len() => next_start,
+            None => end
+        }
+    }
+
+    // Returns the index of the original node that `node` was copied from, if any.
+    fn original_index(&self, node: &LiveNode) -> Option<usize> {
+        let token_id = node.origin.token_id() ?;
+        if !self.preserve_source || token_id.file_id() != self.file_id {
+            return None
+        }
+        self.original_indices.get(&token_id.token_index()).cloned()
+    }
+
+    // Returns the index of the original node that `node` was copied from when reformatting.
+    fn reformatted_original_index(&self, node: &LiveNode) -> Option<usize> {
+        let token_id = node.origin.token_id() ?;
+        if self.preserve_source || token_id.file_id() != self.file_id {
+            return None
+        }
+        self.original_indices.get(&token_id.token_index()).cloned()
+    }
+
+    // Returns the index of the first token of the original node at `index`.
+    fn first_token(&self, index: usize) -> usize {
+        let node = &self.original().nodes[index];
+        let token_index = node.origin.token_id().unwrap().token_index();
+        match node.value {
+            LiveValue::DSL {token_start, ..} => token_start as usize,
+            // the `use` keyword or the prefix comes before the origin token
+            LiveValue::Use(_) | LiveValue::UseComponent(_) => token_index - 1,
+            _ if node.origin.node_has_prefix() => token_index - 1,
+            _ => token_index
+        }
+    }
+
+    // Returns the index of the last token of the original node at `index`, which includes the
+    // delimiter after it, if any.
+    fn last_token(&self, index: usize) -> usize {
+        let nodes = &self.original().nodes;
+        let next_index = nodes.skip_node(index);
+        if nodes[next_index].is_close() {
+            nodes[next_index].origin.token_id().unwrap().token_index() - 1
+        }
+        else {
+            self.first_token(next_index) - 1
+        }
+    }
+
+    // Prints the member of an object at `index`, and returns the index of the next member.
+    fn print_member(&mut self, nodes: &[LiveNode], index: usize, indent: &str) -> usize {
+        if let Some(original_index) = self.original_index(&nodes[index]) {
+            let first_token = self.first_token(original_index);
+            let last_token = self.last_token(original_index);
+            // comments on the lines before a node belong to it, and so does a comment on the
+            // same line after it
+            let start = if first_token > 0 {self.trailing_end(first_token - 1)} else {0};
+            let leading = &self.source()[start..self.token_start(first_token)];
+            let end = self.trailing_end(last_token);
+
+            let next_index = nodes.skip_node(index);
+            let original = &self.original().nodes;
+            if next_index - index == original.skip_node(original_index) - original_index
+                && nodes[index..next_index].iter().zip(original[original_index..].iter()).all( | (a, b) | a == b) {
+                self.out.push_str(&self.source()[start..end]);
+                return next_index
+            }
+
+            self.out.push_str(leading);
+            let indent = match leading.rfind('\n') {
+                Some(line_start) if leading[line_start + 1..].trim().is_empty() => leading[line_start + 1..].to_string(),
+                _ => indent.to_string()
+            };
+            let next_index = self.print_node(nodes, index, &indent, Some(original_index));
+            match self.original().tokens[last_token].token {
+                LiveToken::Punct(id!(,)) => self.out.push(','),
+                LiveToken::Punct(id!(;)) => self.out.push(';'),
+                _ => ()
+            }
+            self.out.push_str(&self.source()[self.token_end(last_token)..end]);
+            return next_index
+        }
+        let comments = self.reformatted_comments(&nodes[index]);
+        if let Some((empty_line, leading, _)) = comments {
+            if empty_line && !self.out.is_empty() && !self.out.ends_with('{') {
+                self.out.push('\n');
+            }
+            self.print_comment_lines(leading, indent);
+        }
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out.push_str(indent);
+        let next_index = self.print_node(nodes, index, indent, None);
+        if let Some((_, _, trailing)) = comments {
+            if !trailing.is_empty() {
+                self.out.push(' ');
+                self.out.push_str(trailing);
+            }
+        }
+        next_index
+    }
+
+    // Returns the comments around the original node of `node` when reformatting: whether an
+    // empty line comes before it, the text before it and the comment on the same line after it.
+    fn reformatted_comments(&self, node: &LiveNode) -> Option<(bool, &'a str, &'a str)> {
+        let original_index = self.reformatted_original_index(node) ?;
+        let first_token = self.first_token(original_index);
+        let last_token = self.last_token(original_index);
+        let start = if first_token > 0 {self.trailing_end(first_token - 1)} else {0};
+        let leading = &self.source()[start..self.token_start(first_token)];
+        let before_comments = &leading[..leading.find('/').unwrap_or(leading.len())];
+        let empty_line = before_comments.matches('\n').count() > 1;
+        let trailing = self.source()[self.token_end(last_token)..self.trailing_end(last_token)].trim();
+        Some((empty_line, leading, trailing))
+    }
+
+    // Prints each line of the comments in `text` on its own line.
+    fn print_comment_lines(&mut self, text: &str, indent: &str) {
+        for line in text.lines().map( | line | line.trim()).filter( | line | !line.is_empty()) {
+            if !self.out.is_empty() {
+                self.out.push('\n');
+            }
+            self.out.push_str(indent);
+            self.out.push_str(line);
+        }
+    }
+
+    // Prints the node at `index` with its name, and returns the index of the next node.
+    fn print_node(&mut self, nodes: &[LiveNode], index: usize, indent: &str, original_index: Option<usize>) -> usize {
+        let node = &nodes[index];
+        if let LiveValue::DSL {..} | LiveValue::Use(_) | LiveValue::UseComponent(_) = node.value {
+            // these nodes print their own name
+            return self.print_value(nodes, index, indent, false)
+        }
+        self.print_prop(node);
+        match &node.value {
+            value if is_object_like(value) => {
+                self.print_container_start(node);
+                self.out.push('{');
+                let open_end = self.out.len();
+                let child_indent = format!("{}{}", indent, INDENT);
+                let mut child_index = index + 1;
+                let mut has_children = false;
+                while !nodes[child_index].is_close() {
+                    child_index = self.print_member(nodes, child_index, &child_indent);
+                    has_children = true;
+                }
+                let original = &self.original().nodes;
+                match original_index {
+                    Some(original_index) if is_object_like(&original[original_index].value) => {
+                        let close_token = original[original.skip_node(original_index) - 1].origin.token_id().unwrap().token_index();
+                        let start = self.trailing_end(close_token - 1);
+                        let leading = &self.source()[start..self.token_start(close_token)];
+                        if leading.contains('\n') || !self.out[open_end..].contains('\n') {
+                            self.out.push_str(leading);
+                        }
+                        else {
+                            self.out.push('\n');
+                            self.out.push_str(indent);
+                        }
+                    }
+                    _ => {
+                        // the comments after the last member
+                        if let Some(original_index) = self.reformatted_original_index(node).filter( | index | is_object_like(&original[*index].value)) {
+                            let close_token = original[original.skip_node(original_index) - 1].origin.token_id().unwrap().token_index();
+                            let start = self.trailing_end(close_token - 1);
+                            let leading = &self.source()[start..self.token_start(close_token)];
+                            self.print_comment_lines(leading, &child_indent);
+                            has_children |= self.out.len() > open_end;
+                        }
+                        if has_children {
+                            self.out.push('\n');
+                            self.out.push_str(indent);
+                        }
+                    }
+                }
+                self.out.push('}');
+                child_index + 1
+            }
+            _ => self.print_value(nodes, index, indent, false)
+        }
+    }
+
+    // Prints the prefix, name and assignment of a property, if it has them.
+    fn print_prop(&mut self, node: &LiveNode) {
+        if node.origin.node_has_prefix() {
+            if let Some(token_id) = node.origin.token_id() {
+                let tokens = &self.live_registry.live_files[token_id.file_id().to_index()].original.tokens;
+                if let LiveToken::Ident(prefix) = tokens[token_id.token_index() - 1].token {
+                    let _ = write!(self.out, "{} ", prefix);
+                }
+            }
+        }
+        if is_anonymous_instance(node) {
+            return
+        }
+        match node.origin.prop_type() {
+            LivePropType::Nameless => (),
+            LivePropType::Field => {
+                let _ = write!(self.out, "{}", node.id);
+                self.print_edit_info(node);
+                self.out.push_str(": ");
+            }
+            LivePropType::Instance => {
+                let _ = write!(self.out, "{}", node.id);
+                self.print_edit_info(node);
+                self.out.push_str(" = ");
+            }
+            LivePropType::Template => {
+                let _ = write!(self.out, "{}", node.id);
+                self.print_edit_info(node);
+                self.out.push_str(" =? ");
+            }
+        }
+    }
+
+    fn print_edit_info(&mut self, node: &LiveNode) {
+        let (edit_info, token_id) = match (node.origin.edit_info(), node.origin.token_id()) {
+            (Some(edit_info), Some(token_id)) => (edit_info, token_id),
+            _ => return
+        };
+        let original = &self.live_registry.live_files[token_id.file_id().to_index()].original;
+        let nodes = &original.edit_info;
+        self.out.push_str(".{");
+        let mut index = edit_info.edit_info_index() + 1;
+        while index < nodes.len() && !nodes[index].is_close() {
+            if index > edit_info.edit_info_index() + 1 {
+                self.out.push_str(", ");
+            }
+            let _ = write!(self.out, "{}: ", nodes[index].id);
+            self.print_simple_value(&nodes[index], Some(original));
+            index += 1;
+        }
+        self.out.push('}');
+    }
+
+    // Prints what comes before the `{` of an object-like value.
+    fn print_container_start(&mut self, node: &LiveNode) {
+        match &node.value {
+            LiveValue::Clone(base) => {
+                let _ = write!(self.out, "{} ", base);
+            }
+            LiveValue::Class {live_type, ..} => {
+                // the source is checked first, since tools register placeholder types that
+                // share a live type
+                let type_name = self.class_name_from_tokens(node);
+                let type_name = type_name.or_else( | | self.live_registry.live_type_infos.get(live_type).map( | info | info.type_name));
+                let type_name = type_name.unwrap_or(id!(dummy));
+                let _ = write!(self.out, "{{{{{}}}}} ", type_name);
+            }
+            LiveValue::NamedEnum {base, variant} => {
+                let _ = write!(self.out, "{}::{} ", base, variant);
+            }
+            _ => ()
+        }
+    }
+
+    // Finds the type name in the `{{Type}}` tokens that follow the name of a class.
+    fn class_name_from_tokens(&self, node: &LiveNode) -> Option<LiveId> {
+        let token_id = node.origin.token_id() ?;
+        let tokens = &self.live_registry.live_files[token_id.file_id().to_index()].original.tokens;
+        let start = token_id.token_index();
+        for window in tokens[start..tokens.len().min(start + 32)].windows(3) {
+            if let [
+                TokenWithSpan {token: LiveToken::Open(Delim::Brace), ..},
+                TokenWithSpan {token: LiveToken::Open(Delim::Brace), ..},
+                TokenWithSpan {token: LiveToken::Ident(type_name), ..}
+            ] = window {
+                return Some(*type_name)
+            }
+        }
+        None
+    }
+
+    // Prints the value of the node at `index`, and returns the index of the next node. Values
+    // that are printed inline, such as the items of an array, print their objects on a
+    // single line.
+    fn print_value(&mut self, nodes: &[LiveNode], index: usize, indent: &str, inline: bool) -> usize {
+        let node = &nodes[index];
+        match &node.value {
+            LiveValue::Expr {..} => {
+                self.out.push('(');
+                self.print_expr(nodes, index + 1, 0);
+                self.out.push(')');
+                nodes.skip_node(index)
+            }
+            LiveValue::Array => {
+                self.out.push('[');
+                let next_index = self.print_items(nodes, index + 1, indent);
+                self.out.push(']');
+                next_index
+            }
+            LiveValue::TupleEnum {base, variant} => {
+                let _ = write!(self.out, "{}::{}(", base, variant);
+                let next_index = self.print_items(nodes, index + 1, indent);
+                self.out.push(')');
+                next_index
+            }
+            value if is_object_like(value) => {
+                if !inline {
+                    return self.print_node(nodes, index, indent, None)
+                }
+                self.print_container_start(node);
+                self.out.push('{');
+                let mut child_index = index + 1;
+                while !nodes[child_index].is_close() {
+                    if child_index > index + 1 {
+                        self.out.push_str(", ");
+                    }
+                    self.print_prop(&nodes[child_index]);
+                    child_index = self.print_value(nodes, child_index, indent, true);
+                }
+                self.out.push('}');
+                child_index + 1
+            }
+            LiveValue::DSL {token_start, token_count, ..} => {
+                if let Some(token_id) = node.origin.token_id() {
+                    let live_file = &self.live_registry.live_files[token_id.file_id().to_index()];
+                    let tokens = &live_file.original.tokens;
+                    let start = self.file_offset(token_id.file_id(), tokens[*token_start as usize].span.start);
+                    let end = self.file_offset(token_id.file_id(), tokens[(token_start + token_count - 1) as usize].span.end);
+                    let text = &live_file.source[start..end];
+                    if self.preserve_source {
+                        self.out.push_str(text);
+                    }
+                    else {
+                        // the lines after the first are moved along with the first
+                        let line_start = live_file.source[..start].rfind('\n').map_or(0, | offset | offset + 1);
+                        let line = &live_file.source[line_start..start];
+                        let source_indent = &line[..line.len() - line.trim_start().len()];
+                        for (line_index, line) in text.split('\n').enumerate() {
+                            if line_index > 0 {
+                                self.out.push('\n');
+                                if !line.trim().is_empty() {
+                                    self.out.push_str(indent);
+                                    self.out.push_str(line.strip_prefix(source_indent).unwrap_or_else( | | line.trim_start()));
+                                }
+                            }
+                            else {
+                                self.out.push_str(line);
+                            }
+                        }
+                    }
+                }
+                index + 1
+            }
+            LiveValue::Use(module_id) => {
+                // the parser replaces `crate` with the crate of the file
+                let own_module_id = node.origin.token_id().map( | token_id | {
+                    self.live_registry.live_files[token_id.file_id().to_index()].module_id
+                });
+                if own_module_id.is_some_and( | own_module_id | own_module_id.0 == module_id.0) {
+                    let _ = write!(self.out, "use crate::{}::", module_id.1);
+                }
+                else {
+                    let _ = write!(self.out, "use {}::{}::", module_id.0, module_id.1);
+                }
+                self.print_use_target(node.id);
+                index + 1
+            }
+            LiveValue::UseComponent(component_id) => {
+                let _ = write!(self.out, "use {}::", component_id);
+                self.print_use_target(node.id);
+                index + 1
+            }
+            LiveValue::Color(_) if self.print_original_color(node) => index + 1,
+            _ => {
+                self.print_simple_value(node, None);
+                index + 1
+            }
+        }
+    }
+
+    // Prints a color the way it is spelled in the source when reformatting, so that `#f00`
+    // does not turn into `#ff0000ff`. Returns false if the color is not in the source.
+    fn print_original_color(&mut self, node: &LiveNode) -> bool {
+        let original_index = match self.reformatted_original_index(node) {
+            Some(original_index) => original_index,
+            None => return false
+        };
+        if self.original().nodes[original_index].value != node.value {
+            return false
+        }
+        let mut token_index = self.last_token(original_index);
+        if let LiveToken::Punct(id!(,)) | LiveToken::Punct(id!(;)) = self.original().tokens[token_index].token {
+            token_index -= 1;
+        }
+        if let LiveToken::Color(_) = self.original().tokens[token_index].token {
+            let text = &self.source()[self.token_start(token_index)..self.token_end(token_index)];
+            self.out.push_str(text);
+            return true
+        }
+        false
+    }
+
+    fn print_use_target(&mut self, id: LiveId) {
+        if id == LiveId(0) {
+            self.out.push('*');
+        }
+        else {
+            let _ = write!(self.out, "{}", id);
+        }
+    }
+
+    // Prints the items of an array or tuple up to their close node, and returns the index after
+    // the close node.
+    fn print_items(&mut self, nodes: &[LiveNode], mut index: usize, indent: &str) -> usize {
+        let first_index = index;
+        while !nodes[index].is_close() {
+            if index > first_index {
+                self.out.push_str(", ");
+            }
+            index = self.print_value(nodes, index, indent, true);
+        }
+        index + 1
+    }
+
+    // Prints a value that is not a tree. Strings are looked up in `original` if given, and in
+    // the file of their origin otherwise.
+    fn print_simple_value(&mut self, node: &LiveNode, original: Option<&LiveOriginal>) {
+        match &node.value {
+            LiveValue::DocumentString {string_start, string_count} if original.is_some() => {
+                let mut string = String::new();
+                original.unwrap().get_string(*string_start, *string_count, &mut string);
+                let _ = write!(self.out, "\"{}\"", string);
+            }
+            LiveValue::Str(_) | LiveValue::FittedString(_) | LiveValue::InlineString(_) | LiveValue::DocumentString {..} => {
+                let string = self.live_registry.live_node_as_string(node).unwrap_or_default();
+                let _ = write!(self.out, "\"{}\"", string);
+            }
+            LiveValue::Dependency {string_start, string_count} => {
+                let mut string = String::new();
+                if let Some(token_id) = node.origin.token_id() {
+                    let original = original.unwrap_or(&self.live_registry.live_files[token_id.file_id().to_index()].original);
+                    original.get_string(*string_start, *string_count, &mut string);
+                }
+                let _ = write!(self.out, "d\"{}\"", string);
+            }
+            LiveValue::Bool(v) => {
+                let _ = write!(self.out, "{}", v);
+            }
+            LiveValue::Int(v) => {
+                let _ = write!(self.out, "{}", v);
+            }
+            LiveValue::Float(v) => {
+                let _ = write!(self.out, "{:?}", v);
+            }
+            LiveValue::Color(v) => {
+                let _ = write!(self.out, "#{:08x}", v);
+            }
+            LiveValue::Vec2(v) => {
+                let _ = write!(self.out, "vec2({:?}, {:?})", v.x, v.y);
+            }
+            LiveValue::Vec3(v) => {
+                let _ = write!(self.out, "vec3({:?}, {:?}, {:?})", v.x, v.y, v.z);
+            }
+            LiveValue::Vec4(v) => {
+                let _ = write!(self.out, "vec4({:?}, {:?}, {:?}, {:?})", v.x, v.y, v.z, v.w);
+            }
+            LiveValue::Id(id) => {
+                let _ = write!(self.out, "{}", id);
+            }
+            LiveValue::BareEnum {base, variant} => {
+                let _ = write!(self.out, "{}::{}", base, variant);
+            }
+            _ => ()
+        }
+    }
+
+    // Prints the expression at `index` and returns the index of the node after it. The
+    // expression is put in parentheses if it binds less tightly than `min_precedence`.
+    fn print_expr(&mut self, nodes: &[LiveNode], index: usize, min_precedence: usize) -> usize {
+        match &nodes[index].value {
+            LiveValue::ExprBinOp(op) => {
+                let precedence = bin_op_precedence(*op);
+                if precedence < min_precedence {
+                    self.out.push('(');
+                }
+                let index = self.print_expr(nodes, index + 1, precedence);
+                let _ = write!(self.out, " {} ", bin_op_str(*op));
+                let index = self.print_expr(nodes, index, precedence + 1);
+                if precedence < min_precedence {
+                    self.out.push(')');
+                }
+                index
+            }
+            LiveValue::ExprUnOp(op) => {
+                self.out.push(match op {
+                    LiveUnOp::Not => '!',
+                    LiveUnOp::Neg => '-',
+                });
+                self.print_expr(nodes, index + 1, UNARY_PRECEDENCE)
+            }
+            LiveValue::ExprMember(ident) => {
+                let index = self.print_expr(nodes, index + 1, UNARY_PRECEDENCE + 1);
+                let _ = write!(self.out, ".{}", ident);
+                index
+            }
+            LiveValue::ExprCall {ident, args} if *ident == id!(cond) && *args == 3 => {
+                if min_precedence > 0 {
+                    self.out.push('(');
+                }
+                let index = self.print_expr(nodes, index + 1, 1);
+                self.out.push_str(" ? ");
+                let index = self.print_expr(nodes, index, 0);
+                self.out.push_str(" : ");
+                let index = self.print_expr(nodes, index, 0);
+                if min_precedence > 0 {
+                    self.out.push(')');
+                }
+                index
+            }
+            LiveValue::ExprCall {ident, args} => {
+                let _ = write!(self.out, "{}(", ident);
+                let mut index = index + 1;
+                for arg in 0..*args {
+                    if arg > 0 {
+                        self.out.push_str(", ");
+                    }
+                    index = self.print_expr(nodes, index, 0);
+                }
+                self.out.push(')');
+                index
+            }
+            _ => {
+                self.print_simple_value(&nodes[index], None);
+                index + 1
+            }
+        }
+    }
+}
+
+const UNARY_PRECEDENCE: usize = 7;
+
+fn bin_op_precedence(op: LiveBinOp) -> usize {
+    match op {
+        LiveBinOp::Or => 1,
+        LiveBinOp::And => 2,
+        LiveBinOp::Eq | LiveBinOp::Ne => 3,
+        LiveBinOp::Lt | LiveBinOp::Le | LiveBinOp::Gt | LiveBinOp::Ge => 4,
+        LiveBinOp::Add | LiveBinOp::Sub => 5,
+        LiveBinOp::Mul | LiveBinOp::Div | LiveBinOp::Rem => 6,
+    }
+}
+
+fn bin_op_str(op: LiveBinOp) -> &'static str {
+    match op {
+        LiveBinOp::Or => "||",
+        LiveBinOp::And => "&&",
+        LiveBinOp::Eq => "==",
+        LiveBinOp::Ne => "!=",
+        LiveBinOp::Lt => "<",
+        LiveBinOp::Le => "<=",
+        LiveBinOp::Gt => ">",
+        LiveBinOp::Ge => ">=",
+        LiveBinOp::Add => "+",
+        LiveBinOp::Sub => "-",
+        LiveBinOp::Mul => "*",
+        LiveBinOp::Div => "/",
+        LiveBinOp::Rem => "%",
+    }
+}
+
+// Returns whether `value` is printed as a block of properties between braces.
+fn is_object_like(value: &LiveValue) -> bool {
+    matches!(value, LiveValue::Object | LiveValue::Clone(_) | LiveValue::Class {..} | LiveValue::NamedEnum {..})
+}
+
+// Returns whether `node` is an instance without a name, such as `Button {}`, which the parser
+// names `Button[0]`, `Button[1]` and so on.
+fn is_anonymous_instance(node: &LiveNode) -> bool {
+    if let LiveValue::Clone(base) = node.value {
+        if node.origin.has_prop_type(LivePropType::Instance) && node.id != base {
+            return format!("{}", node.id).starts_with(&format!("{}[", base))
+        }
+    }
+    false
+}
@@ -7,13 +7,17 @@ use {
         live_error::{LiveError, LiveErrorSpan, LiveFileError},
         live_parser::LiveParser,
         live_document::{LiveOriginal, LiveExpanded},
-        live_node::{LiveNodeOrigin, LiveNode, LiveValue, LiveType, LiveTypeInfo, LiveIdAsProp},
+        live_node::{LiveNodeOrigin, LiveNode, LiveValue, LiveType, LiveTypeInfo, LiveIdAsProp, LiveProp, LivePropType},
         live_node_vec::{LiveNodeSlice, LiveNodeVec, LiveNodeMutReader},
         live_ptr::{LiveFileId, LivePtr, LiveModuleId, LiveFileGeneration},
         live_token::{LiveToken, LiveTokenId, TokenWithSpan},
         span::{TextSpan, TextPos},
         live_expander::{LiveExpander},
-        live_component::{LiveComponentRegistries}
+        live_component::{LiveComponentRegistries},
+        live_eval::LiveEval,
+        live_eval_fn::{LiveEvalFn, LiveEvalFns},
+        live_catalog::{format_message, locale_language, plural_category},
+        live_printer::LivePrinter
     }
 };
 
@@ -41,9 +45,13 @@ pub struct LiveRegistry {
     pub module_id_to_file_id: HashMap<LiveModuleId, LiveFileId>,
     pub live_files: Vec<LiveFile>,
     pub live_type_infos: HashMap<LiveType, LiveTypeInfo>,
+    pub enum_variants: HashMap<LiveType, Vec<LiveId>>,
     pub ignore_no_dsl: HashSet<LiveId>,
     pub main_module: Option<LiveFileId>,
-    pub components: LiveComponentRegistries
+    pub active_theme: Option<LiveId>,
+    pub locale: LiveId,
+    pub components: LiveComponentRegistries,
+    pub eval_fns: LiveEvalFns,
 }
 
 impl Default for LiveRegistry {
@@ -54,11 +62,15 @@ impl Default for LiveRegistry {
         Self {
             ignore_no_dsl,
             main_module: None,
+            active_theme: None,
+            locale: id!(en),
             file_ids: HashMap::new(),
             module_id_to_file_id: HashMap::new(),
             live_files: Vec::new(),
             live_type_infos: HashMap::new(),
-            components: LiveComponentRegistries::default()
+            enum_variants: HashMap::new(),
+            components: LiveComponentRegistries::default(),
+            eval_fns: LiveEvalFns::default(),
             //mutated_apply: None,
             //mutated_tokens: None
         }
@@ -90,6 +102,19 @@ impl LiveRegistry {
         }
     }
     
+    /// Registers the bare variants of an enum type, which tools such as inspectors offer as the
+    /// values a property of that type can take.
+    pub fn register_enum_variants(&mut self, live_type: LiveType, variants: Vec<LiveId>) {
+        self.enum_variants.insert(live_type, variants);
+    }
+    
+    /// Registers a function that can be called from live expressions, replacing any function
+    /// with the same name. The function should be pure, since expressions can be evaluated
+    /// any number of times.
+    pub fn register_eval_fn(&mut self, ident: LiveId, f: LiveEvalFn) {
+        self.eval_fns.register(ident, f);
+    }
+    
     pub fn generation_valid(&self, live_ptr: LivePtr) -> bool {
         let doc = &self.live_files[live_ptr.file_id.to_index()];
         doc.generation == live_ptr.generation
@@ -168,6 +193,12 @@ impl LiveRegistry {
         self.module_id_to_file_id.get(&module_id).cloned()
     }
     
+    /// Prints `nodes`, which are the nodes of a document of the file `file_id` or an edited copy
+    /// of them, back to live source. See `LivePrinter`.
+    pub fn file_id_nodes_to_source(&self, file_id: LiveFileId, nodes: &[LiveNode]) -> String {
+        LivePrinter::new(self, file_id).print(nodes)
+    }
+    
     pub fn live_node_as_string(&self, node: &LiveNode) -> Option<String> {
         match &node.value {
             LiveValue::Str(v) => {
@@ -619,6 +650,152 @@ impl LiveRegistry {
         Ok(None)
     }
     
+    /// Updates the live file `file_name` from the whole of its new source, the way
+    /// `live_edit_file` does for an edited range. This is used to reload files that are loaded
+    /// at runtime, such as standalone `.live` files, when they change on disk. For a file that was
+    /// registered from a `live_register!` block, `source` is the new body of the block, whose
+    /// first line starts at the column of the block in the Rust file.
+    pub fn update_live_file(&mut self, file_name: &str, source: String) -> Result<Option<LiveEditEvent>, LiveError> {
+        let file_id = if let Some(file_id) = self.file_ids.get(file_name) {
+            *file_id
+        }
+        else {
+            return Err(LiveError {
+                origin: live_error_origin!(),
+                span: TextSpan::default().into(),
+                message: format!("Live file {} is not registered", file_name)
+            })
+        };
+        let start_pos = self.live_files[file_id.to_index()].start_pos;
+        let start_line = start_pos.line as usize;
+        
+        // the first line is tokenized from the start of its line in the Rust file, so the columns
+        // of its tokens match the ones it was registered with
+        let padded_source = format!("{}{}", " ".repeat(start_pos.column as usize), source);
+        let mut lines = Vec::new();
+        let mut state = State::default();
+        let mut scratch = String::new();
+        for line_str in padded_source.lines() {
+            let line_chars: Vec<char> = line_str.chars().collect();
+            let mut full_tokens = Vec::new();
+            let mut cursor = Cursor::new(&line_chars, &mut scratch);
+            loop {
+                let (next_state, full_token) = state.next(&mut cursor);
+                if let Some(full_token) = full_token {
+                    full_tokens.push(full_token);
+                }
+                else {
+                    break;
+                }
+                state = next_state;
+            }
+            lines.push((line_chars, full_tokens));
+        }
+        
+        let range = TokenRange {
+            start: TokenPos {line: start_line, index: 0},
+            end: TokenPos {line: start_line + lines.len(), index: 0}
+        };
+        let event = self.live_edit_file(file_name, range, | line | {
+            let (line_chars, full_tokens) = &lines[line - start_line];
+            (line_chars, full_tokens)
+        }) ?;
+        self.live_files[file_id.to_index()].source = source;
+        Ok(event)
+    }
+    
+    /// Updates the live file `file_name`, which was registered from the `live_register!` block of
+    /// a Rust file, from the new source of that whole Rust file, such as when the file is edited
+    /// in an editor. The lines before the block can have changed, which moves the block.
+    pub fn update_live_file_from_rust(&mut self, file_name: &str, rust_source: &str) -> Result<Option<LiveEditEvent>, LiveError> {
+        let file_id = if let Some(file_id) = self.file_ids.get(file_name) {
+            *file_id
+        }
+        else {
+            return Err(LiveError {
+                origin: live_error_origin!(),
+                span: TextSpan::default().into(),
+                message: format!("Live file {} is not registered", file_name)
+            })
+        };
+        let (start_pos, body) = if let Some(block) = Self::find_live_register_body(rust_source) {
+            block
+        }
+        else {
+            return Err(LiveError {
+                origin: live_error_origin!(),
+                span: TextSpan::default().into(),
+                message: format!("{} has no live_register block", file_name)
+            })
+        };
+        let old_start_pos = std::mem::replace(&mut self.live_files[file_id.to_index()].start_pos, start_pos);
+        let result = self.update_live_file(file_name, body);
+        if result.is_err() {
+            self.live_files[file_id.to_index()].start_pos = old_start_pos;
+        }
+        result
+    }
+    
+    // Returns the position where the body of the first `live_register!` block in `rust_source`
+    // starts, together with the body, or `None` if there is no such block.
+    fn find_live_register_body(rust_source: &str) -> Option<(TextPos, String)> {
+        let lines: Vec<Vec<char >> = rust_source.lines().map( | line | line.chars().collect()).collect();
+        let mut state = State::default();
+        let mut scratch = String::new();
+        // the tokens of the block header that were seen so far
+        let mut header = 0;
+        let mut start = None;
+        let mut depth = 0;
+        for (line, line_chars) in lines.iter().enumerate() {
+            let mut cursor = Cursor::new(line_chars, &mut scratch);
+            let mut column = 0;
+            loop {
+                let (next_state, full_token) = state.next(&mut cursor);
+                let full_token = if let Some(full_token) = full_token {full_token} else {break};
+                state = next_state;
+                let pos = TextPos {line: line as u32, column: column as u32};
+                column += full_token.len;
+                if let Some(start) = start {
+                    match full_token.token {
+                        FullToken::Open(Delim::Brace) => depth += 1,
+                        FullToken::Close(Delim::Brace) if depth == 0 => {
+                            return Some((start, Self::text_between(&lines, start, pos)))
+                        }
+                        FullToken::Close(Delim::Brace) => depth -= 1,
+                        _ => ()
+                    }
+                    continue
+                }
+                header = match (header, full_token.token) {
+                    (_, FullToken::Whitespace) | (_, FullToken::Comment) => header,
+                    (_, FullToken::Ident(id!(live_register))) => 1,
+                    (1, FullToken::Punct(id!(!))) => 2,
+                    (2, FullToken::Open(Delim::Brace)) => {
+                        start = Some(TextPos {line: line as u32, column: column as u32});
+                        0
+                    }
+                    _ => 0
+                };
+            }
+        }
+        None
+    }
+    
+    // Returns the text of `lines` from `start` up to `end`.
+    fn text_between(lines: &[Vec<char >], start: TextPos, end: TextPos) -> String {
+        let mut text = String::new();
+        for line in start.line..=end.line {
+            let line_chars = &lines[line as usize];
+            let from = if line == start.line {start.column as usize} else {0};
+            let to = if line == end.line {end.column as usize} else {line_chars.len()};
+            text.extend(&line_chars[from..to]);
+            if line != end.line {
+                text.push('\n');
+            }
+        }
+        text
+    }
+    
     pub fn process_next_originals_and_expand(&mut self) -> Result<(), Vec<LiveError >> {
         for live_file in &mut self.live_files {
             if live_file.next_original.is_some() {
@@ -637,6 +814,89 @@ impl LiveRegistry {
         return Ok(())
     }
     
+    /// Switches the active theme. A theme is a root object in a live file whose properties
+    /// replace the root values with the same name in that file, so themes can inherit from
+    /// each other like any other object. `None` restores the values written in the files.
+    /// Every document is expanded again, and files that define the previous or the new theme
+    /// get a new generation since their node layout can change.
+    pub fn set_theme(&mut self, theme: Option<LiveId>) -> Result<(), Vec<LiveError >> {
+        if self.active_theme == theme {
+            return Ok(())
+        }
+        let old_theme = self.active_theme;
+        self.active_theme = theme;
+        for live_file in &mut self.live_files {
+            let nodes = &live_file.original.nodes;
+            let has_theme = | theme: Option<LiveId> | {
+                theme.is_some_and( | theme | !nodes.is_empty() && nodes.child_by_name(0, theme.as_field()).is_some())
+            };
+            if has_theme(old_theme) || has_theme(theme) {
+                live_file.generation.next_gen();
+            }
+            live_file.reexpand = true;
+        }
+        
+        let mut errors = Vec::new();
+        self.expand_all_documents(&mut errors);
+        if errors.is_empty() {Ok(())} else {Err(errors)}
+    }
+    
+    /// Sets the locale that `tr` translates to, such as `nl` or `nl_BE`.
+    pub fn set_locale(&mut self, locale: LiveId) {
+        self.locale = locale;
+    }
+    
+    /// Returns the message `message` of a catalog for `locale`, see `live_catalog`.
+    pub fn find_message(&self, locale: LiveId, message: LiveId) -> Option<(&[LiveNode], usize)> {
+        for live_file in &self.live_files {
+            let nodes = &live_file.expanded.nodes;
+            if nodes.is_empty() {
+                continue
+            }
+            if let Some(catalog) = nodes.child_by_name(0, id!(Catalog).as_field()) {
+                let catalog_locale = nodes.child_by_name(catalog, id!(locale).as_field()).map( | index | &nodes[index].value);
+                if catalog_locale != Some(&LiveValue::Id(locale)) {
+                    continue
+                }
+                if let Some(index) = nodes.child_by_name(catalog, message.as_field()) {
+                    return Some((nodes, index))
+                }
+            }
+        }
+        None
+    }
+    
+    /// Translates the message `message` to the active locale, with its placeholders replaced by
+    /// the arguments. A message that is not in a catalog for the locale is looked up in the
+    /// catalogs for its language, and otherwise its name is returned. For a message with plural
+    /// forms, the form is picked with the first argument that is a number.
+    pub fn tr(&self, message: LiveId, args: &[LiveEval]) -> Result<String, String> {
+        let found = self.find_message(self.locale, message).or_else( || {
+            self.find_message(locale_language(self.locale) ?, message)
+        });
+        let (nodes, index) = match found {
+            Some(found) => found,
+            None => return Ok(message.to_string())
+        };
+        let format_index = if nodes[index].value.is_object() {
+            let n = args.iter().find_map( | arg | match arg {
+                LiveEval::Int(v) => Some(*v as f64),
+                LiveEval::Float(v) => Some(*v),
+                _ => None
+            }).ok_or_else( || format!("message {} has plural forms, but there is no number argument", message)) ?;
+            let category = plural_category(self.locale, n);
+            nodes.child_by_name(index, category.as_field())
+                .or_else( || nodes.child_by_name(index, id!(other).as_field()))
+                .ok_or_else( || format!("message {} has no plural form {} or other", message, category)) ?
+        }
+        else {
+            index
+        };
+        let format = self.live_node_as_string(&nodes[format_index])
+            .ok_or_else( || format!("message {} is not a string", message)) ?;
+        Ok(format_message(&format, args))
+    }
+    
     fn update_documents_from_mutated_tokens(
         &mut self,
         mutated_tokens: &[LiveTokenId]
@@ -661,10 +921,13 @@ impl LiveRegistry {
             
             if is_prop_assign || live_tokens[token_index].is_value_type() {
                 let token_id = LiveTokenId::new(file_id, token_index - 2);
+                // array items have no name, their nodes come from the value token itself
+                let item_token_id = LiveTokenId::new(file_id, token_index);
                 
                 // ok lets scan for this one.
                 let mut file_dep_iter = FileDepIter::new(file_id);
                 let mut path = Vec::new();
+                let mut path_indices = Vec::new();
                 while let Some(file_id) = file_dep_iter.pop_todo() {
                     let is_main = self.main_module == Some(file_id);
                     
@@ -673,34 +936,33 @@ impl LiveRegistry {
                     
                     let mut reader = LiveNodeMutReader::new(0, &mut expanded_nodes);
                     path.clear();
+                    path_indices.clear();
                     reader.walk();
                     while !reader.is_eot() {
                         if reader.is_open() {
-                            path.push(reader.prop())
+                            path.push(reader.prop());
+                            path_indices.push(reader.index());
                         }
                         else if reader.is_close() {
                             path.pop();
+                            path_indices.pop();
                         }
                         // ok this is a direct patch
-                        else if is_prop_assign && reader.origin.token_id() == Some(token_id) {
+                        else if is_prop_assign && reader.origin.token_id() == Some(token_id)
+                            || !is_prop_assign && reader.origin.token_id() == Some(item_token_id)
+                            && reader.origin.has_prop_type(LivePropType::Nameless) {
                             let live_ptr = LivePtr {file_id, index: reader.index() as u32, generation: self.live_files[file_id.to_index()].generation};
                             if !reader.update_from_live_token(&live_tokens[token_index].token) {
                                 println!("update_from_live_token returns false investigate! {:?}", reader.node());
                             }
                             live_ptrs.push(live_ptr);
                             if is_main {
-                                // ok so. lets write by path here
-                                path.push(reader.prop());
-                                diff.replace_or_insert_last_node_by_path(0, &path, reader.node_slice());
-                                path.pop();
+                                Self::write_patch_by_path(&mut diff, &mut path, &path_indices, &mut reader);
                             }
                         }
                         else if reader.is_token_id_inside_dsl(token_id) {
                             if is_main {
-                                // ok so. lets write by path here
-                                path.push(reader.prop());
-                                diff.replace_or_insert_last_node_by_path(0, &path, reader.node_slice());
-                                path.pop();
+                                Self::write_patch_by_path(&mut diff, &mut path, &path_indices, &mut reader);
                             }
                         }
                         reader.walk();
@@ -715,6 +977,21 @@ impl LiveRegistry {
         (diff, live_ptrs)
     }
     
+    // Writes the node the reader is on into the patch, at the path of its parents. Items of an
+    // array are matched by position when they are applied, so a node inside an array is patched
+    // by writing the whole outermost array instead.
+    fn write_patch_by_path(diff: &mut Vec<LiveNode>, path: &mut Vec<LiveProp>, path_indices: &[usize], reader: &mut LiveNodeMutReader) {
+        if let Some(depth) = path_indices.iter().position( | index | reader.nodes()[*index].is_array()) {
+            let array_index = path_indices[depth];
+            diff.replace_or_insert_last_node_by_path(0, &path[0..depth + 1], reader.nodes().node_slice(array_index));
+        }
+        else {
+            path.push(reader.prop());
+            diff.replace_or_insert_last_node_by_path(0, path, reader.node_slice());
+            path.pop();
+        }
+    }
+    
     pub fn register_live_file(
         &mut self,
         file_name: &str,
@@ -0,0 +1,139 @@
+use {
+    std::collections::HashSet,
+    makepad_live_compiler::{
+        makepad_live_id::*,
+        check_catalogs,
+        live_eval,
+        plural_category,
+        LiveEval,
+        LiveIdAsProp,
+        LiveModuleId,
+        LiveNodeSlice,
+        LiveRegistry,
+        TextPos,
+    }
+};
+
+fn register(live_registry: &mut LiveRegistry, file_name: &str, module_path: &str, source: &str) {
+    live_registry.register_live_file(
+        file_name,
+        LiveModuleId::from_str(module_path).unwrap(),
+        source.to_string(),
+        vec![],
+        TextPos::default()
+    ).unwrap_or_else( | error | panic!("{}", error));
+}
+
+fn registry_with_catalogs() -> LiveRegistry {
+    let mut live_registry = LiveRegistry::default();
+    register(&mut live_registry, "app.live", "app::main", r#"
+        App: {
+            title: (tr("greeting", "Ana"))
+            status: (tr("files", 3))
+        }
+    "#);
+    register(&mut live_registry, "en.live", "app::en", r#"
+        Catalog: {
+            locale: en
+            greeting: "Hello {0}"
+            files: {one: "{0} file", other: "{0} files"}
+        }
+    "#);
+    register(&mut live_registry, "nl.live", "app::nl", r#"
+        Catalog: {
+            locale: nl
+            greeting: "Hallo {0}"
+            files: {one: "{0} bestand", other: "{0} bestanden"}
+        }
+    "#);
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    live_registry
+}
+
+// Evaluates the expression that is the value of the property `prop` of `App`.
+fn eval(live_registry: &LiveRegistry, prop: LiveId) -> LiveEval {
+    let doc = live_registry.module_id_and_name_to_doc(LiveModuleId::from_str("app::main").unwrap(), id!(App)).unwrap();
+    let index = doc.nodes.child_by_name(doc.index, prop.as_field()).unwrap();
+    live_eval(live_registry, index, &mut (index + 1), doc.nodes).unwrap_or_else( | error | panic!("{}", error.message))
+}
+
+fn string(value: &str) -> LiveEval {
+    LiveEval::String(value.to_string())
+}
+
+#[test]
+fn expressions_translate_messages() {
+    let mut live_registry = registry_with_catalogs();
+    assert_eq!(eval(&live_registry, id!(title)), string("Hello Ana"));
+    assert_eq!(eval(&live_registry, id!(status)), string("3 files"));
+    live_registry.set_locale(id!(nl));
+    assert_eq!(eval(&live_registry, id!(title)), string("Hallo Ana"));
+    assert_eq!(eval(&live_registry, id!(status)), string("3 bestanden"));
+}
+
+#[test]
+fn messages_pick_plural_forms() {
+    let live_registry = registry_with_catalogs();
+    assert_eq!(live_registry.tr(id!(files), &[LiveEval::Int(1)]), Ok("1 file".to_string()));
+    assert_eq!(live_registry.tr(id!(files), &[LiveEval::Float(1.5)]), Ok("1.5 files".to_string()));
+    assert!(live_registry.tr(id!(files), &[]).is_err());
+}
+
+#[test]
+fn missing_messages_fall_back() {
+    let mut live_registry = registry_with_catalogs();
+    // a locale with a region uses the catalog of its language
+    live_registry.set_locale(LiveId::from_str("nl_BE").unwrap());
+    assert_eq!(live_registry.tr(id!(greeting), &[string("Ana")]), Ok("Hallo Ana".to_string()));
+    live_registry.set_locale(id!(fr));
+    assert_eq!(live_registry.tr(id!(greeting), &[string("Ana")]), Ok("greeting".to_string()));
+}
+
+#[test]
+fn plural_rules_follow_the_language() {
+    assert_eq!(plural_category(id!(en), 0.0), id!(other));
+    assert_eq!(plural_category(id!(fr), 0.0), id!(one));
+    assert_eq!(plural_category(id!(ru), 21.0), id!(one));
+    assert_eq!(plural_category(id!(ru), 22.0), id!(few));
+    assert_eq!(plural_category(id!(ru), 12.0), id!(many));
+    assert_eq!(plural_category(id!(pl), 21.0), id!(many));
+    assert_eq!(plural_category(id!(ja), 1.0), id!(other));
+}
+
+#[test]
+fn checker_reports_missing_and_unused_messages() {
+    let mut live_registry = LiveRegistry::default();
+    register(&mut live_registry, "app.live", "app::main", r#"
+        App: {title: (tr("greeting", "Ana"))}
+    "#);
+    register(&mut live_registry, "en.live", "app::en", r#"
+        Catalog: {
+            locale: en
+            greeting: "Hello {0}"
+            quit: "Quit"
+            unused: "Unused"
+        }
+    "#);
+    register(&mut live_registry, "nl.live", "app::nl", r#"
+        Catalog: {
+            locale: nl
+            greeting: "Hallo {0}"
+        }
+    "#);
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    let used: HashSet<LiveId> = ["quit", "open"].iter().map( | name | LiveId::from_str(name).unwrap()).collect();
+    let mut messages: Vec<String> = check_catalogs(&live_registry, &used).into_iter().map( | error | error.message).collect();
+    messages.sort();
+    assert_eq!(messages, vec![
+        "Catalog en is missing message open",
+        "Catalog nl is missing message open",
+        "Catalog nl is missing message quit",
+        "Catalog nl is missing message unused",
+        "Message unused in catalog en is not used",
+    ]);
+}
@@ -0,0 +1,164 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    makepad_math::*,
+    live_eval,
+    LiveEval,
+    LiveIdAsProp,
+    LiveModuleId,
+    LiveNodeSlice,
+    LiveRegistry,
+    TextPos,
+};
+
+// Registers `source` as a live file and expands it.
+fn registry_with_file(source: &str, register: impl FnOnce(&mut LiveRegistry)) -> LiveRegistry {
+    let mut live_registry = LiveRegistry::default();
+    register(&mut live_registry);
+    live_registry.register_live_file(
+        "test.rs",
+        LiveModuleId::from_str("test::test").unwrap(),
+        source.to_string(),
+        vec![],
+        TextPos::default()
+    ).unwrap_or_else( | error | panic!("{}", error));
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    live_registry
+}
+
+// Evaluates the expression that is the value of the property `prop` of `A`.
+fn eval(live_registry: &LiveRegistry, prop: &str) -> Result<LiveEval, String> {
+    let doc = live_registry.module_id_and_name_to_doc(LiveModuleId::from_str("test::test").unwrap(), id!(A)).unwrap();
+    let index = doc.nodes.child_by_name(doc.index, LiveId::from_str(prop).unwrap().as_field()).unwrap();
+    live_eval(live_registry, index, &mut (index + 1), doc.nodes).map_err( | error | error.message)
+}
+
+fn assert_vec4_eq(value: Result<LiveEval, String>, expected: Vec4) {
+    match value {
+        Ok(LiveEval::Vec4(v)) => assert!(
+            (v.x - expected.x).abs() < 1e-4 &&
+            (v.y - expected.y).abs() < 1e-4 &&
+            (v.z - expected.z).abs() < 1e-4 &&
+            (v.w - expected.w).abs() < 1e-4,
+            "{:?} != {:?}", v, expected
+        ),
+        value => panic!("expected a color, got {:?}", value)
+    }
+}
+
+#[test]
+fn built_in_functions() {
+    let live_registry = registry_with_file(r#"
+        A: {
+            a: 2.0
+            mix: (mix(a, 4.0, 0.5))
+            min: (min(3, 1))
+            max: (max(1.5, 2))
+            clamp: (clamp(7.0, 0.0, 5.0))
+            abs: (abs(-3))
+            floor: (floor(2.7))
+            round: (round(2.5))
+            pow: (pow(2, 10))
+            sin: (sin(0.0))
+            cos: (cos(0.0))
+            vec2: (vec2(1.0))
+            vec3: (vec3(1.0, 2.0, 3.0) * 2.0)
+            mix_vec: (mix(vec2(0.0, 0.0), vec2(2.0, 4.0), 0.5))
+            wrong: (clamp(1.0, 2.0))
+            unknown: (frobnicate(1.0))
+        }
+    "#, | _ | {});
+    assert_eq!(eval(&live_registry, "mix"), Ok(LiveEval::Float(3.0)));
+    assert_eq!(eval(&live_registry, "min"), Ok(LiveEval::Int(1)));
+    assert_eq!(eval(&live_registry, "max"), Ok(LiveEval::Float(2.0)));
+    assert_eq!(eval(&live_registry, "clamp"), Ok(LiveEval::Float(5.0)));
+    assert_eq!(eval(&live_registry, "abs"), Ok(LiveEval::Int(3)));
+    assert_eq!(eval(&live_registry, "floor"), Ok(LiveEval::Float(2.0)));
+    assert_eq!(eval(&live_registry, "round"), Ok(LiveEval::Float(3.0)));
+    assert_eq!(eval(&live_registry, "pow"), Ok(LiveEval::Float(1024.0)));
+    assert_eq!(eval(&live_registry, "sin"), Ok(LiveEval::Float(0.0)));
+    assert_eq!(eval(&live_registry, "cos"), Ok(LiveEval::Float(1.0)));
+    assert_eq!(eval(&live_registry, "vec2"), Ok(LiveEval::Vec2(vec2(1.0, 1.0))));
+    assert_eq!(eval(&live_registry, "vec3"), Ok(LiveEval::Vec3(vec3(2.0, 4.0, 6.0))));
+    assert_eq!(eval(&live_registry, "mix_vec"), Ok(LiveEval::Vec2(vec2(1.0, 2.0))));
+    assert!(eval(&live_registry, "wrong").unwrap_err().contains("clamp"));
+    assert!(eval(&live_registry, "unknown").unwrap_err().contains("not implemented"));
+}
+
+#[test]
+fn color_functions() {
+    let live_registry = registry_with_file(r#"
+        A: {
+            base: #f00
+            hsl: (hsl(120, 1.0, 0.5))
+            hsla: (hsla(240, 1.0, 0.5, 0.5))
+            lighten: (lighten(base, 0.25))
+            darken: (darken(base, 0.25))
+            saturate: (saturate(#bf4040, 1.0))
+            alpha: (alpha(base, 0.5))
+            blend: (blend(#000, #fff8))
+        }
+    "#, | _ | {});
+    assert_vec4_eq(eval(&live_registry, "hsl"), vec4(0.0, 1.0, 0.0, 1.0));
+    assert_vec4_eq(eval(&live_registry, "hsla"), vec4(0.0, 0.0, 1.0, 0.5));
+    assert_vec4_eq(eval(&live_registry, "lighten"), vec4(1.0, 0.5, 0.5, 1.0));
+    assert_vec4_eq(eval(&live_registry, "darken"), vec4(0.5, 0.0, 0.0, 1.0));
+    assert_vec4_eq(eval(&live_registry, "saturate"), vec4(1.0, 0.0, 0.0, 1.0));
+    assert_vec4_eq(eval(&live_registry, "alpha"), vec4(1.0, 0.0, 0.0, 0.5));
+    assert_vec4_eq(eval(&live_registry, "blend"), vec4(8.0 / 15.0, 8.0 / 15.0, 8.0 / 15.0, 1.0));
+}
+
+#[test]
+fn operators() {
+    let live_registry = registry_with_file(r#"
+        A: {
+            a: 2.0
+            rem_int: (7 % 3)
+            rem_negative: (-1 % 3)
+            rem_float: (5.5 % 2.0)
+            rem_zero: (1 % 0)
+            cond_true: (a > 1.0 ? "big" : "small")
+            cond_false: (a > 3.0 ? 1 : 2)
+            cond_lazy: (a > 1.0 ? 1 : frobnicate())
+            cond_not_bool: (a ? 1 : 2)
+            concat: ("width: " + a + "px")
+            concat_int: ("n" + 3)
+        }
+    "#, | _ | {});
+    assert_eq!(eval(&live_registry, "rem_int"), Ok(LiveEval::Int(1)));
+    assert_eq!(eval(&live_registry, "rem_negative"), Ok(LiveEval::Int(2)));
+    assert_eq!(eval(&live_registry, "rem_float"), Ok(LiveEval::Float(1.5)));
+    assert!(eval(&live_registry, "rem_zero").is_err());
+    assert_eq!(eval(&live_registry, "cond_true"), Ok(LiveEval::String("big".to_string())));
+    assert_eq!(eval(&live_registry, "cond_false"), Ok(LiveEval::Int(2)));
+    // the branch that is not picked is not evaluated
+    assert_eq!(eval(&live_registry, "cond_lazy"), Ok(LiveEval::Int(1)));
+    assert!(eval(&live_registry, "cond_not_bool").is_err());
+    assert_eq!(eval(&live_registry, "concat"), Ok(LiveEval::String("width: 2px".to_string())));
+    assert_eq!(eval(&live_registry, "concat_int"), Ok(LiveEval::String("n3".to_string())));
+}
+
+#[test]
+fn registered_functions() {
+    fn double(args: &[LiveEval]) -> Result<LiveEval, String> {
+        match args {
+            [LiveEval::Float(v)] => Ok(LiveEval::Float(v * 2.0)),
+            _ => Err("expected a float".to_string())
+        }
+    }
+    let live_registry = registry_with_file(r#"
+        A: {
+            double: (double(1.5) + 1.0)
+            min: (min(1.0, 2.0))
+            wrong: (double(1))
+        }
+    "#, | live_registry | {
+        live_registry.register_eval_fn(id!(double), double);
+        // registered functions replace built-in functions
+        live_registry.register_eval_fn(id!(min), | _ | Ok(LiveEval::Bool(true)));
+    });
+    assert_eq!(eval(&live_registry, "double"), Ok(LiveEval::Float(4.0)));
+    assert_eq!(eval(&live_registry, "min"), Ok(LiveEval::Bool(true)));
+    assert_eq!(eval(&live_registry, "wrong"), Err("Expression call double failed: expected a float".to_string()));
+}
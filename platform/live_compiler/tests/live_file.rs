@@ -0,0 +1,199 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    makepad_math::*,
+    LiveEditEvent,
+    LiveIdAsProp,
+    LiveModuleId,
+    LiveNodeSlice,
+    LiveRegistry,
+    LiveValue,
+    TextPos,
+};
+
+fn register(live_registry: &mut LiveRegistry, file_name: &str, module_path: &str, source: &str) {
+    live_registry.register_live_file(
+        file_name,
+        LiveModuleId::from_str(module_path).unwrap(),
+        source.to_string(),
+        vec![],
+        TextPos::default()
+    ).unwrap_or_else( | error | panic!("{}", error));
+}
+
+fn expand(live_registry: &mut LiveRegistry) {
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+// Returns the value of the property `prop` of the object `name` in the module `module_path`.
+fn value(live_registry: &LiveRegistry, module_path: &str, name: LiveId, prop: LiveId) -> LiveValue {
+    let doc = live_registry.module_id_and_name_to_doc(LiveModuleId::from_str(module_path).unwrap(), name).unwrap();
+    let index = doc.nodes.child_by_name(doc.index, prop.as_field()).unwrap();
+    doc.nodes[index].value.clone()
+}
+
+#[test]
+fn files_use_each_other() {
+    let mut live_registry = LiveRegistry::default();
+    // the file that uses the theme is registered first, like a file loaded before its dependencies
+    register(&mut live_registry, "app.live", "app::main", r#"
+        use crate::theme::Theme
+        App: Theme {
+            width: 2.0
+        }
+    "#);
+    register(&mut live_registry, "theme.live", "app::theme", r#"
+        Theme: {
+            color: #f00
+            width: 1.0
+        }
+    "#);
+    expand(&mut live_registry);
+    assert_eq!(value(&live_registry, "app::main", id!(App), id!(color)), LiveValue::Color(0xff0000ff));
+    assert_eq!(value(&live_registry, "app::main", id!(App), id!(width)), LiveValue::Float(2.0));
+}
+
+#[test]
+fn changed_values_are_mutations() {
+    let mut live_registry = LiveRegistry::default();
+    register(&mut live_registry, "theme.live", "app::theme", "Theme: {\n    color: #f00\n    width: 1.0\n}\n");
+    expand(&mut live_registry);
+    live_registry.main_module = live_registry.file_ids.get("theme.live").copied();
+
+    let event = live_registry.update_live_file("theme.live", "Theme: {\n    color: #0f0\n    width: 1.0\n}\n".to_string());
+    match event {
+        Ok(Some(LiveEditEvent::Mutation {apply, ..})) => {
+            let index = apply.child_by_path(0, &[id!(Theme).as_field(), id!(color).as_field()]).unwrap();
+            assert_eq!(apply[index].value, LiveValue::Color(0x00ff00ff));
+            assert!(apply.child_by_path(0, &[id!(Theme).as_field(), id!(width).as_field()]).is_none());
+        }
+        event => panic!("expected a mutation, got {:?}", event)
+    }
+    assert_eq!(value(&live_registry, "app::theme", id!(Theme), id!(color)), LiveValue::Color(0x00ff00ff));
+    assert!(live_registry.file_id_to_file(live_registry.file_ids["theme.live"]).source.contains("#0f0"));
+
+    // reloading the same source changes nothing
+    let event = live_registry.update_live_file("theme.live", "Theme: {\n    color: #0f0\n    width: 1.0\n}\n".to_string());
+    assert_eq!(event.unwrap(), None);
+}
+
+#[test]
+fn changed_structure_reparses() {
+    let mut live_registry = LiveRegistry::default();
+    register(&mut live_registry, "theme.live", "app::theme", "Theme: {\n    color: #f00\n}\n");
+    expand(&mut live_registry);
+
+    let event = live_registry.update_live_file("theme.live", "Theme: {\n    color: #f00\n    size: vec2(1.0, 2.0)\n}\n".to_string());
+    assert_eq!(event.unwrap(), Some(LiveEditEvent::ReparseDocument));
+    live_registry.process_next_originals_and_expand().unwrap();
+    assert_eq!(value(&live_registry, "app::theme", id!(Theme), id!(size)), LiveValue::Vec2(vec2(1.0, 2.0)));
+
+    // a source that does not parse keeps the last document
+    assert!(live_registry.update_live_file("theme.live", "Theme: {\n    color: \n".to_string()).is_err());
+    live_registry.process_next_originals_and_expand().unwrap();
+    assert_eq!(value(&live_registry, "app::theme", id!(Theme), id!(color)), LiveValue::Color(0xff0000ff));
+}
+
+#[test]
+fn unknown_files_are_errors() {
+    let mut live_registry = LiveRegistry::default();
+    let error = live_registry.update_live_file("missing.live", String::new()).unwrap_err();
+    assert!(error.message.contains("missing.live"));
+}
+
+const RUST_SOURCE: &str = "use makepad_platform::*;\n\nlive_register!{\n    App: {\n        width: 1.0\n    }\n}\n\nfn main() {}\n";
+
+fn registry_with_rust_file() -> LiveRegistry {
+    let mut live_registry = LiveRegistry::default();
+    live_registry.register_live_file(
+        "app.rs",
+        LiveModuleId::from_str("app::main").unwrap(),
+        "\n    App: {\n        width: 1.0\n    }\n".to_string(),
+        vec![],
+        TextPos {line: 2, column: 15}
+    ).unwrap_or_else( | error | panic!("{}", error));
+    expand(&mut live_registry);
+    live_registry
+}
+
+#[test]
+fn rust_blocks_update_from_their_file() {
+    let mut live_registry = registry_with_rust_file();
+    assert_eq!(live_registry.update_live_file_from_rust("app.rs", RUST_SOURCE).unwrap(), None);
+
+    // lines inserted before the block move it
+    let source = RUST_SOURCE.replace("use", "// a comment with live_register!{}\n\nuse").replace("1.0", "2.0");
+    assert!(matches!(live_registry.update_live_file_from_rust("app.rs", &source), Ok(Some(LiveEditEvent::Mutation {..}))));
+    assert_eq!(value(&live_registry, "app::main", id!(App), id!(width)), LiveValue::Float(2.0));
+    let live_file = live_registry.file_id_to_file(live_registry.file_ids["app.rs"]);
+    assert_eq!(live_file.start_pos, TextPos {line: 4, column: 15});
+    assert_eq!(live_file.source, "\n    App: {\n        width: 2.0\n    }\n");
+}
+
+#[test]
+fn rust_block_errors_are_located_in_the_file() {
+    let mut live_registry = registry_with_rust_file();
+    let source = RUST_SOURCE.replace("width: 1.0", "width: 1.0 ]");
+    let error = live_registry.update_live_file_from_rust("app.rs", &source).unwrap_err();
+    let error = error.into_live_file_error("app.rs");
+    assert_eq!(error.span.start, TextPos {line: 4, column: 19});
+
+    let error = live_registry.update_live_file_from_rust("app.rs", "fn main() {}\n").unwrap_err();
+    assert!(error.message.contains("live_register"));
+}
+
+// Returns the mutation that changing `old` into `new` in the main file `theme.live` results in.
+fn mutation(source: &str, old: &str, new: &str) -> Vec<makepad_live_compiler::LiveNode> {
+    let mut live_registry = LiveRegistry::default();
+    register(&mut live_registry, "theme.live", "app::theme", source);
+    expand(&mut live_registry);
+    live_registry.main_module = live_registry.file_ids.get("theme.live").copied();
+    match live_registry.update_live_file("theme.live", source.replace(old, new)) {
+        Ok(Some(LiveEditEvent::Mutation {apply, ..})) => apply,
+        event => panic!("expected a mutation, got {:?}", event)
+    }
+}
+
+#[test]
+fn array_items_patch_the_whole_array() {
+    let apply = mutation("Theme: {\n    sizes: [1.0, 2.0, 3.0]\n    width: 1.0\n}\n", "2.0", "5.0");
+    let index = apply.child_by_path(0, &[id!(Theme).as_field(), id!(sizes).as_field()]).unwrap();
+    assert!(apply[index].value.is_array());
+    let items: Vec<_> = apply[index + 1..index + 4].iter().map( | node | node.value.clone()).collect();
+    assert_eq!(items, vec![LiveValue::Float(1.0), LiveValue::Float(5.0), LiveValue::Float(3.0)]);
+    assert!(apply[index + 4].value.is_close());
+    assert!(apply.child_by_path(0, &[id!(Theme).as_field(), id!(width).as_field()]).is_none());
+}
+
+#[test]
+fn properties_inside_arrays_patch_the_whole_array() {
+    let apply = mutation("Theme: {\n    items: [{a: 1.0}, {a: 2.0}]\n}\n", "2.0", "5.0");
+    let index = apply.child_by_path(0, &[id!(Theme).as_field(), id!(items).as_field()]).unwrap();
+    assert!(apply[index].value.is_array());
+    assert_eq!(apply.count_children(index), 2);
+    assert_eq!(apply[index + 2].value, LiveValue::Float(1.0));
+    assert_eq!(apply[index + 5].value, LiveValue::Float(5.0));
+}
+
+#[test]
+fn rust_block_bodies_reload_at_their_column() {
+    let mut live_registry = LiveRegistry::default();
+    live_registry.register_live_file(
+        "app.rs",
+        LiveModuleId::from_str("app::main").unwrap(),
+        " App: {width: 1.0}\n".to_string(),
+        vec![],
+        TextPos {line: 2, column: 15}
+    ).unwrap_or_else( | error | panic!("{}", error));
+    expand(&mut live_registry);
+
+    // the first line of the body continues the line of the `live_register!` macro
+    let tokens = match live_registry.update_live_file("app.rs", " App: {width: 2.0}\n".to_string()) {
+        Ok(Some(LiveEditEvent::Mutation {tokens, ..})) => tokens,
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    };
+    let span = live_registry.token_id_to_span(tokens[0]);
+    assert_eq!(span.start, TextPos {line: 2, column: 29});
+    assert_eq!(live_registry.file_id_to_file(live_registry.file_ids["app.rs"]).source, " App: {width: 2.0}\n");
+}
@@ -0,0 +1,310 @@
+use {
+    std::{
+        any::TypeId,
+        fs,
+        path::{Path, PathBuf},
+    },
+    makepad_live_compiler::{
+        makepad_live_id::*,
+        makepad_live_tokenizer::Delim,
+        makepad_math::vec2,
+        LiveBinOp,
+        LiveFileId,
+        LiveIdAsProp,
+        LiveModuleId,
+        LiveNode,
+        LiveNodeOrigin,
+        LiveNodeSlice,
+        LivePrinter,
+        LiveRegistry,
+        LiveToken,
+        LiveTypeInfo,
+        LiveValue,
+        TextPos,
+    }
+};
+
+// Registers `source` as a live file, with a placeholder type for each `{{Type}}` in it.
+fn register(live_registry: &mut LiveRegistry, file_name: &str, source: &str) -> Result<LiveFileId, String> {
+    let (tokens, _) = LiveRegistry::tokenize_from_str(source, TextPos::default(), LiveFileId::new(0)).map_err( | error | error.message) ?;
+    let module_id = LiveModuleId(LiveId::from_str("test").unwrap(), LiveId::from_str_unchecked(file_name));
+    let live_type_infos = tokens.windows(3).filter_map( | window | match (window[0].token, window[1].token, window[2].token) {
+        (LiveToken::Open(Delim::Brace), LiveToken::Open(Delim::Brace), LiveToken::Ident(type_name)) => Some(LiveTypeInfo {
+            live_type: TypeId::of::<()>(),
+            type_name,
+            module_id: LiveModuleId(id!(test), id!(types)),
+            fields: Vec::new(),
+        }),
+        _ => None
+    }).collect();
+    live_registry.register_live_file(file_name, module_id, source.to_string(), live_type_infos, TextPos::default())
+        .map_err( | error | error.to_string())
+}
+
+fn original_nodes(live_registry: &LiveRegistry, file_id: LiveFileId) -> Vec<LiveNode> {
+    live_registry.live_files[file_id.to_index()].original.nodes.clone()
+}
+
+// Returns a description of the node at `index` that does not depend on where it was parsed.
+fn describe_node(live_registry: &LiveRegistry, nodes: &[LiveNode], index: usize) -> String {
+    let node = &nodes[index];
+    let value = match &node.value {
+        LiveValue::DocumentString {..} | LiveValue::Dependency {..} => {
+            format!("{:?}", live_registry.live_node_as_string(node))
+        }
+        LiveValue::DSL {token_start, token_count, ..} => {
+            let original = &live_registry.live_files[node.origin.token_id().unwrap().file_id().to_index()].original;
+            let tokens = &original.tokens[*token_start as usize..(token_start + token_count) as usize];
+            tokens.iter().map( | token | match token.token {
+                LiveToken::String {index, len} => {
+                    let mut string = String::new();
+                    original.get_string(index as usize, len as usize, &mut string);
+                    format!("{:?} ", string)
+                }
+                token => format!("{} ", token)
+            }).collect()
+        }
+        LiveValue::Class {..} => {
+            // the placeholder types all share a live type, so the name is found in the source
+            let token_id = node.origin.token_id().unwrap();
+            let tokens = &live_registry.live_files[token_id.file_id().to_index()].original.tokens;
+            let type_name = tokens[token_id.token_index()..].windows(3).find_map( | window | match (window[0].token, window[1].token, window[2].token) {
+                (LiveToken::Open(Delim::Brace), LiveToken::Open(Delim::Brace), LiveToken::Ident(type_name)) => Some(type_name),
+                _ => None
+            });
+            format!("Class {:?}", type_name)
+        }
+        value => format!("{:?}", value)
+    };
+    format!("{} {:?} {} {}", node.id, node.origin.prop_type(), node.origin.node_has_prefix(), value)
+}
+
+fn describe_nodes(live_registry: &LiveRegistry, nodes: &[LiveNode]) -> Vec<String> {
+    (0..nodes.len()).map( | index | describe_node(live_registry, nodes, index)).collect()
+}
+
+// Returns the bodies of the `live_register!` blocks in the Rust source `source`.
+fn live_register_bodies(source: &str) -> Vec<String> {
+    let mut bodies = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("live_register!") {
+        rest = &rest[start + "live_register!".len()..];
+        let open = match rest.find('{') {
+            Some(open) if rest[..open].trim().is_empty() => open,
+            _ => continue
+        };
+        let chars: Vec<char> = rest[open + 1..].chars().collect();
+        let mut depth = 0;
+        let mut index = 0;
+        let mut body = String::new();
+        while index < chars.len() {
+            match chars[index] {
+                '"' => {
+                    body.push('"');
+                    index += 1;
+                    while index < chars.len() && chars[index] != '"' {
+                        body.push(chars[index]);
+                        index += 1;
+                    }
+                }
+                '/' if chars.get(index + 1) == Some(&'/') => {
+                    while index < chars.len() && chars[index] != '\n' {
+                        body.push(chars[index]);
+                        index += 1;
+                    }
+                    continue
+                }
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => ()
+            }
+            if index < chars.len() {
+                body.push(chars[index]);
+            }
+            index += 1;
+        }
+        bodies.push(body);
+    }
+    bodies
+}
+
+fn find_rust_files(path: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(path).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                find_rust_files(&path, files);
+            }
+        }
+        else if name.ends_with(".rs") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn unchanged_documents_print_as_their_source() {
+    let source = r#"
+        // the default colors
+        use crate::theme::*;
+        Button: {{Button}} {
+            color: #f00, // red
+            instance hover: 0.0
+            label: "Press me"
+            size: (BASE * 2.0 + 1.0)
+
+            fn pixel(self) -> vec4 {
+                return mix(self.color, #fff, self.hover);
+            }
+        }
+    "#;
+    let mut live_registry = LiveRegistry::default();
+    let file_id = register(&mut live_registry, "unchanged", source).unwrap();
+    let nodes = original_nodes(&live_registry, file_id);
+    assert_eq!(live_registry.file_id_nodes_to_source(file_id, &nodes), source);
+}
+
+#[test]
+fn edited_nodes_are_printed_and_the_rest_is_preserved() {
+    let source = r#"
+        A: {
+            // the width
+            width: 10.0,
+            height: 20.0 // in pixels
+            anim: [{time: 0.0, value: 1.0}, {time: 1.0, value: 2.0}]
+            B: Frame {
+                visible: true
+            }
+        }
+    "#;
+    let mut live_registry = LiveRegistry::default();
+    let file_id = register(&mut live_registry, "edited", source).unwrap();
+    let mut nodes = original_nodes(&live_registry, file_id);
+    let a = nodes.child_by_name(0, id!(A).as_field()).unwrap();
+    let width = nodes.child_by_name(a, id!(width).as_field()).unwrap();
+    nodes[width].value = LiveValue::Float(15.0);
+    let height = nodes.child_by_name(a, id!(height).as_field()).unwrap();
+    nodes.remove(height);
+    let b = nodes.child_by_name(a, id!(B).as_field()).unwrap();
+    let close = nodes.skip_node(b) - 1;
+    nodes.insert(close, LiveNode {
+        origin: LiveNodeOrigin::field(),
+        id: LiveId::from_str("color").unwrap(),
+        value: LiveValue::Color(0x336699ff)
+    });
+    assert_eq!(live_registry.file_id_nodes_to_source(file_id, &nodes), r#"
+        A: {
+            // the width
+            width: 15.0,
+            anim: [{time: 0.0, value: 1.0}, {time: 1.0, value: 2.0}]
+            B: Frame {
+                visible: true
+                color: #336699ff
+            }
+        }
+    "#);
+}
+
+#[test]
+fn new_nodes_are_printed_in_the_canonical_format() {
+    let source = "A: {}";
+    let mut live_registry = LiveRegistry::default();
+    let file_id = register(&mut live_registry, "canonical", source).unwrap();
+    let mut nodes = original_nodes(&live_registry, file_id);
+    // ids that are made with `id!` can not be printed, since their names are not known
+    let new_nodes = vec![
+        (LiveId::from_str("a").unwrap(), LiveValue::Int(1), LiveNodeOrigin::field()),
+        (LiveId::from_str("b").unwrap(), LiveValue::BareEnum {base: LiveId::from_str("Axis").unwrap(), variant: LiveId::from_str("Horizontal").unwrap()}, LiveNodeOrigin::field()),
+        (LiveId::from_str("c").unwrap(), LiveValue::Expr {expand_index: None}, LiveNodeOrigin::field()),
+        (LiveId(0), LiveValue::ExprBinOp(LiveBinOp::Mul), LiveNodeOrigin::empty()),
+        (LiveId(0), LiveValue::ExprBinOp(LiveBinOp::Add), LiveNodeOrigin::empty()),
+        (LiveId(0), LiveValue::Id(LiveId::from_str("a").unwrap()), LiveNodeOrigin::empty()),
+        (LiveId(0), LiveValue::Float(1.0), LiveNodeOrigin::empty()),
+        (LiveId(0), LiveValue::Int(2), LiveNodeOrigin::empty()),
+        (LiveId::from_str("c").unwrap(), LiveValue::Close, LiveNodeOrigin::empty()),
+        (LiveId::from_str("d").unwrap(), LiveValue::Clone(LiveId::from_str("Frame").unwrap()), LiveNodeOrigin::instance()),
+        (LiveId::from_str("e").unwrap(), LiveValue::Vec2(vec2(1.0, 2.5)), LiveNodeOrigin::field()),
+        (LiveId::from_str("d").unwrap(), LiveValue::Close, LiveNodeOrigin::empty()),
+    ];
+    let a = nodes.child_by_name(0, id!(A).as_field()).unwrap();
+    for (offset, (id, value, origin)) in new_nodes.into_iter().enumerate() {
+        nodes.insert(a + 1 + offset, LiveNode {origin, id, value});
+    }
+    assert_eq!(live_registry.file_id_nodes_to_source(file_id, &nodes), "A: {
+    a: 1
+    b: Axis::Horizontal
+    c: ((a + 1.0) * 2)
+    d = Frame {
+        e: vec2(1.0, 2.5)
+    }
+}");
+}
+
+#[test]
+fn live_register_blocks_round_trip() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let mut files = Vec::new();
+    find_rust_files(&root, &mut files);
+    files.sort();
+    let mut block_count = 0;
+    let mut live_registry = LiveRegistry::default();
+    for path in files {
+        let rust_source = fs::read_to_string(&path).unwrap();
+        for (block_index, source) in live_register_bodies(&rust_source).into_iter().enumerate() {
+            let file_name = format!("{}#{}", path.display(), block_index);
+            let file_id = match register(&mut live_registry, &file_name, &source) {
+                Ok(file_id) => file_id,
+                // blocks that do not parse are mentions of the macro in test data, not live documents
+                Err(_) => continue
+            };
+            block_count += 1;
+            let nodes = original_nodes(&live_registry, file_id);
+            assert_eq!(live_registry.file_id_nodes_to_source(file_id, &nodes), source, "{}", file_name);
+
+            let printed = LivePrinter::new(&live_registry, file_id).reformat().print(&nodes);
+            let printed_file_name = format!("{} (printed)", file_name);
+            let printed_file_id = register(&mut live_registry, &printed_file_name, &printed)
+                .unwrap_or_else( | error | panic!("{}: {}\n{}", file_name, error, printed));
+            let printed_nodes = original_nodes(&live_registry, printed_file_id);
+            assert_eq!(
+                describe_nodes(&live_registry, &nodes),
+                describe_nodes(&live_registry, &printed_nodes),
+                "{}\n{}",
+                file_name,
+                printed
+            );
+            // the canonical format is stable
+            assert_eq!(LivePrinter::new(&live_registry, printed_file_id).reformat().print(&printed_nodes), printed);
+        }
+    }
+    assert!(block_count > 50, "only found {} live_register! blocks", block_count);
+}
+
+#[test]
+fn reformatting_keeps_comments_and_empty_lines() {
+    let source = r#"
+        // the button
+        Button: {{Button}} {color: #f00, // red
+
+
+            /* the size */ size: 2.0
+            // no more members
+        }
+        // the end
+    "#;
+    let mut live_registry = LiveRegistry::default();
+    let file_id = register(&mut live_registry, "reformat", source).unwrap();
+    let nodes = original_nodes(&live_registry, file_id);
+    assert_eq!(LivePrinter::new(&live_registry, file_id).reformat().print(&nodes), "// the button
+Button: {{Button}} {
+    color: #f00 // red
+
+    /* the size */
+    size: 2.0
+    // no more members
+}
+// the end
+");
+}
@@ -0,0 +1,119 @@
+use makepad_live_compiler::{
+    makepad_live_id::*,
+    live_eval,
+    LiveEval,
+    LiveIdAsProp,
+    LiveModuleId,
+    LiveNodeSlice,
+    LiveRegistry,
+    LiveValue,
+    TextPos,
+};
+
+fn register(live_registry: &mut LiveRegistry, file_name: &str, module_path: &str, source: &str) {
+    live_registry.register_live_file(
+        file_name,
+        LiveModuleId::from_str(module_path).unwrap(),
+        source.to_string(),
+        vec![],
+        TextPos::default()
+    ).unwrap_or_else( | error | panic!("{}", error));
+}
+
+fn registry_with_themes() -> LiveRegistry {
+    let mut live_registry = LiveRegistry::default();
+    register(&mut live_registry, "theme.live", "app::theme", r#"
+        const COLOR: #f00
+        const SIZE: 1.0
+        Label: {
+            width: 1.0
+            height: (SIZE * 2.0)
+        }
+        ThemeLight: {
+            COLOR: #0f0
+            Label: {width: 2.0}
+        }
+        ThemeLarge: ThemeLight {
+            SIZE: 3.0
+        }
+    "#);
+    register(&mut live_registry, "app.live", "app::main", r#"
+        use crate::theme::Label
+        App: Label {}
+    "#);
+    let mut errors = Vec::new();
+    live_registry.expand_all_documents(&mut errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+    live_registry
+}
+
+fn set_theme(live_registry: &mut LiveRegistry, theme: Option<LiveId>) {
+    live_registry.set_theme(theme).unwrap_or_else( | errors | panic!("{:?}", errors));
+}
+
+// Returns the root value `name` of the theme file.
+fn root_value(live_registry: &LiveRegistry, name: LiveId) -> LiveValue {
+    let doc = live_registry.module_id_and_name_to_doc(LiveModuleId::from_str("app::theme").unwrap(), name).unwrap();
+    doc.nodes[doc.index].value.clone()
+}
+
+// Returns the value of the property `prop` of the object `name` in the module `module_path`.
+fn value(live_registry: &LiveRegistry, module_path: &str, name: LiveId, prop: LiveId) -> LiveValue {
+    let doc = live_registry.module_id_and_name_to_doc(LiveModuleId::from_str(module_path).unwrap(), name).unwrap();
+    let index = doc.nodes.child_by_name(doc.index, prop.as_field()).unwrap();
+    doc.nodes[index].value.clone()
+}
+
+fn eval_height(live_registry: &LiveRegistry) -> LiveEval {
+    let doc = live_registry.module_id_and_name_to_doc(LiveModuleId::from_str("app::theme").unwrap(), id!(Label)).unwrap();
+    let index = doc.nodes.child_by_name(doc.index, id!(height).as_field()).unwrap();
+    live_eval(live_registry, index, &mut (index + 1), doc.nodes).unwrap_or_else( | error | panic!("{}", error.message))
+}
+
+#[test]
+fn theme_overrides_constants() {
+    let mut live_registry = registry_with_themes();
+    assert_eq!(root_value(&live_registry, id!(COLOR)), LiveValue::Color(0xff0000ff));
+    set_theme(&mut live_registry, Some(id!(ThemeLight)));
+    assert_eq!(root_value(&live_registry, id!(COLOR)), LiveValue::Color(0x00ff00ff));
+    // the overridden node is still a constant
+    let doc = live_registry.module_id_and_name_to_doc(LiveModuleId::from_str("app::theme").unwrap(), id!(COLOR)).unwrap();
+    assert_eq!(live_registry.get_node_prefix(doc.nodes[doc.index].origin), Some(id!(const)));
+}
+
+#[test]
+fn theme_overrides_objects_used_by_other_files() {
+    let mut live_registry = registry_with_themes();
+    assert_eq!(value(&live_registry, "app::main", id!(App), id!(width)), LiveValue::Float(1.0));
+    set_theme(&mut live_registry, Some(id!(ThemeLight)));
+    assert_eq!(value(&live_registry, "app::main", id!(App), id!(width)), LiveValue::Float(2.0));
+}
+
+#[test]
+fn expressions_use_themed_constants() {
+    let mut live_registry = registry_with_themes();
+    assert_eq!(eval_height(&live_registry), LiveEval::Float(2.0));
+    set_theme(&mut live_registry, Some(id!(ThemeLarge)));
+    assert_eq!(eval_height(&live_registry), LiveEval::Float(6.0));
+}
+
+#[test]
+fn themes_inherit_from_each_other() {
+    let mut live_registry = registry_with_themes();
+    set_theme(&mut live_registry, Some(id!(ThemeLarge)));
+    assert_eq!(root_value(&live_registry, id!(COLOR)), LiveValue::Color(0x00ff00ff));
+    assert_eq!(root_value(&live_registry, id!(SIZE)), LiveValue::Float(3.0));
+    assert_eq!(value(&live_registry, "app::main", id!(App), id!(width)), LiveValue::Float(2.0));
+}
+
+#[test]
+fn no_theme_restores_the_source_values() {
+    let mut live_registry = registry_with_themes();
+    set_theme(&mut live_registry, Some(id!(ThemeLarge)));
+    set_theme(&mut live_registry, None);
+    assert_eq!(live_registry.active_theme, None);
+    assert_eq!(root_value(&live_registry, id!(COLOR)), LiveValue::Color(0xff0000ff));
+    assert_eq!(root_value(&live_registry, id!(SIZE)), LiveValue::Float(1.0));
+    assert_eq!(value(&live_registry, "app::main", id!(App), id!(width)), LiveValue::Float(1.0));
+    assert_eq!(eval_height(&live_registry), LiveEval::Float(2.0));
+}
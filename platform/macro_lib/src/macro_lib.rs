@@ -847,6 +847,13 @@ impl TokenParser {
                     tb.ident_with_span(&ty, span);
                 }
             }
+            // a path like std::collections::HashMap
+            while self.eat_double_colon_destruct() {
+                tb.add("::");
+                if let Some((ty, span)) = self.eat_any_ident_with_span() {
+                    tb.ident_with_span(&ty, span);
+                }
+            }
             tb.stream(self.eat_generic());
             return Some(tb.end())
         }
@@ -212,7 +212,7 @@ pub fn derive_de_json_impl(input: TokenStream) -> TokenStream {
             tb.add("impl").stream(generic.clone());
             tb.add("DeJson for").ident(&name).stream(generic).stream(where_clause);
             tb.add("{ fn de_json ( s : & mut  DeJsonState , i : & mut std :: str :: Chars )");
-            tb.add("-> std :: result :: Result < Self , makepad_microserde :: DeJsonErr > { ");
+            tb.add("-> std :: result :: Result < Self , DeJsonErr > { ");
             tb.add("s . curly_open ( i ) ? ;");
             tb.add("let _ = s . string ( i ) ? ;");
             tb.add("s . colon ( i ) ? ;");
@@ -0,0 +1,160 @@
+use {
+    std::fmt::Write,
+    crate::{
+        makepad_live_compiler::{
+            LiveRegistry,
+            LiveError,
+            LiveTokenId,
+            live_error_origin,
+            LiveErrorOrigin,
+            TextSpan,
+            TokenSpan
+        },
+        generate::{DisplayFnName, DisplayFnNameWithClosureArgs, DisplayClosureName},
+        shader_ast::*,
+        shader_registry::ShaderRegistry
+    }
+};
+
+// The platform compiles the GLSL or Metal source that is generated from a draw shader, and the
+// errors it finds are reported against lines of that source. Each function in the source is
+// generated from a function or closure in the DSL, so an error is mapped back to the DSL by
+// finding the function definition it is in.
+
+/// Returns the line and message of each error in the `log` of the platform's shader compiler.
+/// Lines start at 1. This understands GLSL logs, where errors look like
+/// `ERROR: 0:12: 'x' : undeclared identifier`, Metal logs, where they look like
+/// `program_source:12:5: error: use of undeclared identifier 'x'`, and HLSL logs, where they look
+/// like `Shader@0x0000(12,5-6): error X3004: undeclared identifier 'x'`.
+pub fn parse_compile_log(log: &str) -> Vec<(usize, String)> {
+    let mut errors = Vec::new();
+    for line in log.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("ERROR:") {
+            let mut parts = rest.splitn(3, ':');
+            let (_, line, message) = (parts.next(), parts.next(), parts.next());
+            if let (Some(line), Some(message)) = (line, message) {
+                if let Ok(line) = line.trim().parse() {
+                    errors.push((line, message.trim().to_string()));
+                }
+            }
+        }
+        else if let Some(rest) = line.strip_prefix("program_source:") {
+            let mut parts = rest.splitn(3, ':');
+            let (line, _, message) = (parts.next(), parts.next(), parts.next());
+            if let (Some(line), Some(message)) = (line, message) {
+                if let (Ok(line), Some(message)) = (line.parse(), message.trim().strip_prefix("error:")) {
+                    errors.push((line, message.trim().to_string()));
+                }
+            }
+        }
+        else if let Some((location, message)) = line.split_once("): error ") {
+            let line = location.rsplit_once('(').and_then( | (_, position) | position.split(',').next());
+            // the message starts with the code of the error, like `X3004: `
+            let message = message.split_once(": ").map_or(message, | (_, message) | message);
+            if let Some(Ok(line)) = line.map( | line | line.parse()) {
+                errors.push((line, message.trim().to_string()));
+            }
+        }
+    }
+    errors
+}
+
+/// Maps the errors in the `log` of the platform's compile step of the shader `source` that was
+/// generated for the draw shader `draw_shader_ptr` back to the DSL. An error inside a generated
+/// function is reported at the name of the DSL function, or at the closure, it was generated from.
+/// Any other error, and a log without located errors, is reported at the draw shader itself.
+pub fn map_compile_log(
+    live_registry: &LiveRegistry,
+    shader_registry: &ShaderRegistry,
+    draw_shader_ptr: DrawShaderPtr,
+    source: &str,
+    log: &str
+) -> Vec<LiveError> {
+    let draw_shader_span: TokenSpan = match live_registry.ptr_to_node(draw_shader_ptr.0).origin.token_id() {
+        Some(token_id) => token_id.into(),
+        None => return vec![LiveError {
+            origin: live_error_origin!(),
+            span: TextSpan::default().into(),
+            message: log.trim().to_string()
+        }]
+    };
+    let fn_names = generated_fn_names(shader_registry, draw_shader_ptr);
+
+    // the span each line of the source maps to
+    let mut line_spans = Vec::new();
+    let mut span = draw_shader_span;
+    for line in source.lines() {
+        if !line.starts_with(char::is_whitespace) && !line.starts_with('}') {
+            span = fn_names.iter()
+                .find( | (name, _) | defines_fn(line, name))
+                .map_or(draw_shader_span, | (_, span) | *span);
+        }
+        line_spans.push(span);
+    }
+
+    let errors = parse_compile_log(log);
+    if errors.is_empty() {
+        return vec![LiveError {
+            origin: live_error_origin!(),
+            span: draw_shader_span.into(),
+            message: log.trim().to_string()
+        }]
+    }
+    errors.into_iter().map( | (line, message) | {
+        let span = line.checked_sub(1).and_then( | line | line_spans.get(line)).copied().unwrap_or(draw_shader_span);
+        LiveError {
+            origin: live_error_origin!(),
+            span: span.into(),
+            message
+        }
+    }).collect()
+}
+
+// Returns the name of each function that can be generated for the draw shader `draw_shader_ptr`,
+// together with the span of the DSL it is generated from.
+fn generated_fn_names(shader_registry: &ShaderRegistry, draw_shader_ptr: DrawShaderPtr) -> Vec<(String, TokenSpan)> {
+    let mut names = Vec::new();
+    let draw_shader_def = match shader_registry.draw_shader_defs.get(&draw_shader_ptr) {
+        Some(draw_shader_def) => draw_shader_def,
+        None => return names
+    };
+    for fn_ptr in draw_shader_def.all_fns.borrow().iter() {
+        let fn_def = match shader_registry.all_fns.get(fn_ptr) {
+            Some(fn_def) => fn_def,
+            None => continue
+        };
+        let mut name = String::new();
+        write!(name, "{}", DisplayFnName(fn_def.fn_ptr, fn_def.ident)).unwrap();
+        names.push((name, fn_name_span(fn_def)));
+        for (index, closure_def) in fn_def.closure_defs.iter().enumerate() {
+            let mut name = String::new();
+            write!(name, "{}", DisplayClosureName(fn_def.fn_ptr, ClosureDefIndex(index))).unwrap();
+            names.push((name, closure_def.span));
+        }
+        if let Some(closure_sites) = fn_def.closure_sites.borrow().as_ref() {
+            for (site_index, closure_site) in closure_sites.iter().enumerate() {
+                if let Some(callee) = shader_registry.all_fns.get(&closure_site.call_to) {
+                    let mut name = String::new();
+                    write!(name, "{}", DisplayFnNameWithClosureArgs(site_index, fn_def.fn_ptr, callee.ident)).unwrap();
+                    names.push((name, fn_name_span(callee)));
+                }
+            }
+        }
+    }
+    names
+}
+
+// The span of a function starts at `fn`, which is followed by its name.
+fn fn_name_span(fn_def: &FnDef) -> TokenSpan {
+    let token_id = fn_def.span.token_id;
+    LiveTokenId::new(token_id.file_id(), token_id.token_index() + 1).into()
+}
+
+// Returns whether `line` is the definition of the generated function `name`, which follows its
+// return type and is followed by its parameters.
+fn defines_fn(line: &str, name: &str) -> bool {
+    line.match_indices(name).any( | (index, _) | {
+        line[..index].ends_with(' ') && line[index + name.len()..].trim_start().starts_with('(')
+    })
+}
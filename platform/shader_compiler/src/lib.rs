@@ -14,6 +14,7 @@ pub mod lhs_check;
 pub mod swizzle;
 pub mod util;
 pub mod generate;
+pub mod compile_log;
 
 //#[cfg(any(target_os = "linux", target_arch = "wasm32", test))]
 pub mod generate_glsl;
@@ -44,6 +45,10 @@ pub use {
             ShaderEnum,
             ShaderRegistry,
             DrawShaderQuery
+        },
+        compile_log::{
+            parse_compile_log,
+            map_compile_log
         }
     }
 };
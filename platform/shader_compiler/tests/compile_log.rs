@@ -0,0 +1,31 @@
+use makepad_shader_compiler::parse_compile_log;
+
+#[test]
+fn glsl_logs_are_parsed() {
+    let log = "ERROR: 0:12: 'var_x_0' : undeclared identifier\nWARNING: 0:3: extension is not supported\nERROR: 0:40: '' : compilation terminated \n";
+    assert_eq!(parse_compile_log(log), vec![
+        (12, "'var_x_0' : undeclared identifier".to_string()),
+        (40, "'' : compilation terminated".to_string()),
+    ]);
+}
+
+#[test]
+fn metal_logs_are_parsed() {
+    let log = "Compilation failed: \n\nprogram_source:7:5: warning: unused variable 'y'\n    float y = 1.0;\n    ^\nprogram_source:9:12: error: use of undeclared identifier 'x'\n    return x;\n           ^\n";
+    assert_eq!(parse_compile_log(log), vec![
+        (9, "use of undeclared identifier 'x'".to_string()),
+    ]);
+}
+
+#[test]
+fn logs_without_locations_have_no_errors() {
+    assert!(parse_compile_log("Link failed: too many varyings").is_empty());
+}
+
+#[test]
+fn hlsl_logs_are_parsed() {
+    let log = "Shader@0x000001C8(4,10-14): warning X3206: implicit truncation of vector type\nShader@0x000001C8(12,5-11): error X3004: undeclared identifier 'var_x_0'\n";
+    assert_eq!(parse_compile_log(log), vec![
+        (12, "undeclared identifier 'var_x_0'".to_string()),
+    ]);
+}
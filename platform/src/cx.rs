@@ -227,7 +227,10 @@ impl Default for Cx {
             id!(Wrapping),
             id!(Overflow),
             id!(SplitterAlign),
-            id!(MouseCursor)
+            id!(MouseCursor),
+            id!(Vec),
+            id!(HashMap),
+            id!(Array)
         ]);
         
         Self {
@@ -78,6 +78,9 @@ pub struct CxDrawShaders {
     pub fingerprints: Vec<DrawShaderFingerprint>,
     pub error_set: HashSet<DrawShaderPtr>,
     pub error_fingerprints: Vec<Vec<LiveNode >>,
+    /// The errors of the shaders that failed to compile since the shaders were last flushed, both
+    /// from the shader compiler and from the platform's compile step.
+    pub errors: Vec<LiveFileError>,
 }
 
 impl Cx {
@@ -89,6 +92,21 @@ impl Cx {
         self.draw_shaders.fingerprints.clear();
         self.draw_shaders.error_set.clear();
         self.draw_shaders.error_fingerprints.clear();
+        self.draw_shaders.errors.clear();
+    }
+    
+    /// Returns the errors of the shaders that failed to compile, located in the live documents
+    /// the shaders are defined in. These are cleared when the shaders are flushed after a live
+    /// edit, and the shaders that still fail report them again when they are drawn.
+    pub fn shader_errors(&self) -> &[LiveFileError] {
+        &self.draw_shaders.errors
+    }
+    
+    pub (crate) fn add_shader_error(&mut self, error: LiveError) {
+        let error = self.live_registry.borrow().live_error_to_live_file_error(error);
+        if !self.draw_shaders.errors.contains(&error) {
+            self.draw_shaders.errors.push(error);
+        }
     }
 }
 
@@ -2,6 +2,7 @@ use {
     crate::{
         makepad_derive_live::*,
         makepad_math::*,
+        makepad_micro_serde::*,
         area::Area,
         live_traits::*,
         draw_2d::cx_2d::Cx2d,
@@ -23,6 +24,16 @@ pub struct Walk {
     pub margin: Margin,
     pub width: Size,
     pub height: Size,
+    // Bounds on the evaluated size. Sizes that are only known after the walk, such as `Fit`,
+    // are not bounded before then.
+    pub min_width: Option<f32>,
+    pub max_width: Option<f32>,
+    pub min_height: Option<f32>,
+    pub max_height: Option<f32>,
+    // The ratio of width to height. A size that is not known yet follows from the other one,
+    // and when both are known the larger one shrinks to match. The min and max bounds take
+    // precedence over the ratio.
+    pub aspect_ratio: Option<f32>,
 }
 
 #[derive(Clone, Copy, Debug, Live, LiveHook)]
@@ -47,7 +58,7 @@ pub struct Padding {
     pub bottom: f32
 }
 
-#[derive(Copy, Clone, Debug, Live, LiveHook)]
+#[derive(Copy, Clone, Debug, Live, LiveHook, SerRon, DeRon)]
 pub enum Axis {
     #[pick] Horizontal,
     Vertical
@@ -72,13 +83,17 @@ pub enum Size {
     #[live(200.0)] Fixed(f32),
     #[live(0.0)] Negative(f32),
     Fit,
+    // A percentage of the padded size of the parent, margins included.
+    #[live(100.0)] Percent(f32),
+    // Like `Fill`, but the space that is left is shared between the deferred fill walks of a
+    // turtle in proportion to their weights. `Fill` has a weight of 1.
+    #[live(1.0)] Weight(f32),
 }
 
 #[derive(Clone, Default, Debug)]
 pub struct DeferWalk {
     defer_index: usize,
-    margin: Margin,
-    other_axis: Size,
+    walk: Walk,
     pos: Vec2
 }
 
@@ -89,13 +104,24 @@ pub struct TurtleWalk {
     rect: Rect,
 }
 
+// The sizes of the deferred fill walks of a turtle, computed once for the space that is left along
+// the flow, so that resolving each walk does not share out the space again.
+#[derive(Clone, Default, Debug)]
+struct DeferSizes {
+    left: f32,
+    sizes: Vec<f32>,
+    // The sum of the sizes of the walks before each walk, and of all walks at the end.
+    shifts: Vec<f32>,
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct Turtle {
     walk: Walk,
     layout: Layout,
     align_start: usize,
     turtle_walks_start: usize,
-    defer_count: usize,
+    defer_walks: Vec<Walk>,
+    defer_sizes: DeferSizes,
     pos: Vec2,
     origin: Vec2,
     width: f32,
@@ -120,12 +146,9 @@ impl<'a> Cx2d<'a> {
     
     pub fn defer_walk(&mut self, walk: Walk) -> Option<DeferWalk> {
         let turtle = self.turtles.last_mut().unwrap();
-        let defer_index = turtle.defer_count;
+        let defer_index = turtle.defer_walks.len();
         let pos = turtle.pos;
-        let size = vec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk(&walk);
         let margin_size = walk.margin.size();
         match turtle.layout.flow {
             Flow::Right if walk.width.is_fill() => {
@@ -133,11 +156,10 @@ impl<'a> Cx2d<'a> {
                 turtle.pos.x += margin_size.x + spacing.x;
                 turtle.update_width_max(0.0);
                 turtle.update_height_max(size.y + margin_size.y);
-                turtle.defer_count += 1;
+                turtle.defer_walks.push(walk);
                 Some(DeferWalk {
                     defer_index,
-                    margin: walk.margin,
-                    other_axis: walk.height,
+                    walk,
                     pos: pos + spacing
                 })
             },
@@ -146,11 +168,10 @@ impl<'a> Cx2d<'a> {
                 turtle.pos.y += margin_size.y + spacing.y;
                 turtle.update_width_max(size.x + margin_size.x);
                 turtle.update_height_max(0.0);
-                turtle.defer_count += 1;
+                turtle.defer_walks.push(walk);
                 Some(DeferWalk {
                     defer_index,
-                    margin: walk.margin,
-                    other_axis: walk.width,
+                    walk,
                     pos: pos + spacing
                 })
             },
@@ -162,19 +183,15 @@ impl<'a> Cx2d<'a> {
     
     pub fn begin_turtle_with_guard(&mut self, walk: Walk, layout: Layout, guard_area: Area) {
         
-        let (origin, width, height) = if let Some(parent) = self.turtles.last() {
+        let (origin, size) = if let Some(parent) = self.turtles.last() {
             let o = walk.margin.left_top() + if let Some(pos) = walk.abs_pos {pos} else {
                 parent.pos + parent.child_spacing(self.turtle_walks.len())
             };
-            let w = parent.eval_width(walk.width, walk.margin, parent.layout.flow);
-            let h = parent.eval_height(walk.height, walk.margin, parent.layout.flow);
-            (o, w, h)
+            (o, parent.eval_walk(&walk))
         }
         else {
             let o = Vec2 {x: walk.margin.left, y: walk.margin.top};
-            let w = walk.width.fixed_or_nan();
-            let h = walk.height.fixed_or_nan();
-            (o, w, h)
+            (o, walk.constrain(vec2(walk.width.fixed_or_nan(), walk.height.fixed_or_nan())))
         };
         
         let turtle = Turtle {
@@ -182,14 +199,15 @@ impl<'a> Cx2d<'a> {
             layout,
             align_start: self.align_list.len(),
             turtle_walks_start: self.turtle_walks.len(),
-            defer_count: 0,
+            defer_walks: Vec::new(),
+            defer_sizes: DeferSizes::default(),
             pos: Vec2 {
                 x: origin.x + layout.padding.left,
                 y: origin.y + layout.padding.top
             },
             origin,
-            width,
-            height,
+            width: size.x,
+            height: size.y,
             width_used: layout.padding.left,
             height_used: layout.padding.top,
             guard_area,
@@ -203,7 +221,7 @@ impl<'a> Cx2d<'a> {
     }
     
     pub fn end_turtle_with_guard(&mut self, guard_area: Area) -> Rect {
-        let turtle = self.turtles.pop().unwrap();
+        let mut turtle = self.turtles.pop().unwrap();
         if guard_area != turtle.guard_area {
             panic!("End turtle guard area misaligned!, begin/end pair not matched begin {:?} end {:?}", turtle.guard_area, guard_area)
         }
@@ -225,12 +243,11 @@ impl<'a> Cx2d<'a> {
         
         match turtle.layout.flow {
             Flow::Right => {
-                if turtle.defer_count > 0 {
-                    let left = turtle.width_left();
-                    let part = left / turtle.defer_count as f32;
+                if !turtle.defer_walks.is_empty() {
+                    turtle.update_defer_sizes();
                     for i in turtle.turtle_walks_start..self.turtle_walks.len() {
                         let walk = &self.turtle_walks[i];
-                        let shift_x = walk.defer_index as f32 * part;
+                        let shift_x = turtle.defer_sizes.shifts[walk.defer_index];
                         let shift_y = turtle.layout.align.y * (turtle.padded_height_or_used() - walk.rect.size.y);
                         let align_start = walk.align_start;
                         let align_end = self.get_turtle_walk_align_end(i);
@@ -249,13 +266,12 @@ impl<'a> Cx2d<'a> {
                 }
             },
             Flow::Down => {
-                if turtle.defer_count > 0 {
-                    let left = turtle.height_left();
-                    let part = left / turtle.defer_count as f32;
+                if !turtle.defer_walks.is_empty() {
+                    turtle.update_defer_sizes();
                     for i in turtle.turtle_walks_start..self.turtle_walks.len() {
                         let walk = &self.turtle_walks[i];
                         let shift_x = turtle.layout.align.x * (turtle.padded_width_or_used() - walk.rect.size.x);
-                        let shift_y = walk.defer_index as f32 * part;
+                        let shift_y = turtle.defer_sizes.shifts[walk.defer_index];
                         let align_start = walk.align_start;
                         let align_end = self.get_turtle_walk_align_end(i);
                         self.move_align_list(shift_x, shift_y, align_start, align_end);
@@ -324,10 +340,7 @@ impl<'a> Cx2d<'a> {
     fn walk_turtle_internal(&mut self, walk: Walk, align_start: usize, actually_move:bool) -> Rect {
         
         let turtle = self.turtles.last_mut().unwrap();
-        let size = vec2(
-            turtle.eval_width(walk.width, walk.margin, turtle.layout.flow),
-            turtle.eval_height(walk.height, walk.margin, turtle.layout.flow)
-        );
+        let size = turtle.eval_walk(&walk);
         
         if let Some(pos) = walk.abs_pos {
             if actually_move{
@@ -375,7 +388,7 @@ impl<'a> Cx2d<'a> {
                 
                 self.turtle_walks.push(TurtleWalk {
                     align_start,
-                    defer_index: turtle.defer_count,
+                    defer_index: turtle.defer_walks.len(),
                     rect: Rect {pos, size: size + margin_size}
                 });
             }
@@ -466,7 +479,7 @@ impl Turtle {
     }
     
     fn child_spacing(&self, walks_len: usize) -> Vec2 {
-        if self.turtle_walks_start < walks_len || self.defer_count > 0 {
+        if self.turtle_walks_start < walks_len || !self.defer_walks.is_empty() {
             match self.layout.flow {
                 Flow::Right => {
                     vec2(self.layout.spacing, 0.0)
@@ -504,12 +517,21 @@ impl Turtle {
         self.pos
     }
     
+    /// Evaluates the size of `walk` in this turtle, within the bounds and aspect ratio of the walk.
+    pub fn eval_walk(&self, walk: &Walk) -> Vec2 {
+        walk.constrain(vec2(
+            self.eval_width(walk.width, walk.margin, self.layout.flow),
+            self.eval_height(walk.height, walk.margin, self.layout.flow)
+        ))
+    }
+    
     pub fn eval_width(&self, width: Size, margin: Margin, flow: Flow) -> f32 {
         return match width {
             Size::Fit => std::f32::NAN,
             Size::Negative(v) => -v,
             Size::Fixed(v) => max_zero_keep_nan(v),
-            Size::Fill => {
+            Size::Percent(v) => max_zero_keep_nan(self.padded_width_or_used() * v / 100.0 - margin.width()),
+            Size::Fill | Size::Weight(_) => {
                 match flow {
                     Flow::Right => {
                         max_zero_keep_nan(self.width_left() - margin.width())
@@ -531,7 +553,8 @@ impl Turtle {
             Size::Fit => std::f32::NAN,
             Size::Negative(v) => -v,
             Size::Fixed(v) => max_zero_keep_nan(v),
-            Size::Fill => {
+            Size::Percent(v) => max_zero_keep_nan(self.padded_height_or_used() * v / 100.0 - margin.height()),
+            Size::Fill | Size::Weight(_) => {
                 match flow {
                     Flow::Right | Flow::Overlay=>{
                         let r = max_zero_keep_nan(self.height - self.layout.padding.height() - margin.height());
@@ -600,30 +623,89 @@ impl Turtle {
             r
         }
     }    
+    
+    // Brings the sizes of the deferred fill walks up to date, if a walk was deferred or the space
+    // that is left along the flow changed since they were last computed.
+    fn update_defer_sizes(&mut self) {
+        let left = match self.layout.flow {
+            Flow::Right => self.width_left(),
+            Flow::Down => self.height_left(),
+            Flow::Overlay => 0.0
+        };
+        if self.defer_sizes.sizes.len() == self.defer_walks.len() && self.defer_sizes.left.to_bits() == left.to_bits() {
+            return
+        }
+        let sizes = self.compute_defer_sizes(left);
+        let mut shifts = Vec::with_capacity(sizes.len() + 1);
+        let mut shift = 0.0;
+        shifts.push(shift);
+        for size in &sizes {
+            shift += size;
+            shifts.push(shift);
+        }
+        self.defer_sizes = DeferSizes {left, sizes, shifts};
+    }
+    
+    // Shares the space that is left along the flow between the deferred fill walks, in proportion
+    // to their weights. A walk whose share is out of its bounds is clamped, and the space that is
+    // then left is shared again between the walks that were not.
+    fn compute_defer_sizes(&self, left: f32) -> Vec<f32> {
+        let bounds: Vec<_> = match self.layout.flow {
+            Flow::Right => self.defer_walks.iter().map( | walk | {
+                (walk.width.fill_weight(), walk.min_width, walk.max_width)
+            }).collect(),
+            Flow::Down => self.defer_walks.iter().map( | walk | {
+                (walk.height.fill_weight(), walk.min_height, walk.max_height)
+            }).collect(),
+            Flow::Overlay => return Vec::new()
+        };
+        let mut sizes = vec![0.0; bounds.len()];
+        let mut clamped = vec![false; bounds.len()];
+        loop {
+            let mut free = left;
+            let mut weight = 0.0;
+            for (i, (w, _, _)) in bounds.iter().enumerate() {
+                if clamped[i] {free -= sizes[i]} else {weight += w}
+            }
+            let free = max_zero_keep_nan(free);
+            let mut any_clamped = false;
+            for (i, (w, min, max)) in bounds.iter().enumerate() {
+                if clamped[i] {
+                    continue
+                }
+                let share = if weight > 0.0 {free * w / weight} else {0.0};
+                sizes[i] = clamp_keep_nan(share, *min, *max);
+                if !share.is_nan() && sizes[i] != share {
+                    clamped[i] = true;
+                    any_clamped = true;
+                }
+            }
+            if !any_clamped {
+                return sizes
+            }
+        }
+    }
 }
 
 impl DeferWalk {
-    pub fn resolve(&self, cx: &Cx2d) -> Walk {
-        let turtle = cx.turtles.last().unwrap();
+    pub fn resolve(&self, cx: &mut Cx2d) -> Walk {
+        let turtle = cx.turtles.last_mut().unwrap();
+        turtle.update_defer_sizes();
+        let shift = turtle.defer_sizes.shifts[self.defer_index];
+        let size = turtle.defer_sizes.sizes[self.defer_index];
         match turtle.layout.flow {
             Flow::Right => {
-                let left = turtle.width_left();
-                let part = left / turtle.defer_count as f32;
                 Walk {
-                    abs_pos: Some(self.pos + vec2(part * self.defer_index as f32, 0.)),
-                    margin: self.margin,
-                    width: Size::Fixed(part),
-                    height: self.other_axis
+                    abs_pos: Some(self.pos + vec2(shift, 0.)),
+                    width: Size::Fixed(size),
+                    ..self.walk
                 }
             },
             Flow::Down => {
-                let left = turtle.height_left();
-                let part = left / turtle.defer_count as f32;
                 Walk {
-                    abs_pos: Some(self.pos + vec2(0., part * self.defer_index as f32)),
-                    margin: self.margin,
-                    height: Size::Fixed(part),
-                    width: self.other_axis
+                    abs_pos: Some(self.pos + vec2(0., shift)),
+                    height: Size::Fixed(size),
+                    ..self.walk
                 }
             }
             Flow::Overlay => panic!()
@@ -690,37 +772,33 @@ impl Layout {
 impl Walk {
     pub fn empty() -> Self {
         Self {
-            abs_pos: None,
-            margin: Margin::default(),
             width: Size::Fixed(0.0),
             height: Size::Fixed(0.0),
+            ..Self::default()
         }
     }
     
     pub fn size(w: Size, h: Size) -> Self {
         Self {
-            abs_pos: None,
-            margin: Margin::default(),
             width: w,
             height: h,
+            ..Self::default()
         }
     }
     
     pub fn fixed_size(w: f32, h: f32) -> Self {
         Self {
-            abs_pos: None,
-            margin: Margin::default(),
             width: Size::Fixed(w),
             height: Size::Fixed(h),
+            ..Self::default()
         }
     }
     
     pub fn fit() -> Self {
         Self {
-            abs_pos: None,
-            margin: Margin::default(),
             width: Size::Fit,
             height: Size::Fit,
+            ..Self::default()
         }
     }
     
@@ -753,6 +831,52 @@ impl Walk {
         self.margin = v;
         self
     }
+    
+    pub fn with_min_width(mut self, v: f32) -> Self {
+        self.min_width = Some(v);
+        self
+    }
+    
+    pub fn with_max_width(mut self, v: f32) -> Self {
+        self.max_width = Some(v);
+        self
+    }
+    
+    pub fn with_min_height(mut self, v: f32) -> Self {
+        self.min_height = Some(v);
+        self
+    }
+    
+    pub fn with_max_height(mut self, v: f32) -> Self {
+        self.max_height = Some(v);
+        self
+    }
+    
+    pub fn with_aspect_ratio(mut self, v: f32) -> Self {
+        self.aspect_ratio = Some(v);
+        self
+    }
+    
+    /// Applies the bounds and the aspect ratio of this walk to an evaluated `size`.
+    pub fn constrain(&self, size: Vec2) -> Vec2 {
+        let mut w = clamp_keep_nan(size.x, self.min_width, self.max_width);
+        let mut h = clamp_keep_nan(size.y, self.min_height, self.max_height);
+        if let Some(ratio) = self.aspect_ratio.filter( | ratio | *ratio > 0.0) {
+            if w.is_nan() && h >= 0.0 {
+                w = clamp_keep_nan(h * ratio, self.min_width, self.max_width);
+            }
+            else if h.is_nan() && w >= 0.0 {
+                h = clamp_keep_nan(w / ratio, self.min_height, self.max_height);
+            }
+            else if w > h * ratio && h >= 0.0 {
+                w = clamp_keep_nan(h * ratio, self.min_width, self.max_width);
+            }
+            else if w >= 0.0 && h >= 0.0 {
+                h = clamp_keep_nan(w / ratio, self.min_height, self.max_height);
+            }
+        }
+        vec2(w, h)
+    }
 }
 
 impl Default for Align {
@@ -897,10 +1021,19 @@ impl Size {
     
     pub fn is_fill(&self) -> bool {
         match self {
-            Self::Fill => true,
+            Self::Fill | Self::Weight(_) => true,
             _ => false
         }
     }
+    
+    /// The weight with which this size shares the space that is left with its siblings.
+    pub fn fill_weight(&self) -> f32 {
+        match self {
+            Self::Fill => 1.0,
+            Self::Weight(v) => max_zero_keep_nan(*v),
+            _ => 0.0
+        }
+    }
 }
 
 fn max_zero_keep_nan(v: f32) -> f32 {
@@ -912,4 +1045,14 @@ fn max_zero_keep_nan(v: f32) -> f32 {
     }
 }
 
+// Sizes that are not known yet, and negative sizes which walk backwards, are not clamped. The
+// min bound takes precedence over the max bound.
+fn clamp_keep_nan(v: f32, min: Option<f32>, max: Option<f32>) -> f32 {
+    if v.is_nan() || v < 0.0 {
+        return v
+    }
+    let v = max.map_or(v, | max | v.min(max));
+    min.map_or(v, | min | v.max(min))
+}
+
 
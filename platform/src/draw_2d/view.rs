@@ -239,7 +239,7 @@ impl View {
             
             let w = Size::Fixed(cx.cx.draw_lists[self.draw_list_id].rect.size.x);
             let h = Size::Fixed(cx.cx.draw_lists[self.draw_list_id].rect.size.y);
-            let walk = Walk {abs_pos: None, width: w, height: h, margin: walk.margin};
+            let walk = Walk {abs_pos: None, width: w, height: h, ..walk};
             let pos = cx.peek_walk_pos(walk);
             if pos == cx.cx.draw_lists[self.draw_list_id].rect.pos {
                 cx.walk_turtle(walk);
@@ -5,7 +5,6 @@ use {
         makepad_math::*,
         cx::Cx,
         texture::Texture,
-        console_log,
         area::Area,
         geometry::{GeometryFields},
         live_traits::*,
@@ -189,9 +188,7 @@ impl DrawVars {
                 Err(e) => {
                     cx.draw_shaders.error_set.insert(draw_shader_ptr);
                     cx.draw_shaders.error_fingerprints.push(fingerprint);
-                    // ok so. lets get the source for this file id
-                    let err = live_registry.live_error_to_live_file_error(e);
-                    console_log!("Error {}", err);
+                    cx.add_shader_error(e);
                 }
                 Ok(()) => {
                     // OK! SO the shader parsed
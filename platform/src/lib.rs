@@ -107,6 +107,7 @@ pub use {
         event::{
             KeyCode,
             Event,
+            DrawEvent,
             HitEvent,
             DragEvent,
             Signal,
@@ -154,7 +155,7 @@ pub use {
             FingerDropHitEvent,
         },
         cursor::MouseCursor,
-        menu::Menu,
+        menu::{Menu, Command},
         font::Font,
         draw_2d::{
             turtle::{
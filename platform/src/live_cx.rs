@@ -69,6 +69,14 @@ impl Cx {
         self.apply_error(origin, index, nodes, format!("wrong value type. Prop: {} primitive: {} value: {:?}", nodes[index].id, prim, nodes[index].value))
     }
     
+    pub fn apply_error_wrong_value_type_for_container(&mut self, origin: LiveErrorOrigin, index: usize, nodes: &[LiveNode], container: &str) {
+        self.apply_error(origin, index, nodes, format!("wrong value type. Prop: {} container: {} value: {:?}", nodes[index].id, container, nodes[index].value))
+    }
+    
+    pub fn apply_error_wrong_array_length(&mut self, origin: LiveErrorOrigin, index: usize, nodes: &[LiveNode], expected: usize, got: usize) {
+        self.apply_error(origin, index, nodes, format!("wrong array length. Prop: {} expected: {} got: {}", nodes[index].id, expected, got))
+    }
+    
     pub fn apply_error_wrong_expression_type_for_primitive(&mut self, origin: LiveErrorOrigin, index: usize, nodes: &[LiveNode], prim: &str, b: LiveEval) {
         self.apply_error(origin, index, nodes, format!("wrong expression return. Prop: {} primitive: {} value: {:?}", nodes[index].id, prim, b))
     }
@@ -170,6 +178,142 @@ impl Cx {
         }
     }
     
+    /// Registers a standalone live file, such as a `.live` file loaded at runtime, as the module
+    /// `module_path`. Files can `use` each other and the modules registered from Rust in any
+    /// order, as long as they are all registered before `live_expand`.
+    pub fn register_live_file_from_bytes(&mut self, file_name: &str, module_path: &str, data: &[u8]) {
+        let code = match std::str::from_utf8(data) {
+            Ok(code) => code.to_string(),
+            Err(_) => {
+                println!("Error parsing live file {}: not valid utf8", file_name);
+                return
+            }
+        };
+        let module_id = match LiveModuleId::from_str(module_path) {
+            Ok(module_id) => module_id,
+            Err(err) => {
+                println!("Error parsing live file {}: {}", file_name, err);
+                return
+            }
+        };
+        let result = self.live_registry.borrow_mut().register_live_file(
+            file_name,
+            module_id,
+            code,
+            Vec::new(),
+            TextPos::default()
+        );
+        if let Err(err) = result {
+            println!("Error parsing live file {}", err);
+        }
+    }
+    
+    /// Reloads a live file from its new contents. For a file registered from a `live_register!`
+    /// block, these are the new body of the block, as in `LiveFile::source`. The change is sent to the app as a `LiveEdit` event after the current event.
+    pub fn reload_live_file_from_bytes(&mut self, file_name: &str, data: &[u8]) {
+        let code = match std::str::from_utf8(data) {
+            Ok(code) => code.to_string(),
+            Err(_) => {
+                println!("Error reloading live file {}: not valid utf8", file_name);
+                return
+            }
+        };
+        let live_registry_rc = self.live_registry.clone();
+        let mut live_registry = live_registry_rc.borrow_mut();
+        match live_registry.update_live_file(file_name, code) {
+            Ok(Some(LiveEditEvent::ReparseDocument)) => {
+                if let Err(errs) = live_registry.process_next_originals_and_expand() {
+                    for err in errs {
+                        println!("Error expanding live file {}", live_registry.live_error_to_live_file_error(err));
+                    }
+                }
+                self.live_edit_event = Some(LiveEditEvent::ReparseDocument);
+            }
+            Ok(Some(live_edit_event)) => {
+                self.live_edit_event = Some(live_edit_event);
+            }
+            Ok(None) => (),
+            Err(err) => {
+                println!("Error reloading live file {}", err.into_live_file_error(file_name));
+            }
+        }
+    }
+    
+    /// Updates the live document registered from the `live_register!` block of a Rust file from
+    /// the new source of the whole file, such as when an editor changes it. Like
+    /// `reload_live_file_from_bytes`, the change is sent to the app as a `LiveEdit` event after the
+    /// current event. The errors are returned rather than printed, so they can be shown in the
+    /// source.
+    pub fn update_live_file_from_rust(&mut self, file_name: &str, rust_source: &str) -> Vec<LiveFileError> {
+        let live_registry_rc = self.live_registry.clone();
+        let mut live_registry = live_registry_rc.borrow_mut();
+        match live_registry.update_live_file_from_rust(file_name, rust_source) {
+            Ok(Some(LiveEditEvent::ReparseDocument)) => {
+                self.live_edit_event = Some(LiveEditEvent::ReparseDocument);
+                match live_registry.process_next_originals_and_expand() {
+                    Ok(()) => Vec::new(),
+                    Err(errs) => errs.into_iter().map( | err | live_registry.live_error_to_live_file_error(err)).collect()
+                }
+            }
+            Ok(Some(live_edit_event)) => {
+                self.live_edit_event = Some(live_edit_event);
+                Vec::new()
+            }
+            Ok(None) => Vec::new(),
+            Err(err) => vec![err.into_live_file_error(file_name)]
+        }
+    }
+    
+    /// Switches the active theme of the live documents, see `LiveRegistry::set_theme`.
+    /// The change is sent to the app as a `LiveEdit` event after the current event, which
+    /// applies the documents again and redraws. The theme is not switched on its own when the
+    /// light or dark preference of the OS changes; apps that want to follow it call this.
+    pub fn set_theme(&mut self, theme: Option<LiveId>) {
+        let live_registry_rc = self.live_registry.clone();
+        let mut live_registry = live_registry_rc.borrow_mut();
+        if live_registry.active_theme == theme {
+            return
+        }
+        if let Err(errs) = live_registry.set_theme(theme) {
+            for err in errs {
+                println!("Error switching theme {}", live_registry.live_error_to_live_file_error(err));
+            }
+        }
+        self.live_edit_event = Some(LiveEditEvent::ReparseDocument);
+    }
+
+    pub fn theme(&self) -> Option<LiveId> {
+        self.live_registry.borrow().active_theme
+    }
+
+    /// Switches the locale that messages are translated to. Like `set_theme`, the change is sent
+    /// to the app as a `LiveEdit` event, which applies the documents again so their `tr` calls
+    /// are evaluated for the new locale, and redraws.
+    pub fn set_locale(&mut self, locale: LiveId) {
+        let mut live_registry = self.live_registry.borrow_mut();
+        if live_registry.locale == locale {
+            return
+        }
+        live_registry.set_locale(locale);
+        self.live_edit_event = Some(LiveEditEvent::ReparseDocument);
+    }
+
+    pub fn locale(&self) -> LiveId {
+        self.live_registry.borrow().locale
+    }
+
+    /// Translates a message for Rust code, see `LiveRegistry::tr`. Errors are printed, and the
+    /// name of the message is returned instead.
+    pub fn tr(&self, message: LiveId, args: &[LiveEval]) -> String {
+        match self.live_registry.borrow().tr(message, args) {
+            Ok(text) => text,
+            Err(err) => {
+                println!("Error translating message {}: {}", message, err);
+                message.to_string()
+            }
+        }
+    }
+
     fn update_buffer_from_live_value(slots: usize, output: &mut [f32], offset: usize, v: &LiveValue) {
         match slots {
             1 => {
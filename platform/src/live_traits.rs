@@ -160,6 +160,12 @@ impl ApplyFrom {
         }
     }
     
+    // live edits and animations only hold the values that changed, so containers keep the
+    // elements that are not in them
+    pub fn is_partial(&self) -> bool {
+        matches!(self, Self::LiveEdit | Self::Animate)
+    }
+    
     pub fn file_id(&self) -> Option<LiveFileId> {
         match self {
             Self::NewFromDoc {file_id} => Some(*file_id),
@@ -224,3 +230,143 @@ impl<T> LiveNew for Option<T> where T: LiveApply + LiveNew + 'static{
         T::live_type_info(_cx)
     }
 }
+
+// Vec<T> is applied from an array, reusing the elements it already has
+impl<T> LiveHook for Vec<T> where T: LiveApply + LiveNew + 'static {}
+impl<T> LiveApply for Vec<T> where T: LiveApply + LiveNew + 'static {
+    fn apply(&mut self, cx: &mut Cx, from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> usize {
+        if !nodes[index].is_array() {
+            cx.apply_error_wrong_value_type_for_container(live_error_origin!(), index, nodes, "Vec");
+            return nodes.skip_node(index)
+        }
+        let mut item_index = index + 1;
+        let mut count = 0;
+        while !nodes[item_index].is_close() {
+            if let Some(item) = self.get_mut(count) {
+                item_index = item.apply(cx, from, item_index, nodes);
+            }
+            else {
+                let mut item = T::new(cx);
+                item_index = item.apply(cx, from, item_index, nodes);
+                self.push(item);
+            }
+            count += 1;
+        }
+        if !from.is_partial() {
+            self.truncate(count);
+        }
+        item_index + 1
+    }
+}
+
+impl<T> LiveNew for Vec<T> where T: LiveApply + LiveNew + 'static {
+    fn new(_cx: &mut Cx) -> Self {
+        Vec::new()
+    }
+    
+    fn live_register(cx: &mut Cx) {
+        T::live_register(cx)
+    }
+    
+    fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
+        LiveTypeInfo {
+            module_id: LiveModuleId::from_str(module_path!()).unwrap(),
+            live_type: LiveType::of::<Self>(),
+            fields: Vec::new(),
+            type_name: LiveId::from_str("Vec").unwrap()
+        }
+    }
+}
+
+// HashMap<LiveId, T> is applied from an object, with an element for each property
+impl<T> LiveHook for std::collections::HashMap<LiveId, T> where T: LiveApply + LiveNew + 'static {}
+impl<T> LiveApply for std::collections::HashMap<LiveId, T> where T: LiveApply + LiveNew + 'static {
+    fn apply(&mut self, cx: &mut Cx, from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> usize {
+        if !nodes[index].is_open() || nodes[index].is_array() {
+            cx.apply_error_wrong_value_type_for_container(live_error_origin!(), index, nodes, "HashMap");
+            return nodes.skip_node(index)
+        }
+        let mut ids = Vec::new();
+        let mut item_index = index + 1;
+        while !nodes[item_index].is_close() {
+            let id = nodes[item_index].id;
+            if let Some(item) = self.get_mut(&id) {
+                item_index = item.apply(cx, from, item_index, nodes);
+            }
+            else {
+                let mut item = T::new(cx);
+                item_index = item.apply(cx, from, item_index, nodes);
+                self.insert(id, item);
+            }
+            ids.push(id);
+        }
+        if !from.is_partial() {
+            self.retain( | id, _ | ids.contains(id));
+        }
+        item_index + 1
+    }
+}
+
+impl<T> LiveNew for std::collections::HashMap<LiveId, T> where T: LiveApply + LiveNew + 'static {
+    fn new(_cx: &mut Cx) -> Self {
+        std::collections::HashMap::new()
+    }
+    
+    fn live_register(cx: &mut Cx) {
+        T::live_register(cx)
+    }
+    
+    fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
+        LiveTypeInfo {
+            module_id: LiveModuleId::from_str(module_path!()).unwrap(),
+            live_type: LiveType::of::<Self>(),
+            fields: Vec::new(),
+            type_name: LiveId::from_str("HashMap").unwrap()
+        }
+    }
+}
+
+// [T; N] is applied from an array with exactly N elements
+impl<T, const N: usize> LiveHook for [T; N] where T: LiveApply + LiveNew + 'static {}
+impl<T, const N: usize> LiveApply for [T; N] where T: LiveApply + LiveNew + 'static {
+    fn apply(&mut self, cx: &mut Cx, from: ApplyFrom, index: usize, nodes: &[LiveNode]) -> usize {
+        if !nodes[index].is_array() {
+            cx.apply_error_wrong_value_type_for_container(live_error_origin!(), index, nodes, "Array");
+            return nodes.skip_node(index)
+        }
+        let mut item_index = index + 1;
+        let mut count = 0;
+        while !nodes[item_index].is_close() {
+            if let Some(item) = self.get_mut(count) {
+                item_index = item.apply(cx, from, item_index, nodes);
+            }
+            else {
+                item_index = nodes.skip_node(item_index);
+            }
+            count += 1;
+        }
+        if count > N || count < N && !from.is_partial() {
+            cx.apply_error_wrong_array_length(live_error_origin!(), index, nodes, N, count);
+        }
+        item_index + 1
+    }
+}
+
+impl<T, const N: usize> LiveNew for [T; N] where T: LiveApply + LiveNew + 'static {
+    fn new(cx: &mut Cx) -> Self {
+        std::array::from_fn( | _ | T::new(cx))
+    }
+    
+    fn live_register(cx: &mut Cx) {
+        T::live_register(cx)
+    }
+    
+    fn live_type_info(_cx: &mut Cx) -> LiveTypeInfo {
+        LiveTypeInfo {
+            module_id: LiveModuleId::from_str(module_path!()).unwrap(),
+            live_type: LiveType::of::<Self>(),
+            fields: Vec::new(),
+            type_name: LiveId::from_str("Array").unwrap()
+        }
+    }
+}
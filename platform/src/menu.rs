@@ -78,4 +78,23 @@ impl Menu {
             command: command
         }
     }
+    
+    /// Returns the name and command of every item in this menu and its submenus, in the order in
+    /// which they appear.
+    pub fn commands(&self) -> Vec<(&str, Command)> {
+        fn collect<'a>(menu: &'a Menu, out: &mut Vec<(&'a str, Command)>) {
+            match menu {
+                Menu::Main {items} | Menu::Sub {items, ..} => {
+                    for item in items {
+                        collect(item, out);
+                    }
+                }
+                Menu::Item {name, command} => out.push((name, *command)),
+                Menu::Line => ()
+            }
+        }
+        let mut out = Vec::new();
+        collect(self, &mut out);
+        out
+    }
 }
\ No newline at end of file
@@ -10,6 +10,7 @@ use {
         makepad_shader_compiler::{
             generate_metal,
             generate_metal::MetalGeneratedShader,
+            map_compile_log,
         },
         makepad_math::*,
         makepad_live_id::*,
@@ -599,6 +600,7 @@ pub struct SlErr {
 impl Cx {
     
     pub fn mtl_compile_shaders(&mut self, metal_cx: &MetalCx) {
+        let mut errors = Vec::new();
         for draw_shader_ptr in &self.draw_shaders.compile_set {
             if let Some(item) = self.draw_shaders.ptr_to_item.get(&draw_shader_ptr) {
                 let cx_shader = &mut self.draw_shaders.shaders[item.draw_shader_id];
@@ -620,14 +622,30 @@ impl Cx {
                     }
                 }
                 if cx_shader.platform.is_none() {
-                    if let Some(shp) = CxPlatformDrawShader::new(metal_cx, gen) {
-                        cx_shader.platform = Some(self.draw_shaders.platform.len());
-                        self.draw_shaders.platform.push(shp);
+                    let mtlsl = gen.mtlsl.clone();
+                    match CxPlatformDrawShader::new(metal_cx, gen) {
+                        Ok(shp) => {
+                            cx_shader.platform = Some(self.draw_shaders.platform.len());
+                            self.draw_shaders.platform.push(shp);
+                        }
+                        Err(log) => {
+                            // map the errors back to the DSL so they can be shown in the source
+                            errors.extend(map_compile_log(
+                                &self.live_registry.borrow(),
+                                &self.shader_registry,
+                                *draw_shader_ptr,
+                                &mtlsl,
+                                &log
+                            ));
+                        }
                     }
                 }
             }
         }
         self.draw_shaders.compile_set.clear();
+        for error in errors {
+            self.add_shader_error(error);
+        }
     }
 }
 
@@ -670,7 +688,7 @@ impl CxPlatformDrawShader {
     pub fn new(
         metal_cx: &MetalCx,
         shader: MetalGeneratedShader,
-    ) -> Option<Self> {
+    ) -> Result<Self, String> {
         let options = RcObjcId::from_owned(unsafe {msg_send![class!(MTLCompileOptions), new]});
         unsafe {
             let _: () = msg_send![options.as_id(), setFastMathEnabled: YES];
@@ -689,12 +707,7 @@ impl CxPlatformDrawShader {
             Some(library) => library,
             None => {
                 let description: ObjcId = unsafe {msg_send![error, localizedDescription]};
-                let string = nsstring_to_string(description);
-                println!("{}", string);
-                for (index, line) in shader.mtlsl.split("\n").enumerate() {
-                    println!("{}: {}", index + 1, line);
-                }
-                panic!("{}", string);
+                return Err(nsstring_to_string(description));
             }
         });
         
@@ -752,7 +765,7 @@ impl CxPlatformDrawShader {
             buffer_id += 1;
         }
         
-        return Some(Self {
+        return Ok(Self {
             _library: library,
             render_pipeline_state,
             draw_uniform_buffer_id,
@@ -2,6 +2,7 @@ use {
     std::{
         io::prelude::*,
         fs::File,
+        fs,
         io,
     },
     crate::{
@@ -15,6 +16,17 @@ use {
     }
 };
 
+use {
+    std::{
+        sync::{Arc, Mutex},
+        time::{Duration, SystemTime},
+    },
+    crate::{
+        makepad_live_id::*,
+        cx_api::CxPlatformApi,
+    }
+};
+
 #[macro_export]
 macro_rules!console_log {
     ( $ ( $t: tt) *) => {
@@ -28,6 +40,7 @@ pub struct CxDesktop {
     //pub file_read_id: u64,
     //pub file_reads: Vec<FileRead>,
     pub profiler_start: Option<u64>,
+    pub live_file_watcher: LiveFileWatcher,
 }
 
 impl Default for CxDesktop {
@@ -37,12 +50,101 @@ impl Default for CxDesktop {
             //file_read_id: 1,
             //file_reads: Vec::new(),
             profiler_start: None,
+            live_file_watcher: LiveFileWatcher::default(),
         }
     }
 }
 
+// The path and the last seen modification time of every watched live file.
+type WatchedFiles = Arc<Mutex<Vec<(String, Option<SystemTime>)>>>;
+
+// How often the live files that are loaded from disk are checked for changes.
+const LIVE_FILE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Watches the live files that are loaded from disk by polling their modification times, which
+// works the same on every desktop platform and also catches editors that save by replacing a file.
+#[derive(Clone, Default)]
+pub struct LiveFileWatcher {
+    files: WatchedFiles,
+    is_polling: bool,
+}
+
+impl LiveFileWatcher {
+    pub fn watch(&mut self, path: &str) {
+        let mut files = self.files.lock().unwrap();
+        if files.iter().all( | (file_path, _) | file_path != path) {
+            files.push((path.to_string(), modified(path)));
+        }
+    }
+    
+    // Starts a thread that posts a `LiveFileChanged` signal whenever a watched file has changed,
+    // so that the event loop picks up the change with `take_changed`.
+    pub fn start_polling(&mut self) {
+        if self.is_polling {
+            return
+        }
+        self.is_polling = true;
+        let files = self.files.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(LIVE_FILE_POLL_INTERVAL);
+            let has_changed = files.lock().unwrap().iter().any( | (path, time) | modified(path) != *time);
+            if has_changed {
+                Cx::post_signal(id!(LiveFileChanged).into());
+            }
+        });
+    }
+    
+    // Returns the paths of the watched files that changed since they were watched or since the
+    // last call.
+    pub fn take_changed(&mut self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (path, time) in self.files.lock().unwrap().iter_mut() {
+            let new_time = modified(path);
+            if new_time != *time {
+                *time = new_time;
+                paths.push(path.clone());
+            }
+        }
+        paths
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then( | metadata | metadata.modified()).ok()
+}
+
 impl Cx {
     
+    /// Loads the live file at `path` as the module `module_path`, see
+    /// `register_live_file_from_bytes`. The file is watched, and its changes are sent to the
+    /// running app as `LiveEdit` events.
+    pub fn register_live_file_from_disk(&mut self, path: &str, module_path: &str) {
+        match fs::read(path) {
+            Ok(data) => {
+                self.register_live_file_from_bytes(path, module_path, &data);
+                self.platform.desktop.live_file_watcher.watch(path);
+                self.platform.desktop.live_file_watcher.start_polling();
+            }
+            Err(err) => {
+                println!("Error loading live file {}: {}", path, err);
+            }
+        }
+    }
+    
+    fn reload_changed_live_files(&mut self) {
+        for path in self.platform.desktop.live_file_watcher.take_changed() {
+            match fs::read(&path) {
+                Ok(data) => {
+                    self.reload_live_file_from_bytes(&path, &data);
+                    self.call_live_edit();
+                }
+                Err(err) => {
+                    println!("Error reloading live file {}: {}", path, err);
+                }
+            }
+        }
+    }
+    
     pub fn desktop_load_dependencies(&mut self){
         for (path,dep) in &mut self.dependencies{
             if let Ok(mut file_handle) = File::open(path) {
@@ -97,6 +199,9 @@ impl Cx {
             Event::AppLostFocus => {
                 self.call_all_keys_up();
             },
+            Event::Signal(se) if se.signals.contains(&id!(LiveFileChanged).into()) => {
+                self.reload_changed_live_files();
+            },
             _ => ()
         };
     }
@@ -452,6 +452,13 @@ pub struct ToWasmPaintDirty {
 #[derive(ToWasm)]
 pub struct ToWasmRedrawAll {}
 
+#[derive(ToWasm)]
+pub struct ToWasmCompileWebGLShaderError {
+    pub shader_id: usize,
+    pub is_vertex: bool,
+    pub log: String
+}
+
 #[derive(ToWasm, Clone)]
 pub struct WVec3 {
     pub x: f32,
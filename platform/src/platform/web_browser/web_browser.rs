@@ -251,6 +251,11 @@ impl Cx {
                     self.redraw_all();
                 }
                 
+                id!(ToWasmCompileWebGLShaderError) => {
+                    let tw = ToWasmCompileWebGLShaderError::read_to_wasm(&mut to_wasm);
+                    self.webgl_compile_shader_error(tw.shader_id, tw.is_vertex, &tw.log);
+                }
+                
                 id!(ToWasmPaintDirty) => {
                     self.passes[self.windows[0].main_pass_id.unwrap()].paint_dirty = true;
                 }
@@ -312,6 +317,7 @@ impl Cx {
             to_wasm.block_skip(skip);
         };
         
+        self.call_live_edit();
         self.call_signals_and_triggers();
         
         if self.need_redrawing(){
@@ -588,6 +594,7 @@ pub unsafe extern "C" fn wasm_get_js_msg_class() -> u32 {
     ToWasmTimerFired::to_wasm_js(&mut out);
     ToWasmPaintDirty::to_wasm_js(&mut out);
     ToWasmRedrawAll::to_wasm_js(&mut out);
+    ToWasmCompileWebGLShaderError::to_wasm_js(&mut out);
     ToWasmXRUpdate::to_wasm_js(&mut out);
     ToWasmAppGotFocus::to_wasm_js(&mut out);
     ToWasmAppLostFocus::to_wasm_js(&mut out);
@@ -45,6 +45,7 @@ export class WasmWebGL extends WasmWebBrowser {
         gl.shaderSource(vsh, args.vertex)
         gl.compileShader(vsh)
         if (!gl.getShaderParameter(vsh, gl.COMPILE_STATUS)) {
+            this.compile_webgl_shader_error(args.shader_id, true, gl.getShaderInfoLog(vsh));
             return console.log(
                 gl.getShaderInfoLog(vsh),
                 add_line_numbers_to_string(args.vertex)
@@ -56,6 +57,7 @@ export class WasmWebGL extends WasmWebBrowser {
         gl.shaderSource(fsh, args.pixel)
         gl.compileShader(fsh)
         if (!gl.getShaderParameter(fsh, gl.COMPILE_STATUS)) {
+            this.compile_webgl_shader_error(args.shader_id, false, gl.getShaderInfoLog(fsh));
             return console.log(
                 gl.getShaderInfoLog(fsh),
                 add_line_numbers_to_string(args.pixel)
@@ -67,6 +69,7 @@ export class WasmWebGL extends WasmWebBrowser {
         gl.attachShader(program, fsh)
         gl.linkProgram(program)
         if (!gl.getProgramParameter(program, gl.LINK_STATUS)) {
+            this.compile_webgl_shader_error(args.shader_id, false, gl.getProgramInfoLog(program));
             return console.log(
                 gl.getProgramInfoLog(program),
                 add_line_numbers_to_string(args.vertex),
@@ -306,6 +309,13 @@ export class WasmWebGL extends WasmWebBrowser {
         gl.enable(gl.BLEND);
     }
     
+    // shaders are compiled while the messages from wasm are dispatched, so the error is sent
+    // back with a pump of its own
+    compile_webgl_shader_error(shader_id, is_vertex, log) {
+        this.to_wasm.ToWasmCompileWebGLShaderError({shader_id, is_vertex, log});
+        setTimeout(_ => this.do_wasm_pump(), 0);
+    }
+    
     
     
     init_webgl_context() {
@@ -2,6 +2,7 @@ use {
     crate::{
         makepad_shader_compiler::{
             generate_glsl,
+            map_compile_log,
         },
         makepad_wasm_bridge::*,
         makepad_math::*,
@@ -381,6 +382,29 @@ impl Cx {
         }
         self.draw_shaders.compile_set.clear();
     }
+    
+    // The shaders are compiled by the browser after `webgl_compile_shaders`, which sends back the
+    // log of any shader that fails so its errors can be mapped back to the DSL.
+    pub fn webgl_compile_shader_error(&mut self, shader_id: usize, is_vertex: bool, log: &str) {
+        let draw_shader_ptr = match self.draw_shaders.ptr_to_item.iter().find( | (_, item) | item.draw_shader_id == shader_id) {
+            Some((draw_shader_ptr, _)) => *draw_shader_ptr,
+            None => return
+        };
+        let shp = match self.draw_shaders.shaders.get(shader_id).and_then( | shader | shader.platform) {
+            Some(platform) => &self.draw_shaders.platform[platform],
+            None => return
+        };
+        let errors = map_compile_log(
+            &self.live_registry.borrow(),
+            &self.shader_registry,
+            draw_shader_ptr,
+            if is_vertex {&shp.vertex} else {&shp.pixel},
+            log
+        );
+        for error in errors {
+            self.add_shader_error(error);
+        }
+    }
 }
 
 impl CxPlatformDrawShader{
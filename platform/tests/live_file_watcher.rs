@@ -0,0 +1,46 @@
+use {
+    std::{env, fs, time::{Duration, SystemTime}},
+    makepad_platform::{
+        *,
+        makepad_live_compiler::LiveEditEvent,
+        platform::cx_desktop::LiveFileWatcher,
+    },
+};
+
+// Writes `source` to the file at `path`, and moves its modification time forward, since
+// consecutive writes can get the same time on file systems with a coarse resolution.
+fn write(path: &str, source: &str, seconds: u64) {
+    fs::write(path, source).unwrap();
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(seconds)).unwrap();
+}
+
+#[test]
+fn changed_live_files_are_reloaded_as_live_edits() {
+    let dir = env::temp_dir().join(format!("makepad_live_file_watcher_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("theme.live").to_string_lossy().into_owned();
+    let other_path = dir.join("other.live").to_string_lossy().into_owned();
+    write(&path, "Theme: {width: 1.0}\n", 0);
+    write(&other_path, "Other: {width: 1.0}\n", 0);
+
+    let mut cx = Cx::default();
+    cx.register_live_file_from_bytes(&path, "theme", &fs::read(&path).unwrap());
+    cx.live_expand();
+    let mut watcher = LiveFileWatcher::default();
+    watcher.watch(&path);
+    watcher.watch(&other_path);
+    assert!(watcher.take_changed().is_empty());
+
+    write(&path, "Theme: {width: 2.0}\n", 10);
+    let changed = watcher.take_changed();
+    assert_eq!(changed, vec![path.clone()]);
+    // a change is only reported once
+    assert!(watcher.take_changed().is_empty());
+
+    for path in changed {
+        cx.reload_live_file_from_bytes(&path, &fs::read(&path).unwrap());
+    }
+    assert!(matches!(cx.live_edit_event, Some(LiveEditEvent::Mutation {..})));
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,116 @@
+use {
+    std::collections::HashMap,
+    makepad_platform::{*, makepad_live_compiler::LiveEditEvent},
+};
+
+#[derive(Live, LiveHook)]
+struct Item {
+    #[live] value: f32,
+    // set by the tests to tell reused items from new ones
+    #[rust] tag: usize,
+}
+
+#[derive(Live, LiveHook)]
+struct Holder {
+    #[live] items: Vec<Item>,
+    #[live] named: HashMap<LiveId, Item>,
+    #[live] pair: [f32; 2],
+    #[live] after: f32,
+}
+
+const SOURCE: &str = "
+    holder: {
+        items: [{value: 1.0}, {value: 2.0}]
+        named: {a: {value: 3.0}}
+        pair: [4.0, 5.0]
+        after: 6.0
+    }
+";
+
+fn register(cx: &mut Cx, source: &str) {
+    cx.register_live_file_from_bytes("holder.live", "holder", source.as_bytes());
+    cx.live_expand();
+}
+
+fn values(holder: &Holder) -> Vec<f32> {
+    holder.items.iter().map( | item | item.value).collect()
+}
+
+#[test]
+fn containers_are_applied_from_arrays_and_objects() {
+    let mut cx = Cx::default();
+    register(&mut cx, SOURCE);
+    let holder = Holder::new_from_module(&mut cx, "holder", id!(holder)).unwrap();
+    assert_eq!(values(&holder), vec![1.0, 2.0]);
+    assert_eq!(holder.named.len(), 1);
+    assert_eq!(holder.named[&id!(a)].value, 3.0);
+    assert_eq!(holder.pair, [4.0, 5.0]);
+    assert_eq!(holder.after, 6.0);
+}
+
+#[test]
+fn items_are_reused_when_containers_are_applied_again() {
+    let mut cx = Cx::default();
+    register(&mut cx, SOURCE);
+    let mut holder = Holder::new_from_module(&mut cx, "holder", id!(holder)).unwrap();
+    holder.items[0].tag = 1;
+    holder.named.get_mut(&id!(a)).unwrap().tag = 2;
+    holder.apply_over(&mut cx, live!{
+        items: [{value: 7.0}, {value: 8.0}, {value: 9.0}]
+        named: {a: {value: 10.0}, b: {value: 11.0}}
+    });
+    assert_eq!(values(&holder), vec![7.0, 8.0, 9.0]);
+    assert_eq!(holder.items[0].tag, 1);
+    assert_eq!(holder.named[&id!(a)].tag, 2);
+    assert_eq!(holder.named[&id!(b)].value, 11.0);
+    // applying a whole document drops the items it no longer has
+    holder.apply(&mut cx, ApplyFrom::NewFromDoc {file_id: LiveFileId::new(0)}, 0, live_object!{
+        holder: {
+            items: [{value: 1.0}]
+            named: {b: {value: 12.0}}
+        }
+    });
+    assert_eq!(values(&holder), vec![1.0]);
+    assert_eq!(holder.items[0].tag, 1);
+    assert!(!holder.named.contains_key(&id!(a)));
+}
+
+#[test]
+fn live_edits_inside_arrays_are_applied() {
+    let mut cx = Cx::default();
+    register(&mut cx, SOURCE);
+    let mut holder = Holder::new_from_module(&mut cx, "holder", id!(holder)).unwrap();
+    holder.items[1].tag = 1;
+    let event = {
+        let mut live_registry = cx.live_registry.borrow_mut();
+        live_registry.main_module = live_registry.file_ids.get("holder.live").copied();
+        let source = SOURCE.replace("2.0", "20.0").replace("5.0", "50.0");
+        live_registry.update_live_file("holder.live", source).unwrap().unwrap()
+    };
+    assert!(matches!(event, LiveEditEvent::Mutation {..}));
+    holder.handle_live_edit_event(&mut cx, &Event::LiveEdit(event), id!(holder));
+    assert_eq!(values(&holder), vec![1.0, 20.0]);
+    assert_eq!(holder.items[1].tag, 1);
+    assert_eq!(holder.pair, [4.0, 50.0]);
+}
+
+#[test]
+fn wrong_value_types_leave_containers_unchanged() {
+    let mut cx = Cx::default();
+    register(&mut cx, SOURCE);
+    let mut holder = Holder::new_from_module(&mut cx, "holder", id!(holder)).unwrap();
+    holder.apply_over(&mut cx, live!{
+        items: {value: 1.0}
+        named: [{value: 1.0}]
+        pair: 1.0
+        after: 7.0
+    });
+    assert_eq!(values(&holder), vec![1.0, 2.0]);
+    assert_eq!(holder.named.len(), 1);
+    assert_eq!(holder.pair, [4.0, 5.0]);
+    // the fields after the wrong values are still applied
+    assert_eq!(holder.after, 7.0);
+    // an array of the wrong length is applied as far as it goes
+    holder.apply_over(&mut cx, live!{pair: [8.0, 9.0, 10.0]});
+    assert_eq!(holder.pair, [8.0, 9.0]);
+}
@@ -0,0 +1,40 @@
+use makepad_platform::{*, makepad_live_compiler::LiveEditEvent};
+
+#[derive(Live, LiveHook)]
+struct Label {
+    #[live] width: f32,
+}
+
+const SOURCE: &str = "
+    const WIDTH: 1.0
+    label: {width: (WIDTH)}
+    ThemeWide: {WIDTH: 2.0}
+";
+
+#[test]
+fn switching_themes_applies_the_main_module_again() {
+    let mut cx = Cx::default();
+    cx.register_live_file_from_bytes("app.live", "app", SOURCE.as_bytes());
+    cx.live_expand();
+    {
+        let mut live_registry = cx.live_registry.borrow_mut();
+        live_registry.main_module = live_registry.file_ids.get("app.live").copied();
+    }
+    let mut label = Label::new_from_module(&mut cx, "app", id!(label)).unwrap();
+    assert_eq!(label.width, 1.0);
+
+    cx.set_theme(Some(id!(ThemeWide)));
+    assert_eq!(cx.theme(), Some(id!(ThemeWide)));
+    let event = cx.live_edit_event.take().unwrap();
+    assert!(matches!(event, LiveEditEvent::ReparseDocument));
+    label.handle_live_edit_event(&mut cx, &Event::LiveEdit(event), id!(label));
+    assert_eq!(label.width, 2.0);
+
+    // switching to the theme that is already active does nothing
+    cx.set_theme(Some(id!(ThemeWide)));
+    assert!(cx.live_edit_event.is_none());
+    cx.set_theme(None);
+    let event = cx.live_edit_event.take().unwrap();
+    label.handle_live_edit_event(&mut cx, &Event::LiveEdit(event), id!(label));
+    assert_eq!(label.width, 1.0);
+}
@@ -0,0 +1,133 @@
+use makepad_platform::*;
+
+// Runs `f` with a 2d context outside of a draw pass, so the turtle can be walked without a window.
+fn with_cx_2d<R>(f: impl FnOnce(&mut Cx2d) -> R) -> R {
+    let mut cx = Cx::default();
+    let draw_event = DrawEvent::default();
+    let mut cx = Cx2d::new(&mut cx, &draw_event);
+    f(&mut cx)
+}
+
+fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+    Rect {pos: vec2(x, y), size: vec2(w, h)}
+}
+
+// Defers `walks` in a turtle of 300 by 100 with the given flow and returns the rects they resolve to.
+fn layout_deferred(flow: Flow, walks: &[Walk]) -> Vec<Rect> {
+    with_cx_2d( | cx | {
+        cx.begin_turtle(Walk::fixed_size(300.0, 100.0), Layout {flow, ..Layout::default()});
+        let defers: Vec<_> = walks.iter().map( | walk | cx.defer_walk(*walk).unwrap()).collect();
+        let rects = defers.iter().map( | defer | {
+            let walk = defer.resolve(cx);
+            cx.walk_turtle(walk)
+        }).collect();
+        cx.end_turtle();
+        rects
+    })
+}
+
+#[test]
+fn percent_is_relative_to_the_padded_parent() {
+    let rects = with_cx_2d( | cx | {
+        cx.begin_turtle(Walk::fixed_size(220.0, 120.0), Layout::flow_right().with_padding_all(10.0));
+        let a = cx.walk_turtle(Walk::size(Size::Percent(25.0), Size::Percent(50.0)));
+        let b = cx.walk_turtle(Walk::size(Size::Percent(50.0), Size::Fixed(10.0)).with_margin_left(10.0));
+        cx.end_turtle();
+        (a, b)
+    });
+    assert_eq!(rects, (rect(10.0, 10.0, 50.0, 50.0), rect(70.0, 10.0, 90.0, 10.0)));
+}
+
+#[test]
+fn fill_weights_share_the_space_left() {
+    let rects = layout_deferred(Flow::Right, &[
+        Walk::size(Size::Fill, Size::Fill),
+        Walk::size(Size::Weight(2.0), Size::Fixed(10.0)),
+    ]);
+    assert_eq!(rects, vec![rect(0.0, 0.0, 100.0, 100.0), rect(100.0, 0.0, 200.0, 10.0)]);
+}
+
+#[test]
+fn clamped_fills_give_their_space_to_the_others() {
+    let rects = layout_deferred(Flow::Down, &[
+        Walk::size(Size::Fill, Size::Fill).with_max_height(10.0),
+        Walk::size(Size::Fill, Size::Fill),
+        Walk::size(Size::Weight(3.0), Size::Fill).with_min_height(80.0),
+    ]);
+    assert_eq!(rects, vec![
+        rect(0.0, 0.0, 300.0, 10.0),
+        rect(0.0, 10.0, 300.0, 10.0),
+        rect(0.0, 20.0, 300.0, 80.0)
+    ]);
+}
+
+#[test]
+fn fills_share_the_space_left_by_the_walks_between_them() {
+    let rects = with_cx_2d( | cx | {
+        cx.begin_turtle(Walk::fixed_size(300.0, 100.0), Layout::flow_right());
+        let a = cx.defer_walk(Walk::size(Size::Fill, Size::Fill)).unwrap();
+        cx.walk_turtle(Walk::size(Size::Fixed(100.0), Size::Fill));
+        let b = cx.defer_walk(Walk::size(Size::Fill, Size::Fill)).unwrap();
+        let a = a.resolve(cx);
+        let a = cx.walk_turtle(a);
+        let b = b.resolve(cx);
+        let b = cx.walk_turtle(b);
+        cx.end_turtle();
+        (a, b)
+    });
+    assert_eq!(rects, (rect(0.0, 0.0, 100.0, 100.0), rect(200.0, 0.0, 100.0, 100.0)));
+}
+
+#[test]
+fn min_and_max_bound_the_size() {
+    let rects = with_cx_2d( | cx | {
+        cx.begin_turtle(Walk::fixed_size(300.0, 100.0), Layout::flow_right());
+        let a = cx.walk_turtle(Walk::fixed_size(10.0, 500.0).with_min_width(20.0).with_max_height(50.0));
+        // the min bound takes precedence over the max bound
+        let b = cx.walk_turtle(Walk::fixed_size(10.0, 10.0).with_min_width(30.0).with_max_width(5.0));
+        cx.end_turtle();
+        (a, b)
+    });
+    assert_eq!(rects, (rect(0.0, 0.0, 20.0, 50.0), rect(20.0, 0.0, 30.0, 10.0)));
+}
+
+#[test]
+fn fit_turtles_are_bounded_when_they_end() {
+    let rect = with_cx_2d( | cx | {
+        cx.begin_turtle(Walk::fixed_size(300.0, 100.0), Layout::flow_right());
+        cx.begin_turtle(Walk::fit().with_max_width(40.0).with_min_height(30.0), Layout::flow_right());
+        cx.walk_turtle(Walk::fixed_size(100.0, 10.0));
+        let rect = cx.end_turtle();
+        cx.end_turtle();
+        rect
+    });
+    assert_eq!(rect, self::rect(0.0, 0.0, 40.0, 30.0));
+}
+
+#[test]
+fn aspect_ratio_derives_or_shrinks_a_side() {
+    let rects = with_cx_2d( | cx | {
+        cx.begin_turtle(Walk::fixed_size(300.0, 100.0), Layout::flow_right());
+        let a = cx.walk_turtle(Walk::size(Size::Fit, Size::Fixed(20.0)).with_aspect_ratio(2.0));
+        let b = cx.walk_turtle(Walk::size(Size::Fixed(60.0), Size::Fill).with_aspect_ratio(2.0));
+        let c = cx.walk_turtle(Walk::size(Size::Fill, Size::Fill).with_aspect_ratio(0.5));
+        cx.end_turtle();
+        (a, b, c)
+    });
+    assert_eq!(rects, (rect(0.0, 0.0, 40.0, 20.0), rect(40.0, 0.0, 60.0, 30.0), rect(100.0, 0.0, 50.0, 100.0)));
+}
+
+#[test]
+fn sizes_and_constraints_are_set_from_the_dsl() {
+    let mut cx = Cx::default();
+    cx.register_live_file_from_bytes("turtle.live", "turtle", b"
+        walk: {width: Size::Percent(50), height: Size::Weight(2), min_width: 10, max_height: 20.5, aspect_ratio: 1.5}
+    ");
+    cx.live_expand();
+    let walk = Walk::new_from_module(&mut cx, "turtle", id!(walk)).unwrap();
+    assert!(matches!(walk.width, Size::Percent(v) if v == 50.0));
+    assert!(matches!(walk.height, Size::Weight(v) if v == 2.0));
+    assert_eq!((walk.min_width, walk.max_width), (Some(10.0), None));
+    assert_eq!((walk.min_height, walk.max_height), (None, Some(20.5)));
+    assert_eq!(walk.aspect_ratio, Some(1.5));
+}
@@ -2,6 +2,7 @@ use {
     crate::{
         makepad_editor_core::{
             delta::Delta,
+            range::Range,
             text::Text
         },
         makepad_live_id::*,
@@ -49,6 +50,37 @@ pub enum CollabRequest {
     /// given id. If the client was the last participant for the file, this also closes the file on
     /// the collab server.
     CloseFile(TextFileId),
+    /// Requests the collab server to search the files in its file tree for the given query. The
+    /// search runs in the background. Each match is sent back as a `SearchResult` notification,
+    /// followed by a `SearchFinished` notification once the search is done or cancelled.
+    ///
+    /// The `search_id` is chosen by the client, so it can tell the notifications for this search
+    /// apart from those of other searches. Files that are open on the collab server are searched
+    /// as they are on the server, rather than as they are on disk. Files and directories that are
+    /// ignored by a `.gitignore` file are skipped. If `include_globs` is not empty, only files that
+    /// match one of its globs are searched, and files and directories that match one of the
+    /// `exclude_globs` are skipped. Globs without a `/` are matched against the name of a file or
+    /// directory, other globs against its path.
+    SearchWorkspace {
+        search_id: SearchId,
+        query: String,
+        regex: bool,
+        case_sensitive: bool,
+        include_globs: Vec<String>,
+        exclude_globs: Vec<String>,
+    },
+    /// Requests the collab server to stop the search with the given id.
+    CancelSearch(SearchId),
+    /// Requests the collab server to return the git status of the files in its file tree that
+    /// differ from the `HEAD` commit of the repository that contains it.
+    GitStatus,
+    /// Requests the collab server to compare the file with the given path to its contents in the
+    /// `HEAD` commit, and return the lines that differ. If the file is open on the collab server,
+    /// it is compared as it is on the server, rather than as it is on disk.
+    GitDiff(UnixPathBuf),
+    /// Requests the collab server to return which commit last changed each line of the file with
+    /// the given path, as it is on disk.
+    GitBlame(UnixPathBuf),
 }
 
 /// A type for representing either a response or a notification from the collab server.
@@ -74,6 +106,19 @@ pub enum CollabResponse {
     /// The result of requesting the collab server to remove the client as a participant from the
     /// file with the given id.
     CloseFile(Result<TextFileId, CollabError>),
+    /// The result of requesting the collab server to search the files in its file tree. This is
+    /// sent as soon as the search has started.
+    SearchWorkspace(Result<SearchId, CollabError>),
+    /// The result of requesting the collab server to stop a search.
+    CancelSearch(Result<SearchId, CollabError>),
+    /// The result of requesting the collab server to return the git status of its files.
+    GitStatus(Result<Vec<GitFileStatus>, CollabError>),
+    /// The result of requesting the collab server to compare the file with the given path to its
+    /// contents in the `HEAD` commit.
+    GitDiff(UnixPathBuf, Result<Vec<GitDiffHunk>, CollabError>),
+    /// The result of requesting the collab server to blame the file with the given path. The
+    /// result contains an entry for each line of the file.
+    GitBlame(UnixPathBuf, Result<Vec<GitBlameLine>, CollabError>),
 }
 
 /// A type for representing data about a file tree.
@@ -111,6 +156,72 @@ pub enum CollabNotification {
     /// Notifies the client that another client applied the given delta to the file with the given
     /// id. This is only sent for files for which the client is a participant.
     DeltaWasApplied(TextFileId, Delta),
+    /// Notifies the client of a match for the search with the given id.
+    SearchResult(SearchId, SearchResult),
+    /// Notifies the client that the search with the given id has finished, either because all
+    /// files have been searched, or because it was cancelled. No further results are sent for it.
+    SearchFinished(SearchId),
+}
+
+/// A type for representing a match for a search.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct SearchResult {
+    /// The path of the file that contains the match, relative to the root of the file tree.
+    pub path: UnixPathBuf,
+    /// The range of the match in the file. A match never spans more than one line.
+    pub range: Range,
+    /// The contents of the line that contains the match.
+    pub preview: String,
+}
+
+/// A type for representing the git status of a file.
+#[derive(Clone, Debug, Eq, PartialEq, SerBin, DeBin)]
+pub struct GitFileStatus {
+    /// The path of the file, relative to the root of the file tree.
+    pub path: UnixPathBuf,
+    pub status: GitStatus,
+}
+
+/// The ways in which a file can differ from the `HEAD` commit.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
+pub enum GitStatus {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    /// The file has merge conflicts.
+    Conflicted,
+}
+
+/// A type for representing a range of lines that differ from the `HEAD` commit.
+///
+/// The lines are those of the current contents of the file. For a hunk of lines that were
+/// deleted, `start_line` and `end_line` are both the line before which the lines were deleted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, SerBin, DeBin)]
+pub struct GitDiffHunk {
+    pub kind: GitDiffKind,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, SerBin, DeBin)]
+pub enum GitDiffKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A type for representing the commit that last changed a line.
+#[derive(Clone, Debug, Default, PartialEq, SerBin, DeBin)]
+pub struct GitBlameLine {
+    /// The abbreviated hash of the commit. Lines that were not committed yet have a hash of zeros.
+    pub commit: String,
+    pub author: String,
+    /// The time at which the commit was authored, in seconds since the Unix epoch.
+    pub time: i64,
+    /// The first line of the message of the commit.
+    pub summary: String,
 }
 
 /// A type for representing errors from the collab server.
@@ -122,6 +233,11 @@ pub enum CollabError {
     /// Attempted to either apply a delta to, or remove the client as a participant from a file for
     /// which it was not a participant.
     NotAParticipant,
+    /// Attempted to search for a regex that could not be parsed.
+    InvalidSearchQuery(String),
+    /// A git command failed, or the file tree is not inside a git repository. Contains the error
+    /// output of git.
+    Git(String),
     /// Unknown error
     Unknown(String),
 }
@@ -141,3 +257,19 @@ impl DeBin for TextFileId {
         Ok(TextFileId(LiveId(DeBin::de_bin(o, d)?)))
     }
 }
+
+/// An identifier for searches on the collab server.
+#[derive(Clone, Debug, Default, Eq, Hash, Copy, PartialEq, FromLiveId)]
+pub struct SearchId(pub LiveId);
+
+impl SerBin for SearchId {
+    fn ser_bin(&self, s: &mut Vec<u8>) {
+        self.0.0.ser_bin(s);
+    }
+}
+
+impl DeBin for SearchId {
+    fn de_bin(o: &mut usize, d: &[u8]) -> Result<Self, DeBinErr> {
+        Ok(SearchId(LiveId(DeBin::de_bin(o, d)?)))
+    }
+}
@@ -0,0 +1,140 @@
+use {
+    std::{
+        process::{Command, Child, ChildStdin, ChildStdout, ChildStderr, Stdio},
+        sync::mpsc::{self, Receiver, Sender},
+        thread,
+        io::prelude::*,
+        io::{BufReader},
+        str,
+        path::{PathBuf},
+    }
+};
+
+pub struct ChildProcess {
+    pub child: Option<Child>,
+    pub stdin: Option<ChildStdin>,
+    pub stdout: Option<ChildStdout>,
+    pub line_receiver: Receiver<ChildLine>,
+}
+
+pub enum ChildLine {
+    StdOut(String),
+    StdErr(String),
+    Term
+}
+
+impl ChildProcess {
+    
+    pub fn start(cmd: &str, args: &[&str], current_dir: PathBuf, env: &[(&str, &str)]) -> Result<ChildProcess, std::io::Error> {
+        
+        let mut child = Self::spawn(cmd, args, current_dir, env, Stdio::null())?;
+        
+        let (line_sender, line_receiver) = mpsc::channel();
+
+        let stdout = child.stdout.take().expect("stdout cannot be taken!");
+        let stderr = child.stderr.take().expect("stderr cannot be taken!");
+        let _stdout_thread = {
+            let line_sender = line_sender.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stdout);
+                loop{
+                    let mut line = String::new();
+                    if let Ok(len) = reader.read_line(&mut line){
+                        if len == 0{
+                            break
+                        }
+                        if line_sender.send(ChildLine::StdOut(line)).is_err(){
+                            break;
+                        }
+                    }
+                    else{
+                        let _ = line_sender.send(ChildLine::Term);
+                        break;
+                    }
+                }
+            })
+        };
+        Self::spawn_stderr_thread(stderr, line_sender);
+        
+        Ok(ChildProcess {
+            child: Some(child),
+            stdin: None,
+            stdout: None,
+            line_receiver,
+        })
+    }
+    
+    /// Starts a process that we talk to through its stdin and stdout. Unlike `start`, the stdout
+    /// of the process is not split into lines, but left for the caller to read from, since
+    /// protocols that run over stdio usually need the raw bytes. The stderr of the process is
+    /// still sent to the line receiver.
+    pub fn start_piped(cmd: &str, args: &[&str], current_dir: PathBuf, env: &[(&str, &str)]) -> Result<ChildProcess, std::io::Error> {
+        
+        let mut child = Self::spawn(cmd, args, current_dir, env, Stdio::piped())?;
+        
+        let (line_sender, line_receiver) = mpsc::channel();
+        
+        let stdin = child.stdin.take().expect("stdin cannot be taken!");
+        let stdout = child.stdout.take().expect("stdout cannot be taken!");
+        let stderr = child.stderr.take().expect("stderr cannot be taken!");
+        Self::spawn_stderr_thread(stderr, line_sender);
+        
+        Ok(ChildProcess {
+            child: Some(child),
+            stdin: Some(stdin),
+            stdout: Some(stdout),
+            line_receiver,
+        })
+    }
+    
+    fn spawn(cmd: &str, args: &[&str], current_dir: PathBuf, env: &[(&str, &str)], stdin: Stdio) -> Result<Child, std::io::Error> {
+        let mut cmd_build = Command::new(cmd);
+        
+        cmd_build.args(args)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(current_dir);
+        
+        for (key, value) in env {
+            cmd_build.env(key, value);
+        }
+        
+        cmd_build.spawn()
+    }
+    
+    fn spawn_stderr_thread(stderr: ChildStderr, line_sender: Sender<ChildLine>) {
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stderr);
+            loop{
+                let mut line = String::new();
+                if let Ok(len) = reader.read_line(&mut line){
+                    if len == 0{
+                        break
+                    }
+                    if line_sender.send(ChildLine::StdErr(line)).is_err(){
+                        break
+                    };
+                }
+                else{
+                    break;
+                }
+            }
+        });
+    }
+    
+    pub fn wait(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.wait();
+            self.child = None;
+        }
+    }
+    
+    pub fn kill(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+            self.child = None;
+        }
+    }
+}
@@ -14,8 +14,15 @@ use {
             CollabNotification,
             CollabRequest,
             CollabResponse,
+            GitBlameLine,
+            GitDiffHunk,
+            GitFileStatus,
+            SearchId,
+            unix_path::UnixPathBuf,
             unix_str::UnixString,
         },
+        git,
+        search::{self, SearchMatcher},
     },
     std::{ 
         cmp::Ordering,
@@ -24,7 +31,11 @@ use {
         fs,
         mem,
         path::{Path, PathBuf},
-        sync::{Arc, Mutex, RwLock},
+        sync::{
+            atomic::{AtomicBool, Ordering as AtomicOrdering},
+            Arc, Mutex, RwLock,
+        },
+        thread,
     },
 };
 
@@ -64,6 +75,7 @@ impl CollabServer {
             connection_id,
             shared: self.shared.clone(),
             notification_sender,
+            searches: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -76,6 +88,9 @@ pub struct CollabConnection {
     shared: Arc<RwLock<Shared>>,
     // Used to send notifications for this connection.
     notification_sender: Box<dyn NotificationSender>,
+    // A map from search ids to the cancellation flags of the searches that are still running for
+    // this connection.
+    searches: Arc<Mutex<HashMap<SearchId, Arc<AtomicBool>>>>,
 }
 
 impl CollabConnection {
@@ -98,6 +113,31 @@ impl CollabConnection {
                 CollabResponse::ApplyDelta(self.apply_delta(text_file_id, revision, delta))
             }
             CollabRequest::CloseFile(path) => CollabResponse::CloseFile(self.close_file(path)),
+            CollabRequest::SearchWorkspace {
+                search_id,
+                query,
+                regex,
+                case_sensitive,
+                include_globs,
+                exclude_globs,
+            } => CollabResponse::SearchWorkspace(self.search_workspace(
+                search_id,
+                &query,
+                regex,
+                case_sensitive,
+                include_globs,
+                exclude_globs,
+            )),
+            CollabRequest::CancelSearch(search_id) => CollabResponse::CancelSearch(self.cancel_search(search_id)),
+            CollabRequest::GitStatus => CollabResponse::GitStatus(self.git_status()),
+            CollabRequest::GitDiff(path) => {
+                let result = self.git_diff(&path);
+                CollabResponse::GitDiff(path, result)
+            }
+            CollabRequest::GitBlame(path) => {
+                let result = self.git_blame(&path);
+                CollabResponse::GitBlame(path, result)
+            }
         }
     }
     
@@ -184,6 +224,106 @@ impl CollabConnection {
         Ok(FileTreeData {path:"".into(), root})
     }
     
+    // Handles a `SearchWorkspace` request.
+    fn search_workspace(
+        &self,
+        search_id: SearchId,
+        query: &str,
+        regex: bool,
+        case_sensitive: bool,
+        include_globs: Vec<String>,
+        exclude_globs: Vec<String>,
+    ) -> Result<SearchId, CollabError> {
+        let matcher = SearchMatcher::new(query, regex, case_sensitive) ?;
+        
+        // Register the search, so it can be cancelled while it is running.
+        let is_cancelled = Arc::new(AtomicBool::new(false));
+        self.searches.lock().unwrap().insert(search_id, is_cancelled.clone());
+        
+        let shared = self.shared.clone();
+        let searches = self.searches.clone();
+        let notification_sender = self.notification_sender.clone();
+        // Searching can take a while, so we do it on a separate thread, and stream the results
+        // back as notifications.
+        thread::spawn(move || {
+            let root = shared.read().unwrap().path.clone();
+            
+            // Open files are searched as they are on the server, since they might have been
+            // edited.
+            let open_text = | path: &Path | {
+                let shared_guard = shared.read().unwrap();
+                let file_id = *shared_guard.file_ids_by_path.get(path) ?;
+                let text = shared_guard.files[file_id].lock().unwrap().text.to_string();
+                Some(text)
+            };
+            search::search_directory(
+                &root,
+                &matcher,
+                &include_globs,
+                &exclude_globs,
+                &is_cancelled,
+                &open_text,
+                &mut | result | notification_sender.send_notification(CollabNotification::SearchResult(search_id, result)),
+            );
+            
+            // A later search with the same id replaces the entry of this one, so only remove the
+            // entry if it is still ours.
+            let mut searches = searches.lock().unwrap();
+            if searches.get(&search_id).is_some_and( | entry | Arc::ptr_eq(entry, &is_cancelled)) {
+                searches.remove(&search_id);
+            }
+            drop(searches);
+            notification_sender.send_notification(CollabNotification::SearchFinished(search_id));
+        });
+        
+        Ok(search_id)
+    }
+    
+    // Handles a `CancelSearch` request.
+    fn cancel_search(&self, search_id: SearchId) -> Result<SearchId, CollabError> {
+        // If the search is no longer registered, it has already finished, so there is nothing
+        // left to do.
+        if let Some(is_cancelled) = self.searches.lock().unwrap().get(&search_id) {
+            is_cancelled.store(true, AtomicOrdering::Relaxed);
+        }
+        Ok(search_id)
+    }
+    
+    // Handles a `GitStatus` request.
+    fn git_status(&self) -> Result<Vec<GitFileStatus>, CollabError> {
+        let root = self.shared.read().unwrap().path.clone();
+        git::status(&root)
+    }
+    
+    // Handles a `GitDiff` request.
+    fn git_diff(&self, path: &UnixPathBuf) -> Result<Vec<GitDiffHunk>, CollabError> {
+        use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+        
+        let path = PathBuf::from(OsString::from_vec(path.clone().into_unix_string().into_vec()));
+        let shared_guard = self.shared.read().unwrap();
+        let root = shared_guard.path.clone();
+        let full_path = root.join(&path);
+        // Open files are compared as they are on the server, since they might have been edited.
+        let text = match shared_guard.file_ids_by_path.get(&full_path) {
+            Some(&file_id) => shared_guard.files[file_id].lock().unwrap().text.to_string(),
+            None => {
+                let bytes = fs::read(&full_path).map_err( | error | CollabError::Unknown(error.to_string())) ?;
+                String::from_utf8_lossy(&bytes).into_owned()
+            }
+        };
+        drop(shared_guard);
+        git::diff(&root, &path, &text)
+    }
+    
+    // Handles a `GitBlame` request.
+    fn git_blame(&self, path: &UnixPathBuf) -> Result<Vec<GitBlameLine>, CollabError> {
+        use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+        
+        let path = PathBuf::from(OsString::from_vec(path.clone().into_unix_string().into_vec()));
+        let root = self.shared.read().unwrap().path.clone();
+        git::blame(&root, &path)
+    }
+    
     // Handles an `OpenFile` request.
     fn open_file(&self, path: PathBuf) -> Result<(TextFileId, u32, Text), CollabError> {
         // We need to update the list of files in the shared state, so lock it for writing. This is
@@ -241,10 +381,7 @@ impl CollabConnection {
                 // Converts the file contents to a `Text`. This is necessarily a lossy conversion
                 // because `Text` assumes everything is UTF-8 encoded, and this isn't always the
                 // case for files on disk (is this a problem?)
-                let text: Text = Text::from_lines(String::from_utf8_lossy(&bytes)
-                    .lines()
-                    .map( | line | line.chars().collect::<Vec<_ >> ())
-                    .collect::<Vec<_ >>());
+                let text = Text::from(String::from_utf8_lossy(&bytes).as_ref());
                 
                 // Create the list of participants for this file and add the file to it.
                 let mut participants_by_connection_id = HashMap::new();
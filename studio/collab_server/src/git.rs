@@ -0,0 +1,260 @@
+//! Support for reading the state of a git repository, by running the `git` command line tool.
+
+use {
+    crate::{
+        makepad_collab_protocol::{
+            CollabError,
+            GitBlameLine,
+            GitDiffHunk,
+            GitDiffKind,
+            GitFileStatus,
+            GitStatus,
+            unix_path::UnixPathBuf,
+        },
+    },
+    std::{
+        collections::HashMap,
+        path::Path,
+        process::{Command, Stdio},
+    },
+};
+
+/// Returns the status of each file under the directory at `root` that differs from the `HEAD`
+/// commit of the repository that contains it. The paths are relative to `root`.
+pub fn status(root: &Path) -> Result<Vec<GitFileStatus>, CollabError> {
+    // The paths in the output of `git status` are relative to the root of the repository, which
+    // can be an ancestor of `root`.
+    let prefix = git(root, &["rev-parse", "--show-prefix"]) ?;
+    let prefix = prefix.trim_end();
+    let output = git(root, &["status", "--porcelain", "-z", "--untracked-files=all", "--", "."]) ?;
+    Ok(parse_status(&output, prefix))
+}
+
+/// Parses the output of `git status --porcelain -z`, leaving out files that are not under the
+/// directory with the given `prefix`, and making the paths of the other files relative to it.
+pub fn parse_status(output: &str, prefix: &str) -> Vec<GitFileStatus> {
+    let mut statuses = Vec::new();
+    let mut entries = output.split('\0');
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+        let mut code = code.chars();
+        let (x, y) = (code.next().unwrap(), code.next().unwrap());
+        let status = match (x, y) {
+            ('?', '?') => GitStatus::Untracked,
+            ('!', '!') => continue,
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => GitStatus::Conflicted,
+            ('R', _) | ('C', _) => {
+                // the original path of a renamed or copied file follows as a separate entry
+                entries.next();
+                GitStatus::Renamed
+            }
+            ('A', _) => GitStatus::Added,
+            ('D', _) | (_, 'D') => GitStatus::Deleted,
+            _ => GitStatus::Modified,
+        };
+        if let Some(path) = path.strip_prefix(prefix) {
+            statuses.push(GitFileStatus {path: UnixPathBuf::from(path), status});
+        }
+    }
+    statuses
+}
+
+/// Compares the given `text` of the file at `path`, which is relative to `root`, to the contents
+/// of that file in the `HEAD` commit. Files that are not in the `HEAD` commit are compared to an
+/// empty file.
+pub fn diff(root: &Path, path: &Path, text: &str) -> Result<Vec<GitDiffHunk>, CollabError> {
+    let head_text = match git(root, &["show", &format!("HEAD:./{}", path.to_string_lossy())]) {
+        Ok(head_text) => head_text,
+        Err(error) => {
+            // Either the file is new, or there is no repository or `HEAD` commit at all, in which
+            // case there is nothing to compare to.
+            git(root, &["rev-parse", "--verify", "HEAD"]).map_err( | _ | error) ?;
+            String::new()
+        }
+    };
+    // The text on the server has its line breaks normalized, so those of the committed file are
+    // normalized the same way, or every line of a file with CRLF line breaks would differ.
+    let head_text = head_text.replace("\r\n", "\n").replace('\r', "\n");
+    let old_lines: Vec<&str> = if head_text.is_empty() {Vec::new()} else {head_text.split('\n').collect()};
+    let new_lines: Vec<&str> = text.split('\n').collect();
+    Ok(diff_lines(&old_lines, &new_lines))
+}
+
+// The maximum number of lines that `diff_lines` tries to match between the old and the new lines.
+// If they differ by more, all lines between the first and last difference are treated as changed.
+const MAX_EDIT_COUNT: usize = 1000;
+
+/// Returns the ranges of `new_lines` that were added or modified compared to `old_lines`, and the
+/// positions in `new_lines` at which lines of `old_lines` were deleted.
+pub fn diff_lines(old_lines: &[&str], new_lines: &[&str]) -> Vec<GitDiffHunk> {
+    // Lines at the start and end that did not change are skipped, since the lines in between are
+    // usually much fewer.
+    let prefix_len = old_lines.iter().zip(new_lines).take_while( | (old, new) | old == new).count();
+    let suffix_len = old_lines[prefix_len..].iter().rev()
+        .zip(new_lines[prefix_len..].iter().rev())
+        .take_while( | (old, new) | old == new)
+        .count();
+    let old_lines = &old_lines[prefix_len..old_lines.len() - suffix_len];
+    let new_lines = &new_lines[prefix_len..new_lines.len() - suffix_len];
+
+    let (old_is_kept, new_is_kept) = kept_lines(old_lines, new_lines);
+    let mut hunks = Vec::new();
+    let (mut old_index, mut new_index) = (0, 0);
+    while old_index < old_lines.len() || new_index < new_lines.len() {
+        if old_index < old_lines.len() && new_index < new_lines.len() && old_is_kept[old_index] && new_is_kept[new_index] {
+            old_index += 1;
+            new_index += 1;
+            continue;
+        }
+        let start_index = new_index;
+        let mut deleted_count = 0;
+        while old_index < old_lines.len() && !old_is_kept[old_index] {
+            old_index += 1;
+            deleted_count += 1;
+        }
+        while new_index < new_lines.len() && !new_is_kept[new_index] {
+            new_index += 1;
+        }
+        let kind = match (deleted_count, new_index - start_index) {
+            (0, _) => GitDiffKind::Added,
+            (_, 0) => GitDiffKind::Deleted,
+            _ => GitDiffKind::Modified,
+        };
+        hunks.push(GitDiffHunk {
+            kind,
+            start_line: prefix_len + start_index,
+            end_line: prefix_len + new_index,
+        });
+    }
+    hunks
+}
+
+// Returns for each of the `old_lines` and each of the `new_lines` whether it is part of a longest
+// common subsequence of both, using Myers' diff algorithm.
+fn kept_lines(old_lines: &[&str], new_lines: &[&str]) -> (Vec<bool>, Vec<bool>) {
+    let mut old_is_kept = vec![false; old_lines.len()];
+    let mut new_is_kept = vec![false; new_lines.len()];
+    let (old_len, new_len) = (old_lines.len() as isize, new_lines.len() as isize);
+    if old_len == 0 || new_len == 0 {
+        return (old_is_kept, new_is_kept);
+    }
+
+    // For each diagonal `k`, the furthest `x` that has been reached on it with `d` edits. For each
+    // `d`, the part of `xs` for diagonals `-d..=d` is saved, so we can backtrack afterwards.
+    let offset = old_len + new_len + 1;
+    let mut xs = vec![0; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+    let is_down = | xs: &[isize], k: isize, d: isize | {
+        k == -d || k != d && xs[(offset + k - 1) as usize] < xs[(offset + k + 1) as usize]
+    };
+    'outer: for d in 0..=(old_len + new_len) {
+        if d as usize > MAX_EDIT_COUNT {
+            return (old_is_kept, new_is_kept);
+        }
+        trace.push(xs[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if is_down(&xs, k, d) {
+                xs[(offset + k + 1) as usize]
+            } else {
+                xs[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < old_len && y < new_len && old_lines[x as usize] == new_lines[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            xs[(offset + k) as usize] = x;
+            if x >= old_len && y >= new_len {
+                break 'outer;
+            }
+        }
+    }
+
+    let (mut x, mut y) = (old_len, new_len);
+    for (d, window) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        // `window` starts at diagonal `-d`, so translate it back to the layout of `xs`
+        let mut xs = vec![0; 2 * offset as usize + 1];
+        xs[(offset - d) as usize..=(offset + d) as usize].copy_from_slice(window);
+        let k = x - y;
+        let prev_k = if is_down(&xs, k, d) {k + 1} else {k - 1};
+        let prev_x = if d == 0 {0} else {xs[(offset + prev_k) as usize]};
+        let prev_y = if d == 0 {0} else {prev_x - prev_k};
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            old_is_kept[x as usize] = true;
+            new_is_kept[y as usize] = true;
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    (old_is_kept, new_is_kept)
+}
+
+/// Returns the commit that last changed each line of the file at `path`, which is relative to
+/// `root`, as it is on disk.
+pub fn blame(root: &Path, path: &Path) -> Result<Vec<GitBlameLine>, CollabError> {
+    let output = git(root, &["blame", "--porcelain", "--", &path.to_string_lossy()]) ?;
+    Ok(parse_blame(&output))
+}
+
+/// Parses the output of `git blame --porcelain`.
+pub fn parse_blame(output: &str) -> Vec<GitBlameLine> {
+    // The information about each commit is only given for the first line that it changed.
+    let mut commits: HashMap<&str, GitBlameLine> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<&str> = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            // the contents of the line, which ends the entry for it
+            if let Some(hash) = current.take() {
+                lines.push(commits[hash].clone());
+            }
+            continue;
+        }
+        match current {
+            None => {
+                let hash = line.split(' ').next().unwrap_or("");
+                commits.entry(hash).or_insert_with( | | GitBlameLine {
+                    commit: hash.chars().take(8).collect(),
+                    ..GitBlameLine::default()
+                });
+                current = Some(hash);
+            }
+            Some(hash) => {
+                let commit = commits.get_mut(hash).unwrap();
+                let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                match key {
+                    "author" => commit.author = value.to_string(),
+                    "author-time" => commit.time = value.parse().unwrap_or(0),
+                    "summary" => commit.summary = value.to_string(),
+                    _ => {}
+                }
+            }
+        }
+    }
+    lines
+}
+
+// Runs git with the given `args` in the directory at `root`, and returns its output.
+//
+// The output is collected as bytes, since reading stdout and stderr line by line can block once
+// the pipe of the other stream is full, and stops at the first line that is not valid UTF-8. Such
+// bytes, for instance in the contents of a file with another encoding, are replaced instead.
+fn git(root: &Path, args: &[&str]) -> Result<String, CollabError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .output()
+        .map_err( | error | CollabError::Git(error.to_string())) ?;
+    if !output.status.success() {
+        return Err(CollabError::Git(String::from_utf8_lossy(&output.stderr).trim_end().to_string()));
+    }
+    Ok(String::from_utf8(output.stdout)
+        .unwrap_or_else( | error | String::from_utf8_lossy(error.as_bytes()).into_owned()))
+}
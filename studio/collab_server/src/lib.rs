@@ -3,6 +3,13 @@
 pub mod collab_server;
 #[cfg(not(target_arch = "wasm32"))]
 pub use collab_server::*;
+pub mod child_process;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod git;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod regex;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod search;
 
 pub use makepad_micro_serde;
 pub use makepad_editor_core;
@@ -0,0 +1,401 @@
+use std::fmt;
+
+/// A small backtracking regular expression engine, used for searching files.
+///
+/// The supported syntax is a subset of the usual one: literals, `.`, character classes such as
+/// `[a-z_]` and `[^0-9]`, the escapes `\d`, `\w`, `\s` (and their negations), `\b` and `\B`,
+/// the anchors `^` and `$`, groups with `(...)` or `(?:...)`, alternation with `|`, and the
+/// quantifiers `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}`, each of which can be made lazy by
+/// following it with `?`. Groups do not capture.
+///
+/// Regexes are matched against a single line at a time, so `.` matches any character.
+#[derive(Clone, Debug)]
+pub struct Regex {
+    node: Node,
+    case_sensitive: bool,
+}
+
+impl Regex {
+    /// Parses the given `pattern` into a regex.
+    pub fn new(pattern: &str, case_sensitive: bool) -> Result<Regex, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            position: 0,
+        };
+        let node = parser.parse_alternation() ?;
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unmatched `)`"));
+        }
+        Ok(Regex {node, case_sensitive})
+    }
+
+    /// Returns the ranges of all non-overlapping, non-empty matches in the given `chars`, as pairs
+    /// of start and end indices.
+    pub fn find_all(&self, chars: &[char]) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start <= chars.len() {
+            match self.find_at(chars, start) {
+                Some(end) => {
+                    matches.push((start, end));
+                    start = end;
+                }
+                _ => start += 1,
+            }
+        }
+        matches
+    }
+
+    // Returns the end of the preferred non-empty match that starts at `start`, if any.
+    fn find_at(&self, chars: &[char], start: usize) -> Option<usize> {
+        let mut end = None;
+        self.match_node(&self.node, chars, start, &mut | position | {
+            if position == start {
+                return false;
+            }
+            end = Some(position);
+            true
+        });
+        end
+    }
+
+    // Matches `node` at `position`, and calls `next` with each position at which the match could
+    // end, in order of preference, until it returns `true`.
+    fn match_node(&self, node: &Node, chars: &[char], position: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        match node {
+            Node::Empty => next(position),
+            Node::Char(ch) => match chars.get(position) {
+                Some(other) if self.chars_eq(*ch, *other) => next(position + 1),
+                _ => false,
+            },
+            Node::Any => position < chars.len() && next(position + 1),
+            Node::Class(class) => match chars.get(position) {
+                Some(ch) if self.class_contains(class, *ch) => next(position + 1),
+                _ => false,
+            },
+            Node::Start => position == 0 && next(position),
+            Node::End => position == chars.len() && next(position),
+            Node::WordBoundary(is_boundary) => {
+                is_word_boundary(chars, position) == *is_boundary && next(position)
+            }
+            Node::Concat(nodes) => self.match_concat(nodes, chars, position, next),
+            Node::Alternation(nodes) => nodes.iter().any( | node | self.match_node(node, chars, position, next)),
+            Node::Repeat(repeat) => self.match_repeat(repeat, 0, chars, position, next),
+        }
+    }
+
+    fn match_concat(&self, nodes: &[Node], chars: &[char], position: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        match nodes.split_first() {
+            Some((node, rest)) => self.match_node(node, chars, position, &mut | position | {
+                self.match_concat(rest, chars, position, next)
+            }),
+            None => next(position),
+        }
+    }
+
+    fn match_repeat(&self, repeat: &Repeat, count: usize, chars: &[char], position: usize, next: &mut dyn FnMut(usize) -> bool) -> bool {
+        let can_stop = count >= repeat.min;
+        let can_continue = repeat.max.is_none_or( | max | count < max);
+        let match_more = | next: &mut dyn FnMut(usize) -> bool | {
+            can_continue && self.match_node(&repeat.node, chars, position, &mut | new_position | {
+                // Once we have matched the minimum number of times, stop repeating a node that
+                // matches the empty string, or we would never stop.
+                (new_position != position || !can_stop)
+                    && self.match_repeat(repeat, count + 1, chars, new_position, next)
+            })
+        };
+        // A greedy repetition tries to match once more before it tries to stop, and a lazy one
+        // the other way around.
+        if repeat.is_greedy && match_more(next) {
+            return true;
+        }
+        if can_stop && next(position) {
+            return true;
+        }
+        !repeat.is_greedy && match_more(next)
+    }
+
+    fn chars_eq(&self, a: char, b: char) -> bool {
+        a == b || !self.case_sensitive && a.to_lowercase().eq(b.to_lowercase())
+    }
+
+    fn class_contains(&self, class: &Class, ch: char) -> bool {
+        let contains = | ch: char | class.items.iter().any( | item | item.contains(ch));
+        let is_match = contains(ch) || !self.case_sensitive && (
+            ch.to_lowercase().any(contains) || ch.to_uppercase().any(contains)
+        );
+        is_match != class.is_negated
+    }
+}
+
+/// An error that occurred while parsing a regex.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegexError {
+    /// The index of the character in the pattern at which the error occurred.
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat(Box<Repeat>),
+}
+
+#[derive(Clone, Debug)]
+struct Repeat {
+    node: Node,
+    min: usize,
+    max: Option<usize>,
+    is_greedy: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Class {
+    items: Vec<ClassItem>,
+    is_negated: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn contains(&self, ch: char) -> bool {
+        match *self {
+            ClassItem::Range(start, end) => start <= ch && ch <= end,
+            ClassItem::Digit(is_positive) => ch.is_ascii_digit() == is_positive,
+            ClassItem::Word(is_positive) => is_word_char(ch) == is_positive,
+            ClassItem::Space(is_positive) => ch.is_whitespace() == is_positive,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).cloned()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.position += 1;
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    fn error(&self, message: &'static str) -> RegexError {
+        RegexError {position: self.position, message}
+    }
+
+    fn parse_alternation(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = vec![self.parse_concat() ?];
+        while self.eat('|') {
+            nodes.push(self.parse_concat() ?);
+        }
+        Ok(if nodes.len() == 1 {nodes.pop().unwrap()} else {Node::Alternation(nodes)})
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(ch) = self.peek() {
+            if ch == '|' || ch == ')' {
+                break;
+            }
+            nodes.push(self.parse_repeat() ?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn parse_repeat(&mut self) -> Result<Node, RegexError> {
+        let mut node = self.parse_atom() ?;
+        loop {
+            let (min, max) = match self.peek() {
+                Some('{') => match self.parse_counts() {
+                    Some(counts) => counts,
+                    None => break,
+                },
+                Some(ch @ '*') | Some(ch @ '+') | Some(ch @ '?') => {
+                    self.position += 1;
+                    match ch {
+                        '*' => (0, None),
+                        '+' => (1, None),
+                        _ => (0, Some(1)),
+                    }
+                }
+                _ => break,
+            };
+            if let Node::Start | Node::End | Node::WordBoundary(_) = node {
+                return Err(self.error("cannot repeat an anchor"));
+            }
+            let is_greedy = !self.eat('?');
+            node = Node::Repeat(Box::new(Repeat {node, min, max, is_greedy}));
+        }
+        Ok(node)
+    }
+
+    // Parses a counted quantifier such as `{2,5}`. If what follows is not a valid counted
+    // quantifier, nothing is consumed, and the `{` is treated as a literal instead.
+    fn parse_counts(&mut self) -> Option<(usize, Option<usize>)> {
+        let end = self.chars[self.position..].iter().position( | ch | *ch == '}') ? + self.position;
+        let inner: String = self.chars[self.position + 1..end].iter().collect();
+        let (min, max) = match inner.split_once(',') {
+            Some((min, "")) => (min.parse().ok() ?, None),
+            Some((min, max)) => (min.parse().ok() ?, Some(max.parse().ok() ?)),
+            None => {
+                let count = inner.parse().ok() ?;
+                (count, Some(count))
+            }
+        };
+        if max.is_some_and( | max | max < min) {
+            return None;
+        }
+        self.position = end + 1;
+        Some((min, max))
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, RegexError> {
+        let ch = self.peek().unwrap();
+        self.position += 1;
+        Ok(match ch {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err(self.error("unsupported group syntax"));
+                }
+                let node = self.parse_alternation() ?;
+                if !self.eat(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                node
+            }
+            '[' => Node::Class(self.parse_class() ?),
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '*' | '+' | '?' => return Err(self.error("nothing to repeat")),
+            '\\' => match self.parse_escape() ? {
+                Escape::Char(ch) => Node::Char(ch),
+                Escape::Item(item) => Node::Class(Class {items: vec![item], is_negated: false}),
+                Escape::WordBoundary(is_boundary) => Node::WordBoundary(is_boundary),
+            },
+            ch => Node::Char(ch),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Class, RegexError> {
+        let is_negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut is_first = true;
+        loop {
+            let ch = match self.peek() {
+                Some(ch) => ch,
+                None => return Err(self.error("unclosed character class")),
+            };
+            self.position += 1;
+            if ch == ']' && !is_first {
+                break;
+            }
+            is_first = false;
+            let start = match ch {
+                '\\' => match self.parse_escape() ? {
+                    Escape::Char(ch) => ch,
+                    Escape::Item(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                    Escape::WordBoundary(_) => return Err(self.error("word boundary in character class")),
+                },
+                ch => ch,
+            };
+            let end = if self.peek() == Some('-') && self.chars.get(self.position + 1).is_some_and( | ch | *ch != ']') {
+                self.position += 1;
+                let ch = self.peek().unwrap();
+                self.position += 1;
+                match ch {
+                    '\\' => match self.parse_escape() ? {
+                        Escape::Char(ch) => ch,
+                        _ => return Err(self.error("invalid range in character class")),
+                    },
+                    ch => ch,
+                }
+            }
+            else {
+                start
+            };
+            if end < start {
+                return Err(self.error("invalid range in character class"));
+            }
+            items.push(ClassItem::Range(start, end));
+        }
+        Ok(Class {items, is_negated})
+    }
+
+    // Parses the part of an escape sequence after the `\`.
+    fn parse_escape(&mut self) -> Result<Escape, RegexError> {
+        let ch = match self.peek() {
+            Some(ch) => ch,
+            None => return Err(self.error("unfinished escape sequence")),
+        };
+        self.position += 1;
+        Ok(match ch {
+            'd' => Escape::Item(ClassItem::Digit(true)),
+            'D' => Escape::Item(ClassItem::Digit(false)),
+            'w' => Escape::Item(ClassItem::Word(true)),
+            'W' => Escape::Item(ClassItem::Word(false)),
+            's' => Escape::Item(ClassItem::Space(true)),
+            'S' => Escape::Item(ClassItem::Space(false)),
+            'b' => Escape::WordBoundary(true),
+            'B' => Escape::WordBoundary(false),
+            'n' => Escape::Char('\n'),
+            'r' => Escape::Char('\r'),
+            't' => Escape::Char('\t'),
+            ch if ch.is_ascii_alphanumeric() => return Err(self.error("unknown escape sequence")),
+            ch => Escape::Char(ch),
+        })
+    }
+}
+
+enum Escape {
+    Char(char),
+    Item(ClassItem),
+    WordBoundary(bool),
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+fn is_word_boundary(chars: &[char], position: usize) -> bool {
+    let before = position > 0 && is_word_char(chars[position - 1]);
+    let after = position < chars.len() && is_word_char(chars[position]);
+    before != after
+}
@@ -0,0 +1,360 @@
+use {
+    crate::{
+        makepad_editor_core::{
+            position::Position,
+            range::Range,
+            text::Text,
+        },
+        makepad_collab_protocol::{
+            CollabError,
+            SearchResult,
+            unix_path::UnixPathBuf,
+        },
+        regex::Regex,
+    },
+    std::{
+        fs,
+        path::Path,
+        sync::atomic::{AtomicBool, Ordering},
+    },
+};
+
+/// A type for finding the matches for a search query in a line of text.
+#[derive(Clone, Debug)]
+pub enum SearchMatcher {
+    Literal {
+        chars: Vec<char>,
+        case_sensitive: bool,
+    },
+    Regex(Regex),
+}
+
+impl SearchMatcher {
+    /// Creates a matcher for the given `query`, which is interpreted as a regex if `regex` is
+    /// `true`, and as literal text otherwise.
+    pub fn new(query: &str, regex: bool, case_sensitive: bool) -> Result<SearchMatcher, CollabError> {
+        if regex {
+            Regex::new(query, case_sensitive)
+                .map(SearchMatcher::Regex)
+                .map_err( | error | CollabError::InvalidSearchQuery(error.to_string()))
+        }
+        else {
+            Ok(SearchMatcher::Literal {
+                chars: query.chars().collect(),
+                case_sensitive,
+            })
+        }
+    }
+
+    /// Returns the ranges of all non-overlapping, non-empty matches in the given `line`, as pairs
+    /// of start and end indices.
+    pub fn find_all(&self, line: &[char]) -> Vec<(usize, usize)> {
+        match self {
+            SearchMatcher::Literal {chars, case_sensitive} => {
+                let mut matches = Vec::new();
+                if chars.is_empty() {
+                    return matches;
+                }
+                let mut start = 0;
+                while start + chars.len() <= line.len() {
+                    let is_match = line[start..start + chars.len()].iter().zip(chars).all( | (a, b) | {
+                        a == b || !case_sensitive && a.to_lowercase().eq(b.to_lowercase())
+                    });
+                    if is_match {
+                        matches.push((start, start + chars.len()));
+                        start += chars.len();
+                    }
+                    else {
+                        start += 1;
+                    }
+                }
+                matches
+            }
+            SearchMatcher::Regex(regex) => regex.find_all(line),
+        }
+    }
+}
+
+/// Returns whether the given `path`, which is relative to the root of the file tree, matches the
+/// given `glob`.
+///
+/// A glob without a `/` is matched against the last component of the path, and a glob with a
+/// `/` against the whole path, ignoring a leading `/`. In a glob, `*` matches any sequence of
+/// characters other than `/`, `**` matches any sequence of characters, `?` matches any single
+/// character other than `/`, `[...]` and `[!...]` match a single character in or not in a set,
+/// and `{a,b}` matches either of the alternatives.
+pub fn path_matches_glob(glob: &str, path: &str) -> bool {
+    if glob.contains('/') {
+        glob_matches(glob.trim_start_matches('/'), path)
+    }
+    else {
+        glob_matches(glob, &path[path.rfind('/').map_or(0, | index | index + 1)..])
+    }
+}
+
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    expand_braces(glob).iter().any( | glob | {
+        let glob: Vec<char> = glob.chars().collect();
+        glob_matches_chars(&glob, &text)
+    })
+}
+
+// Expands the first `{a,b}` in `glob` into one glob for each alternative, recursively.
+fn expand_braces(glob: &str) -> Vec<String> {
+    let open = match glob.find('{') {
+        Some(open) => open,
+        None => return vec![String::from(glob)],
+    };
+    let close = match glob[open..].find('}') {
+        Some(close) => open + close,
+        None => return vec![String::from(glob)],
+    };
+    glob[open + 1..close]
+        .split(',')
+        .flat_map( | alternative | expand_braces(&format!("{}{}{}", &glob[..open], alternative, &glob[close + 1..])))
+        .collect()
+}
+
+fn glob_matches_chars(glob: &[char], text: &[char]) -> bool {
+    match glob.first() {
+        None => text.is_empty(),
+        Some('*') if glob.get(1) == Some(&'*') => {
+            let rest = &glob[2..];
+            // `**/` also matches no directories at all
+            rest.first() == Some(&'/') && glob_matches_chars(&rest[1..], text)
+                || (0..=text.len()).any( | index | glob_matches_chars(rest, &text[index..]))
+        }
+        Some('*') => {
+            let len = text.iter().position( | ch | *ch == '/').unwrap_or(text.len());
+            (0..=len).any( | index | glob_matches_chars(&glob[1..], &text[index..]))
+        }
+        Some('?') => match text.first() {
+            Some(ch) if *ch != '/' => glob_matches_chars(&glob[1..], &text[1..]),
+            _ => false,
+        },
+        Some('[') => {
+            let close = match glob.iter().skip(2).position( | ch | *ch == ']') {
+                Some(close) => close + 2,
+                None => return text.first() == Some(&'[') && glob_matches_chars(&glob[1..], &text[1..]),
+            };
+            let ch = match text.first() {
+                Some(ch) if *ch != '/' => *ch,
+                _ => return false,
+            };
+            let (is_negated, set) = match glob[1] {
+                '!' | '^' => (true, &glob[2..close]),
+                _ => (false, &glob[1..close]),
+            };
+            let mut is_match = false;
+            let mut index = 0;
+            while index < set.len() {
+                if index + 2 < set.len() && set[index + 1] == '-' {
+                    is_match |= set[index] <= ch && ch <= set[index + 2];
+                    index += 3;
+                }
+                else {
+                    is_match |= set[index] == ch;
+                    index += 1;
+                }
+            }
+            is_match != is_negated && glob_matches_chars(&glob[close + 1..], &text[1..])
+        }
+        Some('\\') if glob.len() > 1 => {
+            text.first() == Some(&glob[1]) && glob_matches_chars(&glob[2..], &text[1..])
+        }
+        Some(ch) => text.first() == Some(ch) && glob_matches_chars(&glob[1..], &text[1..]),
+    }
+}
+
+/// The rules in a `.gitignore` file.
+#[derive(Clone, Debug)]
+pub struct GitIgnore {
+    // The path of the directory that contains the file, relative to the root of the file tree.
+    // This is either empty, or ends with a `/`.
+    base: String,
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Clone, Debug)]
+struct IgnoreRule {
+    glob: String,
+    is_negated: bool,
+    is_dir_only: bool,
+}
+
+impl GitIgnore {
+    /// Parses the `contents` of a `.gitignore` file in the directory with the given `base` path,
+    /// relative to the root of the file tree.
+    pub fn parse(base: &str, contents: &str) -> GitIgnore {
+        let rules = contents.lines().filter_map( | line | {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (is_negated, line) = match line.strip_prefix('!') {
+                Some(line) => (true, line),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (is_dir_only, line) = match line.strip_suffix('/') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            // a rule with a `/` anywhere but at the end is relative to the `.gitignore` file
+            let glob = if line.contains('/') {
+                format!("/{}", line.trim_start_matches('/'))
+            }
+            else {
+                String::from(line)
+            };
+            Some(IgnoreRule {glob, is_negated, is_dir_only})
+        }).collect();
+        GitIgnore {
+            base: if base.is_empty() {String::new()} else {format!("{}/", base.trim_end_matches('/'))},
+            rules,
+        }
+    }
+
+    /// Returns whether the file or directory with the given `path`, relative to the root of the
+    /// file tree, is ignored or explicitly not ignored by these rules, or `None` if no rule applies
+    /// to it. Later rules take precedence over earlier ones.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> Option<bool> {
+        let path = path.strip_prefix(self.base.as_str()) ?;
+        self.rules.iter().rev().find( | rule | {
+            (is_dir || !rule.is_dir_only) && path_matches_glob(&rule.glob, path)
+        }).map( | rule | !rule.is_negated)
+    }
+}
+
+/// Searches the files in the directory with the given `root` path for matches of `matcher`, and
+/// calls `send_result` with each match, in the order in which the files appear in the file tree.
+///
+/// Hidden files and directories, and directories called `target`, are skipped, just like they are
+/// in the file tree, as are files that look like binary files. See `CollabRequest::SearchWorkspace`
+/// for how `.gitignore` files and the globs are used. The `open_text` callback is called with the
+/// path of each file, and should return the contents of the file if it is open on the collab
+/// server. The search stops early once `is_cancelled` is set.
+pub fn search_directory(
+    root: &Path,
+    matcher: &SearchMatcher,
+    include_globs: &[String],
+    exclude_globs: &[String],
+    is_cancelled: &AtomicBool,
+    open_text: &dyn Fn(&Path) -> Option<String>,
+    send_result: &mut dyn FnMut(SearchResult),
+) {
+    let mut searcher = Searcher {
+        matcher,
+        include_globs,
+        exclude_globs,
+        is_cancelled,
+        open_text,
+        send_result,
+        git_ignores: Vec::new(),
+    };
+    searcher.search_directory(root, "");
+}
+
+struct Searcher<'a> {
+    matcher: &'a SearchMatcher,
+    include_globs: &'a [String],
+    exclude_globs: &'a [String],
+    is_cancelled: &'a AtomicBool,
+    open_text: &'a dyn Fn(&Path) -> Option<String>,
+    send_result: &'a mut dyn FnMut(SearchResult),
+    // The rules of the `.gitignore` files in the directory we are searching and its ancestors.
+    git_ignores: Vec<GitIgnore>,
+}
+
+impl<'a> Searcher<'a> {
+    fn search_directory(&mut self, path: &Path, relative_path: &str) {
+        let git_ignore = fs::read_to_string(path.join(".gitignore")).ok().map( | contents | {
+            GitIgnore::parse(relative_path, &contents)
+        });
+        let has_git_ignore = git_ignore.is_some();
+        self.git_ignores.extend(git_ignore);
+
+        let mut entries: Vec<_> = match fs::read_dir(path) {
+            Ok(entries) => entries.filter_map( | entry | {
+                let entry = entry.ok() ?;
+                // Skip over entries with a non UTF-8 file name.
+                let name = entry.file_name().into_string().ok() ?;
+                Some((entry.path().is_dir(), name, entry.path()))
+            }).collect(),
+            Err(_) => Vec::new(),
+        };
+        // Visit the entries in the same order as the file tree: directories first, then files,
+        // both sorted by name.
+        entries.sort_by( | a, b | b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        for (is_dir, name, entry_path) in entries {
+            if self.is_cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            if name.starts_with('.') || is_dir && name == "target" {
+                continue;
+            }
+            let entry_relative_path = if relative_path.is_empty() {
+                name
+            }
+            else {
+                format!("{}/{}", relative_path, name)
+            };
+            if self.is_ignored(&entry_relative_path, is_dir) {
+                continue;
+            }
+            if is_dir {
+                self.search_directory(&entry_path, &entry_relative_path);
+            }
+            else if entry_path.is_file() && self.is_included(&entry_relative_path) {
+                self.search_file(&entry_path, &entry_relative_path);
+            }
+        }
+
+        if has_git_ignore {
+            self.git_ignores.pop();
+        }
+    }
+
+    fn is_ignored(&self, relative_path: &str, is_dir: bool) -> bool {
+        self.exclude_globs.iter().any( | glob | path_matches_glob(glob, relative_path))
+            || self.git_ignores.iter().rev().find_map( | git_ignore | {
+                git_ignore.is_ignored(relative_path, is_dir)
+            }).unwrap_or(false)
+    }
+
+    fn is_included(&self, relative_path: &str) -> bool {
+        self.include_globs.is_empty()
+            || self.include_globs.iter().any( | glob | path_matches_glob(glob, relative_path))
+    }
+
+    fn search_file(&mut self, path: &Path, relative_path: &str) {
+        let text = match (self.open_text)(path) {
+            Some(text) => text,
+            None => match fs::read(path) {
+                // Skip over files that look like they are binary.
+                Ok(bytes) if !bytes.iter().take(8192).any( | byte | *byte == 0) => {
+                    String::from_utf8_lossy(&bytes).into_owned()
+                }
+                _ => return,
+            },
+        };
+        // Lines are split the same way as in a `Text`, so that the positions of the results are the
+        // positions in the text of the file once it is opened.
+        let text = Text::from(text);
+        for line in 0..text.line_count() {
+            let chars: Vec<char> = text.line(line).chars().collect();
+            let line_text: String = chars.iter().collect();
+            for (start, end) in self.matcher.find_all(&chars) {
+                (self.send_result)(SearchResult {
+                    path: UnixPathBuf::from(relative_path),
+                    range: Range {
+                        start: Position {line, column: start},
+                        end: Position {line, column: end},
+                    },
+                    preview: line_text.clone(),
+                });
+            }
+        }
+    }
+}
@@ -41,7 +41,7 @@ live_register!{
         fn get_color(self) -> vec4 {
             return mix(
                 mix(
-                    COLOR_TEXT_DEFAULT * self.scale,
+                    mix(COLOR_TEXT_DEFAULT, self.status_color, self.has_status) * self.scale,
                     COLOR_TEXT_SELECTED,
                     self.selected
                 ),
@@ -209,6 +209,8 @@ struct DrawBgQuad {
 #[derive(Live, LiveHook)]#[repr(C)]
 struct DrawNameText {
     draw_super: DrawText,
+    status_color: Vec4,
+    has_status: f32,
     is_even: f32,
     scale: f32,
     is_folder: f32,
@@ -309,6 +311,13 @@ impl FileTreeNode {
         self.name_text.font_scale = scale;
     }
     
+    /// Sets the color of the name of the node, which is used instead of the default text color to
+    /// show the status of the node, such as whether it was modified.
+    pub fn set_status_color(&mut self, status_color: Option<Vec4>) {
+        self.name_text.has_status = if status_color.is_some() {1.0} else {0.0};
+        self.name_text.status_color = status_color.unwrap_or_default();
+    }
+    
     pub fn draw_folder(&mut self, cx: &mut Cx2d, name: &str, is_even: f32, node_height: f32, depth: usize, scale: f32) {
         self.set_draw_state(is_even, scale);
         
@@ -344,6 +353,7 @@ impl FileTreeNode {
                 right: depth as f32 * 4.0,
                 bottom: 0.0,
             },
+            ..Walk::default()
         }
     }
     
@@ -455,6 +465,7 @@ impl FileTree {
         cx: &mut Cx2d,
         node_id: FileNodeId,
         name: &str,
+        status_color: Option<Vec4>,
     ) -> Result<(), ()> {
         let scale = self.stack.last().cloned().unwrap_or(1.0);
         
@@ -474,6 +485,7 @@ impl FileTree {
                 (tree_node, id!(folder_node))
             });
             
+            tree_node.set_status_color(status_color);
             tree_node.draw_folder(cx, name, Self::is_even(self.count), self.node_height, self.stack.len(), scale);
             self.stack.push(tree_node.opened * scale);
             if tree_node.opened == 0.0 {
@@ -496,7 +508,7 @@ impl FileTree {
         self.stack.pop();
     }
     
-    pub fn file(&mut self, cx: &mut Cx2d, node_id: FileNodeId, name: &str) {
+    pub fn file(&mut self, cx: &mut Cx2d, node_id: FileNodeId, name: &str, status_color: Option<Vec4>) {
         let scale = self.stack.last().cloned().unwrap_or(1.0);
         
         if scale > 0.2 {
@@ -507,6 +519,7 @@ impl FileTree {
             let (tree_node, _) = self.tree_nodes.get_or_insert(cx, node_id, | cx | {
                 (FileTreeNode::new_from_ptr(cx, file_node), id!(file_node))
             });
+            tree_node.set_status_color(status_color);
             tree_node.draw_file(cx, name, Self::is_even(self.count), self.node_height, self.stack.len(), scale);
         }
     }
@@ -515,6 +528,10 @@ impl FileTree {
         self.tree_nodes.clear();
     }
     
+    pub fn open_folder_ids(&self) -> impl Iterator<Item = FileNodeId> + '_ {
+        self.open_nodes.iter().cloned()
+    }
+    
     pub fn forget_node(&mut self, file_node_id: FileNodeId) {
         self.tree_nodes.remove(&file_node_id);
     }
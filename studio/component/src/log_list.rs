@@ -480,7 +480,7 @@ impl LogList {
         &mut self,
         cx: &mut Cx,
         event: &mut Event,
-        _dispatch_action: &mut dyn FnMut(&mut Cx, LogListAction),
+        dispatch_action: &mut dyn FnMut(&mut Cx, LogListAction),
     ) {
         if self.scroll_view.handle_event(cx, event) {
             self.scroll_view.redraw(cx);
@@ -508,7 +508,7 @@ impl LogList {
                     }
                     self.selected_node_ids.clear();
                     self.selected_node_ids.insert(node_id);
-                    //dispatch_action(cx, FileTreeAction::WasClicked(node_id));
+                    dispatch_action(cx, LogListAction::WasClicked(node_id));
                 }
                 LogNodeAction::ShouldStartDragging => {
                     //if self.dragging_node_id.is_none() {
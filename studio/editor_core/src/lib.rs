@@ -6,6 +6,7 @@ pub mod text;
 pub mod position_set;
 pub mod range_set;
 pub use makepad_micro_serde;
+pub use makepad_collections;
 
 pub use delta::*;
 pub use position::*;
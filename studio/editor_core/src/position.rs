@@ -11,7 +11,7 @@ use {
 };
 
 /// A type for representing a position in a text.
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, SerBin, DeBin, SerRon, DeRon)]
 pub struct Position {
     pub line: usize,
     pub column: usize,
@@ -44,6 +44,10 @@ impl Position {
     }
 
     /// Applies the given delta to this position.
+    ///
+    /// Text that is inserted at this position is inserted after it, so the position stays in
+    /// front of the inserted text. Edit operations rely on this to move the cursors past the text
+    /// they insert with an explicit offset.
     pub fn apply_delta(&mut self, delta: &Delta) -> Position {
         let mut position = Position::origin();
         let mut distance = *self - Position::origin();
@@ -61,6 +65,11 @@ impl Position {
                         break position + distance;
                     }
                 },
+                // A retain that reaches this position stops before an insert that follows it, but
+                // at the origin there is no retain, so an insert there is checked for separately.
+                Some(OperationSpan::Insert(_)) if distance.is_zero() => {
+                    break position;
+                }
                 Some(OperationSpan::Insert(count)) => {
                     position += count;
                     operation_span_slot = operation_span_iter.next();
@@ -2,28 +2,31 @@ use crate::makepad_micro_serde::{DeBin, DeBinErr, SerBin};
 use {
     crate::{
         delta::{Delta, Operation},
+        makepad_collections::{btree_string::Slice, BTreeString},
         position::Position,
         range::Range,
         size::Size,
     },
-    std::{fmt, iter, mem, ops::AddAssign},
+    std::{
+        borrow::Cow,
+        fmt,
+        hash::{Hash, Hasher},
+        iter, mem,
+        ops::AddAssign,
+    },
 };
 
 /// A type for representing text.
 ///
-/// A text is structured as a vec of lines, where each line is a vec of chars. This is not a very
-/// compact representation, since most characters are expected to be ASCII, and thus take up only
-/// a single byte, whereas a char always takes up 4 bytes. This means that for a typical text, up
-/// to 75% of memory it uses is wasted. However, this representation is very convenient to work
-/// with, because it closely matches the structure expected by most operations performed on a text.
-/// Since large texts are relatively rare, we've thus decided to accept this inefficiency for now.
-/// In the future, we might want to replace this representation with a more efficient one, such as
-/// an UTF-8 rope.
+/// A text is stored as a UTF-8 B-tree string, so that inserting and deleting text, and converting
+/// between positions and byte offsets, takes logarithmic time in the size of the text. Positions
+/// are expressed as a line and a column, where the column counts chars.
 ///
-/// A text maintains the invariant that it always contains at least one (possibly empty) line.
-#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin)]
+/// Line breaks are normalized to `'\n'` when a text is created, so that each line break is always
+/// a single char. A text always contains at least one (possibly empty) line.
+#[derive(Clone)]
 pub struct Text {
-    lines: Vec<Vec<char>>,
+    string: BTreeString,
 }
 
 impl Text {
@@ -47,7 +50,14 @@ impl Text {
     /// Panics if the vec is empty.
     pub fn from_lines(lines: Vec<Vec<char>>) -> Text {
         assert!(!lines.is_empty());
-        Text { lines }
+        let mut string = String::new();
+        let mut sep = "";
+        for line in lines {
+            string.push_str(sep);
+            string.extend(line);
+            sep = "\n";
+        }
+        Text::from(string)
     }
 
     /// Returns `true` if this text is empty.
@@ -77,24 +87,115 @@ impl Text {
     /// assert!(text.len() == Size { line: 1, column: 3 });
     /// ```
     pub fn len(&self) -> Size {
+        let line_count = self.line_count();
         Size {
-            line: self.lines.len() - 1,
-            column: self.lines.last().unwrap().len(),
+            line: line_count - 1,
+            column: self.line_len(line_count - 1),
         }
     }
 
-    /// Returns a slice of the lines in this text.
+    /// Returns the number of lines in this text.
     /// 
     /// # Examples
     /// 
     /// ```
-    /// use makepad_studio::code_editor::{Size, Text};
+    /// use makepad_studio::code_editor::Text;
+    /// 
+    /// let text = Text::from("abc\ndef\n");
+    /// assert_eq!(text.line_count(), 3);
+    /// ```
+    pub fn line_count(&self) -> usize {
+        self.string.line_count()
+    }
+
+    /// Returns the length in chars of the line with the given index, not including the line break.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the index is out of bounds.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use makepad_studio::code_editor::Text;
+    /// 
+    /// let text = Text::from("abc\nd😀f");
+    /// assert_eq!(text.line_len(1), 3);
+    /// ```
+    pub fn line_len(&self, index: usize) -> usize {
+        let range = self.string.line_range(index);
+        self.string.char_count_at(range.end) - self.string.char_count_at(range.start)
+    }
+
+    /// Returns the line with the given index, not including the line break.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the index is out of bounds.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use makepad_studio::code_editor::Text;
     /// 
     /// let text = Text::from("abc\ndef");
-    /// assert_eq!(text.as_lines(), &[vec!['a', 'b', 'c'], vec!['d', 'e', 'f']]);
+    /// assert_eq!(text.line(1).chars().collect::<String>(), "def");
     /// ```
-    pub fn as_lines(&self) -> &[Vec<char>] {
-        &self.lines
+    pub fn line(&self, index: usize) -> Slice<'_> {
+        self.string.line(index)
+    }
+
+    /// Returns an iterator over the lengths in chars of the lines in this text.
+    /// 
+    /// This walks over the text once, so it is faster than calling `line_len` for each line.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use makepad_studio::code_editor::Text;
+    /// 
+    /// let text = Text::from("abc\n\nd😀");
+    /// assert_eq!(text.line_lens().collect::<Vec<_>>(), [3, 0, 2]);
+    /// ```
+    pub fn line_lens(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut chars = self.string.chars();
+        let mut is_done = false;
+        iter::from_fn(move || {
+            if is_done {
+                return None;
+            }
+            let mut len = 0;
+            loop {
+                match chars.next() {
+                    Some('\n') => break Some(len),
+                    Some(_) => len += 1,
+                    None => {
+                        is_done = true;
+                        break Some(len);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Returns the byte offset of the given position in this text.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if the position is out of bounds.
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use makepad_studio::code_editor::{Position, Text};
+    /// 
+    /// let text = Text::from("abc\nd😀f");
+    /// assert_eq!(text.position_to_byte(Position { line: 1, column: 2 }), 9);
+    /// ```
+    pub fn position_to_byte(&self, position: Position) -> usize {
+        assert!(position.column <= self.line_len(position.line));
+        let line_start = self.string.line_to_byte(position.line);
+        self.string.char_to_byte(self.string.char_count_at(line_start) + position.column)
     }
 
     /// Copies the given range from this text into a new text.
@@ -120,36 +221,12 @@ impl Text {
     /// );
     /// ```
     pub fn copy(&self, range: Range) -> Text {
-        Text {
-            lines: if range.start.line == range.end.line {
-                vec![
-                    self.lines[range.start.line][range.start.column..range.end.column]
-                        .iter()
-                        .cloned()
-                        .collect::<Vec<_>>(),
-                ]
-            } else {
-                let mut lines = Vec::with_capacity(range.end.line - range.start.line + 1);
-                lines.push(
-                    self.lines[range.start.line][range.start.column..]
-                        .iter()
-                        .cloned()
-                        .collect::<Vec<_>>(),
-                );
-                lines.extend(
-                    self.lines[range.start.line + 1..range.end.line]
-                        .iter()
-                        .cloned(),
-                );
-                lines.push(
-                    self.lines[range.end.line][..range.end.column]
-                        .iter()
-                        .cloned()
-                        .collect::<Vec<_>>(),
-                );
-                lines
-            },
-        }
+        let start = self.position_to_byte(range.start);
+        let end = self.position_to_byte(range.end);
+        let mut string = self.string.clone();
+        string.truncate_back(end);
+        string.truncate_front(start);
+        Text { string }
     }
 
     /// Appends the given range of this text to the given string.
@@ -172,30 +249,9 @@ impl Text {
     /// assert_eq!(string, "bc\nde");
     /// ```
     pub fn append_to_string(&self, range: Range, out: &mut String) {
-        if range.start.line == range.end.line {
-            out.extend(
-                self.lines[range.start.line][range.start.column..range.end.column]
-                    .iter()
-                    .cloned(),
-            );
-        } else {
-            out.extend(
-                self.lines[range.start.line][range.start.column..]
-                    .iter()
-                    .cloned()
-                    .chain(iter::once('\n'))
-                    .chain(
-                        self.lines[range.start.line + 1..range.end.line]
-                            .iter()
-                            .flat_map(|line| line.iter().cloned().chain(iter::once('\n'))),
-                    )
-                    .chain(
-                        self.lines[range.end.line][..range.end.column]
-                            .iter()
-                            .cloned(),
-                    ),
-            );
-        }
+        let start = self.position_to_byte(range.start);
+        let end = self.position_to_byte(range.end);
+        out.extend(self.string.slice(start..end).chunks());
     }
 
     /// Removes the given amount of text from the start of this text, and returns it as a new text.
@@ -208,15 +264,11 @@ impl Text {
     /// assert_eq!(text, Text::from("ef"));
     /// ```
     pub fn take(&mut self, len: Size) -> Text {
-        let mut lines = self.lines.drain(..len.line).collect::<Vec<_>>();
-        lines.push(
-            self.lines
-                .first_mut()
-                .unwrap()
-                .drain(..len.column)
-                .collect::<Vec<_>>(),
-        );
-        Text { lines }
+        let end = self.position_to_byte(Position::origin() + len);
+        let string = self.string.split_off(end);
+        Text {
+            string: mem::replace(&mut self.string, string),
+        }
     }
 
     /// Removes the given amount of text from the start of this text.
@@ -229,8 +281,8 @@ impl Text {
     /// assert_eq!(text, Text::from("ef"));
     /// ```
     pub fn skip(&mut self, len: Size) {
-        self.lines.drain(..len.line);
-        self.lines.first_mut().unwrap().drain(..len.column);
+        let end = self.position_to_byte(Position::origin() + len);
+        self.string.truncate_front(end);
     }
 
     /// Inserts the given text at the given position in this text.
@@ -248,24 +300,11 @@ impl Text {
     /// text.insert(Position { line: 1, column: 1 }, Text::from("xyz"));
     /// assert_eq!(text, Text::from("abc\ndxyzef"));
     /// ```
-    pub fn insert(&mut self, position: Position, mut text: Text) {
-        if text.len().line == 0 {
-            self.lines[position.line].splice(
-                position.column..position.column,
-                text.lines.first().unwrap().iter().cloned(),
-            );
-        } else {
-            text.lines.first_mut().unwrap().splice(
-                ..0,
-                self.lines[position.line][..position.column].iter().cloned(),
-            );
-            text.lines
-                .last_mut()
-                .unwrap()
-                .extend(self.lines[position.line][position.column..].iter().cloned());
-            self.lines
-                .splice(position.line..position.line + 1, text.lines.into_iter());
-        }
+    pub fn insert(&mut self, position: Position, text: Text) {
+        let at = self.position_to_byte(position);
+        let string = self.string.split_off(at);
+        self.string.append(text.string);
+        self.string.append(string);
     }
 
     /// Deletes the given amount of text at the given position from this text.
@@ -284,24 +323,11 @@ impl Text {
     /// assert_eq!(text, Text::from("abef"));
     /// ```
     pub fn delete(&mut self, position: Position, count: Size) {
-        if count.line == 0 {
-            self.lines[position.line].splice(
-                position.column..position.column + count.column,
-                iter::empty(),
-            );
-        } else {
-            let mut line = mem::replace(&mut self.lines[position.line], Vec::new());
-            line.splice(
-                position.column..,
-                self.lines[position.line + count.line][count.column..]
-                    .iter()
-                    .cloned(),
-            );
-            self.lines.splice(
-                position.line..position.line + count.line + 1,
-                iter::once(line),
-            );
-        }
+        let start = self.position_to_byte(position);
+        let end = self.position_to_byte(position + count);
+        let string = self.string.split_off(end);
+        self.string.truncate_back(start);
+        self.string.append(string);
     }
 
     /// Applies the given delta to this text.
@@ -327,34 +353,62 @@ impl Text {
 
 impl AddAssign for Text {
     fn add_assign(&mut self, other: Text) {
-        self.lines
-            .last_mut()
-            .unwrap()
-            .extend(other.lines.first().unwrap());
-        self.lines.extend(other.lines.into_iter().skip(1))
+        self.string.append(other.string);
     }
 }
 
 impl Default for Text {
     fn default() -> Text {
-        Text::from_lines(vec![vec![]])
+        Text {
+            string: BTreeString::new(),
+        }
+    }
+}
+
+impl PartialEq for Text {
+    fn eq(&self, other: &Text) -> bool {
+        self.string.len() == other.string.len() && self.string.bytes().eq(other.string.bytes())
+    }
+}
+
+impl Eq for Text {}
+
+impl Hash for Text {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.string.len().hash(state);
+        for byte in self.string.bytes() {
+            state.write_u8(byte);
+        }
+    }
+}
+
+impl fmt::Debug for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Text").field(&self.to_string()).finish()
     }
 }
 
 impl fmt::Display for Text {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut sep = "";
-        for line in self.lines.iter() {
-            write!(f, "{}", sep)?;
-            for ch in line {
-                write!(f, "{}", ch)?;
-            }
-            sep = "\n";
+        for chunk in self.string.chunks() {
+            f.write_str(chunk)?;
         }
         Ok(())
     }
 }
 
+impl SerBin for Text {
+    fn ser_bin(&self, s: &mut Vec<u8>) {
+        self.to_string().ser_bin(s);
+    }
+}
+
+impl DeBin for Text {
+    fn de_bin(o: &mut usize, d: &[u8]) -> Result<Text, DeBinErr> {
+        String::de_bin(o, d).map(Text::from)
+    }
+}
+
 impl From<String> for Text {
     fn from(string: String) -> Text {
         Text::from(string.as_str())
@@ -363,11 +417,18 @@ impl From<String> for Text {
 
 impl From<&str> for Text {
     fn from(string: &str) -> Text {
-        Text::from_lines(
-            string
-                .lines()
-                .map(|line| line.chars().collect::<Vec<_>>())
-                .collect::<Vec<_>>(),
-        )
+        Text {
+            string: BTreeString::from(normalize_line_breaks(string).as_ref()),
+        }
+    }
+}
+
+// Only `\r\n` and `\r` are turned into `\n`. Other characters that Unicode considers line breaks
+// (such as form feeds and U+2028) are kept as text, so that a file round-trips unchanged apart
+// from its line endings.
+fn normalize_line_breaks(string: &str) -> Cow<'_, str> {
+    if !string.contains('\r') {
+        return Cow::Borrowed(string);
     }
+    Cow::Owned(string.replace("\r\n", "\n").replace('\r', "\n"))
 }
@@ -13,6 +13,11 @@ live_register!{
         const FS_ROOT: ""
         inner: {
             window: {caption:"Makepad Studio", pass: {clear_color: (COLOR_BG_EDITOR)}}
+            // set this to true to apply edits to the live_register! blocks of studio itself
+            // while typing, instead of only checking the app run by the builder
+            live_edit_studio: false
+            // how often, in seconds, the workspace is saved when it changed
+            autosave_interval: 2.0
             collab_client: {
                 //bind: "127.0.0.1"
                 path: (FS_ROOT)
@@ -20,6 +25,13 @@ live_register!{
             builder_client: {
                 //bind: "127.0.0.1"
                 path: (FS_ROOT)
+                // set this to true to check again whenever a file in the workspace changes
+                watch: false
+            }
+            lsp_client: {
+                path: (FS_ROOT)
+                // set this to "rust-analyzer" to get hover, completion and diagnostics as you type
+                cmd: ""
             }
         }
     }
@@ -42,8 +54,13 @@ impl App {
         makepad_studio_component::live_register(cx);
         crate::builder::builder_client::live_register(cx);
         crate::collab_client::live_register(cx);
+        crate::lsp::lsp_client::live_register(cx);
         crate::rust_editor::live_register(cx);
         crate::log_view::live_register(cx);
+        crate::history_view::live_register(cx);
+        crate::search_view::live_register(cx);
+        crate::quick_open::live_register(cx);
+        crate::code_editor::minimap::live_register(cx);
         crate::code_editor::code_editor_impl::live_register(cx);
         crate::editors::live_register(cx);
         crate::app_inner::live_register(cx);
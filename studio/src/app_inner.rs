@@ -1,6 +1,12 @@
 use {
     crate::{
         makepad_platform::*,
+        makepad_micro_serde::SerRon,
+        makepad_editor_core::{
+            position::Position,
+            range::Range,
+            text::Text,
+        },
         makepad_component::{
             splitter::{SplitterAlign},
             DesktopWindow,
@@ -18,6 +24,7 @@ use {
             CollabRequest,
             CollabResponse,
             CollabClientAction,
+            GitStatus,
             unix_path::{UnixPath, UnixPathBuf},
         },
         builder::{
@@ -28,13 +35,26 @@ use {
         },
         app_state::{TabKind, AppState, SplitPanel, TabPanel, Panel, Tab},
         log_view::{LogView},
+        history_view::{HistoryView, HistoryViewAction},
+        quick_open::{QuickOpen, QuickOpenAction},
+        search_state::SearchOptions,
+        search_view::{SearchView, SearchViewAction},
+        workspace::{self, Workspace},
         editors::{Editors},
+        editor_state::SessionId,
+        code_editor::code_editor_impl::{CodeEditorAction, WrapMode},
+        lsp::lsp_client::{LspClient, LspClientAction},
     },
 };
 
 live_register!{
     AppInner: {{AppInner}} {
        // window: {caption: "Makepad Studio"}
+        git_color_modified: #e2c08d
+        git_color_added: #81b88b
+        git_color_untracked: #73c991
+        git_color_deleted: #c74e39
+        git_color_conflicted: #e4676b
     }
 }
 
@@ -45,11 +65,78 @@ pub struct AppInner {
     dock: Dock,
     file_tree: FileTree,
     log_view: LogView,
+    history_view: HistoryView,
+    search_view: SearchView,
     shader_view: ShaderView,
     slides_view: SlidesView,
     editors: Editors,
+    quick_open: QuickOpen,
     collab_client: CollabClient,
-    builder_client: BuilderClient
+    builder_client: BuilderClient,
+    lsp_client: LspClient,
+    // the colors of the names of files and folders in the file tree, by their git status
+    git_color_modified: Vec4,
+    git_color_added: Vec4,
+    git_color_untracked: Vec4,
+    git_color_deleted: Vec4,
+    git_color_conflicted: Vec4,
+    // whether edits to the live_register! blocks of studio itself are applied to studio while
+    // typing, see `LiveState`
+    live_edit_studio: bool,
+    autosave_interval: f64,
+    #[rust(studio_commands())] commands: Menu,
+    // replacements of search results in documents that were not loaded yet when the results were
+    // replaced, which are applied once their document is loaded
+    #[rust] pending_replacements: Vec<PendingReplacement>,
+    #[rust] autosave_timer: Timer,
+    // the workspace as it was last saved, so it is only saved again when it changed, and whether
+    // the saved workspace was restored, since saving before that would overwrite it
+    #[rust] saved_workspace: String,
+    #[rust] is_workspace_restored: bool,
+}
+
+// A replacement of search results in a document, see `AppInner::replace_search_results`.
+struct PendingReplacement {
+    session_id: SessionId,
+    ranges: Vec<(Range, String)>,
+    text: Text,
+}
+
+/// The commands that can be run from the quick open palette.
+fn studio_commands() -> Menu {
+    Menu::main(vec![
+        Menu::sub("Build", vec![
+            Menu::item("Cargo Check", id!(cargo_check).into()),
+        ]),
+        Menu::sub("Edit", vec![
+            Menu::item("Undo", id!(undo).into()),
+            Menu::item("Redo", id!(redo).into()),
+            Menu::line(),
+            Menu::item("Go To Line", id!(go_to_line).into()),
+            Menu::line(),
+            Menu::item("Fold All", id!(fold_all).into()),
+            Menu::item("Unfold All", id!(unfold_all).into()),
+        ]),
+        Menu::sub("View", vec![
+            Menu::item("Soft Wrap Off", id!(soft_wrap_off).into()),
+            Menu::item("Soft Wrap To Viewport", id!(soft_wrap_viewport).into()),
+            Menu::item("Soft Wrap To Column", id!(soft_wrap_column).into()),
+            Menu::line(),
+            Menu::item("Toggle Minimap", id!(toggle_minimap).into()),
+        ]),
+        Menu::sub("Git", vec![
+            Menu::item("Refresh Git Status", id!(git_refresh).into()),
+            Menu::item("Toggle Inline Blame", id!(toggle_blame).into()),
+        ]),
+        Menu::sub("Search", vec![
+            Menu::item("Search In Files", id!(search_in_files).into()),
+            Menu::item("Replace In Files", id!(replace_in_files).into()),
+            Menu::item("Cancel Search", id!(cancel_search).into()),
+        ]),
+        Menu::sub("Workspace", vec![
+            Menu::item("Save Workspace", id!(save_workspace).into()),
+        ]),
+    ])
 }
 
 impl AppInner {
@@ -60,6 +147,8 @@ impl AppInner {
                 self.draw_panel(cx, state, id!(root).into());
                 self.dock.end(cx);
             }
+            let rect = cx.turtle().rect();
+            self.quick_open.draw(cx, state, rect);
             self.window.end(cx);
         }
     }
@@ -95,6 +184,12 @@ impl AppInner {
                         TabKind::LogView => {
                             self.log_view.draw(cx, &state.editor_state)
                         }
+                        TabKind::HistoryView => {
+                            self.history_view.draw(cx, &state.editor_state, state.selected_session_id())
+                        }
+                        TabKind::SearchView => {
+                            self.search_view.draw(cx, &state.search_state)
+                        }
                         TabKind::FileTree => {
                             if self.file_tree.begin(cx).is_ok() {
                                 self.draw_file_node(cx, state, id!(root).into());
@@ -117,9 +212,16 @@ impl AppInner {
     
     fn draw_file_node(&mut self, cx: &mut Cx2d, state: &AppState, file_node_id: FileNodeId) {
         let file_node = &state.file_nodes[file_node_id];
+        let status_color = state.git_state.status(&state.file_node_path(file_node_id)).map( | status | match status {
+            GitStatus::Modified | GitStatus::Renamed => self.git_color_modified,
+            GitStatus::Added => self.git_color_added,
+            GitStatus::Untracked => self.git_color_untracked,
+            GitStatus::Deleted => self.git_color_deleted,
+            GitStatus::Conflicted => self.git_color_conflicted,
+        });
         match &file_node.child_edges {
             Some(child_edges) => {
-                if self.file_tree.begin_folder(cx, file_node_id, &file_node.name).is_ok()
+                if self.file_tree.begin_folder(cx, file_node_id, &file_node.name, status_color).is_ok()
                 {
                     for child_edge in child_edges {
                         self.draw_file_node(cx, state, child_edge.file_node_id);
@@ -128,7 +230,7 @@ impl AppInner {
                 }
             }
             None => {
-                self.file_tree.file(cx, file_node_id, &file_node.name);
+                self.file_tree.file(cx, file_node_id, &file_node.name, status_color);
             }
         }
     }
@@ -148,12 +250,60 @@ impl AppInner {
                     false
                 );
                 self.builder_client.send_cmd(BuilderCmd::CargoCheck);
+                self.autosave_timer = cx.start_timer(self.autosave_interval, true);
             }
             Event::Draw(draw_event) => {
                 self.draw(&mut Cx2d::new(cx, draw_event), state);
+                // shaders are compiled as they are drawn, so this is when their errors are known
+                if state.live_state.update_messages(cx, &state.path, &mut state.editor_state) {
+                    self.editors.redraw_all_views(cx);
+                    self.log_view.redraw(cx);
+                }
+            }
+            Event::WindowClosed(_) => {
+                self.save_workspace(cx, state, true);
+            }
+            Event::Command(command) => {
+                self.run_command(cx, state, *command);
             }
             _ => ()
         }
+        if event.is_timer(self.autosave_timer) && self.is_workspace_restored {
+            self.save_workspace(cx, state, false);
+        }
+        
+        let quick_open_actions = self.quick_open.handle_event(
+            cx,
+            event,
+            state,
+            &self.commands,
+            &mut self.collab_client.request_sender()
+        );
+        for action in quick_open_actions {
+            match action {
+                QuickOpenAction::OpenFile {path, line} => {
+                    let session_id = self.create_code_editor_tab(cx, state, state.selected_panel_id, None, path, true);
+                    if let Some(line) = line {
+                        self.go_to_line(cx, state, session_id, line);
+                    }
+                }
+                QuickOpenAction::GoToLine(line) => {
+                    if let Some(session_id) = state.selected_session_id() {
+                        self.go_to_line(cx, state, session_id, line);
+                    }
+                }
+                QuickOpenAction::RunCommand(command) => {
+                    self.run_command(cx, state, command);
+                }
+                QuickOpenAction::Search(input) => {
+                    state.search_state.start(SearchOptions::parse(&input), &mut self.collab_client.request_sender());
+                    self.select_search_tab(cx, state);
+                }
+                QuickOpenAction::Replace(text) => {
+                    self.replace_search_results(cx, state, Text::from(text.as_str()));
+                }
+            }
+        }
         
         for action in self.dock.handle_event(cx, event) {
             match action {
@@ -241,6 +391,7 @@ impl AppInner {
             }
         }
         
+        let mut editor_actions = Vec::new();
         let mut panel_id_stack = vec![id!(root).into()];
         while let Some(panel_id) = panel_id_stack.pop() {
             let panel = &state.panels[panel_id];
@@ -253,36 +404,88 @@ impl AppInner {
                 Panel::Tab(tab_panel) => {
                     if let Some(tab_id) = tab_panel.selected_tab_id() {
                         if self.editors.has_editor(tab_id.into()) {
-                            self.editors.handle_event(
+                            editor_actions.extend(self.editors.handle_event(
                                 cx,
                                 &mut state.editor_state,
                                 tab_id.into(),
                                 event,
                                 &mut self.collab_client.request_sender(),
-                            );
+                            ));
                         }
                     }
                 }
             }
         }
         
+        for action in editor_actions {
+            match action {
+                CodeEditorAction::RequestHover(session_id) => {
+                    self.lsp_client.hover(&mut state.editor_state, session_id);
+                }
+                CodeEditorAction::RequestDefinition(session_id) => {
+                    self.lsp_client.go_to_definition(&mut state.editor_state, session_id);
+                }
+                CodeEditorAction::RequestCompletion(session_id) => {
+                    self.lsp_client.completion(&mut state.editor_state, session_id);
+                }
+                _ => ()
+            }
+        }
+        if self.live_edit_studio {
+            state.live_state.sync_documents(cx, &state.path, &mut state.editor_state);
+        }
+        
         for action in self.collab_client.handle_event(cx, event) {
             match action {
                 CollabClientAction::Response(response) => match response {
                     CollabResponse::LoadFileTree(response) => {
                         self.load_file_tree(cx, state, response.unwrap());
+                        state.git_state.refresh(&mut self.collab_client.request_sender());
                         self.select_tab(cx, state, id!(file_tree).into(), id!(file_tree).into(), Animate::No);
+                        self.restore_workspace(cx, state);
                     }
                     response => {
-                        self.editors.handle_collab_response(cx, &mut state.editor_state, response, &mut self.collab_client.request_sender())
+                        if state.search_state.handle_response(&response) {
+                            self.search_view.redraw(cx);
+                            continue;
+                        }
+                        if state.git_state.handle_response(&response) {
+                            self.file_tree.redraw(cx);
+                            continue;
+                        }
+                        if let CollabResponse::GitDiff(path, Ok(hunks)) = &response {
+                            if state.git_state.is_stale(path, hunks) {
+                                state.git_state.refresh(&mut self.collab_client.request_sender());
+                            }
+                        }
+                        self.editors.handle_collab_response(cx, &mut state.editor_state, response, &mut self.collab_client.request_sender());
+                        // the file that is being previewed might have been loaded
+                        if self.quick_open.is_open() {
+                            self.quick_open.redraw(cx);
+                        }
+                        // as might the documents of pending replacements
+                        self.apply_pending_replacements(cx, state);
                     }
                 },
                 CollabClientAction::Notification(notification) => {
+                    if state.search_state.handle_notification(&notification) {
+                        self.search_view.redraw(cx);
+                        continue;
+                    }
                     self.editors.handle_collab_notification(cx, &mut state.editor_state, notification)
                 }
             }
         }
         
+        // watch mode checks the files that are open more often than the rest of the workspace
+        if state.editor_state.take_documents_changed() {
+            let watch_paths = state.editor_state.documents_by_path
+                .keys()
+                .map( | path | path.as_unix_str().to_string_lossy().into_owned())
+                .collect();
+            self.builder_client.set_watch_paths(watch_paths);
+        }
+        
         let msgs = self.builder_client.handle_event(cx, event);
         if msgs.len()>0 {
             self.editors.handle_builder_messages(cx, &mut state.editor_state, msgs);
@@ -290,7 +493,58 @@ impl AppInner {
             self.log_view.redraw(cx);
         }
         
+        for action in self.lsp_client.handle_event(cx, event, &mut state.editor_state) {
+            match action {
+                LspClientAction::Hover {session_id, text} => {
+                    self.editors.show_hover(cx, &state.editor_state, session_id, &text);
+                }
+                LspClientAction::Completion {session_id, items} => {
+                    self.editors.show_completion(cx, &state.editor_state, session_id, items);
+                }
+                LspClientAction::GoToDefinition {session_id, path, position} => {
+                    self.go_to_definition(cx, state, session_id, path, position);
+                }
+                LspClientAction::RedrawViewsForDocument(document_id) => {
+                    self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+                }
+            }
+        }
+        
         self.log_view.handle_event_with_fn(cx, event,&mut |_,_|{});
+        
+        let mut history_actions = Vec::new();
+        self.history_view.handle_event_with_fn(cx, event, &mut | _, action | history_actions.push(action));
+        for action in history_actions {
+            match action {
+                HistoryViewAction::JumpToUndoState(index) => {
+                    if let Some(session_id) = state.selected_session_id() {
+                        state.editor_state.jump_to_undo_state(session_id, index, &mut self.collab_client.request_sender());
+                        let document_id = state.editor_state.sessions[session_id].document_id;
+                        self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+                        self.history_view.redraw(cx);
+                    }
+                }
+            }
+        }
+        let mut search_actions = Vec::new();
+        self.search_view.handle_event_with_fn(cx, event, &mut | _, action | search_actions.push(action));
+        for action in search_actions {
+            match action {
+                SearchViewAction::OpenResult(index) => {
+                    if let Some(result) = state.search_state.results.get(index).cloned() {
+                        let session_id = self.create_code_editor_tab(cx, state, state.selected_panel_id, None, result.path, true);
+                        state.editor_state.move_cursors_to(session_id, result.range.start, false);
+                        state.editor_state.move_cursors_to(session_id, result.range.end, true);
+                        let document_id = state.editor_state.sessions[session_id].document_id;
+                        self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+                    }
+                }
+            }
+        }
+        // edits, undo and redo can all change the undo tree of the selected document
+        if let Event::KeyDown(_) | Event::TextInput(_) = event {
+            self.history_view.redraw(cx);
+        }
         self.shader_view.handle_event(cx, event);
         self.slides_view.handle_event(cx, event);
     }
@@ -303,6 +557,72 @@ impl AppInner {
         self.file_tree.redraw(cx);
     }
     
+    // Saves the workspace, unless `force` is false and it did not change since it was last saved.
+    fn save_workspace(&mut self, cx: &mut Cx, state: &AppState, force: bool) {
+        let path = match self.collab_client.local_path() {
+            Some(path) => path.to_path_buf(),
+            None => return,
+        };
+        let editors = &self.editors;
+        let workspace = Workspace::capture(
+            state,
+            self.file_tree.open_folder_ids(),
+            | tab_id | editors.view_scroll_pos(cx, tab_id.into())
+        );
+        let ron = workspace.serialize_ron();
+        if !force && ron == self.saved_workspace {
+            return;
+        }
+        workspace.save(&path);
+        workspace::save_undo_histories(&path, &state.editor_state.capture_undo_histories());
+        self.saved_workspace = ron;
+    }
+    
+    fn restore_workspace(&mut self, cx: &mut Cx, state: &mut AppState) {
+        self.is_workspace_restored = true;
+        // the histories are restored as the files are opened, so they are loaded first
+        if let Some(path) = self.collab_client.local_path() {
+            for (file_path, history) in workspace::load_undo_histories(path) {
+                state.editor_state.undo_histories.entry(file_path).or_insert(history);
+            }
+        }
+        let workspace = match self.collab_client.local_path().and_then(Workspace::load) {
+            Some(workspace) if workspace.is_valid() => workspace,
+            _ => return,
+        };
+        
+        // close the code editor tabs of the default layout
+        let code_editor_tabs: Vec<(TabId, SessionId)> = state.tabs.iter().filter_map( | (tab_id, tab) | match tab.kind {
+            TabKind::CodeEditor {session_id} => Some((*tab_id, session_id)),
+            _ => None
+        }).collect();
+        for (tab_id, session_id) in code_editor_tabs {
+            self.editors.set_view_session_id(cx, &mut state.editor_state, tab_id.into(), None);
+            state.editor_state.destroy_session(session_id, &mut self.collab_client.request_sender());
+        }
+        
+        let restored = workspace.restore(state, &mut self.collab_client.request_sender());
+        
+        for (panel_id, panel) in state.panels.iter() {
+            if let Panel::Tab(panel) = panel {
+                self.dock.set_selected_tab_id(cx, *panel_id, panel.selected_tab_id(), Animate::No);
+            }
+        }
+        for (tab_id, tab) in state.tabs.iter() {
+            if let TabKind::CodeEditor {session_id} = tab.kind {
+                self.editors.set_view_session_id(cx, &mut state.editor_state, (*tab_id).into(), Some(session_id));
+            }
+        }
+        for (tab_id, pos) in restored.scroll_positions {
+            self.editors.set_view_scroll_pos(cx, tab_id.into(), pos);
+        }
+        for file_node_id in restored.open_folder_ids {
+            self.file_tree.set_folder_is_open(cx, file_node_id, true, Animate::No);
+        }
+        self.dock.redraw(cx);
+        self.redraw_panel(cx, state, id!(root).into());
+    }
+    
     fn split_tab_panel(
         &mut self,
         cx: &mut Cx,
@@ -355,7 +675,7 @@ impl AppInner {
         next_tab_id: Option<TabId>,
         path: UnixPathBuf,
         select: bool
-    ) {
+    ) -> SessionId {
         let name = path.file_name().unwrap().to_string_lossy().into_owned();
 
         let session_id = state.editor_state.create_session(path, &mut self.collab_client.request_sender());
@@ -376,6 +696,180 @@ impl AppInner {
         if select{
             self.select_tab(cx, state, panel_id, tab_id, Animate::No);
         }
+        session_id
+    }
+    
+    fn go_to_definition(
+        &mut self,
+        cx: &mut Cx,
+        state: &mut AppState,
+        session_id: SessionId,
+        path: UnixPathBuf,
+        position: Position
+    ) {
+        // if the definition is in the same file, we stay in the same tab
+        let session = &state.editor_state.sessions[session_id];
+        let session_id = if state.editor_state.documents[session.document_id].path == path {
+            session_id
+        }
+        else {
+            self.create_code_editor_tab(cx, state, state.selected_panel_id, None, path, true)
+        };
+        state.editor_state.move_cursors_to(session_id, position, false);
+        let document_id = state.editor_state.sessions[session_id].document_id;
+        self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+    }
+    
+    fn go_to_line(&mut self, cx: &mut Cx, state: &mut AppState, session_id: SessionId, line: usize) {
+        let document_id = state.editor_state.sessions[session_id].document_id;
+        // if the document is not loaded yet, the cursor is clamped to the text once it is
+        let line = match &state.editor_state.documents[document_id].inner {
+            Some(inner) => line.min(inner.text.line_count().saturating_sub(1)),
+            None => line,
+        };
+        state.editor_state.move_cursors_to(session_id, Position {line, column: 0}, false);
+        self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+    }
+    
+    fn run_command(&mut self, cx: &mut Cx, state: &mut AppState, command: Command) {
+        match command.0 {
+            id!(cargo_check) => {
+                self.builder_client.send_cmd(BuilderCmd::CargoCheck);
+            }
+            id!(undo) | id!(redo) => {
+                if let Some(session_id) = state.selected_session_id() {
+                    let document_id = state.editor_state.sessions[session_id].document_id;
+                    if state.editor_state.documents[document_id].inner.is_some() {
+                        let send_request = &mut self.collab_client.request_sender();
+                        if command.0 == id!(undo) {
+                            state.editor_state.undo(session_id, send_request);
+                        }
+                        else {
+                            state.editor_state.redo(session_id, send_request);
+                        }
+                        self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+                        self.history_view.redraw(cx);
+                    }
+                }
+            }
+            id!(fold_all) | id!(unfold_all) => {
+                if let Some(session_id) = state.selected_session_id() {
+                    let document_id = state.editor_state.sessions[session_id].document_id;
+                    if state.editor_state.documents[document_id].inner.is_some() {
+                        if command.0 == id!(fold_all) {
+                            state.editor_state.fold_all(session_id);
+                        }
+                        else {
+                            state.editor_state.unfold_all(session_id);
+                        }
+                        self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+                    }
+                }
+            }
+            id!(soft_wrap_off) => {
+                self.editors.set_wrap_mode(cx, WrapMode::Off);
+            }
+            id!(soft_wrap_viewport) => {
+                self.editors.set_wrap_mode(cx, WrapMode::Viewport);
+            }
+            id!(soft_wrap_column) => {
+                self.editors.set_wrap_mode(cx, WrapMode::Column);
+            }
+            id!(toggle_minimap) => {
+                self.editors.toggle_minimap(cx);
+            }
+            id!(git_refresh) => {
+                let send_request = &mut self.collab_client.request_sender();
+                state.git_state.refresh(send_request);
+                self.editors.refresh_git(&state.editor_state, send_request);
+            }
+            id!(toggle_blame) => {
+                self.editors.toggle_blame(cx, &state.editor_state, &mut self.collab_client.request_sender());
+            }
+            id!(go_to_line) => {
+                let commands = self.commands.commands().into_iter().map( | (name, command) | (String::from(name), command)).collect();
+                self.quick_open.open(cx, state, commands, ":");
+            }
+            id!(search_in_files) | id!(replace_in_files) => {
+                let commands = self.commands.commands().into_iter().map( | (name, command) | (String::from(name), command)).collect();
+                let input = if command.0 == id!(search_in_files) {"?"} else {"="};
+                self.quick_open.open(cx, state, commands, input);
+            }
+            id!(cancel_search) => {
+                state.search_state.cancel(&mut self.collab_client.request_sender());
+                self.search_view.redraw(cx);
+            }
+            id!(save_workspace) => {
+                self.save_workspace(cx, state, true);
+            }
+            _ => ()
+        }
+    }
+    
+    // Selects the search tab in whichever panel it is in, if any.
+    fn select_search_tab(&mut self, cx: &mut Cx, state: &mut AppState) {
+        let tab_id: TabId = id!(search_view).into();
+        let panel_id = state.panels.iter().find_map( | (panel_id, panel) | match panel {
+            Panel::Tab(panel) if panel.tab_ids.contains(&tab_id) => Some(*panel_id),
+            _ => None,
+        });
+        match panel_id {
+            Some(panel_id) => self.select_tab(cx, state, panel_id, tab_id, Animate::Yes),
+            None => self.search_view.redraw(cx),
+        }
+    }
+    
+    // Replaces the results of the last search with `text`, by editing the document of each file
+    // that has results through a code editor tab for it. Tabs are opened, but not selected, for
+    // files that do not have one yet.
+    fn replace_search_results(&mut self, cx: &mut Cx, state: &mut AppState, text: Text) {
+        for (path, ranges) in state.search_state.take_matches_by_path() {
+            let session_id = state.tabs.values().find_map( | tab | match tab.kind {
+                TabKind::CodeEditor {session_id} => {
+                    let document_id = state.editor_state.sessions[session_id].document_id;
+                    if state.editor_state.documents[document_id].path == path {Some(session_id)} else {None}
+                }
+                _ => None,
+            });
+            let session_id = match session_id {
+                Some(session_id) => session_id,
+                None => self.create_code_editor_tab(cx, state, state.selected_panel_id, None, path, false),
+            };
+            self.pending_replacements.push(PendingReplacement {session_id, ranges, text: text.clone()});
+        }
+        self.apply_pending_replacements(cx, state);
+        self.dock.redraw(cx);
+        self.search_view.redraw(cx);
+    }
+    
+    // Applies the pending replacements for which the document is loaded.
+    fn apply_pending_replacements(&mut self, cx: &mut Cx, state: &mut AppState) {
+        let mut index = 0;
+        while index < self.pending_replacements.len() {
+            let session_id = self.pending_replacements[index].session_id;
+            // the tab might have been closed while its document was loading
+            let document_id = match state.editor_state.sessions.get(&session_id) {
+                Some(session) => session.document_id,
+                None => {
+                    self.pending_replacements.remove(index);
+                    continue;
+                }
+            };
+            if state.editor_state.documents[document_id].inner.is_none() {
+                index += 1;
+                continue;
+            }
+            let replacement = self.pending_replacements.remove(index);
+            let count = state.editor_state.replace_ranges(
+                session_id,
+                &replacement.ranges,
+                &replacement.text,
+                &mut self.collab_client.request_sender()
+            );
+            state.search_state.add_replaced_matches(count);
+            self.editors.redraw_views_for_document(cx, &state.editor_state, document_id);
+            self.search_view.redraw(cx);
+        }
     }
     
     fn select_tab(&mut self, cx: &mut Cx, state: &mut AppState, panel_id: PanelId, tab_id: TabId, animate: Animate) {
@@ -392,9 +886,13 @@ impl AppInner {
                     tab_id.into(),
                     Some(session_id),
                 );
+                let document_id = state.editor_state.sessions[session_id].document_id;
+                let path = state.editor_state.documents[document_id].path.clone();
+                state.add_recent_path(path);
             }
             _ => {}
         }
+        self.history_view.redraw(cx);
         self.redraw_panel(cx, state, panel_id);
     }
     
@@ -420,6 +918,12 @@ impl AppInner {
                         TabKind::LogView => {
                             self.log_view.redraw(cx);
                         }
+                        TabKind::HistoryView => {
+                            self.history_view.redraw(cx);
+                        }
+                        TabKind::SearchView => {
+                            self.search_view.redraw(cx);
+                        }
                         TabKind::FileTree => {
                             self.file_tree.redraw(cx);
                         }
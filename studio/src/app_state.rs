@@ -10,6 +10,9 @@ use {
         },
         makepad_platform::*,
         editor_state::{EditorState, SessionId},
+        search_state::SearchState,
+        git_state::GitState,
+        live_state::LiveState,
         makepad_collab_protocol::{
             FileNodeData, FileTreeData,
             unix_path::UnixPathBuf,
@@ -27,8 +30,17 @@ pub struct AppState {
     
     pub path: UnixPathBuf,
     pub editor_state: EditorState,
+    pub search_state: SearchState,
+    pub git_state: GitState,
+    pub live_state: LiveState,
+    /// The paths of the files that were most recently shown in a code editor tab, with the most
+    /// recent one first.
+    pub recent_paths: Vec<UnixPathBuf>,
 }
 
+/// The maximum number of paths that are kept in `AppState::recent_paths`.
+pub const MAX_RECENT_PATHS: usize = 32;
+
 impl AppState {
     pub fn new() -> AppState {
         let mut file_nodes = LiveIdMap::new();
@@ -48,7 +60,7 @@ impl AppState {
         panels.insert(
             id!(log_view),
             Panel::Tab(TabPanel {
-                tab_ids: vec![id!(log_view).into(), id!(shader_view).into()],
+                tab_ids: vec![id!(log_view).into(), id!(history_view).into(), id!(search_view).into(), id!(shader_view).into()],
                 selected_tab: Some(0)
             }),
         );
@@ -60,6 +72,20 @@ impl AppState {
                 kind: TabKind::LogView,
             },
         );
+        tabs.insert(
+            id!(history_view),
+            Tab {
+                name: String::from("History"),
+                kind: TabKind::HistoryView,
+            },
+        );
+        tabs.insert(
+            id!(search_view),
+            Tab {
+                name: String::from("Search"),
+                kind: TabKind::SearchView,
+            },
+        );
         tabs.insert(
             id!(shader_view),
             Tab {
@@ -125,6 +151,10 @@ impl AppState {
             file_nodes,
             path: UnixPathBuf::new(),
             editor_state: EditorState::new(),
+            search_state: SearchState::default(),
+            git_state: GitState::default(),
+            live_state: LiveState::default(),
+            recent_paths: Vec::new(),
         }
     }
     
@@ -137,6 +167,15 @@ impl AppState {
         None
     }
     
+    /// Returns the session of the code editor tab that is selected in the selected panel, if any.
+    pub fn selected_session_id(&self) -> Option<SessionId> {
+        let tab_id = self.panels[self.selected_panel_id].as_tab_panel().selected_tab_id() ?;
+        match self.tabs[tab_id].kind {
+            TabKind::CodeEditor {session_id} => Some(session_id),
+            _ => None,
+        }
+    }
+    
     pub fn file_node_path(&self, file_node_id: FileNodeId) -> UnixPathBuf {
         let mut components = Vec::new();
         let mut file_node = &self.file_nodes[file_node_id];
@@ -147,6 +186,26 @@ impl AppState {
         self.path.join(components.into_iter().rev().collect::<UnixPathBuf>())
     }
     
+    /// Returns the paths of all files in the file tree, in the order in which they appear in it.
+    pub fn file_paths(&self) -> Vec<UnixPathBuf> {
+        let mut paths = Vec::new();
+        let mut stack = vec![FileNodeId::from(id!(root))];
+        while let Some(file_node_id) = stack.pop() {
+            match &self.file_nodes[file_node_id].child_edges {
+                Some(child_edges) => stack.extend(child_edges.iter().rev().map( | edge | edge.file_node_id)),
+                None => paths.push(self.file_node_path(file_node_id)),
+            }
+        }
+        paths
+    }
+    
+    /// Marks the file with the given `path` as the one that was most recently shown.
+    pub fn add_recent_path(&mut self, path: UnixPathBuf) {
+        self.recent_paths.retain( | recent_path | *recent_path != path);
+        self.recent_paths.insert(0, path);
+        self.recent_paths.truncate(MAX_RECENT_PATHS);
+    }
+    
     pub fn file_path_join(&self, components: &[&str]) -> UnixPathBuf {
         self.path.join(components.into_iter().rev().collect::<UnixPathBuf>())
     }
@@ -281,6 +340,8 @@ pub struct Tab {
 
 pub enum TabKind {
     LogView,
+    HistoryView,
+    SearchView,
     ShaderView,
     SlidesView,
     FileTree,
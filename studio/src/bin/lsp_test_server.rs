@@ -0,0 +1,209 @@
+//! A scripted stand-in for a language server, so the language server client can be tested without
+//! a real language server such as rust-analyzer.
+//!
+//! The server keeps track of the text of each open document by applying incremental changes, and:
+//! - publishes a warning for each occurrence of "TODO" whenever a document is opened or changed,
+//! - responds to hover requests with the identifier under the cursor,
+//! - responds to definition requests with the first occurrence of the identifier under the cursor,
+//! - responds to completion requests with a fixed list of items,
+//! - responds to "test/text" requests with the current text of the document, so tests can check
+//!   that the document was kept in sync.
+
+use {
+    makepad_studio::lsp::{
+        json::JsonValue,
+        lsp_connection::{read_message, write_message},
+        lsp_protocol::{
+            LspContentChange,
+            LspDiagnostic,
+            LspDiagnosticSeverity,
+            LspMessage,
+            LspPosition,
+            LspRange,
+        },
+    },
+    std::{
+        collections::HashMap,
+        io::{self, BufReader, Stdout},
+    },
+};
+
+fn main() {
+    let stdin = io::stdin();
+    let mut reader = BufReader::new(stdin.lock());
+    let mut stdout = io::stdout();
+    let mut documents = HashMap::new();
+    while let Ok(Some(json)) = read_message(&mut reader) {
+        match LspMessage::from_json(&json) {
+            Some(LspMessage::Request {id, method, params}) => {
+                let result = handle_request(&documents, &method, &params);
+                send(&mut stdout, LspMessage::Response {id, result: Ok(result)});
+            }
+            Some(LspMessage::Notification {method, params}) => {
+                if method == "exit" {
+                    break;
+                }
+                if let Some(uri) = handle_notification(&mut documents, &method, &params) {
+                    publish_diagnostics(&mut stdout, &uri, &documents[&uri]);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn handle_request(documents: &HashMap<String, String>, method: &str, params: &JsonValue) -> JsonValue {
+    let text = params
+        .get("textDocument")
+        .and_then( | text_document | text_document.get("uri"))
+        .and_then( | uri | uri.as_str())
+        .and_then( | uri | documents.get(uri));
+    let position = params.get("position").and_then(LspPosition::from_json);
+    match (method, text, position) {
+        ("initialize", _, _) => JsonValue::object([
+            ("capabilities", JsonValue::object([
+                ("textDocumentSync", 2u64.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("completionProvider", JsonValue::object([])),
+            ])),
+        ]),
+        ("textDocument/hover", Some(text), Some(position)) => match identifier_at(text, position) {
+            Some(range) => JsonValue::object([
+                ("contents", JsonValue::object([
+                    ("kind", "plaintext".into()),
+                    ("value", format!("identifier `{}`", slice(text, range)).into()),
+                ])),
+            ]),
+            None => JsonValue::Null,
+        },
+        ("textDocument/definition", Some(text), Some(position)) => {
+            let uri = params.get("textDocument").unwrap().get("uri").unwrap().clone();
+            match identifier_at(text, position) {
+                Some(range) => {
+                    let identifier = slice(text, range);
+                    let offset = text.find(identifier).unwrap();
+                    let range = LspRange {
+                        start: position_at(text, offset),
+                        end: position_at(text, offset + identifier.len()),
+                    };
+                    JsonValue::Array(vec![JsonValue::object([("uri", uri), ("range", range.to_json())])])
+                }
+                None => JsonValue::Null,
+            }
+        }
+        ("textDocument/completion", Some(_), Some(_)) => JsonValue::object([
+            ("isIncomplete", false.into()),
+            ("items", JsonValue::Array(vec![
+                JsonValue::object([("label", "println!".into()), ("insertText", "println!(\"\")".into())]),
+                JsonValue::object([("label", "print!".into()), ("detail", "macro".into())]),
+            ])),
+        ]),
+        ("test/text", Some(text), _) => text.as_str().into(),
+        _ => JsonValue::Null,
+    }
+}
+
+// Applies the given notification to `documents`. Returns the uri of the document, if the
+// notification changed the text of a document.
+fn handle_notification(documents: &mut HashMap<String, String>, method: &str, params: &JsonValue) -> Option<String> {
+    let text_document = params.get("textDocument") ?;
+    let uri = text_document.get("uri") ?.as_str() ?.to_string();
+    match method {
+        "textDocument/didOpen" => {
+            let text = text_document.get("text") ?.as_str() ?.to_string();
+            documents.insert(uri.clone(), text);
+            Some(uri)
+        }
+        "textDocument/didChange" => {
+            let text = documents.get_mut(&uri) ?;
+            for change in params.get("contentChanges") ?.as_array() ? {
+                let change = LspContentChange::from_json(change) ?;
+                let start = offset_at(text, change.range.start);
+                let end = offset_at(text, change.range.end);
+                text.replace_range(start..end, &change.text);
+            }
+            Some(uri)
+        }
+        "textDocument/didClose" => {
+            documents.remove(&uri);
+            None
+        }
+        _ => None,
+    }
+}
+
+fn publish_diagnostics(stdout: &mut Stdout, uri: &str, text: &str) {
+    let diagnostics = text
+        .match_indices("TODO")
+        .map( | (offset, _) | LspDiagnostic {
+            range: LspRange {
+                start: position_at(text, offset),
+                end: position_at(text, offset + 4),
+            },
+            severity: LspDiagnosticSeverity::Warning,
+            message: "unfinished code".to_string(),
+        }.to_json())
+        .collect::<Vec<_>>();
+    send(stdout, LspMessage::Notification {
+        method: "textDocument/publishDiagnostics".to_string(),
+        params: JsonValue::object([("uri", uri.into()), ("diagnostics", diagnostics.into())]),
+    });
+}
+
+fn send(stdout: &mut Stdout, message: LspMessage) {
+    write_message(&mut stdout.lock(), &message.to_json()).unwrap();
+}
+
+// Returns the byte offset in `text` for the given position.
+fn offset_at(text: &str, position: LspPosition) -> usize {
+    let mut offset = 0;
+    for (line_index, line) in text.split('\n').enumerate() {
+        if line_index == position.line {
+            let mut character = 0;
+            for (index, ch) in line.char_indices() {
+                if character >= position.character {
+                    return offset + index;
+                }
+                character += ch.len_utf16();
+            }
+            return offset + line.len();
+        }
+        offset += line.len() + 1;
+    }
+    text.len()
+}
+
+// Returns the position for the given byte offset in `text`.
+fn position_at(text: &str, offset: usize) -> LspPosition {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, | index | index + 1);
+    LspPosition {
+        line: before.matches('\n').count(),
+        character: before[line_start..].chars().map( | ch | ch.len_utf16()).sum(),
+    }
+}
+
+// Returns the byte range of the identifier at the given position, if any.
+fn identifier_at(text: &str, position: LspPosition) -> Option<(usize, usize)> {
+    let is_identifier_char = | ch: char | ch.is_alphanumeric() || ch == '_';
+    let offset = offset_at(text, position);
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while( | (_, ch) | is_identifier_char(*ch))
+        .last()
+        .map_or(offset, | (index, _) | index);
+    let end = text[offset..]
+        .char_indices()
+        .find( | (_, ch) | !is_identifier_char(*ch))
+        .map_or(text.len(), | (index, _) | offset + index);
+    if start == end {
+        return None;
+    }
+    Some((start, end))
+}
+
+fn slice(text: &str, (start, end): (usize, usize)) -> &str {
+    &text[start..end]
+}
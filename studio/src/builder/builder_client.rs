@@ -27,6 +27,8 @@ live_register!{
 pub struct BuilderClient {
     bind: Option<String>,
     path: String,
+    watch: bool,
+    #[rust] is_watching: bool,
     #[rust] cmd_id_counter: u64,
     #[rust] inner: Option<BuilderClientInner>
 }
@@ -36,6 +38,10 @@ impl LiveHook for BuilderClient{
         if self.inner.is_none(){
             self.inner = Some(BuilderClientInner::new_with_local_server(&self.path))
         }
+        if self.watch != self.is_watching {
+            self.is_watching = self.watch;
+            self.send_cmd(BuilderCmd::Watch {enabled: self.watch});
+        }
     }
 }
 
@@ -54,6 +60,12 @@ impl BuilderClient{
     pub fn send_cmd(&mut self, _cmd: BuilderCmd) {
     }
     
+    /// Sets the paths that watch mode checks for changes more often than the rest of the
+    /// workspace.
+    pub fn set_watch_paths(&mut self, paths: Vec<String>) {
+        self.send_cmd(BuilderCmd::WatchPaths {paths});
+    }
+    
     pub fn handle_event(&mut self, cx: &mut Cx, event: &mut Event) -> Vec<BuilderMsgWrap> {
         let mut a = Vec::new();
         self.handle_event_with_fn(cx, event, &mut | _, v | a.push(v));
@@ -153,7 +165,7 @@ fn spawn_connection_listener(listener: TcpListener, mut server: BuilderServer) {
 }
 
 fn spawn_remote_cmd_handler(
-    connection: BuilderConnection,
+    mut connection: BuilderConnection,
     mut stream: TcpStream,
 ) {
     thread::spawn(move || loop {
@@ -218,7 +230,7 @@ fn _spawn_msg_receiver(
 
 fn spawn_local_cmd_handler(
     cmd_receiver: Receiver<BuilderCmdWrap>,
-    connection: BuilderConnection,
+    mut connection: BuilderConnection,
 ) {
     thread::spawn(move || loop {
         let cmd = cmd_receiver.recv().unwrap();
@@ -6,7 +6,9 @@ use {
 };
 
 
-#[derive(Clone, Copy, Debug, SerBin, DeBin)]
+/// The id of a builder command. Since every run of the builder is started by a command, this also
+/// serves as the generation of the messages produced by that run.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, SerBin, DeBin)]
 pub struct BuilderCmdId(pub u64);
 
 #[derive(Clone, Debug, SerBin, DeBin)]
@@ -26,7 +28,13 @@ impl BuilderCmdId{
 
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum BuilderCmd {
-    CargoCheck
+    CargoCheck,
+    /// Enables or disables watch mode. While watch mode is enabled, the last command is run again
+    /// whenever a file in the workspace changes, cancelling the run that is in progress, if any.
+    Watch {enabled: bool},
+    /// Sets the files, relative to the root of the workspace, that watch mode checks for changes
+    /// more often than the rest of the workspace, which are usually the files that are open.
+    WatchPaths {paths: Vec<String>},
 }
 
 #[derive(Clone, Debug, SerBin, DeBin)]
@@ -58,6 +66,11 @@ pub struct BuilderMsgBare{
 
 #[derive(Clone, Debug, SerBin, DeBin)]
 pub enum BuilderMsg {
+    /// Sent before any other message of a run.
+    RunStarted,
+    /// Sent after all other messages of a run. Messages of a run that never finishes (because it
+    /// was cancelled, for instance) should be discarded.
+    RunFinished,
     Bare(BuilderMsgBare),
     Location(BuilderMsgLocation)
 }
@@ -2,20 +2,31 @@
 use {
     crate::{
         makepad_micro_serde::*,
-        makepad_editor_core::range::{Range},
+        makepad_editor_core::{position::Position, range::Range},
+        makepad_collab_server::child_process::{
+            ChildProcess,
+            ChildLine
+        },
         builder::{
             builder_protocol::*,
-            child_process::{
-                ChildProcess,
-                ChildLine
-            },
             rustc_json::*,
         },
     },
     std::{
+        collections::HashMap,
         fmt,
-        path::{PathBuf},
-        sync::{Arc, RwLock},
+        fs,
+        path::{Path, PathBuf},
+        process::Child,
+        slice,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+            Mutex,
+            RwLock,
+        },
+        thread,
+        time::{Duration, Instant, SystemTime},
     },
 };
 
@@ -40,7 +51,13 @@ impl BuilderServer {
         BuilderConnection {
             connection_id,
             shared: self.shared.clone(),
-            msg_sender,
+            runner: BuilderRunner {
+                shared: self.shared.clone(),
+                msg_sender,
+                state: Arc::new(Mutex::new(RunnerState::default())),
+            },
+            watch_paths: Arc::new(Mutex::new(Vec::new())),
+            watcher: None,
         }
     }
 }
@@ -48,82 +65,275 @@ impl BuilderServer {
 pub struct BuilderConnection {
     connection_id: ConnectionId,
     shared: Arc<RwLock<Shared >>,
-    msg_sender: Box<dyn MsgSender>,
+    runner: BuilderRunner,
+    // The paths that are checked for changes in watch mode, shared with the watcher.
+    watch_paths: Arc<Mutex<Vec<PathBuf >>>,
+    watcher: Option<Watcher>,
 }
 
 impl BuilderConnection {
-    pub fn handle_cmd(&self, cmd_wrap: BuilderCmdWrap) {
+    pub fn handle_cmd(&mut self, cmd_wrap: BuilderCmdWrap) {
         match cmd_wrap.cmd {
             BuilderCmd::CargoCheck => {
-                self.cargo_check(cmd_wrap.cmd_id);
+                self.runner.start_run(cmd_wrap);
             }
+            BuilderCmd::Watch {enabled} => {
+                if let Some(watcher) = self.watcher.take() {
+                    watcher.stop();
+                }
+                if enabled {
+                    self.watcher = Some(Watcher::start(self.runner.clone(), self.watch_paths.clone()));
+                }
+            }
+            BuilderCmd::WatchPaths {paths} => {
+                let root = self.shared.read().unwrap().path.clone();
+                *self.watch_paths.lock().unwrap() = paths
+                    .iter()
+                    .map( | path | root.join(path.split('/').collect::<PathBuf>()))
+                    .collect();
+            }
+        }
+    }
+}
+
+impl Drop for BuilderConnection {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.stop();
+        }
+        self.runner.cancel_run();
+    }
+}
+
+// Starts runs of cargo commands for a connection. Only one run can be in progress at a time;
+// starting a new run cancels the run that is in progress, if any.
+#[derive(Clone)]
+struct BuilderRunner {
+    shared: Arc<RwLock<Shared >>,
+    msg_sender: Box<dyn MsgSender>,
+    state: Arc<Mutex<RunnerState >>,
+}
+
+#[derive(Default)]
+struct RunnerState {
+    // Incremented whenever a run is started or cancelled. A run only sends messages as long as its
+    // generation is the current one. Since messages are sent while holding the lock on this state,
+    // no message of a cancelled run can be sent after the messages of the run that replaced it.
+    generation: u64,
+    // The command of the last run that was started. This is the command that is run again when
+    // watch mode detects a change.
+    last_cmd: Option<BuilderCmdWrap>,
+    // The process of the run that is in progress, if any.
+    child: Option<Child>,
+}
+
+impl RunnerState {
+    fn cancel_run(&mut self) {
+        self.generation += 1;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl BuilderRunner {
+    fn start_run(&self, cmd_wrap: BuilderCmdWrap) {
+        let run = {
+            let mut state = self.state.lock().unwrap();
+            state.cancel_run();
+            state.last_cmd = Some(cmd_wrap.clone());
+            BuilderRun {
+                cmd_id: cmd_wrap.cmd_id,
+                generation: state.generation,
+                runner: self.clone(),
+            }
+        };
+        match cmd_wrap.cmd {
+            BuilderCmd::CargoCheck => {
+                run.send_msg(BuilderMsg::RunStarted);
+                // the processes of the run are started on its own thread, so a slow start does not
+                // block the connection
+                thread::spawn(move || run.cargo_run());
+            }
+            BuilderCmd::Watch {..} | BuilderCmd::WatchPaths {..} => unreachable!()
         }
     }
     
-    fn send_bare_msg(&self, cmd_id: BuilderCmdId, level: BuilderMsgLevel, line: String) {
-        self.msg_sender.send_message(
-            cmd_id.wrap_msg(BuilderMsg::Bare(BuilderMsgBare {
-                line,
-                level
-            }))
-        );
+    fn rerun_last_cmd(&self) {
+        let last_cmd = self.state.lock().unwrap().last_cmd.clone();
+        if let Some(cmd_wrap) = last_cmd {
+            self.start_run(cmd_wrap);
+        }
     }
     
-    fn send_location_msg(&self, cmd_id: BuilderCmdId, level: BuilderMsgLevel, file_name: String, range: Range, msg: String) {
-        self.msg_sender.send_message(
-            cmd_id.wrap_msg(BuilderMsg::Location(BuilderMsgLocation {
-                level,
-                file_name,
-                range,
-                msg
-            }))
-        );
+    fn cancel_run(&self) {
+        self.state.lock().unwrap().cancel_run();
+    }
+}
+
+// A single run of a cargo command.
+struct BuilderRun {
+    cmd_id: BuilderCmdId,
+    generation: u64,
+    runner: BuilderRunner,
+}
+
+impl BuilderRun {
+    // Sends a message for this run, unless it has been cancelled. Returns `false` if the run has
+    // been cancelled.
+    fn send_msg(&self, msg: BuilderMsg) -> bool {
+        let state = self.runner.state.lock().unwrap();
+        if state.generation != self.generation {
+            return false;
+        }
+        self.runner.msg_sender.send_message(self.cmd_id.wrap_msg(msg));
+        true
     }
     
-    fn process_compiler_message(&self, cmd_id: BuilderCmdId, msg: RustcCompilerMessage) {
+    fn send_bare_msg(&self, level: BuilderMsgLevel, line: String) -> bool {
+        self.send_msg(BuilderMsg::Bare(BuilderMsgBare {
+            line,
+            level
+        }))
+    }
+    
+    fn send_location_msg(&self, level: BuilderMsgLevel, file_name: String, range: Range, msg: String) -> bool {
+        self.send_msg(BuilderMsg::Location(BuilderMsgLocation {
+            level,
+            file_name,
+            range,
+            msg
+        }))
+    }
+    
+    fn process_compiler_message(&self, msg: RustcCompilerMessage) -> bool {
         if let Some(msg) = msg.message {
             let level = match msg.level.as_ref() {
                 "error" => BuilderMsgLevel::Error,
                 "warning" => BuilderMsgLevel::Warning,
                 other => {
-                    self.send_bare_msg(cmd_id, BuilderMsgLevel::Error, format!("process_compiler_message: unexpected level {}", other));
-                    return
+                    return self.send_bare_msg(BuilderMsgLevel::Error, format!("process_compiler_message: unexpected level {}", other));
                 }
             };
             if let Some(span) = msg.spans.iter().find( | span | span.is_primary) {
                 let range = span.to_range();
-                self.send_location_msg(cmd_id, level, span.file_name.clone(), range, msg.message.clone());
+                self.send_location_msg(level, span.file_name.clone(), range, msg.message.clone())
                 /*
                 if let Some(label) = &span.label {
-                    self.send_location_msg(cmd_id, level, span.file_name.clone(), range, label.clone());
+                    self.send_location_msg(level, span.file_name.clone(), range, label.clone());
                 }
                 else if let Some(text) = span.text.iter().next() {
-                    self.send_location_msg(cmd_id, level, span.file_name.clone(), range, text.text.clone());
+                    self.send_location_msg(level, span.file_name.clone(), range, text.text.clone());
                 }
                 else {
-                    self.send_location_msg(cmd_id, level, span.file_name.clone(), range, msg.message.clone());
+                    self.send_location_msg(level, span.file_name.clone(), range, msg.message.clone());
                 }*/
             }
             else {
-                self.send_bare_msg(cmd_id, BuilderMsgLevel::Error, format!("process_compiler_message: no span:  {}", msg.message));
+                self.send_bare_msg(BuilderMsgLevel::Error, format!("process_compiler_message: no span:  {}", msg.message))
+            }
+        }
+        else {
+            true
+        }
+    }
+    
+    fn cargo_run(&self) {
+        let args = ["check", "-p", APP_PACKAGE, "--message-format=json"];
+        let process = match self.start_next_process(&args) {
+            Some(process) => process,
+            None => return
+        };
+        if !self.cargo_check(process) {
+            return;
+        }
+        // rustc does not look inside the live_register! blocks of the app, so its live documents
+        // are checked separately
+        let args = ["run", "-q", "-p", "live_check", "--", APP_PATH];
+        let process = match self.start_next_process(&args) {
+            Some(process) => process,
+            None => return
+        };
+        if !self.live_check(process) {
+            return;
+        }
+        let mut state = self.runner.state.lock().unwrap();
+        if state.generation == self.generation {
+            if let Some(mut child) = state.child.take() {
+                let _ = child.wait();
             }
+            self.runner.msg_sender.send_message(self.cmd_id.wrap_msg(BuilderMsg::RunFinished));
         }
     }
     
-    pub fn cargo_check(&self, cmd_id: BuilderCmdId) {
-        
-        // alright lets run a cargo check and parse its output
-        let path = self.shared.read().unwrap().path.clone();
-        let args = [
-            "check",
-            "-p",
-            "cmdline_example",
-            "--message-format=json"
-        ];
-        
-        let process = ChildProcess::start("cargo", &args, path, &[]).expect("Cannot start process");
+    // Starts the next process of this run, once the previous one has exited. Returns `None` if the
+    // run has been cancelled, or if the process could not be started, which is reported as an
+    // error that finishes the run.
+    fn start_next_process(&self, args: &[&str]) -> Option<ChildProcess> {
+        let previous_child = {
+            let mut state = self.runner.state.lock().unwrap();
+            if state.generation != self.generation {
+                return None;
+            }
+            state.child.take()
+        };
+        if let Some(mut child) = previous_child {
+            let _ = child.wait();
+        }
+        // The process is started without holding the lock on the state, so starting it does not
+        // hold up cancelling the run.
+        let path = self.runner.shared.read().unwrap().path.clone();
+        let mut process = match ChildProcess::start("cargo", args, path, &[]) {
+            Ok(process) => process,
+            Err(error) => {
+                self.send_bare_msg(BuilderMsgLevel::Error, format!("Cannot start cargo {}: {}", args.join(" "), error));
+                self.send_msg(BuilderMsg::RunFinished);
+                return None;
+            }
+        };
+        let mut state = self.runner.state.lock().unwrap();
+        if state.generation != self.generation {
+            // the run was cancelled while the process was starting
+            if let Some(mut child) = process.child.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            return None;
+        }
+        state.child = process.child.take();
+        Some(process)
+    }
+    
+    // Sends the errors that live_check reports as messages. Returns `false` if the run has been
+    // cancelled.
+    fn live_check(&self, process: ChildProcess) -> bool {
         while let Ok(line) = process.line_receiver.recv() {
-            match line {
+            let is_running = match line {
+                ChildLine::StdErr(line) => match live_check_msg(&line) {
+                    Some(msg) => self.send_msg(BuilderMsg::Location(msg)),
+                    None => self.send_bare_msg(BuilderMsgLevel::Log, line)
+                }
+                ChildLine::StdOut(line) => {
+                    self.send_bare_msg(BuilderMsgLevel::Log, line)
+                }
+                ChildLine::Term => {
+                    break;
+                }
+            };
+            if !is_running {
+                return false;
+            }
+        }
+        true
+    }
+    
+    // Sends the messages of the compiler as messages. Returns `false` if the run has been
+    // cancelled.
+    fn cargo_check(&self, process: ChildProcess) -> bool {
+        // alright lets parse the output of the cargo process
+        while let Ok(line) = process.line_receiver.recv() {
+            let is_running = match line {
                 ChildLine::StdOut(line) => {
                     let parsed: Result<RustcCompilerMessage, DeJsonErr> = DeJson::deserialize_json(&line);
                     match parsed {
@@ -131,31 +341,163 @@ impl BuilderConnection {
                             // alright we have a couple of 'reasons'
                             match msg.reason.as_str() {
                                 "compiler-message" => {
-                                    //println!("OK MSG {:#?}", msg);
-                                    self.process_compiler_message(cmd_id, msg);
-                                    //println!("MSG");
+                                    self.process_compiler_message(msg)
                                 }
                                 "compiler-artifact" => {
-                                    //println!("ARTIFACT");
+                                    true
                                 }
-                                _ => ()
+                                _ => true
                             }
-                            //println!("OK MSG {:#?}", msg);
                         }
                         Err(_) => { // we should output a log string
-                            self.send_bare_msg(cmd_id, BuilderMsgLevel::Log, line);
+                            self.send_bare_msg(BuilderMsgLevel::Log, line)
                         }
                     }
                 }
                 ChildLine::StdErr(line) => {
-                    self.send_bare_msg(cmd_id, BuilderMsgLevel::Error, line);
+                    self.send_bare_msg(BuilderMsgLevel::Error, line)
                 }
                 ChildLine::Term => {
                     break;
                 }
+            };
+            if !is_running {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// The app that is checked by the builder, as a package and as a path relative to the workspace.
+const APP_PACKAGE: &str = "cmdline_example";
+const APP_PATH: &str = "examples/cmdline_example";
+
+/// Returns the message for a line of the output of live_check, if it is an error. These look like
+/// `src/app.rs:12:5: error: message`, with the line and column starting at 1.
+pub fn live_check_msg(line: &str) -> Option<BuilderMsgLocation> {
+    let (location, msg) = line.split_once(": error: ") ?;
+    let mut parts = location.rsplitn(3, ':');
+    let column: usize = parts.next() ?.parse().ok() ?;
+    let line: usize = parts.next() ?.parse().ok() ?;
+    let file_name = parts.next() ?;
+    let start = Position {line: line.checked_sub(1) ?, column: column.checked_sub(1) ?};
+    Some(BuilderMsgLocation {
+        level: BuilderMsgLevel::Error,
+        file_name: file_name.to_string(),
+        range: Range {start, end: Position {line: start.line, column: start.column + 1}},
+        msg: msg.to_string(),
+    })
+}
+
+// How often the watcher checks the files that are open in the editor for changes.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+// How many of those checks the watcher does for each check of the whole workspace, which is
+// slower since it walks every directory.
+const WATCH_POLLS_PER_WORKSPACE_POLL: usize = 8;
+// How long the workspace has to remain unchanged before the watcher starts a new run, so that a
+// burst of changes (such as saving several files at once) only causes a single run.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+// Watches the files in the workspace, and runs the last command again when any of them changes.
+//
+// Changes are detected by polling the modification times of the files. This catches changes made
+// through the collab server as well as changes made by other programs, such as a git checkout.
+// The whole workspace is walked every few seconds, and the files at the watch paths, which are
+// the files that are open in the editor, are checked more often so edits to them are seen soon.
+struct Watcher {
+    should_stop: Arc<AtomicBool>,
+}
+
+impl Watcher {
+    fn start(runner: BuilderRunner, watch_paths: Arc<Mutex<Vec<PathBuf >>>) -> Watcher {
+        let should_stop = Arc::new(AtomicBool::new(false));
+        thread::spawn({
+            let should_stop = should_stop.clone();
+            move || {
+                let root = runner.shared.read().unwrap().path.clone();
+                let workspace_paths = | paths: &[PathBuf] | [slice::from_ref(&root), paths].concat();
+                let mut paths = watch_paths.lock().unwrap().clone();
+                let mut snapshot = take_snapshot(&workspace_paths(&paths));
+                let mut last_change = None;
+                let mut poll_count = 0;
+                while !should_stop.load(Ordering::Relaxed) {
+                    thread::sleep(WATCH_POLL_INTERVAL);
+                    poll_count += 1;
+                    let new_paths = watch_paths.lock().unwrap().clone();
+                    if poll_count % WATCH_POLLS_PER_WORKSPACE_POLL == 0 {
+                        // the watch paths are included, since they can be in ignored directories
+                        let new_snapshot = take_snapshot(&workspace_paths(&new_paths));
+                        if new_snapshot != snapshot && new_paths == paths {
+                            last_change = Some(Instant::now());
+                        }
+                        snapshot = new_snapshot;
+                    }
+                    else {
+                        for (path, modified) in take_snapshot(&new_paths) {
+                            // opening a file does not change it, even if it was not seen before
+                            let is_new = !paths.contains(&path);
+                            if snapshot.insert(path, modified) != Some(modified) && !is_new {
+                                last_change = Some(Instant::now());
+                            }
+                        }
+                    }
+                    paths = new_paths;
+                    if let Some(instant) = last_change {
+                        if instant.elapsed() >= WATCH_DEBOUNCE && !should_stop.load(Ordering::Relaxed) {
+                            last_change = None;
+                            runner.rerun_last_cmd();
+                        }
+                    }
+                }
             }
-        } 
+        });
+        Watcher {should_stop}
+    }
+    
+    fn stop(self) {
+        self.should_stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Returns the modification time of each file at the given `paths`, looking into directories
+// recursively, where the same files are skipped as in the file tree of the collab server.
+pub fn take_snapshot(paths: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    // A recursive helper function for traversing the entries of a directory.
+    fn visit_directory(path: &Path, snapshot: &mut HashMap<PathBuf, SystemTime>) {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // Skip over the "target" directory and hidden files, for the same reasons the collab
+            // server leaves them out of the file tree. Changes to these should not cause a run
+            // either, and the "target" directory is changed by the run itself.
+            if entry_path.is_dir() && name == "target" || name.starts_with('.') {
+                continue;
+            }
+            if entry_path.is_dir() {
+                visit_directory(&entry_path, snapshot);
+            }
+            else if let Ok(modified) = entry.metadata().and_then( | metadata | metadata.modified()) {
+                snapshot.insert(entry_path, modified);
+            }
+        }
+    }
+    
+    let mut snapshot = HashMap::new();
+    for path in paths {
+        if path.is_dir() {
+            visit_directory(path, &mut snapshot);
+        }
+        else if let Ok(modified) = fs::metadata(path).and_then( | metadata | metadata.modified()) {
+            snapshot.insert(path.clone(), modified);
+        }
     }
+    snapshot
 }
 
 pub trait MsgSender: Send {
@@ -26,15 +26,18 @@ use {
         code_editor::{
             cursor::Cursor,
             indent_cache::IndentCache,
+            line_cursor,
+            minimap::{Minimap, MinimapAction},
             msg_cache::MsgCache
             
         },
         builder::{
             builder_protocol::{BuilderMsg, BuilderMsgLevel}
         },
-        makepad_collab_protocol::CollabRequest,
+        lsp::lsp_protocol::LspCompletionItem,
+        makepad_collab_protocol::{CollabRequest, GitDiffHunk, GitDiffKind},
     },
-    std::mem,
+    std::ops,
 };
 
 live_register!{
@@ -139,9 +142,22 @@ live_register!{
             //draw_depth: 4.0
         }
         
+        git_marker_quad: {
+            no_h_scroll: true
+        }
+        
+        annotation_text: code_text {
+            color: (COLOR_TEXT_META)
+        }
+        
         line_num_width: 45.0,
         padding_top: 30.0,
         
+        git_marker_width: 3.0
+        git_color_added: #587c0c
+        git_color_modified: #0c7d9d
+        git_color_deleted: #94151b
+        
         text_color_linenum: (COLOR_TEXT_META)
         text_color_linenum_current: (COLOR_TEXT_DEFAULT)
         text_color_indent_line:(COLOR_TEXT_DEFAULT)
@@ -155,6 +171,26 @@ live_register!{
             color: (COLOR_BG_CURSOR)
         }
         
+        delim_quad: {
+            color: (COLOR_UP_15)
+        }
+        
+        popup_quad: {
+            color: (COLOR_BG_HEADER)
+        }
+        
+        popup_selected_quad: {
+            color: (COLOR_BG_SELECTED)
+        }
+        
+        popup_text: code_text {
+            color: (COLOR_TEXT_DEFAULT)
+        }
+        
+        popup_max_lines: 12
+        
+        wrap_column: 100
+        
         state:{
             caret = {
                 default:on
@@ -199,6 +235,10 @@ pub struct CodeEditorImpl {
     #[rust] last_move_position: Option<Position>,
     #[rust] zoom_anim_center: Option<Position>,
     #[rust] zoom_last_pos: Option<Vec2>,
+    #[rust] popup: Option<Popup>,
+    #[rust] pending_scroll_pos: Option<Vec2>,
+    #[rust] wrap_mode: WrapMode,
+    #[rust] show_minimap: bool,
     
     pub scroll_view: ScrollView,
     
@@ -216,15 +256,33 @@ pub struct CodeEditorImpl {
     line_num_text: DrawText,
     indent_line_quad: DrawIndentLine,
     msg_line_quad: DrawMsgLine,
+    git_marker_quad: DrawColor,
+    annotation_text: DrawText,
+    
+    git_marker_width: f32,
+    git_color_added: Vec4,
+    git_color_modified: Vec4,
+    git_color_deleted: Vec4,
     
     text_color_linenum: Vec4,
     text_color_linenum_current: Vec4,
     text_color_indent_line: Vec4,
     
     current_line_quad: DrawColor,
+    delim_quad: DrawColor,
+    
+    popup_quad: DrawColor,
+    popup_selected_quad: DrawColor,
+    popup_text: DrawText,
+    popup_max_lines: usize,
     
     scroll_shadow: ScrollShadow,
     
+    pub minimap: Minimap,
+    
+    // The column at which lines are wrapped when the wrap mode is `WrapMode::Column`.
+    wrap_column: usize,
+    
     pub line_num_width: f32,
     caret_blink_timeout: f64,
     
@@ -272,9 +330,35 @@ pub struct DrawMsgLine {
     level: MsgLineLevel
 }
 
+/// How lines that are too long to fit are displayed. They are either not wrapped at all, soft
+/// wrapped at the width of the editor, or soft wrapped at a fixed column.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Live, LiveHook)]
+pub enum WrapMode {
+    #[pick] #[default] Off,
+    Viewport,
+    Column,
+}
+
 pub enum CodeEditorAction {
     RedrawViewsForDocument(DocumentId),
-    CursorBlink
+    CursorBlink,
+    RequestHover(SessionId),
+    RequestDefinition(SessionId),
+    RequestCompletion(SessionId),
+}
+
+/// A popup that is shown below a position in the text, such as hover information or a list of
+/// completions.
+enum Popup {
+    Hover {
+        position: Position,
+        lines: Vec<String>,
+    },
+    Completion {
+        position: Position,
+        items: Vec<LspCompletionItem>,
+        selected: usize,
+    },
 }
 
 impl CodeEditorImpl {
@@ -283,6 +367,33 @@ impl CodeEditorImpl {
         self.scroll_view.redraw(cx);
     }
     
+    pub fn scroll_pos(&self, cx: &Cx) -> Vec2 {
+        self.pending_scroll_pos.unwrap_or_else( || self.scroll_view.get_scroll_pos(cx))
+    }
+    
+    /// Scrolls to the given position. Since the position can only be clipped once we know the
+    /// size of the text, this takes effect after the next draw.
+    pub fn set_scroll_pos(&mut self, cx: &mut Cx, pos: Vec2) {
+        self.pending_scroll_pos = Some(pos);
+        self.scroll_view.redraw(cx);
+    }
+    
+    /// Sets how lines that are too long to fit in the editor are displayed.
+    pub fn set_wrap_mode(&mut self, cx: &mut Cx, wrap_mode: WrapMode) {
+        self.wrap_mode = wrap_mode;
+        self.scroll_view.redraw(cx);
+    }
+    
+    pub fn set_show_minimap(&mut self, cx: &mut Cx, show_minimap: bool) {
+        self.show_minimap = show_minimap;
+        self.scroll_view.redraw(cx);
+    }
+    
+    // Returns the width taken up by the minimap, or zero if it is not shown.
+    fn minimap_width(&self) -> f32 {
+        if self.show_minimap {self.minimap.width} else {0.0}
+    }
+    
     pub fn begin<'a>(&mut self, cx: &mut Cx2d, state: &'a EditorState) -> Result<(&'a Document, &'a DocumentInner, &'a Session), ()> {
         self.scroll_view.begin(cx, Walk::default(), Layout::flow_right()) ?;
         
@@ -314,6 +425,12 @@ impl CodeEditorImpl {
         
         self.scroll_shadow.draw(cx, &self.scroll_view, vec2(self.line_num_width, 0.));
         self.scroll_view.end(cx);
+        
+        if let Some(pos) = self.pending_scroll_pos.take() {
+            if self.scroll_view.set_scroll_pos(cx, pos) {
+                self.scroll_view.redraw(cx);
+            }
+        }
     }
     
     // lets calculate visible lines
@@ -355,7 +472,7 @@ impl CodeEditorImpl {
         
         let viewport_end = viewport_start + viewport_size;
         
-        if document_inner.text.as_lines().len() != document_inner.indent_cache.len() {
+        if document_inner.text.line_count() != document_inner.indent_cache.len() {
             panic!()
         }
         
@@ -368,9 +485,18 @@ impl CodeEditorImpl {
         let mut end = None;
         let mut max_line_width = 0;
         
-        for (line_index, text_line) in document_inner.text.as_lines().iter().enumerate() {
+        let wrap_column = match self.wrap_mode {
+            WrapMode::Off => None,
+            WrapMode::Viewport => {
+                let text_width = viewport_size.x - self.line_num_width - self.minimap_width() - self.text_glyph_size.x * 2.0;
+                Some((text_width / self.text_glyph_size.x).max(1.0) as usize)
+            }
+            WrapMode::Column => Some(self.wrap_column),
+        };
+        
+        for (line_index, line_len) in document_inner.text.line_lens().enumerate() {
             
-            max_line_width = text_line.len().max(max_line_width);
+            max_line_width = line_len.max(max_line_width);
             
             let output = compute_height(
                 cx,
@@ -383,9 +509,17 @@ impl CodeEditorImpl {
                     viewport_end: viewport_end.y
                 }
             );
-            let font_scale = 1.0 - self.max_zoom_out * output.zoom_out;
+            // Lines that are hidden by a folded range are laid out with a scale of zero, so they take
+            // up no space.
+            let font_scale = if output.is_hidden {0.0} else {1.0 - self.max_zoom_out * output.zoom_out};
             let widget_height = output.widget_height * font_scale;
-            let text_height = self.text_glyph_size.y * font_scale;
+            let wraps = match wrap_column {
+                Some(wrap_column) if line_len > wrap_column && !output.is_hidden => {
+                    line_cursor::wrap_columns(document_inner.text.line(line_index), wrap_column)
+                }
+                _ => Vec::new()
+            };
+            let text_height = self.text_glyph_size.y * font_scale * (wraps.len() + 1) as f32;
             
             lines_layout.lines.push(LineLayout {
                 start_y,
@@ -393,6 +527,8 @@ impl CodeEditorImpl {
                 widget_height,
                 total_height: text_height + widget_height,
                 font_scale,
+                is_hidden: output.is_hidden,
+                wraps,
                 zoom_out: output.zoom_out,
                 zoom_column: output.zoom_column,
                 zoom_displace: output.zoom_column as f32 * self.text_glyph_size.x * (1.0 - font_scale)
@@ -410,9 +546,12 @@ impl CodeEditorImpl {
         }
         // unwrap the computed values
         lines_layout.total_height = start_y;
+        if let Some(wrap_column) = wrap_column {
+            max_line_width = max_line_width.min(wrap_column);
+        }
         lines_layout.max_line_width = max_line_width as f32 * self.text_glyph_size.x;
         lines_layout.view_start = start.unwrap_or(0);
-        lines_layout.view_end = end.unwrap_or(document_inner.text.as_lines().len());
+        lines_layout.view_end = end.unwrap_or(document_inner.text.line_count());
         lines_layout.start_y = start_line_y.unwrap_or(0.0);
     }
     
@@ -422,6 +561,7 @@ impl CodeEditorImpl {
         // since this also locks in draw-call-order, some draw apis call new_draw_call here
         self.selection_quad.begin_many_instances(cx);
         self.current_line_quad.new_draw_call(cx);
+        self.delim_quad.begin_many_instances(cx);
         self.code_text.begin_many_instances(cx);
         self.indent_line_quad.begin_many_instances(cx);
         self.msg_line_quad.begin_many_instances(cx);
@@ -430,6 +570,7 @@ impl CodeEditorImpl {
     
     pub fn end_instances(&mut self, cx: &mut Cx2d) {
         self.selection_quad.end_many_instances(cx);
+        self.delim_quad.end_many_instances(cx);
         self.code_text.end_many_instances(cx);
         self.indent_line_quad.end_many_instances(cx);
         self.msg_line_quad.end_many_instances(cx);
@@ -475,7 +616,6 @@ impl CodeEditorImpl {
         lines_layout: &LinesLayout,
     ) {
         let origin = cx.turtle().pos();
-        let start_x = origin.x + self.line_num_width;
         let mut line_count = lines_layout.view_start;
         let mut span_iter = selections.spans();
         let mut span_slot = span_iter.next();
@@ -495,66 +635,29 @@ impl CodeEditorImpl {
             span_slot = span_iter.next();
         }
         
-        let mut selected_rects_on_previous_line = Vec::new();
-        let mut selected_rects_on_current_line = Vec::new();
-        let mut selected_rects_on_next_line = Vec::new();
-        let mut start_y = lines_layout.start_y + origin.y;
+        // The selected rects on each row of the visible lines, in the order in which the rows are
+        // displayed. We need to know the rects on the rows above and below a rect to draw it.
+        let mut selected_rects_by_row: Vec<Vec<Rect>> = Vec::new();
         let mut start = 0;
-        
-        // Iterate over each line with one line lookahead. During each iteration, we compute the
-        // selected rects for the next line, and draw the selected rects for the current line.
-        //
-        // Note that since the iterator always points to the next line, the current line is not
-        // defined until after the first iteration, and the previous line is not defined until after
-        // the second iteration.
-        for (next_line_index, next_line) in text.as_lines()[lines_layout.view_start..lines_layout.view_end].iter().enumerate() {
-            let line_index = next_line_index + lines_layout.view_start;
-            
+        for line_index in lines_layout.view_start..lines_layout.view_end {
             let layout = &lines_layout.lines[line_index];
-            let draw_height = layout.text_height;
-            let line_height = layout.total_height;
-            // Rotate so that the next line becomes the current line, the current line becomes the
-            // previous line, and the previous line becomes the next line.
-            mem::swap(&mut selected_rects_on_previous_line, &mut selected_rects_on_current_line);
-            mem::swap(&mut selected_rects_on_current_line, &mut selected_rects_on_next_line);
-            
-            // Compute the selected rects for the next line.
-            selected_rects_on_next_line.clear();
+            let first_row = selected_rects_by_row.len();
+            if !layout.is_hidden {
+                selected_rects_by_row.resize_with(first_row + layout.row_count(), Vec::new);
+            }
             while let Some(span) = span_slot {
                 let end = if span.len.line == 0 {
                     start + span.len.column
                 } else {
-                    next_line.len() + 1
+                    text.line_len(line_index) + 1
                 };
-                if span.is_included {
-                    
-                    let end_x = if end > layout.zoom_column {
-                        start_x + end as f32 * self.text_glyph_size.x * layout.font_scale
-                            + layout.zoom_displace
-                    }
-                    else {
-                        start_x + end as f32 * self.text_glyph_size.x
-                    };
-                    let start_x = if start > layout.zoom_column {
-                        start_x + start as f32 * self.text_glyph_size.x * layout.font_scale
-                            + layout.zoom_displace
+                if span.is_included && !layout.is_hidden {
+                    for (row, rect) in self.column_range_rects(layout, start, end) {
+                        selected_rects_by_row[first_row + row].push(Rect {
+                            pos: origin + rect.pos,
+                            size: rect.size,
+                        });
                     }
-                    else {
-                        start_x + start as f32 * self.text_glyph_size.x
-                    };
-                    
-                    let size_x = end_x - start_x;
-                    
-                    selected_rects_on_next_line.push(Rect {
-                        pos: Vec2 {
-                            x: start_x,
-                            y: start_y,
-                        },
-                        size: Vec2 {
-                            x: size_x,
-                            y: draw_height,
-                        },
-                    });
                 }
                 if span.len.line == 0 {
                     start = end;
@@ -571,45 +674,30 @@ impl CodeEditorImpl {
                     break;
                 }
             }
-            start_y += line_height;
-            
-            // Draw the selected rects for the current line.
-            if next_line_index > 0 {
-                for &rect in &selected_rects_on_current_line {
-                    if let Some(r) = selected_rects_on_previous_line.first() {
-                        self.selection_quad.prev_x = r.pos.x - rect.pos.x;
-                        self.selection_quad.prev_w = r.size.x;
-                    }
-                    else {
-                        self.selection_quad.prev_x = 0.0;
-                        self.selection_quad.prev_w = -1.0;
-                    }
-                    if let Some(r) = selected_rects_on_next_line.first() {
-                        self.selection_quad.next_x = r.pos.x - rect.pos.x;
-                        self.selection_quad.next_w = r.size.x;
-                    }
-                    else {
-                        self.selection_quad.next_x = 0.0;
-                        self.selection_quad.next_w = -1.0;
-                    }
-                    self.selection_quad.draw_abs(cx, rect);
-                }
-            }
         }
         
-        // Draw the selected rects for the last line.
-        for &rect in &selected_rects_on_next_line {
-            if let Some(r) = selected_rects_on_previous_line.first() {
-                self.selection_quad.prev_x = r.pos.x - rect.pos.x;
-                self.selection_quad.prev_w = r.size.x;
-            }
-            else {
-                self.selection_quad.prev_x = 0.0;
-                self.selection_quad.prev_w = -1.0;
+        for (row, selected_rects) in selected_rects_by_row.iter().enumerate() {
+            let prev = row.checked_sub(1).and_then( | row | selected_rects_by_row[row].first());
+            let next = selected_rects_by_row.get(row + 1).and_then( | rects | rects.first());
+            for &rect in selected_rects {
+                if let Some(r) = prev {
+                    self.selection_quad.prev_x = r.pos.x - rect.pos.x;
+                    self.selection_quad.prev_w = r.size.x;
+                }
+                else {
+                    self.selection_quad.prev_x = 0.0;
+                    self.selection_quad.prev_w = -1.0;
+                }
+                if let Some(r) = next {
+                    self.selection_quad.next_x = r.pos.x - rect.pos.x;
+                    self.selection_quad.next_w = r.size.x;
+                }
+                else {
+                    self.selection_quad.next_x = 0.0;
+                    self.selection_quad.next_w = -1.0;
+                }
+                self.selection_quad.draw_abs(cx, rect);
             }
-            self.selection_quad.next_x = 0.0;
-            self.selection_quad.next_w = -1.0;
-            self.selection_quad.draw_abs(cx, rect);
         }
     }
     
@@ -655,6 +743,9 @@ impl CodeEditorImpl {
         
         for i in lines_layout.view_start..lines_layout.view_end {
             let layout = &lines_layout.lines[i];
+            if layout.is_hidden {
+                continue;
+            }
             
             if i == cursor.head.line {
                 self.line_num_text.color = self.text_color_linenum_current;
@@ -677,6 +768,77 @@ impl CodeEditorImpl {
         }
     }
     
+    /// Draws a marker at the right edge of the gutter for the lines that differ from the `HEAD`
+    /// commit: a bar next to lines that were added or modified, and a short line between the lines
+    /// where lines were deleted.
+    pub fn draw_git_markers(
+        &mut self,
+        cx: &mut Cx2d,
+        lines_layout: &LinesLayout,
+        hunks: &[GitDiffHunk],
+    ) {
+        let origin = cx.turtle().rect().pos;
+        let x = origin.x + self.line_num_width - self.git_marker_width;
+        // the markers go on top of the background of the gutter
+        self.git_marker_quad.new_draw_call(cx);
+        for hunk in hunks {
+            if hunk.kind == GitDiffKind::Deleted {
+                // the deleted lines were right above `start_line`, which can be past the last line
+                let y = match lines_layout.lines.get(hunk.start_line) {
+                    Some(layout) => layout.start_y,
+                    None => match lines_layout.lines.last() {
+                        Some(layout) => layout.start_y + layout.total_height,
+                        None => continue,
+                    }
+                };
+                self.git_marker_quad.color = self.git_color_deleted;
+                self.git_marker_quad.draw_abs(cx, Rect {
+                    pos: Vec2 {x: x - self.git_marker_width, y: origin.y + y - 1.0},
+                    size: Vec2 {x: 2.0 * self.git_marker_width, y: 2.0},
+                });
+                continue;
+            }
+            self.git_marker_quad.color = if hunk.kind == GitDiffKind::Added {
+                self.git_color_added
+            } else {
+                self.git_color_modified
+            };
+            let start = hunk.start_line.max(lines_layout.view_start);
+            let end = hunk.end_line.min(lines_layout.view_end);
+            for layout in lines_layout.lines[start.min(end)..end].iter() {
+                if layout.is_hidden {
+                    continue;
+                }
+                self.git_marker_quad.draw_abs(cx, Rect {
+                    pos: Vec2 {x, y: origin.y + layout.start_y},
+                    size: Vec2 {x: self.git_marker_width, y: layout.text_height},
+                });
+            }
+        }
+    }
+    
+    /// Draws the given `annotation`, such as the commit that last changed the line, a few columns
+    /// after the end of the line with the given `line_index`.
+    pub fn draw_line_annotation(
+        &mut self,
+        cx: &mut Cx2d,
+        lines_layout: &LinesLayout,
+        text: &Text,
+        line_index: usize,
+        annotation: &str,
+    ) {
+        match lines_layout.lines.get(line_index) {
+            Some(layout) if !layout.is_hidden => {
+                self.annotation_text.font_scale = layout.font_scale;
+            }
+            _ => return
+        }
+        let position = Position {line: line_index, column: text.line(line_index).chars().count()};
+        let origin = cx.turtle().pos();
+        let pos = origin + self.position_to_vec2(position, lines_layout) + vec2(4.0 * self.text_glyph_size.x, 0.0);
+        self.annotation_text.draw_abs(cx, pos, annotation);
+    }
+    
     pub fn draw_indent_guides(
         &mut self,
         cx: &mut Cx2d,
@@ -712,7 +874,7 @@ impl CodeEditorImpl {
         &mut self,
         cx: &mut Cx2d,
         msg_cache: &MsgCache,
-        state: &EditorState,
+        messages: &[BuilderMsg],
         lines_layout: &LinesLayout,
     ) {
         let origin = cx.turtle().pos();
@@ -725,18 +887,21 @@ impl CodeEditorImpl {
         {
             let line_index = line_index + lines_layout.view_start;
             let layout = &lines_layout.lines[line_index];
+            if layout.is_hidden {
+                continue;
+            }
             for span in spans.spans() {
-                let start = self.position_to_vec2(Position {line: line_index, column: span.start_column}, lines_layout);
-                let end = self.position_to_vec2(Position {line: line_index, column: span.end_column}, lines_layout);
                 // letse draw it
-                let msg = &state.messages[span.msg_id];
+                let msg = &messages[span.msg_id];
                 match msg {
                     BuilderMsg::Location(loc) => {
                         self.msg_line_quad.level = MsgLineLevel::from(loc.level);
-                        self.msg_line_quad.draw_abs(cx, Rect {
-                            pos: origin + start,
-                            size: vec2(end.x - start.x, layout.total_height+1.0),
-                        });
+                        for (_, rect) in self.column_range_rects(layout, span.start_column, span.end_column) {
+                            self.msg_line_quad.draw_abs(cx, Rect {
+                                pos: origin + rect.pos,
+                                size: vec2(rect.size.x, rect.size.y + 1.0),
+                            });
+                        }
                     }
                     _ => ()
                 }
@@ -744,6 +909,207 @@ impl CodeEditorImpl {
         }
     }
     
+    /// Draws the text of the first message on each visible line after the end of that line.
+    pub fn draw_message_annotations(
+        &mut self,
+        cx: &mut Cx2d,
+        msg_cache: &MsgCache,
+        messages: &[BuilderMsg],
+        lines_layout: &LinesLayout,
+        text: &Text,
+    ) {
+        for line_index in lines_layout.view_start..lines_layout.view_end {
+            let span = match msg_cache.get(line_index).and_then( | line | line.spans().first()) {
+                Some(span) => span,
+                None => continue
+            };
+            if let BuilderMsg::Location(loc) = &messages[span.msg_id] {
+                self.draw_line_annotation(cx, lines_layout, text, line_index, &loc.msg);
+            }
+        }
+    }
+    
+    pub fn draw_popup(
+        &mut self,
+        cx: &mut Cx2d,
+        lines_layout: &LinesLayout,
+    ) {
+        let (position, lines, selected) = match &self.popup {
+            Some(Popup::Hover {position, lines}) => (*position, lines.clone(), None),
+            Some(Popup::Completion {position, items, selected}) => (
+                *position,
+                items.iter().map( | item | match &item.detail {
+                    Some(detail) => format!("{}  {}", item.label, detail),
+                    None => item.label.clone()
+                }).collect(),
+                Some(*selected)
+            ),
+            None => return
+        };
+        // the text might have changed since the popup was shown
+        if position.line >= lines_layout.lines.len() {
+            return
+        }
+        // scroll the list of completions so the selected one stays visible
+        let first = match selected {
+            Some(selected) if selected >= self.popup_max_lines => selected + 1 - self.popup_max_lines,
+            _ => 0
+        };
+        let visible_lines = &lines[first..(first + self.popup_max_lines).min(lines.len())];
+        
+        let origin = cx.turtle().pos();
+        // the popup goes below the row of the position
+        let layout = &lines_layout.lines[position.line];
+        let pos = origin + self.position_to_vec2(position, lines_layout) + vec2(0.0, layout.row_height());
+        let padding = 4.0;
+        let max_len = visible_lines.iter().map( | line | line.chars().count()).max().unwrap_or(0);
+        let size = vec2(
+            max_len as f32 * self.text_glyph_size.x + 2.0 * padding,
+            visible_lines.len() as f32 * self.text_glyph_size.y + 2.0 * padding
+        );
+        
+        // the popup has to go on top of everything else we drew
+        self.popup_quad.new_draw_call(cx);
+        self.popup_quad.draw_abs(cx, Rect {pos, size});
+        if let Some(selected) = selected {
+            self.popup_selected_quad.draw_abs(cx, Rect {
+                pos: pos + vec2(0.0, padding + (selected - first) as f32 * self.text_glyph_size.y),
+                size: vec2(size.x, self.text_glyph_size.y)
+            });
+        }
+        self.popup_text.new_draw_call(cx);
+        for (index, line) in visible_lines.iter().enumerate() {
+            self.popup_text.draw_abs(cx, pos + vec2(padding, padding + index as f32 * self.text_glyph_size.y), line);
+        }
+    }
+    
+    /// Starts drawing the minimap along the right edge of the editor, for a text with `line_count`
+    /// lines. Returns the range of lines for which the tokens should be drawn on the minimap, or
+    /// `None` if the minimap is not shown.
+    pub fn begin_minimap(&mut self, cx: &mut Cx2d, line_count: usize) -> Option<ops::Range<usize>> {
+        if !self.show_minimap {
+            return None
+        }
+        let Rect {pos: origin, size: viewport_size} = cx.turtle().rect();
+        let width = self.minimap.width;
+        let rect = Rect {
+            pos: vec2(origin.x + viewport_size.x - width - self.scroll_view.v_scroll.bar_size, origin.y),
+            size: vec2(width, viewport_size.y),
+        };
+        let total = self.scroll_view.get_scroll_view_total().y;
+        let visible = self.scroll_view.get_scroll_view_visible().y;
+        let scroll_fraction = if total > visible {
+            self.scroll_view.get_scroll_pos(cx).y / (total - visible)
+        }
+        else {
+            0.0
+        };
+        Some(self.minimap.begin(cx, rect, line_count, scroll_fraction))
+    }
+    
+    /// Finishes drawing the minimap, by highlighting the lines that are visible in the editor and
+    /// marking the lines that have messages in any of the given message caches.
+    pub fn end_minimap(
+        &mut self,
+        cx: &mut Cx2d,
+        lines_layout: &LinesLayout,
+        msg_caches: &[(&MsgCache, &[BuilderMsg])],
+    ) {
+        let mut markers = Vec::new();
+        for (msg_cache, messages) in msg_caches {
+            for (line_index, spans) in msg_cache.iter().enumerate() {
+                for span in spans.spans() {
+                    if let BuilderMsg::Location(loc) = &messages[span.msg_id] {
+                        markers.push((line_index, loc.level));
+                    }
+                }
+            }
+        }
+        self.minimap.end(cx, lines_layout.view_start..lines_layout.view_end, &markers);
+    }
+    
+    // Scrolls the editor so that the given `line` is centered vertically, as far as possible.
+    fn scroll_to_center_line(&mut self, cx: &mut Cx, lines_layout: &LinesLayout, line: usize) {
+        if let Some(layout) = lines_layout.lines.get(line) {
+            let pos = self.scroll_view.get_scroll_pos(cx);
+            let visible = self.scroll_view.get_scroll_view_visible();
+            let y = layout.start_y + 0.5 * layout.text_height - 0.5 * visible.y;
+            if self.scroll_view.set_scroll_pos(cx, vec2(pos.x, y.max(0.0))) {
+                self.scroll_view.redraw(cx);
+            }
+        }
+    }
+    
+    /// Shows the given hover text below the last cursor.
+    pub fn show_hover(&mut self, cx: &mut Cx, state: &EditorState, text: &str) {
+        if let Some(session_id) = self.session_id {
+            let session = &state.sessions[session_id];
+            self.popup = Some(Popup::Hover {
+                position: session.cursors.last_inserted().head,
+                lines: text.lines().map( | line | line.to_string()).collect(),
+            });
+            self.scroll_view.redraw(cx);
+        }
+    }
+    
+    /// Shows the given list of completions below the identifier before the last cursor.
+    pub fn show_completion(&mut self, cx: &mut Cx, state: &EditorState, items: Vec<LspCompletionItem>) {
+        if let Some(session_id) = self.session_id {
+            let session = &state.sessions[session_id];
+            let document = &state.documents[session.document_id];
+            let document_inner = document.inner.as_ref().unwrap();
+            self.popup = Some(Popup::Completion {
+                position: identifier_start(&document_inner.text, session.cursors.last_inserted().head),
+                items,
+                selected: 0,
+            });
+            self.scroll_view.redraw(cx);
+        }
+    }
+    
+    fn is_showing_completion(&self) -> bool {
+        match self.popup {
+            Some(Popup::Completion {..}) => true,
+            _ => false
+        }
+    }
+    
+    fn move_completion_selection(&mut self, cx: &mut Cx, up: bool) {
+        if let Some(Popup::Completion {items, selected, ..}) = &mut self.popup {
+            if up {
+                *selected = selected.checked_sub(1).unwrap_or(items.len() - 1);
+            }
+            else {
+                *selected = (*selected + 1) % items.len();
+            }
+            self.scroll_view.redraw(cx);
+        }
+    }
+    
+    // Replaces the identifier before the last cursor with the selected completion. Returns the id of
+    // the document that was edited.
+    fn accept_completion(
+        &mut self,
+        state: &mut EditorState,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) -> Option<DocumentId> {
+        let session_id = self.session_id ?;
+        let item = match self.popup.take() {
+            Some(Popup::Completion {mut items, selected, ..}) => items.swap_remove(selected),
+            _ => return None
+        };
+        let session = &state.sessions[session_id];
+        let document_id = session.document_id;
+        let document_inner = state.documents[document_id].inner.as_ref().unwrap();
+        let end = session.cursors.last_inserted().head;
+        let start = identifier_start(&document_inner.text, end);
+        let text = Text::from(item.insert_text.as_str());
+        let new_end = start + text.len();
+        state.replace_text_direct(session_id, start, end - start, text, send_request);
+        state.move_cursors_to(session_id, new_end, false);
+        Some(document_id)
+    }
+    
     pub fn draw_carets(
         &mut self,
         cx: &mut Cx2d,
@@ -787,22 +1153,46 @@ impl CodeEditorImpl {
         }
     }
     
+    pub fn draw_matching_delims(
+        &mut self,
+        cx: &mut Cx2d,
+        delims: &[Position],
+        lines_layout: &LinesLayout,
+    ) {
+        let origin = cx.turtle().pos();
+        for &delim in delims {
+            if delim.line < lines_layout.view_start || delim.line >= lines_layout.view_end {
+                continue;
+            }
+            let layout = &lines_layout.lines[delim.line];
+            let width = if delim.column >= layout.zoom_column {
+                self.text_glyph_size.x * layout.font_scale
+            }
+            else {
+                self.text_glyph_size.x
+            };
+            let pos = self.position_to_vec2(delim, lines_layout);
+            self.delim_quad.draw_abs(cx, Rect {
+                pos: pos + origin,
+                size: Vec2 {
+                    x: width,
+                    y: self.text_glyph_size.y * layout.font_scale,
+                },
+            });
+        }
+    }
+    
     pub fn draw_code_chunk(
         &mut self,
         cx: &mut Cx2d,
         font_scale: f32,
         color: Vec4,
         pos: Vec2,
-        chunk: &[char],
+        chunk: &str,
     ) {
         self.code_text.font_scale = font_scale;
         self.code_text.color = color;
-        self.code_text.draw_inner_fix_later_when_editor_rep_is_not_vec_of_char(
-            cx,
-            pos,
-            0,
-            chunk
-        );
+        self.code_text.draw_inner(cx, pos, 0, chunk);
     }
     
     pub fn draw_current_line(
@@ -853,12 +1243,24 @@ impl CodeEditorImpl {
             }
         }
         
+        // the minimap goes first, so that clicking it doesn't move the cursor
+        if self.show_minimap {
+            let mut scroll_to_line = None;
+            self.minimap.handle_event_with_fn(cx, event, &mut | _, action | match action {
+                MinimapAction::ScrollToLine(line) => scroll_to_line = Some(line)
+            });
+            if let Some(line) = scroll_to_line {
+                self.scroll_to_center_line(cx, lines_layout, line);
+            }
+        }
+        
         match event.hits(cx, self.scroll_view.area()) {
             HitEvent::Trigger(_) => { //
                 self.handle_select_scroll_in_trigger(cx, state, lines_layout);
             },
             HitEvent::FingerDown(f) => {
                 self.last_move_position = None;
+                self.popup = None;
                 self.reset_caret_blink(cx);
                 // TODO: How to handle key focus?
                 cx.set_key_focus(self.scroll_view.area());
@@ -872,6 +1274,9 @@ impl CodeEditorImpl {
                         KeyModifiers {control: true, ..} => {
                             state.add_cursor(session_id, position);
                         }
+                        KeyModifiers {shift: false, ..} if f.tap_count == 2 => {
+                            state.select_word(session_id, position);
+                        }
                         KeyModifiers {shift, ..} => {
                             state.move_cursors_to(session_id, position, shift);
                         }
@@ -900,6 +1305,105 @@ impl CodeEditorImpl {
                     }
                 }
             }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::Escape,
+                ..
+            }) if self.popup.is_some() => {
+                self.popup = None;
+                self.scroll_view.redraw(cx);
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowUp,
+                ..
+            }) if self.is_showing_completion() => {
+                self.move_completion_selection(cx, true);
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowDown,
+                ..
+            }) if self.is_showing_completion() => {
+                self.move_completion_selection(cx, false);
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::Return,
+                ..
+            }) if self.is_showing_completion() => {
+                self.reset_caret_blink(cx);
+                if let Some(document_id) = self.accept_completion(state, send_request) {
+                    self.keep_last_cursor_in_view(cx, state, lines_layout);
+                    dispatch_action(cx, CodeEditorAction::RedrawViewsForDocument(document_id))
+                }
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::F1,
+                ..
+            }) => {
+                if let Some(session_id) = self.session_id {
+                    dispatch_action(cx, CodeEditorAction::RequestHover(session_id))
+                }
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyI,
+                modifiers,
+                ..
+            }) if modifiers.control || modifiers.logo => {
+                if let Some(session_id) = self.session_id {
+                    dispatch_action(cx, CodeEditorAction::RequestHover(session_id))
+                }
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::Backslash,
+                modifiers,
+                ..
+            }) if modifiers.control || modifiers.logo => {
+                self.reset_caret_blink(cx);
+                if let Some(session_id) = self.session_id {
+                    state.move_cursors_to_matching_delims(session_id, modifiers.shift);
+                    self.keep_last_cursor_in_view(cx, state, lines_layout);
+                    self.scroll_view.redraw(cx);
+                }
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::F12,
+                ..
+            }) => {
+                if let Some(session_id) = self.session_id {
+                    dispatch_action(cx, CodeEditorAction::RequestDefinition(session_id))
+                }
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::Space,
+                modifiers,
+                ..
+            }) if modifiers.control => {
+                if let Some(session_id) = self.session_id {
+                    dispatch_action(cx, CodeEditorAction::RequestCompletion(session_id))
+                }
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowLeft,
+                modifiers,
+                ..
+            }) if modifiers.control || modifiers.alt => {
+                self.reset_caret_blink(cx);
+                if let Some(session_id) = self.session_id {
+                    state.move_cursors_word_left(session_id, modifiers.shift);
+                    self.keep_last_cursor_in_view(cx, state, lines_layout);
+                    self.scroll_view.redraw(cx);
+                }
+            }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::ArrowRight,
+                modifiers,
+                ..
+            }) if modifiers.control || modifiers.alt => {
+                self.reset_caret_blink(cx);
+                if let Some(session_id) = self.session_id {
+                    state.move_cursors_word_right(session_id, modifiers.shift);
+                    self.keep_last_cursor_in_view(cx, state, lines_layout);
+                    self.scroll_view.redraw(cx);
+                }
+            }
             HitEvent::KeyDown(KeyEvent {
                 key_code: KeyCode::ArrowLeft,
                 modifiers: KeyModifiers {shift, ..},
@@ -931,7 +1435,12 @@ impl CodeEditorImpl {
             }) => {
                 self.reset_caret_blink(cx);
                 if let Some(session_id) = self.session_id {
-                    state.move_cursors_up(session_id, shift);
+                    if self.wrap_mode == WrapMode::Off {
+                        state.move_cursors_up(session_id, shift);
+                    }
+                    else {
+                        self.move_cursors_by_row(state, lines_layout, true, shift);
+                    }
                     self.keep_last_cursor_in_view(cx, state, lines_layout);
                     self.scroll_view.redraw(cx);
                 }
@@ -943,11 +1452,29 @@ impl CodeEditorImpl {
             }) => {
                 self.reset_caret_blink(cx);
                 if let Some(session_id) = self.session_id {
-                    state.move_cursors_down(session_id, shift);
+                    if self.wrap_mode == WrapMode::Off {
+                        state.move_cursors_down(session_id, shift);
+                    }
+                    else {
+                        self.move_cursors_by_row(state, lines_layout, false, shift);
+                    }
                     self.keep_last_cursor_in_view(cx, state, lines_layout);
                     self.scroll_view.redraw(cx);
                 }
             }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::Backspace,
+                modifiers,
+                ..
+            }) if modifiers.control || modifiers.alt => {
+                self.reset_caret_blink(cx);
+                if let Some(session_id) = self.session_id {
+                    state.delete_word_backward(session_id, send_request);
+                    let session = &state.sessions[session_id];
+                    self.keep_last_cursor_in_view(cx, state, lines_layout);
+                    dispatch_action(cx, CodeEditorAction::RedrawViewsForDocument(session.document_id))
+                }
+            }
             HitEvent::KeyDown(KeyEvent {
                 key_code: KeyCode::Backspace,
                 ..
@@ -976,6 +1503,18 @@ impl CodeEditorImpl {
                     dispatch_action(cx, CodeEditorAction::RedrawViewsForDocument(session.document_id))
                 }
             }
+            HitEvent::KeyDown(KeyEvent {
+                key_code: KeyCode::Delete,
+                modifiers,
+                ..
+            }) if modifiers.control || modifiers.alt => {
+                self.reset_caret_blink(cx);
+                if let Some(session_id) = self.session_id {
+                    state.delete_word_forward(session_id, send_request);
+                    let session = &state.sessions[session_id];
+                    dispatch_action(cx, CodeEditorAction::RedrawViewsForDocument(session.document_id))
+                }
+            }
             HitEvent::KeyDown(KeyEvent {
                 key_code: KeyCode::Delete,
                 ..
@@ -1060,6 +1599,7 @@ impl CodeEditorImpl {
             },
             HitEvent::TextInput(TextInputEvent {input, ..}) => {
                 self.reset_caret_blink(cx);
+                self.popup = None;
                 if let Some(session_id) = self.session_id {
                     state.insert_text(
                         session_id,
@@ -1167,22 +1707,17 @@ impl CodeEditorImpl {
     }
     
     // coordinate maps a text position to a 2d position
-    fn position_to_vec2(&self, position: Position, lines_layout: &LinesLayout) -> Vec2 {
+    pub fn position_to_vec2(&self, position: Position, lines_layout: &LinesLayout) -> Vec2 {
         // we need to compute the position in the editor space
         let layout = &lines_layout.lines[position.line];
-        let x = if position.column >= layout.zoom_column {
-            self.line_num_width + position.column as f32 * self.text_glyph_size.x * layout.font_scale + layout.zoom_displace
-        }
-        else {
-            position.column as f32 * self.text_glyph_size.x + self.line_num_width
-        };
+        let row = layout.row(position.column);
         vec2(
-            x,
-            layout.start_y,
+            self.line_num_width + self.column_to_x(layout, row, position.column),
+            layout.start_y + row as f32 * layout.row_height(),
         )
     }
     
-    fn vec2_to_position(&self, text: &Text, vec2: Vec2, lines_layout: &LinesLayout) -> Position {
+    pub fn vec2_to_position(&self, text: &Text, vec2: Vec2, lines_layout: &LinesLayout) -> Position {
         
         if vec2.y < self.padding_top {
             return Position {
@@ -1191,27 +1726,145 @@ impl CodeEditorImpl {
             }
         }
         for (line, layout) in lines_layout.lines.iter().enumerate() {
-            if vec2.y >= layout.start_y && vec2.y <= layout.start_y + layout.total_height {
-                let start_x = vec2.x - self.line_num_width;
-                let zoom_start = layout.zoom_column as f32 * self.text_glyph_size.x;
-                let column = if start_x >= zoom_start {
-                    let scale_x = self.text_glyph_size.x * layout.font_scale;
-                    ((start_x + 0.5 * scale_x - zoom_start) / scale_x) as usize + layout.zoom_column
-                }
-                else {
-                    ((start_x + 0.5 * self.text_glyph_size.x) / self.text_glyph_size.x) as usize
-                };
+            if !layout.is_hidden && vec2.y >= layout.start_y && vec2.y <= layout.start_y + layout.total_height {
+                let row = (((vec2.y - layout.start_y) / layout.row_height()) as usize).min(layout.row_count() - 1);
                 return Position {
                     line,
-                    column: column.min(text.as_lines()[line].len()),
+                    column: self.x_to_column(layout, row, vec2.x - self.line_num_width, text.line_len(line)),
                 }
             }
         }
         
-        return Position {
-            line: text.as_lines().len() - 1,
-            column: text.as_lines().last().unwrap().len()
+        return Position::origin() + text.len()
+    }
+    
+    // Returns the x coordinate of the given `column` on the given `row` of the line with the given
+    // `layout`, relative to the start of the text. Columns before the zoom column are not scaled, but
+    // rows after the first are scaled from their start, since they are never indented.
+    fn column_to_x(&self, layout: &LineLayout, row: usize, column: usize) -> f32 {
+        if row > 0 {
+            (column - layout.row_start(row)) as f32 * self.text_glyph_size.x * layout.font_scale
         }
+        else if column >= layout.zoom_column {
+            column as f32 * self.text_glyph_size.x * layout.font_scale + layout.zoom_displace
+        }
+        else {
+            column as f32 * self.text_glyph_size.x
+        }
+    }
+    
+    // Returns the column on the given `row` of the line with the given `layout` and length
+    // `line_len` that is closest to the given x coordinate, relative to the start of the text.
+    fn x_to_column(&self, layout: &LineLayout, row: usize, x: f32, line_len: usize) -> usize {
+        let zoom_start = layout.zoom_column as f32 * self.text_glyph_size.x;
+        let scale_x = self.text_glyph_size.x * layout.font_scale;
+        let column = if row > 0 {
+            ((x + 0.5 * scale_x) / scale_x) as usize + layout.row_start(row)
+        }
+        else if x >= zoom_start {
+            ((x + 0.5 * scale_x - zoom_start) / scale_x) as usize + layout.zoom_column
+        }
+        else {
+            ((x + 0.5 * self.text_glyph_size.x) / self.text_glyph_size.x) as usize
+        };
+        // the end of a row that is followed by another is the start of that row, so the last column
+        // we can put the caret on is the one before it
+        let row_end = layout.row_end(row).map_or(line_len, | row_end | row_end - 1);
+        column.max(layout.row_start(row)).min(row_end)
+    }
+    
+    // Returns a rect for each row of the line with the given `layout` that the columns from `start`
+    // to `end` are displayed on, together with the index of the row. The rects are in the same
+    // coordinates as those returned by `position_to_vec2`.
+    fn column_range_rects(&self, layout: &LineLayout, start: usize, end: usize) -> Vec<(usize, Rect)> {
+        let mut rects = Vec::new();
+        for row in layout.row(start)..=layout.row(end) {
+            let row_start = layout.row_start(row).max(start);
+            let row_end = layout.row_end(row).map_or(end, | row_end | row_end.min(end));
+            if row_start >= row_end {
+                continue;
+            }
+            let start_x = self.column_to_x(layout, row, row_start);
+            let end_x = self.column_to_x(layout, row, row_end);
+            rects.push((row, Rect {
+                pos: vec2(
+                    self.line_num_width + start_x,
+                    layout.start_y + row as f32 * layout.row_height()
+                ),
+                size: vec2(end_x - start_x, layout.row_height()),
+            }));
+        }
+        rects
+    }
+    
+    // Returns the position on the row above or below the row of the given `position`, at the same x
+    // coordinate, or `None` if there is no such row. Lines that are hidden by a folded range are
+    // skipped.
+    fn position_on_adjacent_row(
+        &self,
+        text: &Text,
+        position: Position,
+        lines_layout: &LinesLayout,
+        up: bool,
+    ) -> Option<Position> {
+        // the text might have changed since it was last laid out
+        let layout = lines_layout.lines.get(position.line) ?;
+        let row = layout.row(position.column);
+        let x = self.column_to_x(layout, row, position.column);
+        let (line, row) = if up {
+            if row > 0 {
+                (position.line, row - 1)
+            }
+            else {
+                let line = (0..position.line).rev().find( | &line | !lines_layout.lines[line].is_hidden) ?;
+                (line, lines_layout.lines[line].row_count() - 1)
+            }
+        }
+        else if row + 1 < layout.row_count() {
+            (position.line, row + 1)
+        }
+        else {
+            let line = (position.line + 1..lines_layout.lines.len()).find( | &line | !lines_layout.lines[line].is_hidden) ?;
+            (line, 0)
+        };
+        if line >= text.line_count() {
+            return None;
+        }
+        Some(Position {
+            line,
+            column: self.x_to_column(&lines_layout.lines[line], row, x, text.line_len(line)),
+        })
+    }
+    
+    // Moves all cursors of the session one row up or down. Unlike `EditorState::move_cursors_up`
+    // and `EditorState::move_cursors_down`, this takes soft wrapped lines into account.
+    fn move_cursors_by_row(&self, state: &mut EditorState, lines_layout: &LinesLayout, up: bool, select: bool) {
+        if let Some(session_id) = self.session_id {
+            let session = &state.sessions[session_id];
+            let document = &state.documents[session.document_id];
+            let document_inner = document.inner.as_ref().unwrap();
+            let mut positions = session
+                .cursors
+                .iter()
+                .map( | cursor | self.position_on_adjacent_row(&document_inner.text, cursor.head, lines_layout, up))
+                .collect::<Vec<_>>()
+                .into_iter();
+            state.move_cursors_with(session_id, | _ | positions.next().flatten(), select);
+        }
+    }
+}
+
+// Returns the start of the identifier that ends at the given position.
+fn identifier_start(text: &Text, position: Position) -> Position {
+    let line = text.line(position.line).chars().take(position.column).collect::<Vec<_>>();
+    let len = line
+        .iter()
+        .rev()
+        .take_while( | ch | ch.is_alphanumeric() || **ch == '_')
+        .count();
+    Position {
+        line: position.line,
+        column: position.column - len,
     }
 }
 
@@ -1236,6 +1889,7 @@ pub struct LineLayoutOutput {
     pub widget_height: f32,
     pub zoom_out: f32,
     pub zoom_column: usize,
+    pub is_hidden: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -1245,12 +1899,44 @@ pub struct LineLayout {
     pub widget_height: f32,
     pub total_height: f32,
     pub font_scale: f32,
+    pub is_hidden: bool,
+    /// The columns at which the rows after the first start, if the line is soft wrapped.
+    pub wraps: Vec<usize>,
     
     pub zoom_out: f32,
     pub zoom_column: usize,
     pub zoom_displace: f32
 }
 
+impl LineLayout {
+    /// Returns the number of rows the line is displayed on, which is more than one if the line is
+    /// soft wrapped.
+    pub fn row_count(&self) -> usize {
+        self.wraps.len() + 1
+    }
+    
+    /// Returns the height of each row of the line.
+    pub fn row_height(&self) -> f32 {
+        self.text_height / self.row_count() as f32
+    }
+    
+    /// Returns the row that the given `column` of the line is displayed on.
+    pub fn row(&self, column: usize) -> usize {
+        self.wraps.partition_point( | &wrap | wrap <= column)
+    }
+    
+    /// Returns the column at which the given `row` of the line starts.
+    pub fn row_start(&self, row: usize) -> usize {
+        if row == 0 {0} else {self.wraps[row - 1]}
+    }
+    
+    /// Returns the column at which the given `row` of the line ends, or `None` if it is the last
+    /// row, which ends at the end of the line.
+    pub fn row_end(&self, row: usize) -> Option<usize> {
+        self.wraps.get(row).cloned()
+    }
+}
+
 #[derive(Clone, Default, Debug)]
 pub struct LinesLayout {
     pub view_start: usize,
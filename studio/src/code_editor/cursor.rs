@@ -1,10 +1,14 @@
 use {
-    crate::makepad_editor_core::{
-        delta::Delta,
-        position::Position,
-        size::Size,
-        text::Text,
-    },    
+    crate::{
+        makepad_micro_serde::{SerBin, DeBin, DeBinErr, SerRon, SerRonState, DeRon, DeRonState, DeRonErr},
+        makepad_editor_core::{
+            delta::Delta,
+            position::Position,
+            size::Size,
+            text::Text,
+        },
+        code_editor::line_cursor,
+    },
 };
 
 /// A type for representing a cursor in a text.
@@ -24,12 +28,11 @@ use {
 /// column it was on. If the cursor is then moved up or down again, and the line the cursor
 /// moved to is long enough, the cursor is moved back to the original column it was on.
 /// 
-/// This implementation assumes that each code point represents a single character on the screen.
-/// While this is true for the Latin script, it is definitely not true for others. To correctly
-/// implement this for all Unicode scripts, we need to group code points into grapheme clusters.
-/// Doing so requires generating several large Unicode tables, which is why we've held off from this
-/// for now.
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+/// Moving a cursor left or right moves it by one grapheme cluster rather than by one code point,
+/// so that the cursor never ends up in the middle of what is displayed as a single character on the
+/// screen (such as an emoji, or a letter followed by a combining mark). Moving a cursor up or down
+/// still moves it by code points, since that is what `max_column` counts.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, SerBin, DeBin, SerRon, DeRon)]
 pub struct Cursor {
     pub head: Position,
     pub tail: Position,
@@ -94,11 +97,11 @@ impl Cursor {
         self.head.max(self.tail)
     }
 
-    /// Moves this `Cursor` one column to the left.
+    /// Moves this `Cursor` one grapheme cluster to the left.
     ///
     /// This method takes the `text` on which the cursor operates as argument, because the structure
     /// of the text determines the behavior of the cursor when it moves. If there is no previous
-    /// column (i.e. the cursor is at the start of the line) the cursor is moved to the previous
+    /// grapheme cluster (i.e. the cursor is at the start of the line) the cursor is moved to the previous
     /// line instead. If there is no previous line either (i.e. the cursor) is at the start of the
     /// `text`, this method has no effect.
     /// 
@@ -133,9 +136,9 @@ impl Cursor {
                 return
             }
             self.head.line -= 1;
-            self.head.column = text.as_lines()[self.head.line].len();
+            self.head.column = text.line_len(self.head.line);
         } else {
-            self.head.column -= 1;
+            self.head.column = line_cursor::prev_grapheme_boundary(text.line(self.head.line), self.head.column);
         }
         if !select {
             self.tail = self.head;
@@ -143,11 +146,11 @@ impl Cursor {
         self.max_column = self.head.column;
     }
 
-    /// Moves this `Cursor` one column to the right.
+    /// Moves this `Cursor` one grapheme cluster to the right.
     ///
     /// This method takes the `text` on which the cursor operates as argument, because the structure
-    /// of the text determines the behavior of the cursor when it moves. If there is no next column
-    /// (i.e. the cursor is at the end of the line) the cursor is moved to the next line instead. If
+    /// of the text determines the behavior of the cursor when it moves. If there is no next grapheme
+    /// cluster (i.e. the cursor is at the end of the line) the cursor is moved to the next line instead. If
     /// there is no next line either (i.e. the cursor) is at the end of the `text`, this method has
     /// no effect.
     /// 
@@ -177,14 +180,14 @@ impl Cursor {
     /// );
     /// ```
     pub fn move_right(&mut self, text: &Text, select: bool) {
-        if self.head.column == text.as_lines()[self.head.line].len() {
-            if self.head.line == text.as_lines().len() - 1 {
+        if self.head.column == text.line_len(self.head.line) {
+            if self.head.line == text.line_count() - 1 {
                 return;
             }
             self.head.line += 1;
             self.head.column = 0;
         } else {
-            self.head.column += 1;
+            self.head.column = line_cursor::next_grapheme_boundary(text.line(self.head.line), self.head.column);
         }
         if !select {
             self.tail = self.head;
@@ -192,6 +195,60 @@ impl Cursor {
         self.max_column = self.head.column;
     }
     
+    /// Moves this `Cursor` to the start of the previous word.
+    ///
+    /// Any whitespace between the cursor and the previous word is skipped over. If the cursor is at
+    /// the start of the line, the cursor is moved to the end of the previous line instead. If there
+    /// is no previous line either (i.e. the cursor is at the start of the `text`), this method has no
+    /// effect.
+    ///
+    /// The `select` argument indicates whether the cursor is selecting while it moves. If `true`,
+    /// only the `head` of the cursor is changed, while the `tail` remains unchanged. Otherwise, the
+    /// `tail` is set to the same position as the `head`.
+    pub fn move_word_left(&mut self, text: &Text, select: bool) {
+        if self.head.column == 0 {
+            self.move_left(text, select);
+            return;
+        }
+        self.head.column = line_cursor::prev_word_boundary(text.line(self.head.line), self.head.column);
+        if !select {
+            self.tail = self.head;
+        }
+        self.max_column = self.head.column;
+    }
+
+    /// Moves this `Cursor` to the end of the next word.
+    ///
+    /// Any whitespace between the cursor and the next word is skipped over. If the cursor is at the
+    /// end of the line, the cursor is moved to the start of the next line instead. If there is no
+    /// next line either (i.e. the cursor is at the end of the `text`), this method has no effect.
+    ///
+    /// The `select` argument indicates whether the cursor is selecting while it moves. If `true`,
+    /// only the `head` of the cursor is changed, while the `tail` remains unchanged. Otherwise, the
+    /// `tail` is set to the same position as the `head`.
+    pub fn move_word_right(&mut self, text: &Text, select: bool) {
+        if self.head.column == text.line_len(self.head.line) {
+            self.move_right(text, select);
+            return;
+        }
+        self.head.column = line_cursor::next_word_boundary(text.line(self.head.line), self.head.column);
+        if !select {
+            self.tail = self.head;
+        }
+        self.max_column = self.head.column;
+    }
+
+    /// Selects the word that contains the given `position` in `text`.
+    ///
+    /// The `tail` of the cursor is moved to the start of the word, and the `head` to the end of the
+    /// word. If `position` lies between two words, the word after it is selected.
+    pub fn select_word(&mut self, text: &Text, position: Position) {
+        let range = line_cursor::word_range(text.line(position.line), position.column);
+        self.tail = Position {line: position.line, column: range.start};
+        self.head = Position {line: position.line, column: range.end};
+        self.max_column = self.head.column;
+    }
+
     /// Moves this `Cursor` one line up.
     /// 
     /// This method takes the `text` on which the cursor operates as argument, because the structure
@@ -230,7 +287,7 @@ impl Cursor {
         self.head.line -= 1;
         self.head.column = self
             .max_column
-            .min(text.as_lines()[self.head.line].len());
+            .min(text.line_len(self.head.line));
         if !select {
             self.tail = self.head;
         }
@@ -268,13 +325,13 @@ impl Cursor {
     /// );
     /// ```
     pub fn move_down(&mut self, text: &Text, select: bool) {
-        if self.head.line == text.as_lines().len() - 1 {
+        if self.head.line == text.line_count() - 1 {
             return;
         }
         self.head.line += 1;
         self.head.column = self
             .max_column
-            .min(text.as_lines()[self.head.line].len());
+            .min(text.line_len(self.head.line));
         if !select {
             self.tail = self.head;
         }
@@ -351,6 +408,17 @@ impl Cursor {
         self.max_column = self.head.column;
     }
 
+    /// Moves this `Cursor` to the nearest position inside the given `text` if it lies outside it.
+    pub fn clamp(&mut self, text: &Text) {
+        let head = clamp_position(text, self.head);
+        let tail = clamp_position(text, self.tail);
+        if head != self.head || tail != self.tail {
+            self.head = head;
+            self.tail = tail;
+            self.max_column = head.column;
+        }
+    }
+
     /// Shifts this `Cursor` forward by the given `offset`.
     pub fn apply_offset(&mut self, offset: Size) {
         self.head += offset;
@@ -358,3 +426,12 @@ impl Cursor {
         self.max_column = self.head.column;
     }
 }
+
+// Returns the position inside the given `text` that is nearest to the given `position`.
+fn clamp_position(text: &Text, position: Position) -> Position {
+    let line = position.line.min(text.line_count() - 1);
+    Position {
+        line,
+        column: position.column.min(text.line_len(line)),
+    }
+}
@@ -1,5 +1,6 @@
 use {
     crate::{
+        makepad_micro_serde::{SerBin, DeBin, DeBinErr, SerRon, SerRonState, DeRon, DeRonState, DeRonErr},
         makepad_editor_core::{
             delta::Delta,
             position::Position,
@@ -25,7 +26,7 @@ use {
 /// list, and the list is sorted by the start position of each cursor. The last inserted cursor is
 /// special (because it determines the scroll position in the document), so we also remember its
 /// index in the list. A `CursorSet` always contains at least one cursor.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, SerBin, DeBin, SerRon, DeRon)]
 pub struct CursorSet {
     cursors: Vec<Cursor>,
     last_inserted_index: usize,
@@ -215,15 +216,9 @@ impl CursorSet {
     pub fn select_all(&mut self, text: &Text) {
         self.cursors.clear();
         self.last_inserted_index = 0;
-        let lines = text.as_lines();
         self.cursors.push(Cursor {
             head: Position {line: 0, column: 0},
-            tail: if let Some(last) = lines.last() {
-                Position {line: text.as_lines().len() - 1, column: last.len()}
-            }
-            else {
-                Position {line: 0, column: 0}
-            },
+            tail: Position::origin() + text.len(),
             max_column: 0
         });
     }
@@ -268,7 +263,7 @@ impl CursorSet {
         self.normalize();
     }
 
-    /// Move all cursors in this `CursorSet` one column to the left.
+    /// Move all cursors in this `CursorSet` one grapheme cluster to the left.
     pub fn move_left(&mut self, text: &Text, select: bool) {
         for cursor in &mut self.cursors {
             cursor.move_left(text, select);
@@ -276,7 +271,7 @@ impl CursorSet {
         self.normalize();
     }
 
-    /// Move all cursors in this `CursorSet` one column to the right.
+    /// Move all cursors in this `CursorSet` one grapheme cluster to the right.
     pub fn move_right(&mut self, text: &Text, select: bool) {
         for cursor in &mut self.cursors {
             cursor.move_right(text, select);
@@ -284,6 +279,22 @@ impl CursorSet {
         self.normalize();
     }
 
+    /// Move all cursors in this `CursorSet` to the start of the previous word.
+    pub fn move_word_left(&mut self, text: &Text, select: bool) {
+        for cursor in &mut self.cursors {
+            cursor.move_word_left(text, select);
+        }
+        self.normalize();
+    }
+
+    /// Move all cursors in this `CursorSet` to the end of the next word.
+    pub fn move_word_right(&mut self, text: &Text, select: bool) {
+        for cursor in &mut self.cursors {
+            cursor.move_word_right(text, select);
+        }
+        self.normalize();
+    }
+
     /// Move all cursors in this `CursorSet` one line up.
     pub fn move_up(&mut self, text: &Text, select: bool) {
         for cursor in &mut self.cursors {
@@ -316,6 +327,27 @@ impl CursorSet {
         }
     }
 
+    /// Move each cursor in this `CursorSet` to the position returned by `f` for the head of that
+    /// cursor. Cursors for which `f` returns `None` are left where they are.
+    pub fn move_each_to(&mut self, mut f: impl FnMut(Position) -> Option<Position>, select: bool) {
+        for cursor in &mut self.cursors {
+            if let Some(position) = f(cursor.head) {
+                cursor.move_to(position, select);
+            }
+        }
+        self.normalize();
+    }
+
+    /// Replace all cursors in this `CursorSet` with a single cursor that selects the word at the
+    /// given `position`.
+    pub fn select_word(&mut self, text: &Text, position: Position) {
+        let mut cursor = Cursor::new();
+        cursor.select_word(text, position);
+        self.cursors.clear();
+        self.cursors.push(cursor);
+        self.last_inserted_index = 0;
+    }
+
     pub fn apply_delta(&mut self, delta: &Delta) {
         for cursor in &mut self.cursors {
             cursor.apply_delta(delta);
@@ -323,6 +355,18 @@ impl CursorSet {
         self.normalize();
     }
 
+    /// Moves each cursor in this `CursorSet` that lies outside the given `text` to the nearest
+    /// position inside it.
+    ///
+    /// This is used when the text a set of cursors refers to may have changed in the meantime,
+    /// such as when restoring the cursors of a file that was changed outside of the editor.
+    pub fn clamp(&mut self, text: &Text) {
+        for cursor in &mut self.cursors {
+            cursor.clamp(text);
+        }
+        self.normalize();
+    }
+
     pub fn apply_offsets(&mut self, offsets: &[Size]) {
         for (cursor, &offset) in self.cursors.iter_mut().zip(offsets) {
             cursor.apply_offset(offset);
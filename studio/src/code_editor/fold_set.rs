@@ -0,0 +1,122 @@
+use {
+    crate::makepad_editor_core::{
+        delta::Delta,
+        position::Position,
+        text::Text,
+    },
+    std::ops::Range,
+};
+
+/// A range of lines that can be folded.
+///
+/// The line `start_line` stays visible when the range is folded, and the lines after it, up to but
+/// not including `end_line`, are hidden. For a block, `end_line` is the line of its closing
+/// delimiter, so that the delimiter stays visible.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FoldRange {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl FoldRange {
+    /// Returns the lines that are hidden when this range is folded.
+    pub fn hidden_lines(self) -> Range<usize> {
+        self.start_line + 1..self.end_line
+    }
+}
+
+/// A type for representing the set of folded ranges in a session.
+///
+/// Each folded range is stored as an anchor at the end of its start line, rather than as a line
+/// number, so that it can be kept stable through edits by applying deltas to it, like a cursor.
+/// After the text has changed, `update` should be called with the new fold ranges of the text to
+/// drop any anchors that no longer start a range.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FoldSet {
+    anchors: Vec<Position>,
+}
+
+impl FoldSet {
+    pub fn new() -> FoldSet {
+        FoldSet::default()
+    }
+
+    /// Creates a `FoldSet` in which the ranges that start on the given `lines` are folded.
+    ///
+    /// This is used when the text is not known yet, such as when restoring the folded ranges of a
+    /// session from a saved workspace, so `update` should be called once the text is known.
+    pub fn from_lines(lines: impl IntoIterator<Item = usize>) -> FoldSet {
+        let mut anchors: Vec<Position> = lines.into_iter().map( | line | Position {line, column: 0}).collect();
+        anchors.sort();
+        anchors.dedup();
+        FoldSet {anchors}
+    }
+
+    /// Returns `true` if this `FoldSet` contains no folded ranges.
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+
+    /// Returns the start lines of the folded ranges in this `FoldSet`, in ascending order.
+    pub fn lines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.anchors.iter().map( | anchor | anchor.line)
+    }
+
+    /// Returns `true` if the range that starts on the given `line` is folded.
+    pub fn is_folded(&self, line: usize) -> bool {
+        self.anchors.binary_search_by_key(&line, | anchor | anchor.line).is_ok()
+    }
+
+    /// Folds the range that starts on the given `line` of `text`.
+    pub fn fold(&mut self, line: usize, text: &Text) {
+        if let Err(index) = self.anchors.binary_search_by_key(&line, | anchor | anchor.line) {
+            self.anchors.insert(index, Position {line, column: text.line_len(line)});
+        }
+    }
+
+    /// Unfolds the range that starts on the given `line`.
+    pub fn unfold(&mut self, line: usize) {
+        if let Ok(index) = self.anchors.binary_search_by_key(&line, | anchor | anchor.line) {
+            self.anchors.remove(index);
+        }
+    }
+
+    /// Unfolds all ranges.
+    pub fn clear(&mut self) {
+        self.anchors.clear();
+    }
+
+    pub fn apply_delta(&mut self, delta: &Delta) {
+        for anchor in &mut self.anchors {
+            *anchor = anchor.apply_delta(delta);
+        }
+    }
+
+    /// Drops each folded range that no longer starts one of the given fold `ranges` of `text`, and
+    /// moves the anchors of the others back to the end of their line.
+    pub fn update(&mut self, ranges: &[FoldRange], text: &Text) {
+        self.anchors.retain( | anchor | {
+            anchor.line < text.line_count() && ranges.iter().any( | range | range.start_line == anchor.line)
+        });
+        self.anchors.dedup_by_key( | anchor | anchor.line);
+        for anchor in &mut self.anchors {
+            anchor.column = text.line_len(anchor.line);
+        }
+    }
+
+    /// Returns for each of the first `line_count` lines whether it is hidden by one of the given
+    /// fold `ranges` that is folded.
+    pub fn hidden_lines(&self, ranges: &[FoldRange], line_count: usize) -> Vec<bool> {
+        let mut hidden_lines = vec![false; line_count];
+        for range in ranges {
+            if self.is_folded(range.start_line) {
+                for line in range.hidden_lines() {
+                    if line < line_count {
+                        hidden_lines[line] = true;
+                    }
+                }
+            }
+        }
+        hidden_lines
+    }
+}
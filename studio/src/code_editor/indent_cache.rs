@@ -16,7 +16,7 @@ pub struct IndentCache {
 impl IndentCache {
     pub fn new(text: &Text) -> IndentCache {
         let mut cache = IndentCache {
-            lines: (0..text.as_lines().len())
+            lines: (0..text.line_count())
                 .map(|_| Line::default())
                 .collect::<Vec<_>>(),
         };
@@ -48,8 +48,8 @@ impl IndentCache {
                 continue;
             }
             line.leading_whitespace = Some(
-                text.as_lines()[index]
-                    .iter()
+                text.line(index)
+                    .chars()
                     .position(|ch| !ch.is_whitespace()),
             );
         }
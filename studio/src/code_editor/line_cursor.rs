@@ -0,0 +1,202 @@
+use {
+    crate::{
+        makepad_editor_core::makepad_collections::btree_string::{self, Slice},
+        makepad_segment::cursor::char,
+    },
+    std::ops::Range,
+};
+
+/// A cursor over the `char`s of a single line of a `Text`.
+///
+/// This allows us to use the grapheme and word cursors from `makepad_segment` on the lines of a
+/// `Text`, without copying the line out of the text first. Each position is a `char` index, and
+/// each position is a `char` boundary.
+#[derive(Clone)]
+pub struct LineCursor<'a> {
+    cursor: btree_string::Cursor<'a>,
+    position: usize,
+}
+
+impl<'a> LineCursor<'a> {
+    pub fn new(line: Slice<'a>, position: usize) -> LineCursor<'a> {
+        use char::Cursor;
+
+        let mut cursor = LineCursor {
+            cursor: line.cursor_front(),
+            position: 0,
+        };
+        cursor.set_position(position);
+        cursor
+    }
+}
+
+impl<'a> char::Cursor for LineCursor<'a> {
+    fn is_at_start(&self) -> bool {
+        self.position == 0
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.cursor.is_at_end()
+    }
+
+    fn is_at_boundary(&self) -> bool {
+        true
+    }
+
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn current(&self) -> char {
+        self.cursor.char()
+    }
+
+    fn move_next(&mut self) {
+        assert!(!self.is_at_end());
+        self.cursor.move_next_char();
+        self.position += 1;
+    }
+
+    fn move_prev(&mut self) {
+        assert!(!self.is_at_start());
+        self.cursor.move_prev_char();
+        self.position -= 1;
+    }
+
+    // The grapheme and word cursors only set the position to one near the current one, so moving
+    // there one `char` at a time is cheap.
+    fn set_position(&mut self, position: usize) {
+        while self.position < position {
+            self.move_next();
+        }
+        while self.position > position {
+            self.move_prev();
+        }
+    }
+}
+
+/// Returns the column of the grapheme boundary before the given `column` in `line`, or `column`
+/// itself if it is at the start of the line.
+pub fn prev_grapheme_boundary(line: Slice<'_>, column: usize) -> usize {
+    use char::Cursor;
+
+    let mut cursor = LineCursor::new(line, column).into_grapheme_cursor();
+    if !cursor.is_at_start() {
+        cursor.move_prev();
+    }
+    cursor.position()
+}
+
+/// Returns the column of the grapheme boundary after the given `column` in `line`, or `column`
+/// itself if it is at the end of the line.
+pub fn next_grapheme_boundary(line: Slice<'_>, column: usize) -> usize {
+    use char::Cursor;
+
+    let mut cursor = LineCursor::new(line, column).into_grapheme_cursor();
+    if !cursor.is_at_end() {
+        cursor.move_next();
+    }
+    cursor.position()
+}
+
+/// Returns the column of the start of the word before the given `column` in `line`, skipping over
+/// any whitespace in between, or the start of the line if there is no such word.
+pub fn prev_word_boundary(line: Slice<'_>, column: usize) -> usize {
+    use char::Cursor;
+
+    let mut chars = LineCursor::new(line, column);
+    let mut cursor = chars.clone().into_word_cursor();
+    while !cursor.is_at_start() {
+        cursor.move_prev();
+        if !move_over_whitespace(&mut chars, cursor.position()) {
+            break;
+        }
+    }
+    cursor.position()
+}
+
+/// Returns the column of the end of the word after the given `column` in `line`, skipping over any
+/// whitespace in between, or the end of the line if there is no such word.
+pub fn next_word_boundary(line: Slice<'_>, column: usize) -> usize {
+    use char::Cursor;
+
+    let mut chars = LineCursor::new(line, column);
+    let mut cursor = chars.clone().into_word_cursor();
+    while !cursor.is_at_end() {
+        cursor.move_next();
+        if !move_over_whitespace(&mut chars, cursor.position()) {
+            break;
+        }
+    }
+    cursor.position()
+}
+
+/// Returns the range of columns of the word that contains the given `column` in `line`. If
+/// `column` is at the end of the line, this is the last word on the line.
+pub fn word_range(line: Slice<'_>, column: usize) -> Range<usize> {
+    use char::Cursor;
+
+    if line.is_empty() {
+        return column..column;
+    }
+    let column = column.min(line.char_count() - 1);
+    let mut cursor = LineCursor::new(line, column + 1).into_word_cursor();
+    cursor.move_prev();
+    let start = cursor.position();
+    cursor.move_next();
+    start..cursor.position()
+}
+
+/// Returns the columns at which `line` is broken into rows when it is soft wrapped to at most
+/// `max_columns` columns per row. These are the columns at which the second and later rows start.
+///
+/// Rows are broken at the start of a word, and whitespace is allowed to hang past the end of a row
+/// rather than starting the next one. A word that does not fit on a row by itself is broken at the
+/// maximum number of columns instead.
+pub fn wrap_columns(line: Slice<'_>, max_columns: usize) -> Vec<usize> {
+    use char::Cursor;
+
+    let max_columns = max_columns.max(1);
+    let mut wraps = Vec::new();
+    if line.char_count() <= max_columns {
+        return wraps;
+    }
+    let mut row_start = 0;
+    let mut chars = LineCursor::new(line, 0);
+    let mut cursor = chars.clone().into_word_cursor();
+    while !cursor.is_at_end() {
+        let start = cursor.position();
+        cursor.move_next();
+        let end = cursor.position();
+        let is_whitespace = move_over_whitespace(&mut chars, end);
+        if end - row_start <= max_columns || is_whitespace {
+            continue;
+        }
+        if start > row_start {
+            row_start = start;
+            wraps.push(row_start);
+        }
+        while end - row_start > max_columns {
+            row_start += max_columns;
+            wraps.push(row_start);
+        }
+    }
+    wraps
+}
+
+// Moves `cursor` to the given `position`, and returns `true` if all the `char`s it moved over are
+// whitespace.
+fn move_over_whitespace(cursor: &mut LineCursor<'_>, position: usize) -> bool {
+    use char::Cursor;
+
+    let mut is_whitespace = true;
+    while cursor.position() < position {
+        is_whitespace &= cursor.current().is_whitespace();
+        cursor.move_next();
+    }
+    while cursor.position() > position {
+        cursor.move_prev();
+        is_whitespace &= cursor.current().is_whitespace();
+    }
+    is_whitespace
+}
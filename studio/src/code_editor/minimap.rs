@@ -0,0 +1,202 @@
+use {
+    crate::{
+        makepad_platform::*,
+        builder::builder_protocol::BuilderMsgLevel,
+    },
+    std::ops::Range,
+};
+
+live_register!{
+    use makepad_component::theme::*;
+
+    Minimap: {{Minimap}} {
+        bg_quad: {
+            no_h_scroll: true
+            no_v_scroll: true
+            color: (COLOR_BG_EDITOR)
+        }
+
+        token_quad: {
+            no_h_scroll: true
+            no_v_scroll: true
+        }
+
+        view_quad: {
+            no_h_scroll: true
+            no_v_scroll: true
+            color: (COLOR_UP_10)
+        }
+
+        marker_quad: {
+            no_h_scroll: true
+            no_v_scroll: true
+        }
+
+        width: 80.0
+        line_height: 2.0
+        column_width: 1.0
+        marker_width: 3.0
+
+        color_error: (COLOR_ERROR)
+        color_warning: (COLOR_WARNING)
+    }
+}
+
+/// A scaled down overview of the text, drawn along the right edge of the editor.
+///
+/// Each line is drawn as a row that is `line_height` high, and each token on it as a block that is
+/// `column_width` wide for each of its columns, in the color of the token. If the text does not
+/// fit on the minimap, the minimap scrolls along with the editor, so that its top and bottom line
+/// up with those of the text when the editor is scrolled all the way up or down.
+///
+/// The lines that are visible in the editor are highlighted, and the lines that have errors or
+/// warnings are marked along the right edge, relative to the whole text. Clicking on the minimap
+/// scrolls the editor to the line that was clicked, and dragging scrolls it further from there.
+#[derive(Live, LiveHook)]
+pub struct Minimap {
+    bg_quad: DrawColor,
+    token_quad: DrawColor,
+    view_quad: DrawColor,
+    marker_quad: DrawColor,
+
+    pub width: f32,
+    line_height: f32,
+    column_width: f32,
+    marker_width: f32,
+
+    color_error: Vec4,
+    color_warning: Vec4,
+
+    // The rect the minimap was last drawn in.
+    #[rust] rect: Rect,
+    // The number of lines of the text the minimap was last drawn for.
+    #[rust] line_count: usize,
+    // How far the minimap was scrolled when it was last drawn.
+    #[rust] scroll_y: f32,
+    // The y coordinate at which the current drag started, and the line it started on.
+    #[rust] drag_start: Option<(f32, usize)>,
+}
+
+pub enum MinimapAction {
+    /// The editor should be scrolled so that the given line is centered.
+    ScrollToLine(usize),
+}
+
+impl Minimap {
+    /// Starts drawing the minimap in the given `rect`, for a text with `line_count` lines.
+    /// `scroll_fraction` is how far the editor is scrolled, from 0 at the top to 1 at the bottom.
+    ///
+    /// Returns the range of lines that are visible on the minimap, for which the tokens should be
+    /// drawn with `draw_token`.
+    pub fn begin(&mut self, cx: &mut Cx2d, rect: Rect, line_count: usize, scroll_fraction: f32) -> Range<usize> {
+        self.rect = rect;
+        self.line_count = line_count;
+        let overflow = line_count as f32 * self.line_height - rect.size.y;
+        self.scroll_y = overflow.max(0.0) * scroll_fraction.clamp(0.0, 1.0);
+
+        // the minimap has to go on top of the text
+        self.bg_quad.new_draw_call(cx);
+        self.bg_quad.draw_abs(cx, rect);
+        self.token_quad.new_draw_call(cx);
+
+        let start = (self.scroll_y / self.line_height) as usize;
+        let end = ((self.scroll_y + rect.size.y) / self.line_height).ceil() as usize;
+        start.min(line_count)..end.min(line_count)
+    }
+
+    /// Draws a token that is `len` columns long, and starts at the given `column` of the given
+    /// `line`, in the given `color`.
+    pub fn draw_token(&mut self, cx: &mut Cx2d, line: usize, column: usize, len: usize, color: Vec4) {
+        let max_x = self.rect.size.x - self.marker_width;
+        let x = column as f32 * self.column_width;
+        if x >= max_x {
+            return
+        }
+        let rect = Rect {
+            pos: vec2(self.rect.pos.x + x, self.line_y(line)),
+            size: vec2((len as f32 * self.column_width).min(max_x - x), self.line_height),
+        };
+        self.token_quad.color = color;
+        self.token_quad.draw_abs(cx, rect);
+    }
+
+    /// Finishes drawing the minimap, by highlighting the `visible_lines` of the editor, and marking
+    /// each line that has a message of the given level in `markers`.
+    pub fn end(&mut self, cx: &mut Cx2d, visible_lines: Range<usize>, markers: &[(usize, BuilderMsgLevel)]) {
+        let start_y = self.line_y(visible_lines.start).max(self.rect.pos.y);
+        let end_y = self.line_y(visible_lines.end).min(self.rect.pos.y + self.rect.size.y);
+        self.view_quad.new_draw_call(cx);
+        if end_y > start_y {
+            self.view_quad.draw_abs(cx, Rect {
+                pos: vec2(self.rect.pos.x, start_y),
+                size: vec2(self.rect.size.x, end_y - start_y),
+            });
+        }
+
+        // markers are placed relative to the whole text rather than to the lines on the minimap,
+        // so that they are all visible even if the minimap is scrolled
+        let scale_y = self.fitted_line_height();
+        self.marker_quad.new_draw_call(cx);
+        for &(line, level) in markers {
+            self.marker_quad.color = match level {
+                BuilderMsgLevel::Error => self.color_error,
+                BuilderMsgLevel::Warning => self.color_warning,
+                BuilderMsgLevel::Log => continue,
+            };
+            self.marker_quad.draw_abs(cx, Rect {
+                pos: vec2(
+                    self.rect.pos.x + self.rect.size.x - self.marker_width,
+                    self.rect.pos.y + line as f32 * scale_y
+                ),
+                size: vec2(self.marker_width, self.line_height.max(2.0)),
+            });
+        }
+    }
+
+    pub fn handle_event_with_fn(
+        &mut self,
+        cx: &mut Cx,
+        event: &mut Event,
+        dispatch_action: &mut dyn FnMut(&mut Cx, MinimapAction),
+    ) {
+        match event.hits(cx, self.bg_quad.area()) {
+            HitEvent::FingerHover(_) => {
+                cx.set_hover_mouse_cursor(MouseCursor::Default);
+            }
+            HitEvent::FingerDown(f) => {
+                let line = self.line_at(f.rel.y);
+                self.drag_start = Some((f.rel.y, line));
+                dispatch_action(cx, MinimapAction::ScrollToLine(line));
+            }
+            HitEvent::FingerMove(f) => {
+                // while dragging, the minimap itself scrolls, so we scroll relative to where the
+                // drag started, at the same rate as the markers are placed
+                if let Some((start_y, start_line)) = self.drag_start {
+                    let delta = ((f.rel.y - start_y) / self.fitted_line_height()) as isize;
+                    let line = (start_line as isize + delta).max(0) as usize;
+                    dispatch_action(cx, MinimapAction::ScrollToLine(line.min(self.line_count.saturating_sub(1))));
+                }
+            }
+            HitEvent::FingerUp(_) => {
+                self.drag_start = None;
+            }
+            _ => ()
+        }
+    }
+
+    // Returns the height of each line if the whole text were scaled to fit on the minimap.
+    fn fitted_line_height(&self) -> f32 {
+        (self.line_count as f32 * self.line_height).min(self.rect.size.y) / self.line_count.max(1) as f32
+    }
+
+    // Returns the y coordinate of the top of the given `line`.
+    fn line_y(&self, line: usize) -> f32 {
+        self.rect.pos.y + line as f32 * self.line_height - self.scroll_y
+    }
+
+    // Returns the line at the given y coordinate, relative to the top of the minimap.
+    fn line_at(&self, y: f32) -> usize {
+        let line = ((y + self.scroll_y) / self.line_height).max(0.0) as usize;
+        line.min(self.line_count.saturating_sub(1))
+    }
+}
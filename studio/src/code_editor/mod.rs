@@ -1,8 +1,12 @@
 pub mod code_editor_impl;
 pub mod cursor;
 pub mod cursor_set;
+pub mod fold_set;
 pub mod indent_cache;
+pub mod line_cursor;
+pub mod minimap;
 pub mod msg_cache;
+pub mod undo_tree;
 
 pub use {
     cursor::*,
@@ -1,83 +1,101 @@
 use {
-    std::{iter, ops::{Deref, Index}, slice::Iter},
+    std::{ops::{Deref, Index}, slice::Iter},
     crate::makepad_editor_core::{
         range::Range,
-        delta::{Delta, OperationRange},
+        delta::Delta,
         text::Text,
     },
 };
 
-#[derive(Clone, Debug,PartialEq)]
+/// A line-based cache containing the spans of the builder messages for each line.
+///
+/// The cache stores the range of each message in the text, so that when the text is edited, the
+/// ranges can be moved along with the code they refer to until the next builder run replaces them.
+#[derive(Clone, Debug, PartialEq)]
 pub struct MsgCache {
+    ranges: Vec<MsgRange>,
     lines: Vec<Line>,
+    is_dirty: bool,
 }
 
 impl MsgCache {
     pub fn new(text: &Text) -> MsgCache {
-        let cache = MsgCache {
-            lines: (0..text.as_lines().len()).map(|_| Line::default()).collect::<Vec<_>>(),
+        let mut cache = MsgCache {
+            ranges: Vec::new(),
+            lines: Vec::new(),
+            is_dirty: true,
         };
-        //cache.refresh(text, msg_ranges);
+        cache.refresh(text);
         cache
     }
 
+    /// Moves the range of each message through the given delta, so that it keeps referring to the
+    /// same code after the delta has been applied to the text.
     pub fn invalidate(&mut self, delta: &Delta) {
-        for operation_range in delta.operation_ranges() {
-            match operation_range {
-                OperationRange::Insert(range) => {
-                    self.lines[range.start.line] = Line::default();
-                    self.lines.splice(
-                        range.start.line..range.start.line,
-                        iter::repeat(Line::default()).take(range.end.line - range.start.line),
-                    );
-                }
-                OperationRange::Delete(range) => {
-                    self.lines.drain(range.start.line..range.end.line);
-                    self.lines[range.start.line] = Line::default();
-                }
-            }
+        for msg_range in &mut self.ranges {
+            msg_range.range = Range {
+                start: msg_range.range.start.apply_delta(delta),
+                end: msg_range.range.end.apply_delta(delta),
+            };
         }
+        self.is_dirty = true;
     }
 
-    pub fn clear(&mut self) {
-        for line in &mut self.lines{
-            line.spans.clear();
-        }
+    /// Replaces the messages in this cache with the given ones.
+    pub fn replace(&mut self, text: &Text, ranges: impl IntoIterator<Item = (usize, Range)>) {
+        self.ranges = ranges
+            .into_iter()
+            .map(|(msg_id, range)| MsgRange {msg_id, range})
+            .collect();
+        self.is_dirty = true;
+        self.refresh(text);
+    }
+
+    pub fn clear(&mut self, text: &Text) {
+        self.replace(text, None);
     }
 
-    pub fn add_range(&mut self, text:&Text, msg_id: usize, range:Range) {
-        // ok so.. we now have to go from line to line
-        let start = range.start;
-        let end = range.end;
-        let lines = text.as_lines();
-        if start.line < self.lines.len() && end.line < self.lines.len(){
-            if start.line != end.line{
-                self.lines[start.line].spans.push(BuilderMsgSpan{
+    pub fn refresh(&mut self, text: &Text) {
+        if !self.is_dirty {
+            return;
+        }
+        self.lines.clear();
+        self.lines.resize(text.line_count(), Line::default());
+        for msg_range in &self.ranges {
+            let msg_id = msg_range.msg_id;
+            let start = msg_range.range.start;
+            let end = msg_range.range.end;
+            if start.line >= self.lines.len() || end.line >= self.lines.len() {
+                continue;
+            }
+            if start.line != end.line {
+                self.lines[start.line].spans.push(BuilderMsgSpan {
                     start_column: start.column,
-                    end_column: lines[start.line].len(),
+                    end_column: text.line_len(start.line),
                     msg_id
                 });
-                for line in start.line+1..end.line{
-                    self.lines[line].spans.push(BuilderMsgSpan{
+                for line in start.line + 1..end.line {
+                    self.lines[line].spans.push(BuilderMsgSpan {
                         start_column: 0,
-                        end_column: lines[start.line].len(),
+                        end_column: text.line_len(line),
                         msg_id
                     });
                 }
-                self.lines[end.line].spans.push(BuilderMsgSpan{
+                self.lines[end.line].spans.push(BuilderMsgSpan {
                     start_column: 0,
                     end_column: end.column,
                     msg_id
                 });
             }
-            else{
-                self.lines[start.line].spans.push(BuilderMsgSpan{
+            else {
+                self.lines[start.line].spans.push(BuilderMsgSpan {
                     start_column: start.column,
                     end_column: end.column,
                     msg_id
                 });
             }
         }
+        self.is_dirty = false;
     }
 }
 
@@ -106,6 +124,12 @@ impl<'a> IntoIterator for &'a MsgCache {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MsgRange {
+    msg_id: usize,
+    range: Range,
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct BuilderMsgSpan{
     pub start_column: usize,
@@ -122,4 +146,4 @@ impl Line {
     pub fn spans(&self) -> &[BuilderMsgSpan] {
         &self.spans
     }
-}
\ No newline at end of file
+}
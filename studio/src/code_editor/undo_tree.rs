@@ -0,0 +1,243 @@
+use {
+    crate::{
+        makepad_micro_serde::{SerBin, DeBin, DeBinErr},
+        makepad_editor_core::{
+            delta::Delta,
+        },
+        code_editor::cursor_set::CursorSet,
+    },
+    std::{
+        collections::VecDeque,
+        mem,
+    },
+};
+
+/// A type for representing the undo history of a document as a tree.
+///
+/// Each node in the tree represents a state of the text. The root node represents the state of the
+/// text before any edits were made, and each other node represents the state after applying the
+/// edit on the edge from its parent. Undoing moves from the current node to its parent, and redoing
+/// moves from the current node to the child that was visited last. Making an edit after undoing
+/// does not destroy the redo history, but starts a new branch instead, so that every state the text
+/// has been in can still be reached with `move_to`.
+///
+/// Nodes are stored in an arena in the order in which they were created, so the index of a node
+/// can be used as its id, and iterating over the nodes yields them in chronological order.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct UndoTree {
+    nodes: Vec<UndoNode>,
+    current: usize,
+}
+
+impl UndoTree {
+    /// Creates an `UndoTree` with only a root node, where the root node has the given `cursors`.
+    pub fn new(cursors: CursorSet) -> UndoTree {
+        UndoTree {
+            nodes: vec![UndoNode {
+                parent: None,
+                last_child: None,
+                delta: Delta::identity(),
+                inverse_delta: Delta::identity(),
+                cursors,
+                injected_char_stack: Vec::new(),
+            }],
+            current: 0,
+        }
+    }
+
+    /// Returns the number of nodes in this `UndoTree`.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the index of the current node in this `UndoTree`.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Returns the node with the given `index`.
+    pub fn node(&self, index: usize) -> &UndoNode {
+        &self.nodes[index]
+    }
+
+    /// Returns an iterator over the nodes in this `UndoTree`, in the order in which they were
+    /// created.
+    pub fn nodes(&self) -> impl Iterator<Item = &UndoNode> {
+        self.nodes.iter()
+    }
+
+    /// Returns whether the node with the given `index` lies on the path from the root to the
+    /// current node.
+    pub fn is_ancestor_of_current(&self, index: usize) -> bool {
+        let mut current = Some(self.current);
+        while let Some(node) = current {
+            if node == index {
+                return true;
+            }
+            current = self.nodes[node].parent;
+        }
+        false
+    }
+
+    /// Records an edit that was made to the text in the state of the current node.
+    ///
+    /// The `delta` is the change made to the text, and `inverse_delta` is its inverse. The
+    /// `cursors` and `injected_char_stack` are the state of the session right before the edit,
+    /// and are stored in the current node, so they can be restored when the edit is undone.
+    ///
+    /// If `group` is `true`, and the current node is a leaf other than the root, the edit is
+    /// merged into the edge leading to the current node. Otherwise, a new child node is created,
+    /// and becomes the current node.
+    pub fn push(
+        &mut self,
+        delta: Delta,
+        inverse_delta: Delta,
+        cursors: CursorSet,
+        injected_char_stack: Vec<char>,
+        group: bool,
+    ) {
+        let index = self.nodes.len();
+        let current = &mut self.nodes[self.current];
+        if group && current.parent.is_some() && current.last_child.is_none() {
+            current.delta = mem::take(&mut current.delta).compose(delta);
+            current.inverse_delta = inverse_delta.compose(mem::take(&mut current.inverse_delta));
+            return;
+        }
+        current.cursors = cursors;
+        current.injected_char_stack = injected_char_stack;
+        current.last_child = Some(index);
+        self.nodes.push(UndoNode {
+            parent: Some(self.current),
+            last_child: None,
+            delta,
+            inverse_delta,
+            cursors: CursorSet::new(),
+            injected_char_stack: Vec::new(),
+        });
+        self.current = index;
+    }
+
+    /// Moves to the state of the node with the given `index`.
+    ///
+    /// The `cursors` and `injected_char_stack` are the state of the session in the current node,
+    /// and are stored in the current node, so they can be restored when we return to it. Returns
+    /// the delta that should be applied to the text to get from the state of the current node to
+    /// the state of the node with the given `index`, or `None` if that node is the current node.
+    pub fn move_to(
+        &mut self,
+        index: usize,
+        cursors: CursorSet,
+        injected_char_stack: Vec<char>,
+    ) -> Option<Delta> {
+        if index == self.current {
+            return None;
+        }
+        let current = &mut self.nodes[self.current];
+        current.cursors = cursors;
+        current.injected_char_stack = injected_char_stack;
+
+        let mut ancestors = Vec::new();
+        let mut node = Some(index);
+        while let Some(index) = node {
+            ancestors.push(index);
+            node = self.nodes[index].parent;
+        }
+
+        // Walk up from the current node until we reach a common ancestor with the target node.
+        let mut delta = Delta::identity();
+        let mut node = self.current;
+        while !ancestors.contains(&node) {
+            delta = delta.compose(self.nodes[node].inverse_delta.clone());
+            node = self.nodes[node].parent.unwrap();
+        }
+
+        // Then walk down from the common ancestor to the target node.
+        let position = ancestors.iter().position( | &index | index == node).unwrap();
+        for &child in ancestors[..position].iter().rev() {
+            self.nodes[node].last_child = Some(child);
+            delta = delta.compose(self.nodes[child].delta.clone());
+            node = child;
+        }
+
+        self.current = index;
+        Some(delta)
+    }
+
+    /// Returns the index of the node that undoing would move to, if any.
+    pub fn undo_target(&self) -> Option<usize> {
+        self.nodes[self.current].parent
+    }
+
+    /// Returns the index of the node that redoing would move to, if any.
+    pub fn redo_target(&self) -> Option<usize> {
+        self.nodes[self.current].last_child
+    }
+
+    /// Transforms this `UndoTree` by a remote `delta` that was applied to the text in the state of
+    /// the current node.
+    ///
+    /// The deltas on each edge are transformed so that they apply to the text with the remote delta
+    /// applied, and the cursors of each node are transformed along with them. We do this by walking
+    /// the tree outwards from the current node, transforming the remote delta across each edge as
+    /// we go, so that we always know what the remote delta looks like in the state of the node we
+    /// are visiting.
+    pub fn transform(&mut self, delta: Delta) {
+        let mut children = vec![Vec::new(); self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                children[parent].push(index);
+            }
+        }
+        let mut deltas = vec![None; self.nodes.len()];
+        self.nodes[self.current].cursors.apply_delta(&delta);
+        deltas[self.current] = Some(delta);
+        let mut queue = VecDeque::new();
+        queue.push_back(self.current);
+        while let Some(index) = queue.pop_front() {
+            let delta = deltas[index].clone().unwrap();
+            if let Some(parent) = self.nodes[index].parent {
+                if deltas[parent].is_none() {
+                    let node = &mut self.nodes[index];
+                    let (parent_delta, inverse_delta) = delta.clone().transform(mem::take(&mut node.inverse_delta));
+                    node.inverse_delta = inverse_delta;
+                    node.delta = parent_delta.clone().transform(mem::take(&mut node.delta)).1;
+                    self.nodes[parent].cursors.apply_delta(&parent_delta);
+                    deltas[parent] = Some(parent_delta);
+                    queue.push_back(parent);
+                }
+            }
+            for &child in &children[index] {
+                if deltas[child].is_some() {
+                    continue;
+                }
+                let node = &mut self.nodes[child];
+                let (child_delta, forward_delta) = delta.clone().transform(mem::take(&mut node.delta));
+                node.delta = forward_delta;
+                node.inverse_delta = child_delta.clone().transform(mem::take(&mut node.inverse_delta)).1;
+                node.cursors.apply_delta(&child_delta);
+                deltas[child] = Some(child_delta);
+                queue.push_back(child);
+            }
+        }
+    }
+}
+
+/// A node in an `UndoTree`.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct UndoNode {
+    /// The index of the parent of this node, or `None` if this is the root node.
+    pub parent: Option<usize>,
+    /// The index of the child of this node that was visited last. This is where redoing from this
+    /// node moves to.
+    pub last_child: Option<usize>,
+    /// A delta representing the change made to the text on the edge from the parent to this node.
+    pub delta: Delta,
+    /// The inverse of `delta`.
+    pub inverse_delta: Delta,
+    /// The state of the cursor set when the text was last in the state of this node. We store this
+    /// explicitly because it cannot be recovered from the deltas alone.
+    pub cursors: CursorSet,
+    /// The state of the injected character stack when the text was last in the state of this node.
+    /// We store this explicitly because it cannot be recovered from the deltas alone.
+    pub injected_char_stack: Vec<char>,
+}
@@ -11,7 +11,7 @@ use {
         net::{TcpListener, TcpStream},
         sync::mpsc::{self, Receiver, Sender, TryRecvError},
         thread,
-        path::PathBuf
+        path::{Path, PathBuf},
     },
 };
 
@@ -35,6 +35,8 @@ impl LiveHook for CollabClient {
 }
 
 pub struct CollabClientInner {
+    /// The local path of the workspace served by the collab server, if the server runs locally.
+    pub local_path: Option<PathBuf>,
     pub request_sender: Sender<CollabRequest>,
     pub action_signal: Signal,
     pub action_receiver: Receiver<CollabClientAction>,
@@ -45,6 +47,11 @@ impl CollabClient {
         self.inner.as_ref().unwrap().request_sender.send(request).unwrap();
     }
     
+    /// Returns the local path of the workspace, if the collab server runs locally.
+    pub fn local_path(&self) -> Option<&Path> {
+        self.inner.as_ref().unwrap().local_path.as_deref()
+    }
+    
     pub fn request_sender(&mut self) -> impl FnMut(CollabRequest) + '_ {
         let request_sender = &self.inner.as_ref().unwrap().request_sender;
         move | request | request_sender.send(request).unwrap()
@@ -83,7 +90,7 @@ impl CollabClientInner {
         
         let base_path = env::current_dir().unwrap();
         let final_path = base_path.join(subdir.split('/').collect::<PathBuf>());
-        let mut server = CollabServer::new(final_path);
+        let mut server = CollabServer::new(final_path.clone());
         spawn_local_request_handler(
             request_receiver,
             server.connect(Box::new({
@@ -99,6 +106,7 @@ impl CollabClientInner {
         spawn_connection_listener(TcpListener::bind("127.0.0.1:0").unwrap(), server);
         
         Self {
+            local_path: Some(final_path),
             request_sender,
             action_signal,
             action_receiver
@@ -115,6 +123,7 @@ impl CollabClientInner {
         spawn_response_or_notification_receiver(stream, action_signal, action_sender,);
         
         Self {
+            local_path: None,
             request_sender,
             action_signal,
             action_receiver
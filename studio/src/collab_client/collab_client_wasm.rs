@@ -1,6 +1,7 @@
 use {
     std::cell::RefCell,
     std::rc::Rc,
+    std::path::Path,
     crate::{
         makepad_micro_serde::*,
         makepad_platform::*,
@@ -54,6 +55,12 @@ impl CollabClient {
         Cx::post_signal(self.signal);
     }
     
+    /// Returns the local path of the workspace, if the collab server runs locally. In the browser,
+    /// it never does.
+    pub fn local_path(&self) -> Option<&Path> {
+        None
+    }
+    
     pub fn request_sender(&mut self) -> impl FnMut(CollabRequest) + '_ {
         let requests = self.requests.clone();
         let signal = self.signal;
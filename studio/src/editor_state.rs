@@ -3,29 +3,43 @@ use {
         makepad_platform::*,
         makepad_editor_core::{
             delta::{self, Delta},
+            makepad_collections::btree_string::Slice,
             position::Position,
             position_set::PositionSet,
+            range::Range,
             range_set::RangeSet,
             size::Size,
             text::Text,
         },
-        builder::builder_protocol::BuilderMsg,
+        builder::builder_protocol::{BuilderCmdId, BuilderMsg, BuilderMsgWrap},
         code_editor::{
             cursor_set::CursorSet,
+            fold_set::{FoldRange, FoldSet},
             indent_cache::IndentCache,
+            line_cursor,
             msg_cache::MsgCache,
+            undo_tree::UndoTree,
         },
-        rust_editor::rust_tokenizer::token_cache::TokenCache,
+        rust_editor::rust_tokenizer::{
+            full_token::{Delim, FullToken, TokenWithLen},
+            token_cache::TokenCache,
+        },
+        makepad_micro_serde::{SerBin, DeBin, DeBinErr},
         makepad_collab_protocol::{
             CollabRequest,
+            GitBlameLine,
+            GitDiffHunk,
             TextFileId,
-            unix_path::UnixPathBuf,
+            unix_path::{UnixPath, UnixPathBuf},
         },
         editors::EditorViewId,
+        languages::LanguageKind,
+        lsp::lsp_protocol::LspContentChange,
     },
 
     std::{
-        collections::{HashMap, HashSet, VecDeque},
+        collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
+        hash::{Hash, Hasher},
         iter,
         mem,
     },
@@ -65,7 +79,24 @@ pub struct EditorState {
     /// The queue of outstanding documents for this code editor. A document is outstanding if it has
     /// been created, but we have not yet received its contents from the collab server.
     pub outstanding_document_queue: VecDeque<DocumentId>,
+    /// The messages of the last builder run that finished.
     pub messages: Vec<BuilderMsg>,
+    /// The id of the builder run that is currently in progress, if any.
+    pub builder_run: Option<BuilderCmdId>,
+    /// The messages received so far for the builder run that is currently in progress. These
+    /// replace `messages` once the run finishes.
+    pub pending_messages: Vec<BuilderMsg>,
+    /// The errors in the live documents that studio itself is built from, and in the shaders that
+    /// are compiled from them. These are updated as the documents are edited, rather than by
+    /// builder runs.
+    pub live_messages: Vec<BuilderMsg>,
+    /// The undo history of each document that was closed, by file path. This is used to restore
+    /// the undo history of a document when its file is reopened, also after a restart, since the
+    /// histories are saved with the workspace.
+    pub undo_histories: HashMap<UnixPathBuf, UndoHistory>,
+    /// Whether a document was created or destroyed since the last call to
+    /// `take_documents_changed`.
+    documents_changed: bool,
 }
 
 impl EditorState {
@@ -91,6 +122,7 @@ impl EditorState {
             cursors: CursorSet::new(),
             selections: RangeSet::new(),
             carets: PositionSet::new(),
+            folds: FoldSet::new(),
             document_id,
         });
         let document = &mut self.documents[document_id];
@@ -143,6 +175,7 @@ impl EditorState {
                     inner: None,
                 });
                 self.documents_by_path.insert(path.clone(), document_id);
+                self.documents_changed = true;
                 self.outstanding_document_queue.push_back(document_id);
                 send_request(CollabRequest::OpenFile(path));
                 document_id
@@ -166,29 +199,124 @@ impl EditorState {
     ) -> DocumentId {
         let document_id = self.outstanding_document_queue.pop_front().unwrap();
         let document = &mut self.documents[document_id];
-        let token_cache = TokenCache::new(&text);
+        let token_cache = TokenCache::with_language(LanguageKind::from_path(&document.path), &text);
+        let fold_ranges = token_cache.fold_ranges();
+        // The cursors and folded ranges of a session can be set before its document is initialized
+        // (for instance, when they are restored from a saved workspace), so make sure they lie
+        // inside the text.
+        for session_id in &document.session_ids {
+            let session = &mut self.sessions[*session_id];
+            session.cursors.clamp(&text);
+            session.folds.update(&fold_ranges, &text);
+            session.update_selections_and_carets();
+        }
         let indent_cache = IndentCache::new(&text);
-        let msg_cache = MsgCache::new(&text);
+        let mut msg_cache = MsgCache::new(&text);
+        msg_cache.replace(&text, msg_ranges_for_path(&self.messages, &document.path));
+        let lsp_msg_cache = MsgCache::new(&text);
+        let mut live_msg_cache = MsgCache::new(&text);
+        live_msg_cache.replace(&text, msg_ranges_for_path(&self.live_messages, &document.path));
+        
+        // Restore the undo history for this document, unless the file was changed since the
+        // history was saved.
+        let undo_tree = self.undo_histories
+            .remove(&document.path)
+            .filter( | history | history.text_hash == text_hash(&text))
+            .map_or_else( || UndoTree::new(CursorSet::new()), | history | history.undo_tree);
 
         document.inner = Some(DocumentInner {
             file_id,
             revision: revision as usize,
             text,
             token_cache,
+            fold_ranges,
             indent_cache,
             msg_cache,
+            lsp_changes: None,
+            lsp_messages: Vec::new(),
+            lsp_msg_cache,
+            live_msg_cache,
+            live_edited: false,
             edit_group: None,
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
+            undo_tree,
             outstanding_deltas: VecDeque::new(),
+            git_hunks: Vec::new(),
+            git_blame: None,
         });
         self.documents_by_file.insert(file_id, document_id);
         if document.should_be_destroyed {
             self.destroy_document_deferred(document_id, send_request);
+        } else {
+            send_request(CollabRequest::GitDiff(document.path.clone()));
         }
         document_id
     }
 
+    /// Handles a message from the builder.
+    ///
+    /// Each builder run is identified by the id of the command that started it, which acts as the
+    /// generation of the messages it produces. Messages are collected while a run is in progress,
+    /// and replace the current messages once it finishes, so that the messages for a file are
+    /// always replaced as a whole rather than appended to. Messages from any run other than the one
+    /// that was started last are stale, and are dropped.
+    ///
+    /// Returns `true` if the current messages were replaced.
+    pub fn handle_builder_msg(&mut self, wrap: BuilderMsgWrap) -> bool {
+        match wrap.msg {
+            BuilderMsg::RunStarted => {
+                self.builder_run = Some(wrap.cmd_id);
+                self.pending_messages.clear();
+                false
+            }
+            _ if self.builder_run != Some(wrap.cmd_id) => false,
+            BuilderMsg::RunFinished => {
+                self.builder_run = None;
+                self.messages = mem::take(&mut self.pending_messages);
+                for document in self.documents.values_mut() {
+                    if let Some(inner) = &mut document.inner {
+                        inner.msg_cache.replace(
+                            &inner.text,
+                            msg_ranges_for_path(&self.messages, &document.path),
+                        );
+                    }
+                }
+                true
+            }
+            msg => {
+                self.pending_messages.push(msg);
+                false
+            }
+        }
+    }
+
+    /// Replaces the diagnostics from the language server for the document with the given `path`.
+    ///
+    /// Returns the id of the document, or `None` if there is no initialized document for `path`.
+    pub fn set_lsp_messages(
+        &mut self,
+        path: &UnixPath,
+        messages: Vec<BuilderMsg>,
+    ) -> Option<DocumentId> {
+        let document_id = *self.documents_by_path.get(path) ?;
+        let inner = self.documents[document_id].inner.as_mut() ?;
+        inner.lsp_msg_cache.replace(&inner.text, msg_ranges_for_path(&messages, path));
+        inner.lsp_messages = messages;
+        Some(document_id)
+    }
+
+    /// Replaces the errors in the live documents of studio itself.
+    pub fn set_live_messages(&mut self, messages: Vec<BuilderMsg>) {
+        self.live_messages = messages;
+        for document in self.documents.values_mut() {
+            if let Some(inner) = &mut document.inner {
+                inner.live_msg_cache.replace(
+                    &inner.text,
+                    msg_ranges_for_path(&self.live_messages, &document.path),
+                );
+            }
+        }
+    }
+
     /// Schedules the document with the given `document_id` to be destroyed.
     ///
     /// If the document is already initialized, it is destroyed immediately, and the `send_request`
@@ -214,15 +342,48 @@ impl EditorState {
         document_id: DocumentId,
         send_request: &mut dyn FnMut(CollabRequest),
     ) {
-        let document = &mut self.documents[document_id];
-        let inner = document.inner.as_ref().unwrap();
+        let document = self.documents.remove(&document_id).unwrap();
+        let inner = document.inner.unwrap();
         let file_id = inner.file_id;
         self.documents_by_file.remove(&file_id);
         self.documents_by_path.remove(&document.path);
-        self.documents.remove(&document_id);
+        self.documents_changed = true;
+        self.undo_histories.insert(document.path, UndoHistory {
+            text_hash: text_hash(&inner.text),
+            undo_tree: inner.undo_tree,
+        });
         send_request(CollabRequest::CloseFile(file_id))
     }
 
+    /// Returns whether a document was created or destroyed since the last call, so that state that
+    /// depends on the set of open files only has to be updated when it changes.
+    pub fn take_documents_changed(&mut self) -> bool {
+        std::mem::take(&mut self.documents_changed)
+    }
+    
+    /// Returns the undo history of each document, including the ones that are still open, so that
+    /// they can be saved with the workspace and restored with `undo_histories`.
+    pub fn capture_undo_histories(&self) -> HashMap<UnixPathBuf, UndoHistory> {
+        let mut undo_histories = self.undo_histories.clone();
+        for document in self.documents.values() {
+            if let Some(inner) = &document.inner {
+                undo_histories.insert(document.path.clone(), UndoHistory {
+                    text_hash: text_hash(&inner.text),
+                    undo_tree: inner.undo_tree.clone(),
+                });
+            }
+        }
+        undo_histories
+    }
+
+    /// Replaces the cursor set of the session with the given `session_id`.
+    pub fn set_cursors(&mut self, session_id: SessionId, cursors: CursorSet) {
+        let session = &mut self.sessions[session_id];
+        session.cursors = cursors;
+        session.update_selections_and_carets();
+        self.unfold_at_carets(session_id);
+    }
+
     /// Adds a cursor to the cursor set of the session with the given `session_id`, wotj tje caret
     /// at the given position.
     pub fn add_cursor(&mut self, session_id: SessionId, position: Position) {
@@ -230,6 +391,7 @@ impl EditorState {
         session.cursors.add(position);
         session.update_selections_and_carets();
         session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
     }
 
     // Replaces the cursor set of the session with the given `session_id` with a single cursor, such
@@ -244,18 +406,19 @@ impl EditorState {
         session.injected_char_stack.clear();
     }
 
-    /// Move all cursors in the cursor set of the session with the given `session_id` one column to
-    /// the left.
+    /// Move all cursors in the cursor set of the session with the given `session_id` one grapheme
+    /// cluster to the left.
     pub fn move_cursors_left(&mut self, session_id: SessionId, select: bool) {
         let session = &mut self.sessions[session_id];
         let document = &self.documents[session.document_id];
         let document_inner = document.inner.as_ref().unwrap();
         session.cursors.move_left(&document_inner.text, select);
         session.update_selections_and_carets();
+        self.unfold_at_carets(session_id);
     }
 
-    /// Move all cursors in the cursor set of the session with the given `session_id` one column to
-    /// the left.
+    /// Move all cursors in the cursor set of the session with the given `session_id` one grapheme
+    /// cluster to the right.
     pub fn move_cursors_right(&mut self, session_id: SessionId, select: bool) {
         let session = &mut self.sessions[session_id];
         let document = &self.documents[session.document_id];
@@ -263,6 +426,31 @@ impl EditorState {
         session.cursors.move_right(&document_inner.text, select);
         session.update_selections_and_carets();
         session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
+    }
+
+    /// Move all cursors in the cursor set of the session with the given `session_id` to the start
+    /// of the previous word.
+    pub fn move_cursors_word_left(&mut self, session_id: SessionId, select: bool) {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        session.cursors.move_word_left(&document_inner.text, select);
+        session.update_selections_and_carets();
+        session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
+    }
+
+    /// Move all cursors in the cursor set of the session with the given `session_id` to the end of
+    /// the next word.
+    pub fn move_cursors_word_right(&mut self, session_id: SessionId, select: bool) {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        session.cursors.move_word_right(&document_inner.text, select);
+        session.update_selections_and_carets();
+        session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
     }
 
     /// Move all cursors in the cursor set of the session with the given `session_id` one line up.
@@ -273,6 +461,7 @@ impl EditorState {
         session.cursors.move_up(&document_inner.text, select);
         session.update_selections_and_carets();
         session.injected_char_stack.clear();
+        self.skip_folded_lines(session_id, false, select);
     }
 
     /// Move all cursors in the cursor set of the session with the given `session_id` one line down.
@@ -283,6 +472,23 @@ impl EditorState {
         session.cursors.move_down(&document_inner.text, select);
         session.update_selections_and_carets();
         session.injected_char_stack.clear();
+        self.skip_folded_lines(session_id, true, select);
+    }
+
+    /// Move each cursor in the cursor set of the session with the given `session_id` to the
+    /// position returned by `f` for its caret. Cursors for which `f` returns `None` are left where
+    /// they are.
+    pub fn move_cursors_with(
+        &mut self,
+        session_id: SessionId,
+        f: impl FnMut(Position) -> Option<Position>,
+        select: bool,
+    ) {
+        let session = &mut self.sessions[session_id];
+        session.cursors.move_each_to(f, select);
+        session.update_selections_and_carets();
+        session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
     }
 
     /// Move all cursors in the cursor set of the session with the given `session_id` to the given
@@ -292,6 +498,142 @@ impl EditorState {
         session.cursors.move_to(position, select);
         session.update_selections_and_carets();
         session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
+    }
+
+    /// Move each cursor in the cursor set of the session with the given `session_id` that is next to
+    /// a delimiter to the delimiter that matches it.
+    pub fn move_cursors_to_matching_delims(&mut self, session_id: SessionId, select: bool) {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        session.cursors.move_each_to( | position | {
+            document_inner
+                .token_cache
+                .find_matching_delims(position)
+                .map( | (_, other) | other)
+        }, select);
+        session.update_selections_and_carets();
+        session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
+    }
+
+    /// Replaces the cursor set of the session with the given `session_id` with a single cursor that
+    /// selects the word at the given `position`.
+    pub fn select_word(&mut self, session_id: SessionId, position: Position) {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        session.cursors.select_word(&document_inner.text, position);
+        session.update_selections_and_carets();
+        session.injected_char_stack.clear();
+        self.unfold_at_carets(session_id);
+    }
+
+    /// Folds the fold range that starts on the given `line` in the session with the given
+    /// `session_id`. Carets that would be hidden are moved to the end of that line.
+    ///
+    /// Returns `false` if no fold range starts on that line.
+    pub fn fold_line(&mut self, session_id: SessionId, line: usize) -> bool {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        if !document_inner.fold_ranges.iter().any( | range | range.start_line == line) {
+            return false;
+        }
+        session.folds.fold(line, &document_inner.text);
+        self.move_carets_out_of_folds(session_id);
+        true
+    }
+
+    /// Unfolds the fold range that starts on the given `line` in the session with the given
+    /// `session_id`.
+    pub fn unfold_line(&mut self, session_id: SessionId, line: usize) {
+        self.sessions[session_id].folds.unfold(line);
+    }
+
+    /// Folds all fold ranges in the session with the given `session_id`.
+    pub fn fold_all(&mut self, session_id: SessionId) {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        for range in &document_inner.fold_ranges {
+            session.folds.fold(range.start_line, &document_inner.text);
+        }
+        self.move_carets_out_of_folds(session_id);
+    }
+
+    /// Unfolds all fold ranges in the session with the given `session_id`.
+    pub fn unfold_all(&mut self, session_id: SessionId) {
+        self.sessions[session_id].folds.clear();
+    }
+
+    /// Replaces the folded ranges of the session with the given `session_id` with the ranges that
+    /// start on the given `lines`.
+    ///
+    /// This can be called before the document of the session is initialized, in which case lines
+    /// that do not start a fold range are dropped once it is.
+    pub fn set_folded_lines(&mut self, session_id: SessionId, lines: &[usize]) {
+        let session = &mut self.sessions[session_id];
+        session.folds = FoldSet::from_lines(lines.iter().cloned());
+        let document = &self.documents[session.document_id];
+        if let Some(document_inner) = &document.inner {
+            session.folds.update(&document_inner.fold_ranges, &document_inner.text);
+            self.move_carets_out_of_folds(session_id);
+        }
+    }
+
+    // Moves each caret of the session with the given `session_id` that is hidden by a folded range
+    // to the end of the line before the hidden lines.
+    fn move_carets_out_of_folds(&mut self, session_id: SessionId) {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        let text = &document_inner.text;
+        let hidden_lines = session.folds.hidden_lines(&document_inner.fold_ranges, text.line_count());
+        session.cursors.move_each_to( | position | {
+            if !hidden_lines[position.line] {
+                return None;
+            }
+            let line = (0..position.line).rev().find( | &line | !hidden_lines[line]).unwrap();
+            Some(Position {line, column: text.line_len(line)})
+        }, false);
+        session.update_selections_and_carets();
+    }
+
+    // Moves each caret of the session with the given `session_id` that is hidden by a folded range
+    // to the nearest visible line above it, or below it if `down` is `true` (or the other way
+    // around if there is no such line). This is used after moving carets up or down, so that they
+    // skip over folded ranges.
+    fn skip_folded_lines(&mut self, session_id: SessionId, down: bool, select: bool) {
+        let session = &mut self.sessions[session_id];
+        if session.folds.is_empty() {
+            return;
+        }
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        let text = &document_inner.text;
+        let hidden_lines = session.folds.hidden_lines(&document_inner.fold_ranges, text.line_count());
+        session.cursors.move_each_to( | position | {
+            if !hidden_lines[position.line] {
+                return None;
+            }
+            let line_below = (position.line..hidden_lines.len()).find( | &line | !hidden_lines[line]);
+            let line_above = (0..position.line).rev().find( | &line | !hidden_lines[line]);
+            let line = if down {line_below.or(line_above)} else {line_above.or(line_below)}?;
+            Some(Position {line, column: position.column.min(text.line_len(line))})
+        }, select);
+        session.update_selections_and_carets();
+    }
+
+    // Unfolds each folded range of the session with the given `session_id` that hides one of its
+    // carets, so that carets that are moved into a folded range are always visible.
+    fn unfold_at_carets(&mut self, session_id: SessionId) {
+        let session = &mut self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        if let Some(document_inner) = &document.inner {
+            session.unfold_at_carets(&document_inner.fold_ranges);
+        }
     }
 
     pub fn replace_text_direct(
@@ -318,6 +660,58 @@ impl EditorState {
         self.edit(session_id, None, delta, &offsets, send_request);
     }
 
+    /// Replaces each of the given ranges in the document of the session with the given
+    /// `session_id` with the given `text`, as a single edit. Returns the number of ranges that were
+    /// replaced.
+    ///
+    /// Each range is paired with the text that it is expected to contain. Ranges that no longer
+    /// contain their expected text, for instance because the document was edited since they were
+    /// computed, are skipped. The ranges should be sorted, and should not overlap.
+    pub fn replace_ranges(
+        &mut self,
+        session_id: SessionId,
+        ranges: &[(Range, String)],
+        text: &Text,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) -> usize {
+        let session = &self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+
+        let mut builder = delta::Builder::new();
+        let mut position = Position::origin();
+        let mut count = 0;
+        for (range, expected_text) in ranges {
+            if range.start < position
+                || range.end.line >= document_inner.text.line_count()
+                || range.end.column > document_inner.text.line_len(range.end.line) {
+                continue;
+            }
+            let mut actual_text = String::new();
+            document_inner.text.append_to_string(*range, &mut actual_text);
+            if actual_text != *expected_text {
+                continue;
+            }
+            builder.retain(range.start - position);
+            builder.delete(range.end - range.start);
+            builder.insert(text.clone());
+            position = range.end;
+            count += 1;
+        }
+        if count == 0 {
+            return 0;
+        }
+        let delta = builder.build();
+
+        let mut offsets = Vec::new();
+        for _ in &session.cursors {
+            offsets.push(Size::zero());
+        }
+
+        self.edit(session_id, None, delta, &offsets, send_request);
+        count
+    }
+
     /// For each cursor in the cursor set of the session with the given `session_id`, removes the
     /// selection of the cursor, and then inserts the given text at the caret of the cursor.
     pub fn insert_text(
@@ -327,39 +721,45 @@ impl EditorState {
         send_request: &mut dyn FnMut(CollabRequest),
     ) {
         let session = &self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+
+        // Only single characters get special treatment. Anything else (such as pasted text) is
+        // inserted as is.
+        let mut chars = text.line(0).chars();
+        let ch = match (text.line_count(), chars.next(), chars.next()) {
+            (1, Some(ch), None) => Some(ch),
+            _ => None,
+        };
 
         // If the character to be inserted is the same as an automatically injected character, we
         // skip over the automatically injected character rather than insert the same character
         // again.
-        if let Some(ch) = text.as_lines().first().and_then( | line | line.first()) {
-            if let Some(injected_char) = session.injected_char_stack.last() {
-                if ch == injected_char {
-                    let session = &mut self.sessions[session_id];
-                    let document = &self.documents[session.document_id];
-                    let document_inner = document.inner.as_ref().unwrap();
-                    session.cursors.move_right(&document_inner.text, false);
-                    session.update_selections_and_carets();
-                    session.injected_char_stack.pop();
-                    return;
-                }
-            }
+        let skip_injected_char = ch.is_some() && ch == session.injected_char_stack.last().copied();
+        if skip_injected_char && session.cursors.iter().all( | cursor | {
+            cursor.head == cursor.tail
+                && document_inner.text.line(cursor.head.line).chars().nth(cursor.head.column) == ch
+        }) {
+            let session = &mut self.sessions[session_id];
+            session.cursors.move_right(&document_inner.text, false);
+            session.update_selections_and_carets();
+            session.injected_char_stack.pop();
+            return;
         }
 
-        // If the character to be inserted is an opening delimiter, we automatically insert the
-        // corresponding closing delimiter.
-        let injected_char = text
-            .as_lines()
-            .first()
-            .and_then( | line | line.first())
-            .and_then( | ch | match ch {
+        // If the character to be inserted is an opening delimiter or a quote, we automatically
+        // insert the corresponding closing delimiter or quote.
+        let injected_char = ch.and_then( | ch | match ch {
             '(' => Some(')'),
             '[' => Some(']'),
             '{' => Some('}'),
+            '"' => Some('"'),
             _ => None,
-        });
+        }).filter( | _ | !skip_injected_char);
 
         // Build a delta and a list of cursor offsets for the edit operation.
         let mut offsets = Vec::new();
+        let mut did_inject_char = false;
 
         let mut builder_0 = delta::Builder::new();
         let mut position = Position::origin();
@@ -372,14 +772,58 @@ impl EditorState {
 
         let mut builder_1 = delta::Builder::new();
         let mut position = Position::origin();
+        let mut dedented_line = None;
         for cursor in &session.cursors {
-            builder_1.retain(cursor.start() - position);
+            let start = cursor.start();
+            let line = document_inner.text.line(start.line);
+            let next_char = document_inner.text.line(cursor.end().line).chars().nth(cursor.end().column);
+
+            if skip_injected_char && cursor.head == cursor.tail && next_char == ch {
+                offsets.push(Size {line: 0, column: 1});
+                continue;
+            }
+
+            // Automatically dedent a closing delimiter that is the first character on its line, so
+            // that it lines up with the line of its opening delimiter.
+            let delim = match ch {
+                Some(')') => Some(Delim::Paren),
+                Some(']') => Some(Delim::Bracket),
+                Some('}') => Some(Delim::Brace),
+                _ => None,
+            };
+            let dedent = delim
+                .filter( | _ | line.chars().take(start.column).all( | ch | ch.is_whitespace()))
+                .and_then( | delim | document_inner.token_cache.find_open_delim(start, delim))
+                .map( | open | document_inner.indent_cache[open.line].leading_whitespace().unwrap());
+            if let Some(indent) = dedent {
+                // A line is dedented only once. The whitespace in front of any other caret on the
+                // same line is already replaced by the dedent of the first one.
+                if dedented_line == Some(start.line) {
+                    offsets.push(Size::zero());
+                    continue;
+                }
+                dedented_line = Some(start.line);
+                builder_1.retain(Position {line: start.line, column: 0} - position);
+                builder_1.delete(Size {line: 0, column: start.column});
+                let text = Text::from_lines(vec![
+                    iter::repeat(' ').take(indent).chain(ch).collect::<Vec<_>>(),
+                ]);
+                offsets.push(text.len());
+                builder_1.insert(text);
+                position = start;
+                continue;
+            }
+
+            builder_1.retain(start - position);
             builder_1.insert(text.clone());
             if let Some(injected_char) = injected_char {
-                builder_1.insert(Text::from_lines(vec![vec![injected_char]]));
+                if should_inject_char(document_inner, injected_char, start, next_char) {
+                    builder_1.insert(Text::from_lines(vec![vec![injected_char]]));
+                    did_inject_char = true;
+                }
             }
             offsets.push(text.len());
-            position = cursor.end();
+            position = start;
         }
         let delta_1 = builder_1.build();
 
@@ -395,10 +839,15 @@ impl EditorState {
             send_request,
         );
 
-        // If we automatically inserted a character, store it on the injected character stack.
+        // If we skipped over an automatically injected character, remove it from the injected
+        // character stack. If we automatically inserted a character, store it on the injected
+        // character stack instead.
         let session = &mut self.sessions[session_id];
-        if let Some(injected_char) = injected_char {
-            session.injected_char_stack.push(injected_char);
+        if skip_injected_char {
+            session.injected_char_stack.pop();
+        }
+        if did_inject_char {
+            session.injected_char_stack.push(injected_char.unwrap());
         }
     }
 
@@ -428,51 +877,76 @@ impl EditorState {
         let mut builder_1 = delta::Builder::new();
         let mut position = Position::origin();
         for cursor in &session.cursors {
-            builder_1.retain(cursor.start() - position);
+            let start = cursor.start();
+            let line = document_inner.text.line(start.line);
+            builder_1.retain(start - position);
 
+            // Automatically indent the text to be inserted, based on the indentation of the last
+            // non-empty line up to the caret. If that line ends with an opening delimiter, the new
+            // line is indented one level deeper.
             let mut indent_count = 0;
-
-            // Automatically indent the text to be inserted.
-            //
-            // This is sort of a hack. I designed multiple cursors so that all cursors are applied
-            // at once, but operations such as autoindenting are much asier to implement if you
-            // apply one cursor at a time.
-            //
-            // This should be refactored in the future, by in the meantime we work around the 
-            // problem by only performing autoindenting if there is just a single cursor.
-            if session.cursors.len() == 1 {
-                let lines = document_inner.text.as_lines();
-                if let Some((first_non_whitespace_line_before, first_non_whitespace_char_before)) =
-                lines[cursor.start().line][..cursor.start().column]
-                    .iter()
-                    .rev()
-                    .find( | ch | !ch.is_whitespace())
-                    .map( | &ch | (cursor.start().line, ch))
-                    .or_else( || {
-                    (0..cursor.start().line).rev().find_map( | line | {
-                        lines[line]
-                            .iter()
-                            .rev()
-                            .find( | ch | !ch.is_whitespace())
-                            .map( | &ch | (line, ch))
-                    })
+            let mut open_char = None;
+            if let Some((first_non_whitespace_line_before, first_non_whitespace_char_before)) =
+            line
+                .chars()
+                .take(start.column)
+                .filter( | ch | !ch.is_whitespace())
+                .last()
+                .map( | ch | (start.line, ch))
+                .or_else( || {
+                (0..start.line).rev().find_map( | line | {
+                    document_inner.text.line(line)
+                        .chars()
+                        .rev()
+                        .find( | ch | !ch.is_whitespace())
+                        .map( | ch | (line, ch))
                 })
-                {
-                    indent_count = (document_inner.indent_cache[first_non_whitespace_line_before] .leading_whitespace() .unwrap() + 3) / 4;
-                    match first_non_whitespace_char_before {
-                        '(' | '[' | '{' => indent_count += 1,
-                        _ => {}
+            })
+            {
+                indent_count = (document_inner.indent_cache[first_non_whitespace_line_before] .leading_whitespace() .unwrap() + 3) / 4;
+                match first_non_whitespace_char_before {
+                    '(' | '[' | '{' => {
+                        indent_count += 1;
+                        if first_non_whitespace_line_before == start.line {
+                            open_char = Some(first_non_whitespace_char_before);
+                        }
                     }
+                    _ => {}
                 }
+            }
+
+            // If the caret is between an opening delimiter and its closing delimiter, the closing
+            // delimiter is moved to a line of its own, with the original indentation.
+            let next_char = document_inner.text.line(cursor.end().line)
+                .chars()
+                .skip(cursor.end().column)
+                .find( | ch | !ch.is_whitespace());
+            let split = match (open_char, next_char) {
+                (Some('('), Some(')')) | (Some('['), Some(']')) | (Some('{'), Some('}')) => true,
+                _ => false,
             };
-            let text = Text::from_lines(vec![
+
+            let mut new_lines = vec![
                 vec![],
                 iter::repeat(' ').take(indent_count * 4).collect::<Vec<_ >> (),
-            ]);
-            let len = text.len();
+            ];
+            if split {
+                new_lines.push(iter::repeat(' ').take((indent_count - 1) * 4).collect::<Vec<_ >> ());
+            }
+            let text = Text::from_lines(new_lines);
+            offsets.push(Size {line: 1, column: indent_count * 4});
             builder_1.insert(text);
-            offsets.push(len);
-            position = cursor.end();
+            position = start;
+            if split && cursor.head == cursor.tail {
+                // Remove any whitespace between the caret and the closing delimiter.
+                let whitespace_count = line
+                    .chars()
+                    .skip(start.column)
+                    .take_while( | ch | ch.is_whitespace())
+                    .count();
+                builder_1.delete(Size {line: 0, column: whitespace_count});
+                position.column += whitespace_count;
+            }
         }
         let delta_1 = builder_1.build();
 
@@ -505,6 +979,7 @@ impl EditorState {
             ')' => '(',
             ']' => '[',
             '}' => '{',
+            '"' => '"',
             _ => panic!(),
         });
 
@@ -530,8 +1005,7 @@ impl EditorState {
                         builder_1.retain(
                             Position {
                                 line: cursor.start().line - 1,
-                                column: document_inner.text.as_lines()[cursor.start().line - 1]
-                                    .len(),
+                                column: document_inner.text.line_len(cursor.start().line - 1),
                             } -position,
                         );
                         builder_1.delete(Size {line: 1, column: 0});
@@ -546,10 +1020,10 @@ impl EditorState {
                     // This should be refactored in the future, by in the meantime we work around
                     // the problem by only performing autoindenting if there is just a single cursor.
                     if session.cursors.len() == 1
-                        && document_inner.text.as_lines()[cursor.start().line]
-                    [..cursor.start().column]
-                        .iter()
-                        .all( | &ch | ch.is_whitespace())
+                        && document_inner.text.line(cursor.start().line)
+                        .chars()
+                        .take(cursor.start().column)
+                        .all( | ch | ch.is_whitespace())
                     {
                         if cursor.start().line == 0 {
                             builder_1.retain(
@@ -566,8 +1040,7 @@ impl EditorState {
                             builder_1.retain(
                                 Position {
                                     line: cursor.start().line - 1,
-                                    column: document_inner.text.as_lines()[cursor.start().line - 1]
-                                        .len(),
+                                    column: document_inner.text.line_len(cursor.start().line - 1),
                                 } -position,
                             );
                             builder_1.delete(Size {
@@ -576,20 +1049,24 @@ impl EditorState {
                             });
                         }
                     } else {
+                        // Delete an entire grapheme cluster, so we never leave part of a character
+                        // behind.
+                        let line = document_inner.text.line(cursor.start().line);
+                        let column = line_cursor::prev_grapheme_boundary(line, cursor.start().column);
                         builder_1.retain(
                             Position {
                                 line: cursor.start().line,
-                                column: cursor.start().column - 1,
+                                column,
                             } -position,
                         );
-                        builder_1.delete(Size {line: 0, column: 1});
+                        builder_1.delete(Size {line: 0, column: cursor.start().column - column});
 
                         // If we're deleting the character that triggered an automatic character
                         // injection, we also remove the automatically injected character.
                         if let Some(last_injected_char_inverse) = last_injected_char_inverse {
-                            if document_inner.text.as_lines()[cursor.start().line]
-                            [cursor.start().column - 1]
-                                == last_injected_char_inverse
+                            let mut chars = line.chars().skip(cursor.start().column - 1);
+                            if chars.next() == Some(last_injected_char_inverse)
+                                && chars.next().as_ref() == session.injected_char_stack.last()
                             {
                                 builder_1.delete(Size {line: 0, column: 1});
                             }
@@ -616,9 +1093,52 @@ impl EditorState {
     }
 
     /// For each cursor in the cursor set of the session with the given `session_id`, if the
-    /// selection of the cursor is empty, deletes the character at the caret of the cursor.
+    /// selection of the cursor is empty, deletes the grapheme cluster at the caret of the cursor.
     /// Otherwise, removes the selection of the cursor.
     pub fn delete(&mut self, session_id: SessionId, send_request: &mut dyn FnMut(CollabRequest)) {
+        self.delete_from_carets(session_id, false, | line, column | {
+            line_cursor::next_grapheme_boundary(line, column)
+        }, send_request);
+    }
+
+    /// For each cursor in the cursor set of the session with the given `session_id`, if the
+    /// selection of the cursor is empty, deletes everything from the start of the previous word up
+    /// to the caret of the cursor. Otherwise, removes the selection of the cursor.
+    pub fn delete_word_backward(
+        &mut self,
+        session_id: SessionId,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) {
+        self.delete_from_carets(session_id, true, | line, column | {
+            line_cursor::prev_word_boundary(line, column)
+        }, send_request);
+    }
+
+    /// For each cursor in the cursor set of the session with the given `session_id`, if the
+    /// selection of the cursor is empty, deletes everything from the caret of the cursor up to the
+    /// end of the next word. Otherwise, removes the selection of the cursor.
+    pub fn delete_word_forward(
+        &mut self,
+        session_id: SessionId,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) {
+        self.delete_from_carets(session_id, false, | line, column | {
+            line_cursor::next_word_boundary(line, column)
+        }, send_request);
+    }
+
+    // Removes the selection of each cursor with a non-empty selection, and for each other cursor,
+    // deletes everything between its caret and the column returned by `column_to` for the line and
+    // column of the caret. If `column_to` returns the column of the caret itself (i.e. the caret is
+    // at the start or end of its line), the line break before the caret is deleted instead if
+    // `backward` is `true`, and the line break after the caret otherwise.
+    fn delete_from_carets(
+        &mut self,
+        session_id: SessionId,
+        backward: bool,
+        column_to: impl Fn(Slice<'_>, usize) -> usize,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) {
         let session = &self.sessions[session_id];
         let document = &self.documents[session.document_id];
         let document_inner = document.inner.as_ref().unwrap();
@@ -637,20 +1157,51 @@ impl EditorState {
         let mut builder_1 = delta::Builder::new();
         let mut position = Position::origin();
         for cursor in &session.cursors {
+            offsets.push(Size::zero());
             if cursor.head != cursor.tail {
                 continue;
             }
-            builder_1.retain(cursor.start() - position);
-            if cursor.start().column == document_inner.text.as_lines()[cursor.start().line].len() {
-                if cursor.start().line == document_inner.text.as_lines().len() - 1 {
+            let caret = cursor.start();
+            let column = column_to(document_inner.text.line(caret.line), caret.column);
+            let range = if column < caret.column {
+                Range {
+                    start: Position {line: caret.line, column},
+                    end: caret,
+                }
+            } else if column > caret.column {
+                Range {
+                    start: caret,
+                    end: Position {line: caret.line, column},
+                }
+            } else if backward {
+                if caret.line == 0 {
                     continue;
                 }
-                builder_1.delete(Size {line: 1, column: 0});
+                Range {
+                    start: Position {
+                        line: caret.line - 1,
+                        column: document_inner.text.line_len(caret.line - 1),
+                    },
+                    end: caret,
+                }
             } else {
-                builder_1.delete(Size {line: 0, column: 1});
+                if caret.line == document_inner.text.line_count() - 1 {
+                    continue;
+                }
+                Range {
+                    start: caret,
+                    end: Position {line: caret.line + 1, column: 0},
+                }
+            };
+            // The range of a caret can overlap the range of the caret before it (when deleting
+            // words, for instance), in which case only the part that is not deleted yet is.
+            let start = range.start.max(position);
+            if range.end <= start {
+                continue;
             }
-            offsets.push(Size::zero());
-            position = cursor.start();
+            builder_1.retain(start - position);
+            builder_1.delete(range.end - start);
+            position = range.end;
         }
         let delta_1 = builder_1.build();
 
@@ -679,35 +1230,24 @@ impl EditorState {
         let document = &mut self.documents[session.document_id];
         let document_inner = document.inner.as_mut().unwrap();
 
-        // Compute the inverse delta so we can put it on the undo stack.
+        // Compute the inverse delta so we can put it in the undo tree.
         let inverse_delta = delta.clone().invert(&document_inner.text);
 
-        // Figure out if the edit operation should be grouped with the previous one on the undo
-        // stack.
+        // Figure out if the edit operation should be grouped with the previous one in the undo
+        // tree.
         let group_undo = edit_group.map_or(false, | edit_group | {
             document_inner
                 .edit_group
                 .map_or(false, | current_edit_group | current_edit_group == edit_group)
         });
-
-        if group_undo {
-            // Group the edit operation with the previous one on the undo stack.
-            let edit = document_inner.undo_stack.pop().unwrap();
-            document_inner.undo_stack.push(Edit {
-                injected_char_stack: edit.injected_char_stack,
-                cursors: edit.cursors,
-                delta: inverse_delta.compose(edit.delta),
-            });
-        } else {
-            // Add the edit operation to the undo stack.
-            document_inner.edit_group = edit_group;
-            document_inner.undo_stack.push(Edit {
-                injected_char_stack: session.injected_char_stack.clone(),
-                cursors: session.cursors.clone(),
-                delta: inverse_delta,
-            });
-        }
-        document_inner.redo_stack.clear();
+        document_inner.edit_group = edit_group;
+        document_inner.undo_tree.push(
+            delta.clone(),
+            inverse_delta,
+            session.cursors.clone(),
+            session.injected_char_stack.clone(),
+            group_undo,
+        );
 
         // Apply the edit operation to the session.
         let session = &mut self.sessions[session_id];
@@ -721,46 +1261,55 @@ impl EditorState {
     /// Undoes the last edit operation.
     pub fn undo(&mut self, session_id: SessionId, send_request: &mut dyn FnMut(CollabRequest)) {
         let session = &self.sessions[session_id];
-        let document = &mut self.documents[session.document_id];
-        let document_inner = document.inner.as_mut().unwrap();
-        if let Some(undo) = document_inner.undo_stack.pop() {
-            document_inner.edit_group = None;
-
-            let inverse_delta = undo.delta.clone().invert(&document_inner.text);
-            document_inner.redo_stack.push(Edit {
-                injected_char_stack: session.injected_char_stack.clone(),
-                cursors: session.cursors.clone(),
-                delta: inverse_delta,
-            });
-
-            let session = &mut self.sessions[session_id];
-            session.cursors = undo.cursors;
-            session.update_selections_and_carets();
-
-            self.apply_delta(session_id, undo.delta, send_request);
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        if let Some(index) = document_inner.undo_tree.undo_target() {
+            self.jump_to_undo_state(session_id, index, send_request);
         }
     }
 
-    /// Redoes the last edit operation.
+    /// Redoes the last edit operation that was undone.
+    ///
+    /// If the last undone state has more than one branch, this redoes the branch that was visited
+    /// last.
     pub fn redo(&mut self, session_id: SessionId, send_request: &mut dyn FnMut(CollabRequest)) {
+        let session = &self.sessions[session_id];
+        let document = &self.documents[session.document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        if let Some(index) = document_inner.undo_tree.redo_target() {
+            self.jump_to_undo_state(session_id, index, send_request);
+        }
+    }
+
+    /// Moves the document of the session with the given `session_id` to the state of the node with
+    /// the given `index` in its undo tree. This can be any earlier state of the document, including
+    /// states on branches that were abandoned by making an edit after undoing.
+    pub fn jump_to_undo_state(
+        &mut self,
+        session_id: SessionId,
+        index: usize,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) {
         let session = &self.sessions[session_id];
         let document = &mut self.documents[session.document_id];
         let document_inner = document.inner.as_mut().unwrap();
-        if let Some(redo) = document_inner.redo_stack.pop() {
+        if let Some(delta) = document_inner.undo_tree.move_to(
+            index,
+            session.cursors.clone(),
+            session.injected_char_stack.clone(),
+        ) {
             document_inner.edit_group = None;
 
-            let inverse_delta = redo.delta.clone().invert(&document_inner.text);
-            document_inner.undo_stack.push(Edit {
-                injected_char_stack: session.injected_char_stack.clone(),
-                cursors: session.cursors.clone(),
-                delta: inverse_delta,
-            });
+            let node = document_inner.undo_tree.node(index);
+            let cursors = node.cursors.clone();
+            let injected_char_stack = node.injected_char_stack.clone();
 
             let session = &mut self.sessions[session_id];
-            session.cursors = redo.cursors;
+            session.cursors = cursors;
+            session.injected_char_stack = injected_char_stack;
             session.update_selections_and_carets();
 
-            self.apply_delta(session_id, redo.delta, send_request);
+            self.apply_delta(session_id, delta, send_request);
         }
     }
 
@@ -786,6 +1335,7 @@ impl EditorState {
         let document = &mut self.documents[document_id];
         document.apply_delta(delta.clone());
         document.schedule_apply_delta_request(delta, send_request);
+        self.update_folds(document_id);
     }
 
     /// Handles and apply delta response from the collab server.
@@ -806,9 +1356,38 @@ impl EditorState {
                 document_inner.revision as u32,
                 outstanding_delta.clone(),
             ));
+        } else {
+            // the text on the server is now the same as ours, so the diff is up to date
+            send_request(CollabRequest::GitDiff(document.path.clone()));
         }
     }
 
+    /// Handles a response from the collab server with the lines of the document for the file at
+    /// `path` that differ from the `HEAD` commit. Returns the id of the document, if it is open.
+    pub fn handle_git_diff_response(
+        &mut self,
+        path: &UnixPath,
+        hunks: Vec<GitDiffHunk>,
+    ) -> Option<DocumentId> {
+        let document_id = *self.documents_by_path.get(path) ?;
+        let document_inner = self.documents[document_id].inner.as_mut() ?;
+        document_inner.git_hunks = hunks;
+        Some(document_id)
+    }
+
+    /// Handles a response from the collab server with the commit that last changed each line of
+    /// the file at `path`. Returns the id of the document, if it is open.
+    pub fn handle_git_blame_response(
+        &mut self,
+        path: &UnixPath,
+        lines: Vec<GitBlameLine>,
+    ) -> Option<DocumentId> {
+        let document_id = *self.documents_by_path.get(path) ?;
+        let document_inner = self.documents[document_id].inner.as_mut() ?;
+        document_inner.git_blame = Some(lines);
+        Some(document_id)
+    }
+
     /// Handles a notification from the collab server that a remote delta was applied.
     pub fn handle_delta_applied_notification(
         &mut self,
@@ -827,8 +1406,7 @@ impl EditorState {
             *outstanding_delta_ref = new_outstanding_delta;
         }
 
-        transform_edit_stack(&mut document_inner.undo_stack, delta.clone());
-        transform_edit_stack(&mut document_inner.redo_stack, delta.clone());
+        document_inner.undo_tree.transform(delta.clone());
 
         for session_id in document.session_ids.iter().cloned() {
             let session = &mut self.sessions[session_id];
@@ -839,9 +1417,23 @@ impl EditorState {
         let document_inner = document.inner.as_mut().unwrap();
         document_inner.revision += 1;
         document.apply_delta(delta);
+        self.update_folds(document_id);
 
         document_id
     }
+
+    // Drops the folded ranges of each session of the document with the given `document_id` that
+    // no longer start a fold range after its text has changed, and unfolds those that now hide a
+    // caret.
+    fn update_folds(&mut self, document_id: DocumentId) {
+        let document = &self.documents[document_id];
+        let document_inner = document.inner.as_ref().unwrap();
+        for session_id in document.session_ids.iter().cloned() {
+            let session = &mut self.sessions[session_id];
+            session.folds.update(&document_inner.fold_ranges, &document_inner.text);
+            session.unfold_at_carets(&document_inner.fold_ranges);
+        }
+    }
 }
 
 /// An id for a `Session`. This can be used to refer to a session without borrowing it.
@@ -860,6 +1452,8 @@ pub struct Session {
     pub injected_char_stack: Vec<char>,
     /// The set of cursors for this session.
     pub cursors: CursorSet,
+    /// The set of folded ranges for this session.
+    pub folds: FoldSet,
     /// The minimal set of non-overlapping ranges that covers the selections of all cursors for this
     /// session. This information can be derived from the set of cursors, but is cached here
     /// because it is somewhat expensive to compute.
@@ -877,6 +1471,7 @@ impl Session {
     // session, and then recomputes the derived information for this set of cursors.
     fn apply_delta(&mut self, delta: &Delta) {
         self.cursors.apply_delta(delta);
+        self.folds.apply_delta(delta);
         self.update_selections_and_carets();
     }
 
@@ -887,6 +1482,18 @@ impl Session {
         self.update_selections_and_carets();
     }
 
+    // Unfolds each folded range of this session that hides one of its carets, given the fold
+    // `ranges` of its document.
+    fn unfold_at_carets(&mut self, ranges: &[FoldRange]) {
+        for range in ranges {
+            if self.folds.is_folded(range.start_line)
+                && self.cursors.iter().any( | cursor | range.hidden_lines().contains(&cursor.head.line))
+            {
+                self.folds.unfold(range.start_line);
+            }
+        }
+    }
+
     // Recomputes the derived information for the set of cursors for this session.
     fn update_selections_and_carets(&mut self) {
         self.selections = self.cursors.selections();
@@ -918,11 +1525,22 @@ impl Document {
         inner.token_cache.invalidate(&delta);
         inner.indent_cache.invalidate(&delta);
         inner.msg_cache.invalidate(&delta);
+        inner.lsp_msg_cache.invalidate(&delta);
+        inner.live_msg_cache.invalidate(&delta);
+        inner.live_edited = true;
+
+        if let Some(lsp_changes) = &mut inner.lsp_changes {
+            lsp_changes.extend(LspContentChange::from_delta(&inner.text, &delta));
+        }
 
         inner.text.apply_delta(delta);
 
         inner.token_cache.refresh(&inner.text);
+        inner.fold_ranges = inner.token_cache.fold_ranges();
         inner.indent_cache.refresh(&inner.text);
+        inner.msg_cache.refresh(&inner.text);
+        inner.lsp_msg_cache.refresh(&inner.text);
+        inner.live_msg_cache.refresh(&inner.text);
     }
 
     // Schedules a request to the collab server to apply this delta to the remote document.
@@ -962,19 +1580,36 @@ pub struct DocumentInner {
     pub text: Text,
     /// A line-based cache containing the tokens for each line.
     pub token_cache: TokenCache,
+    /// The ranges of lines that can be folded, computed from the token cache.
+    pub fold_ranges: Vec<FoldRange>,
     /// A line-based cache containing the indent level for each line.
     pub indent_cache: IndentCache,
+    /// A line-based cache containing the spans of the builder messages for each line.
     pub msg_cache: MsgCache,
+    /// The changes to this document that have not been sent to the language server yet, or `None`
+    /// if the language server does not know about this document.
+    pub lsp_changes: Option<Vec<LspContentChange>>,
+    /// The diagnostics from the language server for this document.
+    pub lsp_messages: Vec<BuilderMsg>,
+    /// A line-based cache containing the spans of the language server diagnostics for each line.
+    pub lsp_msg_cache: MsgCache,
+    /// A line-based cache containing the spans of the live messages for each line.
+    pub live_msg_cache: MsgCache,
+    /// Whether this document was edited since the live documents were last updated from it.
+    pub live_edited: bool,
     //// Whether the last typed character was a backspace character or a non-backspace character.
     pub edit_group: Option<EditGroup>,
-    /// The undo stack for this document.
-    pub undo_stack: Vec<Edit>,
-    /// The redo stack for this document.
-    pub redo_stack: Vec<Edit>,
+    /// The undo tree for this document.
+    pub undo_tree: UndoTree,
     /// The queue of outstanding deltas for this document. A delta is outstanding if it has been
     /// applied to the local document, but we have not yet received confirmation from the collab
     /// server that it has been applied to the remote document.
     pub outstanding_deltas: VecDeque<Delta>,
+    /// The ranges of lines of this document that differ from the `HEAD` commit.
+    pub git_hunks: Vec<GitDiffHunk>,
+    /// The commit that last changed each line of the file on disk, or `None` if it has not been
+    /// requested yet.
+    pub git_blame: Option<Vec<GitBlameLine>>,
 }
 
 /// An `EditGroup` keeps track of whether the last typed character was a backspace character or a
@@ -992,28 +1627,69 @@ pub enum EditGroup {
     Backspace,
 }
 
-/// An `Edit` represents an atomic edit operation.
-///
-/// The primary purpose of this type is to be stored on the undo stack.
-#[derive(Debug)]
-pub struct Edit {
-    /// The state of the injected character stack at the time of this `Edit`. We store this
-    /// explicitly because it cannot be recovered from the `delta` alone.
-    pub injected_char_stack: Vec<char>,
-    /// The state of the cursor set at the time of this `Edit`. We store this explicitly
-    /// because it cannot be recovered from the `delta` alone.
-    pub cursors: CursorSet,
-    /// A delta representing the change made to the text by this `Edit`.
-    pub delta: Delta,
+/// The undo history of a document that was closed.
+#[derive(Clone, Debug, SerBin, DeBin)]
+pub struct UndoHistory {
+    /// A hash of the text of the document when it was closed. The undo history is only restored
+    /// if the text of the document is the same when it is reopened.
+    pub text_hash: u64,
+    /// The undo tree of the document when it was closed.
+    pub undo_tree: UndoTree,
 }
 
-fn transform_edit_stack(edit_stack: &mut Vec<Edit>, delta: Delta) {
-    let mut delta = delta;
-    for edit in edit_stack.iter_mut().rev() {
-        let edit_delta = mem::replace(&mut edit.delta, Delta::identity());
-        edit.cursors.apply_delta(&delta);
-        let (new_delta, new_edit_delta) = delta.transform(edit_delta);
-        delta = new_delta;
-        edit.delta = new_edit_delta;
+// Returns whether the closing delimiter or quote `injected_char` should be automatically inserted
+// when its opening counterpart is typed at the given `position`, where `next_char` is the character
+// after the caret, if any.
+fn should_inject_char(
+    document_inner: &DocumentInner,
+    injected_char: char,
+    position: Position,
+    next_char: Option<char>,
+) -> bool {
+    // Don't inject a character if the caret is right before some other text, since then the
+    // delimiter or quote is most likely meant to enclose that text.
+    if let Some(next_char) = next_char {
+        if !next_char.is_whitespace() && !")]},;".contains(next_char) {
+            return false;
+        }
+    }
+    if injected_char != '"' || position.column == 0 {
+        return true;
     }
+    // Don't inject a quote right after an identifier or an escape, or if the quote that is typed
+    // ends a string or is part of a comment.
+    let prev_position = Position {line: position.line, column: position.column - 1};
+    let prev_char = document_inner.text.line(position.line).chars().nth(prev_position.column).unwrap();
+    if prev_char.is_alphanumeric() || prev_char == '_' || prev_char == '\\' {
+        return false;
+    }
+    match document_inner.token_cache.token_at(prev_position) {
+        Some((_, TokenWithLen {token: FullToken::Comment, ..})) => false,
+        Some((start, TokenWithLen {token: FullToken::String, len})) => start.column + len <= position.column,
+        _ => true,
+    }
+}
+
+// Returns a hash of the given `text`, used to check whether an undo history still applies to it.
+fn text_hash(text: &Text) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Returns the id and range of each message in `messages` that refers to the file with the given
+// `path`. The id of a message is its index in `messages`.
+fn msg_ranges_for_path<'a>(
+    messages: &'a [BuilderMsg],
+    path: &'a UnixPath,
+) -> impl Iterator<Item = (usize, Range)> + 'a {
+    messages
+        .iter()
+        .enumerate()
+        .filter_map(move |(msg_id, msg)| match msg {
+            BuilderMsg::Location(loc) if UnixPath::new(&loc.file_name) == path => {
+                Some((msg_id, loc.range))
+            }
+            _ => None,
+        })
 }
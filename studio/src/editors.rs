@@ -12,26 +12,26 @@ use {
         },
         code_editor::{
             code_editor_impl::{
-                CodeEditorAction
+                CodeEditorAction,
+                WrapMode,
             },
         },
         makepad_collab_protocol::{
             CollabNotification,
             CollabRequest,
             CollabResponse,
-            unix_path::{UnixPath},
         },
         builder::{
             builder_protocol::{
                 BuilderMsgWrap,
-                BuilderMsg
             }
         },
         rust_editor::{
             rust_editor::{
                RustEditor
             },
-        }
+        },
+        lsp::lsp_protocol::LspCompletionItem,
     },
 
 };
@@ -47,6 +47,48 @@ impl EditorView {
         }
     }
     
+    pub fn scroll_pos(&self, cx: &Cx) -> Vec2 {
+        match self {
+            Self::RustEditor(e) => e.scroll_pos(cx)
+        }
+    }
+    
+    pub fn set_scroll_pos(&mut self, cx: &mut Cx, pos: Vec2) {
+        match self {
+            Self::RustEditor(e) => e.set_scroll_pos(cx, pos)
+        }
+    }
+    
+    pub fn set_wrap_mode(&mut self, cx: &mut Cx, wrap_mode: WrapMode) {
+        match self {
+            Self::RustEditor(e) => e.set_wrap_mode(cx, wrap_mode)
+        }
+    }
+    
+    pub fn set_show_minimap(&mut self, cx: &mut Cx, show_minimap: bool) {
+        match self {
+            Self::RustEditor(e) => e.set_show_minimap(cx, show_minimap)
+        }
+    }
+    
+    pub fn set_show_blame(&mut self, cx: &mut Cx, show_blame: bool) {
+        match self {
+            Self::RustEditor(e) => e.set_show_blame(cx, show_blame)
+        }
+    }
+    
+    pub fn show_hover(&mut self, cx: &mut Cx, state: &EditorState, text: &str) {
+        match self {
+            Self::RustEditor(e) => e.show_hover(cx, state, text)
+        }
+    }
+    
+    pub fn show_completion(&mut self, cx: &mut Cx, state: &EditorState, items: Vec<LspCompletionItem>) {
+        match self {
+            Self::RustEditor(e) => e.show_completion(cx, state, items)
+        }
+    }
+    
     pub fn set_session_id(&mut self, session_id: Option<SessionId>) {
         match self {
             Self::RustEditor(e) => e.set_session_id(session_id)
@@ -90,6 +132,9 @@ live_register!{
     
     Editors: {{Editors}} {
         rust_editor: RustEditor {},
+        wrap_mode: Off,
+        show_minimap: true,
+        show_blame: false,
     }
 }
 
@@ -105,6 +150,11 @@ pub struct Editors {
     #[rust] editor_views: ComponentMap<EditorViewId, EditorView>,
     
     rust_editor: Option<LivePtr>,
+    
+    // These apply to all editor views.
+    wrap_mode: WrapMode,
+    show_minimap: bool,
+    show_blame: bool,
 }
 
 impl LiveHook for Editors {
@@ -137,8 +187,15 @@ impl Editors {
         session_id: Option<SessionId>,
     ) {
         let rust_editor = self.rust_editor;
+        let wrap_mode = self.wrap_mode;
+        let show_minimap = self.show_minimap;
+        let show_blame = self.show_blame;
         let view = self.editor_views.get_or_insert(cx, view_id.into(), | cx | {
-            EditorView::RustEditor(RustEditor::new_from_ptr(cx, rust_editor))
+            let mut view = EditorView::RustEditor(RustEditor::new_from_ptr(cx, rust_editor));
+            view.set_wrap_mode(cx, wrap_mode);
+            view.set_show_minimap(cx, show_minimap);
+            view.set_show_blame(cx, show_blame);
+            view
         });
         
         if let Some(session_id) = view.session_id() {
@@ -162,6 +219,66 @@ impl Editors {
         view.redraw(cx);
     }
     
+    pub fn view_scroll_pos(&self, cx: &Cx, view_id: EditorViewId) -> Option<Vec2> {
+        self.editor_views.get(&view_id).map( | view | view.scroll_pos(cx))
+    }
+    
+    pub fn set_view_scroll_pos(&mut self, cx: &mut Cx, view_id: EditorViewId, pos: Vec2) {
+        let view = &mut self.editor_views[view_id];
+        view.set_scroll_pos(cx, pos);
+    }
+    
+    /// Sets how lines that are too long to fit are displayed in all editor views.
+    pub fn set_wrap_mode(&mut self, cx: &mut Cx, wrap_mode: WrapMode) {
+        self.wrap_mode = wrap_mode;
+        for view in self.editor_views.values_mut() {
+            view.set_wrap_mode(cx, wrap_mode);
+        }
+    }
+    
+    /// Shows the minimap in all editor views if it is hidden, or hides it if it is shown.
+    pub fn toggle_minimap(&mut self, cx: &mut Cx) {
+        self.show_minimap = !self.show_minimap;
+        for view in self.editor_views.values_mut() {
+            view.set_show_minimap(cx, self.show_minimap);
+        }
+    }
+    
+    /// Shows the commit that last changed the line of the caret in all editor views if it is
+    /// hidden, or hides it if it is shown. The commits for the lines of each document are requested
+    /// when they are first needed.
+    pub fn toggle_blame(
+        &mut self,
+        cx: &mut Cx,
+        state: &EditorState,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) {
+        self.show_blame = !self.show_blame;
+        if self.show_blame {
+            for document in state.documents.values() {
+                if document.inner.as_ref().is_some_and( | inner | inner.git_blame.is_none()) {
+                    send_request(CollabRequest::GitBlame(document.path.clone()));
+                }
+            }
+        }
+        for view in self.editor_views.values_mut() {
+            view.set_show_blame(cx, self.show_blame);
+        }
+    }
+    
+    /// Requests the lines that differ from the `HEAD` commit for all open documents, and the
+    /// commits that last changed their lines if those are shown, for instance after a commit.
+    pub fn refresh_git(&mut self, state: &EditorState, send_request: &mut dyn FnMut(CollabRequest)) {
+        for document in state.documents.values() {
+            if document.inner.is_some() {
+                send_request(CollabRequest::GitDiff(document.path.clone()));
+                if self.show_blame {
+                    send_request(CollabRequest::GitBlame(document.path.clone()));
+                }
+            }
+        }
+    }
+    
     pub fn handle_event(
         &mut self,
         cx: &mut Cx,
@@ -169,18 +286,40 @@ impl Editors {
         view_id: EditorViewId,
         event: &mut Event,
         send_request: &mut dyn FnMut(CollabRequest),
-    ) {
+    ) -> Vec<CodeEditorAction> {
         let view = &mut self.editor_views[view_id];
         let mut actions = Vec::new();
         view.handle_event(cx, state, event, send_request, &mut | _, action | actions.push(action));
+        let mut unhandled_actions = Vec::new();
         for action in actions {
             match action {
                 CodeEditorAction::RedrawViewsForDocument(document_id) => {
                     self.redraw_views_for_document(cx, state, document_id);
                 }
-                _ => ()
+                CodeEditorAction::CursorBlink => (),
+                // these need a language server, which we don't own
+                action => unhandled_actions.push(action)
             }
         }
+        unhandled_actions
+    }
+    
+    pub fn show_hover(&mut self, cx: &mut Cx, state: &EditorState, session_id: SessionId, text: &str) {
+        if let Some(view_id) = state.sessions[session_id].session_view {
+            self.editor_views[view_id].show_hover(cx, state, text);
+        }
+    }
+    
+    pub fn show_completion(
+        &mut self,
+        cx: &mut Cx,
+        state: &EditorState,
+        session_id: SessionId,
+        items: Vec<LspCompletionItem>,
+    ) {
+        if let Some(view_id) = state.sessions[session_id].session_view {
+            self.editor_views[view_id].show_completion(cx, state, items);
+        }
     }
     
     pub fn handle_collab_response(
@@ -194,12 +333,26 @@ impl Editors {
             CollabResponse::OpenFile(response) => {
                 let (file_id, revision, text) = response.unwrap();
                 let document_id = state.handle_open_file_response(file_id, revision, text, send_request);
+                if self.show_blame {
+                    send_request(CollabRequest::GitBlame(state.documents[document_id].path.clone()));
+                }
                 self.redraw_views_for_document(cx, state, document_id);
             }
             CollabResponse::ApplyDelta(response) => {
                 let file_id = response.unwrap();
                 state.handle_apply_delta_response(file_id, send_request);
             }
+            // files that are not in a git repository have no diff or blame, so errors are ignored
+            CollabResponse::GitDiff(path, Ok(hunks)) => {
+                if let Some(document_id) = state.handle_git_diff_response(&path, hunks) {
+                    self.redraw_views_for_document(cx, state, document_id);
+                }
+            }
+            CollabResponse::GitBlame(path, Ok(lines)) => {
+                if let Some(document_id) = state.handle_git_blame_response(&path, lines) {
+                    self.redraw_views_for_document(cx, state, document_id);
+                }
+            }
             _ => {}
         }
     }
@@ -215,6 +368,7 @@ impl Editors {
                 let document_id = state.handle_delta_applied_notification(file_id, delta);
                 self.redraw_views_for_document(cx, state, document_id);
             }
+            _ => {}
         }
     }
     
@@ -224,22 +378,19 @@ impl Editors {
         state: &mut EditorState,
         msgs: Vec<BuilderMsgWrap>,
     ) {
+        let mut messages_were_replaced = false;
         for wrap in msgs {
-            let msg_id = state.messages.len();
-            match &wrap.msg {
-                BuilderMsg::Location(loc) => {
-                    if let Some(doc_id) = state.documents_by_path.get(UnixPath::new(&loc.file_name)) {
-                        let doc = &mut state.documents[*doc_id];
-                        if let Some(inner) = &mut doc.inner {
-                            inner.msg_cache.add_range(&inner.text, msg_id, loc.range);
-                        }
-                        // lets redraw this doc. with new squigglies
-                        self.redraw_views_for_document(cx, state, *doc_id);
-                    }
-                }
-                _ => ()
-            }
-            state.messages.push(wrap.msg);
+            messages_were_replaced |= state.handle_builder_msg(wrap);
+        }
+        if messages_were_replaced {
+            // lets redraw all docs with their new squigglies
+            self.redraw_all_views(cx);
+        }
+    }
+    
+    pub fn redraw_all_views(&mut self, cx: &mut Cx) {
+        for view in self.editor_views.values_mut() {
+            view.redraw(cx);
         }
     }
     
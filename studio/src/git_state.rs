@@ -0,0 +1,106 @@
+use {
+    std::collections::HashMap,
+    crate::{
+        makepad_collab_protocol::{
+            CollabRequest,
+            CollabResponse,
+            GitBlameLine,
+            GitDiffHunk,
+            GitFileStatus,
+            GitStatus,
+            unix_path::{UnixPath, UnixPathBuf},
+        },
+    },
+};
+
+/// The git status of the files in the workspace.
+#[derive(Default)]
+pub struct GitState {
+    // the status of each file that differs from the `HEAD` commit, and of each folder that
+    // contains such a file
+    statuses_by_path: HashMap<UnixPathBuf, GitStatus>,
+    /// A message about the last status update, such as an error.
+    pub message: Option<String>,
+}
+
+impl GitState {
+    /// Requests the status of the files in the workspace.
+    pub fn refresh(&mut self, send_request: &mut dyn FnMut(CollabRequest)) {
+        send_request(CollabRequest::GitStatus);
+    }
+
+    /// Returns the status of the file or folder at `path`, if it differs from the `HEAD` commit.
+    /// A folder has the status of the files in it if they all have the same status, and is
+    /// modified otherwise.
+    pub fn status(&self, path: &UnixPath) -> Option<GitStatus> {
+        self.statuses_by_path.get(path).cloned()
+    }
+
+    /// Replaces the status of all files with the given `statuses`.
+    pub fn set_statuses(&mut self, statuses: Vec<GitFileStatus>) {
+        self.statuses_by_path.clear();
+        for GitFileStatus {path, status} in statuses {
+            let mut folder_path = UnixPathBuf::new();
+            for component in path.components() {
+                let folder_status = self.statuses_by_path.entry(folder_path.clone()).or_insert(status);
+                if *folder_status != status {
+                    *folder_status = GitStatus::Modified;
+                }
+                folder_path.push(component);
+            }
+            self.statuses_by_path.insert(path, status);
+        }
+    }
+
+    /// Returns whether the status of the file at `path` is out of date, given the lines of its
+    /// document that differ from the `HEAD` commit. This happens when a file without changes is
+    /// edited, or when its changes are undone.
+    pub fn is_stale(&self, path: &UnixPath, hunks: &[GitDiffHunk]) -> bool {
+        hunks.is_empty() == self.status(path).is_some()
+    }
+
+    /// Handles a response from the collab server. Returns whether the response was for the git
+    /// status, in which case it has been handled.
+    pub fn handle_response(&mut self, response: &CollabResponse) -> bool {
+        match response {
+            CollabResponse::GitStatus(Ok(statuses)) => {
+                self.set_statuses(statuses.clone());
+                self.message = None;
+                true
+            }
+            CollabResponse::GitStatus(Err(error)) => {
+                // the workspace is probably not in a git repository
+                self.statuses_by_path.clear();
+                self.message = Some(format!("Git status failed: {:?}", error));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the annotation that is shown next to a line that was last changed by the given commit,
+/// such as `"Jane Doe, 2022-03-14 • Fix the build"`.
+pub fn blame_annotation(line: &GitBlameLine) -> String {
+    // git blames lines that have not been committed yet on a commit with a hash of all zeros
+    if line.commit.chars().all( | ch | ch == '0') {
+        return String::from("Not committed yet");
+    }
+    let (year, month, day) = civil_from_days(line.time.div_euclid(24 * 60 * 60));
+    format!("{}, {:04}-{:02}-{:02} • {}", line.author, year, month, day, line.summary)
+}
+
+// Returns the year, month and day of the day that is `days` days after 1970-01-01, using the
+// algorithm from http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {month_index + 3} else {month_index - 9} as u32;
+    let year = year_of_era + era * 400 + if month <= 2 {1} else {0};
+    (year, month, day)
+}
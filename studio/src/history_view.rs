@@ -0,0 +1,133 @@
+use {
+    std::{
+        fmt::Write,
+    },
+    crate::{
+        makepad_editor_core::delta::{Delta, Operation},
+        makepad_studio_component::{
+            log_icon::LogIconType,
+            log_list::{LogList, LogListAction}
+        },
+        makepad_platform::*,
+        editor_state::{EditorState, SessionId},
+    },
+};
+
+live_register!{
+    use makepad_platform::shader::std::*;
+
+    HistoryView: {{HistoryView}} {
+    }
+}
+
+/// A timeline of the undo tree of the document of the selected code editor.
+///
+/// Each state of the document is shown as one node, in the order in which the states were
+/// created. States on the path from the initial state to the current state are shown as ok, while
+/// states on abandoned branches are shown as waiting. Clicking a node jumps to its state.
+#[derive(Live, LiveHook)]
+pub struct HistoryView {
+    log_list: LogList
+}
+
+pub enum HistoryViewAction {
+    JumpToUndoState(usize),
+}
+
+impl HistoryView {
+    pub fn redraw(&mut self, cx: &mut Cx) {
+        self.log_list.redraw(cx)
+    }
+
+    pub fn draw(&mut self, cx: &mut Cx2d, state: &EditorState, session_id: Option<SessionId>) {
+        let mut file = String::new();
+        let mut body = String::new();
+        if self.log_list.begin(cx).is_ok() {
+            let undo_tree = session_id.and_then( | session_id | {
+                let session = &state.sessions[session_id];
+                state.documents[session.document_id].inner.as_ref()
+            }).map( | inner | &inner.undo_tree);
+            if let Some(undo_tree) = undo_tree {
+                for (index, node) in undo_tree.nodes().enumerate() {
+                    if self.log_list.should_node_draw(cx) {
+                        file.clear();
+                        body.clear();
+                        let id = LiveId(index as u64).into();
+                        if index == undo_tree.current() {
+                            write!(file, "#{} (current)", index).unwrap();
+                        }
+                        else {
+                            write!(file, "#{}", index).unwrap();
+                        }
+                        match node.parent {
+                            Some(parent) => write_delta_summary(&mut body, parent, &node.delta),
+                            None => body.push_str("opened"),
+                        }
+                        let icon = if undo_tree.is_ancestor_of_current(index) {
+                            LogIconType::Ok
+                        }
+                        else {
+                            LogIconType::Wait
+                        };
+                        self.log_list.draw_node(cx, icon, id, &file, &body, false);
+                    }
+                }
+            }
+            self.log_list.end(cx);
+        }
+    }
+
+    pub fn handle_event_with_fn(
+        &mut self,
+        cx: &mut Cx,
+        event: &mut Event,
+        dispatch_action: &mut dyn FnMut(&mut Cx, HistoryViewAction),
+    ) {
+        self.log_list.handle_event_with_fn(cx, event, &mut | cx, action | {
+            if let LogListAction::WasClicked(node_id) = action {
+                dispatch_action(cx, HistoryViewAction::JumpToUndoState(node_id.0.0 as usize))
+            }
+        })
+    }
+}
+
+// Writes a short, single line description of the change made by `delta`, which is the edit from
+// the state with index `parent`, to `out`.
+fn write_delta_summary(out: &mut String, parent: usize, delta: &Delta) {
+    const MAX_LEN: usize = 32;
+
+    let mut inserted = String::new();
+    let mut deleted_lines = 0;
+    let mut deleted_columns = 0;
+    for operation in delta {
+        match operation {
+            Operation::Retain(_) => {}
+            Operation::Insert(text) => write!(inserted, "{}", text).unwrap(),
+            Operation::Delete(count) => {
+                deleted_lines += count.line;
+                deleted_columns += count.column;
+            }
+        }
+    }
+    write!(out, "from #{}:", parent).unwrap();
+    if deleted_lines > 0 {
+        write!(out, " deleted {} lines", deleted_lines).unwrap();
+    }
+    else if deleted_columns > 0 {
+        write!(out, " deleted {} chars", deleted_columns).unwrap();
+    }
+    if !inserted.is_empty() {
+        out.push_str(" inserted \"");
+        for ch in inserted.chars().take(MAX_LEN) {
+            match ch {
+                '\n' => out.push_str("\\n"),
+                '\t' => out.push_str("\\t"),
+                ch => out.push(ch),
+            }
+        }
+        if inserted.chars().count() > MAX_LEN {
+            out.push('…');
+        }
+        out.push('"');
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    makepad_live_id::*,
+    rust_editor::rust_tokenizer::full_token::{Delim, FullToken, TokenWithLen},
+    languages::{
+        scanner::{scan_line, Scanner},
+        Language,
+    },
+};
+
+/// GLSL, as generated from the shaders of the live DSL.
+pub struct Glsl;
+
+/// The state of the GLSL tokenizer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    BlockCommentTail,
+}
+
+impl Language for Glsl {
+    type State = State;
+
+    fn tokenize_line(
+        state: State,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> State {
+        scan_line(state, line, scratch, tokens, | state, scanner | match state {
+            State::Initial => next(scanner),
+            State::BlockCommentTail => block_comment_tail(scanner),
+        })
+    }
+}
+
+// The operators that are made up of more than one char, longest first.
+const OPERATORS: [&str; 21] = [
+    "<<=", ">>=", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "==", "!=", "<=",
+    ">=", "&&", "||", "^^", "<<", ">>",
+];
+
+fn next(scanner: &mut Scanner) -> (State, FullToken) {
+    let token = match scanner.peek(0) {
+        ch if ch.is_whitespace() => scanner.whitespace(),
+        '/' if scanner.peek(1) == '/' => {
+            scanner.skip_to_end();
+            FullToken::Comment
+        }
+        '/' if scanner.peek(1) == '*' => {
+            scanner.skip(2);
+            return block_comment_tail(scanner);
+        }
+        '(' => scanner.token(1, FullToken::Open(Delim::Paren)),
+        ')' => scanner.token(1, FullToken::Close(Delim::Paren)),
+        '[' => scanner.token(1, FullToken::Open(Delim::Bracket)),
+        ']' => scanner.token(1, FullToken::Close(Delim::Bracket)),
+        '{' => scanner.token(1, FullToken::Open(Delim::Brace)),
+        '}' => scanner.token(1, FullToken::Close(Delim::Brace)),
+        ch if ch.is_ascii_digit() || ch == '.' && scanner.peek(1).is_ascii_digit() => {
+            let start = scanner.index();
+            scanner.skip(1);
+            loop {
+                match scanner.peek(0) {
+                    'e' | 'E' if matches!(scanner.peek(1), '+' | '-') => scanner.skip(2),
+                    ch if ch.is_ascii_alphanumeric() || ch == '.' => scanner.skip(1),
+                    _ => break,
+                }
+            }
+            // suffixes such as the `u` of unsigned ints and the `f` of floats are not part of the
+            // value
+            match scanner.str_from(start).trim_end_matches(['u', 'U', 'f', 'F', 'l', 'L']) {
+                text if text.starts_with("0x") || text.starts_with("0X") => FullToken::OtherNumber,
+                text => if let Ok(value) = text.parse::<i64>() {
+                    FullToken::Int(value)
+                } else if let Ok(value) = text.parse::<f64>() {
+                    FullToken::Float(value)
+                } else {
+                    FullToken::OtherNumber
+                }
+            }
+        }
+        ch if ch.is_alphabetic() || ch == '_' => {
+            let start = scanner.index();
+            scanner.skip_while( | ch | ch.is_alphanumeric() || ch == '_');
+            match scanner.id_from(start) {
+                id!(true) => FullToken::Bool(true),
+                id!(false) => FullToken::Bool(false),
+                id => FullToken::Ident(id),
+            }
+        }
+        ch if ch.is_ascii_punctuation() => {
+            let start = scanner.index();
+            let len = OPERATORS.iter().find( | operator | scanner.starts_with(operator)).map_or(1, | operator | operator.len());
+            scanner.skip(len);
+            FullToken::Punct(scanner.id_from(start))
+        }
+        _ => scanner.token(1, FullToken::Unknown),
+    };
+    (State::Initial, token)
+}
+
+fn block_comment_tail(scanner: &mut Scanner) -> (State, FullToken) {
+    if scanner.skip_past("*/") {
+        (State::Initial, FullToken::Comment)
+    } else {
+        (State::BlockCommentTail, FullToken::Comment)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    makepad_live_id::*,
+    rust_editor::rust_tokenizer::full_token::{Delim, FullToken, TokenWithLen},
+    languages::{
+        scanner::{scan_line, Scanner},
+        Language,
+    },
+};
+
+/// JSON, including the `//` and `/* */` comments of JSON with comments.
+pub struct Json;
+
+/// The state of the JSON tokenizer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    BlockCommentTail,
+}
+
+impl Language for Json {
+    type State = State;
+
+    fn tokenize_line(
+        state: State,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> State {
+        scan_line(state, line, scratch, tokens, | state, scanner | match state {
+            State::Initial => next(scanner),
+            State::BlockCommentTail => block_comment_tail(scanner),
+        })
+    }
+}
+
+fn next(scanner: &mut Scanner) -> (State, FullToken) {
+    let token = match scanner.peek(0) {
+        ch if ch.is_whitespace() => scanner.whitespace(),
+        '/' if scanner.peek(1) == '/' => {
+            scanner.skip_to_end();
+            FullToken::Comment
+        }
+        '/' if scanner.peek(1) == '*' => {
+            scanner.skip(2);
+            return block_comment_tail(scanner);
+        }
+        '"' => {
+            scanner.skip(1);
+            loop {
+                match scanner.peek(0) {
+                    '\\' => scanner.skip(2),
+                    '"' => {
+                        scanner.skip(1);
+                        break;
+                    }
+                    _ if scanner.is_at_end() => break,
+                    _ => scanner.skip(1),
+                }
+            }
+            FullToken::String
+        }
+        '{' => scanner.token(1, FullToken::Open(Delim::Brace)),
+        '}' => scanner.token(1, FullToken::Close(Delim::Brace)),
+        '[' => scanner.token(1, FullToken::Open(Delim::Bracket)),
+        ']' => scanner.token(1, FullToken::Close(Delim::Bracket)),
+        ':' => scanner.token(1, FullToken::Punct(id!(:))),
+        ',' => scanner.token(1, FullToken::Punct(id!(,))),
+        ch if ch == '-' || ch.is_ascii_digit() => {
+            let start = scanner.index();
+            scanner.skip(1);
+            scanner.skip_while( | ch | ch.is_ascii_alphanumeric() || ch == '.' || ch == '+' || ch == '-');
+            scanner.number_from(start)
+        }
+        ch if ch.is_alphabetic() => {
+            let start = scanner.index();
+            scanner.skip_while( | ch | ch.is_alphanumeric() || ch == '_');
+            match scanner.id_from(start) {
+                id!(true) => FullToken::Bool(true),
+                id!(false) => FullToken::Bool(false),
+                id => FullToken::Ident(id),
+            }
+        }
+        _ => scanner.token(1, FullToken::Unknown),
+    };
+    (State::Initial, token)
+}
+
+fn block_comment_tail(scanner: &mut Scanner) -> (State, FullToken) {
+    if scanner.skip_past("*/") {
+        (State::Initial, FullToken::Comment)
+    } else {
+        (State::BlockCommentTail, FullToken::Comment)
+    }
+}
@@ -0,0 +1,115 @@
+use {
+    crate::{
+        makepad_collab_protocol::unix_path::UnixPath,
+        rust_editor::rust_tokenizer::full_token::TokenWithLen,
+        languages::{
+            glsl::{self, Glsl},
+            json::{self, Json},
+            live::{self, Live},
+            markdown::{self, Markdown},
+            plain_text::PlainText,
+            rust::{self, Rust},
+            toml::{self, Toml},
+        },
+    },
+    std::fmt::Debug,
+};
+
+/// A language that can be tokenized one line at a time.
+///
+/// Like the Rust tokenizer, the tokenizer of a language has an explicit state, which is recorded
+/// at the start and end of each line, so that a line only has to be tokenized again if either its
+/// contents or its start state changed.
+pub trait Language {
+    /// The state of the tokenizer at the start of a line. The default state is the one at the
+    /// start of the text.
+    type State: Clone + Copy + Debug + Default + Eq;
+
+    /// Tokenizes the given `line`, starting in the given `state`, and appends its tokens to
+    /// `tokens`. The `scratch` parameter provides scratch storage for building a string when
+    /// necessary. Returns the state at the end of the line.
+    fn tokenize_line(
+        state: Self::State,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> Self::State;
+}
+
+/// The languages that the editor knows how to tokenize.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LanguageKind {
+    Rust,
+    Live,
+    Toml,
+    Json,
+    Markdown,
+    Glsl,
+    PlainText,
+}
+
+impl LanguageKind {
+    /// Returns the language of the file at the given `path`, based on its extension.
+    pub fn from_path(path: &UnixPath) -> LanguageKind {
+        let path = path.as_unix_str().to_string_lossy();
+        let file_name = path.rsplit('/').next().unwrap_or("");
+        match file_name.rsplit_once('.').map( | (_, extension) | extension) {
+            Some("rs") => LanguageKind::Rust,
+            Some("live") => LanguageKind::Live,
+            Some("toml") => LanguageKind::Toml,
+            Some("json") => LanguageKind::Json,
+            Some("md") | Some("markdown") => LanguageKind::Markdown,
+            Some("glsl") | Some("vert") | Some("frag") => LanguageKind::Glsl,
+            _ => LanguageKind::PlainText,
+        }
+    }
+}
+
+/// The state of the tokenizer of any of the languages in `LanguageKind`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LanguageState {
+    Rust(rust::State),
+    Live(live::State),
+    Toml(toml::State),
+    Json(json::State),
+    Markdown(markdown::State),
+    Glsl(glsl::State),
+    PlainText,
+}
+
+impl LanguageState {
+    /// Returns the state at the start of a text in the given `language`.
+    pub fn new(language: LanguageKind) -> LanguageState {
+        match language {
+            LanguageKind::Rust => LanguageState::Rust(Default::default()),
+            LanguageKind::Live => LanguageState::Live(Default::default()),
+            LanguageKind::Toml => LanguageState::Toml(Default::default()),
+            LanguageKind::Json => LanguageState::Json(Default::default()),
+            LanguageKind::Markdown => LanguageState::Markdown(Default::default()),
+            LanguageKind::Glsl => LanguageState::Glsl(Default::default()),
+            LanguageKind::PlainText => LanguageState::PlainText,
+        }
+    }
+
+    /// Tokenizes the given `line` with the tokenizer of the language this state belongs to. See
+    /// `Language::tokenize_line`.
+    pub fn tokenize_line(
+        self,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> LanguageState {
+        match self {
+            LanguageState::Rust(state) => LanguageState::Rust(Rust::tokenize_line(state, line, scratch, tokens)),
+            LanguageState::Live(state) => LanguageState::Live(Live::tokenize_line(state, line, scratch, tokens)),
+            LanguageState::Toml(state) => LanguageState::Toml(Toml::tokenize_line(state, line, scratch, tokens)),
+            LanguageState::Json(state) => LanguageState::Json(Json::tokenize_line(state, line, scratch, tokens)),
+            LanguageState::Markdown(state) => LanguageState::Markdown(Markdown::tokenize_line(state, line, scratch, tokens)),
+            LanguageState::Glsl(state) => LanguageState::Glsl(Glsl::tokenize_line(state, line, scratch, tokens)),
+            LanguageState::PlainText => {
+                PlainText::tokenize_line((), line, scratch, tokens);
+                LanguageState::PlainText
+            }
+        }
+    }
+}
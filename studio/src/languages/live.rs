@@ -0,0 +1,88 @@
+use crate::{
+    makepad_live_tokenizer::{
+        full_token::{Delim as LiveDelim, FullToken as LiveToken},
+        tokenizer::Cursor,
+    },
+    rust_editor::rust_tokenizer::full_token::{Delim, FullToken, TokenWithLen},
+    languages::Language,
+};
+
+pub use crate::makepad_live_tokenizer::tokenizer::State;
+
+/// The live DSL, as used in `.live` files and in the body of `live_register!` macros, tokenized
+/// with the tokenizer of the live compiler.
+pub struct Live;
+
+impl Language for Live {
+    type State = State;
+
+    fn tokenize_line(
+        state: State,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> State {
+        tokenize_until(state, line, scratch, tokens, | _ | false).0
+    }
+}
+
+/// Tokenizes the given `line`, starting in the given `state`, until either the end of the line
+/// or the first token for which `is_last` returns true, and appends the tokens to `tokens`.
+///
+/// Returns the state after the last token, and the number of chars that were tokenized.
+pub fn tokenize_until<F>(
+    mut state: State,
+    line: &[char],
+    scratch: &mut String,
+    tokens: &mut Vec<TokenWithLen>,
+    mut is_last: F
+) -> (State, usize)
+where
+    F: FnMut(FullToken) -> bool,
+{
+    let mut cursor = Cursor::new(line, scratch);
+    let mut len = 0;
+    loop {
+        let (next_state, token) = state.next(&mut cursor);
+        state = next_state;
+        let token = match token {
+            Some(token) => token,
+            None => break,
+        };
+        let token = TokenWithLen {len: token.len, token: to_full_token(token.token)};
+        len += token.len;
+        tokens.push(token);
+        if is_last(token.token) {
+            break;
+        }
+    }
+    (state, len)
+}
+
+// Converts a token of the live tokenizer to the equivalent token of the Rust tokenizer.
+fn to_full_token(token: LiveToken) -> FullToken {
+    match token {
+        LiveToken::Punct(id) => FullToken::Punct(id),
+        LiveToken::Ident(id) => FullToken::Ident(id),
+        LiveToken::Open(delim) => FullToken::Open(to_delim(delim)),
+        LiveToken::Close(delim) => FullToken::Close(to_delim(delim)),
+        LiveToken::String | LiveToken::Dependency => FullToken::String,
+        LiveToken::Bool(value) => FullToken::Bool(value),
+        LiveToken::Color(value) => FullToken::Color(value),
+        LiveToken::Float(value) => FullToken::Float(value),
+        LiveToken::Int(value) => FullToken::Int(value),
+        LiveToken::OtherNumber => FullToken::OtherNumber,
+        LiveToken::Lifetime => FullToken::Lifetime,
+        LiveToken::Comment => FullToken::Comment,
+        LiveToken::Whitespace => FullToken::Whitespace,
+        LiveToken::Unknown => FullToken::Unknown,
+    }
+}
+
+fn to_delim(delim: LiveDelim) -> Delim {
+    match delim {
+        LiveDelim::Paren => Delim::Paren,
+        LiveDelim::Bracket => Delim::Bracket,
+        LiveDelim::Brace => Delim::Brace,
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    makepad_live_id::*,
+    rust_editor::rust_tokenizer::full_token::{FullToken, TokenWithLen},
+    languages::{
+        scanner::{scan_line, Scanner},
+        Language,
+    },
+};
+
+/// Markdown, where headings, emphasis, code and the markers of block quotes and lists are
+/// tokenized, and everything else is text.
+pub struct Markdown;
+
+/// The state of the Markdown tokenizer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    /// Inside a fenced code block that was opened with `len` times the `fence` char.
+    FencedCode {fence: char, len: usize},
+    HtmlCommentTail,
+}
+
+impl Language for Markdown {
+    type State = State;
+
+    fn tokenize_line(
+        state: State,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> State {
+        scan_line(state, line, scratch, tokens, | state, scanner | match state {
+            State::Initial => next(scanner),
+            State::FencedCode {fence, len} => fenced_code(scanner, fence, len),
+            State::HtmlCommentTail => html_comment_tail(scanner),
+        })
+    }
+}
+
+fn next(scanner: &mut Scanner) -> (State, FullToken) {
+    let ch = scanner.peek(0);
+    if ch.is_whitespace() {
+        return (State::Initial, scanner.whitespace());
+    }
+    if scanner.is_at_line_start() {
+        if let Some(result) = block_marker(scanner, ch) {
+            return result;
+        }
+    }
+    let token = match ch {
+        '<' if scanner.starts_with("<!--") => {
+            scanner.skip(4);
+            return html_comment_tail(scanner);
+        }
+        '\\' => scanner.token(2, FullToken::Text),
+        '`' => {
+            let len = scanner.count('`');
+            match find_run(scanner.rest(), '`', len) {
+                Some(end) => scanner.token(end + len, FullToken::String),
+                None => scanner.token(len, FullToken::Text),
+            }
+        }
+        '*' | '_' => {
+            let len = scanner.count(ch);
+            match find_run(scanner.rest(), ch, len) {
+                Some(end) if len <= 3 && end > len => scanner.token(end + len, FullToken::Emphasis),
+                _ => scanner.token(len, FullToken::Text),
+            }
+        }
+        _ => {
+            scanner.skip(1);
+            scanner.skip_while( | ch | !ch.is_whitespace() && !matches!(ch, '<' | '\\' | '`' | '*' | '_'));
+            FullToken::Text
+        }
+    };
+    (State::Initial, token)
+}
+
+// Returns the marker that starts the block on the current line, if any.
+fn block_marker(scanner: &mut Scanner, ch: char) -> Option<(State, FullToken)> {
+    let start = scanner.index();
+    let len = scanner.count(ch);
+    let rest = scanner.rest();
+    let is_followed_by_whitespace = | offset: usize | rest.get(offset).is_none_or( | ch | ch.is_whitespace());
+    match ch {
+        '`' | '~' if len >= 3 => {
+            scanner.skip(len);
+            let id = scanner.id_from(start);
+            Some((State::FencedCode {fence: ch, len}, FullToken::Punct(id)))
+        }
+        '#' if len <= 6 && is_followed_by_whitespace(len) => {
+            scanner.skip_to_end();
+            Some((State::Initial, FullToken::Heading))
+        }
+        '-' | '*' | '_' if len >= 3 && rest.iter().all( | other | *other == ch || other.is_whitespace()) => {
+            // a thematic break
+            scanner.skip_to_end();
+            Some((State::Initial, FullToken::Punct(scanner.id_from(start))))
+        }
+        '>' => Some((State::Initial, scanner.token(1, FullToken::Punct(id!(>))))),
+        '-' | '*' | '+' if is_followed_by_whitespace(1) => {
+            scanner.skip(1);
+            let id = scanner.id_from(start);
+            Some((State::Initial, FullToken::Punct(id)))
+        }
+        ch if ch.is_ascii_digit() => {
+            let digits = rest.iter().take_while( | ch | ch.is_ascii_digit()).count();
+            if !matches!(scanner.peek(digits), '.' | ')') || !is_followed_by_whitespace(digits + 1) {
+                return None;
+            }
+            scanner.skip(digits + 1);
+            Some((State::Initial, FullToken::Punct(scanner.id_from(start))))
+        }
+        _ => None,
+    }
+}
+
+fn fenced_code(scanner: &mut Scanner, fence: char, len: usize) -> (State, FullToken) {
+    if scanner.is_at_line_start() {
+        if scanner.peek(0).is_whitespace() {
+            return (State::FencedCode {fence, len}, scanner.whitespace());
+        }
+        let fence_len = scanner.count(fence);
+        if fence_len >= len && scanner.rest()[fence_len..].iter().all( | ch | ch.is_whitespace()) {
+            let start = scanner.index();
+            scanner.skip(fence_len);
+            return (State::Initial, FullToken::Punct(scanner.id_from(start)));
+        }
+    }
+    scanner.skip_to_end();
+    (State::FencedCode {fence, len}, FullToken::String)
+}
+
+fn html_comment_tail(scanner: &mut Scanner) -> (State, FullToken) {
+    if scanner.skip_past("-->") {
+        (State::Initial, FullToken::Comment)
+    } else {
+        (State::HtmlCommentTail, FullToken::Comment)
+    }
+}
+
+// Returns the offset in `chars` of the first run of exactly `len` times `ch` after the one that
+// `chars` starts with, if any.
+fn find_run(chars: &[char], ch: char, len: usize) -> Option<usize> {
+    let mut index = len;
+    while index < chars.len() {
+        if chars[index] != ch {
+            index += 1;
+            continue;
+        }
+        let run_len = chars[index..].iter().take_while( | other | **other == ch).count();
+        if run_len == len {
+            return Some(index);
+        }
+        index += run_len;
+    }
+    None
+}
@@ -0,0 +1,11 @@
+pub mod language;
+pub mod scanner;
+pub mod glsl;
+pub mod json;
+pub mod live;
+pub mod markdown;
+pub mod plain_text;
+pub mod rust;
+pub mod toml;
+
+pub use language::*;
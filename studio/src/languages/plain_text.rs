@@ -0,0 +1,29 @@
+use crate::{
+    rust_editor::rust_tokenizer::full_token::{FullToken, TokenWithLen},
+    languages::{
+        scanner::scan_line,
+        Language,
+    },
+};
+
+/// Text without any syntax, which is split into runs of whitespace and of other chars.
+pub struct PlainText;
+
+impl Language for PlainText {
+    type State = ();
+
+    fn tokenize_line(
+        state: (),
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) {
+        scan_line(state, line, scratch, tokens, | state, scanner | {
+            if scanner.peek(0).is_whitespace() {
+                return (state, scanner.whitespace());
+            }
+            scanner.skip_while( | ch | !ch.is_whitespace());
+            (state, FullToken::Text)
+        })
+    }
+}
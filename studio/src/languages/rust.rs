@@ -0,0 +1,86 @@
+use crate::{
+    makepad_live_id::*,
+    rust_editor::rust_tokenizer::{
+        full_token::{FullToken, TokenWithLen},
+        tokenizer::{self, Cursor},
+    },
+    languages::{
+        live,
+        Language,
+    },
+};
+
+/// Rust, where the bodies of `live_register!` macros are tokenized as the live DSL.
+pub struct Rust;
+
+/// The state of the Rust tokenizer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum State {
+    /// Tokenizing Rust. `macro_prefix` is the number of tokens of `live_register!` that were last
+    /// seen, ignoring whitespace and comments.
+    Rust {state: tokenizer::State, macro_prefix: usize},
+    /// Tokenizing the body of a `live_register!` macro. `depth` is the number of delimiters that
+    /// were opened inside the body and not closed yet.
+    Live {state: live::State, depth: usize},
+}
+
+impl Default for State {
+    fn default() -> State {
+        State::Rust {state: tokenizer::State::default(), macro_prefix: 0}
+    }
+}
+
+impl Language for Rust {
+    type State = State;
+
+    fn tokenize_line(
+        mut state: State,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> State {
+        let mut start = 0;
+        loop {
+            match state {
+                State::Rust {state: mut rust_state, mut macro_prefix} => {
+                    let mut cursor = Cursor::new(&line[start..], scratch);
+                    loop {
+                        let (next_state, token) = rust_state.next(&mut cursor);
+                        rust_state = next_state;
+                        let token = match token {
+                            Some(token) => token,
+                            None => return State::Rust {state: rust_state, macro_prefix},
+                        };
+                        start += token.len;
+                        tokens.push(token);
+                        macro_prefix = match (macro_prefix, token.token) {
+                            (_, FullToken::Whitespace) | (_, FullToken::Comment) => macro_prefix,
+                            (_, FullToken::Ident(id!(live_register))) => 1,
+                            (1, FullToken::Punct(id!(!))) => 2,
+                            (2, FullToken::Open(_)) => break,
+                            _ => 0,
+                        };
+                    }
+                    state = State::Live {state: live::State::default(), depth: 0};
+                }
+                State::Live {state: live_state, mut depth} => {
+                    let mut is_closed = false;
+                    let (live_state, len) = live::tokenize_until(live_state, &line[start..], scratch, tokens, | token | {
+                        match token {
+                            FullToken::Open(_) => depth += 1,
+                            FullToken::Close(_) if depth == 0 => is_closed = true,
+                            FullToken::Close(_) => depth -= 1,
+                            _ => {}
+                        }
+                        is_closed
+                    });
+                    if !is_closed {
+                        return State::Live {state: live_state, depth};
+                    }
+                    start += len;
+                    state = State::default();
+                }
+            }
+        }
+    }
+}
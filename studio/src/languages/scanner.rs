@@ -0,0 +1,160 @@
+use crate::{
+    makepad_live_id::LiveId,
+    rust_editor::rust_tokenizer::full_token::{FullToken, TokenWithLen},
+};
+
+/// A cursor over the chars of a single line, shared by the tokenizers of the languages that don't
+/// have a tokenizer of their own elsewhere.
+pub struct Scanner<'a> {
+    chars: &'a [char],
+    scratch: &'a mut String,
+    index: usize,
+}
+
+impl<'a> Scanner<'a> {
+    /// Creates a scanner over a slice of chars. The `scratch` parameter provides scratch storage
+    /// for building a string when necessary.
+    pub fn new(chars: &'a [char], scratch: &'a mut String) -> Scanner<'a> {
+        Scanner {chars, scratch, index: 0}
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.index >= self.chars.len()
+    }
+
+    /// Returns true if there is nothing but whitespace before the scanner on its line.
+    pub fn is_at_line_start(&self) -> bool {
+        self.chars[..self.index].iter().all( | ch | ch.is_whitespace())
+    }
+
+    /// Returns the char `offset` chars ahead of the scanner, or `'\0'` if that is past the end of
+    /// the line.
+    pub fn peek(&self, offset: usize) -> char {
+        self.chars.get(self.index + offset).cloned().unwrap_or('\0')
+    }
+
+    /// Returns the chars from the scanner to the end of the line.
+    pub fn rest(&self) -> &'a [char] {
+        &self.chars[self.index..]
+    }
+
+    /// Returns true if the chars ahead of the scanner are those of `prefix`.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        let mut offset = 0;
+        prefix.chars().all( | ch | {
+            offset += 1;
+            self.peek(offset - 1) == ch
+        })
+    }
+
+    /// Returns the number of times `ch` occurs in a row ahead of the scanner.
+    pub fn count(&self, ch: char) -> usize {
+        self.chars[self.index..].iter().take_while( | other | **other == ch).count()
+    }
+
+    pub fn skip(&mut self, count: usize) {
+        self.index = (self.index + count).min(self.chars.len());
+    }
+
+    pub fn skip_if<P>(&mut self, predicate: P) -> bool
+    where
+        P: FnOnce(char) -> bool,
+    {
+        if !self.is_at_end() && predicate(self.peek(0)) {
+            self.skip(1);
+            return true;
+        }
+        false
+    }
+
+    pub fn skip_while<P>(&mut self, mut predicate: P)
+    where
+        P: FnMut(char) -> bool,
+    {
+        while self.skip_if(&mut predicate) {}
+    }
+
+    /// Skips `len` chars that make up the given `token`, and returns the token.
+    pub fn token(&mut self, len: usize, token: FullToken) -> FullToken {
+        self.skip(len);
+        token
+    }
+
+    pub fn skip_to_end(&mut self) {
+        self.index = self.chars.len();
+    }
+
+    /// Skips up to and including the given `suffix`. Returns false, after skipping to the end of
+    /// the line, if the suffix does not occur on the rest of the line.
+    pub fn skip_past(&mut self, suffix: &str) -> bool {
+        while !self.is_at_end() {
+            if self.starts_with(suffix) {
+                self.skip(suffix.chars().count());
+                return true;
+            }
+            self.skip(1);
+        }
+        false
+    }
+
+    /// Returns the chars from `start` up to the scanner as a string.
+    pub fn str_from(&mut self, start: usize) -> &str {
+        self.scratch.clear();
+        self.scratch.extend(&self.chars[start..self.index]);
+        self.scratch
+    }
+
+    /// Returns the id of the chars from `start` up to the scanner.
+    pub fn id_from(&mut self, start: usize) -> LiveId {
+        LiveId::from_str(self.str_from(start)).unwrap()
+    }
+
+    /// Returns the token for the number from `start` up to the scanner, ignoring any underscores
+    /// in it. Numbers that are neither an integer nor a float are returned as `OtherNumber`.
+    pub fn number_from(&mut self, start: usize) -> FullToken {
+        self.scratch.clear();
+        self.scratch.extend(self.chars[start..self.index].iter().filter( | ch | **ch != '_'));
+        if let Ok(value) = self.scratch.parse::<i64>() {
+            FullToken::Int(value)
+        } else if let Ok(value) = self.scratch.parse::<f64>() {
+            FullToken::Float(value)
+        } else {
+            FullToken::OtherNumber
+        }
+    }
+
+    /// Skips a run of whitespace.
+    pub fn whitespace(&mut self) -> FullToken {
+        self.skip_while( | ch | ch.is_whitespace());
+        FullToken::Whitespace
+    }
+}
+
+/// Runs `next` on a scanner over `line` until it reaches the end of the line, and appends the
+/// tokens it returns to `tokens`. Each call to `next` gets the state returned by the previous one,
+/// starting with `state`, and must move the scanner forward. Returns the state at the end of the
+/// line.
+pub fn scan_line<S, F>(
+    mut state: S,
+    line: &[char],
+    scratch: &mut String,
+    tokens: &mut Vec<TokenWithLen>,
+    mut next: F
+) -> S
+where
+    F: FnMut(S, &mut Scanner) -> (S, FullToken),
+{
+    let mut scanner = Scanner::new(line, scratch);
+    while !scanner.is_at_end() {
+        let start = scanner.index();
+        let (next_state, token) = next(state, &mut scanner);
+        state = next_state;
+        assert!(start < scanner.index());
+        tokens.push(TokenWithLen {len: scanner.index() - start, token});
+    }
+    state
+}
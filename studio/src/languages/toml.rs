@@ -0,0 +1,132 @@
+use crate::{
+    makepad_live_id::*,
+    rust_editor::rust_tokenizer::full_token::{Delim, FullToken, TokenWithLen},
+    languages::{
+        scanner::{scan_line, Scanner},
+        Language,
+    },
+};
+
+/// TOML, where the headers of tables are tokenized as headings.
+pub struct Toml;
+
+/// The state of the TOML tokenizer.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum State {
+    #[default]
+    Initial,
+    MultiLineBasicStringTail,
+    MultiLineLiteralStringTail,
+}
+
+impl Language for Toml {
+    type State = State;
+
+    fn tokenize_line(
+        state: State,
+        line: &[char],
+        scratch: &mut String,
+        tokens: &mut Vec<TokenWithLen>
+    ) -> State {
+        scan_line(state, line, scratch, tokens, | state, scanner | match state {
+            State::Initial => next(scanner),
+            State::MultiLineBasicStringTail => multi_line_basic_string_tail(scanner),
+            State::MultiLineLiteralStringTail => multi_line_literal_string_tail(scanner),
+        })
+    }
+}
+
+fn next(scanner: &mut Scanner) -> (State, FullToken) {
+    let token = match scanner.peek(0) {
+        ch if ch.is_whitespace() => scanner.whitespace(),
+        '#' => {
+            scanner.skip_to_end();
+            FullToken::Comment
+        }
+        '[' if scanner.is_at_line_start() => {
+            scanner.skip_past("]");
+            scanner.skip_if( | ch | ch == ']');
+            FullToken::Heading
+        }
+        '[' => scanner.token(1, FullToken::Open(Delim::Bracket)),
+        ']' => scanner.token(1, FullToken::Close(Delim::Bracket)),
+        '{' => scanner.token(1, FullToken::Open(Delim::Brace)),
+        '}' => scanner.token(1, FullToken::Close(Delim::Brace)),
+        '=' => scanner.token(1, FullToken::Punct(id!(=))),
+        '.' => scanner.token(1, FullToken::Punct(id!(.))),
+        ',' => scanner.token(1, FullToken::Punct(id!(,))),
+        '"' if scanner.starts_with("\"\"\"") => {
+            scanner.skip(3);
+            return multi_line_basic_string_tail(scanner);
+        }
+        '"' => {
+            scanner.skip(1);
+            loop {
+                match scanner.peek(0) {
+                    '\\' => scanner.skip(2),
+                    '"' => {
+                        scanner.skip(1);
+                        break;
+                    }
+                    _ if scanner.is_at_end() => break,
+                    _ => scanner.skip(1),
+                }
+            }
+            FullToken::String
+        }
+        '\'' if scanner.starts_with("'''") => {
+            scanner.skip(3);
+            return multi_line_literal_string_tail(scanner);
+        }
+        '\'' => {
+            scanner.skip(1);
+            scanner.skip_past("'");
+            FullToken::String
+        }
+        ch if ch.is_ascii_digit() || (ch == '+' || ch == '-') && scanner.peek(1).is_ascii_digit() => {
+            // dates and times are scanned as a single number as well
+            let start = scanner.index();
+            scanner.skip(1);
+            scanner.skip_while( | ch | {
+                ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == ':' || ch == '+' || ch == '-'
+            });
+            scanner.number_from(start)
+        }
+        ch if ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' => {
+            let start = scanner.index();
+            scanner.skip_while( | ch | ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+            match scanner.id_from(start) {
+                id!(true) => FullToken::Bool(true),
+                id!(false) => FullToken::Bool(false),
+                id => FullToken::Ident(id),
+            }
+        }
+        _ => scanner.token(1, FullToken::Unknown),
+    };
+    (State::Initial, token)
+}
+
+fn multi_line_basic_string_tail(scanner: &mut Scanner) -> (State, FullToken) {
+    loop {
+        match scanner.peek(0) {
+            '\\' => scanner.skip(2),
+            '"' if scanner.starts_with("\"\"\"") => {
+                // up to two more quotes can be part of the string
+                let len = scanner.count('"').min(5);
+                scanner.skip(len);
+                return (State::Initial, FullToken::String);
+            }
+            _ if scanner.is_at_end() => return (State::MultiLineBasicStringTail, FullToken::String),
+            _ => scanner.skip(1),
+        }
+    }
+}
+
+fn multi_line_literal_string_tail(scanner: &mut Scanner) -> (State, FullToken) {
+    if scanner.skip_past("'''") {
+        scanner.skip(scanner.count('\'').min(2));
+        (State::Initial, FullToken::String)
+    } else {
+        (State::MultiLineLiteralStringTail, FullToken::String)
+    }
+}
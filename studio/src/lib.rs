@@ -9,6 +9,15 @@ pub mod code_editor;
 pub mod editors;
 pub mod editor_state;
 pub mod log_view;
+pub mod history_view;
+pub mod quick_open;
+pub mod git_state;
+pub mod live_state;
+pub mod search_state;
+pub mod search_view;
+pub mod workspace;
+pub mod lsp;
+pub mod languages;
 pub mod rust_editor;
 
 pub use makepad_collab_protocol;
@@ -21,7 +30,8 @@ pub use makepad_platform::makepad_live_id;
 
 #[cfg(target_arch = "wasm32")]
 pub use makepad_platform::makepad_wasm_bridge;
-//pub use makepad_platform::makepad_live_tokenizer;
+pub use makepad_platform::makepad_live_tokenizer;
 pub use makepad_platform::makepad_live_compiler;
 pub use makepad_platform::makepad_math;
 pub use makepad_editor_core;
+pub use makepad_segment;
@@ -0,0 +1,81 @@
+use {
+    std::collections::BTreeMap,
+    crate::{
+        makepad_platform::*,
+        makepad_live_compiler::LiveFileError,
+        makepad_collab_protocol::unix_path::UnixPath,
+        makepad_editor_core::{
+            position::Position,
+            range::Range,
+        },
+        builder::builder_protocol::{BuilderMsg, BuilderMsgLevel, BuilderMsgLocation},
+        editor_state::EditorState,
+    },
+};
+
+/// The live documents that studio itself is built from, such as the shaders of its views, are
+/// defined in `live_register!` blocks of its Rust files. When live editing of studio is enabled
+/// with `live_edit_studio`, such a file that is edited in studio updates its live document right
+/// away, so the change shows up while typing. This is off by default, since it changes the
+/// running studio rather than the app that is being worked on, whose live documents are checked
+/// by the builder instead.
+///
+/// The errors in those documents, and in the shaders that are compiled from them, are turned into
+/// the live messages of the editor, which are shown in the source.
+#[derive(Default)]
+pub struct LiveState {
+    // the errors of the last update of each live file that was edited, by file name
+    edit_errors: BTreeMap<String, Vec<LiveFileError>>,
+    // the errors that the live messages were last made from
+    errors: Vec<LiveFileError>,
+}
+
+impl LiveState {
+    /// Updates the live documents from the documents in `state` that define them and that were
+    /// edited since the last update. `root` is the path that the paths of the documents are
+    /// relative to.
+    pub fn sync_documents(&mut self, cx: &mut Cx, root: &UnixPath, state: &mut EditorState) {
+        for document in state.documents.values_mut() {
+            let path = &document.path;
+            let inner = match &mut document.inner {
+                Some(inner) if inner.live_edited => inner,
+                _ => continue
+            };
+            inner.live_edited = false;
+            let file_name = cx.live_registry.borrow().file_ids.keys().find( | file_name | {
+                file_name.ends_with(".rs") && root.join(file_name.as_str()) == *path
+            }).cloned();
+            if let Some(file_name) = file_name {
+                let errors = cx.update_live_file_from_rust(&file_name, &inner.text.to_string());
+                self.edit_errors.insert(file_name, errors);
+            }
+        }
+    }
+
+    /// Replaces the live messages in `state` if the errors in the live documents or in their
+    /// shaders changed since they were last replaced. Returns whether they were replaced.
+    pub fn update_messages(&mut self, cx: &Cx, root: &UnixPath, state: &mut EditorState) -> bool {
+        let errors: Vec<LiveFileError> = self.edit_errors
+            .values()
+            .flatten()
+            .chain(cx.shader_errors())
+            .cloned()
+            .collect();
+        if errors == self.errors {
+            return false;
+        }
+        state.set_live_messages(errors.iter().map( | error | {
+            BuilderMsg::Location(BuilderMsgLocation {
+                level: BuilderMsgLevel::Error,
+                file_name: root.join(error.file.as_str()).as_unix_str().to_string_lossy().into_owned(),
+                range: Range {
+                    start: Position {line: error.span.start.line as usize, column: error.span.start.column as usize},
+                    end: Position {line: error.span.end.line as usize, column: error.span.end.column as usize},
+                },
+                msg: error.message.clone(),
+            })
+        }).collect());
+        self.errors = errors;
+        true
+    }
+}
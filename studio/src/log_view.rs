@@ -56,7 +56,14 @@ impl LogView {
         let mut file = String::new();
         let mut body = String::new();
         if self.log_list.begin(cx).is_ok(){
-            for (index, msg) in state.messages.iter().enumerate(){
+            // while a run is in progress we show its messages as they come in
+            let messages = if state.builder_run.is_some() {
+                &state.pending_messages
+            }
+            else {
+                &state.messages
+            };
+            for (index, msg) in messages.iter().chain(&state.live_messages).enumerate(){
                 if self.log_list.should_node_draw(cx){
                     file.clear();
                     body.clear();
@@ -64,6 +71,8 @@ impl LogView {
                     match msg{
                         BuilderMsg::Bare(_msg)=>{
                         }
+                        BuilderMsg::RunStarted | BuilderMsg::RunFinished=>{
+                        }
                         BuilderMsg::Location(msg)=>{
                             write!(file, "{}:{}", msg.file_name, msg.range.start.line).unwrap();
                             self.log_list.draw_node(cx, msg.level.into(), id, &file, &msg.msg, true);
@@ -0,0 +1,186 @@
+use {
+    crate::makepad_micro_serde::*,
+    std::str::Chars,
+};
+
+/// A type for representing arbitrary JSON values.
+///
+/// The messages of the language server protocol have a lot of optional fields and fields that can
+/// take on several different types, and servers are free to add fields of their own. This does not
+/// map well onto the derived `DeJson` implementations, which expect every field to be known in
+/// advance, so we parse each message into a `JsonValue` first, and pick it apart from there.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Creates an object with the given fields.
+    pub fn object<'a>(fields: impl IntoIterator<Item = (&'a str, JsonValue)>) -> JsonValue {
+        JsonValue::Object(
+            fields
+                .into_iter()
+                .map( | (key, value) | (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Returns the value of the field with the given `key` if this is an object that has such a
+    /// field, or `None` otherwise.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields
+                .iter()
+                .find( | (field_key, _) | field_key == key)
+                .map( | (_, value) | value),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        match self {
+            JsonValue::Null => true,
+            _ => false,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        JsonValue::Bool(value)
+    }
+}
+
+impl From<u64> for JsonValue {
+    fn from(value: u64) -> Self {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(value: usize) -> Self {
+        JsonValue::Number(value as f64)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        JsonValue::String(value.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        JsonValue::String(value)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(values: Vec<JsonValue>) -> Self {
+        JsonValue::Array(values)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => JsonValue::Null,
+        }
+    }
+}
+
+impl SerJson for JsonValue {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        match self {
+            JsonValue::Null => s.out.push_str("null"),
+            JsonValue::Bool(value) => value.ser_json(d, s),
+            JsonValue::Number(value) => value.ser_json(d, s),
+            JsonValue::String(value) => value.ser_json(d, s),
+            JsonValue::Array(values) => values.ser_json(d, s),
+            JsonValue::Object(fields) => {
+                s.out.push('{');
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        s.out.push(',');
+                    }
+                    key.ser_json(d + 1, s);
+                    s.out.push(':');
+                    value.ser_json(d + 1, s);
+                }
+                s.out.push('}');
+            }
+        }
+    }
+}
+
+impl DeJson for JsonValue {
+    fn de_json(s: &mut DeJsonState, i: &mut Chars) -> Result<JsonValue, DeJsonErr> {
+        match s.tok {
+            DeJsonTok::Null => {
+                s.next_tok(i) ?;
+                Ok(JsonValue::Null)
+            }
+            DeJsonTok::Bool(_) => Ok(JsonValue::Bool(DeJson::de_json(s, i) ?)),
+            DeJsonTok::U64(_) | DeJsonTok::I64(_) | DeJsonTok::F64(_) => {
+                Ok(JsonValue::Number(DeJson::de_json(s, i) ?))
+            }
+            DeJsonTok::Str => Ok(JsonValue::String(DeJson::de_json(s, i) ?)),
+            DeJsonTok::BlockOpen => Ok(JsonValue::Array(DeJson::de_json(s, i) ?)),
+            DeJsonTok::CurlyOpen => {
+                s.curly_open(i) ?;
+                let mut fields = Vec::new();
+                while s.tok != DeJsonTok::CurlyClose {
+                    let key = s.as_string() ?;
+                    s.next_colon(i) ?;
+                    let value = DeJson::de_json(s, i) ?;
+                    fields.push((key, value));
+                    s.eat_comma_curly(i) ?;
+                }
+                s.curly_close(i) ?;
+                Ok(JsonValue::Object(fields))
+            }
+            _ => Err(s.err_token("json value")),
+        }
+    }
+}
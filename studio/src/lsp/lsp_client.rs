@@ -0,0 +1,473 @@
+use {
+    crate::{
+        makepad_platform::*,
+        makepad_editor_core::position::Position,
+        makepad_collab_protocol::unix_path::{UnixPath, UnixPathBuf},
+        builder::builder_protocol::{BuilderMsg, BuilderMsgLevel, BuilderMsgLocation},
+        editor_state::{DocumentId, EditorState, SessionId},
+        lsp::{
+            json::JsonValue,
+            lsp_connection::LspConnection,
+            lsp_protocol::{
+                self,
+                LspCompletionItem,
+                LspDiagnosticSeverity,
+                LspError,
+                LspLocation,
+                LspMessage,
+                LspPosition,
+                LspPublishDiagnostics,
+            },
+        },
+    },
+    std::{
+        collections::HashMap,
+        env,
+        path::{Path, PathBuf},
+        sync::mpsc::TryRecvError,
+    },
+};
+
+live_register!{
+    LspClient: {{LspClient}} {}
+}
+
+/// A client for a language server, such as rust-analyzer.
+///
+/// The client keeps the language server in sync with the documents in the `EditorState`, turns the
+/// diagnostics published by the server into messages for those documents, and forwards hover,
+/// go to definition, and completion requests from the code editor to the server. If `cmd` is empty,
+/// no language server is started, and all requests are ignored.
+#[derive(Live)]
+pub struct LspClient {
+    path: String,
+    cmd: String,
+    #[rust] inner: Option<LspClientInner>,
+}
+
+impl LiveHook for LspClient {
+    fn after_apply(&mut self, _cx: &mut Cx, _apply_from: ApplyFrom, _index: usize, _nodes: &[LiveNode]) {
+        if self.inner.is_none() && !self.cmd.is_empty() {
+            self.inner = LspClientInner::start(&self.path, &self.cmd);
+        }
+    }
+}
+
+pub enum LspClientAction {
+    /// The server sent hover information for the last cursor of the given session.
+    Hover {session_id: SessionId, text: String},
+    /// The server found a definition for the symbol under the last cursor of the given session.
+    GoToDefinition {session_id: SessionId, path: UnixPathBuf, position: Position},
+    /// The server sent a list of completions for the last cursor of the given session.
+    Completion {session_id: SessionId, items: Vec<LspCompletionItem>},
+    /// The diagnostics for the given document were replaced.
+    RedrawViewsForDocument(DocumentId),
+}
+
+impl LspClient {
+    /// Brings the language server up to date with the documents in `state`. Newly initialized
+    /// documents are opened, edited documents are changed, and destroyed documents are closed.
+    pub fn sync_documents(&mut self, state: &mut EditorState) {
+        if let Some(inner) = &mut self.inner {
+            inner.sync_documents(state);
+        }
+    }
+
+    pub fn hover(&mut self, state: &mut EditorState, session_id: SessionId) {
+        self.send_position_request(state, session_id, "textDocument/hover", LspPendingRequest::Hover(session_id));
+    }
+
+    pub fn go_to_definition(&mut self, state: &mut EditorState, session_id: SessionId) {
+        self.send_position_request(state, session_id, "textDocument/definition", LspPendingRequest::Definition(session_id));
+    }
+
+    pub fn completion(&mut self, state: &mut EditorState, session_id: SessionId) {
+        self.send_position_request(state, session_id, "textDocument/completion", LspPendingRequest::Completion(session_id));
+    }
+
+    fn send_position_request(
+        &mut self,
+        state: &mut EditorState,
+        session_id: SessionId,
+        method: &str,
+        pending_request: LspPendingRequest,
+    ) {
+        if let Some(inner) = &mut self.inner {
+            // The request refers to the text as we see it, so the server should see it too.
+            inner.sync_documents(state);
+            let session = &state.sessions[session_id];
+            let document = match inner.documents.get(&session.document_id) {
+                Some(document) => document,
+                None => return,
+            };
+            let text = match &state.documents[session.document_id].inner {
+                Some(inner) => &inner.text,
+                None => return,
+            };
+            let position = LspPosition::from_position(text, session.cursors.last_inserted().head);
+            let params = JsonValue::object([
+                ("textDocument", JsonValue::object([("uri", document.uri.as_str().into())])),
+                ("position", position.to_json()),
+            ]);
+            let id = inner.connection.send_request(method, params);
+            inner.pending_requests.insert(id, pending_request);
+        }
+    }
+
+    pub fn handle_event(&mut self, cx: &mut Cx, event: &mut Event, state: &mut EditorState) -> Vec<LspClientAction> {
+        let mut a = Vec::new();
+        self.handle_event_with_fn(cx, event, state, &mut | _, v | a.push(v));
+        a
+    }
+
+    pub fn handle_event_with_fn(
+        &mut self,
+        cx: &mut Cx,
+        event: &mut Event,
+        state: &mut EditorState,
+        dispatch_action: &mut dyn FnMut(&mut Cx, LspClientAction),
+    ) {
+        let inner = match &mut self.inner {
+            Some(inner) => inner,
+            None => return,
+        };
+        match event {
+            Event::Signal(event)
+            if event.signals.contains(&inner.message_signal) => {
+                loop {
+                    match inner.connection.message_receiver().try_recv() {
+                        Ok(message) => inner.handle_message(cx, state, message, dispatch_action),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            // The language server went away. Drop the connection, so we don't
+                            // keep sending it requests.
+                            self.inner = None;
+                            return;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        inner.sync_documents(state);
+    }
+}
+
+struct LspClientInner {
+    connection: LspConnection,
+    message_signal: Signal,
+    root_path: PathBuf,
+    is_initialized: bool,
+    documents: HashMap<DocumentId, LspDocument>,
+    pending_requests: HashMap<u64, LspPendingRequest>,
+}
+
+/// A document as known to the language server.
+struct LspDocument {
+    path: UnixPathBuf,
+    uri: String,
+    version: u64,
+}
+
+enum LspPendingRequest {
+    Initialize,
+    Hover(SessionId),
+    Definition(SessionId),
+    Completion(SessionId),
+}
+
+impl LspClientInner {
+    #[cfg(target_arch = "wasm32")]
+    fn start(_subdir: &str, _cmd: &str) -> Option<Self> {
+        None
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start(subdir: &str, cmd: &str) -> Option<Self> {
+        let base_path = env::current_dir().unwrap();
+        let root_path = base_path.join(subdir.split('/').collect::<PathBuf>());
+        let message_signal = LiveId::unique().into();
+        let mut args = cmd.split_whitespace();
+        let program = args.next() ?;
+        let args = args.collect::<Vec<_>>();
+        let mut connection = match LspConnection::start(
+            program,
+            &args,
+            root_path.clone(),
+            Box::new(move || Cx::post_signal(message_signal)),
+        ) {
+            Ok(connection) => connection,
+            Err(error) => {
+                println!("Could not start language server {}: {}", cmd, error);
+                return None;
+            }
+        };
+        let id = connection.send_request("initialize", JsonValue::object([
+            ("processId", u64::from(std::process::id()).into()),
+            ("rootUri", lsp_protocol::path_to_uri(&root_path).into()),
+            ("capabilities", JsonValue::object([
+                ("textDocument", JsonValue::object([
+                    ("synchronization", JsonValue::object([("dynamicRegistration", false.into())])),
+                    ("hover", JsonValue::object([
+                        ("contentFormat", vec!["plaintext".into(), "markdown".into()].into()),
+                    ])),
+                    ("completion", JsonValue::object([
+                        ("completionItem", JsonValue::object([("snippetSupport", false.into())])),
+                    ])),
+                    ("publishDiagnostics", JsonValue::object([])),
+                ])),
+            ])),
+        ]));
+        let mut pending_requests = HashMap::new();
+        pending_requests.insert(id, LspPendingRequest::Initialize);
+        Some(Self {
+            connection,
+            message_signal,
+            root_path,
+            is_initialized: false,
+            documents: HashMap::new(),
+            pending_requests,
+        })
+    }
+
+    fn sync_documents(&mut self, state: &mut EditorState) {
+        // The server is not allowed to receive any notifications about documents before it has
+        // responded to the initialize request.
+        if !self.is_initialized {
+            return;
+        }
+        let closed_document_ids = self
+            .documents
+            .keys()
+            .filter( | document_id | !state.documents.contains_key(document_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        for document_id in closed_document_ids {
+            let document = self.documents.remove(&document_id).unwrap();
+            self.connection.send_notification("textDocument/didClose", JsonValue::object([
+                ("textDocument", JsonValue::object([("uri", document.uri.into())])),
+            ]));
+        }
+        for (document_id, document) in state.documents.iter_mut() {
+            let inner = match &mut document.inner {
+                Some(inner) => inner,
+                None => continue,
+            };
+            match self.documents.get_mut(document_id) {
+                Some(lsp_document) => {
+                    let changes = match &mut inner.lsp_changes {
+                        Some(changes) if !changes.is_empty() => changes,
+                        _ => continue,
+                    };
+                    lsp_document.version += 1;
+                    self.connection.send_notification("textDocument/didChange", JsonValue::object([
+                        ("textDocument", JsonValue::object([
+                            ("uri", lsp_document.uri.as_str().into()),
+                            ("version", lsp_document.version.into()),
+                        ])),
+                        ("contentChanges", changes.drain(..).map( | change | change.to_json()).collect::<Vec<_>>().into()),
+                    ]));
+                }
+                None => {
+                    let uri = lsp_protocol::path_to_uri(&self.local_path(&document.path));
+                    inner.lsp_changes = Some(Vec::new());
+                    self.connection.send_notification("textDocument/didOpen", JsonValue::object([
+                        ("textDocument", JsonValue::object([
+                            ("uri", uri.as_str().into()),
+                            ("languageId", language_id(&document.path).into()),
+                            ("version", 0u64.into()),
+                            ("text", inner.text.to_string().into()),
+                        ])),
+                    ]));
+                    self.documents.insert(*document_id, LspDocument {
+                        path: document.path.clone(),
+                        uri,
+                        version: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    fn handle_message(
+        &mut self,
+        cx: &mut Cx,
+        state: &mut EditorState,
+        message: LspMessage,
+        dispatch_action: &mut dyn FnMut(&mut Cx, LspClientAction),
+    ) {
+        match message {
+            LspMessage::Response {id, result} => {
+                let pending_request = match id.as_u64().and_then( | id | self.pending_requests.remove(&id)) {
+                    Some(pending_request) => pending_request,
+                    None => return,
+                };
+                let result = match result {
+                    Ok(result) => result,
+                    Err(error) => {
+                        println!("Language server error: {}", error.message);
+                        return;
+                    }
+                };
+                self.handle_response(cx, state, pending_request, result, dispatch_action);
+            }
+            LspMessage::Notification {method, params} => {
+                if method == "textDocument/publishDiagnostics" {
+                    if let Some(params) = LspPublishDiagnostics::from_json(&params) {
+                        self.handle_publish_diagnostics(cx, state, params, dispatch_action);
+                    }
+                }
+            }
+            LspMessage::Request {id, method, params} => {
+                // Servers are not supposed to depend on any of their requests being answered in a
+                // particular way, but they do expect an answer.
+                let result = match method.as_str() {
+                    "workspace/configuration" => Ok(JsonValue::Array(
+                        params
+                            .get("items")
+                            .and_then( | items | items.as_array())
+                            .map( | items | items.iter().map( | _ | JsonValue::Null).collect())
+                            .unwrap_or_default(),
+                    )),
+                    "window/workDoneProgress/create" | "client/registerCapability" => Ok(JsonValue::Null),
+                    _ => Err(LspError {
+                        code: -32601.0,
+                        message: format!("unsupported method {}", method),
+                    }),
+                };
+                self.connection.send_response(id, result);
+            }
+        }
+    }
+
+    fn handle_response(
+        &mut self,
+        cx: &mut Cx,
+        state: &mut EditorState,
+        pending_request: LspPendingRequest,
+        result: JsonValue,
+        dispatch_action: &mut dyn FnMut(&mut Cx, LspClientAction),
+    ) {
+        match pending_request {
+            LspPendingRequest::Initialize => {
+                self.connection.send_notification("initialized", JsonValue::object([]));
+                self.is_initialized = true;
+                self.sync_documents(state);
+            }
+            // The session might have been destroyed while we were waiting for the response.
+            LspPendingRequest::Hover(session_id) if state.sessions.contains_key(&session_id) => {
+                if let Some(text) = lsp_protocol::hover_contents(&result) {
+                    dispatch_action(cx, LspClientAction::Hover {session_id, text});
+                }
+            }
+            LspPendingRequest::Definition(session_id) if state.sessions.contains_key(&session_id) => {
+                let location = match LspLocation::from_definition_result(&result) {
+                    Some(location) => location,
+                    None => return,
+                };
+                let path = match lsp_protocol::uri_to_path(&location.uri)
+                    .and_then( | path | self.unix_path(&path))
+                {
+                    Some(path) => path,
+                    None => return,
+                };
+                // If we have the text of the document, we can convert the position exactly.
+                // Otherwise, we assume that each char takes up a single UTF-16 code unit.
+                let position = match state
+                    .documents_by_path
+                    .get(&path)
+                    .and_then( | document_id | state.documents[*document_id].inner.as_ref())
+                {
+                    Some(inner) => location.range.start.to_position(&inner.text),
+                    None => Position {
+                        line: location.range.start.line,
+                        column: location.range.start.character,
+                    },
+                };
+                dispatch_action(cx, LspClientAction::GoToDefinition {session_id, path, position});
+            }
+            LspPendingRequest::Completion(session_id) if state.sessions.contains_key(&session_id) => {
+                let items = LspCompletionItem::from_completion_result(&result);
+                if !items.is_empty() {
+                    dispatch_action(cx, LspClientAction::Completion {session_id, items});
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_publish_diagnostics(
+        &mut self,
+        cx: &mut Cx,
+        state: &mut EditorState,
+        params: LspPublishDiagnostics,
+        dispatch_action: &mut dyn FnMut(&mut Cx, LspClientAction),
+    ) {
+        let path = match self
+            .documents
+            .values()
+            .find( | document | document.uri == params.uri)
+        {
+            Some(document) => document.path.clone(),
+            None => return,
+        };
+        // The document can have been closed or not be loaded yet when the diagnostics arrive.
+        let text = match state
+            .documents_by_path
+            .get(&path)
+            .and_then( | document_id | state.documents.get(document_id))
+            .and_then( | document | document.inner.as_ref())
+        {
+            Some(inner) => &inner.text,
+            None => return,
+        };
+        let file_name = path.as_unix_str().to_string_lossy().into_owned();
+        let messages = params
+            .diagnostics
+            .into_iter()
+            .map( | diagnostic | BuilderMsg::Location(BuilderMsgLocation {
+                level: match diagnostic.severity {
+                    LspDiagnosticSeverity::Error => BuilderMsgLevel::Error,
+                    LspDiagnosticSeverity::Warning => BuilderMsgLevel::Warning,
+                    LspDiagnosticSeverity::Information | LspDiagnosticSeverity::Hint => BuilderMsgLevel::Log,
+                },
+                file_name: file_name.clone(),
+                range: diagnostic.range.to_range(text),
+                msg: diagnostic.message,
+            }))
+            .collect();
+        if let Some(document_id) = state.set_lsp_messages(&path, messages) {
+            dispatch_action(cx, LspClientAction::RedrawViewsForDocument(document_id));
+        }
+    }
+
+    // Returns the path on the local file system for the given path relative to the root.
+    fn local_path(&self, path: &UnixPath) -> PathBuf {
+        let path = path.as_unix_str().to_string_lossy();
+        self.root_path.join(path.split('/').collect::<PathBuf>())
+    }
+
+    // Returns the path relative to the root for the given path on the local file system, or `None`
+    // if the path is outside the root.
+    fn unix_path(&self, path: &Path) -> Option<UnixPathBuf> {
+        let path = path.strip_prefix(&self.root_path).ok() ?;
+        Some(
+            path.components()
+                .map( | component | component.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/")
+                .as_str()
+                .into(),
+        )
+    }
+}
+
+fn language_id(path: &UnixPath) -> &'static str {
+    match path.as_unix_str().to_string_lossy().rsplit('.').next() {
+        Some("rs") => "rust",
+        Some("toml") => "toml",
+        Some("js") => "javascript",
+        Some("html") => "html",
+        _ => "plaintext",
+    }
+}
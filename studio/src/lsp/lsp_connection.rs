@@ -0,0 +1,144 @@
+use {
+    crate::{
+        makepad_micro_serde::*,
+        makepad_collab_server::child_process::ChildProcess,
+        lsp::{
+            json::JsonValue,
+            lsp_protocol::{LspError, LspMessage},
+        },
+    },
+    std::{
+        io::{self, BufRead, BufReader, Write},
+        path::PathBuf,
+        process::ChildStdin,
+        sync::mpsc::{self, Receiver},
+        thread,
+    },
+};
+
+/// A connection to a language server that runs as a child process, and that we talk to with
+/// JSON-RPC over its stdin and stdout.
+pub struct LspConnection {
+    process: ChildProcess,
+    stdin: ChildStdin,
+    message_receiver: Receiver<LspMessage>,
+    next_request_id: u64,
+}
+
+impl LspConnection {
+    /// Starts the language server with the given command and arguments. The `on_message` callback
+    /// is called from the reader thread whenever a message arrives, so the caller can wake itself
+    /// up and pick the message from `message_receiver`.
+    pub fn start(
+        cmd: &str,
+        args: &[&str],
+        current_dir: PathBuf,
+        on_message: Box<dyn Fn() + Send>,
+    ) -> Result<LspConnection, io::Error> {
+        let mut process = ChildProcess::start_piped(cmd, args, current_dir, &[]) ?;
+        let stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+        let (message_sender, message_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            while let Ok(Some(json)) = read_message(&mut reader) {
+                let message = match LspMessage::from_json(&json) {
+                    Some(message) => message,
+                    None => continue,
+                };
+                if message_sender.send(message).is_err() {
+                    break;
+                }
+                on_message();
+            }
+        });
+        Ok(LspConnection {
+            process,
+            stdin,
+            message_receiver,
+            next_request_id: 0,
+        })
+    }
+
+    pub fn message_receiver(&self) -> &Receiver<LspMessage> {
+        &self.message_receiver
+    }
+
+    /// Sends a request to the server, and returns the id of the request, so the caller can match
+    /// it with its response.
+    pub fn send_request(&mut self, method: &str, params: JsonValue) -> u64 {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+        self.send_message(&LspMessage::Request {
+            id: id.into(),
+            method: method.to_string(),
+            params,
+        });
+        id
+    }
+
+    pub fn send_notification(&mut self, method: &str, params: JsonValue) {
+        self.send_message(&LspMessage::Notification {
+            method: method.to_string(),
+            params,
+        });
+    }
+
+    pub fn send_response(&mut self, id: JsonValue, result: Result<JsonValue, LspError>) {
+        self.send_message(&LspMessage::Response {id, result});
+    }
+
+    fn send_message(&mut self, message: &LspMessage) {
+        // If the server went away, there is nobody left to tell, so we ignore the error here. The
+        // reader thread will notice that the server is gone as well.
+        let _ = write_message(&mut self.stdin, &message.to_json());
+    }
+}
+
+impl Drop for LspConnection {
+    fn drop(&mut self) {
+        self.process.kill();
+    }
+}
+
+/// Reads a single message from the given reader, or returns `None` if the end of the stream was
+/// reached.
+///
+/// Each message starts with a set of headers, each terminated by "\r\n", followed by an empty line,
+/// and the content of the message, which is a JSON value. The only header we care about is
+/// Content-Length, which tells us how many bytes of content follow.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<JsonValue>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line) ? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let content_length = content_length.ok_or_else( || {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    }) ?;
+    let mut content = vec![0; content_length];
+    reader.read_exact(&mut content) ?;
+    let content = String::from_utf8(content)
+        .map_err( | error | io::Error::new(io::ErrorKind::InvalidData, error)) ?;
+    JsonValue::deserialize_json(&content)
+        .map(Some)
+        .map_err( | error | io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", error)))
+}
+
+/// Writes a single message to the given writer.
+pub fn write_message(writer: &mut impl Write, json: &JsonValue) -> io::Result<()> {
+    let content = json.serialize_json();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", content.len(), content) ?;
+    writer.flush()
+}
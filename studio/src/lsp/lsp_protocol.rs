@@ -0,0 +1,467 @@
+use {
+    crate::{
+        makepad_editor_core::{
+            delta::{Delta, Operation},
+            position::Position,
+            range::Range,
+            text::Text,
+        },
+        lsp::json::JsonValue,
+    },
+    std::path::{Path, PathBuf},
+};
+
+/// A message sent between a language server and its client, using JSON-RPC.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LspMessage {
+    Request {
+        id: JsonValue,
+        method: String,
+        params: JsonValue,
+    },
+    Response {
+        id: JsonValue,
+        result: Result<JsonValue, LspError>,
+    },
+    Notification {
+        method: String,
+        params: JsonValue,
+    },
+}
+
+impl LspMessage {
+    pub fn from_json(json: &JsonValue) -> Option<LspMessage> {
+        let id = json.get("id").cloned();
+        let method = json.get("method").and_then( | method | method.as_str());
+        let params = json.get("params").cloned().unwrap_or(JsonValue::Null);
+        Some(match (id, method) {
+            (Some(id), Some(method)) => LspMessage::Request {
+                id,
+                method: method.to_string(),
+                params,
+            },
+            (None, Some(method)) => LspMessage::Notification {
+                method: method.to_string(),
+                params,
+            },
+            (Some(id), None) => LspMessage::Response {
+                id,
+                result: match json.get("error") {
+                    Some(error) => Err(LspError::from_json(error) ?),
+                    None => Ok(json.get("result").cloned().unwrap_or(JsonValue::Null)),
+                },
+            },
+            (None, None) => return None,
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            LspMessage::Request {id, method, params} => JsonValue::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("method", method.as_str().into()),
+                ("params", params.clone()),
+            ]),
+            LspMessage::Response {id, result: Ok(result)} => JsonValue::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("result", result.clone()),
+            ]),
+            LspMessage::Response {id, result: Err(error)} => JsonValue::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("error", error.to_json()),
+            ]),
+            LspMessage::Notification {method, params} => JsonValue::object([
+                ("jsonrpc", "2.0".into()),
+                ("method", method.as_str().into()),
+                ("params", params.clone()),
+            ]),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspError {
+    pub code: f64,
+    pub message: String,
+}
+
+impl LspError {
+    pub fn from_json(json: &JsonValue) -> Option<LspError> {
+        Some(LspError {
+            code: json.get("code") ?.as_f64() ?,
+            message: json.get("message") ?.as_str() ?.to_string(),
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("code", JsonValue::Number(self.code)),
+            ("message", self.message.as_str().into()),
+        ])
+    }
+}
+
+/// A position in a text, as used by the language server protocol.
+///
+/// Unlike our own `Position`, which counts columns in chars, the `character` of an `LspPosition`
+/// counts UTF-16 code units, so we need the text to convert from one to the other.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LspPosition {
+    pub line: usize,
+    pub character: usize,
+}
+
+impl LspPosition {
+    pub fn from_position(text: &Text, position: Position) -> LspPosition {
+        let character = if position.line < text.line_count() {
+            text.line(position.line)
+                .chars()
+                .take(position.column)
+                .map( | ch | ch.len_utf16())
+                .sum()
+        } else {
+            position.column
+        };
+        LspPosition {
+            line: position.line,
+            character,
+        }
+    }
+
+    /// Converts this position to a `Position` in the given `text`. If this position is in the
+    /// middle of a char, or past the end of its line, the position is moved back to the nearest
+    /// char boundary.
+    pub fn to_position(self, text: &Text) -> Position {
+        let column = if self.line < text.line_count() {
+            let mut character = 0;
+            text.line(self.line)
+                .chars()
+                .take_while( | ch | {
+                    character += ch.len_utf16();
+                    character <= self.character
+                })
+                .count()
+        } else {
+            self.character
+        };
+        Position {
+            line: self.line,
+            column,
+        }
+    }
+
+    pub fn from_json(json: &JsonValue) -> Option<LspPosition> {
+        Some(LspPosition {
+            line: json.get("line") ?.as_u64() ? as usize,
+            character: json.get("character") ?.as_u64() ? as usize,
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("line", self.line.into()),
+            ("character", self.character.into()),
+        ])
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LspRange {
+    pub start: LspPosition,
+    pub end: LspPosition,
+}
+
+impl LspRange {
+    pub fn from_range(text: &Text, range: Range) -> LspRange {
+        LspRange {
+            start: LspPosition::from_position(text, range.start),
+            end: LspPosition::from_position(text, range.end),
+        }
+    }
+
+    pub fn to_range(self, text: &Text) -> Range {
+        Range {
+            start: self.start.to_position(text),
+            end: self.end.to_position(text),
+        }
+    }
+
+    pub fn from_json(json: &JsonValue) -> Option<LspRange> {
+        Some(LspRange {
+            start: LspPosition::from_json(json.get("start") ?) ?,
+            end: LspPosition::from_json(json.get("end") ?) ?,
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("start", self.start.to_json()),
+            ("end", self.end.to_json()),
+        ])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspLocation {
+    pub uri: String,
+    pub range: LspRange,
+}
+
+impl LspLocation {
+    /// Parses the result of a definition request. Servers can respond with a single location, a
+    /// list of locations, or a list of location links. We only care about the first one.
+    pub fn from_definition_result(json: &JsonValue) -> Option<LspLocation> {
+        let json = match json.as_array() {
+            Some(values) => values.first() ?,
+            None => json,
+        };
+        if let Some(uri) = json.get("targetUri") {
+            return Some(LspLocation {
+                uri: uri.as_str() ?.to_string(),
+                range: LspRange::from_json(
+                    json.get("targetSelectionRange").or(json.get("targetRange")) ?,
+                ) ?,
+            });
+        }
+        Some(LspLocation {
+            uri: json.get("uri") ?.as_str() ?.to_string(),
+            range: LspRange::from_json(json.get("range") ?) ?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LspDiagnosticSeverity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspDiagnostic {
+    pub range: LspRange,
+    pub severity: LspDiagnosticSeverity,
+    pub message: String,
+}
+
+impl LspDiagnostic {
+    pub fn from_json(json: &JsonValue) -> Option<LspDiagnostic> {
+        Some(LspDiagnostic {
+            range: LspRange::from_json(json.get("range") ?) ?,
+            severity: match json.get("severity").and_then( | severity | severity.as_u64()) {
+                Some(2) => LspDiagnosticSeverity::Warning,
+                Some(3) => LspDiagnosticSeverity::Information,
+                Some(4) => LspDiagnosticSeverity::Hint,
+                _ => LspDiagnosticSeverity::Error,
+            },
+            message: json.get("message") ?.as_str() ?.to_string(),
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("range", self.range.to_json()),
+            ("severity", match self.severity {
+                LspDiagnosticSeverity::Error => 1u64,
+                LspDiagnosticSeverity::Warning => 2,
+                LspDiagnosticSeverity::Information => 3,
+                LspDiagnosticSeverity::Hint => 4,
+            }.into()),
+            ("message", self.message.as_str().into()),
+        ])
+    }
+}
+
+/// The parameters of a `textDocument/publishDiagnostics` notification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspPublishDiagnostics {
+    pub uri: String,
+    pub diagnostics: Vec<LspDiagnostic>,
+}
+
+impl LspPublishDiagnostics {
+    pub fn from_json(json: &JsonValue) -> Option<LspPublishDiagnostics> {
+        Some(LspPublishDiagnostics {
+            uri: json.get("uri") ?.as_str() ?.to_string(),
+            diagnostics: json
+                .get("diagnostics") ?
+                .as_array() ?
+                .iter()
+                .filter_map(LspDiagnostic::from_json)
+                .collect(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspCompletionItem {
+    pub label: String,
+    pub detail: Option<String>,
+    /// The text to insert when this item is picked.
+    pub insert_text: String,
+}
+
+impl LspCompletionItem {
+    /// Parses the result of a completion request. Servers can respond with either a list of items
+    /// or a completion list object.
+    pub fn from_completion_result(json: &JsonValue) -> Vec<LspCompletionItem> {
+        let items = json
+            .as_array()
+            .or_else( || json.get("items").and_then( | items | items.as_array()))
+            .unwrap_or(&[]);
+        items.iter().filter_map(LspCompletionItem::from_json).collect()
+    }
+
+    pub fn from_json(json: &JsonValue) -> Option<LspCompletionItem> {
+        let label = json.get("label") ?.as_str() ?.to_string();
+        let insert_text = json
+            .get("textEdit")
+            .and_then( | text_edit | text_edit.get("newText"))
+            .or(json.get("insertText"))
+            .and_then( | text | text.as_str())
+            .unwrap_or(&label)
+            .to_string();
+        Some(LspCompletionItem {
+            detail: json.get("detail").and_then( | detail | detail.as_str()).map( | detail | detail.to_string()),
+            label,
+            insert_text,
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("label", self.label.as_str().into()),
+            ("detail", self.detail.clone().into()),
+            ("insertText", self.insert_text.as_str().into()),
+        ])
+    }
+}
+
+/// Returns the contents of the result of a hover request as plain text, or `None` if there is
+/// nothing to show.
+pub fn hover_contents(json: &JsonValue) -> Option<String> {
+    fn marked_string(json: &JsonValue) -> Option<&str> {
+        json.as_str().or_else( || json.get("value") ?.as_str())
+    }
+
+    let contents = json.get("contents") ?;
+    let text = match contents.as_array() {
+        Some(values) => values
+            .iter()
+            .filter_map(marked_string)
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        None => marked_string(contents) ?.to_string(),
+    };
+    if text.trim().is_empty() {
+        return None;
+    }
+    Some(text)
+}
+
+/// A change to a text, as sent in a `textDocument/didChange` notification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LspContentChange {
+    pub range: LspRange,
+    pub text: String,
+}
+
+impl LspContentChange {
+    /// Returns the list of changes that has the same effect as applying the given `delta` to the
+    /// given `text`.
+    ///
+    /// The language server applies the changes in a `didChange` notification one after the other,
+    /// so the range of each change has to be relative to the text after all previous changes have
+    /// been applied. To keep things simple, we emit the changes from the end of the text towards
+    /// the start, so that the range of each change can be computed relative to the original text.
+    pub fn from_delta(text: &Text, delta: &Delta) -> Vec<LspContentChange> {
+        let mut changes: Vec<LspContentChange> = Vec::new();
+        let mut position = Position::origin();
+        let mut last_delete_end = None;
+        for operation in delta {
+            match operation {
+                Operation::Retain(count) => {
+                    position += *count;
+                }
+                Operation::Insert(inserted_text) => {
+                    // Since deltas are normalized so that a delete is never preceded by an insert,
+                    // an insert that follows a delete at the same position replaces the deleted
+                    // text, so we merge them into a single change.
+                    let inserted_string = inserted_text.to_string();
+                    match changes.last_mut() {
+                        Some(change) if last_delete_end == Some(position) => {
+                            change.text = inserted_string;
+                        }
+                        _ => changes.push(LspContentChange {
+                            range: LspRange::from_range(text, Range {start: position, end: position}),
+                            text: inserted_string,
+                        }),
+                    }
+                }
+                Operation::Delete(count) => {
+                    let end = position + *count;
+                    changes.push(LspContentChange {
+                        range: LspRange::from_range(text, Range {start: position, end}),
+                        text: String::new(),
+                    });
+                    position = end;
+                    last_delete_end = Some(position);
+                    continue;
+                }
+            }
+            last_delete_end = None;
+        }
+        changes.reverse();
+        changes
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("range", self.range.to_json()),
+            ("text", self.text.as_str().into()),
+        ])
+    }
+
+    pub fn from_json(json: &JsonValue) -> Option<LspContentChange> {
+        Some(LspContentChange {
+            range: LspRange::from_json(json.get("range") ?) ?,
+            text: json.get("text") ?.as_str() ?.to_string(),
+        })
+    }
+}
+
+/// Returns the `file` uri for the given absolute `path`.
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Returns the path for the given `file` uri, or `None` if this is not a `file` uri.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://") ?.as_bytes();
+    let mut bytes = Vec::new();
+    let mut index = 0;
+    while index < encoded.len() {
+        if encoded[index] == b'%' && index + 2 < encoded.len() {
+            let hex = std::str::from_utf8(&encoded[index + 1..index + 3]).ok() ?;
+            bytes.push(u8::from_str_radix(hex, 16).ok() ?);
+            index += 3;
+        } else {
+            bytes.push(encoded[index]);
+            index += 1;
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok() ?))
+}
@@ -0,0 +1,541 @@
+use {
+    std::{
+        cmp::Ordering,
+    },
+    crate::{
+        makepad_platform::*,
+        makepad_collab_protocol::{
+            CollabRequest,
+            unix_path::{UnixPath, UnixPathBuf},
+        },
+        app_state::AppState,
+        editor_state::SessionId,
+        search_state::SearchOptions,
+    },
+};
+
+live_register!{
+    use makepad_component::theme::*;
+
+    QuickOpen: {{QuickOpen}} {
+        view: {
+            is_overlay: true
+        }
+
+        bg_quad: {
+            color: (COLOR_BG_HEADER)
+        }
+
+        input_quad: {
+            color: (COLOR_BG_EDITOR)
+        }
+
+        selected_quad: {
+            color: (COLOR_BG_SELECTED)
+        }
+
+        item_text: {
+            text_style: FONT_CODE {}
+            color: (COLOR_TEXT_DEFAULT)
+        }
+
+        meta_text: {
+            text_style: FONT_CODE {}
+            color: (COLOR_TEXT_META)
+        }
+
+        width: 600.0
+        top: 40.0
+        padding: 6.0
+        max_items: 12
+        preview_lines: 12
+    }
+}
+
+/// A palette for quickly opening files, jumping to a line, running a command or searching and
+/// replacing in all files, that is shown on top of the rest of the studio.
+///
+/// What the palette does depends on the query that is typed into it, see `Query`. While it is
+/// open, the file that is selected in it is previewed by opening a session for it.
+#[derive(Live, LiveHook)]
+pub struct QuickOpen {
+    view: View,
+    bg_quad: DrawColor,
+    input_quad: DrawColor,
+    selected_quad: DrawColor,
+    item_text: DrawText,
+    meta_text: DrawText,
+    width: f32,
+    top: f32,
+    padding: f32,
+    max_items: usize,
+    preview_lines: usize,
+
+    #[rust] is_open: bool,
+    #[rust] input: String,
+    #[rust] paths: Vec<UnixPathBuf>,
+    #[rust] commands: Vec<(String, Command)>,
+    #[rust] matches: Vec<usize>,
+    #[rust] selected: usize,
+    #[rust] preview: Option<(UnixPathBuf, SessionId)>,
+    #[rust] rect: Rect,
+}
+
+pub enum QuickOpenAction {
+    /// Open the file with the given path in a code editor tab, and move the cursor to the given
+    /// zero-based line, if any.
+    OpenFile {path: UnixPathBuf, line: Option<usize>},
+    /// Move the cursor in the selected code editor to the given zero-based line.
+    GoToLine(usize),
+    RunCommand(Command),
+    /// Search all files for the given input, see `SearchOptions::parse`.
+    Search(String),
+    /// Replace the results of the last search with the given text.
+    Replace(String),
+}
+
+/// A parsed query for the quick open palette.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Query<'a> {
+    /// Fuzzy match `pattern` against the paths of all files. A query of the form `pattern:line`
+    /// also moves the cursor to the given one-based line of the file that is opened.
+    Files {pattern: &'a str, line: Option<usize>},
+    /// A query of the form `:line` moves the cursor in the selected code editor to the given
+    /// one-based line. The line is `None` while it has not been typed yet.
+    GoToLine(Option<usize>),
+    /// A query of the form `>pattern` fuzzy matches `pattern` against the names of all commands.
+    Commands {pattern: &'a str},
+    /// A query of the form `?query` searches all files for `query`.
+    Search(&'a str),
+    /// A query of the form `=text` replaces the results of the last search with `text`. Unlike
+    /// the other queries, the text is not trimmed.
+    Replace(&'a str),
+}
+
+impl<'a> Query<'a> {
+    pub fn parse(input: &'a str) -> Query<'a> {
+        if let Some(text) = input.trim_start().strip_prefix('=') {
+            return Query::Replace(text);
+        }
+        let input = input.trim();
+        if let Some(query) = input.strip_prefix('?') {
+            return Query::Search(query.trim_start());
+        }
+        if let Some(pattern) = input.strip_prefix('>') {
+            return Query::Commands {pattern: pattern.trim()};
+        }
+        if let Some(index) = input.rfind(':') {
+            let (pattern, line) = (&input[..index], &input[index + 1..]);
+            if line.chars().all( | ch | ch.is_ascii_digit()) {
+                let line = line.parse().ok();
+                return if pattern.is_empty() {
+                    Query::GoToLine(line)
+                }
+                else {
+                    Query::Files {pattern, line}
+                };
+            }
+        }
+        Query::Files {pattern: input, line: None}
+    }
+}
+
+impl QuickOpen {
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Opens the palette with the given `input`, offering the files in the file tree of `state`
+    /// and the given `commands`.
+    pub fn open(&mut self, cx: &mut Cx, state: &AppState, commands: Vec<(String, Command)>, input: &str) {
+        self.is_open = true;
+        self.input = String::from(input);
+        self.paths = state.file_paths();
+        self.commands = commands;
+        self.update_matches(state);
+        // take key focus away from whatever had it, so we can give it back when we close
+        cx.set_key_focus(Area::Empty);
+        self.view.redraw(cx);
+    }
+
+    pub fn close(&mut self, cx: &mut Cx, state: &mut AppState, send_request: &mut dyn FnMut(CollabRequest)) {
+        if !self.is_open {
+            return;
+        }
+        self.is_open = false;
+        self.set_preview(state, None, send_request);
+        cx.revert_key_focus();
+        self.view.redraw(cx);
+    }
+
+    pub fn redraw(&mut self, cx: &mut Cx) {
+        self.view.redraw(cx);
+    }
+
+    /// Draws the palette, if it is open, centered horizontally in the given `rect`.
+    pub fn draw(&mut self, cx: &mut Cx2d, state: &AppState, rect: Rect) {
+        if self.view.begin(cx, Walk::default(), Layout::default()).is_err() {
+            return;
+        }
+        if !self.is_open {
+            self.view.end(cx);
+            return;
+        }
+        let glyph_size = self.item_text.text_style.font_size * self.item_text.get_monospace_base(cx);
+        let line_height = glyph_size.y;
+        let padding = self.padding;
+        let max_len = ((self.width - 2.0 * padding) / glyph_size.x).max(0.0) as usize;
+
+        let lines = self.lines(state);
+        let first = self.selected.saturating_sub(self.max_items.saturating_sub(1));
+        let visible_lines = &lines[first.min(lines.len())..(first + self.max_items).min(lines.len())];
+        let preview_lines = self.preview_text(state);
+
+        let mut height = 3.0 * padding + line_height * (1 + visible_lines.len()) as f32;
+        if !preview_lines.is_empty() {
+            height += padding + line_height * preview_lines.len() as f32;
+        }
+        let pos = vec2(rect.pos.x + ((rect.size.x - self.width) / 2.0).max(0.0), rect.pos.y + self.top);
+        self.rect = Rect {pos, size: vec2(self.width, height)};
+
+        self.bg_quad.draw_abs(cx, self.rect);
+        self.input_quad.draw_abs(cx, Rect {
+            pos: pos + vec2(padding, padding),
+            size: vec2(self.width - 2.0 * padding, line_height),
+        });
+        let mut y = pos.y + 2.0 * padding + line_height;
+        if let Some(selected) = self.selected.checked_sub(first) {
+            if !self.matches.is_empty() && selected < visible_lines.len() {
+                self.selected_quad.draw_abs(cx, Rect {
+                    pos: vec2(pos.x, y + padding + selected as f32 * line_height),
+                    size: vec2(self.width, line_height),
+                });
+            }
+        }
+
+        let input = format!("{}|", self.input);
+        self.item_text.draw_abs(cx, pos + vec2(padding, padding), truncate(&input, max_len));
+        y += padding;
+        for (text, is_meta) in visible_lines {
+            let draw_text = if *is_meta {&mut self.meta_text} else {&mut self.item_text};
+            draw_text.draw_abs(cx, vec2(pos.x + padding, y), truncate(text, max_len));
+            y += line_height;
+        }
+        y += padding;
+        for line in &preview_lines {
+            self.meta_text.draw_abs(cx, vec2(pos.x + padding, y), truncate(line, max_len));
+            y += line_height;
+        }
+        self.view.end(cx);
+    }
+
+    // Returns the lines to show below the input, and whether each line is a hint rather than an
+    // item that can be selected.
+    fn lines(&self, state: &AppState) -> Vec<(String, bool)> {
+        match Query::parse(&self.input) {
+            Query::Files {..} => {
+                if self.matches.is_empty() {
+                    return vec![(String::from("No matching files"), true)];
+                }
+                self.matches.iter().map( | index | (path_to_string(&self.paths[*index]), false)).collect()
+            }
+            Query::GoToLine(line) => {
+                let line_count = state.selected_session_id().and_then( | session_id | {
+                    let session = &state.editor_state.sessions[session_id];
+                    let document = &state.editor_state.documents[session.document_id];
+                    document.inner.as_ref().map( | inner | inner.text.line_count())
+                });
+                let hint = match (line, line_count) {
+                    (_, None) => String::from("Open a file to go to a line"),
+                    (None, Some(line_count)) => format!("Type a line number between 1 and {}", line_count),
+                    (Some(line), Some(_)) => format!("Go to line {}", line),
+                };
+                vec![(hint, true)]
+            }
+            Query::Commands {..} => {
+                if self.matches.is_empty() {
+                    return vec![(String::from("No matching commands"), true)];
+                }
+                self.matches.iter().map( | index | (self.commands[*index].0.clone(), false)).collect()
+            }
+            Query::Search(query) => {
+                let hint = if query.is_empty() {
+                    String::from("Type text to search for, optionally followed by in:glob, not:glob, +regex or +case")
+                }
+                else {
+                    format!("Search all files for \"{}\"", SearchOptions::parse(query).query)
+                };
+                vec![(hint, true)]
+            }
+            Query::Replace(text) => {
+                let search_state = &state.search_state;
+                let hint = if search_state.results.is_empty() {
+                    String::from("Search first, then replace its results")
+                }
+                else if search_state.is_running {
+                    String::from("Wait for the search to finish")
+                }
+                else {
+                    format!(
+                        "Replace {} results of \"{}\" with \"{}\"",
+                        search_state.results.len(),
+                        search_state.options.query,
+                        text
+                    )
+                };
+                vec![(hint, true)]
+            }
+        }
+    }
+
+    // Returns the first lines of the file that is being previewed, if any.
+    fn preview_text(&self, state: &AppState) -> Vec<String> {
+        let (_, session_id) = match &self.preview {
+            Some(preview) => preview,
+            None => return Vec::new(),
+        };
+        let session = &state.editor_state.sessions[*session_id];
+        let document = &state.editor_state.documents[session.document_id];
+        match &document.inner {
+            Some(inner) => (0..inner.text.line_count().min(self.preview_lines))
+                .map( | line | inner.text.line(line).chars().map( | ch | if ch == '\t' {' '} else {ch}).collect())
+                .collect(),
+            None => vec![String::from("Loading...")],
+        }
+    }
+
+    fn update_matches(&mut self, state: &AppState) {
+        self.matches = match Query::parse(&self.input) {
+            Query::Files {pattern, ..} => rank_paths(pattern, &self.paths, &state.recent_paths),
+            Query::GoToLine(_) | Query::Search(_) | Query::Replace(_) => Vec::new(),
+            Query::Commands {pattern} => rank_commands(
+                pattern,
+                &self.commands.iter().map( | (name, _) | name.as_str()).collect::<Vec<_ >>()
+            ),
+        };
+        self.selected = 0;
+    }
+
+    // Opens a session for the file that is selected, if the query is for files, and closes the
+    // session for the file that was previewed before.
+    fn update_preview(&mut self, state: &mut AppState, send_request: &mut dyn FnMut(CollabRequest)) {
+        let path = match Query::parse(&self.input) {
+            Query::Files {..} => self.matches.get(self.selected).map( | index | self.paths[*index].clone()),
+            _ => None,
+        };
+        self.set_preview(state, path, send_request);
+    }
+
+    fn set_preview(&mut self, state: &mut AppState, path: Option<UnixPathBuf>, send_request: &mut dyn FnMut(CollabRequest)) {
+        if self.preview.as_ref().map( | (preview_path, _) | preview_path) == path.as_ref() {
+            return;
+        }
+        if let Some((_, session_id)) = self.preview.take() {
+            state.editor_state.destroy_session(session_id, send_request);
+        }
+        self.preview = path.map( | path | {
+            let session_id = state.editor_state.create_session(path.clone(), send_request);
+            (path, session_id)
+        });
+    }
+
+    // Returns the action for the item that is selected, if any.
+    fn selected_action(&self, state: &AppState) -> Option<QuickOpenAction> {
+        match Query::parse(&self.input) {
+            Query::Files {line, ..} => {
+                let index = self.matches.get(self.selected) ?;
+                Some(QuickOpenAction::OpenFile {
+                    path: self.paths[*index].clone(),
+                    line: line.map( | line | line.saturating_sub(1)),
+                })
+            }
+            Query::GoToLine(line) => Some(QuickOpenAction::GoToLine(line ?.saturating_sub(1))),
+            Query::Commands {..} => {
+                let index = self.matches.get(self.selected) ?;
+                Some(QuickOpenAction::RunCommand(self.commands[*index].1))
+            }
+            Query::Search(query) if !query.is_empty() => Some(QuickOpenAction::Search(String::from(query))),
+            Query::Search(_) => None,
+            Query::Replace(text) => {
+                let search_state = &state.search_state;
+                if search_state.results.is_empty() || search_state.is_running {
+                    return None;
+                }
+                Some(QuickOpenAction::Replace(String::from(text)))
+            }
+        }
+    }
+
+    /// Handles an event. Pressing Ctrl/Cmd+P opens the palette, and Ctrl/Cmd+Shift+P opens it for
+    /// commands. While the palette is open, it handles all key and text input.
+    pub fn handle_event(
+        &mut self,
+        cx: &mut Cx,
+        event: &mut Event,
+        state: &mut AppState,
+        commands: &Menu,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) -> Vec<QuickOpenAction> {
+        let mut actions = Vec::new();
+        match event {
+            Event::KeyDown(KeyEvent {
+                key_code: KeyCode::KeyP,
+                modifiers,
+                ..
+            }) if modifiers.control || modifiers.logo => {
+                let commands = commands.commands().into_iter().map( | (name, command) | (String::from(name), command)).collect();
+                self.open(cx, state, commands, if modifiers.shift {">"} else {""});
+                self.update_preview(state, send_request);
+            }
+            _ if !self.is_open => {}
+            Event::KeyDown(KeyEvent {key_code: KeyCode::Escape, ..}) => {
+                self.close(cx, state, send_request);
+            }
+            Event::KeyDown(KeyEvent {key_code: KeyCode::Return, ..}) => {
+                let action = self.selected_action(state);
+                self.close(cx, state, send_request);
+                actions.extend(action);
+            }
+            Event::KeyDown(KeyEvent {key_code: KeyCode::ArrowUp, ..}) => {
+                if !self.matches.is_empty() {
+                    self.selected = self.selected.checked_sub(1).unwrap_or(self.matches.len() - 1);
+                    self.update_preview(state, send_request);
+                    self.view.redraw(cx);
+                }
+            }
+            Event::KeyDown(KeyEvent {key_code: KeyCode::ArrowDown, ..}) => {
+                if !self.matches.is_empty() {
+                    self.selected = (self.selected + 1) % self.matches.len();
+                    self.update_preview(state, send_request);
+                    self.view.redraw(cx);
+                }
+            }
+            Event::KeyDown(KeyEvent {key_code: KeyCode::Backspace, ..}) => {
+                self.input.pop();
+                self.update_matches(state);
+                self.update_preview(state, send_request);
+                self.view.redraw(cx);
+            }
+            Event::TextInput(TextInputEvent {input, replace_last, ..}) => {
+                if *replace_last {
+                    self.input.pop();
+                }
+                self.input.extend(input.chars().filter( | ch | !ch.is_control()));
+                self.update_matches(state);
+                self.update_preview(state, send_request);
+                self.view.redraw(cx);
+            }
+            Event::FingerDown(event) if !self.rect.contains(event.abs) => {
+                self.close(cx, state, send_request);
+            }
+            _ => ()
+        }
+        actions
+    }
+}
+
+/// Returns a score for how well `pattern` fuzzy matches `candidate`, or `None` if it does not match
+/// at all. Higher scores are better.
+///
+/// The characters of `pattern` must occur in `candidate` in the same order, ignoring case and
+/// whitespace in the pattern. Matches at the start of a word, consecutive matches and matches
+/// with the same case score higher, and gaps between matches score lower.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i32> {
+    const MATCH_SCORE: i32 = 16;
+    const WORD_START_BONUS: i32 = 24;
+    const CONSECUTIVE_BONUS: i32 = 16;
+    const SAME_CASE_BONUS: i32 = 1;
+    const MAX_GAP_PENALTY: i32 = 8;
+
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut start = 0;
+    let mut last_index: Option<usize> = None;
+    for pattern_char in pattern.chars().filter( | ch | !ch.is_whitespace()) {
+        let offset = candidate[start..]
+            .iter()
+            .position( | ch | ch.to_lowercase().eq(pattern_char.to_lowercase())) ?;
+        let index = start + offset;
+        score += MATCH_SCORE;
+        if is_word_start(&candidate, index) {
+            score += WORD_START_BONUS;
+        }
+        if candidate[index] == pattern_char {
+            score += SAME_CASE_BONUS;
+        }
+        match last_index {
+            Some(last_index) if last_index + 1 == index => score += CONSECUTIVE_BONUS,
+            Some(_) => score -= (offset as i32).min(MAX_GAP_PENALTY),
+            None => score -= (offset as i32).min(MAX_GAP_PENALTY) / 2,
+        }
+        last_index = Some(index);
+        start = index + 1;
+    }
+    Some(score)
+}
+
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    if index == 0 {
+        return true;
+    }
+    let (prev, ch) = (chars[index - 1], chars[index]);
+    matches!(prev, '/' | '\\' | '_' | '-' | '.' | ' ') || prev.is_lowercase() && ch.is_uppercase()
+}
+
+/// Returns a score for how well `pattern` fuzzy matches the file with the given `path`, or `None`
+/// if it does not match at all. Matches in the file name score higher than matches elsewhere in
+/// the path.
+pub fn path_score(pattern: &str, path: &str) -> Option<i32> {
+    const FILE_NAME_BONUS: i32 = 32;
+
+    let file_name = &path[path.rfind('/').map_or(0, | index | index + 1)..];
+    let file_name_score = fuzzy_score(pattern, file_name).map( | score | score + FILE_NAME_BONUS);
+    let path_score = fuzzy_score(pattern, path);
+    file_name_score.max(path_score)
+}
+
+/// Returns the indices of the `paths` that match `pattern`, with the best match first.
+///
+/// Files in `recent_paths`, which has the most recently used file first, get a bonus that is
+/// larger the more recently they were used. Ties are broken by preferring shorter paths, and then
+/// by the order of the paths.
+pub fn rank_paths(pattern: &str, paths: &[UnixPathBuf], recent_paths: &[UnixPathBuf]) -> Vec<usize> {
+    const RECENCY_BONUS: i32 = 4;
+
+    let mut matches: Vec<(usize, i32, usize)> = paths.iter().enumerate().filter_map( | (index, path) | {
+        let string = path_to_string(path);
+        let mut score = path_score(pattern, &string) ?;
+        if let Some(recency) = recent_paths.iter().position( | recent_path | recent_path == path) {
+            score += RECENCY_BONUS * (recent_paths.len() - recency) as i32;
+        }
+        Some((index, score, string.chars().count()))
+    }).collect();
+    matches.sort_by( | a, b | compare_matches((a.1, a.2, a.0), (b.1, b.2, b.0)));
+    matches.into_iter().map( | (index, _, _) | index).collect()
+}
+
+/// Returns the indices of the command `names` that match `pattern`, with the best match first.
+pub fn rank_commands(pattern: &str, names: &[&str]) -> Vec<usize> {
+    let mut matches: Vec<(usize, i32)> = names.iter().enumerate().filter_map( | (index, name) | {
+        Some((index, fuzzy_score(pattern, name) ?))
+    }).collect();
+    matches.sort_by( | a, b | compare_matches((a.1, 0, a.0), (b.1, 0, b.0)));
+    matches.into_iter().map( | (index, _) | index).collect()
+}
+
+// Orders matches, given as score, length and index, from best to worst.
+fn compare_matches(a: (i32, usize, usize), b: (i32, usize, usize)) -> Ordering {
+    b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2))
+}
+
+fn path_to_string(path: &UnixPath) -> String {
+    path.as_unix_str().to_string_lossy().into_owned()
+}
+
+fn truncate(text: &str, max_len: usize) -> &str {
+    match text.char_indices().nth(max_len) {
+        Some((index, _)) => &text[..index],
+        None => text,
+    }
+}
+
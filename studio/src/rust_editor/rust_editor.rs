@@ -1,7 +1,12 @@
 use {
     crate::{
         makepad_platform::*,
+        makepad_component::{
+            ComponentMap,
+            fold_button::{FoldButton, FoldButtonAction},
+        },
         makepad_editor_core::{
+            position::Position,
             text::{Text},
         },
         rust_editor::rust_tokenizer::{
@@ -13,7 +18,8 @@ use {
             DocumentInner
         },
         code_editor::{
-            code_editor_impl::{CodeEditorImpl, CodeEditorAction, LinesLayout, LineLayoutOutput}
+            code_editor_impl::{CodeEditorImpl, CodeEditorAction, LinesLayout, LineLayoutOutput, WrapMode},
+            fold_set::{FoldRange, FoldSet},
         },
         rust_editor::rust_tokenizer::token_cache::TokenCache,
         makepad_collab_protocol::{
@@ -23,8 +29,10 @@ use {
         editor_state::{
             SessionId
         },
+        lsp::lsp_protocol::LspCompletionItem,
+        git_state::blame_annotation,
     },
-    
+    std::ops::Range,
 };
 
 live_register!{
@@ -36,6 +44,8 @@ live_register!{
         
         fold_button: FoldButton {
             bg_quad: {no_h_scroll: true}
+            abs_size: vec2(12, 12)
+            abs_offset: vec2(0., 0.)
         }
         
         widget_layout: {
@@ -61,6 +71,9 @@ live_register!{
         text_color_whitespace: #6e6e6e
         text_color_unknown: #808080
         text_color_color: #cc917b
+        text_color_heading: #5b9bd3
+        text_color_emphasis: #c485be
+        text_color_fold_placeholder: #808080
         
         editor_impl: {}
     }
@@ -92,8 +105,15 @@ pub struct RustEditor {
     text_color_string: Vec4,
     text_color_whitespace: Vec4,
     text_color_unknown: Vec4,
+    text_color_heading: Vec4,
+    text_color_emphasis: Vec4,
+    text_color_fold_placeholder: Vec4,
     
     #[rust] lines_layout: LinesLayout,
+    // Whether the commit that last changed the line of the caret is shown after it.
+    #[rust] show_blame: bool,
+    // The fold buttons in the gutter, by the line of the fold range they belong to.
+    #[rust] fold_buttons: ComponentMap<usize, FoldButton>,
 }
 
 impl LiveHook for RustEditor {
@@ -116,8 +136,43 @@ impl RustEditor {
         self.editor_impl.redraw(cx);
     }
     
+    pub fn scroll_pos(&self, cx: &Cx) -> Vec2 {
+        self.editor_impl.scroll_pos(cx)
+    }
+    
+    pub fn set_scroll_pos(&mut self, cx: &mut Cx, pos: Vec2) {
+        self.editor_impl.set_scroll_pos(cx, pos)
+    }
+    
+    pub fn set_wrap_mode(&mut self, cx: &mut Cx, wrap_mode: WrapMode) {
+        self.editor_impl.set_wrap_mode(cx, wrap_mode)
+    }
+    
+    pub fn set_show_minimap(&mut self, cx: &mut Cx, show_minimap: bool) {
+        self.editor_impl.set_show_minimap(cx, show_minimap)
+    }
+    
+    pub fn set_show_blame(&mut self, cx: &mut Cx, show_blame: bool) {
+        self.show_blame = show_blame;
+        self.editor_impl.redraw(cx);
+    }
+    
+    pub fn show_hover(&mut self, cx: &mut Cx, state: &EditorState, text: &str) {
+        self.editor_impl.show_hover(cx, state, text);
+    }
+    
+    pub fn show_completion(&mut self, cx: &mut Cx, state: &EditorState, items: Vec<LspCompletionItem>) {
+        self.editor_impl.show_completion(cx, state, items);
+    }
+    
     
-    pub fn calc_layout_with_widgets(&mut self, cx: &mut Cx2d, _path: &UnixPath, document_inner: &DocumentInner) {
+    pub fn calc_layout_with_widgets(
+        &mut self,
+        cx: &mut Cx2d,
+        _path: &UnixPath,
+        document_inner: &DocumentInner,
+        hidden_lines: &[bool],
+    ) {
         
         let token_cache = &document_inner.token_cache;
         
@@ -128,6 +183,15 @@ impl RustEditor {
         let zoom_indent_depth = self.zoom_indent_depth;
         
         self.editor_impl.calc_lines_layout(cx, document_inner, &mut self.lines_layout, | _cx, input | {
+            
+            if hidden_lines[input.line] {
+                return LineLayoutOutput {
+                    zoom_out: 0.0,
+                    zoom_column: 0,
+                    widget_height: 0.0,
+                    is_hidden: true,
+                }
+            }
 
             let max_height = 0.0f32;
             
@@ -158,7 +222,8 @@ impl RustEditor {
             return LineLayoutOutput {
                 zoom_out,
                 zoom_column,
-                widget_height: max_height
+                widget_height: max_height,
+                is_hidden: false,
             }
         });
     }
@@ -167,12 +232,18 @@ impl RustEditor {
         if let Ok((document, document_inner, session)) = self.editor_impl.begin(cx, state) {
             let path = document.path.clone();
             
+            let hidden_lines = session.folds.hidden_lines(
+                &document_inner.fold_ranges,
+                document_inner.text.line_count(),
+            );
+            
             // if we are folding we need to store the last lead cursor y pos
             // then we calc layout and get a new one, then we scroll, and calc again
             self.calc_layout_with_widgets(
                 cx,
                 &path,
                 document_inner,
+                &hidden_lines,
             );
             
             self.editor_impl.draw_selections(
@@ -188,6 +259,14 @@ impl RustEditor {
                 &self.lines_layout,
             );
             
+            let delims = session
+                .carets
+                .iter()
+                .filter_map( | &caret | document_inner.token_cache.find_matching_delims(caret))
+                .flat_map( | (delim, other) | [delim, other])
+                .collect::<Vec<_>>();
+            self.editor_impl.draw_matching_delims(cx, &delims, &self.lines_layout);
+            
             self.editor_impl.draw_carets(
                 cx,
                 &session.selections,
@@ -199,6 +278,7 @@ impl RustEditor {
                 cx,
                 &document_inner.text,
                 &document_inner.token_cache,
+                &session.folds,
             );
             
             self.editor_impl.draw_current_line(
@@ -210,16 +290,74 @@ impl RustEditor {
             self.editor_impl.draw_message_lines(
                 cx,
                 &document_inner.msg_cache,
-                state,
+                &state.messages,
+                &self.lines_layout,
+            );
+            
+            self.editor_impl.draw_message_lines(
+                cx,
+                &document_inner.lsp_msg_cache,
+                &document_inner.lsp_messages,
+                &self.lines_layout,
+            );
+            
+            self.editor_impl.draw_message_lines(
+                cx,
+                &document_inner.live_msg_cache,
+                &state.live_messages,
                 &self.lines_layout,
             );
             
+            // live errors change as you type, so their text is shown right where they are
+            self.editor_impl.draw_message_annotations(
+                cx,
+                &document_inner.live_msg_cache,
+                &state.live_messages,
+                &self.lines_layout,
+                &document_inner.text,
+            );
+            
             self.editor_impl.draw_linenums(
                 cx,
                 &self.lines_layout,
                 *session.cursors.last_inserted()
             );
             
+            self.editor_impl.draw_git_markers(cx, &self.lines_layout, &document_inner.git_hunks);
+            
+            if self.show_blame {
+                let line_index = session.cursors.last_inserted().head.line;
+                // the blame is for the file on disk, so it is only shown if the line was not edited
+                let is_edited = document_inner.git_hunks.iter().any( | hunk | {
+                    (hunk.start_line..hunk.end_line).contains(&line_index)
+                });
+                let has_live_message = !document_inner.live_msg_cache[line_index].spans().is_empty();
+                if let Some(line) = document_inner.git_blame.as_ref().and_then( | blame | blame.get(line_index)) {
+                    if !is_edited && !has_live_message {
+                        self.editor_impl.draw_line_annotation(
+                            cx,
+                            &self.lines_layout,
+                            &document_inner.text,
+                            line_index,
+                            &blame_annotation(line),
+                        );
+                    }
+                }
+            }
+            
+            self.draw_fold_buttons(cx, &document_inner.fold_ranges, &session.folds);
+            
+            if let Some(lines) = self.editor_impl.begin_minimap(cx, document_inner.text.line_count()) {
+                self.draw_minimap_tokens(cx, &document_inner.text, &document_inner.token_cache, lines);
+                self.editor_impl.end_minimap(cx, &self.lines_layout, &[
+                    (&document_inner.msg_cache, &state.messages),
+                    (&document_inner.lsp_msg_cache, &document_inner.lsp_messages),
+                    (&document_inner.live_msg_cache, &state.live_messages),
+                ]);
+            }
+            
+            self.editor_impl.draw_popup(cx, &self.lines_layout);
+            
             self.editor_impl.end(cx, &self.lines_layout);
         }
     }
@@ -229,47 +367,125 @@ impl RustEditor {
         cx: &mut Cx2d,
         text: &Text,
         token_cache: &TokenCache,
+        folds: &FoldSet,
     ) {
         let lines_layout = &self.lines_layout;
         let origin = cx.turtle().pos();
         //let mut start_y = visible_lines.start_y;
-        for (line_index, (chars, token_info)) in text
-            .as_lines()
-            .iter()
-            .zip(token_cache.iter())
-            .skip(lines_layout.view_start)
-            .take(lines_layout.view_end - lines_layout.view_start)
-            .enumerate()
-        {
-            let line_index = line_index + lines_layout.view_start;
+        let mut line = String::new();
+        for line_index in lines_layout.view_start..lines_layout.view_end {
             let layout = &lines_layout.lines[line_index];
+            if layout.is_hidden {
+                continue;
+            }
+            line.clear();
+            line.extend(text.line(line_index).chars());
             
-            let mut start_x = origin.x + self.editor_impl.line_num_width + layout.zoom_displace;
             let mut start = 0;
+            let mut column = 0;
             
-            let mut token_iter = token_info.tokens().iter().peekable();
+            let mut token_iter = token_cache[line_index].tokens().iter().peekable();
             while let Some(token) = token_iter.next() {
                 
                 let next_token = token_iter.peek();
-                let end_x = start_x + token.len as f32 * self.editor_impl.text_glyph_size.x * layout.font_scale;
-                let end = start + token.len;
+                // Token lengths are in chars, so convert them to a byte offset into the line.
+                let end = start + line[start..].chars().take(token.len).map( | ch | ch.len_utf8()).sum::<usize>();
+                let end_column = column + token.len;
                 
                 // check if we are whitespace. ifso, just skip rendering
                 if !token.token.is_whitespace() {
-                    self.editor_impl.draw_code_chunk(
-                        cx,
-                        layout.font_scale,
-                        self.text_color(&chars[start..end], token.token, next_token.map( | next_token | next_token.token)),
-                        Vec2 {x: start_x, y: layout.start_y + origin.y},
-                        &chars[start..end]
-                    );
+                    let color = self.text_color(&line[start..end], token.token, next_token.map( | next_token | next_token.token));
+                    // if the line is soft wrapped, the token might be split over several rows
+                    let mut chunk_start = start;
+                    let mut chunk_column = column;
+                    while chunk_column < end_column {
+                        let row = layout.row(chunk_column);
+                        let chunk_end_column = layout.row_end(row).map_or(end_column, | row_end | row_end.min(end_column));
+                        let chunk_end = chunk_start + line[chunk_start..]
+                            .chars()
+                            .take(chunk_end_column - chunk_column)
+                            .map( | ch | ch.len_utf8())
+                            .sum::<usize>();
+                        let pos = self.editor_impl.position_to_vec2(Position {line: line_index, column: chunk_column}, lines_layout);
+                        self.editor_impl.draw_code_chunk(
+                            cx,
+                            layout.font_scale,
+                            color,
+                            origin + pos,
+                            &line[chunk_start..chunk_end]
+                        );
+                        chunk_start = chunk_end;
+                        chunk_column = chunk_end_column;
+                    }
                 }
                 start = end;
-                start_x = end_x;
+                column = end_column;
+            }
+            
+            // show that the lines after a folded line are hidden
+            if folds.is_folded(line_index) {
+                let pos = self.editor_impl.position_to_vec2(Position {line: line_index, column}, lines_layout);
+                self.editor_impl.draw_code_chunk(
+                    cx,
+                    layout.font_scale,
+                    self.text_color_fold_placeholder,
+                    origin + pos,
+                    " ..."
+                );
             }
         }
     }
     
+    // Draws the tokens on the given `lines` on the minimap, in the same colors as in the editor.
+    fn draw_minimap_tokens(&mut self, cx: &mut Cx2d, text: &Text, token_cache: &TokenCache, lines: Range<usize>) {
+        let mut line = String::new();
+        for line_index in lines {
+            line.clear();
+            line.extend(text.line(line_index).chars());
+            
+            let mut start = 0;
+            let mut column = 0;
+            let mut token_iter = token_cache[line_index].tokens().iter().peekable();
+            while let Some(token) = token_iter.next() {
+                let next_token = token_iter.peek();
+                let end = start + line[start..].chars().take(token.len).map( | ch | ch.len_utf8()).sum::<usize>();
+                if !token.token.is_whitespace() {
+                    let color = self.text_color(&line[start..end], token.token, next_token.map( | next_token | next_token.token));
+                    self.editor_impl.minimap.draw_token(cx, line_index, column, token.len, color);
+                }
+                start = end;
+                column += token.len;
+            }
+        }
+    }
+    
+    // Draws a fold button in the gutter for each visible line that starts a fold range. The button
+    // is closed if the range is folded.
+    fn draw_fold_buttons(&mut self, cx: &mut Cx2d, fold_ranges: &[FoldRange], folds: &FoldSet) {
+        let origin = cx.turtle().pos();
+        let fold_button = self.fold_button;
+        for range in fold_ranges {
+            if range.start_line < self.lines_layout.view_start || range.start_line >= self.lines_layout.view_end {
+                continue;
+            }
+            let layout = &self.lines_layout.lines[range.start_line];
+            if layout.is_hidden {
+                continue;
+            }
+            let is_folded = folds.is_folded(range.start_line);
+            let button = self.fold_buttons.get_or_insert(cx, range.start_line, | cx | {
+                FoldButton::new_from_ptr(cx, fold_button)
+            });
+            // buttons are kept by line, so make sure they match the folded state of the range
+            // that starts on their line now
+            if button.is_open(cx) == is_folded {
+                button.set_is_open(cx, !is_folded, Animate::No);
+            }
+            button.draw_abs(cx, Vec2 {x: origin.x, y: layout.start_y + origin.y}, 1.0);
+        }
+        self.fold_buttons.retain_visible();
+    }
+    
     
     pub fn handle_event(
         &mut self,
@@ -286,7 +502,27 @@ impl RustEditor {
         if self.editor_impl.session_id.is_none() {
             return
         }
-        //let session_id = self.editor_impl.session_id.unwrap();
+        let session_id = self.editor_impl.session_id.unwrap();
+        
+        // the fold buttons go first, so that clicking them doesn't move the cursor
+        let mut fold_actions = Vec::new();
+        for (line, button) in self.fold_buttons.iter_mut() {
+            let line = *line;
+            button.handle_event_with_fn(cx, event, &mut | _, action | fold_actions.push((line, action)));
+        }
+        for (line, action) in fold_actions {
+            match action {
+                FoldButtonAction::Closing => {
+                    state.fold_line(session_id, line);
+                    self.editor_impl.redraw(cx);
+                }
+                FoldButtonAction::Opening => {
+                    state.unfold_line(session_id, line);
+                    self.editor_impl.redraw(cx);
+                }
+                _ => ()
+            }
+        }
         
         // what if the code editor changes something?
         self.editor_impl.handle_event_with_fn(
@@ -302,6 +538,7 @@ impl RustEditor {
                     }
                     CodeEditorAction::CursorBlink => {
                     }
+                    _ => ()
                 }
                 dispatch_action(cx, action);
             }
@@ -309,11 +546,11 @@ impl RustEditor {
         
     }
     
-    fn text_color(&self, text: &[char], token: FullToken, next_token: Option<FullToken>) -> Vec4 {
+    fn text_color(&self, text: &str, token: FullToken, next_token: Option<FullToken>) -> Vec4 {
         match (token, next_token) {
             (FullToken::Comment, _) => self.text_color_comment,
             (FullToken::Ident(id), _) if id.is_capitalised() => {
-                if text.len() > 1 && text[1].is_uppercase() {
+                if text.chars().nth(1).map_or(false, | ch | ch.is_uppercase()) {
                     self.text_color_string
                 }
                 else {
@@ -418,6 +655,9 @@ impl RustEditor {
             (FullToken::Whitespace, _) => self.text_color_whitespace,
             (FullToken::Color(_), _) => self.text_color_color,
             (FullToken::Unknown, _) => self.text_color_unknown,
+            (FullToken::Text, _) => self.text_color_identifier,
+            (FullToken::Heading, _) => self.text_color_heading,
+            (FullToken::Emphasis, _) => self.text_color_emphasis,
             (FullToken::Open(_), _) |
             (FullToken::Close(_), _) => self.text_color_punctuator,
         }
@@ -40,6 +40,11 @@ pub enum FullToken {
     Comment,
     Whitespace,
     Unknown,
+    
+    // Tokens of languages other than Rust.
+    Text,
+    Heading,
+    Emphasis,
 }
 
 impl FullToken {
@@ -1,28 +1,42 @@
 use {
-    crate::makepad_editor_core::{
-        delta::{Delta, OperationRange},
-        text::Text,
-    },
-    crate::rust_editor::rust_tokenizer::{
-        full_token::TokenWithLen,
-        tokenizer::{Cursor, State},
+    crate::{
+        makepad_editor_core::{
+            delta::{Delta, OperationRange},
+            position::Position,
+            text::Text,
+        },
+        makepad_live_id::*,
+        code_editor::fold_set::FoldRange,
+        languages::{LanguageKind, LanguageState},
     },
+    crate::rust_editor::rust_tokenizer::full_token::{Delim, FullToken, TokenWithLen},
     std::{iter, ops::{Deref, Index}, slice::Iter},
 };
 
 #[derive(Clone, Debug,PartialEq)]
 pub struct TokenCache {
+    language: LanguageKind,
     lines: Vec<Line>,
 }
 
 impl TokenCache {
     pub fn new(text: &Text) -> TokenCache {
+        TokenCache::with_language(LanguageKind::Rust, text)
+    }
+    
+    /// Creates a token cache for a `text` in the given `language`.
+    pub fn with_language(language: LanguageKind, text: &Text) -> TokenCache {
         let mut cache = TokenCache {
-            lines: (0..text.as_lines().len()).map(|_| Line::default()).collect::<Vec<_>>(),
+            language,
+            lines: (0..text.line_count()).map(|_| Line::default()).collect::<Vec<_>>(),
         };
         cache.refresh(text);
         cache
     }
+    
+    pub fn language(&self) -> LanguageKind {
+        self.language
+    }
 
     pub fn invalidate(&mut self, delta: &Delta) {
         for operation_range in delta.operation_ranges() {
@@ -43,8 +57,9 @@ impl TokenCache {
     }
 
     pub fn refresh(&mut self, text: &Text) {
-        let mut state = State::default();
+        let mut state = LanguageState::new(self.language);
         let mut scratch = String::new();
+        let mut chars = Vec::new();
         for (index, line) in self.lines.iter_mut().enumerate() {
             match line.token_info {
                 Some(TokenInfo {
@@ -57,15 +72,9 @@ impl TokenCache {
                 _ => {
                     let start_state = state;
                     let mut tokens = Vec::new();
-                    let mut cursor = Cursor::new(&text.as_lines()[index], &mut scratch);
-                    loop {
-                        let (next_state, token) = state.next(&mut cursor);
-                        state = next_state;
-                        match token {
-                            Some(token) => tokens.push(token),
-                            None => break,
-                        }
-                    }
+                    chars.clear();
+                    chars.extend(text.line(index).chars());
+                    state = state.tokenize_line(&chars, &mut scratch, &mut tokens);
                     line.token_info = Some(TokenInfo {
                         start_state,
                         tokens,
@@ -77,6 +86,221 @@ impl TokenCache {
     }
 }
 
+impl TokenCache {
+    /// Returns the start position and the token that contains the given `position`, if any.
+    pub fn token_at(&self, position: Position) -> Option<(Position, TokenWithLen)> {
+        let mut column = 0;
+        for token in self.lines[position.line].tokens() {
+            if position.column < column + token.len {
+                return Some((Position {line: position.line, column}, *token));
+            }
+            column += token.len;
+        }
+        None
+    }
+
+    /// Returns the positions of a pair of matching delimiters at the given caret `position`, if
+    /// any. The first position in the pair is that of the delimiter at the caret, and the second
+    /// position that of the delimiter it matches.
+    ///
+    /// The delimiter right after the caret takes precedence over the one right before it.
+    pub fn find_matching_delims(&self, position: Position) -> Option<(Position, Position)> {
+        let mut candidates = vec![position];
+        if position.column > 0 {
+            candidates.push(Position {line: position.line, column: position.column - 1});
+        }
+        candidates.into_iter().find_map( | position | {
+            let (start, token) = self.token_at(position) ?;
+            if start != position {
+                return None;
+            }
+            match token.token {
+                FullToken::Open(delim) => self.find_close_delim(position, delim),
+                FullToken::Close(delim) => self.find_open_delim(position, delim),
+                _ => None,
+            }.map( | other | (position, other))
+        })
+    }
+
+    /// Returns the position of the innermost opening delimiter of kind `delim` before the given
+    /// `position` that is not closed before that position, if any.
+    pub fn find_open_delim(&self, position: Position, delim: Delim) -> Option<Position> {
+        let mut depth = 0;
+        for line in (0..=position.line).rev() {
+            let tokens = self.lines[line].tokens();
+            let mut column = tokens.iter().map( | token | token.len).sum::<usize>();
+            for token in tokens.iter().rev() {
+                column -= token.len;
+                if line == position.line && column >= position.column {
+                    continue;
+                }
+                if token.is_close_delim(delim) {
+                    depth += 1;
+                } else if token.is_open_delim(delim) {
+                    if depth == 0 {
+                        return Some(Position {line, column});
+                    }
+                    depth -= 1;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the position of the innermost closing delimiter of kind `delim` after the given
+    /// `position` that is not opened after that position, if any.
+    pub fn find_close_delim(&self, position: Position, delim: Delim) -> Option<Position> {
+        let mut depth = 0;
+        for line in position.line..self.lines.len() {
+            let mut column = 0;
+            for token in self.lines[line].tokens() {
+                let token_column = column;
+                column += token.len;
+                if line == position.line && token_column <= position.column {
+                    continue;
+                }
+                if token.is_open_delim(delim) {
+                    depth += 1;
+                } else if token.is_close_delim(delim) {
+                    if depth == 0 {
+                        return Some(Position {line, column: token_column});
+                    }
+                    depth -= 1;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl TokenCache {
+    /// Returns the ranges of lines that can be folded, sorted by start line.
+    ///
+    /// These are blocks between braces that span more than two lines, `fn`, `impl` and `mod`
+    /// items (which start on the line of their keyword, even if their body opens on a later line),
+    /// the bodies of `live_register!` macros, and runs of two or more lines that contain only
+    /// comments or that are part of consecutive `use` declarations. If more than one range starts on
+    /// the same line, only the largest one is returned.
+    pub fn fold_ranges(&self) -> Vec<FoldRange> {
+        let mut ranges = Vec::new();
+        // For each open delimiter, the line of the range it starts, or `None` if it does not start
+        // a range.
+        let mut open_delims: Vec<Option<usize>> = Vec::new();
+        // The line of the `fn`, `impl` or `mod` keyword of the item whose body has not been opened
+        // yet, if any, together with the depth of delimiters it occurs at.
+        let mut pending_item: Option<(usize, usize)> = None;
+        // The line of the last `live_register!` macro name, if it was the last token seen.
+        let mut pending_macro: Option<usize> = None;
+        // The start and end of the current run of comment lines, and of `use` lines.
+        let mut comment_run: Option<(usize, usize)> = None;
+        let mut use_run: Option<(usize, usize)> = None;
+        let mut use_depth = None;
+        
+        for (line_index, line) in self.lines.iter().enumerate() {
+            let mut tokens = line.tokens().iter().map( | token | token.token).filter( | token | !token.is_whitespace());
+            let first_token = tokens.next();
+            let is_comment_line = first_token.is_some() && line.tokens().iter().all( | token | {
+                token.token.is_whitespace() || token.token.is_comment()
+            });
+            let is_use_line = use_depth.is_some() || match first_token {
+                Some(FullToken::Ident(id!(use))) => true,
+                Some(FullToken::Ident(id!(pub))) => tokens.next() == Some(FullToken::Ident(id!(use))),
+                _ => false,
+            };
+            
+            comment_run = extend_run(&mut ranges, comment_run, is_comment_line, line_index);
+            use_run = extend_run(&mut ranges, use_run, is_use_line, line_index);
+            
+            for token in line.tokens() {
+                match token.token {
+                    FullToken::Whitespace | FullToken::Comment => continue,
+                    FullToken::Ident(id!(fn)) | FullToken::Ident(id!(impl)) | FullToken::Ident(id!(mod)) => {
+                        if pending_item.is_none() {
+                            pending_item = Some((line_index, open_delims.len()));
+                        }
+                    }
+                    FullToken::Ident(id!(use)) if use_depth.is_none() => {
+                        use_depth = Some(open_delims.len());
+                    }
+                    FullToken::Punct(id!(;)) => {
+                        if pending_item.is_some_and( | (_, depth) | depth == open_delims.len()) {
+                            pending_item = None;
+                        }
+                        if use_depth == Some(open_delims.len()) {
+                            use_depth = None;
+                        }
+                    }
+                    FullToken::Open(delim) => {
+                        let start_line = if let Some(start_line) = pending_macro {
+                            Some(start_line)
+                        } else if delim == Delim::Brace {
+                            match pending_item {
+                                Some((start_line, depth)) if depth == open_delims.len() => {
+                                    pending_item = None;
+                                    Some(start_line)
+                                }
+                                _ => Some(line_index),
+                            }
+                        } else {
+                            None
+                        };
+                        open_delims.push(start_line);
+                    }
+                    FullToken::Close(_) => {
+                        if let Some(start_line) = open_delims.pop().flatten() {
+                            if line_index > start_line + 1 {
+                                ranges.push(FoldRange {start_line, end_line: line_index});
+                            }
+                        }
+                        if pending_item.is_some_and( | (_, depth) | depth > open_delims.len()) {
+                            pending_item = None;
+                        }
+                        if use_depth.is_some_and( | depth | depth > open_delims.len()) {
+                            use_depth = None;
+                        }
+                    }
+                    _ => {}
+                }
+                pending_macro = match (token.token, pending_macro) {
+                    (FullToken::Ident(id!(live_register)), _) => Some(line_index),
+                    (FullToken::Punct(id!(!)), Some(start_line)) => Some(start_line),
+                    _ => None,
+                };
+            }
+        }
+        let line_count = self.lines.len();
+        extend_run(&mut ranges, comment_run, false, line_count);
+        extend_run(&mut ranges, use_run, false, line_count);
+        
+        // Keep only the largest range that starts on each line.
+        ranges.sort_by_key( | range | (range.start_line, usize::MAX - range.end_line));
+        ranges.dedup_by_key( | range | range.start_line);
+        ranges
+    }
+}
+
+// Extends the given `run` of lines with the line at `line_index` if `is_included` is true.
+// Otherwise, ends the run, and adds it to `ranges` if it spans at least two lines. Returns the
+// updated run.
+fn extend_run(
+    ranges: &mut Vec<FoldRange>,
+    run: Option<(usize, usize)>,
+    is_included: bool,
+    line_index: usize
+) -> Option<(usize, usize)> {
+    match (run, is_included) {
+        (Some((start_line, _)), true) => Some((start_line, line_index)),
+        (None, true) => Some((line_index, line_index)),
+        (Some((start_line, end_line)), false) => {
+            if end_line > start_line {
+                ranges.push(FoldRange {start_line, end_line: end_line + 1});
+            }
+            None
+        }
+        (None, false) => None,
+    }
+}
+
 impl Deref for TokenCache {
     type Target = [Line];
 
@@ -113,9 +337,9 @@ impl Line {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 struct TokenInfo {
-    start_state: State,
+    start_state: LanguageState,
     tokens: Vec<TokenWithLen>,
-    end_state: State,
+    end_state: LanguageState,
 }
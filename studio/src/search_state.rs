@@ -0,0 +1,175 @@
+use {
+    std::collections::BTreeMap,
+    crate::{
+        makepad_live_id::LiveId,
+        makepad_editor_core::range::Range,
+        makepad_collab_protocol::{
+            CollabError,
+            CollabNotification,
+            CollabRequest,
+            CollabResponse,
+            SearchId,
+            SearchResult,
+            unix_path::UnixPathBuf,
+        },
+    },
+};
+
+/// The options for a project-wide search.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchOptions {
+    pub query: String,
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub include_globs: Vec<String>,
+    pub exclude_globs: Vec<String>,
+}
+
+impl SearchOptions {
+    /// Parses search options from the given `input`.
+    ///
+    /// Trailing words of the form `in:glob` and `not:glob` add include and exclude globs, and the
+    /// trailing words `+regex` and `+case` make the search use a regex and be case sensitive. The
+    /// rest of the input, with surrounding whitespace removed, is the query.
+    pub fn parse(input: &str) -> SearchOptions {
+        let mut options = SearchOptions::default();
+        let mut query = input.trim();
+        while let Some(index) = query.rfind(char::is_whitespace) {
+            let word = &query[index + 1..];
+            if let Some(glob) = word.strip_prefix("in:") {
+                options.include_globs.insert(0, String::from(glob));
+            }
+            else if let Some(glob) = word.strip_prefix("not:") {
+                options.exclude_globs.insert(0, String::from(glob));
+            }
+            else if word == "+regex" {
+                options.regex = true;
+            }
+            else if word == "+case" {
+                options.case_sensitive = true;
+            }
+            else {
+                break;
+            }
+            query = query[..index].trim_end();
+        }
+        options.query = String::from(query);
+        options
+    }
+}
+
+/// The state of the project-wide search.
+///
+/// Only one search runs at a time. Starting a new search cancels the one that is running, and
+/// results for searches other than the current one are ignored.
+#[derive(Default)]
+pub struct SearchState {
+    pub search_id: Option<SearchId>,
+    pub options: SearchOptions,
+    pub is_running: bool,
+    pub results: Vec<SearchResult>,
+    /// A message about the last search or replacement, such as an error.
+    pub message: Option<String>,
+    /// The number of matches that have been replaced by the last replacement so far.
+    pub replaced_matches: usize,
+    /// The number of files in which matches have been replaced by the last replacement so far.
+    pub replaced_files: usize,
+}
+
+impl SearchState {
+    /// Starts a search with the given `options`, cancelling the search that is running, if any.
+    pub fn start(&mut self, options: SearchOptions, send_request: &mut dyn FnMut(CollabRequest)) {
+        self.cancel(send_request);
+        let search_id = SearchId(LiveId::unique());
+        send_request(CollabRequest::SearchWorkspace {
+            search_id,
+            query: options.query.clone(),
+            regex: options.regex,
+            case_sensitive: options.case_sensitive,
+            include_globs: options.include_globs.clone(),
+            exclude_globs: options.exclude_globs.clone(),
+        });
+        self.search_id = Some(search_id);
+        self.options = options;
+        self.is_running = true;
+        self.results.clear();
+        self.message = None;
+    }
+
+    /// Cancels the search that is running, if any. Its results so far are kept.
+    pub fn cancel(&mut self, send_request: &mut dyn FnMut(CollabRequest)) {
+        if self.is_running {
+            send_request(CollabRequest::CancelSearch(self.search_id.unwrap()));
+            self.is_running = false;
+        }
+    }
+
+    /// Handles a response from the collab server. Returns whether the response was for a search,
+    /// in which case it has been handled.
+    pub fn handle_response(&mut self, response: &CollabResponse) -> bool {
+        match response {
+            CollabResponse::SearchWorkspace(Err(error)) => {
+                self.is_running = false;
+                self.message = Some(match error {
+                    CollabError::InvalidSearchQuery(message) => format!("Invalid regex: {}", message),
+                    error => format!("Search failed: {:?}", error),
+                });
+                true
+            }
+            CollabResponse::SearchWorkspace(Ok(_)) | CollabResponse::CancelSearch(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Handles a notification from the collab server. Returns whether the notification was for a
+    /// search, in which case it has been handled.
+    pub fn handle_notification(&mut self, notification: &CollabNotification) -> bool {
+        match notification {
+            CollabNotification::SearchResult(search_id, result) => {
+                if Some(*search_id) == self.search_id {
+                    self.results.push(result.clone());
+                }
+                true
+            }
+            CollabNotification::SearchFinished(search_id) => {
+                if Some(*search_id) == self.search_id {
+                    self.is_running = false;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records that `count` matches in one file have been replaced.
+    pub fn add_replaced_matches(&mut self, count: usize) {
+        if count > 0 {
+            self.replaced_matches += count;
+            self.replaced_files += 1;
+        }
+        self.message = Some(format!(
+            "Replaced {} matches in {} files",
+            self.replaced_matches,
+            self.replaced_files
+        ));
+    }
+
+    /// Returns the results, grouped by file, as the range of each match together with the text it
+    /// matched when the search ran. The results are removed, since replacing them invalidates
+    /// them, and the replacement counts are reset.
+    pub fn take_matches_by_path(&mut self) -> BTreeMap<UnixPathBuf, Vec<(Range, String)>> {
+        let mut matches_by_path: BTreeMap<UnixPathBuf, Vec<(Range, String)>> = BTreeMap::new();
+        for result in self.results.drain(..) {
+            let text = result
+                .preview
+                .chars()
+                .skip(result.range.start.column)
+                .take(result.range.end.column - result.range.start.column)
+                .collect();
+            matches_by_path.entry(result.path).or_default().push((result.range, text));
+        }
+        self.replaced_matches = 0;
+        self.replaced_files = 0;
+        matches_by_path
+    }
+}
@@ -0,0 +1,101 @@
+use {
+    std::{
+        fmt::Write,
+    },
+    crate::{
+        makepad_studio_component::{
+            log_icon::LogIconType,
+            log_list::{LogList, LogListAction}
+        },
+        makepad_platform::*,
+        search_state::SearchState,
+    },
+};
+
+live_register!{
+    use makepad_platform::shader::std::*;
+
+    SearchView: {{SearchView}} {
+    }
+}
+
+/// The results of the project-wide search.
+///
+/// The first node summarizes the search, and is shown as waiting while the search is running.
+/// Each result is shown as one node with its location and the line it was found on. Clicking a
+/// result opens its file at the range that matched.
+#[derive(Live, LiveHook)]
+pub struct SearchView {
+    log_list: LogList
+}
+
+pub enum SearchViewAction {
+    OpenResult(usize),
+}
+
+impl SearchView {
+    pub fn redraw(&mut self, cx: &mut Cx) {
+        self.log_list.redraw(cx)
+    }
+
+    pub fn draw(&mut self, cx: &mut Cx2d, state: &SearchState) {
+        const MAX_PREVIEW_LEN: usize = 120;
+
+        let mut file = String::new();
+        let mut body = String::new();
+        if self.log_list.begin(cx).is_ok() {
+            if self.log_list.should_node_draw(cx) {
+                if state.search_id.is_some() {
+                    write!(file, "\"{}\"", state.options.query).unwrap();
+                    write!(body, "{} results", state.results.len()).unwrap();
+                    if state.is_running {
+                        body.push_str(", searching...");
+                    }
+                }
+                if let Some(message) = &state.message {
+                    if !body.is_empty() {
+                        body.push_str(", ");
+                    }
+                    body.push_str(message);
+                }
+                let icon = if state.is_running {LogIconType::Wait} else {LogIconType::Ok};
+                self.log_list.draw_node(cx, icon, id!(summary).into(), &file, &body, false);
+            }
+            for (index, result) in state.results.iter().enumerate() {
+                if self.log_list.should_node_draw(cx) {
+                    file.clear();
+                    body.clear();
+                    let id = LiveId(index as u64).into();
+                    write!(
+                        file,
+                        "{}:{}",
+                        result.path.as_unix_str().to_string_lossy(),
+                        result.range.start.line + 1
+                    ).unwrap();
+                    let preview = result.preview.trim();
+                    body.extend(preview.chars().take(MAX_PREVIEW_LEN));
+                    if preview.chars().count() > MAX_PREVIEW_LEN {
+                        body.push('…');
+                    }
+                    self.log_list.draw_node(cx, LogIconType::Ok, id, &file, &body, false);
+                }
+            }
+            self.log_list.end(cx);
+        }
+    }
+
+    pub fn handle_event_with_fn(
+        &mut self,
+        cx: &mut Cx,
+        event: &mut Event,
+        dispatch_action: &mut dyn FnMut(&mut Cx, SearchViewAction),
+    ) {
+        self.log_list.handle_event_with_fn(cx, event, &mut | cx, action | {
+            if let LogListAction::WasClicked(node_id) = action {
+                if node_id != id!(summary).into() {
+                    dispatch_action(cx, SearchViewAction::OpenResult(node_id.0.0 as usize))
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,333 @@
+use {
+    std::{
+        collections::{HashMap, HashSet},
+        fs,
+        path::Path,
+    },
+    crate::{
+        makepad_micro_serde::*,
+        makepad_platform::*,
+        makepad_component::{
+            splitter::SplitterAlign,
+            dock::PanelId,
+            tab_bar::TabId,
+        },
+        makepad_studio_component::file_tree::FileNodeId,
+        makepad_collab_protocol::{
+            CollabRequest,
+            unix_path::{UnixPath, UnixPathBuf},
+        },
+        app_state::{AppState, Panel, SplitPanel, TabPanel, Tab, TabKind},
+        code_editor::cursor_set::CursorSet,
+        editor_state::UndoHistory,
+    },
+};
+
+/// The name of the file, in the root of a workspace, that the layout of the workspace is saved to.
+pub const WORKSPACE_FILE_NAME: &str = ".makepad_studio.ron";
+
+/// The name of the file, next to the workspace file, that the undo histories of the files in the
+/// workspace are saved to. These are kept in a binary file of their own, since they can be large.
+pub const UNDO_HISTORIES_FILE_NAME: &str = ".makepad_studio_undo.bin";
+
+/// The layout of the studio for a workspace, as it is saved between runs.
+///
+/// This mirrors the panels and tabs in `AppState`, but refers to panels and tabs by the raw value of
+/// their id, and to files by their path, since file node ids are reallocated each time the file
+/// tree is loaded.
+#[derive(Debug, SerRon, DeRon)]
+pub struct Workspace {
+    pub panels: Vec<WorkspacePanel>,
+    pub tabs: Vec<WorkspaceTab>,
+    pub selected_panel_id: u64,
+    /// The paths of the folders that are open in the file tree.
+    pub open_folders: Vec<String>,
+}
+
+#[derive(Debug, SerRon, DeRon)]
+pub enum WorkspacePanel {
+    Split {
+        id: u64,
+        axis: Axis,
+        align: SplitterAlign,
+        child_panel_ids: (u64, u64),
+    },
+    Tab {
+        id: u64,
+        tab_ids: Vec<u64>,
+        selected_tab: Option<usize>,
+    },
+}
+
+#[derive(Debug, SerRon, DeRon)]
+pub struct WorkspaceTab {
+    pub id: u64,
+    pub name: String,
+    pub kind: WorkspaceTabKind,
+}
+
+#[derive(Debug, SerRon, DeRon)]
+pub enum WorkspaceTabKind {
+    LogView,
+    HistoryView,
+    SearchView,
+    ShaderView,
+    SlidesView,
+    FileTree,
+    CodeEditor {
+        path: String,
+        cursors: CursorSet,
+        scroll_pos: (f32, f32),
+        /// The start lines of the folded ranges. This is optional so that workspaces saved before
+        /// folding was added can still be loaded.
+        folded_lines: Option<Vec<usize>>,
+    },
+}
+
+/// The parts of a restored workspace that live in the UI components rather than in `AppState`,
+/// and still need to be applied to them.
+#[derive(Debug, Default)]
+pub struct RestoredWorkspace {
+    /// The scroll position of the code editor for each code editor tab.
+    pub scroll_positions: Vec<(TabId, Vec2)>,
+    /// The folders that should be open in the file tree.
+    pub open_folder_ids: Vec<FileNodeId>,
+}
+
+impl Workspace {
+    /// Captures the layout of the given `state`.
+    ///
+    /// The `open_folder_ids` are the folders that are open in the file tree, and `scroll_pos`
+    /// returns the scroll position of the code editor for a tab, if it has one.
+    pub fn capture(
+        state: &AppState,
+        open_folder_ids: impl Iterator<Item = FileNodeId>,
+        scroll_pos: impl Fn(TabId) -> Option<Vec2>,
+    ) -> Workspace {
+        let panels = state.panels.iter().map( | (panel_id, panel) | match panel {
+            Panel::Split(panel) => WorkspacePanel::Split {
+                id: panel_id.0.0,
+                axis: panel.axis,
+                align: panel.align,
+                child_panel_ids: (panel.child_panel_ids[0].0.0, panel.child_panel_ids[1].0.0),
+            },
+            Panel::Tab(panel) => WorkspacePanel::Tab {
+                id: panel_id.0.0,
+                tab_ids: panel.tab_ids.iter().map( | tab_id | tab_id.0.0).collect(),
+                selected_tab: panel.selected_tab,
+            },
+        }).collect();
+
+        let tabs = state.tabs.iter().map( | (tab_id, tab) | WorkspaceTab {
+            id: tab_id.0.0,
+            name: tab.name.clone(),
+            kind: match tab.kind {
+                TabKind::LogView => WorkspaceTabKind::LogView,
+                TabKind::HistoryView => WorkspaceTabKind::HistoryView,
+                TabKind::SearchView => WorkspaceTabKind::SearchView,
+                TabKind::ShaderView => WorkspaceTabKind::ShaderView,
+                TabKind::SlidesView => WorkspaceTabKind::SlidesView,
+                TabKind::FileTree => WorkspaceTabKind::FileTree,
+                TabKind::CodeEditor {session_id} => {
+                    let session = &state.editor_state.sessions[session_id];
+                    let document = &state.editor_state.documents[session.document_id];
+                    let scroll_pos = scroll_pos(*tab_id).unwrap_or(Vec2::default());
+                    WorkspaceTabKind::CodeEditor {
+                        path: path_to_string(&document.path),
+                        cursors: session.cursors.clone(),
+                        scroll_pos: (scroll_pos.x, scroll_pos.y),
+                        folded_lines: if session.folds.is_empty() {
+                            None
+                        } else {
+                            Some(session.folds.lines().collect())
+                        },
+                    }
+                }
+            },
+        }).collect();
+
+        Workspace {
+            panels,
+            tabs,
+            selected_panel_id: state.selected_panel_id.0.0,
+            open_folders: open_folder_ids
+                .map( | file_node_id | path_to_string(&state.file_node_path(file_node_id)))
+                .collect(),
+        }
+    }
+
+    /// Loads the workspace saved in the directory with the given `path`, if there is one and it
+    /// can be parsed.
+    pub fn load(path: &Path) -> Option<Workspace> {
+        let string = fs::read_to_string(path.join(WORKSPACE_FILE_NAME)).ok() ?;
+        match Workspace::deserialize_ron(&string) {
+            Ok(workspace) => Some(workspace),
+            Err(err) => {
+                println!("Ignoring saved workspace: {:?}", err);
+                None
+            }
+        }
+    }
+
+    /// Saves this workspace in the directory with the given `path`.
+    pub fn save(&self, path: &Path) {
+        if let Err(err) = fs::write(path.join(WORKSPACE_FILE_NAME), self.serialize_ron()) {
+            println!("Could not save workspace: {}", err);
+        }
+    }
+
+    /// Returns whether the panels of this workspace form a tree that is rooted at the root panel,
+    /// every tab they refer to exists, no id is used twice, and the selected panel is a tab panel.
+    /// A workspace for which this is not the case cannot be restored.
+    pub fn is_valid(&self) -> bool {
+        let panels: HashMap<u64, &WorkspacePanel> = self.panels.iter().map( | panel | match panel {
+            WorkspacePanel::Split {id, ..} | WorkspacePanel::Tab {id, ..} => (*id, panel)
+        }).collect();
+        let tab_ids: HashSet<u64> = self.tabs.iter().map( | tab | tab.id).collect();
+        if panels.len() != self.panels.len() || tab_ids.len() != self.tabs.len() {
+            return false;
+        }
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![id!(root).0];
+        while let Some(panel_id) = stack.pop() {
+            if !visited.insert(panel_id) {
+                return false;
+            }
+            match panels.get(&panel_id) {
+                Some(WorkspacePanel::Split {child_panel_ids, ..}) => {
+                    stack.push(child_panel_ids.0);
+                    stack.push(child_panel_ids.1);
+                }
+                Some(WorkspacePanel::Tab {tab_ids: panel_tab_ids, ..}) => {
+                    if !panel_tab_ids.iter().all( | tab_id | tab_ids.contains(tab_id)) {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        matches!(panels.get(&self.selected_panel_id), Some(WorkspacePanel::Tab {..}))
+    }
+
+    /// Replaces the panels and tabs of the given `state` with the ones in this workspace, which
+    /// should be valid.
+    ///
+    /// A session is created for each code editor tab, using `send_request` to open its file.
+    /// Code editor tabs for files that no longer exist in the file tree of `state` are dropped, as
+    /// are open folders that no longer exist. The caller is responsible for destroying the
+    /// sessions of any code editor tabs that `state` had before.
+    pub fn restore(
+        self,
+        state: &mut AppState,
+        send_request: &mut dyn FnMut(CollabRequest),
+    ) -> RestoredWorkspace {
+        let file_node_ids: HashMap<UnixPathBuf, FileNodeId> = state
+            .file_nodes
+            .keys()
+            .map( | file_node_id | (state.file_node_path(*file_node_id), *file_node_id))
+            .collect();
+        let mut restored = RestoredWorkspace::default();
+
+        state.tabs.clear();
+        let mut missing_tab_ids = HashSet::new();
+        for tab in self.tabs {
+            let tab_id = TabId(LiveId(tab.id));
+            let kind = match tab.kind {
+                WorkspaceTabKind::LogView => TabKind::LogView,
+                WorkspaceTabKind::HistoryView => TabKind::HistoryView,
+                WorkspaceTabKind::SearchView => TabKind::SearchView,
+                WorkspaceTabKind::ShaderView => TabKind::ShaderView,
+                WorkspaceTabKind::SlidesView => TabKind::SlidesView,
+                WorkspaceTabKind::FileTree => TabKind::FileTree,
+                WorkspaceTabKind::CodeEditor {path, cursors, scroll_pos, folded_lines} => {
+                    let path = UnixPathBuf::from(path.as_str());
+                    if !file_node_ids.contains_key(&path) {
+                        missing_tab_ids.insert(tab.id);
+                        continue;
+                    }
+                    let session_id = state.editor_state.create_session(path, send_request);
+                    state.editor_state.set_cursors(session_id, cursors);
+                    if let Some(folded_lines) = folded_lines {
+                        state.editor_state.set_folded_lines(session_id, &folded_lines);
+                    }
+                    restored.scroll_positions.push((tab_id, vec2(scroll_pos.0, scroll_pos.1)));
+                    TabKind::CodeEditor {session_id}
+                }
+            };
+            state.tabs.insert(tab_id, Tab {name: tab.name, kind});
+        }
+
+        state.panels.clear();
+        for panel in self.panels {
+            match panel {
+                WorkspacePanel::Split {id, axis, align, child_panel_ids} => {
+                    state.panels.insert(PanelId(LiveId(id)), Panel::Split(SplitPanel {
+                        axis,
+                        align,
+                        child_panel_ids: [
+                            PanelId(LiveId(child_panel_ids.0)),
+                            PanelId(LiveId(child_panel_ids.1)),
+                        ],
+                    }));
+                }
+                WorkspacePanel::Tab {id, tab_ids, selected_tab} => {
+                    let selected_tab_id = selected_tab.and_then( | index | tab_ids.get(index).cloned());
+                    let tab_ids: Vec<u64> = tab_ids
+                        .into_iter()
+                        .filter( | tab_id | !missing_tab_ids.contains(tab_id))
+                        .collect();
+                    // If the selected tab was dropped, select the first tab instead.
+                    let selected_tab = selected_tab_id
+                        .and_then( | selected_tab_id | tab_ids.iter().position( | tab_id | *tab_id == selected_tab_id))
+                        .or(if tab_ids.is_empty() {None} else {Some(0)});
+                    state.panels.insert(PanelId(LiveId(id)), Panel::Tab(TabPanel {
+                        tab_ids: tab_ids.into_iter().map( | tab_id | TabId(LiveId(tab_id))).collect(),
+                        selected_tab,
+                    }));
+                }
+            }
+        }
+        state.selected_panel_id = PanelId(LiveId(self.selected_panel_id));
+
+        restored.open_folder_ids = self.open_folders
+            .iter()
+            .filter_map( | path | file_node_ids.get(UnixPath::new(path.as_str())).cloned())
+            .collect();
+        restored
+    }
+}
+
+/// Saves the given undo histories, by file path, in the directory with the given `path`.
+pub fn save_undo_histories(path: &Path, undo_histories: &HashMap<UnixPathBuf, UndoHistory>) {
+    let undo_histories: Vec<(String, UndoHistory)> = undo_histories
+        .iter()
+        .map( | (file_path, history) | (path_to_string(file_path), history.clone()))
+        .collect();
+    if let Err(err) = fs::write(path.join(UNDO_HISTORIES_FILE_NAME), undo_histories.serialize_bin()) {
+        println!("Could not save undo histories: {}", err);
+    }
+}
+
+/// Loads the undo histories saved in the directory with the given `path`. If there are none, or
+/// they cannot be read, no histories are returned.
+pub fn load_undo_histories(path: &Path) -> HashMap<UnixPathBuf, UndoHistory> {
+    let bytes = match fs::read(path.join(UNDO_HISTORIES_FILE_NAME)) {
+        Ok(bytes) => bytes,
+        Err(_) => return HashMap::new(),
+    };
+    match Vec::<(String, UndoHistory)>::deserialize_bin(&bytes) {
+        Ok(undo_histories) => undo_histories
+            .into_iter()
+            .map( | (file_path, history) | (UnixPathBuf::from(file_path.as_str()), history))
+            .collect(),
+        Err(err) => {
+            println!("Ignoring saved undo histories: {:?}", err);
+            HashMap::new()
+        }
+    }
+}
+
+fn path_to_string(path: &UnixPath) -> String {
+    path.as_unix_str().to_string_lossy().into_owned()
+}
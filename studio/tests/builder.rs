@@ -0,0 +1,80 @@
+use {
+    makepad_editor_core::{position::Position, range::Range},
+    makepad_studio::builder::{
+        builder_protocol::{BuilderCmd, BuilderCmdId, BuilderCmdWrap, BuilderMsg, BuilderMsgLevel, BuilderMsgWrap},
+        builder_server::{live_check_msg, take_snapshot, BuilderServer},
+    },
+    std::{
+        collections::HashMap,
+        env,
+        fs,
+        path::PathBuf,
+        slice,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+#[test]
+fn live_check_errors_are_located() {
+    let msg = live_check_msg("examples/cmdline_example/src/main.rs:12:5: error: unknown property width").unwrap();
+    assert_eq!(msg.file_name, "examples/cmdline_example/src/main.rs");
+    assert_eq!(msg.range, Range {
+        start: Position {line: 11, column: 4},
+        end: Position {line: 11, column: 5},
+    });
+    assert_eq!(msg.msg, "unknown property width");
+    // paths can contain colons themselves
+    assert_eq!(live_check_msg("C:\\app\\main.rs:1:1: error: x").unwrap().file_name, "C:\\app\\main.rs");
+}
+
+#[test]
+fn other_live_check_output_is_not_an_error() {
+    assert!(live_check_msg("Checked 3 live_register! blocks in 2 files and 0 live files: 1 errors").is_none());
+    assert!(live_check_msg("src/main.rs:0:0: error: no such position").is_none());
+}
+
+#[test]
+fn snapshot_skips_ignored_files_unless_they_are_watched() {
+    let root = env::temp_dir().join(format!("makepad_builder_watch_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src/nested")).unwrap();
+    fs::create_dir_all(root.join("target")).unwrap();
+    for path in ["a.rs", "src/nested/b.rs", "target/c.rs", ".hidden.rs"] {
+        fs::write(root.join(path), "").unwrap();
+    }
+    let paths = | snapshot: HashMap<PathBuf, SystemTime> | {
+        let mut paths: Vec<_> = snapshot.into_keys().collect();
+        paths.sort();
+        paths
+    };
+    assert_eq!(paths(take_snapshot(slice::from_ref(&root))), vec![root.join("a.rs"), root.join("src/nested/b.rs")]);
+    assert_eq!(
+        paths(take_snapshot(&[root.join("target/c.rs"), root.join("src"), root.join("missing.rs")])),
+        vec![root.join("src/nested/b.rs"), root.join("target/c.rs")]
+    );
+    assert!(take_snapshot(&[]).is_empty());
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn failing_to_start_cargo_finishes_the_run_with_an_error() {
+    let root = env::temp_dir().join(format!("makepad_builder_missing_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+    let mut server = BuilderServer::new(&root);
+    let mut connection = server.connect(Box::new({
+        let msgs = msgs.clone();
+        move | wrap: BuilderMsgWrap | msgs.lock().unwrap().push(wrap.msg)
+    }));
+    connection.handle_cmd(BuilderCmdWrap {cmd_id: BuilderCmdId(1), cmd: BuilderCmd::CargoCheck});
+    let start = Instant::now();
+    while !msgs.lock().unwrap().iter().any( | msg | matches!(msg, BuilderMsg::RunFinished)) {
+        assert!(start.elapsed() < Duration::from_secs(10), "the run did not finish");
+        thread::sleep(Duration::from_millis(10));
+    }
+    let msgs = msgs.lock().unwrap();
+    assert!(matches!(msgs.first(), Some(BuilderMsg::RunStarted)));
+    assert!(msgs.iter().any( | msg | matches!(msg, BuilderMsg::Bare(bare) if matches!(bare.level, BuilderMsgLevel::Error))));
+}
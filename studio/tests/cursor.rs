@@ -0,0 +1,107 @@
+use {
+    makepad_editor_core::{position::Position, text::Text},
+    makepad_studio::code_editor::{line_cursor, Cursor},
+};
+
+
+fn cursor_at(line: usize, column: usize) -> Cursor {
+    let mut cursor = Cursor::new();
+    cursor.move_to(Position {line, column}, false);
+    cursor
+}
+
+#[test]
+fn grapheme_boundaries() {
+    // "e" followed by a combining acute accent, and a family emoji made of several code points
+    // joined by zero width joiners.
+    let text = Text::from("ae\u{301}b👨\u{200d}👩\u{200d}👧c");
+    let line = text.line(0);
+    assert_eq!(line_cursor::next_grapheme_boundary(line, 0), 1);
+    assert_eq!(line_cursor::next_grapheme_boundary(line, 1), 3);
+    assert_eq!(line_cursor::next_grapheme_boundary(line, 4), 9);
+    assert_eq!(line_cursor::next_grapheme_boundary(line, 10), 10);
+    assert_eq!(line_cursor::prev_grapheme_boundary(line, 10), 9);
+    assert_eq!(line_cursor::prev_grapheme_boundary(line, 9), 4);
+    assert_eq!(line_cursor::prev_grapheme_boundary(line, 3), 1);
+    assert_eq!(line_cursor::prev_grapheme_boundary(line, 0), 0);
+}
+
+#[test]
+fn word_boundaries() {
+    let text = Text::from("let foo_bar = baz(1);");
+    let line = text.line(0);
+    assert_eq!(line_cursor::next_word_boundary(line, 0), 3);
+    assert_eq!(line_cursor::next_word_boundary(line, 3), 11);
+    assert_eq!(line_cursor::next_word_boundary(line, 11), 13);
+    assert_eq!(line_cursor::prev_word_boundary(line, 21), 20);
+    assert_eq!(line_cursor::prev_word_boundary(line, 14), 12);
+    assert_eq!(line_cursor::prev_word_boundary(line, 11), 4);
+    assert_eq!(line_cursor::prev_word_boundary(line, 3), 0);
+    assert_eq!(line_cursor::word_range(line, 6), 4..11);
+    assert_eq!(line_cursor::word_range(line, 3), 3..4);
+    assert_eq!(line_cursor::word_range(line, 21), 20..21);
+    assert_eq!(line_cursor::word_range(Text::new().line(0), 0), 0..0);
+}
+
+#[test]
+fn cursor_moves_by_graphemes() {
+    let text = Text::from("e\u{301}\n😀");
+    let mut cursor = cursor_at(0, 0);
+    cursor.move_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 0, column: 2});
+    cursor.move_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 1, column: 0});
+    cursor.move_right(&text, true);
+    assert_eq!(cursor.head, Position {line: 1, column: 1});
+    assert_eq!(cursor.tail, Position {line: 1, column: 0});
+    cursor.move_left(&text, false);
+    cursor.move_left(&text, false);
+    assert_eq!(cursor.head, Position {line: 0, column: 2});
+    cursor.move_left(&text, false);
+    assert_eq!(cursor.head, Position {line: 0, column: 0});
+}
+
+#[test]
+fn cursor_moves_by_words() {
+    let text = Text::from("fn main() {\n    foo\n}");
+    let mut cursor = cursor_at(0, 0);
+    cursor.move_word_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 0, column: 2});
+    cursor.move_word_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 0, column: 7});
+    let mut cursor = cursor_at(0, 11);
+    cursor.move_word_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 1, column: 0});
+    cursor.move_word_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 1, column: 7});
+    cursor.move_word_left(&text, true);
+    assert_eq!(cursor.head, Position {line: 1, column: 4});
+    assert_eq!(cursor.tail, Position {line: 1, column: 7});
+    cursor.move_word_left(&text, false);
+    assert_eq!(cursor.head, Position {line: 1, column: 0});
+    cursor.move_word_left(&text, false);
+    assert_eq!(cursor.head, Position {line: 0, column: 11});
+
+    let mut cursor = Cursor::new();
+    cursor.select_word(&text, Position {line: 0, column: 5});
+    assert_eq!(cursor.tail, Position {line: 0, column: 3});
+    assert_eq!(cursor.head, Position {line: 0, column: 7});
+}
+
+#[test]
+fn boundaries_in_lines_that_span_several_chunks() {
+    let line = format!("{}e\u{301}", "word ".repeat(500));
+    let text = Text::from(format!("first\n{}\nlast", line));
+    let line = text.line(1);
+    assert_eq!(line_cursor::next_word_boundary(line, 0), 4);
+    assert_eq!(line_cursor::next_word_boundary(line, 1204), 1209);
+    assert_eq!(line_cursor::prev_word_boundary(line, 2500), 2495);
+    assert_eq!(line_cursor::word_range(line, 1502), 1500..1504);
+    assert_eq!(line_cursor::prev_grapheme_boundary(line, 2502), 2500);
+    assert_eq!(line_cursor::next_grapheme_boundary(line, 2500), 2502);
+    let mut cursor = cursor_at(1, 2500);
+    cursor.move_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 1, column: 2502});
+    cursor.move_right(&text, false);
+    assert_eq!(cursor.head, Position {line: 2, column: 0});
+}
@@ -0,0 +1,242 @@
+use {
+    makepad_editor_core::{delta, position::Position, range::Range, size::Size, text::Text},
+    makepad_studio::{
+        builder::builder_protocol::{BuilderMsg, BuilderMsgLevel, BuilderMsgLocation},
+        editor_state::{EditorState, SessionId, UndoHistory},
+        makepad_collab_protocol::{unix_path::UnixPathBuf, TextFileId},
+        makepad_live_id::LiveId,
+        makepad_micro_serde::{DeBin, SerBin},
+    },
+};
+
+fn open(text: &str) -> (EditorState, SessionId) {
+    let text = Text::from(text);
+    let mut state = EditorState::new();
+    let session_id = state.create_session(UnixPathBuf::from("test.rs"), &mut | _ | {});
+    state.handle_open_file_response(TextFileId(LiveId(1)), 0, text, &mut | _ | {});
+    (state, session_id)
+}
+
+fn text(state: &EditorState, session_id: SessionId) -> String {
+    let session = &state.sessions[session_id];
+    state.documents[session.document_id].inner.as_ref().unwrap().text.to_string()
+}
+
+fn carets(state: &EditorState, session_id: SessionId) -> Vec<Position> {
+    state.sessions[session_id].cursors.iter().map( | cursor | cursor.head).collect()
+}
+
+fn type_str(state: &mut EditorState, session_id: SessionId, string: &str) {
+    for ch in string.chars() {
+        match ch {
+            '\n' => state.insert_newline(session_id, &mut | _ | {}),
+            _ => state.insert_text(session_id, Text::from(ch.to_string().as_str()), &mut | _ | {}),
+        }
+    }
+}
+
+fn position(line: usize, column: usize) -> Position {
+    Position {line, column}
+}
+
+#[test]
+fn closing_delimiters_are_injected_and_overtyped() {
+    let (mut state, session_id) = open("\n");
+    state.add_cursor(session_id, position(1, 0));
+    type_str(&mut state, session_id, "f(a[1]");
+    assert_eq!(text(&state, session_id), "f(a[1])\nf(a[1])");
+    type_str(&mut state, session_id, ")");
+    assert_eq!(text(&state, session_id), "f(a[1])\nf(a[1])");
+    assert_eq!(carets(&state, session_id), vec![position(0, 7), position(1, 7)]);
+}
+
+#[test]
+fn quotes_are_injected_outside_strings_only() {
+    let (mut state, session_id) = open("");
+    type_str(&mut state, session_id, "let s = \"a");
+    assert_eq!(text(&state, session_id), "let s = \"a\"");
+    type_str(&mut state, session_id, "\"");
+    assert_eq!(text(&state, session_id), "let s = \"a\"");
+    // no quote is injected right after an identifier
+    type_str(&mut state, session_id, " b\"");
+    assert_eq!(text(&state, session_id), "let s = \"a\" b\"");
+}
+
+#[test]
+fn delimiters_are_not_injected_before_text() {
+    let (mut state, session_id) = open("foo");
+    type_str(&mut state, session_id, "(");
+    assert_eq!(text(&state, session_id), "(foo");
+}
+
+#[test]
+fn backspace_removes_injected_delimiter() {
+    let (mut state, session_id) = open("");
+    type_str(&mut state, session_id, "[");
+    state.insert_backspace(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "");
+}
+
+#[test]
+fn newline_between_braces_is_indented() {
+    let (mut state, session_id) = open("fn f() {}\nfn g() {}");
+    state.move_cursors_to(session_id, position(0, 8), false);
+    state.add_cursor(session_id, position(1, 8));
+    type_str(&mut state, session_id, "\n");
+    assert_eq!(text(&state, session_id), "fn f() {\n    \n}\nfn g() {\n    \n}");
+    assert_eq!(carets(&state, session_id), vec![position(1, 4), position(4, 4)]);
+}
+
+#[test]
+fn closing_delimiter_is_dedented() {
+    let (mut state, session_id) = open("    if x {\n        y\n        ");
+    state.move_cursors_to(session_id, position(2, 8), false);
+    type_str(&mut state, session_id, "}");
+    assert_eq!(text(&state, session_id), "    if x {\n        y\n    }");
+    assert_eq!(carets(&state, session_id), vec![position(2, 5)]);
+}
+
+#[test]
+fn closing_delimiter_is_dedented_once_per_line() {
+    let (mut state, session_id) = open("fn f() {\n        \n");
+    state.move_cursors_to(session_id, position(1, 2), false);
+    state.add_cursor(session_id, position(1, 6));
+    type_str(&mut state, session_id, "}");
+    assert_eq!(text(&state, session_id), "fn f() {\n}      \n");
+    assert_eq!(carets(&state, session_id), vec![position(1, 1), position(1, 5)]);
+}
+
+#[test]
+fn cursors_jump_to_matching_delimiters() {
+    let (mut state, session_id) = open("f(a, [b], \")\")");
+    state.move_cursors_to(session_id, position(0, 1), false);
+    state.move_cursors_to_matching_delims(session_id, false);
+    assert_eq!(carets(&state, session_id), vec![position(0, 13)]);
+    state.move_cursors_to_matching_delims(session_id, false);
+    assert_eq!(carets(&state, session_id), vec![position(0, 1)]);
+    // the delimiter before the caret is used if there is none after it
+    state.move_cursors_to(session_id, position(0, 8), false);
+    state.move_cursors_to_matching_delims(session_id, false);
+    assert_eq!(carets(&state, session_id), vec![position(0, 5)]);
+}
+
+#[test]
+fn word_delete_with_overlapping_cursors() {
+    let (mut state, session_id) = open("abcdef ghi");
+    state.move_cursors_to(session_id, position(0, 2), false);
+    state.add_cursor(session_id, position(0, 4));
+    state.delete_word_backward(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "ef ghi");
+    let (mut state, session_id) = open("abc def");
+    state.move_cursors_to(session_id, position(0, 1), false);
+    state.add_cursor(session_id, position(0, 2));
+    state.delete_word_forward(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "a def");
+}
+
+#[test]
+fn editing_after_undo_starts_a_new_branch() {
+    let (mut state, session_id) = open("");
+    type_str(&mut state, session_id, "abc");
+    state.undo(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "");
+    type_str(&mut state, session_id, "x");
+    assert_eq!(text(&state, session_id), "x");
+    // the undone edit can still be reached
+    state.jump_to_undo_state(session_id, 1, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "abc");
+    assert_eq!(carets(&state, session_id), vec![position(0, 3)]);
+    // redo follows the branch that was visited last
+    state.undo(session_id, &mut | _ | {});
+    state.redo(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "abc");
+}
+
+#[test]
+fn undo_history_is_restored_when_file_is_reopened() {
+    let (mut state, session_id) = open("a");
+    state.move_cursors_to(session_id, position(0, 1), false);
+    type_str(&mut state, session_id, "b\nc");
+    state.destroy_session(session_id, &mut | _ | {});
+    let session_id = state.create_session(UnixPathBuf::from("test.rs"), &mut | _ | {});
+    state.handle_open_file_response(TextFileId(LiveId(2)), 0, Text::from("ab\nc"), &mut | _ | {});
+    state.undo(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "ab\n");
+    state.undo(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "ab");
+}
+
+#[test]
+fn undo_history_is_dropped_when_file_changed() {
+    let (mut state, session_id) = open("");
+    type_str(&mut state, session_id, "a");
+    state.destroy_session(session_id, &mut | _ | {});
+    let session_id = state.create_session(UnixPathBuf::from("test.rs"), &mut | _ | {});
+    state.handle_open_file_response(TextFileId(LiveId(2)), 0, Text::from("b"), &mut | _ | {});
+    state.undo(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "b");
+}
+
+#[test]
+fn undo_history_is_transformed_by_remote_deltas() {
+    let (mut state, session_id) = open("");
+    type_str(&mut state, session_id, "ab");
+    state.undo(session_id, &mut | _ | {});
+    type_str(&mut state, session_id, "\ncd");
+    let mut builder = delta::Builder::new();
+    builder.insert(Text::from("xy"));
+    let delta = builder.build();
+    state.handle_delta_applied_notification(TextFileId(LiveId(1)), delta);
+    assert_eq!(text(&state, session_id), "xy\ncd");
+    state.undo(session_id, &mut | _ | {});
+    state.undo(session_id, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "xy");
+    state.jump_to_undo_state(session_id, 1, &mut | _ | {});
+    assert_eq!(text(&state, session_id), "xyab");
+}
+
+#[test]
+fn undo_history_round_trips_through_serbin() {
+    let (mut state, session_id) = open("");
+    type_str(&mut state, session_id, "ab\nc");
+    state.destroy_session(session_id, &mut | _ | {});
+    let history = &state.undo_histories[&UnixPathBuf::from("test.rs")];
+    let bytes = history.serialize_bin();
+    let history = UndoHistory::deserialize_bin(&bytes).unwrap();
+    assert_eq!(history.undo_tree.len(), 4);
+    let mut text = Text::from("ab\nc");
+    text.apply_delta(history.undo_tree.node(3).inverse_delta.clone());
+    text.apply_delta(history.undo_tree.node(2).inverse_delta.clone());
+    assert_eq!(text.len(), Size {line: 0, column: 2});
+}
+
+#[test]
+fn live_messages_follow_edits() {
+    let (mut state, session_id) = open("a\nb");
+    let document_id = state.sessions[session_id].document_id;
+    assert!(!state.documents[document_id].inner.as_ref().unwrap().live_edited);
+    state.set_live_messages(vec![BuilderMsg::Location(BuilderMsgLocation {
+        level: BuilderMsgLevel::Error,
+        file_name: "test.rs".to_string(),
+        range: Range {start: position(1, 0), end: position(1, 1)},
+        msg: "error".to_string(),
+    })]);
+    type_str(&mut state, session_id, "\n");
+    let inner = state.documents[document_id].inner.as_ref().unwrap();
+    assert!(inner.live_edited);
+    assert!(inner.live_msg_cache[1].spans().is_empty());
+    assert_eq!(inner.live_msg_cache[2].spans().len(), 1);
+}
+
+#[test]
+fn opening_and_closing_documents_is_reported_once() {
+    let (mut state, session_id) = open("a");
+    assert!(state.take_documents_changed());
+    assert!(!state.take_documents_changed());
+    // a second session for the same file shares its document
+    let other_session_id = state.create_session(UnixPathBuf::from("test.rs"), &mut | _ | {});
+    assert!(!state.take_documents_changed());
+    state.destroy_session(other_session_id, &mut | _ | {});
+    state.destroy_session(session_id, &mut | _ | {});
+    assert!(state.take_documents_changed());
+}
@@ -0,0 +1,145 @@
+use {
+    makepad_editor_core::{position::Position, text::Text},
+    makepad_studio::{
+        code_editor::fold_set::FoldRange,
+        editor_state::{EditorState, SessionId},
+        makepad_collab_protocol::{unix_path::UnixPathBuf, TextFileId},
+        makepad_live_id::LiveId,
+        rust_editor::rust_tokenizer::token_cache::TokenCache,
+    },
+};
+
+fn fold_ranges(text: &str) -> Vec<(usize, usize)> {
+    TokenCache::new(&Text::from(text))
+        .fold_ranges()
+        .into_iter()
+        .map( | FoldRange {start_line, end_line} | (start_line, end_line))
+        .collect()
+}
+
+fn open(text: &str) -> (EditorState, SessionId) {
+    let mut state = EditorState::new();
+    let session_id = state.create_session(UnixPathBuf::from("test.rs"), &mut | _ | {});
+    state.handle_open_file_response(TextFileId(LiveId(1)), 0, Text::from(text), &mut | _ | {});
+    (state, session_id)
+}
+
+fn folded_lines(state: &EditorState, session_id: SessionId) -> Vec<usize> {
+    state.sessions[session_id].folds.lines().collect()
+}
+
+fn carets(state: &EditorState, session_id: SessionId) -> Vec<Position> {
+    state.sessions[session_id].cursors.iter().map( | cursor | cursor.head).collect()
+}
+
+fn position(line: usize, column: usize) -> Position {
+    Position {line, column}
+}
+
+const SOURCE: &str = "\
+use a;
+use b::{
+    c,
+};
+// one
+// two
+fn f()
+where T: X
+{
+    let x = (
+        1,
+        2
+    );
+}
+";
+
+#[test]
+fn fold_ranges_cover_blocks_items_and_runs() {
+    assert_eq!(fold_ranges(SOURCE), vec![
+        // the run of use declarations, including the multi-line one
+        (0, 4),
+        // the braces of the multi-line one
+        (1, 3),
+        // the run of comments
+        (4, 6),
+        // the fn item starts at its keyword rather than at its body
+        (6, 13),
+    ]);
+    // parentheses don't start a range, and neither do blocks that span fewer than three lines
+    assert_eq!(fold_ranges("fn f() {\n    g(\n        1,\n    );\n}\nif x {\n}\n"), vec![(0, 4)]);
+    // a fn pointer type inside a struct doesn't start an item
+    assert_eq!(fold_ranges("struct S {\n    f: fn(),\n}\nimpl S {\n    x\n}\n"), vec![(0, 2), (3, 5)]);
+}
+
+#[test]
+fn live_register_bodies_can_be_folded() {
+    assert_eq!(fold_ranges("live_register!{\n    A: {}\n}\n"), vec![(0, 2)]);
+    assert_eq!(fold_ranges("live_register!(\n    A: {}\n);\n"), vec![(0, 2)]);
+}
+
+#[test]
+fn folds_are_kept_through_edits() {
+    let (mut state, session_id) = open(SOURCE);
+    assert!(state.fold_line(session_id, 6));
+    assert!(!state.fold_line(session_id, 7));
+    assert_eq!(folded_lines(&state, session_id), vec![6]);
+
+    // inserting lines above the fold moves it down
+    state.move_cursors_to(session_id, position(0, 0), false);
+    state.insert_newline(session_id, &mut | _ | {});
+    assert_eq!(folded_lines(&state, session_id), vec![7]);
+
+    // typing at the end of the folded line keeps it folded
+    state.move_cursors_to(session_id, position(7, 6), false);
+    state.insert_text(session_id, Text::from(" "), &mut | _ | {});
+    assert_eq!(folded_lines(&state, session_id), vec![7]);
+
+    // the fold is dropped once its range is gone
+    state.move_cursors_to(session_id, position(7, 0), false);
+    state.move_cursors_to(session_id, position(7, 2), true);
+    state.insert_text(session_id, Text::from("x"), &mut | _ | {});
+    assert_eq!(folded_lines(&state, session_id), Vec::<usize>::new());
+}
+
+#[test]
+fn folding_moves_hidden_carets() {
+    let (mut state, session_id) = open(SOURCE);
+    state.move_cursors_to(session_id, position(10, 4), false);
+    state.fold_line(session_id, 6);
+    assert_eq!(carets(&state, session_id), vec![position(6, 6)]);
+
+    // moving down skips over the hidden lines
+    state.move_cursors_down(session_id, false);
+    assert_eq!(carets(&state, session_id), vec![position(13, 1)]);
+    state.move_cursors_up(session_id, false);
+    assert_eq!(carets(&state, session_id), vec![position(6, 1)]);
+
+    // moving a caret into a folded range unfolds it
+    state.move_cursors_to(session_id, position(8, 0), false);
+    assert_eq!(folded_lines(&state, session_id), Vec::<usize>::new());
+}
+
+#[test]
+fn all_ranges_can_be_folded_and_unfolded() {
+    let (mut state, session_id) = open(SOURCE);
+    state.fold_all(session_id);
+    assert_eq!(folded_lines(&state, session_id), vec![0, 1, 4, 6]);
+    let session = &state.sessions[session_id];
+    let document_inner = state.documents[session.document_id].inner.as_ref().unwrap();
+    let hidden_lines = session.folds.hidden_lines(&document_inner.fold_ranges, document_inner.text.line_count());
+    let visible_lines: Vec<usize> = (0..hidden_lines.len()).filter( | &line | !hidden_lines[line]).collect();
+    assert_eq!(visible_lines, vec![0, 4, 6, 13, 14]);
+
+    state.unfold_all(session_id);
+    assert_eq!(folded_lines(&state, session_id), Vec::<usize>::new());
+}
+
+#[test]
+fn folded_lines_can_be_set_before_the_document_is_loaded() {
+    let mut state = EditorState::new();
+    let session_id = state.create_session(UnixPathBuf::from("test.rs"), &mut | _ | {});
+    state.set_folded_lines(session_id, &[4, 5, 6]);
+    state.handle_open_file_response(TextFileId(LiveId(1)), 0, Text::from(SOURCE), &mut | _ | {});
+    // line 5 does not start a fold range
+    assert_eq!(folded_lines(&state, session_id), vec![4, 6]);
+}
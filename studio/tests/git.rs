@@ -0,0 +1,188 @@
+use {
+    makepad_studio::{
+        git_state::{blame_annotation, GitState},
+        makepad_collab_protocol::{
+            unix_path::{UnixPath, UnixPathBuf},
+            CollabRequest, CollabResponse, GitBlameLine, GitDiffHunk, GitDiffKind, GitFileStatus,
+            GitStatus,
+        },
+        makepad_collab_server::{git, CollabServer},
+    },
+    std::{env, fs, path::{Path, PathBuf}, process::Command},
+};
+
+fn hunk(kind: GitDiffKind, start_line: usize, end_line: usize) -> GitDiffHunk {
+    GitDiffHunk {kind, start_line, end_line}
+}
+
+// Runs git with the given `args` in the directory at `root`.
+fn run_git(root: &Path, args: &[&str]) {
+    let status = Command::new("git").args(args).current_dir(root).status().unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+// Creates a fresh git repository with the given files in a single commit.
+fn create_repository(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = env::temp_dir().join(format!("makepad_git_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    run_git(&root, &["init", "--quiet"]);
+    run_git(&root, &["config", "user.name", "Jane Doe"]);
+    run_git(&root, &["config", "user.email", "jane@example.com"]);
+    run_git(&root, &["add", "."]);
+    run_git(&root, &["commit", "--quiet", "-m", "Initial commit"]);
+    root
+}
+
+#[test]
+fn diff_lines_finds_added_modified_and_deleted_lines() {
+    assert_eq!(git::diff_lines(&["a", "b", "c"], &["a", "b", "c"]), vec![]);
+    assert_eq!(
+        git::diff_lines(&["a", "b", "c"], &["a", "x", "b", "c", "y"]),
+        vec![hunk(GitDiffKind::Added, 1, 2), hunk(GitDiffKind::Added, 4, 5)]
+    );
+    assert_eq!(
+        git::diff_lines(&["a", "b", "c", "d"], &["a", "B", "C", "d"]),
+        vec![hunk(GitDiffKind::Modified, 1, 3)]
+    );
+    // deleted lines are marked at the line after them
+    assert_eq!(
+        git::diff_lines(&["a", "b", "c", "d"], &["a", "d"]),
+        vec![hunk(GitDiffKind::Deleted, 1, 1)]
+    );
+    assert_eq!(git::diff_lines(&["a", "b"], &["a"]), vec![hunk(GitDiffKind::Deleted, 1, 1)]);
+    assert_eq!(git::diff_lines(&[], &["a", "b"]), vec![hunk(GitDiffKind::Added, 0, 2)]);
+}
+
+#[test]
+fn status_is_parsed_relative_to_the_workspace() {
+    let output = " M studio/src/app.rs\0A  studio/new.rs\0R  studio/b.rs\0studio/a.rs\0?? studio/tmp/x.rs\0UU studio/c.rs\0 D other/d.rs\0";
+    assert_eq!(git::parse_status(output, "studio/"), vec![
+        GitFileStatus {path: UnixPathBuf::from("src/app.rs"), status: GitStatus::Modified},
+        GitFileStatus {path: UnixPathBuf::from("new.rs"), status: GitStatus::Added},
+        GitFileStatus {path: UnixPathBuf::from("b.rs"), status: GitStatus::Renamed},
+        GitFileStatus {path: UnixPathBuf::from("tmp/x.rs"), status: GitStatus::Untracked},
+        GitFileStatus {path: UnixPathBuf::from("c.rs"), status: GitStatus::Conflicted},
+    ]);
+}
+
+#[test]
+fn blame_is_parsed() {
+    let output = "\
+1111111111111111111111111111111111111111 1 1 2
+author Jane Doe
+author-time 1647216000
+summary Fix the build
+filename a.rs
+\tfirst
+1111111111111111111111111111111111111111 2 2
+\tsecond
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-time 1647302400
+summary Version of a.rs from a.rs
+filename a.rs
+\tthird
+";
+    let lines = git::parse_blame(output);
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], lines[1]);
+    assert_eq!(lines[0], GitBlameLine {
+        commit: String::from("11111111"),
+        author: String::from("Jane Doe"),
+        time: 1647216000,
+        summary: String::from("Fix the build"),
+    });
+    assert_eq!(blame_annotation(&lines[0]), "Jane Doe, 2022-03-14 • Fix the build");
+    assert_eq!(blame_annotation(&lines[2]), "Not committed yet");
+}
+
+#[test]
+fn folders_have_the_status_of_their_files() {
+    let mut state = GitState::default();
+    state.set_statuses(vec![
+        GitFileStatus {path: UnixPathBuf::from("src/a.rs"), status: GitStatus::Untracked},
+        GitFileStatus {path: UnixPathBuf::from("src/b/c.rs"), status: GitStatus::Untracked},
+        GitFileStatus {path: UnixPathBuf::from("d.rs"), status: GitStatus::Modified},
+    ]);
+    assert_eq!(state.status(UnixPath::new("src/a.rs")), Some(GitStatus::Untracked));
+    assert_eq!(state.status(UnixPath::new("src/b")), Some(GitStatus::Untracked));
+    assert_eq!(state.status(UnixPath::new("src")), Some(GitStatus::Untracked));
+    // the root folder contains files with different statuses
+    assert_eq!(state.status(UnixPath::new("")), Some(GitStatus::Modified));
+    assert_eq!(state.status(UnixPath::new("e.rs")), None);
+    assert!(state.is_stale(UnixPath::new("e.rs"), &[hunk(GitDiffKind::Added, 0, 1)]));
+    assert!(state.is_stale(UnixPath::new("d.rs"), &[]));
+}
+
+#[test]
+fn collab_server_reports_status_diff_and_blame() {
+    let root = create_repository("server", &[
+        ("src/a.rs", "fn a() {}\nfn b() {}\nfn c() {}\n"),
+        ("src/b.rs", "fn d() {}\n"),
+    ]);
+    fs::write(root.join("src/a.rs"), "fn a() {}\nfn x() {}\nfn c() {}\nfn y() {}\n").unwrap();
+    fs::write(root.join("src/new.rs"), "fn z() {}\n").unwrap();
+    fs::remove_file(root.join("src/b.rs")).unwrap();
+
+    let mut server = CollabServer::new(&root);
+    let connection = server.connect(Box::new( | _ | {}));
+    match connection.handle_request(CollabRequest::GitStatus) {
+        CollabResponse::GitStatus(Ok(mut statuses)) => {
+            statuses.sort_by( | a, b | a.path.cmp(&b.path));
+            assert_eq!(statuses, vec![
+                GitFileStatus {path: UnixPathBuf::from("src/a.rs"), status: GitStatus::Modified},
+                GitFileStatus {path: UnixPathBuf::from("src/b.rs"), status: GitStatus::Deleted},
+                GitFileStatus {path: UnixPathBuf::from("src/new.rs"), status: GitStatus::Untracked},
+            ]);
+        }
+        response => panic!("unexpected response {:?}", response),
+    }
+    match connection.handle_request(CollabRequest::GitDiff(UnixPathBuf::from("src/a.rs"))) {
+        CollabResponse::GitDiff(_, Ok(hunks)) => assert_eq!(hunks, vec![
+            hunk(GitDiffKind::Modified, 1, 2),
+            hunk(GitDiffKind::Added, 3, 4),
+        ]),
+        response => panic!("unexpected response {:?}", response),
+    }
+    match connection.handle_request(CollabRequest::GitDiff(UnixPathBuf::from("src/new.rs"))) {
+        CollabResponse::GitDiff(_, Ok(hunks)) => assert_eq!(hunks, vec![hunk(GitDiffKind::Added, 0, 2)]),
+        response => panic!("unexpected response {:?}", response),
+    }
+    match connection.handle_request(CollabRequest::GitBlame(UnixPathBuf::from("src/a.rs"))) {
+        CollabResponse::GitBlame(_, Ok(lines)) => {
+            assert_eq!(lines.len(), 4);
+            assert_eq!(lines[0].author, "Jane Doe");
+            assert_eq!(lines[0].summary, "Initial commit");
+            // the second line has not been committed yet
+            assert_eq!(lines[1].commit, "00000000");
+        }
+        response => panic!("unexpected response {:?}", response),
+    }
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn diff_handles_output_that_is_not_utf8() {
+    let root = create_repository("latin1", &[("a.txt", "a\n")]);
+    fs::write(root.join("a.txt"), b"caf\xe9\nb\n").unwrap();
+    run_git(&root, &["commit", "--quiet", "-am", "Latin-1"]);
+    let hunks = git::diff(&root, Path::new("a.txt"), "caf\u{FFFD}\nc\n").unwrap();
+    assert_eq!(hunks, vec![hunk(GitDiffKind::Modified, 1, 2)]);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn diff_ignores_crlf_line_breaks_in_head() {
+    let root = create_repository("crlf", &[("a.txt", "a\r\nb\r\nc\r\n")]);
+    assert_eq!(git::diff(&root, Path::new("a.txt"), "a\nb\nc\n").unwrap(), vec![]);
+    assert_eq!(
+        git::diff(&root, Path::new("a.txt"), "a\nx\nc\n").unwrap(),
+        vec![hunk(GitDiffKind::Modified, 1, 2)]
+    );
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,158 @@
+use {
+    makepad_editor_core::text::Text,
+    makepad_studio::{
+        languages::LanguageKind,
+        makepad_collab_protocol::unix_path::UnixPath,
+        makepad_live_id::*,
+        rust_editor::rust_tokenizer::{
+            full_token::{Delim, FullToken},
+            token_cache::TokenCache,
+        },
+    },
+};
+
+// Returns the text and the token of each token on each line of `text`, leaving out whitespace.
+fn tokens(language: LanguageKind, text: &str) -> Vec<Vec<(String, FullToken)>> {
+    let token_cache = TokenCache::with_language(language, &Text::from(text));
+    text.split('\n').zip(token_cache.iter()).map( | (line, cache_line) | {
+        let chars: Vec<char> = line.chars().collect();
+        let mut start = 0;
+        let mut tokens = Vec::new();
+        for token in cache_line.tokens() {
+            let text: String = chars[start..start + token.len].iter().collect();
+            start += token.len;
+            if !token.is_whitespace() {
+                tokens.push((text, token.token));
+            }
+        }
+        assert_eq!(start, chars.len());
+        tokens
+    }).collect()
+}
+
+fn token(text: &str, token: FullToken) -> (String, FullToken) {
+    (text.to_string(), token)
+}
+
+fn punct(text: &str) -> (String, FullToken) {
+    token(text, FullToken::Punct(LiveId::from_str(text).unwrap()))
+}
+
+#[test]
+fn live_register_bodies_are_tokenized_as_live() {
+    let lines = tokens(LanguageKind::Rust, "live_register!{\n    A: {color: #f00, image: d\"a.png\"}\n}\nlet c = d\"a.png\";\n");
+    assert_eq!(lines[1], vec![
+        token("A", FullToken::Ident(id!(A))),
+        token(":", FullToken::Punct(id!(:))),
+        token("{", FullToken::Open(Delim::Brace)),
+        token("color", FullToken::Ident(id!(color))),
+        token(":", FullToken::Punct(id!(:))),
+        token("#f00", FullToken::Color(0xff0000ff)),
+        token(",", FullToken::Punct(id!(,))),
+        token("image", FullToken::Ident(id!(image))),
+        token(":", FullToken::Punct(id!(:))),
+        // dependencies only exist in the live DSL
+        token("d\"a.png\"", FullToken::String),
+        token("}", FullToken::Close(Delim::Brace)),
+    ]);
+    assert_eq!(lines[2], vec![token("}", FullToken::Close(Delim::Brace))]);
+    // after the closing delimiter of the macro, the text is tokenized as Rust again
+    assert_eq!(lines[3][3], token("d", FullToken::Ident(id!(d))));
+}
+
+#[test]
+fn live_files_are_tokenized_as_live() {
+    let lines = tokens(LanguageKind::Live, "A: {\n    b: #0f0 /* c\n    */ d: 1.5\n}");
+    assert_eq!(lines[1][2], token("#0f0", FullToken::Color(0x00ff00ff)));
+    assert_eq!(lines[1][3], token("/* c", FullToken::Comment));
+    assert_eq!(lines[2][0], token("    */", FullToken::Comment));
+    assert_eq!(lines[2][3], token("1.5", FullToken::Float(1.5)));
+}
+
+#[test]
+fn toml_is_tokenized() {
+    let lines = tokens(LanguageKind::Toml, "[package]\nname = \"studio\" # c\nversion = 1_000\ndescription = \"\"\"\nmulti\n\"\"\"\nx = [true]");
+    assert_eq!(lines[0], vec![token("[package]", FullToken::Heading)]);
+    assert_eq!(lines[1], vec![
+        token("name", FullToken::Ident(id!(name))),
+        token("=", FullToken::Punct(id!(=))),
+        token("\"studio\"", FullToken::String),
+        token("# c", FullToken::Comment),
+    ]);
+    assert_eq!(lines[2][2], token("1_000", FullToken::Int(1000)));
+    // multi-line strings are continued on the next lines
+    assert_eq!(lines[3][2], token("\"\"\"", FullToken::String));
+    assert_eq!(lines[4], vec![token("multi", FullToken::String)]);
+    assert_eq!(lines[5], vec![token("\"\"\"", FullToken::String)]);
+    assert_eq!(lines[6][2], token("[", FullToken::Open(Delim::Bracket)));
+    assert_eq!(lines[6][3], token("true", FullToken::Bool(true)));
+}
+
+#[test]
+fn json_is_tokenized() {
+    let lines = tokens(LanguageKind::Json, "{\"a\": [-1, 2.5e3, null]} /* x\ny */ false");
+    assert_eq!(lines[0], vec![
+        token("{", FullToken::Open(Delim::Brace)),
+        token("\"a\"", FullToken::String),
+        token(":", FullToken::Punct(id!(:))),
+        token("[", FullToken::Open(Delim::Bracket)),
+        token("-1", FullToken::Int(-1)),
+        token(",", FullToken::Punct(id!(,))),
+        token("2.5e3", FullToken::Float(2500.0)),
+        token(",", FullToken::Punct(id!(,))),
+        token("null", FullToken::Ident(id!(null))),
+        token("]", FullToken::Close(Delim::Bracket)),
+        token("}", FullToken::Close(Delim::Brace)),
+        token("/* x", FullToken::Comment),
+    ]);
+    assert_eq!(lines[1], vec![token("y */", FullToken::Comment), token("false", FullToken::Bool(false))]);
+}
+
+#[test]
+fn markdown_is_tokenized() {
+    let lines = tokens(LanguageKind::Markdown, "# Title\nsome **strong** `code`\n```rust\n# not a heading\n```\n- item\n1. first");
+    assert_eq!(lines[0], vec![token("# Title", FullToken::Heading)]);
+    assert_eq!(lines[1], vec![
+        token("some", FullToken::Text),
+        token("**strong**", FullToken::Emphasis),
+        token("`code`", FullToken::String),
+    ]);
+    // fenced code is tokenized as a string until the closing fence
+    assert_eq!(lines[2], vec![punct("```"), token("rust", FullToken::String)]);
+    assert_eq!(lines[3], vec![token("# not a heading", FullToken::String)]);
+    assert_eq!(lines[4], vec![punct("```")]);
+    assert_eq!(lines[5], vec![token("-", FullToken::Punct(id!(-))), token("item", FullToken::Text)]);
+    assert_eq!(lines[6][0], punct("1."));
+}
+
+#[test]
+fn glsl_is_tokenized() {
+    let lines = tokens(LanguageKind::Glsl, "vec4 c = vec4(1.0f); /* a\nb */ x <<= 2u;");
+    assert_eq!(lines[0], vec![
+        token("vec4", FullToken::Ident(id!(vec4))),
+        token("c", FullToken::Ident(id!(c))),
+        token("=", FullToken::Punct(id!(=))),
+        token("vec4", FullToken::Ident(id!(vec4))),
+        token("(", FullToken::Open(Delim::Paren)),
+        token("1.0f", FullToken::Float(1.0)),
+        token(")", FullToken::Close(Delim::Paren)),
+        token(";", FullToken::Punct(id!(;))),
+        token("/* a", FullToken::Comment),
+    ]);
+    assert_eq!(lines[1], vec![
+        token("b */", FullToken::Comment),
+        token("x", FullToken::Ident(id!(x))),
+        token("<<=", FullToken::Punct(id!(<<=))),
+        token("2u", FullToken::Int(2)),
+        token(";", FullToken::Punct(id!(;))),
+    ]);
+}
+
+#[test]
+fn languages_are_picked_by_extension() {
+    let language = | path: &str | LanguageKind::from_path(UnixPath::new(path));
+    assert_eq!(language("studio/src/app.rs"), LanguageKind::Rust);
+    assert_eq!(language("Cargo.toml"), LanguageKind::Toml);
+    assert_eq!(language("docs/README.md"), LanguageKind::Markdown);
+    assert_eq!(language("a.b/c"), LanguageKind::PlainText);
+}
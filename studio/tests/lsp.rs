@@ -0,0 +1,230 @@
+use {
+    makepad_editor_core::{
+        delta::{self, Delta},
+        position::Position,
+        size::Size,
+        text::Text,
+    },
+    makepad_studio::lsp::{
+        json::JsonValue,
+        lsp_connection::LspConnection,
+        lsp_protocol::{
+            self,
+            LspCompletionItem,
+            LspContentChange,
+            LspDiagnosticSeverity,
+            LspLocation,
+            LspMessage,
+            LspPosition,
+            LspPublishDiagnostics,
+        },
+    },
+    std::{env, time::Duration},
+};
+
+const URI: &str = "file:///test/main.rs";
+
+fn start_server() -> LspConnection {
+    let mut connection = LspConnection::start(
+        env!("CARGO_BIN_EXE_lsp_test_server"),
+        &[],
+        env::temp_dir(),
+        Box::new( || {}),
+    ).unwrap();
+    request(&mut connection, "initialize", JsonValue::object([]));
+    connection.send_notification("initialized", JsonValue::object([]));
+    connection
+}
+
+// Waits for the first message for which `f` returns `Some`, skipping any other messages.
+fn wait_for<T>(connection: &LspConnection, mut f: impl FnMut(LspMessage) -> Option<T>) -> T {
+    loop {
+        let message = connection
+            .message_receiver()
+            .recv_timeout(Duration::from_secs(10))
+            .expect("language server did not respond");
+        if let Some(value) = f(message) {
+            return value;
+        }
+    }
+}
+
+fn request(connection: &mut LspConnection, method: &str, params: JsonValue) -> JsonValue {
+    let id = connection.send_request(method, params);
+    wait_for(connection, | message | match message {
+        LspMessage::Response {id: response_id, result} if response_id.as_u64() == Some(id) => {
+            Some(result.unwrap())
+        }
+        _ => None,
+    })
+}
+
+fn wait_for_diagnostics(connection: &LspConnection) -> LspPublishDiagnostics {
+    wait_for(connection, | message | match message {
+        LspMessage::Notification {method, params} if method == "textDocument/publishDiagnostics" => {
+            LspPublishDiagnostics::from_json(&params)
+        }
+        _ => None,
+    })
+}
+
+fn open(connection: &mut LspConnection, text: &Text) {
+    connection.send_notification("textDocument/didOpen", JsonValue::object([
+        ("textDocument", JsonValue::object([
+            ("uri", URI.into()),
+            ("languageId", "rust".into()),
+            ("version", 0u64.into()),
+            ("text", text.to_string().into()),
+        ])),
+    ]));
+}
+
+fn change(connection: &mut LspConnection, version: u64, changes: Vec<LspContentChange>) {
+    connection.send_notification("textDocument/didChange", JsonValue::object([
+        ("textDocument", JsonValue::object([("uri", URI.into()), ("version", version.into())])),
+        ("contentChanges", changes.iter().map( | change | change.to_json()).collect::<Vec<_>>().into()),
+    ]));
+}
+
+fn position_params(text: &Text, position: Position) -> JsonValue {
+    JsonValue::object([
+        ("textDocument", JsonValue::object([("uri", URI.into())])),
+        ("position", LspPosition::from_position(text, position).to_json()),
+    ])
+}
+
+fn server_text(connection: &mut LspConnection) -> String {
+    let result = request(connection, "test/text", JsonValue::object([
+        ("textDocument", JsonValue::object([("uri", URI.into())])),
+    ]));
+    result.as_str().unwrap().to_string()
+}
+
+fn size(line: usize, column: usize) -> Size {
+    Size {line, column}
+}
+
+#[test]
+fn json_round_trip() {
+    let json = JsonValue::object([
+        ("string", "a \"quoted\"\nline".into()),
+        ("number", 42u64.into()),
+        ("array", vec![true.into(), JsonValue::Null].into()),
+        ("object", JsonValue::object([("empty", JsonValue::object([]))])),
+    ]);
+    let message = LspMessage::Notification {method: "test".to_string(), params: json};
+    let mut bytes = Vec::new();
+    makepad_studio::lsp::lsp_connection::write_message(&mut bytes, &message.to_json()).unwrap();
+    let json = makepad_studio::lsp::lsp_connection::read_message(&mut bytes.as_slice())
+        .unwrap()
+        .unwrap();
+    assert_eq!(LspMessage::from_json(&json), Some(message));
+}
+
+#[test]
+fn positions_count_utf16_code_units() {
+    let text = Text::from("a😀b\néc");
+    let position = Position {line: 0, column: 2};
+    let lsp_position = LspPosition::from_position(&text, position);
+    assert_eq!(lsp_position, LspPosition {line: 0, character: 3});
+    assert_eq!(lsp_position.to_position(&text), position);
+    // a position in the middle of a surrogate pair moves back to the start of the char
+    assert_eq!(LspPosition {line: 0, character: 2}.to_position(&text), Position {line: 0, column: 1});
+    assert_eq!(LspPosition {line: 1, character: 9}.to_position(&text), Position {line: 1, column: 2});
+}
+
+#[test]
+fn uri_round_trip() {
+    let path = std::path::Path::new("/some dir/naïve.rs");
+    let uri = lsp_protocol::path_to_uri(path);
+    assert_eq!(uri, "file:///some%20dir/na%C3%AFve.rs");
+    assert_eq!(lsp_protocol::uri_to_path(&uri).as_deref(), Some(path));
+}
+
+#[test]
+fn incremental_changes_keep_server_in_sync() {
+    let mut connection = start_server();
+    let mut text = Text::from("fn main() {\n    let x = \"😀\";\n}\n");
+    open(&mut connection, &text);
+
+    let edits: Vec<Box<dyn Fn(&Text) -> Delta>> = vec![
+        // insert after the emoji, which is two UTF-16 code units wide
+        Box::new( | _ | {
+            let mut builder = delta::Builder::new();
+            builder.retain(size(1, 14));
+            builder.insert(Text::from(" wide"));
+            builder.build()
+        }),
+        // several edits in a single delta, including a multiline replacement
+        Box::new( | _ | {
+            let mut builder = delta::Builder::new();
+            builder.retain(size(0, 3));
+            builder.delete(size(0, 4));
+            builder.insert(Text::from("start"));
+            builder.retain(size(1, 0));
+            builder.delete(size(1, 1));
+            builder.insert(Text::from("    println!(\"é\");\n}"));
+            builder.build()
+        }),
+        // delete everything
+        Box::new( | text | {
+            let mut builder = delta::Builder::new();
+            builder.delete(text.len());
+            builder.build()
+        }),
+    ];
+    for (version, edit) in edits.into_iter().enumerate() {
+        let delta = edit(&text);
+        let changes = LspContentChange::from_delta(&text, &delta);
+        text.apply_delta(delta);
+        change(&mut connection, version as u64 + 1, changes);
+        assert_eq!(server_text(&mut connection), text.to_string());
+    }
+}
+
+#[test]
+fn diagnostics_are_published() {
+    let mut connection = start_server();
+    let text = Text::from("fn main() {\n    // TODO\n}\n");
+    open(&mut connection, &text);
+    let diagnostics = wait_for_diagnostics(&connection);
+    assert_eq!(diagnostics.uri, URI);
+    assert_eq!(diagnostics.diagnostics.len(), 1);
+    let diagnostic = &diagnostics.diagnostics[0];
+    assert_eq!(diagnostic.severity, LspDiagnosticSeverity::Warning);
+    assert_eq!(diagnostic.range.start.to_position(&text), Position {line: 1, column: 7});
+    assert_eq!(diagnostic.range.end.to_position(&text), Position {line: 1, column: 11});
+
+    change(&mut connection, 1, vec![LspContentChange {
+        range: diagnostic.range,
+        text: "done".to_string(),
+    }]);
+    assert!(wait_for_diagnostics(&connection).diagnostics.is_empty());
+}
+
+#[test]
+fn hover_definition_and_completion() {
+    let mut connection = start_server();
+    let text = Text::from("fn foo() {}\nfn main() {\n    foo();\n    pri\n}\n");
+    open(&mut connection, &text);
+
+    let hover = request(&mut connection, "textDocument/hover", position_params(&text, Position {line: 2, column: 5}));
+    assert_eq!(lsp_protocol::hover_contents(&hover).as_deref(), Some("identifier `foo`"));
+
+    let definition = request(&mut connection, "textDocument/definition", position_params(&text, Position {line: 2, column: 5}));
+    let location = LspLocation::from_definition_result(&definition).unwrap();
+    assert_eq!(location.uri, URI);
+    assert_eq!(location.range.start.to_position(&text), Position {line: 0, column: 3});
+
+    let completion = request(&mut connection, "textDocument/completion", position_params(&text, Position {line: 3, column: 7}));
+    let items = LspCompletionItem::from_completion_result(&completion);
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].label, "println!");
+    assert_eq!(items[0].insert_text, "println!(\"\")");
+    assert_eq!(items[1].insert_text, "print!");
+    assert_eq!(items[1].detail.as_deref(), Some("macro"));
+
+    // there is nothing to hover over on an empty line
+    let hover = request(&mut connection, "textDocument/hover", position_params(&text, Position {line: 5, column: 0}));
+    assert!(hover.is_null());
+}
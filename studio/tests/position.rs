@@ -0,0 +1,36 @@
+use makepad_editor_core::{delta::{self, Delta}, position::Position, size::Size, text::Text};
+
+fn position(line: usize, column: usize) -> Position {
+    Position {line, column}
+}
+
+fn insert_at(at: Position, text: &str) -> Delta {
+    let mut builder = delta::Builder::new();
+    builder.retain(at - Position::origin());
+    builder.insert(Text::from(text));
+    builder.build()
+}
+
+#[test]
+fn positions_stay_in_front_of_text_inserted_at_them() {
+    let delta = insert_at(position(1, 2), "ab\nc");
+    assert_eq!(position(1, 2).apply_delta(&delta), position(1, 2));
+    assert_eq!(position(1, 3).apply_delta(&delta), position(2, 2));
+    assert_eq!(position(0, 5).apply_delta(&delta), position(0, 5));
+    // the origin behaves like any other position
+    let delta = insert_at(Position::origin(), "ab\nc");
+    assert_eq!(Position::origin().apply_delta(&delta), Position::origin());
+    assert_eq!(position(0, 1).apply_delta(&delta), position(1, 2));
+}
+
+#[test]
+fn positions_in_deleted_text_move_to_its_start() {
+    let mut builder = delta::Builder::new();
+    builder.retain(Size {line: 0, column: 1});
+    builder.delete(Size {line: 1, column: 1});
+    builder.insert(Text::from("x"));
+    let delta = builder.build();
+    assert_eq!(position(0, 3).apply_delta(&delta), position(0, 1));
+    assert_eq!(position(1, 1).apply_delta(&delta), position(0, 1));
+    assert_eq!(position(1, 4).apply_delta(&delta), position(0, 5));
+}
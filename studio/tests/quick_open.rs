@@ -0,0 +1,73 @@
+use makepad_studio::{
+    makepad_collab_protocol::unix_path::UnixPathBuf,
+    makepad_platform::{Command, Menu},
+    makepad_live_id::LiveId,
+    quick_open::{fuzzy_score, path_score, rank_commands, rank_paths, Query},
+};
+
+fn paths(paths: &[&str]) -> Vec<UnixPathBuf> {
+    paths.iter().map( | path | UnixPathBuf::from(*path)).collect()
+}
+
+#[test]
+fn queries_are_parsed() {
+    assert_eq!(Query::parse("main"), Query::Files {pattern: "main", line: None});
+    assert_eq!(Query::parse("main.rs:12"), Query::Files {pattern: "main.rs", line: Some(12)});
+    assert_eq!(Query::parse(":12"), Query::GoToLine(Some(12)));
+    assert_eq!(Query::parse(":"), Query::GoToLine(None));
+    assert_eq!(Query::parse("> cargo"), Query::Commands {pattern: "cargo"});
+    assert_eq!(Query::parse("? a:1 in:*.rs"), Query::Search("a:1 in:*.rs"));
+    // replacement text is kept as is, including surrounding whitespace
+    assert_eq!(Query::parse("= b "), Query::Replace(" b "));
+    // a colon that is not followed by a line number is part of the pattern
+    assert_eq!(Query::parse("a:b"), Query::Files {pattern: "a:b", line: None});
+}
+
+#[test]
+fn fuzzy_score_requires_characters_in_order() {
+    assert!(fuzzy_score("edst", "editor_state.rs").is_some());
+    assert!(fuzzy_score("EDST", "editor_state.rs").is_some());
+    assert!(fuzzy_score("tsde", "editor_state.rs").is_none());
+    assert_eq!(fuzzy_score("", "anything"), Some(0));
+}
+
+#[test]
+fn fuzzy_score_prefers_word_starts_and_consecutive_matches() {
+    assert!(fuzzy_score("es", "editor_state") > fuzzy_score("es", "editors"));
+    assert!(fuzzy_score("dit", "editor") > fuzzy_score("dit", "drift"));
+    assert!(path_score("tree", "studio/src/tree.rs") > path_score("tree", "tree/src/lib.rs"));
+}
+
+#[test]
+fn paths_are_ranked_by_match_quality() {
+    let paths = paths(&["src/app_state.rs", "src/app.rs", "src/editor_state.rs"]);
+    let ranked = rank_paths("app", &paths, &[]);
+    // both app files match equally well, so the shorter one wins
+    assert_eq!(ranked, vec![1, 0]);
+    assert_eq!(rank_paths("edst", &paths, &[]), vec![2]);
+}
+
+#[test]
+fn recent_paths_are_ranked_first() {
+    let paths = paths(&["src/a.rs", "src/b.rs", "src/c.rs"]);
+    let recent_paths = vec![UnixPathBuf::from("src/c.rs"), UnixPathBuf::from("src/a.rs")];
+    assert_eq!(rank_paths("", &paths, &recent_paths), vec![2, 0, 1]);
+    assert_eq!(rank_paths("rs", &paths, &recent_paths), vec![2, 0, 1]);
+}
+
+#[test]
+fn commands_are_collected_from_menu() {
+    let menu = Menu::main(vec![
+        Menu::sub("Build", vec![Menu::item("Cargo Check", Command(LiveId(1)))]),
+        Menu::sub("Edit", vec![
+            Menu::item("Undo", Command(LiveId(2))),
+            Menu::line(),
+            Menu::item("Redo", Command(LiveId(3))),
+        ]),
+    ]);
+    let commands = menu.commands();
+    let names: Vec<&str> = commands.iter().map( | (name, _) | *name).collect();
+    assert_eq!(names, vec!["Cargo Check", "Undo", "Redo"]);
+    assert_eq!(rank_commands("do", &names), vec![1, 2]);
+    assert_eq!(rank_commands("check", &names), vec![0]);
+}
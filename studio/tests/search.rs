@@ -0,0 +1,232 @@
+use {
+    makepad_editor_core::{position::Position, range::Range, text::Text},
+    makepad_studio::{
+        editor_state::EditorState,
+        makepad_collab_protocol::{
+            unix_path::UnixPathBuf, CollabNotification, CollabRequest, CollabResponse, SearchId,
+            SearchResult, TextFileId,
+        },
+        makepad_collab_server::{
+            regex::Regex,
+            search::{path_matches_glob, GitIgnore},
+            CollabServer,
+        },
+        makepad_live_id::LiveId,
+        search_state::{SearchOptions, SearchState},
+    },
+    std::{env, fs, path::{Path, PathBuf}, sync::mpsc, time::Duration},
+};
+
+fn find_all(pattern: &str, case_sensitive: bool, text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    Regex::new(pattern, case_sensitive).unwrap().find_all(&chars)
+}
+
+fn range(line: usize, start: usize, end: usize) -> Range {
+    Range {
+        start: Position {line, column: start},
+        end: Position {line, column: end},
+    }
+}
+
+// Creates a fresh directory with the given files for a collab server to serve.
+fn create_tree(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = env::temp_dir().join(format!("makepad_search_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    root
+}
+
+// Runs a search on a collab server for the given tree, and returns its results.
+fn search(root: &Path, query: &str, regex: bool, include_globs: &[&str], exclude_globs: &[&str]) -> Vec<SearchResult> {
+    let (sender, receiver) = mpsc::channel();
+    let mut server = CollabServer::new(root);
+    let connection = server.connect(Box::new(move | notification | sender.send(notification).unwrap()));
+    let search_id = SearchId(LiveId(1));
+    let response = connection.handle_request(CollabRequest::SearchWorkspace {
+        search_id,
+        query: String::from(query),
+        regex,
+        case_sensitive: false,
+        include_globs: include_globs.iter().map( | glob | String::from(*glob)).collect(),
+        exclude_globs: exclude_globs.iter().map( | glob | String::from(*glob)).collect(),
+    });
+    assert!(matches!(response, CollabResponse::SearchWorkspace(Ok(_))));
+    let mut results = Vec::new();
+    loop {
+        match receiver.recv_timeout(Duration::from_secs(10)).expect("search did not finish") {
+            CollabNotification::SearchResult(_, result) => results.push(result),
+            CollabNotification::SearchFinished(_) => return results,
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn regex_finds_non_overlapping_matches() {
+    assert_eq!(find_all("a+", true, "caaab a"), vec![(1, 4), (6, 7)]);
+    assert_eq!(find_all("fn\\s+\\w+", true, "pub fn main() {}"), vec![(4, 11)]);
+    assert_eq!(find_all("\\bis\\b", true, "this is it"), vec![(5, 7)]);
+    assert_eq!(find_all("^a|b$", true, "aab"), vec![(0, 1), (2, 3)]);
+    assert_eq!(find_all("(?:ab)+?", true, "ababab"), vec![(0, 2), (2, 4), (4, 6)]);
+    assert_eq!(find_all("[a-c]{2}", true, "abcd"), vec![(0, 2)]);
+    assert_eq!(find_all("HELLO", false, "hello Hello"), vec![(0, 5), (6, 11)]);
+    // empty matches are never returned
+    assert_eq!(find_all("x*", true, "abc"), vec![]);
+    assert!(Regex::new("(a", true).is_err());
+    assert!(Regex::new("*a", true).is_err());
+    // a brace that does not start a repetition is a literal
+    assert_eq!(find_all("a{2", true, "aa{2"), vec![(1, 4)]);
+}
+
+#[test]
+fn globs_match_file_names_or_whole_paths() {
+    assert!(path_matches_glob("*.rs", "studio/src/lib.rs"));
+    assert!(!path_matches_glob("*.rs", "studio/src/lib.rsx"));
+    assert!(path_matches_glob("studio/*/lib.rs", "studio/src/lib.rs"));
+    assert!(!path_matches_glob("studio/*.rs", "studio/src/lib.rs"));
+    assert!(path_matches_glob("studio/**/*.rs", "studio/src/lib.rs"));
+    assert!(path_matches_glob("**/lib.rs", "lib.rs"));
+    assert!(path_matches_glob("*.{rs,toml}", "Cargo.toml"));
+    assert!(path_matches_glob("lib.r?", "lib.rs"));
+    assert!(path_matches_glob("[!m]*.rs", "lib.rs"));
+    assert!(!path_matches_glob("[!l]*.rs", "lib.rs"));
+}
+
+#[test]
+fn gitignore_rules_apply_relative_to_their_directory() {
+    let git_ignore = GitIgnore::parse("studio", "*.log\n/build/\n!keep.log\n# comment\n");
+    assert_eq!(git_ignore.is_ignored("studio/out.log", false), Some(true));
+    assert_eq!(git_ignore.is_ignored("studio/a/out.log", false), Some(true));
+    assert_eq!(git_ignore.is_ignored("studio/keep.log", false), Some(false));
+    assert_eq!(git_ignore.is_ignored("studio/build", true), Some(true));
+    // directory-only rules don't apply to files
+    assert_eq!(git_ignore.is_ignored("studio/build", false), None);
+    // rules with a slash are anchored to the directory of the .gitignore file
+    assert_eq!(git_ignore.is_ignored("studio/a/build", true), None);
+    assert_eq!(git_ignore.is_ignored("other/out.log", false), None);
+}
+
+#[test]
+fn search_options_are_parsed() {
+    assert_eq!(SearchOptions::parse("  foo bar "), SearchOptions {
+        query: String::from("foo bar"),
+        ..SearchOptions::default()
+    });
+    assert_eq!(SearchOptions::parse("fn \\w+ in:*.rs not:target/** +regex +case"), SearchOptions {
+        query: String::from("fn \\w+"),
+        regex: true,
+        case_sensitive: true,
+        include_globs: vec![String::from("*.rs")],
+        exclude_globs: vec![String::from("target/**")],
+    });
+}
+
+#[test]
+fn search_honours_gitignore_and_globs() {
+    let root = create_tree("globs", &[
+        (".gitignore", "ignored/\n"),
+        ("ignored/a.rs", "needle"),
+        ("src/a.rs", "let needle = 1;\nno match\nneedle needle"),
+        ("src/b.txt", "Needle"),
+    ]);
+    let results = search(&root, "needle", false, &[], &[]);
+    let found: Vec<(String, Range)> = results.iter().map( | result | {
+        (result.path.as_unix_str().to_string_lossy().into_owned(), result.range)
+    }).collect();
+    assert_eq!(found, vec![
+        (String::from("src/a.rs"), range(0, 4, 10)),
+        (String::from("src/a.rs"), range(2, 0, 6)),
+        (String::from("src/a.rs"), range(2, 7, 13)),
+        (String::from("src/b.txt"), range(0, 0, 6)),
+    ]);
+    assert_eq!(results[0].preview, "let needle = 1;");
+
+    assert_eq!(search(&root, "needle", false, &["*.txt"], &[]).len(), 1);
+    assert_eq!(search(&root, "needle", false, &[], &["*.txt"]).len(), 3);
+    assert_eq!(search(&root, "needle\\s+n", true, &[], &[]).len(), 1);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn search_splits_lines_like_text() {
+    let root = create_tree("line_breaks", &[("a.rs", "needle\rx needle\r\nneedle\n\rneedle")]);
+    let results = search(&root, "needle", false, &[], &[]);
+    let text = Text::from("needle\rx needle\r\nneedle\n\rneedle");
+    for result in &results {
+        let line: String = text.line(result.range.start.line).chars().collect();
+        assert_eq!(result.preview, line);
+    }
+    assert_eq!(results.iter().map( | result | result.range).collect::<Vec<_>>(), vec![
+        range(0, 0, 6),
+        range(1, 2, 8),
+        range(2, 0, 6),
+        range(4, 0, 6),
+    ]);
+    assert_eq!(results[1].preview, "x needle");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn invalid_regex_is_reported() {
+    let root = create_tree("invalid", &[("a.rs", "a")]);
+    let mut server = CollabServer::new(root.clone());
+    let connection = server.connect(Box::new( | _ | {}));
+    let response = connection.handle_request(CollabRequest::SearchWorkspace {
+        search_id: SearchId(LiveId(1)),
+        query: String::from("(a"),
+        regex: true,
+        case_sensitive: false,
+        include_globs: Vec::new(),
+        exclude_globs: Vec::new(),
+    });
+    let mut state = SearchState::default();
+    assert!(state.handle_response(&response));
+    assert!(state.message.unwrap().starts_with("Invalid regex"));
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn search_results_are_replaced_through_edits() {
+    let mut state = SearchState::default();
+    let mut requests = Vec::new();
+    state.start(SearchOptions::parse("ab"), &mut | request | requests.push(request));
+    let search_id = state.search_id.unwrap();
+    for (line, start) in [(0, 0), (0, 3), (1, 1)].iter().copied() {
+        state.handle_notification(&CollabNotification::SearchResult(search_id, SearchResult {
+            path: UnixPathBuf::from("test.rs"),
+            range: range(line, start, start + 2),
+            preview: String::from(if line == 0 {"ab ab"} else {"xab"}),
+        }));
+    }
+    // results for other searches are ignored
+    state.handle_notification(&CollabNotification::SearchResult(SearchId(LiveId(0)), SearchResult {
+        path: UnixPathBuf::from("other.rs"),
+        range: range(0, 0, 2),
+        preview: String::from("ab"),
+    }));
+    state.handle_notification(&CollabNotification::SearchFinished(search_id));
+    assert!(!state.is_running);
+
+    let matches_by_path = state.take_matches_by_path();
+    assert_eq!(matches_by_path.len(), 1);
+    let ranges = &matches_by_path[&UnixPathBuf::from("test.rs")];
+
+    let mut editor_state = EditorState::new();
+    let session_id = editor_state.create_session(UnixPathBuf::from("test.rs"), &mut | _ | {});
+    // the match on the second line has been edited since the search ran, so it is skipped
+    editor_state.handle_open_file_response(TextFileId(LiveId(1)), 0, Text::from("ab ab\nxaX"), &mut | _ | {});
+    let mut requests = Vec::new();
+    let count = editor_state.replace_ranges(session_id, ranges, &Text::from("c\nd"), &mut | request | requests.push(request));
+    assert_eq!(count, 2);
+    let session = &editor_state.sessions[session_id];
+    let text = &editor_state.documents[session.document_id].inner.as_ref().unwrap().text;
+    assert_eq!(text.to_string(), "c\nd c\nd\nxaX");
+    // the replacement is a single edit, sent to the collab server as one delta
+    assert_eq!(requests.len(), 1);
+    assert!(matches!(requests[0], CollabRequest::ApplyDelta(..)));
+}
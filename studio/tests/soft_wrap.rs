@@ -0,0 +1,61 @@
+use {
+    makepad_editor_core::text::Text,
+    makepad_studio::code_editor::{
+        code_editor_impl::LineLayout,
+        line_cursor::wrap_columns,
+    },
+};
+
+fn wraps(line: &str, max_columns: usize) -> Vec<usize> {
+    wrap_columns(Text::from(line).line(0), max_columns)
+}
+
+fn line_layout(wraps: Vec<usize>) -> LineLayout {
+    LineLayout {
+        start_y: 0.0,
+        text_height: 10.0 * (wraps.len() + 1) as f32,
+        widget_height: 0.0,
+        total_height: 10.0 * (wraps.len() + 1) as f32,
+        font_scale: 1.0,
+        is_hidden: false,
+        wraps,
+        zoom_out: 0.0,
+        zoom_column: 0,
+        zoom_displace: 0.0,
+    }
+}
+
+#[test]
+fn lines_are_wrapped_at_word_starts() {
+    // lines that fit are never wrapped
+    assert_eq!(wraps("let x = 1;", 10), Vec::<usize>::new());
+    assert_eq!(wraps("let value = other_value;", 12), vec![12]);
+    assert_eq!(wraps("aaa bbb ccc ddd", 8), vec![8]);
+    // whitespace hangs past the end of a row instead of starting the next one
+    assert_eq!(wraps("aaa     bbb", 4), vec![8]);
+    // the indentation of a line is kept on its first row
+    assert_eq!(wraps("    foo bar", 8), vec![8]);
+}
+
+#[test]
+fn long_words_are_broken() {
+    assert_eq!(wraps("abcdefghij", 4), vec![4, 8]);
+    assert_eq!(wraps("ab cdefghij", 4), vec![3, 7]);
+    // a zero width is treated as a width of one column
+    assert_eq!(wraps("abc", 0), vec![1, 2]);
+}
+
+#[test]
+fn columns_are_mapped_to_rows() {
+    let layout = line_layout(vec![4, 8]);
+    assert_eq!(layout.row_count(), 3);
+    assert_eq!(layout.row_height(), 10.0);
+    assert_eq!(layout.row(0), 0);
+    assert_eq!(layout.row(3), 0);
+    // a column at which a row starts is on that row
+    assert_eq!(layout.row(4), 1);
+    assert_eq!(layout.row(9), 2);
+    assert_eq!(layout.row_start(1), 4);
+    assert_eq!(layout.row_end(1), Some(8));
+    assert_eq!(layout.row_end(2), None);
+}
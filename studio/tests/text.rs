@@ -0,0 +1,53 @@
+use makepad_editor_core::{delta, position::Position, range::Range, size::Size, text::Text};
+
+#[test]
+fn line_breaks_are_normalized() {
+    let text = Text::from("a\r\nb\rc\nd\n");
+    assert_eq!(text.to_string(), "a\nb\nc\nd\n");
+    assert_eq!(text.line_count(), 5);
+    assert_eq!(text.len(), Size {line: 4, column: 0});
+    assert!(Text::from("").is_empty());
+}
+
+#[test]
+fn other_line_break_chars_round_trip() {
+    let string = "a\u{2028}b\x0Cc\u{85}\x0Bd\u{2029}\n";
+    let text = Text::from(string);
+    assert_eq!(text.to_string(), string);
+    assert_eq!(text.line_count(), 2);
+    assert_eq!(text.line_len(0), 9);
+}
+
+#[test]
+fn lines_are_indexed_by_chars() {
+    let text = Text::from("fn main() {\n    \"😀é\";\n}");
+    assert_eq!(text.line_lens().collect::<Vec<_>>(), [11, 9, 1]);
+    assert_eq!(text.line_len(1), 9);
+    assert_eq!(text.line(1).chars().collect::<String>(), "    \"😀é\";");
+    assert_eq!(text.position_to_byte(Position {line: 1, column: 6}), 21);
+    assert_eq!(text.position_to_byte(Position {line: 2, column: 1}), 27);
+}
+
+#[test]
+fn large_texts_can_be_edited() {
+    let string = (0..100_000).map( | index | format!("line {}\n", index)).collect::<String>();
+    let mut text = Text::from(string.as_str());
+    assert_eq!(text.line_count(), 100_001);
+    assert_eq!(text.line(54_321).chars().collect::<String>(), "line 54321");
+
+    let mut builder = delta::Builder::new();
+    builder.retain(Size {line: 54_321, column: 5});
+    builder.delete(Size {line: 1, column: 5});
+    builder.insert(Text::from("x\ny"));
+    text.apply_delta(builder.build());
+    assert_eq!(text.line_count(), 100_001);
+    assert_eq!(text.line(54_321).chars().collect::<String>(), "line x");
+    assert_eq!(text.line(54_322).chars().collect::<String>(), "y54322");
+    assert_eq!(
+        text.copy(Range {
+            start: Position {line: 54_320, column: 5},
+            end: Position {line: 54_322, column: 1},
+        }),
+        Text::from("54320\nline x\ny")
+    );
+}
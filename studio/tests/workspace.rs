@@ -0,0 +1,175 @@
+use {
+    makepad_editor_core::{position::Position, text::Text},
+    makepad_studio::{
+        app_state::{AppState, Panel, Tab, TabKind},
+        makepad_collab_protocol::{
+            unix_path::UnixPathBuf,
+            unix_str::UnixString,
+            DirectoryEntry, FileNodeData, FileTreeData, TextFileId,
+        },
+        makepad_component::splitter::SplitterAlign,
+        makepad_live_id::LiveId,
+        makepad_micro_serde::{DeRon, SerRon},
+        makepad_platform::*,
+        workspace::{self, Workspace},
+    },
+    std::{env, fs},
+};
+
+fn state_with_files(names: &[&str]) -> AppState {
+    let mut state = AppState::new();
+    state.load_file_tree(FileTreeData {
+        path: UnixPathBuf::from(""),
+        root: FileNodeData::Directory {
+            entries: vec![DirectoryEntry {
+                name: UnixString::from_vec(b"src".to_vec()),
+                node: FileNodeData::Directory {
+                    entries: names.iter().map( | name | DirectoryEntry {
+                        name: UnixString::from_vec(name.as_bytes().to_vec()),
+                        node: FileNodeData::File {data: None},
+                    }).collect(),
+                },
+            }],
+        },
+    });
+    state
+}
+
+fn open_code_editor_tab(state: &mut AppState, path: &str) {
+    let session_id = state.editor_state.create_session(UnixPathBuf::from(path), &mut | _ | {});
+    state.editor_state.add_cursor(session_id, Position {line: 2, column: 3});
+    let tab_id = state.tabs.insert_unique(Tab {
+        name: String::from(path),
+        kind: TabKind::CodeEditor {session_id},
+    });
+    let panel = state.panels[id!(content).into()].as_tab_panel_mut();
+    panel.tab_ids.push(tab_id);
+    panel.selected_tab = Some(panel.tab_ids.len() - 1);
+}
+
+fn round_trip(state: &AppState) -> Workspace {
+    let src = state.file_nodes.iter().find( | (_, node) | node.name == "src").map( | (id, _) | *id).unwrap();
+    let workspace = Workspace::capture(state, std::iter::once(src), | _ | Some(vec2(0.0, 120.0)));
+    Workspace::deserialize_ron(&workspace.serialize_ron()).unwrap()
+}
+
+fn code_editor_paths(state: &AppState) -> Vec<String> {
+    let panel = state.panels[id!(content).into()].as_tab_panel();
+    panel.tab_ids.iter().filter_map( | tab_id | match state.tabs[*tab_id].kind {
+        TabKind::CodeEditor {session_id} => {
+            let document_id = state.editor_state.sessions[session_id].document_id;
+            Some(state.editor_state.documents[document_id].path.as_unix_str().to_string_lossy().into_owned())
+        }
+        _ => None,
+    }).collect()
+}
+
+#[test]
+fn layout_is_restored() {
+    let mut state = state_with_files(&["a.rs", "b.rs"]);
+    open_code_editor_tab(&mut state, "src/a.rs");
+    open_code_editor_tab(&mut state, "src/b.rs");
+    if let Panel::Split(panel) = &mut state.panels[id!(root).into()] {
+        panel.align = SplitterAlign::FromEnd(321.0);
+    }
+
+    let workspace = round_trip(&state);
+    assert!(workspace.is_valid());
+    let mut restored_state = state_with_files(&["a.rs", "b.rs"]);
+    let restored = workspace.restore(&mut restored_state, &mut | _ | {});
+
+    assert_eq!(code_editor_paths(&restored_state), vec!["src/a.rs", "src/b.rs"]);
+    let panel = restored_state.panels[id!(content).into()].as_tab_panel();
+    assert_eq!(panel.selected_tab, Some(2));
+    match &restored_state.panels[id!(root).into()] {
+        Panel::Split(panel) => assert!(matches!(panel.align, SplitterAlign::FromEnd(align) if align == 321.0)),
+        _ => panic!(),
+    }
+    let session_id = match restored_state.tabs[panel.tab_ids[2]].kind {
+        TabKind::CodeEditor {session_id} => session_id,
+        _ => panic!(),
+    };
+    let carets: Vec<_> = restored_state.editor_state.sessions[session_id].cursors.iter().map( | cursor | cursor.head).collect();
+    assert_eq!(carets, vec![Position::origin(), Position {line: 2, column: 3}]);
+    assert_eq!(restored.scroll_positions.len(), 2);
+    assert_eq!(restored.scroll_positions[0].1, vec2(0.0, 120.0));
+    assert_eq!(restored.open_folder_ids.len(), 1);
+}
+
+#[test]
+fn tabs_for_missing_files_are_dropped() {
+    let mut state = state_with_files(&["a.rs", "b.rs"]);
+    open_code_editor_tab(&mut state, "src/a.rs");
+    open_code_editor_tab(&mut state, "src/b.rs");
+
+    let workspace = round_trip(&state);
+    let mut restored_state = state_with_files(&["a.rs"]);
+    let restored = workspace.restore(&mut restored_state, &mut | _ | {});
+
+    assert_eq!(code_editor_paths(&restored_state), vec!["src/a.rs"]);
+    // the selected tab was dropped, so the first tab is selected instead
+    let panel = restored_state.panels[id!(content).into()].as_tab_panel();
+    assert_eq!(panel.selected_tab, Some(0));
+    assert_eq!(restored.scroll_positions.len(), 1);
+}
+
+#[test]
+fn folded_lines_are_restored() {
+    let mut state = state_with_files(&["a.rs"]);
+    open_code_editor_tab(&mut state, "src/a.rs");
+    let session_id = state.editor_state.sessions.keys().next().cloned().unwrap();
+    state.editor_state.set_folded_lines(session_id, &[3, 1]);
+
+    let workspace = round_trip(&state);
+    let mut restored_state = state_with_files(&["a.rs"]);
+    workspace.restore(&mut restored_state, &mut | _ | {});
+    let session = restored_state.editor_state.sessions.values().next().unwrap();
+    assert_eq!(session.folds.lines().collect::<Vec<_>>(), vec![1, 3]);
+}
+
+#[test]
+fn workspace_without_root_panel_is_invalid() {
+    let mut state = state_with_files(&[]);
+    state.panels.remove(&id!(root).into());
+    let workspace = round_trip(&state);
+    assert!(!workspace.is_valid());
+}
+
+#[test]
+fn workspace_with_unknown_tab_is_invalid() {
+    let mut state = state_with_files(&[]);
+    state.panels[id!(log_view).into()].as_tab_panel_mut().tab_ids.push(LiveId(1).into());
+    let workspace = round_trip(&state);
+    assert!(!workspace.is_valid());
+}
+
+#[test]
+fn undo_histories_are_saved_and_restored() {
+    let mut state = state_with_files(&["a.rs"]);
+    let session_id = state.editor_state.create_session(UnixPathBuf::from("src/a.rs"), &mut | _ | {});
+    state.editor_state.handle_open_file_response(TextFileId(LiveId(1)), 0, Text::from(""), &mut | _ | {});
+    state.editor_state.insert_text(session_id, Text::from("a"), &mut | _ | {});
+
+    // the history of a document that is still open is saved as well
+    let dir = env::temp_dir().join(format!("makepad_workspace_undo_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    workspace::save_undo_histories(&dir, &state.editor_state.capture_undo_histories());
+    let undo_histories = workspace::load_undo_histories(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(undo_histories.len(), 1);
+
+    let mut restored_state = state_with_files(&["a.rs"]);
+    restored_state.editor_state.undo_histories = undo_histories;
+    let session_id = restored_state.editor_state.create_session(UnixPathBuf::from("src/a.rs"), &mut | _ | {});
+    restored_state.editor_state.handle_open_file_response(TextFileId(LiveId(2)), 0, Text::from("a"), &mut | _ | {});
+    restored_state.editor_state.undo(session_id, &mut | _ | {});
+    let document_id = restored_state.editor_state.sessions[session_id].document_id;
+    let inner = restored_state.editor_state.documents[document_id].inner.as_ref().unwrap();
+    assert_eq!(inner.text.to_string(), "");
+}
+
+#[test]
+fn missing_undo_histories_load_as_empty() {
+    let dir = env::temp_dir().join(format!("makepad_workspace_no_undo_{}", std::process::id()));
+    assert!(workspace::load_undo_histories(&dir).is_empty());
+}
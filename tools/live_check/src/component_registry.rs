@@ -0,0 +1,69 @@
+use {
+    std::collections::{BTreeMap, BTreeSet},
+    makepad_live_compiler::{
+        makepad_live_id::*,
+        LiveComponentInfo,
+        LiveComponentRegistry,
+        LiveModuleId,
+        LiveRegistry,
+        LiveType,
+    },
+};
+
+// The registries are stored by live type, so each one needs a type of its own.
+const REGISTRY_TYPES: [fn() -> LiveType; 8] = [
+    LiveType::of::<[(); 0]>,
+    LiveType::of::<[(); 1]>,
+    LiveType::of::<[(); 2]>,
+    LiveType::of::<[(); 3]>,
+    LiveType::of::<[(); 4]>,
+    LiveType::of::<[(); 5]>,
+    LiveType::of::<[(); 6]>,
+    LiveType::of::<[(); 7]>,
+];
+
+/// A component registry, such as the one for `FrameComponent`, with the components that the
+/// checked sources register, so that `use FrameComponent::*` can be expanded without running them.
+pub struct SourceComponentRegistry {
+    live_type: LiveType,
+    component_type: LiveId,
+    components: Vec<LiveComponentInfo>,
+}
+
+impl LiveComponentRegistry for SourceComponentRegistry {
+    fn type_id(&self) -> LiveType {self.live_type}
+
+    fn get_component_info(&self, name: LiveId) -> Option<LiveComponentInfo> {
+        self.components.iter().find( | info | info.name == name).cloned()
+    }
+
+    fn component_type(&self) -> LiveId {self.component_type}
+
+    fn get_module_set(&self, set: &mut BTreeSet<LiveModuleId>) {
+        set.extend(self.components.iter().map( | info | info.module_id));
+    }
+}
+
+impl SourceComponentRegistry {
+    /// Adds a registry for each component type to `live_registry`, with the components of
+    /// that type.
+    pub fn register_all(live_registry: &LiveRegistry, components: Vec<(LiveId, LiveComponentInfo)>) -> Result<(), String> {
+        let mut by_type: BTreeMap<LiveId, Vec<LiveComponentInfo>> = BTreeMap::new();
+        for (component_type, info) in components {
+            by_type.entry(component_type).or_default().push(info);
+        }
+        let mut registries = live_registry.components.0.borrow_mut();
+        for (index, (component_type, components)) in by_type.into_iter().enumerate() {
+            let live_type = match REGISTRY_TYPES.get(index) {
+                Some(live_type) => live_type(),
+                None => return Err(format!("Too many component types to check {}", component_type))
+            };
+            registries.insert(live_type, Box::new(SourceComponentRegistry {
+                live_type,
+                component_type,
+                components
+            }));
+        }
+        Ok(())
+    }
+}
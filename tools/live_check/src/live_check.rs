@@ -0,0 +1,176 @@
+use {
+    std::collections::{HashMap, HashSet},
+    makepad_live_compiler::{
+        makepad_live_id::*,
+        makepad_live_tokenizer::{Delim, LiveErrorOrigin, live_error_origin},
+        LiveError,
+        LiveFileError,
+        LiveFileId,
+        LiveNode,
+        LiveNodeSlice,
+        LivePropType,
+        LiveRegistry,
+        LiveToken,
+        LiveValue,
+    },
+    crate::rust_source::LiveStruct,
+};
+
+/// Checks the properties of the `{{Type}}` classes in live documents against the fields of the
+/// structs that derive `Live` in the checked sources. Types that are not found are not checked.
+pub struct LiveChecker<'a> {
+    structs: HashMap<LiveId, Vec<&'a LiveStruct>>,
+    // the properties that the classes of each type declare with a prefix, such as `var caption`
+    declared: HashMap<LiveId, HashSet<LiveId>>,
+}
+
+impl<'a> LiveChecker<'a> {
+    pub fn new(live_structs: impl Iterator<Item = &'a LiveStruct>, live_registry: &LiveRegistry, file_ids: &[LiveFileId]) -> Self {
+        let mut structs: HashMap<LiveId, Vec<&'a LiveStruct>> = HashMap::new();
+        for live_struct in live_structs {
+            structs.entry(LiveId::from_str_unchecked(&live_struct.name)).or_default().push(live_struct);
+        }
+        let mut declared: HashMap<LiveId, HashSet<LiveId>> = HashMap::new();
+        for file_id in file_ids {
+            let nodes = &live_registry.live_files[file_id.to_index()].original.nodes;
+            for index in 0..nodes.len() {
+                if let LiveValue::Class {..} = nodes[index].value {
+                    if let Some(name) = class_name(live_registry, &nodes[index]) {
+                        let declared = declared.entry(name).or_default();
+                        let mut child_index = index + 1;
+                        while !nodes[child_index].is_close() {
+                            if nodes[child_index].origin.node_has_prefix() {
+                                declared.insert(nodes[child_index].id);
+                            }
+                            child_index = nodes.skip_node(child_index);
+                        }
+                    }
+                }
+            }
+        }
+        Self {structs, declared}
+    }
+
+    /// Checks the original document of the file `file_id`.
+    pub fn check_file(&self, live_registry: &LiveRegistry, file_id: LiveFileId, errors: &mut Vec<LiveFileError>) {
+        let nodes = &live_registry.live_files[file_id.to_index()].original.nodes;
+        for index in 0..nodes.len() {
+            if let LiveValue::Class {..} = nodes[index].value {
+                if let Some(structs) = class_name(live_registry, &nodes[index]).and_then( | name | self.structs.get(&name)) {
+                    self.check_object(live_registry, nodes, index, structs, errors);
+                }
+            }
+        }
+    }
+
+    // Checks the properties of the object at `index` against the fields of `structs`, which are
+    // all the structs with the name of its type.
+    fn check_object(&self, live_registry: &LiveRegistry, nodes: &[LiveNode], index: usize, structs: &[&LiveStruct], errors: &mut Vec<LiveFileError>) {
+        if structs.iter().any( | live_struct | live_struct.is_enum || live_struct.forwards_to_shader) {
+            return
+        }
+        let declared = self.declared.get(&LiveId::from_str_unchecked(&structs[0].name));
+        let mut child_index = index + 1;
+        while !nodes[child_index].is_close() {
+            let node = &nodes[child_index];
+            let next_index = nodes.skip_node(child_index);
+            // the same properties that `apply_value_unknown` ignores are skipped here
+            if !node.origin.has_prop_type(LivePropType::Field) || node.origin.node_has_prefix() || node.id.is_capitalised() {
+                child_index = next_index;
+                continue
+            }
+            if let LiveValue::DSL {..} | LiveValue::Use(_) | LiveValue::UseComponent(_) = node.value {
+                child_index = next_index;
+                continue
+            }
+            let field_types: Vec<&str> = structs.iter().flat_map( | live_struct | {
+                live_struct.fields.iter().filter( | (name, _) | LiveId::from_str_unchecked(name) == node.id).map( | (_, ty) | ty.as_str())
+            }).collect();
+            if declared.is_some_and( | declared | declared.contains(&node.id)) {
+                // a property that the class declared itself
+            }
+            else if field_types.is_empty() {
+                errors.push(error(live_registry, node, format!("no matching field: {} in {}", node.id, structs[0].name)));
+            }
+            else if field_types.len() == 1 {
+                let field_type = base_type(field_types[0]);
+                if accepts_literal(field_type, &node.value) == Some(false) {
+                    errors.push(error(live_registry, node, format!(
+                        "wrong value type. Prop: {} primitive: {} value: {}",
+                        node.id,
+                        field_type,
+                        value_kind(&node.value)
+                    )));
+                }
+                if let LiveValue::Object = node.value {
+                    if let Some(structs) = self.structs.get(&LiveId::from_str_unchecked(field_type)) {
+                        self.check_object(live_registry, nodes, child_index, structs, errors);
+                    }
+                }
+            }
+            child_index = next_index;
+        }
+    }
+}
+
+fn error(live_registry: &LiveRegistry, node: &LiveNode, message: String) -> LiveFileError {
+    live_registry.live_error_to_live_file_error(LiveError {
+        origin: live_error_origin!(),
+        span: node.origin.token_id().unwrap().into(),
+        message
+    })
+}
+
+/// Returns the name of the type in the `{{Type}}` that follows the name of a class.
+pub fn class_name(live_registry: &LiveRegistry, node: &LiveNode) -> Option<LiveId> {
+    let token_id = node.origin.token_id() ?;
+    let tokens = &live_registry.live_files[token_id.file_id().to_index()].original.tokens;
+    let start = token_id.token_index();
+    tokens[start..tokens.len().min(start + 32)].windows(3).find_map( | window | match (window[0].token, window[1].token, window[2].token) {
+        (LiveToken::Open(Delim::Brace), LiveToken::Open(Delim::Brace), LiveToken::Ident(type_name)) => Some(type_name),
+        _ => None
+    })
+}
+
+// Returns the name of the type of a field without its path, generic arguments and `Option`.
+fn base_type(field_type: &str) -> &str {
+    let mut field_type = field_type.trim();
+    if let Some(inner) = field_type.strip_prefix("Option<").and_then( | inner | inner.strip_suffix('>')) {
+        field_type = inner.trim();
+    }
+    let field_type = field_type.split('<').next().unwrap().trim();
+    field_type.rsplit("::").next().unwrap()
+}
+
+// Returns whether a literal value can be applied to a field of a primitive type, or `None` if
+// the type is not a primitive or the value is not a literal.
+fn accepts_literal(field_type: &str, value: &LiveValue) -> Option<bool> {
+    let is_string = matches!(value, LiveValue::Str(_) | LiveValue::FittedString(_) | LiveValue::InlineString(_) | LiveValue::DocumentString {..});
+    let is_number = matches!(value, LiveValue::Int(_) | LiveValue::Float(_));
+    let is_literal = is_string || is_number || matches!(
+        value,
+        LiveValue::Bool(_) | LiveValue::Color(_) | LiveValue::Vec2(_) | LiveValue::Vec3(_) | LiveValue::Vec4(_)
+    );
+    if !is_literal {
+        return None
+    }
+    Some(match field_type {
+        "bool" => matches!(value, LiveValue::Bool(_) | LiveValue::Int(_)),
+        "f32" | "f64" | "i64" | "usize" => is_number,
+        "Vec2" => is_number || matches!(value, LiveValue::Vec2(_)),
+        "Vec3" => is_number || matches!(value, LiveValue::Vec2(_) | LiveValue::Vec3(_)),
+        "Vec4" => is_number || matches!(value, LiveValue::Vec2(_) | LiveValue::Vec3(_) | LiveValue::Vec4(_) | LiveValue::Color(_)),
+        "String" => is_string,
+        _ => return None
+    })
+}
+
+fn value_kind(value: &LiveValue) -> &'static str {
+    match value {
+        LiveValue::Bool(_) => "bool",
+        LiveValue::Int(_) | LiveValue::Float(_) => "number",
+        LiveValue::Color(_) => "color",
+        LiveValue::Vec2(_) | LiveValue::Vec3(_) | LiveValue::Vec4(_) => "vector",
+        _ => "string"
+    }
+}
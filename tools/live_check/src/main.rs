@@ -0,0 +1,288 @@
+use {
+    std::{
+        any::TypeId,
+        collections::{HashMap, HashSet},
+        env,
+        fs,
+        path::{Path, PathBuf},
+        process,
+    },
+    makepad_live_compiler::{
+        makepad_live_id::*,
+        makepad_live_tokenizer::{Delim, LiveErrorOrigin, live_error_origin},
+        check_catalogs,
+        LiveComponentInfo,
+        LiveFileError,
+        LiveFileId,
+        LiveModuleId,
+        LivePrinter,
+        LiveRegistry,
+        LiveToken,
+        LiveTypeInfo,
+    }
+};
+mod rust_source;
+mod live_check;
+mod component_registry;
+use rust_source::*;
+use live_check::*;
+use component_registry::*;
+
+const USAGE: &str = "usage: live_check [--fmt [--check]] [path...]
+
+Checks the live documents in the live_register! macros of the Rust files and in the .live
+files in the given paths, or in the current directory. The message catalogs are checked for
+missing and unused messages. With --fmt the live_register! bodies are rewritten in the
+canonical format, and with --fmt --check the files that would be rewritten are listed.";
+
+// A live_register! body that was registered as a live file.
+struct RegisteredBlock {
+    source_index: usize,
+    block: LiveBlock,
+    file_id: LiveFileId,
+}
+
+pub fn main() {
+    let mut fmt = false;
+    let mut check = false;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--fmt" => fmt = true,
+            "--check" => check = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("Unknown option {}\n{}", arg, USAGE);
+                process::exit(2);
+            }
+            _ => paths.push(PathBuf::from(arg))
+        }
+    }
+    if paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+
+    let mut files = Vec::new();
+    let mut live_paths = Vec::new();
+    for path in &paths {
+        find_source_files(path, &mut files, &mut live_paths);
+    }
+    files.sort();
+    live_paths.sort();
+    let mut sources = Vec::new();
+    for path in files {
+        match RustSource::load(&path) {
+            Ok(source) => sources.push(source),
+            Err(error) => eprintln!("Cannot read {}", error)
+        }
+    }
+
+    let mut live_registry = LiveRegistry::default();
+    let mut errors = Vec::new();
+
+    // the types and components that are registered by Rust code are found in the sources
+    let live_structs: Vec<LiveStruct> = sources.iter().flat_map( | source | source.live_structs()).collect();
+    let live_type_ids: Vec<LiveId> = live_structs.iter().map( | live_struct | LiveId::from_str_unchecked(&live_struct.name)).collect();
+    live_registry.add_ignore_no_dsl(&live_type_ids);
+    let component_macros: HashMap<String, String> = sources.iter().flat_map( | source | source.component_macros()).collect();
+    let mut components = Vec::new();
+    for source in &sources {
+        for (macro_name, name) in source.live_components() {
+            let component_type = match component_macros.get(&macro_name) {
+                Some(component_type) => component_type,
+                None => continue
+            };
+            if let Ok(module_id) = LiveModuleId::from_str(&source.module_path) {
+                components.push((LiveId::from_str_unchecked(component_type), LiveComponentInfo {
+                    name: LiveId::from_str_unchecked(&name),
+                    module_id
+                }));
+            }
+        }
+    }
+    if let Err(message) = SourceComponentRegistry::register_all(&live_registry, components) {
+        eprintln!("{}", message);
+    }
+
+    let mut blocks = Vec::new();
+    for (source_index, source) in sources.iter().enumerate() {
+        for (block_index, block) in source.live_blocks().into_iter().enumerate() {
+            let file_name = format!("{}#{}", source.path.display(), block_index);
+            match register_block(&mut live_registry, &file_name, source, &block) {
+                Ok(file_id) => blocks.push(RegisteredBlock {source_index, block, file_id}),
+                Err(error) => errors.push(error)
+            }
+        }
+    }
+
+    let mut live_file_ids = Vec::new();
+    for path in &live_paths {
+        match register_live_file(&mut live_registry, path) {
+            Ok(file_id) => live_file_ids.push(file_id),
+            Err(error) => errors.push(error)
+        }
+    }
+
+    let mut live_errors = Vec::new();
+    live_registry.expand_all_documents(&mut live_errors);
+    for live_error in live_errors {
+        errors.push(live_registry.live_error_to_live_file_error(live_error));
+    }
+
+    let mut file_ids: Vec<LiveFileId> = blocks.iter().map( | block | block.file_id).collect();
+    file_ids.extend(live_file_ids.iter().cloned());
+    let checker = LiveChecker::new(live_structs.iter(), &live_registry, &file_ids);
+    for file_id in &file_ids {
+        checker.check_file(&live_registry, *file_id, &mut errors);
+    }
+
+    // the messages Rust code translates count as used, next to the ones of the live documents
+    let used_messages: HashSet<LiveId> = sources.iter().flat_map( | source | source.tr_messages())
+        .filter_map( | message | LiveId::from_str(&message).ok()).collect();
+    for live_error in check_catalogs(&live_registry, &used_messages) {
+        errors.push(live_registry.live_error_to_live_file_error(live_error));
+    }
+
+    for error in &errors {
+        // the file names are made unique with the index of the block, which is left out here
+        let file = error.file.rsplit_once('#').map_or(error.file.as_str(), | (file, _) | file);
+        eprintln!("{}:{}:{}: error: {}", file, error.span.start.line + 1, error.span.start.column + 1, error.message);
+    }
+
+    let mut unformatted = 0;
+    if fmt {
+        for (source_index, source) in sources.iter().enumerate() {
+            let source_blocks: Vec<&RegisteredBlock> = blocks.iter().filter( | block | block.source_index == source_index).collect();
+            if source_blocks.is_empty() {
+                continue
+            }
+            let formatted = format_source(&live_registry, source, &source_blocks);
+            if formatted == source.source {
+                continue
+            }
+            if check {
+                println!("{} is not formatted", source.path.display());
+                unformatted += 1;
+            }
+            else if let Err(error) = fs::write(&source.path, formatted) {
+                eprintln!("Cannot write {}: {}", source.path.display(), error);
+                process::exit(1);
+            }
+            else {
+                println!("Formatted {}", source.path.display());
+            }
+        }
+    }
+
+    println!(
+        "Checked {} live_register! blocks in {} files and {} live files: {} errors",
+        blocks.len(),
+        sources.len(),
+        live_file_ids.len(),
+        errors.len()
+    );
+    if !errors.is_empty() || unformatted > 0 {
+        process::exit(1);
+    }
+}
+
+// Registers a live_register! body, with a placeholder type for each `{{Type}}` in it. The
+// placeholders share a live type, so only the names of the types are known.
+fn register_block(live_registry: &mut LiveRegistry, file_name: &str, source: &RustSource, block: &LiveBlock) -> Result<LiveFileId, LiveFileError> {
+    let body = &source.source[block.range.clone()];
+    let (tokens, _) = LiveRegistry::tokenize_from_str(body, block.start_pos, LiveFileId::new(0))
+        .map_err( | error | error.into_live_file_error(file_name)) ?;
+    let live_type_infos = tokens.windows(3).filter_map( | window | match (window[0].token, window[1].token, window[2].token) {
+        (LiveToken::Open(Delim::Brace), LiveToken::Open(Delim::Brace), LiveToken::Ident(type_name)) => Some(LiveTypeInfo {
+            live_type: TypeId::of::<()>(),
+            type_name,
+            module_id: LiveModuleId(id!(live_check), id!(types)),
+            fields: Vec::new(),
+        }),
+        _ => None
+    }).collect();
+    let module_id = LiveModuleId::from_str(&source.module_path).map_err( | message | LiveFileError {
+        origin: live_error_origin!(),
+        file: file_name.to_string(),
+        span: Default::default(),
+        message,
+    }) ?;
+    live_registry.register_live_file(file_name, module_id, body.to_string(), live_type_infos, block.start_pos)
+}
+
+// Registers a .live file as the module its path would have as a Rust file.
+fn register_live_file(live_registry: &mut LiveRegistry, path: &Path) -> Result<LiveFileId, LiveFileError> {
+    let file_name = path.display().to_string();
+    let file_error = | message | LiveFileError {
+        origin: live_error_origin!(),
+        file: file_name.clone(),
+        span: Default::default(),
+        message,
+    };
+    let source = fs::read_to_string(path).map_err( | error | file_error(format!("Cannot read: {}", error))) ?;
+    let module_id = LiveModuleId::from_str(&module_path(path)).map_err(file_error) ?;
+    live_registry.register_live_file(&file_name, module_id, source, Vec::new(), Default::default())
+}
+
+// Returns `source` with its live_register! bodies in the canonical format. Bodies that would
+// lose comments are left as they are, since the canonical format can not place every comment.
+fn format_source(live_registry: &LiveRegistry, source: &RustSource, blocks: &[&RegisteredBlock]) -> String {
+    let mut out = String::new();
+    let mut last_end = 0;
+    for block in blocks {
+        let body = &source.source[block.block.range.clone()];
+        let nodes = &live_registry.live_files[block.file_id.to_index()].original.nodes;
+        let printed = LivePrinter::new(live_registry, block.file_id).reformat().print(nodes);
+        // the body is indented one level, like the contents of any other block
+        let mut formatted = String::from("\n");
+        for line in printed.lines() {
+            if !line.is_empty() {
+                formatted.push_str("    ");
+                formatted.push_str(line);
+            }
+            formatted.push('\n');
+        }
+        out.push_str(&source.source[last_end..block.block.range.start]);
+        if comment_count(&formatted) == comment_count(body) {
+            out.push_str(&formatted);
+        }
+        else {
+            eprintln!("{}:{}: not formatting a live_register! block that would lose comments", source.path.display(), block.block.start_pos.line + 1);
+            out.push_str(body);
+        }
+        last_end = block.block.range.end;
+    }
+    out.push_str(&source.source[last_end..]);
+    out
+}
+
+fn find_source_files(path: &Path, files: &mut Vec<PathBuf>, live_files: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        if path.extension().is_some_and( | extension | extension == "rs") {
+            files.push(path.to_path_buf());
+        }
+        else if path.extension().is_some_and( | extension | extension == "live") {
+            live_files.push(path.to_path_buf());
+        }
+        return
+    }
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(error) => {
+            eprintln!("Cannot read {}: {}", path.display(), error);
+            return
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() && (name.starts_with('.') || name == "target") {
+            continue
+        }
+        find_source_files(&path, files, live_files);
+    }
+}
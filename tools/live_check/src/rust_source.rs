@@ -0,0 +1,403 @@
+use {
+    std::{
+        fs,
+        ops::Range,
+        path::{Path, PathBuf},
+    },
+    makepad_live_compiler::{
+        makepad_live_tokenizer::{Cursor, Delim, FullToken, State},
+        TextPos,
+    }
+};
+
+/// A token of a Rust source, with the text it was made from.
+struct RustToken<'a> {
+    token: FullToken,
+    text: &'a str,
+    range: Range<usize>,
+    pos: TextPos,
+}
+
+/// The body of a `live_register!` macro in a Rust source.
+pub struct LiveBlock {
+    /// The byte range of the body in the Rust source, without the delimiters of the macro.
+    pub range: Range<usize>,
+    /// The position of the start of the body in the Rust source.
+    pub start_pos: TextPos,
+}
+
+/// A struct or enum that derives `Live`, with the fields that can be set from live documents.
+pub struct LiveStruct {
+    pub name: String,
+    pub is_enum: bool,
+    /// The names and types of the live fields, including aliases.
+    pub fields: Vec<(String, String)>,
+    /// Whether unknown fields are forwarded to the shader of a `draw_super` or `draw_vars` field.
+    pub forwards_to_shader: bool,
+}
+
+/// A Rust source file, tokenized with the live tokenizer, which keeps strings and comments apart
+/// from the code like the studio does.
+pub struct RustSource {
+    pub path: PathBuf,
+    pub module_path: String,
+    pub source: String,
+}
+
+impl RustSource {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err( | error | format!("{}: {}", path.display(), error)) ?;
+        Ok(Self {
+            module_path: module_path(path),
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    fn tokens(&self) -> Vec<RustToken<'_>> {
+        let mut tokens = Vec::new();
+        let mut state = State::default();
+        let mut scratch = String::new();
+        let mut line_chars = Vec::new();
+        let mut line_start = 0;
+        for (line, line_str) in self.source.split('\n').enumerate() {
+            line_chars.clear();
+            line_chars.extend(line_str.chars());
+            let mut cursor = Cursor::new(&line_chars, &mut scratch);
+            let mut offset = line_start;
+            let mut column = 0;
+            loop {
+                let (next_state, full_token) = state.next(&mut cursor);
+                let full_token = match full_token {
+                    Some(full_token) => full_token,
+                    None => break
+                };
+                let len: usize = line_chars[column..column + full_token.len].iter().map( | c | c.len_utf8()).sum();
+                if !full_token.is_whitespace() && !full_token.is_comment() {
+                    tokens.push(RustToken {
+                        token: full_token.token,
+                        text: &self.source[offset..offset + len],
+                        range: offset..offset + len,
+                        pos: TextPos {line: line as u32, column: column as u32},
+                    });
+                }
+                offset += len;
+                column += full_token.len;
+                state = next_state;
+            }
+            line_start += line_str.len() + 1;
+        }
+        tokens
+    }
+
+    /// Returns the bodies of the `live_register!` macros in the source.
+    pub fn live_blocks(&self) -> Vec<LiveBlock> {
+        let tokens = self.tokens();
+        let mut blocks = Vec::new();
+        let mut index = 0;
+        while index + 2 < tokens.len() {
+            if tokens[index].text != "live_register" || tokens[index + 1].text != "!" {
+                index += 1;
+                continue
+            }
+            let open = &tokens[index + 2];
+            if let FullToken::Open(_) = open.token {
+                if let Some(close) = matching_close(&tokens, index + 2) {
+                    blocks.push(LiveBlock {
+                        range: open.range.end..tokens[close].range.start,
+                        start_pos: TextPos {line: open.pos.line, column: open.pos.column + 1},
+                    });
+                    index = close;
+                }
+            }
+            index += 1;
+        }
+        blocks
+    }
+
+    /// Returns the names of the messages that are translated with `tr(id!(message), ...)`.
+    pub fn tr_messages(&self) -> Vec<String> {
+        let tokens = self.tokens();
+        tokens.windows(6).filter( | window | {
+            window[0].text == "tr" && window[1].text == "(" && window[2].text == "id" && window[3].text == "!" && window[4].text == "("
+        }).map( | window | window[5].text.to_string()).collect()
+    }
+
+    /// Returns the components that are registered with attributes like
+    /// `#[live_register(frame_component!(Button))]`, as the name of the macro and the name of the
+    /// component.
+    pub fn live_components(&self) -> Vec<(String, String)> {
+        let tokens = self.tokens();
+        let mut components = Vec::new();
+        for index in 0..tokens.len() {
+            if let Some(("live_register", range, _)) = attribute(&tokens, index) {
+                if let [_, macro_name, bang, _, name, _, _] = &tokens[range] {
+                    if bang.text == "!" {
+                        components.push((macro_name.text.to_string(), name.text.to_string()));
+                    }
+                }
+            }
+        }
+        components
+    }
+
+    /// Returns the macros that register components, like `frame_component!`, with the type of
+    /// the components they register, which is the name of their registry without `Registry`.
+    pub fn component_macros(&self) -> Vec<(String, String)> {
+        let tokens = self.tokens();
+        let mut macros = Vec::new();
+        for index in 0..tokens.len().saturating_sub(3) {
+            if tokens[index].text != "macro_rules" || tokens[index + 1].text != "!" {
+                continue
+            }
+            let close = match matching_close(&tokens, index + 3) {
+                Some(close) => close,
+                None => continue
+            };
+            let body = &tokens[index + 3..close];
+            for window in body.windows(6) {
+                if window[0].text == "register_component_factory" && window[1].text == "!" && window[5].text.ends_with("Registry") {
+                    macros.push((tokens[index + 2].text.to_string(), window[5].text.replace("Registry", "")));
+                }
+            }
+        }
+        macros
+    }
+
+    /// Returns the structs and enums in the source that derive `Live`.
+    pub fn live_structs(&self) -> Vec<LiveStruct> {
+        let tokens = self.tokens();
+        let mut structs = Vec::new();
+        let mut index = 0;
+        while index < tokens.len() {
+            if tokens[index].text != "#" {
+                index += 1;
+                continue
+            }
+            let (attr_name, attr_range, next_index) = match attribute(&tokens, index) {
+                Some(attribute) => attribute,
+                None => {
+                    index += 1;
+                    continue
+                }
+            };
+            index = next_index;
+            let derives_live = attr_name == "derive" && tokens[attr_range].iter().any( | token | token.text == "Live");
+            if !derives_live {
+                continue
+            }
+            // skip the other attributes and the visibility
+            while index < tokens.len() && tokens[index].text != "struct" && tokens[index].text != "enum" {
+                index = match attribute(&tokens, index) {
+                    Some((_, _, next_index)) => next_index,
+                    None if tokens[index].text == "pub" => skip_visibility(&tokens, index),
+                    None => break
+                };
+            }
+            if index + 1 >= tokens.len() || (tokens[index].text != "struct" && tokens[index].text != "enum") {
+                continue
+            }
+            let is_enum = tokens[index].text == "enum";
+            let name = tokens[index + 1].text.to_string();
+            index += 2;
+            while index < tokens.len() && tokens[index].token != FullToken::Open(Delim::Brace) && tokens[index].text != ";" {
+                index += 1;
+            }
+            if index >= tokens.len() || tokens[index].text == ";" {
+                continue
+            }
+            let close = match matching_close(&tokens, index) {
+                Some(close) => close,
+                None => break
+            };
+            if is_enum {
+                structs.push(LiveStruct {name, is_enum, fields: Vec::new(), forwards_to_shader: false});
+            }
+            else {
+                structs.push(self.live_struct(name, &tokens[index + 1..close]));
+            }
+            index = close + 1;
+        }
+        structs
+    }
+
+    fn live_struct(&self, name: String, tokens: &[RustToken]) -> LiveStruct {
+        let mut fields = Vec::new();
+        let mut forwards_to_shader = false;
+        let mut index = 0;
+        while index < tokens.len() {
+            let mut is_rust = false;
+            let mut aliases = Vec::new();
+            while let Some((attr_name, attr_range, next_index)) = attribute(tokens, index) {
+                match attr_name {
+                    "rust" => is_rust = true,
+                    "alias" => aliases.extend(tokens[attr_range].get(1).map( | token | token.text.to_string())),
+                    _ => ()
+                }
+                index = next_index;
+            }
+            index = skip_visibility(tokens, index);
+            if index + 1 >= tokens.len() || tokens[index + 1].text != ":" {
+                break
+            }
+            let field_name = tokens[index].text.to_string();
+            // the type ends at the first comma that is not nested in it
+            let type_start = index + 2;
+            let mut depth = 0;
+            index = type_start;
+            while index < tokens.len() {
+                match tokens[index].token {
+                    FullToken::Open(_) => depth += 1,
+                    FullToken::Close(_) => depth -= 1,
+                    _ if tokens[index].text == "->" => (),
+                    _ => for c in tokens[index].text.chars() {
+                        match c {
+                            '<' => depth += 1,
+                            '>' => depth -= 1,
+                            _ => ()
+                        }
+                    }
+                }
+                if depth == 0 && tokens[index].text == "," {
+                    break
+                }
+                index += 1;
+            }
+            let field_type = if index > type_start {
+                self.source[tokens[type_start].range.start..tokens[index - 1].range.end].to_string()
+            }
+            else {
+                String::new()
+            };
+            index += 1;
+            if field_name == "draw_super" || field_name == "draw_vars" {
+                forwards_to_shader = true;
+            }
+            if !is_rust {
+                fields.push((field_name, field_type));
+            }
+            for alias in aliases {
+                // aliases refer to a path in another field, so their type is not known
+                fields.push((alias, String::new()));
+            }
+        }
+        LiveStruct {name, is_enum: false, fields, forwards_to_shader}
+    }
+}
+
+/// Returns the number of comments in `text`.
+pub fn comment_count(text: &str) -> usize {
+    let mut count = 0;
+    let mut state = State::default();
+    let mut scratch = String::new();
+    let mut line_chars = Vec::new();
+    for line_str in text.split('\n') {
+        line_chars.clear();
+        line_chars.extend(line_str.chars());
+        let mut cursor = Cursor::new(&line_chars, &mut scratch);
+        loop {
+            let (next_state, full_token) = state.next(&mut cursor);
+            match full_token {
+                Some(full_token) => if full_token.is_comment() {
+                    count += 1;
+                }
+                None => break
+            }
+            state = next_state;
+        }
+    }
+    count
+}
+
+// Returns the name of the attribute that starts at `index`, the range of the tokens in its
+// brackets after the name, and the index after it.
+fn attribute<'a>(tokens: &[RustToken<'a>], index: usize) -> Option<(&'a str, Range<usize>, usize)> {
+    if index + 2 >= tokens.len() || tokens[index].text != "#" || tokens[index + 1].token != FullToken::Open(Delim::Bracket) {
+        return None
+    }
+    let close = matching_close(tokens, index + 1) ?;
+    Some((tokens[index + 2].text, index + 3..close, close + 1))
+}
+
+// Skips `pub`, `pub(crate)` and the like.
+fn skip_visibility(tokens: &[RustToken], index: usize) -> usize {
+    if index < tokens.len() && tokens[index].text == "pub" {
+        if index + 1 < tokens.len() && tokens[index + 1].token == FullToken::Open(Delim::Paren) {
+            if let Some(close) = matching_close(tokens, index + 1) {
+                return close + 1
+            }
+        }
+        return index + 1
+    }
+    index
+}
+
+// Returns the index of the delimiter that closes the one at `open`.
+fn matching_close(tokens: &[RustToken], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token.token {
+            FullToken::Open(_) => depth += 1,
+            FullToken::Close(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index)
+                }
+            }
+            _ => ()
+        }
+    }
+    None
+}
+
+/// Returns the module path of a Rust file, like `module_path!` would in that file, based on the
+/// nearest `Cargo.toml` above it.
+pub fn module_path(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else( | _ | path.to_path_buf());
+    for dir in path.ancestors().skip(1) {
+        let manifest = match fs::read_to_string(dir.join("Cargo.toml")) {
+            Ok(manifest) => manifest,
+            Err(_) => continue
+        };
+        let crate_name = match package_name(&manifest) {
+            Some(crate_name) => crate_name.replace('-', "_"),
+            None => continue
+        };
+        let relative = path.strip_prefix(dir).unwrap();
+        let mut components: Vec<String> = relative.with_extension("").iter().map( | component | component.to_string_lossy().to_string()).collect();
+        if components.first().map( | component | component.as_str()) == Some("src") {
+            components.remove(0);
+        }
+        else {
+            // examples and tests are crates of their own, named after their file
+            components.drain(..components.len() - 1);
+        }
+        if let Some(last) = components.last() {
+            if last == "lib" || last == "main" || last == "mod" {
+                components.pop();
+            }
+        }
+        let mut module_path = crate_name;
+        for component in components {
+            module_path.push_str("::");
+            module_path.push_str(&component);
+        }
+        return module_path
+    }
+    path.file_stem().map( | stem | stem.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn package_name(manifest: &str) -> Option<&str> {
+    let mut in_package = false;
+    for line in manifest.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        }
+        else if in_package && line.starts_with("name") {
+            let value = line.split('=').nth(1) ?.trim();
+            return Some(value.trim_matches('"'))
+        }
+    }
+    None
+}